@@ -0,0 +1,132 @@
+//! The "framework tax": whole requests through `app()` (routing, middleware,
+//! extractors, handlers, serialization) with `actix_web::test::call_service`,
+//! no sockets and no database:
+//!
+//! ```text
+//! cargo bench --bench handlers
+//! cargo bench --bench handlers -- users_by_id      # one route
+//! ```
+//!
+//! Handlers read and write [`MemoryUsers`], seeded with the 10 000 rows of
+//! `scripts/init.sql`, instead of PostgreSQL, so a result is what the
+//! framework and the handler code cost per request.  Subtracted from a
+//! `wrk`/`oha` run of the same route it leaves the network and the database.
+//! The configuration is the default one, as in the benchmark runs; the pool
+//! points at a closed port and is never used.
+
+use actix_web::http::header::CONTENT_TYPE;
+use actix_web::http::{Method, StatusCode};
+use actix_web::rt::System;
+use actix_web::test::{call_service, init_service, read_body, TestRequest};
+use actix_web::web;
+use api_actix::config::Config;
+use api_actix::repo::memory::MemoryUsers;
+use api_actix::{app, db, AppState};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use figment::providers::Serialized;
+use figment::Figment;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const SEED_ROWS: usize = 10_000;
+
+/// Method, URI and body of one benchmarked request, and the status it must
+/// get: a route that starts failing would otherwise be timed as its error.
+struct Route {
+    name: &'static str,
+    method: Method,
+    uri: &'static str,
+    body: fn(usize) -> Option<String>,
+    status: StatusCode,
+}
+
+const fn get(name: &'static str, uri: &'static str, status: StatusCode) -> Route {
+    Route { name, method: Method::GET, uri, body: |_| None, status }
+}
+
+fn routes() -> Vec<Route> {
+    vec![
+        get("json", "/json", StatusCode::OK),
+        get("db", "/db", StatusCode::OK),
+        get("queries_20", "/queries?count=20", StatusCode::OK),
+        get("users_by_id", "/users/42", StatusCode::OK),
+        get("users_by_id_fields", "/users/42?fields=id,name", StatusCode::OK),
+        get("users_page_20", "/users?limit=20&offset=100", StatusCode::OK),
+        get("users_missing", "/users/999999", StatusCode::NOT_FOUND),
+        Route {
+            name: "create_user",
+            method: Method::POST,
+            uri: "/users",
+            // A new email each time, or every call after the first is a 409.
+            body: |i| Some(format!(r#"{{"name":"Bench User","email":"bench{i}@benchmark.dev","age":30}}"#)),
+            status: StatusCode::CREATED,
+        },
+        Route {
+            name: "update_user",
+            method: Method::PUT,
+            uri: "/users/42",
+            body: |i| Some(format!(r#"{{"age":{}}}"#, 18 + i % 62)),
+            status: StatusCode::OK,
+        },
+        Route {
+            name: "invalid_body",
+            method: Method::POST,
+            uri: "/users",
+            body: |_| Some(r#"{"name":"Bench User","email":42}"#.to_owned()),
+            status: StatusCode::BAD_REQUEST,
+        },
+    ]
+}
+
+fn config() -> Config {
+    let figment = Figment::new().merge(Serialized::default(
+        "database_url",
+        "postgresql://benchmark@127.0.0.1:1/benchmark",
+    ));
+    Config::from_figment(figment).expect("valid bench configuration")
+}
+
+fn handlers(c: &mut Criterion) {
+    let system = System::new();
+    let app = system.block_on(async {
+        let config = config();
+        let pool = db::build_pool(&config).expect("pool");
+        let state = AppState::new(pool, config)
+            .await
+            .expect("state")
+            .with_users(Arc::new(MemoryUsers::seeded(SEED_ROWS)));
+        init_service(app(web::Data::new(state))).await
+    });
+    let sequence = AtomicUsize::new(0);
+
+    let mut group = c.benchmark_group("handlers");
+    for route in routes() {
+        group.bench_function(route.name, |b| {
+            b.iter_custom(|iters| {
+                system.block_on(async {
+                    let mut elapsed = Duration::ZERO;
+                    for _ in 0..iters {
+                        let i = sequence.fetch_add(1, Ordering::Relaxed);
+                        let mut req = TestRequest::default().method(route.method.clone()).uri(route.uri);
+                        if let Some(body) = (route.body)(i) {
+                            req = req.insert_header((CONTENT_TYPE, "application/json")).set_payload(body);
+                        }
+                        let req = req.to_request();
+                        let start = Instant::now();
+                        let res = call_service(&app, req).await;
+                        let status = res.status();
+                        black_box(read_body(res).await);
+                        elapsed += start.elapsed();
+                        assert_eq!(status, route.status, "{} {}", route.method, route.uri);
+                    }
+                    elapsed
+                })
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, handlers);
+criterion_main!(benches);
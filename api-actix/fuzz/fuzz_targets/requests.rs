@@ -0,0 +1,119 @@
+//! Arbitrary requests (route, query string, headers, body, tenancy on or off)
+//! through the whole application, as in `tests/fuzz_requests.rs`.
+//!
+//! A handler panic becomes a 500 `Internal server error` (`catch_panic`) and
+//! fails the run.  The database points at a closed port, so the one 5xx an
+//! input may produce is the pool's `Database connection error` once every
+//! extractor has accepted it.
+
+#![no_main]
+
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::http::{Method, Uri};
+use actix_web::test::{call_service, init_service, read_body, TestRequest};
+use actix_web::web;
+use api_actix::config::Config;
+use api_actix::{app, db, AppState};
+use arbitrary::Arbitrary;
+use figment::providers::Serialized;
+use figment::Figment;
+use libfuzzer_sys::fuzz_target;
+use serde_json::Value;
+
+#[derive(Arbitrary, Debug)]
+enum Route {
+    CreateUser,
+    UpdateUser,
+    UserById,
+    Users,
+    Queries,
+    Json,
+    AdminCacheClear,
+}
+
+impl Route {
+    fn target(&self) -> (Method, &'static str) {
+        match self {
+            Route::CreateUser => (Method::POST, "/users"),
+            Route::UpdateUser => (Method::PUT, "/users/1"),
+            Route::UserById => (Method::GET, "/users/1"),
+            Route::Users => (Method::GET, "/users"),
+            Route::Queries => (Method::GET, "/queries"),
+            Route::Json => (Method::GET, "/json"),
+            Route::AdminCacheClear => (Method::POST, "/admin/cache-clear"),
+        }
+    }
+}
+
+#[derive(Arbitrary, Debug)]
+enum Header {
+    Accept,
+    ContentType,
+    Authorization,
+    IfNoneMatch,
+    TenantId,
+    Forwarded,
+    XForwardedFor,
+}
+
+impl Header {
+    fn name(&self) -> &'static str {
+        match self {
+            Header::Accept => "accept",
+            Header::ContentType => "content-type",
+            Header::Authorization => "authorization",
+            Header::IfNoneMatch => "if-none-match",
+            Header::TenantId => "x-tenant-id",
+            Header::Forwarded => "forwarded",
+            Header::XForwardedFor => "x-forwarded-for",
+        }
+    }
+}
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    route: Route,
+    query: String,
+    headers: Vec<(Header, Vec<u8>)>,
+    body: Vec<u8>,
+    multi_tenant: bool,
+}
+
+fn config(multi_tenant: bool) -> Config {
+    let figment = Figment::new()
+        .merge(Serialized::default("database_url", "postgresql://benchmark@127.0.0.1:1/benchmark"))
+        .merge(Serialized::default("multi_tenant", multi_tenant))
+        .merge(Serialized::default("admin_token", "a-valid-admin-token"))
+        .merge(Serialized::default("trusted_proxies", "0.0.0.0/0"));
+    Config::from_figment(figment).expect("valid fuzz configuration")
+}
+
+fuzz_target!(|input: Input| {
+    let (method, path) = input.route.target();
+    let uri = format!("{path}?{}", input.query);
+    if uri.parse::<Uri>().is_err() {
+        return;
+    }
+    let mut req = TestRequest::default().method(method).uri(&uri).set_payload(input.body.clone());
+    for (header, value) in &input.headers {
+        if let Ok(value) = HeaderValue::from_bytes(value) {
+            req = req.append_header((HeaderName::from_static(header.name()), value));
+        }
+    }
+
+    // A fresh application per input, so no state carries over between runs.
+    let (status, body) = actix_web::rt::System::new().block_on(async {
+        let config = config(input.multi_tenant);
+        let pool = db::build_pool(&config).expect("pool");
+        let state = web::Data::new(AppState::new(pool, config).await.expect("state"));
+        let app = init_service(app(state)).await;
+        let res = call_service(&app, req.to_request()).await;
+        let status = res.status();
+        let body = read_body(res).await;
+        (status, serde_json::from_slice::<Value>(&body).unwrap_or(Value::Null))
+    });
+
+    if status.is_server_error() {
+        assert_eq!(body["error"], "Database connection error", "{input:?} -> {status} {body}");
+    }
+});
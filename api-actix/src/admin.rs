@@ -0,0 +1,282 @@
+//! Admin routes for the harness (`ADMIN_TOKEN`; not registered without it).
+//!
+//! Every route under `/admin` requires `Authorization: Bearer <ADMIN_TOKEN>`
+//! and answers 401 otherwise.
+//!
+//! `POST /admin/reset[?users=N]` restores the state `scripts/init.sql` leaves
+//! behind, so scenarios can start from identical data without recreating the
+//! containers: every table the API writes to is truncated with its sequences
+//! restarted, `users` is re-seeded with N rows (default 10 000) generated
+//! exactly as in `init.sql`, `user_stats` is refreshed and `users` analyzed.
+//! All of it runs in one transaction, so concurrent requests see either the
+//! old data or the new.  The response cache and the `/cached-users` snapshot
+//! of this instance are cleared afterwards.
+//!
+//! `POST /admin/seed[?users=N&on_conflict=error|skip|update]` runs the same
+//! seed without truncating, for topping up or restoring the seeded rows of a
+//! table that is in use: `skip` inserts only the rows whose email is free,
+//! `update` also rewrites the name and age of those that exist, and `error`
+//! (the default) aborts on the first taken email with 409.  It answers with
+//! the rows inserted, updated and skipped.
+//!
+//! Cold-state controls for sweeps, without a restart:
+//!
+//! * `POST /admin/cache-clear` — empties the response cache and forgets the
+//!   `/cached-users` snapshot;
+//! * `POST /admin/gc` — closes every idle pool connection, so the next
+//!   requests pay for new connections (and a cold backend cache).
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header;
+use actix_web::middleware::{from_fn, Next};
+use actix_web::{post, web, Error, HttpResponse, Responder};
+use serde::Deserialize;
+use std::time::Instant;
+
+use crate::repo::{BulkInsert, OnConflict, RepoError};
+use crate::AppState;
+
+/// Rows seeded by `scripts/init.sql`.
+const DEFAULT_USERS: i64 = 10_000;
+const MAX_USERS: i64 = 1_000_000;
+
+/// Tables written through the API; `RESTART IDENTITY` resets their sequences.
+const TABLES: [&str; 6] = ["users", "accounts", "jobs", "audit_log", "webhooks", "webhook_deliveries"];
+
+/// `ALTER TABLE users <action> TRIGGER` for the change-feed trigger, which
+/// only exists with `scripts/migrations/009-change-feed.sql`.
+fn feed_trigger(action: &str) -> String {
+    format!(
+        "DO $$ BEGIN \
+            IF EXISTS (SELECT 1 FROM pg_trigger \
+                        WHERE tgrelid = 'users'::regclass AND tgname = 'users_notify_change') THEN \
+                ALTER TABLE users {action} TRIGGER users_notify_change; \
+            END IF; \
+         END $$"
+    )
+}
+
+/// The seed of `scripts/init.sql`, with the row count as `$1`.
+const SEED: &str = "\
+    INSERT INTO users (name, email, age, tenant_id) \
+    SELECT \
+        (ARRAY[\
+            'Alice','Bob','Carlos','Diana','Eduardo','Fernanda','Gabriel','Helena',\
+            'Igor','Julia','Kevin','Laura','Marcos','Natalia','Otto','Paula',\
+            'Rafael','Sofia','Thiago','Ursula','Victor','Wendy','Xander','Yasmin','Zeca'\
+        ])[ 1 + (i % 25) ] || ' ' || \
+        (ARRAY[\
+            'Silva','Santos','Oliveira','Souza','Costa','Ferreira','Alves','Pereira',\
+            'Lima','Carvalho','Melo','Ribeiro','Almeida','Nascimento','Gomes'\
+        ])[ 1 + (i % 15) ], \
+        'user' || i || '@' || \
+        (ARRAY['gmail.com','outlook.com','yahoo.com','hotmail.com','benchmark.dev'])[ 1 + (i % 5) ], \
+        18 + (i % 62), \
+        'tenant-' || (i % 10) \
+    FROM generate_series(1, $1::bigint) AS s(i)";
+
+/// Byte comparison whose time does not depend on where the inputs differ.
+fn same(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+async fn require_token(
+    data: web::Data<AppState>,
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let expected = data.config.admin_token.as_deref().unwrap_or_default();
+    let authorized = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .is_some_and(|token| !expected.is_empty() && same(token.trim().as_bytes(), expected.as_bytes()));
+    if !authorized {
+        let response = HttpResponse::Unauthorized()
+            .insert_header((header::WWW_AUTHENTICATE, "Bearer"))
+            .json(serde_json::json!({ "error": "Invalid or missing admin token" }));
+        return Ok(req.into_response(response).map_into_right_body());
+    }
+    next.call(req).await.map(ServiceResponse::map_into_left_body)
+}
+
+#[derive(Deserialize)]
+struct ResetParams {
+    users: Option<i64>,
+}
+
+/// POST /admin/reset  — truncates, re-seeds, clears the caches and restarts
+/// the `RANDOM_SEED` id sequence.
+#[post("/reset")]
+async fn reset(data: web::Data<AppState>, query: web::Query<ResetParams>) -> impl Responder {
+    let users = query.users.unwrap_or(DEFAULT_USERS);
+    if !(0..=MAX_USERS).contains(&users) {
+        return HttpResponse::BadRequest()
+            .json(serde_json::json!({ "error": format!("users must be between 0 and {MAX_USERS}") }));
+    }
+
+    let started = Instant::now();
+    let mut client = match data.pool.get().await {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Pool error: {e}");
+            return HttpResponse::InternalServerError()
+                .json(serde_json::json!({ "error": "Database connection error" }));
+        }
+    };
+
+    let result = async {
+        let tx = client.transaction().await?;
+        // A large seed may outlast STATEMENT_TIMEOUT_MS, which is meant for
+        // benchmark queries.  The change-feed trigger, if installed, is off
+        // during the seed so subscribers are not flooded with inserts.
+        tx.batch_execute(&format!(
+            "SET LOCAL statement_timeout = 0; \
+             TRUNCATE {} RESTART IDENTITY; \
+             {}",
+            TABLES.join(", "),
+            feed_trigger("DISABLE")
+        ))
+        .await?;
+        tx.execute(SEED, &[&users]).await?;
+        tx.batch_execute(&format!(
+            "{}; \
+             REFRESH MATERIALIZED VIEW user_stats; \
+             ANALYZE users",
+            feed_trigger("ENABLE")
+        ))
+        .await?;
+        tx.commit().await
+    }
+    .await;
+    if let Err(e) = result {
+        eprintln!("Reset error: {e}");
+        return HttpResponse::InternalServerError().json(serde_json::json!({ "error": "Database reset error" }));
+    }
+
+    let (cached_responses, _) = clear_caches(&data);
+    data.users.reset();
+    let duration_ms = started.elapsed().as_millis() as u64;
+    println!("Admin reset: {users} users seeded in {duration_ms}ms");
+    HttpResponse::Ok().json(serde_json::json!({
+        "users": users,
+        "truncated": TABLES,
+        "cached_responses_cleared": cached_responses,
+        "duration_ms": duration_ms
+    }))
+}
+
+#[derive(Deserialize)]
+struct SeedParams {
+    users: Option<i64>,
+    #[serde(default)]
+    on_conflict: OnConflict,
+}
+
+/// POST /admin/seed  — the reset's seed on top of the current rows.
+#[post("/seed")]
+async fn seed(data: web::Data<AppState>, query: web::Query<SeedParams>) -> impl Responder {
+    let users = query.users.unwrap_or(DEFAULT_USERS);
+    if !(0..=MAX_USERS).contains(&users) {
+        return HttpResponse::BadRequest()
+            .json(serde_json::json!({ "error": format!("users must be between 0 and {MAX_USERS}") }));
+    }
+
+    let started = Instant::now();
+    let mut client = match data.pool.get().await {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Pool error: {e}");
+            return HttpResponse::InternalServerError()
+                .json(serde_json::json!({ "error": "Database connection error" }));
+        }
+    };
+
+    let result = async {
+        let tx = client.transaction().await?;
+        // As in the reset: no statement timeout, and no change feed flood.
+        tx.batch_execute(&format!("SET LOCAL statement_timeout = 0; {}", feed_trigger("DISABLE"))).await?;
+        let row = tx.query_one(&query.on_conflict.counting(SEED, false), &[&users]).await?;
+        tx.batch_execute(&format!(
+            "{}; \
+             REFRESH MATERIALIZED VIEW user_stats; \
+             ANALYZE users",
+            feed_trigger("ENABLE")
+        ))
+        .await?;
+        tx.commit().await?;
+        let (inserted, updated): (i64, i64) = (row.get(0), row.get(1));
+        Ok::<_, tokio_postgres::Error>(BulkInsert { inserted, updated, skipped: users - inserted - updated })
+    }
+    .await;
+    let counts = match result.map_err(RepoError::from) {
+        Ok(counts) => counts,
+        Err(RepoError::Conflict(e)) => {
+            eprintln!("Seed error: {e}");
+            return HttpResponse::Conflict().json(serde_json::json!({ "error": "Email already exists" }));
+        }
+        Err(e) => {
+            eprintln!("Seed error: {e}");
+            return HttpResponse::InternalServerError().json(serde_json::json!({ "error": "Database seed error" }));
+        }
+    };
+
+    clear_caches(&data);
+    data.users.reset();
+    let duration_ms = started.elapsed().as_millis() as u64;
+    println!(
+        "Admin seed: {} inserted, {} updated, {} skipped in {duration_ms}ms",
+        counts.inserted, counts.updated, counts.skipped
+    );
+    HttpResponse::Ok().json(serde_json::json!({
+        "inserted": counts.inserted,
+        "updated": counts.updated,
+        "skipped": counts.skipped,
+        "duration_ms": duration_ms
+    }))
+}
+
+/// Entries dropped from the response cache, and whether a `/cached-users`
+/// snapshot was dropped.
+fn clear_caches(data: &AppState) -> (usize, bool) {
+    (data.cache.clear(), data.users_snapshot.clear())
+}
+
+/// POST /admin/cache-clear
+#[post("/cache-clear")]
+async fn cache_clear(data: web::Data<AppState>) -> impl Responder {
+    let (cached_responses, cached_users) = clear_caches(&data);
+    println!("Admin cache clear: {cached_responses} cached responses dropped");
+    HttpResponse::Ok().json(serde_json::json!({
+        "cached_responses_cleared": cached_responses,
+        "cached_users_cleared": cached_users
+    }))
+}
+
+/// POST /admin/gc  — closes the idle pool connections.
+#[post("/gc")]
+async fn gc(data: web::Data<AppState>) -> impl Responder {
+    let closed = data.pool.drop_idle().await;
+    let status = data.pool.status();
+    println!("Admin gc: {closed} idle connections closed");
+    HttpResponse::Ok().json(serde_json::json!({
+        "idle_connections_closed": closed,
+        "pool": {
+            "size": status.size,
+            "available": status.available
+        }
+    }))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/admin")
+            .wrap(from_fn(require_token))
+            .service(reset)
+            .service(seed)
+            .service(cache_clear)
+            .service(gc),
+    );
+}
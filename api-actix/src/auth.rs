@@ -0,0 +1,190 @@
+//! Registration and login against the `accounts` table.
+//!
+//! Passwords are hashed with argon2id (the crate defaults: 19 MiB, 2 passes,
+//! 1 lane, the OWASP baseline), so every call costs tens of milliseconds of
+//! CPU.  Hashing and verification run on the worker's blocking pool
+//! (`MAX_BLOCKING_THREADS`) to keep the event loop serving I/O-bound requests,
+//! which is exactly the mixed CPU + I/O load these endpoints exist to measure.
+
+use actix_web::{post, web, HttpResponse, Responder};
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use tokio_postgres::error::SqlState;
+
+use crate::db::cancellable;
+use crate::AppState;
+
+/// Longest password accepted; argon2 input is unbounded, request bodies are not.
+const MAX_PASSWORD_LEN: usize = 1024;
+const MIN_PASSWORD_LEN: usize = 8;
+
+#[derive(Deserialize)]
+pub struct Credentials {
+    pub email: String,
+    pub password: String,
+}
+
+#[derive(Serialize)]
+pub struct Account {
+    pub id: i32,
+    pub email: String,
+    pub created_at: DateTime<Utc>,
+}
+
+fn hash_password(password: &str) -> Result<String, argon2::password_hash::Error> {
+    let salt = SaltString::generate(&mut OsRng);
+    Ok(Argon2::default().hash_password(password.as_bytes(), &salt)?.to_string())
+}
+
+fn verify_password(password: &str, hash: &str) -> bool {
+    PasswordHash::new(hash)
+        .is_ok_and(|parsed| Argon2::default().verify_password(password.as_bytes(), &parsed).is_ok())
+}
+
+/// Hash verified when the email is unknown, so that a miss costs as much as a
+/// wrong password and the response time does not reveal which accounts exist.
+fn dummy_hash() -> &'static str {
+    static HASH: OnceLock<String> = OnceLock::new();
+    HASH.get_or_init(|| hash_password("not-a-real-password").expect("argon2 hashing failed"))
+}
+
+fn validate(body: &Credentials) -> Result<(), &'static str> {
+    if !body.email.contains('@') {
+        return Err("email must be a valid address");
+    }
+    if body.password.len() < MIN_PASSWORD_LEN || body.password.len() > MAX_PASSWORD_LEN {
+        return Err("password must be between 8 and 1024 bytes");
+    }
+    Ok(())
+}
+
+/// POST /auth/register  — creates an account and returns 201, or 409 when the
+/// email is taken.
+#[post("/auth/register")]
+async fn register(data: web::Data<AppState>, body: web::Json<Credentials>) -> impl Responder {
+    if let Err(msg) = validate(&body) {
+        return HttpResponse::BadRequest().json(serde_json::json!({ "error": msg }));
+    }
+
+    let Credentials { email, password } = body.into_inner();
+    let hash = match web::block(move || hash_password(&password)).await {
+        Ok(Ok(h)) => h,
+        Ok(Err(e)) => {
+            eprintln!("Hash error: {e}");
+            return HttpResponse::InternalServerError()
+                .json(serde_json::json!({ "error": "Password hashing error" }));
+        }
+        Err(e) => {
+            eprintln!("Blocking pool error: {e}");
+            return HttpResponse::InternalServerError()
+                .json(serde_json::json!({ "error": "Password hashing error" }));
+        }
+    };
+
+    let client = match data.pool.get().await {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Pool error: {e}");
+            return HttpResponse::InternalServerError()
+                .json(serde_json::json!({ "error": "Database connection error" }));
+        }
+    };
+
+    match cancellable(
+        &client,
+        client.query_one(
+            "INSERT INTO accounts (email, password_hash) VALUES ($1, $2) \
+             RETURNING id, email, created_at",
+            &[&email, &hash],
+        ),
+    )
+    .await
+    {
+        Ok(row) => HttpResponse::Created().json(Account {
+            id: row.get("id"),
+            email: row.get("email"),
+            created_at: row.get("created_at"),
+        }),
+        Err(e) => {
+            if e.code() == Some(&SqlState::UNIQUE_VIOLATION) {
+                HttpResponse::Conflict()
+                    .json(serde_json::json!({ "error": "Email already registered" }))
+            } else {
+                eprintln!("Insert error: {e}");
+                HttpResponse::InternalServerError()
+                    .json(serde_json::json!({ "error": "Database insert error" }))
+            }
+        }
+    }
+}
+
+/// POST /auth/login  — verifies the password and returns the account, or 401.
+#[post("/auth/login")]
+async fn login(data: web::Data<AppState>, body: web::Json<Credentials>) -> impl Responder {
+    if body.password.len() > MAX_PASSWORD_LEN {
+        return HttpResponse::Unauthorized()
+            .json(serde_json::json!({ "error": "Invalid credentials" }));
+    }
+
+    let client = match data.pool.get().await {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Pool error: {e}");
+            return HttpResponse::InternalServerError()
+                .json(serde_json::json!({ "error": "Database connection error" }));
+        }
+    };
+
+    let row = match cancellable(
+        &client,
+        client.query_opt(
+            "SELECT id, email, password_hash, created_at FROM accounts WHERE email = $1",
+            &[&body.email],
+        ),
+    )
+    .await
+    {
+        Ok(row) => row,
+        Err(e) => {
+            eprintln!("Query error: {e}");
+            return HttpResponse::InternalServerError()
+                .json(serde_json::json!({ "error": "Database query error" }));
+        }
+    };
+    // Return the connection before the slow part.
+    drop(client);
+
+    let hash: Option<String> = row.as_ref().map(|r| r.get("password_hash"));
+    let password = body.into_inner().password;
+    let verify = move || match hash {
+        Some(hash) => verify_password(&password, &hash),
+        None => verify_password(&password, dummy_hash()),
+    };
+    let valid = match web::block(verify).await {
+        Ok(valid) => valid,
+        Err(e) => {
+            eprintln!("Blocking pool error: {e}");
+            return HttpResponse::InternalServerError()
+                .json(serde_json::json!({ "error": "Password verification error" }));
+        }
+    };
+
+    match row {
+        Some(row) if valid => HttpResponse::Ok().json(Account {
+            id: row.get("id"),
+            email: row.get("email"),
+            created_at: row.get("created_at"),
+        }),
+        _ => HttpResponse::Unauthorized()
+            .json(serde_json::json!({ "error": "Invalid credentials" })),
+    }
+}
+
+/// Registers the `/auth/*` endpoints.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(register).service(login);
+}
@@ -0,0 +1,60 @@
+//! Bulk insert: POST /users/bulk?on_conflict=error|skip|update.
+//!
+//! The body is a JSON array of up to [`MAX_USERS`] objects shaped like the
+//! body of POST /users, written in one statement.  `on_conflict` decides what
+//! a taken email does to the batch:
+//!
+//! * `error` (default) — nothing is written and the answer is 409, as for a
+//!   single insert;
+//! * `skip`   — `ON CONFLICT DO NOTHING`, the existing row is kept;
+//! * `update` — `ON CONFLICT DO UPDATE`, the existing row gets the new name,
+//!   age and tags (in multi-tenant mode, only a row of the same tenant).
+//!
+//! The answer is `{"inserted", "updated", "skipped"}`, so an ingestion run
+//! that replays emails keeps going and still knows how much it wrote.  Bulk
+//! writes are neither audited nor delivered to webhooks.
+
+use actix_web::{post, web, HttpResponse, Responder};
+use serde::Deserialize;
+
+use crate::negotiate::{Negotiated, Output};
+use crate::repo::{OnConflict, RepoError};
+use crate::tenant::Tenant;
+use crate::{storage_error, AppState, CreateUser};
+
+/// Most users in one request.
+pub const MAX_USERS: usize = 1000;
+
+#[derive(Deserialize)]
+pub struct BulkParams {
+    #[serde(default)]
+    on_conflict: OnConflict,
+}
+
+/// POST /users/bulk  — inserts a batch of users; 200 with the counts.
+#[post("/users/bulk")]
+async fn bulk_insert(
+    data: web::Data<AppState>,
+    query: web::Query<BulkParams>,
+    body: web::Json<Vec<CreateUser>>,
+    output: Output,
+    tenant: Tenant,
+) -> impl Responder {
+    if body.len() > MAX_USERS {
+        return HttpResponse::BadRequest()
+            .json(serde_json::json!({ "error": format!("At most {MAX_USERS} users per request") }));
+    }
+
+    match data.users.insert_many(tenant.scope(), &body, query.on_conflict).await {
+        Ok(counts) => Negotiated::new(output, counts).respond(HttpResponse::Ok()),
+        Err(RepoError::Conflict(e)) => {
+            eprintln!("Insert error: {e}");
+            HttpResponse::Conflict().json(serde_json::json!({ "error": "Email already exists" }))
+        }
+        Err(e) => storage_error("Insert", "Database insert error", e),
+    }
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(bulk_insert);
+}
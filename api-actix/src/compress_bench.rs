@@ -0,0 +1,206 @@
+//! GET /compress-bench?algo=gzip|br|zstd&level=N&bytes=M — compression cost
+//! without HTTP (`COMPRESS_BENCH=on`).
+//!
+//! Each request builds the same deterministic body as /payload-static
+//! (`bytes`, default 65536), compresses it with `algo` at `level` on the
+//! blocking pool and answers with the timing, not the compressed bytes, so the
+//! cost measured is the compressor's alone.  Compared with a run of the same
+//! body through compression middleware, the difference is what HTTP adds.
+//!
+//! Compressors are kept in a pool shared by the workers, one list per
+//! algorithm and level, and reused across requests like a server would: gzip
+//! reuses its deflate state, zstd its context and output buffer.  The brotli
+//! encoder cannot be reset, so only its output buffer is kept.  `reused` in
+//! the response says whether the request found one in the pool.
+//!
+//! Levels: gzip 0–9 (default 6), br 0–11 (default 4), zstd 1–22 (default 3).
+
+use actix_web::{get, web, HttpResponse, Responder};
+use flate2::write::DeflateEncoder;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use crate::payload::{self, MAX_SIZE};
+use crate::AppState;
+
+/// gzip member header: deflate, no flags, no mtime, unknown OS.
+const GZIP_HEADER: [u8; 10] = [0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 0xff];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Algo {
+    Gzip,
+    Br,
+    Zstd,
+}
+
+impl Algo {
+    fn name(self) -> &'static str {
+        match self {
+            Algo::Gzip => "gzip",
+            Algo::Br => "br",
+            Algo::Zstd => "zstd",
+        }
+    }
+
+    fn levels(self) -> (u32, u32) {
+        match self {
+            Algo::Gzip => (0, 9),
+            Algo::Br => (0, 11),
+            Algo::Zstd => (1, 22),
+        }
+    }
+
+    fn default_level(self) -> u32 {
+        match self {
+            Algo::Gzip => 6,
+            Algo::Br => 4,
+            Algo::Zstd => 3,
+        }
+    }
+}
+
+enum Compressor {
+    /// Raw deflate between a gzip header and trailer; `reset` keeps the
+    /// deflate state and hands back the finished stream.
+    Gzip(DeflateEncoder<Vec<u8>>),
+    Br(Vec<u8>),
+    Zstd(zstd::bulk::Compressor<'static>, Vec<u8>),
+}
+
+impl Compressor {
+    fn new(algo: Algo, level: u32) -> io::Result<Compressor> {
+        Ok(match algo {
+            Algo::Gzip => {
+                Compressor::Gzip(DeflateEncoder::new(GZIP_HEADER.to_vec(), flate2::Compression::new(level)))
+            }
+            Algo::Br => Compressor::Br(Vec::new()),
+            Algo::Zstd => Compressor::Zstd(zstd::bulk::Compressor::new(level as i32)?, Vec::new()),
+        })
+    }
+
+    /// Compresses `input` at `level`; the size of the compressed output.
+    fn compress(&mut self, input: &[u8], level: u32) -> io::Result<usize> {
+        match self {
+            Compressor::Gzip(encoder) => {
+                encoder.write_all(input)?;
+                let mut out = encoder.reset(GZIP_HEADER.to_vec())?;
+                let mut crc = flate2::Crc::new();
+                crc.update(input);
+                out.extend_from_slice(&crc.sum().to_le_bytes());
+                out.extend_from_slice(&crc.amount().to_le_bytes());
+                Ok(out.len())
+            }
+            Compressor::Br(out) => {
+                out.clear();
+                let params = brotli::enc::BrotliEncoderParams { quality: level as i32, ..Default::default() };
+                brotli::BrotliCompress(&mut &input[..], out, &params)?;
+                Ok(out.len())
+            }
+            Compressor::Zstd(context, out) => {
+                out.clear();
+                out.reserve(zstd::zstd_safe::compress_bound(input.len()));
+                context.compress_to_buffer(input, out)
+            }
+        }
+    }
+}
+
+/// Idle compressors per algorithm and level, shared by every worker.
+#[derive(Default)]
+pub struct Compressors(Mutex<HashMap<(Algo, u32), Vec<Compressor>>>);
+
+impl Compressors {
+    fn pool(&self) -> std::sync::MutexGuard<'_, HashMap<(Algo, u32), Vec<Compressor>>> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn take(&self, algo: Algo, level: u32) -> Option<Compressor> {
+        self.pool().get_mut(&(algo, level))?.pop()
+    }
+
+    fn put(&self, algo: Algo, level: u32, compressor: Compressor) {
+        self.pool().entry((algo, level)).or_default().push(compressor);
+    }
+}
+
+#[derive(Deserialize)]
+struct BenchParams {
+    algo: Algo,
+    level: Option<u32>,
+    bytes: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct BenchResult {
+    algo: Algo,
+    level: u32,
+    input_bytes: usize,
+    output_bytes: usize,
+    ratio: f64,
+    compress_us: u64,
+    /// Input megabytes (10^6 bytes) compressed per second.
+    throughput_mb_s: f64,
+    reused: bool,
+}
+
+fn bad_request(msg: String) -> HttpResponse {
+    HttpResponse::BadRequest().json(serde_json::json!({ "error": msg }))
+}
+
+/// GET /compress-bench?algo=gzip|br|zstd&level=N&bytes=M
+#[get("/compress-bench")]
+async fn compress_bench(data: web::Data<AppState>, query: web::Query<BenchParams>) -> impl Responder {
+    let algo = query.algo;
+    let level = query.level.unwrap_or_else(|| algo.default_level());
+    let (min, max) = algo.levels();
+    if !(min..=max).contains(&level) {
+        return bad_request(format!("level must be between {min} and {max} for {}", algo.name()));
+    }
+    let bytes = query.bytes.unwrap_or(65536);
+    if bytes == 0 || bytes > MAX_SIZE {
+        return bad_request(format!("bytes must be between 1 and {MAX_SIZE}"));
+    }
+
+    let result = web::block(move || -> io::Result<BenchResult> {
+        let input = payload::body(bytes);
+        let pooled = data.compressors.take(algo, level);
+        let reused = pooled.is_some();
+        let mut compressor = match pooled {
+            Some(compressor) => compressor,
+            None => Compressor::new(algo, level)?,
+        };
+        let started = Instant::now();
+        let output_bytes = compressor.compress(&input, level)?;
+        let elapsed = started.elapsed();
+        data.compressors.put(algo, level, compressor);
+        Ok(BenchResult {
+            algo,
+            level,
+            input_bytes: bytes,
+            output_bytes,
+            ratio: bytes as f64 / output_bytes as f64,
+            compress_us: elapsed.as_micros() as u64,
+            throughput_mb_s: bytes as f64 / elapsed.as_secs_f64().max(1e-9) / 1e6,
+            reused,
+        })
+    })
+    .await;
+    match result {
+        Ok(Ok(result)) => HttpResponse::Ok().json(result),
+        Ok(Err(e)) => failed(e),
+        Err(e) => failed(e),
+    }
+}
+
+fn failed(e: impl std::fmt::Display) -> HttpResponse {
+    eprintln!("Compress bench error: {e}");
+    HttpResponse::InternalServerError().json(serde_json::json!({ "error": "Compression failed" }))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(compress_bench);
+}
@@ -0,0 +1,907 @@
+//! Typed service configuration.
+//!
+//! Loaded once at startup from, in increasing order of precedence:
+//!
+//! 1. built-in defaults;
+//! 2. an optional TOML file (`config.toml` in the working directory, or the
+//!    path in `CONFIG_FILE`), using the lower-case variable names as keys;
+//! 3. environment variables (`DATABASE_URL`, `PORT`, `WORKERS`, …).
+//!
+//! Empty variables count as unset, so docker-compose can pass `${VAR:-}`
+//! through without overriding the defaults.  Everything is validated before
+//! the server starts; a bad value aborts with a message naming the key.
+
+use actix_web::http::KeepAlive;
+use figment::providers::{Env, Format, Toml};
+use figment::Figment;
+use serde::{de, Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::middleware::real_ip::Cidr;
+
+/// Effective configuration, shared with handlers through `AppState`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Config {
+    /// Never serialized: it carries the database password.
+    #[serde(skip)]
+    pub database_url: String,
+    pub port: u16,
+    /// `PROFILING=on` exposes /debug/pprof/* (never enable during measured runs).
+    pub profiling: bool,
+    /// `EVENTS=on` streams user changes at GET /events (`events`).
+    pub events: bool,
+    /// How often a waiting GET /users/poll re-reads the table when `EVENTS`
+    /// is off, in ms (`LONG_POLL_INTERVAL_MS`, see `poll`).
+    pub long_poll_interval_ms: u64,
+    /// `MULTI_TENANT=on` scopes `users` queries to the request's tenant
+    /// (`tenant`, `repo`).
+    pub multi_tenant: bool,
+    /// Base domain whose subdomains name tenants, e.g. `localhost` for
+    /// `acme.localhost` (`TENANT_DOMAIN`).
+    pub tenant_domain: Option<String>,
+    /// How tenant isolation is enforced (`TENANT_SCOPING`, see `repo`).
+    pub tenant_scoping: TenantScoping,
+    /// `AUDIT=on` records every `/users` mutation in `audit_log` (`repo`).
+    pub audit: bool,
+    /// `TRANSACTION_PER_REQUEST=on` runs each request's repository calls in
+    /// one transaction (`middleware::transaction`).
+    pub transaction_per_request: bool,
+    /// `WEBHOOKS=on` enables /webhooks and the delivery task (`webhooks`).
+    pub webhooks: bool,
+    /// Delivery attempts before a webhook delivery is marked `failed`
+    /// (`WEBHOOK_MAX_ATTEMPTS`).
+    pub webhook_max_attempts: u32,
+    /// `HYPERMEDIA=on` adds absolute HAL `_links` to `/users` responses (`hal`).
+    pub hypermedia: bool,
+    /// `JSON_ENVELOPE=on` wraps response payloads in `{"data", "meta"}`
+    /// (`negotiate`).
+    pub json_envelope: bool,
+    /// `JSON_PRETTY=on` indents JSON response bodies (`negotiate`).
+    pub json_pretty: bool,
+    /// `USERS_STREAMING=on` streams GET /users as a JSON array read through
+    /// the export cursor instead of collecting it first (`export`).
+    pub users_streaming: bool,
+    /// Serialized form of `created_at` (`TIMESTAMP_FORMAT`, see `timestamp`).
+    pub timestamp_format: TimestampFormat,
+    /// How /db and /queries pick random rows (`RANDOM_ROW_SELECTION`, see
+    /// `repo::draws`).
+    pub random_rows: RandomRows,
+    /// Seed of the `id-range` draws, `None` = random per process
+    /// (`RANDOM_SEED`).
+    pub random_seed: Option<u64>,
+    /// Age in seconds at which the cached `MAX(id)` is read again, 0 = read
+    /// once (`MAX_ID_REFRESH_SECS`).
+    pub max_id_refresh_secs: u64,
+    /// Body sizes precompressed at startup for GET /payload-static, empty =
+    /// endpoint off (`STATIC_PAYLOAD_SIZES`, comma-separated bytes; see `payload`).
+    pub static_payload_sizes: Vec<usize>,
+    /// zstd level of those bodies, 1..=22 (`STATIC_PAYLOAD_ZSTD_LEVEL`).
+    pub static_payload_zstd_level: i32,
+    /// `COMPRESS_BENCH=on` enables GET /compress-bench (`compress_bench`).
+    pub compress_bench: bool,
+    /// `ROUTING_BENCH=on` registers the generated routes (`routing`).
+    pub routing_bench: bool,
+    /// `RESPONSE_CACHE=on` enables the in-process GET cache (`middleware::cache`).
+    pub response_cache: bool,
+    /// Maximum connections in the Postgres pool.
+    pub pool_size: usize,
+    /// How often the pool status is sampled for /pool-stats and /metrics, in
+    /// ms (`POOL_STATS_INTERVAL_MS`, see `pool_stats`).
+    pub pool_stats_interval_ms: u64,
+    pub pool_autosize: PoolAutosizeSettings,
+    /// `DB_TIMING=on` records total, DB and pool-wait histograms per endpoint
+    /// on /metrics (`timing`).
+    pub db_timing: bool,
+    /// File the per-route latency summary is written to on shutdown, `None`
+    /// = not recorded (`LATENCY_SUMMARY_PATH`, see `middleware::latency`).
+    pub latency_summary_path: Option<String>,
+    /// Per-statement server-side limit in ms, 0 = none (`STATEMENT_TIMEOUT_MS`).
+    pub statement_timeout_ms: u64,
+    /// Deadline for a whole request in ms, 0 = none (`REQUEST_TIMEOUT_MS`).
+    pub request_timeout_ms: u64,
+    /// Initial connection attempts before giving up (`DB_STARTUP_RETRIES`).
+    pub db_startup_retries: u32,
+    /// Overall deadline for the initial connection, in seconds
+    /// (`DB_STARTUP_TIMEOUT`).
+    pub db_startup_timeout: u64,
+    pub runtime: RuntimeSettings,
+    pub tcp: TcpSettings,
+    /// Background job worker tasks, 0 = jobs disabled (`JOB_WORKERS`).
+    pub job_workers: usize,
+    /// Idle worker poll interval in ms (`JOB_POLL_MS`).
+    pub job_poll_ms: u64,
+    /// Period of the scheduled refreshes in seconds, 0 = off
+    /// (`SCHEDULER_INTERVAL_SECS`).
+    pub scheduler_interval_secs: u64,
+    /// Maximum random delay added to each run, in ms (`SCHEDULER_JITTER_MS`).
+    pub scheduler_jitter_ms: u64,
+    /// Whether /cached-users serves a buffer serialized once per refresh or
+    /// serializes the list per request (`CACHED_USERS_ENCODING`, see `scheduler`).
+    pub cached_users_encoding: CachedEncoding,
+    /// Proxies whose `Forwarded`/`X-Forwarded-For` headers are believed
+    /// (`TRUSTED_PROXIES`, comma-separated CIDRs; see `middleware::real_ip`).
+    pub trusted_proxies: Vec<Cidr>,
+    /// Bearer token for the `/admin` routes, `None` = not registered
+    /// (`ADMIN_TOKEN`, see `admin`).  Never serialized.
+    #[serde(skip)]
+    pub admin_token: Option<String>,
+    pub shed: ShedSettings,
+    pub route_concurrency: RouteConcurrencySettings,
+    pub priority: PrioritySettings,
+    pub session: SessionSettings,
+    pub http3: Http3Settings,
+}
+
+/// Thread and runtime sizing.
+///
+/// * `WORKERS`              — Actix worker threads (default: usable CPUs,
+///   honouring the cgroup CPU quota)
+/// * `TOKIO_WORKER_THREADS` — when set, the main system runs on a multi-thread
+///   tokio runtime with this many threads instead of Actix's current-thread one
+/// * `MAX_BLOCKING_THREADS` — blocking-pool size per worker
+///   (default: 512 / usable CPUs, same budget as actix-server)
+#[derive(Debug, Clone, Serialize)]
+pub struct RuntimeSettings {
+    pub cpus: usize,
+    pub workers: usize,
+    pub tokio_worker_threads: Option<usize>,
+    pub max_blocking_threads: usize,
+    pub tokio_flavor: &'static str,
+}
+
+/// Socket and HTTP connection settings, pinned explicitly so every framework
+/// runs with the same values instead of its own defaults.
+///
+/// * `TCP_NODELAY`       — on/off (default: on, as Go and Node do)
+/// * `LISTEN_BACKLOG`    — pending-connection queue (default: 1024)
+/// * `KEEPALIVE_SECS`    — HTTP keep-alive idle timeout, 0 disables (default: 5)
+/// * `CLIENT_TIMEOUT_MS` — time allowed to receive request headers, 0 disables
+///   (default: 5000)
+#[derive(Debug, Clone, Serialize)]
+pub struct TcpSettings {
+    pub tcp_nodelay: bool,
+    pub listen_backlog: u32,
+    pub keepalive_secs: u64,
+    pub client_timeout_ms: u64,
+}
+
+/// Adaptive pool sizing (see `pool_autosize`); `POOL_SIZE` is the starting size.
+///
+/// * `POOL_AUTOSIZE`                — on/off, deadpool only (default: off)
+/// * `POOL_AUTOSIZE_MIN` / `POOL_AUTOSIZE_MAX` — bounds (default: 2 / 4 × `POOL_SIZE`)
+/// * `POOL_AUTOSIZE_TARGET_WAIT_MS` — average checkout wait above which the
+///   pool grows (default: 2)
+/// * `POOL_AUTOSIZE_INTERVAL_MS`    — time between decisions (default: 1000)
+/// * `POOL_AUTOSIZE_RESERVE`        — Postgres connections left free for
+///   other clients (default: 5)
+#[derive(Debug, Clone, Serialize)]
+pub struct PoolAutosizeSettings {
+    pub enabled: bool,
+    pub min: usize,
+    pub max: usize,
+    pub target_wait_ms: u64,
+    pub interval_ms: u64,
+    pub reserve: u64,
+}
+
+/// Load-shedding thresholds (see `middleware::load_shed`); 0 disables each.
+///
+/// * `SHED_MAX_INFLIGHT`  — reject once more requests are in progress (default: 0)
+/// * `SHED_P99_BUDGET_MS` — reject after a window whose p99 exceeded this (default: 0)
+/// * `SHED_WINDOW_MS`     — latency window length (default: 1000)
+#[derive(Debug, Clone, Serialize)]
+pub struct ShedSettings {
+    pub max_in_flight: u64,
+    pub p99_budget_ms: u64,
+    pub window_ms: u64,
+}
+
+/// Per-route concurrency limits (see `middleware::concurrency`).
+///
+/// * `ROUTE_CONCURRENCY` — `route=permits` pairs, comma-separated, with routes
+///   as registered (`/db=32,/users/{id}=8`); in the config file, a table
+/// * `ROUTE_QUEUE_DEPTH` — requests that may wait for a permit per route
+///   before the 429s start (default: 0, reject as soon as all are taken)
+#[derive(Debug, Clone, Serialize)]
+pub struct RouteConcurrencySettings {
+    pub limits: BTreeMap<String, usize>,
+    pub queue_depth: u64,
+}
+
+/// Priority scheduling experiment (see `middleware::priority`).
+///
+/// * `PRIORITY_SLOTS`        — requests served at once through the read and
+///   write queues (default: 0, scheduling off)
+/// * `PRIORITY_CLASSIFY`     — method or header (`X-Request-Class`) (default: method)
+/// * `PRIORITY_READ_WEIGHT` / `PRIORITY_WRITE_WEIGHT` — slots handed to each
+///   class per round under contention (default: 4 / 1)
+#[derive(Debug, Clone, Serialize)]
+pub struct PrioritySettings {
+    pub slots: u64,
+    pub classify: RequestClass,
+    pub read_weight: u32,
+    pub write_weight: u32,
+}
+
+/// `method` classifies GET/HEAD/OPTIONS as reads, `header` reads
+/// `X-Request-Class` first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RequestClass {
+    Method,
+    Header,
+}
+
+/// Server sessions (see `session`).
+///
+/// * `SESSION_STORE` — off, cookie or redis (default: off)
+/// * `REDIS_URL`     — Redis for the `redis` store (default: redis://127.0.0.1:6379)
+/// * `SESSION_KEY`   — cookie signing key, at least 64 bytes (default: random
+///   per process)
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionSettings {
+    pub store: SessionBackend,
+    /// Never serialized: it may carry the Redis password.
+    #[serde(skip)]
+    pub redis_url: String,
+    #[serde(skip)]
+    pub key: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionBackend {
+    Off,
+    Cookie,
+    Redis,
+}
+
+/// `where` filters in the SQL, `rls` leaves it to the row-level security
+/// policy on `users`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TenantScoping {
+    Where,
+    Rls,
+}
+
+/// `id-range` reads uniformly drawn ids by primary key, `order-by-random`
+/// (the only choice, and the default, with `uuid-keys`) is the
+/// `ORDER BY RANDOM()` the other APIs use, for comparison with older
+/// results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RandomRows {
+    IdRange,
+    OrderByRandom,
+}
+
+/// `pre-encoded` serializes the cached user list once per refresh,
+/// `per-request` on every request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CachedEncoding {
+    PreEncoded,
+    PerRequest,
+}
+
+/// How user timestamps are serialized (see `timestamp`): chrono's RFC 3339,
+/// RFC 3339 with a fixed number of fraction digits, or epoch milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TimestampFormat {
+    Rfc3339,
+    Rfc3339Secs,
+    Rfc3339Millis,
+    Rfc3339Micros,
+    Rfc3339Nanos,
+    EpochMillis,
+}
+
+/// Experimental HTTP/3 listener (see `http3`), compiled in with the `http3`
+/// feature.
+///
+/// * `HTTP3`         — on/off (default: off)
+/// * `HTTP3_PORT`    — UDP port (default: same as `PORT`)
+/// * `TLS_CERT_FILE` / `TLS_KEY_FILE` — PEM certificate chain and private key;
+///   when unset a self-signed certificate for `localhost` is generated
+#[derive(Debug, Clone, Serialize)]
+pub struct Http3Settings {
+    pub enabled: bool,
+    pub port: u16,
+    pub tls_cert_file: Option<String>,
+    pub tls_key_file: Option<String>,
+}
+
+/// Startup configuration failure.
+#[derive(Debug)]
+pub enum ConfigError {
+    /// A source could not be read or a value has the wrong type.
+    Load(Box<figment::Error>),
+    /// Values parsed but are not acceptable.
+    Invalid(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Load(e) => write!(f, "{e}"),
+            ConfigError::Invalid(msg) => f.write_str(msg),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Raw shape of the sources; defaults and validation are applied in
+/// [`Config::load`].
+#[derive(Debug, Deserialize)]
+struct RawConfig {
+    database_url: Option<String>,
+    #[serde(default = "default_port")]
+    port: u16,
+    #[serde(default, deserialize_with = "flag")]
+    profiling: bool,
+    #[serde(default, deserialize_with = "flag")]
+    events: bool,
+    #[serde(default = "default_long_poll_interval_ms")]
+    long_poll_interval_ms: u64,
+    #[serde(default, deserialize_with = "flag")]
+    multi_tenant: bool,
+    tenant_domain: Option<String>,
+    #[serde(default = "default_tenant_scoping")]
+    tenant_scoping: TenantScoping,
+    #[serde(default, deserialize_with = "flag")]
+    audit: bool,
+    #[serde(default, deserialize_with = "flag")]
+    transaction_per_request: bool,
+    #[serde(default, deserialize_with = "flag")]
+    webhooks: bool,
+    #[serde(default = "default_webhook_max_attempts")]
+    webhook_max_attempts: u32,
+    #[serde(default, deserialize_with = "flag")]
+    hypermedia: bool,
+    #[serde(default, deserialize_with = "flag")]
+    json_envelope: bool,
+    #[serde(default, deserialize_with = "flag")]
+    json_pretty: bool,
+    #[serde(default, deserialize_with = "flag")]
+    users_streaming: bool,
+    #[serde(default = "default_timestamp_format")]
+    timestamp_format: TimestampFormat,
+    #[serde(default = "default_random_rows")]
+    random_row_selection: RandomRows,
+    random_seed: Option<u64>,
+    #[serde(default = "default_max_id_refresh_secs")]
+    max_id_refresh_secs: u64,
+    #[serde(default, deserialize_with = "size_list")]
+    static_payload_sizes: Vec<usize>,
+    #[serde(default = "default_static_payload_zstd_level")]
+    static_payload_zstd_level: i32,
+    #[serde(default, deserialize_with = "flag")]
+    compress_bench: bool,
+    #[serde(default, deserialize_with = "flag")]
+    routing_bench: bool,
+    #[serde(default, deserialize_with = "flag")]
+    response_cache: bool,
+    #[serde(default = "default_pool_size")]
+    pool_size: usize,
+    #[serde(default = "default_pool_stats_interval_ms")]
+    pool_stats_interval_ms: u64,
+    #[serde(default, deserialize_with = "flag")]
+    pool_autosize: bool,
+    #[serde(default = "default_pool_autosize_min")]
+    pool_autosize_min: usize,
+    pool_autosize_max: Option<usize>,
+    #[serde(default = "default_pool_autosize_target_wait_ms")]
+    pool_autosize_target_wait_ms: u64,
+    #[serde(default = "default_pool_autosize_interval_ms")]
+    pool_autosize_interval_ms: u64,
+    #[serde(default = "default_pool_autosize_reserve")]
+    pool_autosize_reserve: u64,
+    #[serde(default, deserialize_with = "flag")]
+    db_timing: bool,
+    latency_summary_path: Option<String>,
+    #[serde(default)]
+    statement_timeout_ms: u64,
+    #[serde(default = "default_request_timeout_ms")]
+    request_timeout_ms: u64,
+    #[serde(default = "default_db_startup_retries")]
+    db_startup_retries: u32,
+    #[serde(default = "default_db_startup_timeout")]
+    db_startup_timeout: u64,
+    workers: Option<usize>,
+    tokio_worker_threads: Option<usize>,
+    max_blocking_threads: Option<usize>,
+    #[serde(default)]
+    job_workers: usize,
+    #[serde(default = "default_job_poll_ms")]
+    job_poll_ms: u64,
+    #[serde(default)]
+    scheduler_interval_secs: u64,
+    #[serde(default = "default_scheduler_jitter_ms")]
+    scheduler_jitter_ms: u64,
+    #[serde(default = "default_cached_users_encoding")]
+    cached_users_encoding: CachedEncoding,
+    #[serde(default, deserialize_with = "cidr_list")]
+    trusted_proxies: Vec<Cidr>,
+    admin_token: Option<String>,
+    #[serde(default)]
+    shed_max_inflight: u64,
+    #[serde(default)]
+    shed_p99_budget_ms: u64,
+    #[serde(default = "default_shed_window_ms")]
+    shed_window_ms: u64,
+    #[serde(default, deserialize_with = "route_limits")]
+    route_concurrency: BTreeMap<String, usize>,
+    #[serde(default)]
+    route_queue_depth: u64,
+    #[serde(default)]
+    priority_slots: u64,
+    #[serde(default = "default_priority_classify")]
+    priority_classify: RequestClass,
+    #[serde(default = "default_priority_read_weight")]
+    priority_read_weight: u32,
+    #[serde(default = "default_priority_write_weight")]
+    priority_write_weight: u32,
+    #[serde(default = "default_true", deserialize_with = "flag")]
+    tcp_nodelay: bool,
+    #[serde(default = "default_backlog")]
+    listen_backlog: u32,
+    #[serde(default = "default_keepalive_secs")]
+    keepalive_secs: u64,
+    #[serde(default = "default_client_timeout_ms")]
+    client_timeout_ms: u64,
+    #[serde(default = "default_session_store")]
+    session_store: SessionBackend,
+    #[serde(default = "default_redis_url")]
+    redis_url: String,
+    session_key: Option<String>,
+    #[serde(default, deserialize_with = "flag")]
+    http3: bool,
+    http3_port: Option<u16>,
+    tls_cert_file: Option<String>,
+    tls_key_file: Option<String>,
+}
+
+fn default_port() -> u16 { 3004 }
+fn default_pool_size() -> usize { 10 }
+fn default_pool_stats_interval_ms() -> u64 { 1000 }
+fn default_pool_autosize_min() -> usize { 2 }
+fn default_pool_autosize_target_wait_ms() -> u64 { 2 }
+fn default_pool_autosize_interval_ms() -> u64 { 1000 }
+fn default_pool_autosize_reserve() -> u64 { 5 }
+fn default_long_poll_interval_ms() -> u64 { 250 }
+fn default_request_timeout_ms() -> u64 { 5000 }
+fn default_db_startup_retries() -> u32 { 10 }
+fn default_db_startup_timeout() -> u64 { 60 }
+fn default_shed_window_ms() -> u64 { 1000 }
+fn default_priority_classify() -> RequestClass { RequestClass::Method }
+fn default_priority_read_weight() -> u32 { 4 }
+fn default_priority_write_weight() -> u32 { 1 }
+fn default_job_poll_ms() -> u64 { 500 }
+fn default_scheduler_jitter_ms() -> u64 { 1000 }
+fn default_cached_users_encoding() -> CachedEncoding { CachedEncoding::PreEncoded }
+fn default_webhook_max_attempts() -> u32 { 5 }
+fn default_tenant_scoping() -> TenantScoping { TenantScoping::Where }
+fn default_random_rows() -> RandomRows {
+    if cfg!(feature = "uuid-keys") { RandomRows::OrderByRandom } else { RandomRows::IdRange }
+}
+fn default_timestamp_format() -> TimestampFormat { TimestampFormat::Rfc3339 }
+fn default_max_id_refresh_secs() -> u64 { 5 }
+fn default_static_payload_zstd_level() -> i32 { 19 }
+fn default_session_store() -> SessionBackend { SessionBackend::Off }
+fn default_redis_url() -> String { "redis://127.0.0.1:6379".into() }
+fn default_true() -> bool { true }
+fn default_backlog() -> u32 { 1024 }
+fn default_keepalive_secs() -> u64 { 5 }
+fn default_client_timeout_ms() -> u64 { 5000 }
+
+/// Accepts booleans as well as on/off and 1/0, so `PROFILING=on` keeps working.
+fn flag<'de, D: Deserializer<'de>>(d: D) -> Result<bool, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Flag {
+        Bool(bool),
+        Int(u64),
+        Str(String),
+    }
+
+    match Flag::deserialize(d)? {
+        Flag::Bool(b) => Ok(b),
+        Flag::Int(1) => Ok(true),
+        Flag::Int(0) => Ok(false),
+        Flag::Str(s) if s.eq_ignore_ascii_case("on") => Ok(true),
+        Flag::Str(s) if s.eq_ignore_ascii_case("off") => Ok(false),
+        _ => Err(de::Error::custom("expected on/off, true/false or 1/0")),
+    }
+}
+
+/// Accepts a number or comma-separated string (environment) or a TOML array.
+fn size_list<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<usize>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Item {
+        Int(usize),
+        Str(String),
+    }
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum List {
+        One(Item),
+        Many(Vec<Item>),
+    }
+
+    let items = match List::deserialize(d)? {
+        List::One(item) => vec![item],
+        List::Many(v) => v,
+    };
+    let mut sizes = Vec::new();
+    for item in items {
+        match item {
+            Item::Int(n) => sizes.push(n),
+            Item::Str(s) => {
+                for part in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+                    sizes.push(part.parse().map_err(|_| de::Error::custom(format!("`{part}` is not a size in bytes")))?);
+                }
+            }
+        }
+    }
+    sizes.sort_unstable();
+    sizes.dedup();
+    Ok(sizes)
+}
+
+/// Accepts a comma-separated string (environment) or a TOML array.
+fn cidr_list<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<Cidr>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum List {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    let items = match List::deserialize(d)? {
+        List::One(s) => vec![s],
+        List::Many(v) => v,
+    };
+    items
+        .iter()
+        .flat_map(|s| s.split(','))
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse().map_err(de::Error::custom))
+        .collect()
+}
+
+/// Accepts `route=permits` pairs in a comma-separated string (environment)
+/// or a TOML table.
+fn route_limits<'de, D: Deserializer<'de>>(d: D) -> Result<BTreeMap<String, usize>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Limits {
+        Str(String),
+        Table(BTreeMap<String, usize>),
+    }
+
+    match Limits::deserialize(d)? {
+        Limits::Table(table) => Ok(table),
+        Limits::Str(s) => s
+            .split(',')
+            .map(str::trim)
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (route, permits) = pair
+                    .rsplit_once('=')
+                    .ok_or_else(|| de::Error::custom(format!("`{pair}` is not route=permits")))?;
+                let permits = permits
+                    .trim()
+                    .parse()
+                    .map_err(|_| de::Error::custom(format!("`{permits}` is not a number of permits")))?;
+                Ok((route.trim().to_string(), permits))
+            })
+            .collect(),
+    }
+}
+
+impl Config {
+    /// Reads and validates the configuration.
+    pub fn load() -> Result<Config, ConfigError> {
+        // An explicit CONFIG_FILE must exist; the implicit ./config.toml is optional.
+        let mut figment = Figment::new();
+        match std::env::var("CONFIG_FILE") {
+            Ok(path) if !path.is_empty() => figment = figment.merge(Toml::file_exact(path)),
+            _ if Path::new("config.toml").exists() => {
+                figment = figment.merge(Toml::file_exact("config.toml"))
+            }
+            _ => {}
+        }
+
+        Config::from_figment(figment.merge(Env::raw().filter(|key| {
+            std::env::var_os(key.as_str()).is_some_and(|v| !v.is_empty())
+        })))
+    }
+
+    /// Validates the configuration `figment` describes, without reading the
+    /// environment or `config.toml`; defaults fill in what it does not set.
+    pub fn from_figment(figment: Figment) -> Result<Config, ConfigError> {
+        let raw: RawConfig = figment.extract().map_err(|e| ConfigError::Load(Box::new(e)))?;
+        raw.validate()
+    }
+}
+
+impl RawConfig {
+    fn validate(self) -> Result<Config, ConfigError> {
+        let database_url = self.database_url.ok_or_else(|| {
+            ConfigError::Invalid("DATABASE_URL must be set (environment or config file)".into())
+        })?;
+        if let Err(e) = database_url.parse::<tokio_postgres::Config>() {
+            return Err(ConfigError::Invalid(format!(
+                "DATABASE_URL is not a valid connection string: {e}"
+            )));
+        }
+
+        for (name, value) in [
+            ("POOL_SIZE", Some(self.pool_size)),
+            ("WORKERS", self.workers),
+            ("TOKIO_WORKER_THREADS", self.tokio_worker_threads),
+            ("MAX_BLOCKING_THREADS", self.max_blocking_threads),
+        ] {
+            if value == Some(0) {
+                return Err(ConfigError::Invalid(format!("{name} must be greater than 0")));
+            }
+        }
+        if self.db_startup_retries == 0 {
+            return Err(ConfigError::Invalid("DB_STARTUP_RETRIES must be greater than 0".into()));
+        }
+        if self.shed_window_ms == 0 {
+            return Err(ConfigError::Invalid("SHED_WINDOW_MS must be greater than 0".into()));
+        }
+        if let Some((route, _)) = self.route_concurrency.iter().find(|(route, _)| !route.starts_with('/')) {
+            return Err(ConfigError::Invalid(format!(
+                "ROUTE_CONCURRENCY: `{route}` is not a route pattern (must start with /)"
+            )));
+        }
+        if let Some((route, _)) = self.route_concurrency.iter().find(|(_, &permits)| permits == 0) {
+            return Err(ConfigError::Invalid(format!(
+                "ROUTE_CONCURRENCY: {route} must allow at least 1 request"
+            )));
+        }
+        if self.priority_read_weight == 0 || self.priority_write_weight == 0 {
+            return Err(ConfigError::Invalid(
+                "PRIORITY_READ_WEIGHT and PRIORITY_WRITE_WEIGHT must be greater than 0".into(),
+            ));
+        }
+        if self.job_poll_ms == 0 {
+            return Err(ConfigError::Invalid("JOB_POLL_MS must be greater than 0".into()));
+        }
+        if self.multi_tenant && self.scheduler_interval_secs > 0 {
+            return Err(ConfigError::Invalid(
+                "MULTI_TENANT=on cannot be combined with SCHEDULER_INTERVAL_SECS: \
+                 /cached-users and /users/stats span all tenants"
+                    .into(),
+            ));
+        }
+        if cfg!(feature = "uuid-keys") && self.random_row_selection == RandomRows::IdRange {
+            return Err(ConfigError::Invalid(
+                "RANDOM_ROW_SELECTION=id-range draws integer ids; builds with uuid-keys need order-by-random".into(),
+            ));
+        }
+        if self.random_seed.is_some() && self.random_row_selection == RandomRows::OrderByRandom {
+            return Err(ConfigError::Invalid(
+                "RANDOM_SEED requires RANDOM_ROW_SELECTION=id-range".into(),
+            ));
+        }
+        if self.multi_tenant && self.random_seed.is_some() {
+            return Err(ConfigError::Invalid(
+                "MULTI_TENANT=on cannot be combined with RANDOM_SEED: \
+                 tenant-scoped /db and /queries use ORDER BY RANDOM()"
+                    .into(),
+            ));
+        }
+        if self.webhook_max_attempts == 0 {
+            return Err(ConfigError::Invalid("WEBHOOK_MAX_ATTEMPTS must be greater than 0".into()));
+        }
+        if let Some(size) = self
+            .static_payload_sizes
+            .iter()
+            .find(|&&s| s == 0 || s > crate::payload::MAX_SIZE)
+        {
+            return Err(ConfigError::Invalid(format!(
+                "STATIC_PAYLOAD_SIZES: {size} is outside 1..={}",
+                crate::payload::MAX_SIZE
+            )));
+        }
+        if !(1..=22).contains(&self.static_payload_zstd_level) {
+            return Err(ConfigError::Invalid("STATIC_PAYLOAD_ZSTD_LEVEL must be between 1 and 22".into()));
+        }
+        if self.pool_stats_interval_ms == 0 {
+            return Err(ConfigError::Invalid("POOL_STATS_INTERVAL_MS must be greater than 0".into()));
+        }
+        if self.pool_autosize {
+            if cfg!(feature = "bb8") {
+                return Err(ConfigError::Invalid(
+                    "POOL_AUTOSIZE=on requires deadpool (bb8 pools cannot be resized)".into(),
+                ));
+            }
+            let max = self.pool_autosize_max.unwrap_or(self.pool_size * 4);
+            if self.pool_autosize_min == 0 || self.pool_autosize_min > self.pool_size || self.pool_size > max {
+                return Err(ConfigError::Invalid(format!(
+                    "POOL_AUTOSIZE needs 0 < POOL_AUTOSIZE_MIN <= POOL_SIZE <= POOL_AUTOSIZE_MAX (got {} <= {} <= {max})",
+                    self.pool_autosize_min, self.pool_size
+                )));
+            }
+            if self.pool_autosize_interval_ms == 0 {
+                return Err(ConfigError::Invalid("POOL_AUTOSIZE_INTERVAL_MS must be greater than 0".into()));
+            }
+        }
+        if self.long_poll_interval_ms == 0 {
+            return Err(ConfigError::Invalid("LONG_POLL_INTERVAL_MS must be greater than 0".into()));
+        }
+        if self.listen_backlog == 0 {
+            return Err(ConfigError::Invalid("LISTEN_BACKLOG must be greater than 0".into()));
+        }
+
+        if self.admin_token.as_ref().is_some_and(|t| t.len() < 16) {
+            return Err(ConfigError::Invalid("ADMIN_TOKEN must be at least 16 bytes".into()));
+        }
+        if self.session_key.as_ref().is_some_and(|k| k.len() < 64) {
+            return Err(ConfigError::Invalid("SESSION_KEY must be at least 64 bytes".into()));
+        }
+        if self.http3 && !cfg!(feature = "http3") {
+            return Err(ConfigError::Invalid(
+                "HTTP3=on requires a build with the `http3` feature".into(),
+            ));
+        }
+        if self.tls_cert_file.is_some() != self.tls_key_file.is_some() {
+            return Err(ConfigError::Invalid(
+                "TLS_CERT_FILE and TLS_KEY_FILE must be set together".into(),
+            ));
+        }
+
+        let cpus = num_cpus();
+        let runtime = RuntimeSettings {
+            cpus,
+            workers: self.workers.unwrap_or(cpus),
+            tokio_worker_threads: self.tokio_worker_threads,
+            max_blocking_threads: self.max_blocking_threads.unwrap_or((512 / cpus).max(1)),
+            tokio_flavor: if self.tokio_worker_threads.is_some() {
+                "multi_thread"
+            } else {
+                "current_thread"
+            },
+        };
+
+        let tcp = TcpSettings {
+            tcp_nodelay: self.tcp_nodelay,
+            listen_backlog: self.listen_backlog,
+            keepalive_secs: self.keepalive_secs,
+            client_timeout_ms: self.client_timeout_ms,
+        };
+
+        Ok(Config {
+            database_url,
+            port: self.port,
+            profiling: self.profiling,
+            events: self.events,
+            long_poll_interval_ms: self.long_poll_interval_ms,
+            multi_tenant: self.multi_tenant,
+            tenant_domain: self
+                .tenant_domain
+                .map(|d| d.trim_matches('.').to_ascii_lowercase())
+                .filter(|d| !d.is_empty()),
+            tenant_scoping: self.tenant_scoping,
+            audit: self.audit,
+            transaction_per_request: self.transaction_per_request,
+            webhooks: self.webhooks,
+            webhook_max_attempts: self.webhook_max_attempts,
+            hypermedia: self.hypermedia,
+            json_envelope: self.json_envelope,
+            json_pretty: self.json_pretty,
+            users_streaming: self.users_streaming,
+            timestamp_format: self.timestamp_format,
+            random_rows: self.random_row_selection,
+            random_seed: self.random_seed,
+            max_id_refresh_secs: self.max_id_refresh_secs,
+            static_payload_sizes: self.static_payload_sizes,
+            static_payload_zstd_level: self.static_payload_zstd_level,
+            compress_bench: self.compress_bench,
+            routing_bench: self.routing_bench,
+            response_cache: self.response_cache,
+            pool_size: self.pool_size,
+            pool_stats_interval_ms: self.pool_stats_interval_ms,
+            pool_autosize: PoolAutosizeSettings {
+                enabled: self.pool_autosize,
+                min: self.pool_autosize_min,
+                max: self.pool_autosize_max.unwrap_or(self.pool_size * 4),
+                target_wait_ms: self.pool_autosize_target_wait_ms,
+                interval_ms: self.pool_autosize_interval_ms,
+                reserve: self.pool_autosize_reserve,
+            },
+            db_timing: self.db_timing,
+            latency_summary_path: self.latency_summary_path,
+            statement_timeout_ms: self.statement_timeout_ms,
+            request_timeout_ms: self.request_timeout_ms,
+            db_startup_retries: self.db_startup_retries,
+            db_startup_timeout: self.db_startup_timeout,
+            runtime,
+            tcp,
+            job_workers: self.job_workers,
+            job_poll_ms: self.job_poll_ms,
+            scheduler_interval_secs: self.scheduler_interval_secs,
+            scheduler_jitter_ms: self.scheduler_jitter_ms,
+            cached_users_encoding: self.cached_users_encoding,
+            trusted_proxies: self.trusted_proxies,
+            admin_token: self.admin_token,
+            shed: ShedSettings {
+                max_in_flight: self.shed_max_inflight,
+                p99_budget_ms: self.shed_p99_budget_ms,
+                window_ms: self.shed_window_ms,
+            },
+            route_concurrency: RouteConcurrencySettings {
+                limits: self.route_concurrency,
+                queue_depth: self.route_queue_depth,
+            },
+            priority: PrioritySettings {
+                slots: self.priority_slots,
+                classify: self.priority_classify,
+                read_weight: self.priority_read_weight,
+                write_weight: self.priority_write_weight,
+            },
+            session: SessionSettings {
+                store: self.session_store,
+                redis_url: self.redis_url,
+                key: self.session_key,
+            },
+            http3: Http3Settings {
+                enabled: self.http3,
+                port: self.http3_port.unwrap_or(self.port),
+                tls_cert_file: self.tls_cert_file,
+                tls_key_file: self.tls_key_file,
+            },
+        })
+    }
+}
+
+impl RuntimeSettings {
+    /// Builds the tokio runtime that hosts the Actix system (not the workers,
+    /// which always run their own current-thread runtimes).
+    pub fn build_tokio(&self) -> std::io::Result<tokio::runtime::Runtime> {
+        match self.tokio_worker_threads {
+            Some(n) => tokio::runtime::Builder::new_multi_thread()
+                .worker_threads(n)
+                .max_blocking_threads(self.max_blocking_threads)
+                .enable_all()
+                .build(),
+            None => tokio::runtime::Builder::new_current_thread()
+                .max_blocking_threads(self.max_blocking_threads)
+                .enable_all()
+                .build(),
+        }
+    }
+}
+
+impl TcpSettings {
+    pub fn keep_alive(&self) -> KeepAlive {
+        match self.keepalive_secs {
+            0 => KeepAlive::Disabled,
+            secs => KeepAlive::Timeout(Duration::from_secs(secs)),
+        }
+    }
+}
+
+/// Returns the number of CPUs this process may actually use, following the
+/// container's cgroup CPU quota, with a fallback when it cannot be read.
+///
+/// No `cpu.max` parsing here: `available_parallelism` already honours the
+/// cgroup v2 quota (rounded down, at least 1), and unlike a read of the root
+/// `/sys/fs/cgroup/cpu.max` it also finds the quota of a nested cgroup.
+fn num_cpus() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(4)
+}
@@ -0,0 +1,174 @@
+//! Connection reuse instrumentation: GET /conn-stats and the
+//! `http_connection*` series on /metrics.
+//!
+//! Load generators differ in how they reuse connections, and one that
+//! reconnects per request measures accept and handshake rather than the
+//! framework.  This is the server's side of the story: `HttpServer::on_connect`
+//! tags every accepted connection, [`count`] adds each request to its
+//! connection and to the tally by HTTP version, and the tag records how many
+//! requests its connection served when Actix drops it with the connection.
+//!
+//! Only the TCP listener tags connections; requests over HTTP/3 count in the
+//! version tally alone.
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{Extensions, ServiceRequest, ServiceResponse};
+use actix_web::http::Version;
+use actix_web::middleware::Next;
+use actix_web::{get, web, HttpResponse, Responder};
+use std::any::Any;
+use std::cell::Cell;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use crate::metrics::{counter, counter_family, Metrics};
+use crate::AppState;
+
+/// Upper bounds of the requests-per-connection buckets; the last bucket is
+/// everything above.
+const BOUNDS: [u64; 5] = [1, 2, 10, 100, 1000];
+
+#[derive(Default)]
+pub struct ConnStats {
+    pub accepted_total: AtomicU64,
+    pub closed_total: AtomicU64,
+    /// Requests served on closed connections (the histogram's sum).
+    pub closed_requests_total: AtomicU64,
+    /// Closed connections by requests served, per [`BOUNDS`] (not cumulative).
+    per_connection: [AtomicU64; BOUNDS.len() + 1],
+    /// Requests by protocol version.
+    pub http10_total: AtomicU64,
+    pub http11_total: AtomicU64,
+    pub http2_total: AtomicU64,
+    pub http3_total: AtomicU64,
+}
+
+impl ConnStats {
+    fn closed(&self, requests: u64) {
+        Metrics::inc(&self.closed_total);
+        self.closed_requests_total.fetch_add(requests, Ordering::Relaxed);
+        let bucket = BOUNDS.iter().position(|&bound| requests <= bound).unwrap_or(BOUNDS.len());
+        Metrics::inc(&self.per_connection[bucket]);
+    }
+
+    /// Connections accepted and not closed yet.
+    fn open(&self) -> u64 {
+        // Closed is read first: a connection closing in between then shows as
+        // still open rather than the difference going negative.
+        let closed = self.closed_total.load(Ordering::Relaxed);
+        self.accepted_total.load(Ordering::Relaxed).saturating_sub(closed)
+    }
+
+    /// Appends the connection series to a /metrics body.
+    pub fn render(&self, out: &mut String) {
+        counter(out, "http_connections_accepted_total", "TCP connections accepted.", &self.accepted_total);
+        counter(out, "http_connections_closed_total", "TCP connections closed.", &self.closed_total);
+        let _ = writeln!(
+            out,
+            "# HELP http_connections_open Connections accepted and not closed yet.\n\
+             # TYPE http_connections_open gauge\nhttp_connections_open {}",
+            self.open()
+        );
+        let name = "http_connection_requests";
+        let _ = writeln!(out, "# HELP {name} Requests served per closed connection.\n# TYPE {name} histogram");
+        let mut cumulative = 0;
+        for (bound, bucket) in BOUNDS.iter().map(u64::to_string).chain(["+Inf".into()]).zip(&self.per_connection) {
+            cumulative += bucket.load(Ordering::Relaxed);
+            let _ = writeln!(out, "{name}_bucket{{le=\"{bound}\"}} {cumulative}");
+        }
+        let _ = writeln!(out, "{name}_sum {}", self.closed_requests_total.load(Ordering::Relaxed));
+        let _ = writeln!(out, "{name}_count {cumulative}");
+        counter_family(
+            out,
+            "http_requests_by_version_total",
+            "Requests by HTTP protocol version.",
+            "version",
+            &[
+                ("1.0", &self.http10_total),
+                ("1.1", &self.http11_total),
+                ("2", &self.http2_total),
+                ("3", &self.http3_total),
+            ],
+        );
+    }
+}
+
+/// Per-connection state kept in the connection's extensions.
+struct Connection {
+    requests: Cell<u64>,
+    stats: Arc<ConnStats>,
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.stats.closed(self.requests.get());
+    }
+}
+
+/// `HttpServer::on_connect` callback: counts and tags a new connection.
+pub fn on_connect(stats: Arc<ConnStats>) -> impl Fn(&dyn Any, &mut Extensions) + Send + Sync + 'static {
+    move |_, extensions| {
+        Metrics::inc(&stats.accepted_total);
+        extensions.insert(Connection { requests: Cell::new(0), stats: stats.clone() });
+    }
+}
+
+/// Counts the request on its connection and by protocol version.
+pub async fn count(
+    data: web::Data<AppState>,
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    if let Some(conn) = req.conn_data::<Connection>() {
+        conn.requests.set(conn.requests.get() + 1);
+    }
+    let stats = &data.conn_stats;
+    Metrics::inc(match req.version() {
+        Version::HTTP_2 => &stats.http2_total,
+        Version::HTTP_3 => &stats.http3_total,
+        Version::HTTP_11 => &stats.http11_total,
+        _ => &stats.http10_total,
+    });
+    next.call(req).await
+}
+
+/// GET /conn-stats  — connection counts, requests per connection and the
+/// protocol versions seen.
+#[get("/conn-stats")]
+async fn conn_stats(data: web::Data<AppState>) -> impl Responder {
+    let stats = &data.conn_stats;
+    let load = |v: &AtomicU64| v.load(Ordering::Relaxed);
+    let closed = load(&stats.closed_total);
+    let served = load(&stats.closed_requests_total);
+    let buckets: Vec<serde_json::Value> = BOUNDS
+        .iter()
+        .zip(std::iter::once(&0).chain(&BOUNDS))
+        .map(|(&bound, &below)| match bound - below {
+            1 => bound.to_string(),
+            _ => format!("{}-{bound}", below + 1),
+        })
+        .chain([format!(">{}", BOUNDS[BOUNDS.len() - 1])])
+        .zip(&stats.per_connection)
+        .map(|(range, count)| serde_json::json!({ "requests": range, "connections": load(count) }))
+        .collect();
+    HttpResponse::Ok().json(serde_json::json!({
+        "accepted": load(&stats.accepted_total),
+        "closed": closed,
+        "open": stats.open(),
+        "requests_per_connection": {
+            "mean": (closed > 0).then(|| served as f64 / closed as f64),
+            "closed_connections": buckets
+        },
+        "requests_by_version": {
+            "1.0": load(&stats.http10_total),
+            "1.1": load(&stats.http11_total),
+            "2": load(&stats.http2_total),
+            "3": load(&stats.http3_total)
+        }
+    }))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(conn_stats);
+}
@@ -0,0 +1,72 @@
+//! GET /cookies?set=N — cookie parsing and setting.
+//!
+//! The `Cookie` request headers are parsed into name/value pairs and echoed
+//! back as a summary (count, bytes, the cookies themselves).  The response
+//! sets `set` cookies (default 3, at most 50), `bench-0` … `bench-{N-1}`,
+//! each with `Path=/`, `Max-Age`, `Secure`, `HttpOnly` and a `SameSite` that
+//! cycles through `Strict`, `Lax` and `None`, so every `Set-Cookie` is
+//! formatted with its attributes as a real application's would be.
+//!
+//! A `Cookie` header that does not parse is answered with 400.
+
+use actix_web::cookie::{time::Duration, Cookie, SameSite};
+use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+
+/// Most cookies set by one response.
+const MAX_SET: usize = 50;
+const DEFAULT_SET: usize = 3;
+
+#[derive(Deserialize)]
+struct CookieParams {
+    set: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct Summary<'a> {
+    /// Cookies received.
+    count: usize,
+    /// Bytes of their names and values.
+    bytes: usize,
+    cookies: Vec<(&'a str, &'a str)>,
+    /// Cookies set by this response.
+    set: usize,
+}
+
+/// GET /cookies
+#[get("/cookies")]
+async fn cookies(req: HttpRequest, query: web::Query<CookieParams>) -> impl Responder {
+    let set = query.set.unwrap_or(DEFAULT_SET);
+    if set > MAX_SET {
+        return HttpResponse::BadRequest().json(serde_json::json!({ "error": format!("set must be at most {MAX_SET}") }));
+    }
+    let received = match req.cookies() {
+        Ok(received) => received,
+        Err(e) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({ "error": format!("Invalid Cookie header: {e}") }));
+        }
+    };
+
+    let cookies: Vec<(&str, &str)> = received.iter().map(|c| (c.name(), c.value())).collect();
+    let bytes = cookies.iter().map(|(name, value)| name.len() + value.len()).sum();
+    let summary = Summary { count: cookies.len(), bytes, cookies, set };
+
+    let mut res = HttpResponse::Ok();
+    for i in 0..set {
+        let same_site = [SameSite::Strict, SameSite::Lax, SameSite::None][i % 3];
+        res.cookie(
+            Cookie::build(format!("bench-{i}"), format!("value-{i}"))
+                .path("/")
+                .max_age(Duration::hours(1))
+                .secure(true)
+                .http_only(true)
+                .same_site(same_site)
+                .finish(),
+        );
+    }
+    res.json(summary)
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(cookies);
+}
@@ -0,0 +1,25 @@
+//! Collection ETags for conditional GETs on list endpoints.
+//!
+//! The tag is derived from `COUNT(*)` and `MAX(updated_at)` of the table, both
+//! of which change on every insert, update (the `users_touch_updated_at`
+//! trigger in `init.sql`) and delete, whichever API performed the write.
+
+use actix_web::http::header::{self, HeaderMap};
+use chrono::{DateTime, Utc};
+
+/// Strong ETag for a collection version, e.g. `"10000-1760601040077976"`.
+pub fn collection(count: i64, max_updated_at: Option<DateTime<Utc>>) -> String {
+    let micros = max_updated_at.map_or(0, |t| t.timestamp_micros());
+    format!("\"{count}-{micros}\"")
+}
+
+/// Whether `If-None-Match` lists `etag` (or `*`), i.e. the client's copy is current.
+/// Uses the weak comparison RFC 9110 prescribes for this header.
+pub fn matches(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(|tag| tag.trim())
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
@@ -0,0 +1,149 @@
+//! User change feed over Server-Sent Events (`EVENTS=on`).
+//!
+//! The trigger of `scripts/migrations/009-change-feed.sql` calls
+//! `pg_notify('user_changes', …)` on every insert, update and delete of
+//! `users`, whichever API performed it.  It is not part of `init.sql`, so
+//! runs without `EVENTS=on` do not pay for NOTIFY on their writes.  One
+//! dedicated connection, opened outside the pool because a pooled connection
+//! would be handed to other requests, `LISTEN`s on that channel and publishes
+//! each payload on a broadcast channel; every GET /events subscriber receives
+//! it as an `event: user` message.  The listener reconnects with backoff.
+//!
+//! A subscriber that falls more than [`CAPACITY`] events behind gets an
+//! `event: lagged` message with the number it missed, its cue to refetch.
+
+use actix_web::web::Bytes;
+use actix_web::{get, web, HttpResponse, Responder};
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio_postgres::{AsyncMessage, NoTls};
+
+use crate::metrics::Metrics;
+use crate::AppState;
+
+/// Events buffered per subscriber before it starts missing them.
+const CAPACITY: usize = 1024;
+/// Idle comment sent so proxies and clients keep the stream open.
+const HEARTBEAT: Duration = Duration::from_secs(15);
+const CHANNEL: &str = "user_changes";
+
+/// Sender side of the broadcast channel shared by all workers.
+#[derive(Clone)]
+pub struct ChangeFeed(broadcast::Sender<Bytes>);
+
+impl Default for ChangeFeed {
+    fn default() -> Self {
+        ChangeFeed(broadcast::channel(CAPACITY).0)
+    }
+}
+
+impl ChangeFeed {
+    /// Receives every event published from now on, as its SSE message.
+    pub fn subscribe(&self) -> broadcast::Receiver<Bytes> {
+        self.0.subscribe()
+    }
+}
+
+/// Whether an SSE message from the feed announces an insert.
+pub fn is_insert(message: &[u8]) -> bool {
+    // The trigger writes `{"op" : "INSERT", "id" : …}`.
+    message.windows(8).any(|w| w == b"\"INSERT\"")
+}
+
+/// Starts the listener task on the current system.
+pub fn spawn(state: web::Data<AppState>) {
+    actix_web::rt::spawn(async move {
+        let mut delay = Duration::from_millis(100);
+        loop {
+            match listen(&state).await {
+                Ok(()) => eprintln!("Change feed connection closed; reconnecting in {delay:?}"),
+                Err(e) => eprintln!("Change feed error: {e}; reconnecting in {delay:?}"),
+            }
+            tokio::time::sleep(delay).await;
+            delay = (delay * 2).min(Duration::from_secs(5));
+        }
+    });
+}
+
+/// Runs one LISTEN connection until it fails or is closed.
+async fn listen(state: &web::Data<AppState>) -> Result<(), tokio_postgres::Error> {
+    let pg: tokio_postgres::Config = state
+        .config
+        .database_url
+        .parse()
+        .expect("DATABASE_URL was validated at startup");
+    let (client, mut connection) = pg.connect(NoTls).await?;
+
+    // The connection must be polled for LISTEN to complete, and notifications
+    // arrive through the same poll.
+    let feed = state.events.clone();
+    let metrics = state.clone();
+    let driver = actix_web::rt::spawn(async move {
+        while let Some(message) = std::future::poll_fn(|cx| connection.poll_message(cx)).await {
+            if let AsyncMessage::Notification(n) = message? {
+                Metrics::inc(&metrics.metrics.events_published_total);
+                // No subscribers is not an error.
+                let _ = feed.0.send(Bytes::from(format!("event: user\ndata: {}\n\n", n.payload())));
+            }
+        }
+        Ok(())
+    });
+
+    client.batch_execute(&format!("LISTEN {CHANNEL}")).await?;
+    println!("Change feed listening on '{CHANNEL}'");
+    let trigger = "SELECT 1 FROM pg_trigger WHERE tgrelid = 'users'::regclass AND tgname = 'users_notify_change'";
+    if client.query_opt(trigger, &[]).await?.is_none() {
+        eprintln!("Change feed: no trigger on users, apply scripts/migrations/009-change-feed.sql");
+    }
+    let result = driver.await.unwrap_or(Ok(()));
+    drop(client);
+    result
+}
+
+/// Decrements the subscriber gauge when the response stream is dropped.
+struct Subscriber {
+    rx: broadcast::Receiver<Bytes>,
+    state: web::Data<AppState>,
+}
+
+impl Drop for Subscriber {
+    fn drop(&mut self) {
+        self.state.metrics.events_subscribers.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// GET /events  — `text/event-stream` of user changes.
+#[get("/events")]
+async fn events(data: web::Data<AppState>) -> impl Responder {
+    Metrics::inc(&data.metrics.events_subscribers);
+    let subscriber = Subscriber { rx: data.events.subscribe(), state: data.clone() };
+
+    let first = Bytes::from_static(b": connected\n\n");
+    let stream = futures_util::stream::unfold((subscriber, Some(first)), |(mut sub, pending)| async move {
+        if let Some(bytes) = pending {
+            return Some((Ok::<_, actix_web::Error>(bytes), (sub, None)));
+        }
+        let bytes = tokio::select! {
+            received = sub.rx.recv() => match received {
+                Ok(event) => event,
+                Err(RecvError::Lagged(missed)) => {
+                    Bytes::from(format!("event: lagged\ndata: {{\"missed\":{missed}}}\n\n"))
+                }
+                Err(RecvError::Closed) => return None,
+            },
+            _ = tokio::time::sleep(HEARTBEAT) => Bytes::from_static(b": heartbeat\n\n"),
+        };
+        Some((Ok(bytes), (sub, None)))
+    });
+
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(stream)
+}
+
+/// Registers `/events`.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(events);
+}
@@ -0,0 +1,72 @@
+//! Bulk export: GET /users/export, every user as NDJSON.  With
+//! `USERS_STREAMING=on`, GET /users without `limit` reads the same stream and
+//! writes it as a JSON array ([`json_array`]).
+//!
+//! The body is streamed as the repository reads it
+//! ([`UserStream`](crate::repo::UserStream)): one JSON object per line, in id
+//! order, sent a fetched batch at a time, so neither the server nor the
+//! database materializes the table.  The rows come from one snapshot, however
+//! long the client takes to read them.
+//!
+//! The status is sent before the first row is read: a failure mid-export
+//! aborts the response, and the client sees a truncated body rather than an
+//! error document.
+
+use actix_web::web::Bytes;
+use actix_web::{error, get, web, HttpResponse, Responder};
+use futures_util::stream::{self, Stream};
+use futures_util::StreamExt;
+
+use crate::repo::{RepoError, UserStream, EXPORT_BATCH};
+use crate::tenant::Tenant;
+use crate::{storage_error, AppState};
+
+/// GET /users/export  — all users, one JSON object per line.
+#[get("/users/export")]
+async fn export(data: web::Data<AppState>, tenant: Tenant) -> impl Responder {
+    let users = match data.users.stream_all(tenant.scope()).await {
+        Ok(users) => users,
+        Err(e) => return storage_error("Query", "Database query error", e),
+    };
+    // Users of one fetched batch arrive together and go out as one chunk.
+    let body = users.ready_chunks(EXPORT_BATCH).map(|users| {
+        let mut chunk = Vec::new();
+        for user in users {
+            let user = user.map_err(failed)?;
+            serde_json::to_writer(&mut chunk, &user)?;
+            chunk.push(b'\n');
+        }
+        Ok::<_, actix_web::Error>(Bytes::from(chunk))
+    });
+    HttpResponse::Ok().content_type("application/x-ndjson").streaming(body)
+}
+
+/// `users` as one JSON array, `[` first and `]` after the last batch.  As
+/// with the export, an error mid-stream truncates the body.
+pub fn json_array(users: UserStream) -> impl Stream<Item = Result<Bytes, actix_web::Error>> {
+    let mut first = true;
+    let items = users.ready_chunks(EXPORT_BATCH).map(move |users| {
+        let mut chunk = Vec::new();
+        for user in users {
+            let user = user.map_err(failed)?;
+            if !std::mem::take(&mut first) {
+                chunk.push(b',');
+            }
+            serde_json::to_writer(&mut chunk, &user)?;
+        }
+        Ok::<_, actix_web::Error>(Bytes::from(chunk))
+    });
+    stream::once(async { Ok(Bytes::from_static(b"[")) })
+        .chain(items)
+        .chain(stream::once(async { Ok(Bytes::from_static(b"]")) }))
+}
+
+fn failed(e: RepoError) -> actix_web::Error {
+    eprintln!("Export error: {e}");
+    error::ErrorInternalServerError("export failed")
+}
+
+/// Registers `/users/export`; must come before `/users/{id}`.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(export);
+}
@@ -0,0 +1,232 @@
+//! Sparse fieldsets: `?fields=id,name,email` on GET /users and /users/:id.
+//!
+//! The list selects which `users` columns are read and serialized.  A
+//! projection only replaces the column list of the repository's SELECT, and
+//! rows are written by [`Sparse`], a serializer that emits just the requested
+//! keys.  Without `fields` the handlers serialize [`User`] exactly as before,
+//! so the default payload and code path are unchanged.
+//!
+//! Fields are named as in the response body: `created_at`, or `createdAt`
+//! when built with the `camel-case` feature.
+//!
+//! Not part of the OpenAPI document: it is an Actix-only extension and the
+//! spec is the contract every implementation is compared against.
+
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use serde::Deserialize;
+use std::borrow::Cow;
+use tokio_postgres::Row;
+
+use crate::key::UserId;
+use crate::status::Status;
+use crate::timestamp::Timestamp;
+use crate::{row_to_user, User};
+
+/// Columns of the selectable fields, in output order.
+const FIELDS: [&str; 7] = ["id", "name", "email", "age", "created_at", "tags", "status"];
+
+/// Response keys of [`FIELDS`], which also name them in `?fields=`.
+#[cfg(not(feature = "camel-case"))]
+pub const KEYS: [&str; 7] = FIELDS;
+#[cfg(feature = "camel-case")]
+pub const KEYS: [&str; 7] = ["id", "name", "email", "age", "createdAt", "tags", "status"];
+
+/// Column list of every user SELECT in `repo`.
+const ALL_COLUMNS: &str = "SELECT id, name, email, age, created_at, tags, status ";
+
+/// Query parameter shared by the read endpoints.  `fields[users]` is the
+/// JSON:API spelling of the same list (see `jsonapi`).
+#[derive(Deserialize)]
+pub struct FieldsParams {
+    pub fields: Option<String>,
+    #[serde(rename = "fields[users]")]
+    pub fields_users: Option<String>,
+}
+
+impl FieldsParams {
+    pub fn list(&self) -> Option<&str> {
+        self.fields.as_deref().or(self.fields_users.as_deref())
+    }
+}
+
+/// Bit set over [`FIELDS`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fields(u8);
+
+impl Fields {
+    pub const ALL: Fields = Fields((1 << FIELDS.len()) - 1);
+
+    /// Parses a comma-separated list; absent means every field.
+    pub fn parse(list: Option<&str>) -> Result<Fields, String> {
+        let Some(list) = list else {
+            return Ok(Fields::ALL);
+        };
+        let mut mask = 0;
+        for name in list.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+            match KEYS.iter().position(|f| *f == name) {
+                Some(i) => mask |= 1 << i,
+                None => {
+                    return Err(format!("Unknown field '{name}' (allowed: {})", KEYS.join(", ")));
+                }
+            }
+        }
+        if mask == 0 {
+            return Err("fields must name at least one field".into());
+        }
+        Ok(Fields(mask))
+    }
+
+    pub fn is_all(self) -> bool {
+        self == Fields::ALL
+    }
+
+    /// The same selection plus `id`, which JSON:API resources always carry.
+    pub fn with_id(self) -> Fields {
+        Fields(self.0 | 1)
+    }
+
+    /// Canonical `fields` value for links; `None` for every field.
+    pub fn query(self) -> Option<String> {
+        (!self.is_all()).then(|| self.selected().map(|i| KEYS[i]).collect::<Vec<_>>().join(","))
+    }
+
+    /// Indexes into [`FIELDS`] and [`KEYS`] of the selected fields.
+    fn selected(self) -> impl Iterator<Item = usize> {
+        (0..FIELDS.len()).filter(move |i| self.0 & (1 << i) != 0)
+    }
+
+    /// `sql` with its column list narrowed to the selected fields.
+    pub fn project(self, sql: &'static str) -> Cow<'static, str> {
+        match sql.strip_prefix(ALL_COLUMNS) {
+            Some(rest) if !self.is_all() => Cow::Owned(format!("SELECT {} {rest}", self.columns())),
+            _ => Cow::Borrowed(sql),
+        }
+    }
+
+    /// Column list of the selected fields, for SQL built per request.
+    pub fn columns(self) -> String {
+        self.selected().map(|i| FIELDS[i]).collect::<Vec<_>>().join(", ")
+    }
+
+    /// Distinguishes the ETag of a projection from the full representation.
+    pub fn etag(self, etag: String) -> String {
+        if self.is_all() {
+            return etag;
+        }
+        format!("{}-f{:x}\"", etag.trim_end_matches('"'), self.0)
+    }
+}
+
+/// A row read with a projection, serialized with only its selected fields.
+pub struct Sparse {
+    source: Source,
+    fields: Fields,
+}
+
+/// Where a projected record's values live: the projected row as read, or a
+/// whole user held by a repository that does not read rows (`repo::memory`).
+enum Source {
+    Row(Row),
+    User(User),
+}
+
+impl Sparse {
+    fn id(&self) -> UserId {
+        match &self.source {
+            Source::Row(row) => row.get("id"),
+            Source::User(user) => user.id,
+        }
+    }
+
+    fn serialize_fields<M: SerializeMap>(&self, map: &mut M, skip_id: bool) -> Result<(), M::Error> {
+        for i in self.fields.selected() {
+            let (field, key) = (FIELDS[i], KEYS[i]);
+            if field == "id" && skip_id {
+                continue;
+            }
+            match &self.source {
+                Source::Row(row) => match field {
+                    "id" => map.serialize_entry(key, &row.get::<_, UserId>(field))?,
+                    "name" | "email" => map.serialize_entry(key, &row.get::<_, String>(field))?,
+                    "age" => map.serialize_entry(key, &row.get::<_, Option<i32>>(field))?,
+                    "tags" => map.serialize_entry(key, &row.get::<_, Vec<&str>>(field))?,
+                    "status" => map.serialize_entry(key, &row.get::<_, Status>(field))?,
+                    _ => map.serialize_entry(key, &Timestamp(&row.get(field)))?,
+                },
+                Source::User(user) => match field {
+                    "id" => map.serialize_entry(key, &user.id)?,
+                    "name" => map.serialize_entry(key, &user.name)?,
+                    "email" => map.serialize_entry(key, &user.email)?,
+                    "age" => map.serialize_entry(key, &user.age)?,
+                    "tags" => map.serialize_entry(key, &user.tags)?,
+                    "status" => map.serialize_entry(key, &user.status)?,
+                    _ => map.serialize_entry(key, &Timestamp(&user.created_at))?,
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for Sparse {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.fields.0.count_ones() as usize))?;
+        self.serialize_fields(&mut map, false)?;
+        map.end()
+    }
+}
+
+/// A user as returned by the read endpoints: whole, or projected.
+#[derive(serde::Serialize)]
+#[serde(untagged)]
+pub enum Record {
+    Full(User),
+    Sparse(Sparse),
+}
+
+impl Record {
+    pub fn from_row(row: Row, fields: Fields) -> Record {
+        if fields.is_all() {
+            Record::Full(row_to_user(&row))
+        } else {
+            Record::Sparse(Sparse { source: Source::Row(row), fields })
+        }
+    }
+
+    /// `user` with only `fields` kept, for repositories that hold whole users.
+    pub fn from_user(user: User, fields: Fields) -> Record {
+        if fields.is_all() {
+            Record::Full(user)
+        } else {
+            Record::Sparse(Sparse { source: Source::User(user), fields })
+        }
+    }
+
+    /// `None` for a projection that left out `id`.
+    pub fn id(&self) -> Option<UserId> {
+        match self {
+            Record::Full(user) => Some(user.id),
+            Record::Sparse(s) if s.fields.0 & 1 != 0 => Some(s.id()),
+            Record::Sparse(_) => None,
+        }
+    }
+
+    /// Writes the record's fields into `map`, leaving out `id` when
+    /// `skip_id` (JSON:API `attributes`).  Used to extend the plain object.
+    pub fn serialize_fields<M: SerializeMap>(&self, map: &mut M, skip_id: bool) -> Result<(), M::Error> {
+        match self {
+            Record::Full(user) => {
+                if !skip_id {
+                    map.serialize_entry("id", &user.id)?;
+                }
+                map.serialize_entry("name", &user.name)?;
+                map.serialize_entry("email", &user.email)?;
+                map.serialize_entry("age", &user.age)?;
+                map.serialize_entry(KEYS[4], &Timestamp(&user.created_at))?;
+                map.serialize_entry(KEYS[5], &user.tags)?;
+                map.serialize_entry(KEYS[6], &user.status)
+            }
+            Record::Sparse(s) => s.serialize_fields(map, skip_id),
+        }
+    }
+}
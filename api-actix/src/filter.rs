@@ -0,0 +1,50 @@
+//! Filters of GET /users: `?metadata.*=` (see `metadata`), `?tag=` (see
+//! `tags`) and `?status=` (see `status`).  A user is listed when it matches
+//! every parameter; without any, the handler keeps its unfiltered paths
+//! (streaming, row encoding).
+//!
+//! A filtered list answers with the collection version plus a hash of the
+//! filter as its ETag, so any write still invalidates it.
+
+use serde_json::Value;
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use crate::status::{self, Status};
+use crate::{metadata, tags};
+
+/// What a filtered GET /users asks of each user.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Filter {
+    /// Contained in `metadata` (`@>`), when given.
+    pub metadata: Option<Value>,
+    /// All carried by `tags` (`@>`); empty for any.
+    pub tags: Vec<String>,
+    /// Equal to `status`, when given.
+    pub status: Option<Status>,
+}
+
+impl Filter {
+    /// The filter of `query`; `None` without filter parameters.
+    pub fn parse(query: &[(String, String)]) -> Result<Option<Filter>, String> {
+        let filter =
+            Filter { metadata: metadata::filter(query)?, tags: tags::filter(query)?, status: status::filter(query)? };
+        Ok((filter != Filter::default()).then_some(filter))
+    }
+
+    /// Distinguishes the ETag of a filtered list from the whole collection's.
+    pub fn etag(&self, etag: String) -> String {
+        let mut hasher = DefaultHasher::new();
+        self.metadata.as_ref().map(Value::to_string).hash(&mut hasher);
+        self.tags.hash(&mut hasher);
+        self.status.hash(&mut hasher);
+        format!("{}-m{:x}\"", etag.trim_end_matches('"'), hasher.finish())
+    }
+
+    /// Whether a user with `metadata`, `tags` and `status` is listed, for
+    /// the in-memory repository.
+    pub fn matches(&self, metadata: &Value, tags: &[String], status: Status) -> bool {
+        self.metadata.as_ref().is_none_or(|filter| metadata::contains(metadata, filter))
+            && tags::contains(tags, &self.tags)
+            && self.status.is_none_or(|wanted| wanted == status)
+    }
+}
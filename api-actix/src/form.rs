@@ -0,0 +1,70 @@
+//! Form-encoded request bodies.
+//!
+//! [`Body`] decodes a body by its `Content-Type`:
+//! `application/x-www-form-urlencoded` through `web::Form`, anything else
+//! through `web::Json` as before (which still answers 400 to a type it does
+//! not accept).  Both deserialize into the same struct, so a form goes
+//! through the same validation as JSON — for `CreateUser`, the trimmed and
+//! lowercased email and the required fields — and a bad form gets the same
+//! JSON 400 (`FormConfig` in `app()`).  POST /users takes it:
+//!
+//! ```text
+//! curl -d 'name=Ana&email=ana@example.com&age=30' localhost:3004/users
+//! ```
+//!
+//! POST /form-echo parses any form and echoes its fields in order, repeats
+//! included, to measure the extractor on its own.
+//!
+//! Not part of the OpenAPI document, which describes the JSON contract.
+
+use actix_web::dev::Payload;
+use actix_web::http::header::CONTENT_TYPE;
+use actix_web::{post, web, Error, FromRequest, HttpRequest, HttpResponse, Responder};
+use futures_util::future::LocalBoxFuture;
+use serde::de::DeserializeOwned;
+use std::ops::Deref;
+
+/// A request body as JSON or as a URL-encoded form.
+pub struct Body<T>(pub T);
+
+impl<T> Deref for Body<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+fn is_form(req: &HttpRequest) -> bool {
+    req.headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(';').next())
+        .is_some_and(|mime| mime.trim().eq_ignore_ascii_case("application/x-www-form-urlencoded"))
+}
+
+impl<T: DeserializeOwned + 'static> FromRequest for Body<T> {
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        if is_form(req) {
+            let form = web::Form::<T>::from_request(req, payload);
+            Box::pin(async move { Ok(Body(form.await?.into_inner())) })
+        } else {
+            let json = web::Json::<T>::from_request(req, payload);
+            Box::pin(async move { Ok(Body(json.await?.into_inner())) })
+        }
+    }
+}
+
+/// POST /form-echo  — the form's fields, in order.
+#[post("/form-echo")]
+async fn form_echo(form: web::Form<Vec<(String, String)>>) -> impl Responder {
+    let fields = form.into_inner();
+    HttpResponse::Ok().json(serde_json::json!({ "count": fields.len(), "fields": fields }))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(form_echo);
+}
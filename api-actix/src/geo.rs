@@ -0,0 +1,146 @@
+//! Users near a point (`postgis` feature): `GET /users/near?lat=&lon=&radius=`.
+//!
+//! The users whose `location geography(Point)` lies within `radius` metres of
+//! (`lat`, `lon`), nearest first, at most `limit` (default 10, up to
+//! [`MAX_LIMIT`]): `ST_DWithin` over the GiST index, and `ST_Distance` on the
+//! spheroid for each match.  A small body for a lot of work on the database
+//! side, the opposite profile of the list endpoints.
+//!
+//! The column and the extension come from
+//! `scripts/migrations/006-postgis.sql` on a PostGIS image; the default
+//! `postgres:16-alpine` has neither, so the route only exists in builds with
+//! the feature.  Users without a location are never near.
+//!
+//! Locations are read as the EWKB that PostGIS sends for a `geography` in
+//! binary mode and decoded here ([`Point`]), with no GIS crate.
+
+use actix_web::{get, web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use tokio_postgres::types::{FromSql, Type};
+
+use crate::key::UserId;
+use crate::negotiate::{Negotiated, Output};
+use crate::tenant::Tenant;
+use crate::{storage_error, AppState};
+
+/// Largest `radius`, in metres.
+pub const MAX_RADIUS: f64 = 1_000_000.0;
+/// Most users in one answer.
+pub const MAX_LIMIT: i64 = 100;
+
+/// A WGS 84 position, in degrees.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Point {
+    pub lat: f64,
+    pub lon: f64,
+}
+
+/// EWKB geometry type flags; the base type is in the low bits.
+const HAS_Z: u32 = 0x8000_0000;
+const HAS_M: u32 = 0x4000_0000;
+const HAS_SRID: u32 = 0x2000_0000;
+const WKB_POINT: u32 = 1;
+
+/// Reads an EWKB (or ISO WKB) point: byte order, type, optional SRID, then
+/// x (longitude) and y (latitude); Z and M are ignored.
+fn decode(raw: &[u8]) -> Result<Point, String> {
+    let (&order, mut rest) = raw.split_first().ok_or("empty WKB")?;
+    let little = match order {
+        0 => false,
+        1 => true,
+        other => return Err(format!("bad WKB byte order {other}")),
+    };
+    let mut take = |n: usize| -> Result<[u8; 8], String> {
+        if rest.len() < n {
+            return Err("truncated WKB".to_owned());
+        }
+        let mut bytes = [0; 8];
+        bytes[..n].copy_from_slice(&rest[..n]);
+        rest = &rest[n..];
+        Ok(bytes)
+    };
+    let u32_at = |b: [u8; 8]| {
+        let b = [b[0], b[1], b[2], b[3]];
+        if little { u32::from_le_bytes(b) } else { u32::from_be_bytes(b) }
+    };
+    let f64_at = |b: [u8; 8]| if little { f64::from_le_bytes(b) } else { f64::from_be_bytes(b) };
+
+    let kind = u32_at(take(4)?);
+    // ISO WKB writes Z and M as 1001, 2001, 3001.
+    if (kind & !(HAS_Z | HAS_M | HAS_SRID)) % 1000 != WKB_POINT {
+        return Err(format!("WKB type {kind:#x} is not a point"));
+    }
+    if kind & HAS_SRID != 0 {
+        take(4)?;
+    }
+    let (lon, lat) = (f64_at(take(8)?), f64_at(take(8)?));
+    if lon.is_nan() || lat.is_nan() {
+        return Err("empty point".to_owned());
+    }
+    Ok(Point { lat, lon })
+}
+
+impl<'a> FromSql<'a> for Point {
+    fn from_sql(_: &Type, raw: &'a [u8]) -> Result<Point, Box<dyn Error + Sync + Send>> {
+        Ok(decode(raw)?)
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        matches!(ty.name(), "geography" | "geometry")
+    }
+}
+
+/// One user of GET /users/near.
+#[derive(Debug, Clone, Serialize)]
+pub struct Nearby {
+    pub id: UserId,
+    pub name: String,
+    pub location: Point,
+    /// Metres from the requested point.
+    pub distance: f64,
+}
+
+/// Query parameters for GET /users/near.
+#[derive(Deserialize)]
+pub struct NearParams {
+    lat: f64,
+    lon: f64,
+    /// Metres.
+    radius: f64,
+    limit: Option<i64>,
+}
+
+/// GET /users/near?lat=&lon=&radius=  — the nearest users within the radius.
+#[get("/users/near")]
+async fn near(
+    data: web::Data<AppState>,
+    query: web::Query<NearParams>,
+    output: Output,
+    tenant: Tenant,
+) -> impl Responder {
+    let NearParams { lat, lon, radius, limit } = query.into_inner();
+    let error = if !(-90.0..=90.0).contains(&lat) {
+        Some("lat must be between -90 and 90".to_owned())
+    } else if !(-180.0..=180.0).contains(&lon) {
+        Some("lon must be between -180 and 180".to_owned())
+    } else if !(radius > 0.0 && radius <= MAX_RADIUS) {
+        Some(format!("radius must be between 0 and {MAX_RADIUS} metres"))
+    } else {
+        None
+    };
+    if let Some(msg) = error {
+        return HttpResponse::BadRequest().json(serde_json::json!({ "error": msg }));
+    }
+    let limit = limit.unwrap_or(10).clamp(1, MAX_LIMIT);
+
+    match data.users.near(tenant.scope(), Point { lat, lon }, radius, limit).await {
+        Ok(users) => Negotiated::new(output, users).respond(HttpResponse::Ok()),
+        Err(e) => storage_error("Query", "Database query error", e),
+    }
+}
+
+/// Registers `/users/near`; must come before `/users/{id}`.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(near);
+}
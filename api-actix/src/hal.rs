@@ -0,0 +1,153 @@
+//! HAL-style hypermedia links (`HYPERMEDIA=on`) on the `/users` endpoints.
+//!
+//! Every user in a plain JSON response gains
+//! `"_links": {"self": {"href": ...}, "collection": {"href": ...}}`, and a page
+//! of GET /users adds `self`/`first`/`prev`/`next`/`last` links next to
+//! `data`.  The unpaginated GET /users stays an array, so only its items carry
+//! links.  Hrefs are absolute, built on every request from its scheme and
+//! host; `Forwarded` / `X-Forwarded-Proto` / `X-Forwarded-Host` are believed
+//! only from `TRUSTED_PROXIES`, as in `middleware::real_ip`.  The schema has
+//! no relation besides the collection (there are no posts to link to), and a
+//! `?fields=` projection without `id` has no `self` link.
+//!
+//! Off by default, leaving payloads unchanged.  JSON:API responses keep their
+//! own links, and none of this is part of the OpenAPI document.
+
+use actix_web::http::header;
+use actix_web::HttpRequest;
+use serde::ser::{SerializeMap, Serializer};
+use serde::Serialize;
+
+use crate::fields::{Fields, Record};
+use crate::jsonapi;
+use crate::middleware::real_ip::Cidr;
+
+/// `scheme://host` of the current request.
+pub struct Origin(String);
+
+impl Origin {
+    pub fn of(req: &HttpRequest, trusted: &[Cidr]) -> Origin {
+        let proxied = req
+            .peer_addr()
+            .is_some_and(|peer| trusted.iter().any(|net| net.contains(peer.ip().to_canonical())));
+        if proxied {
+            let info = req.connection_info();
+            return Origin(format!("{}://{}", info.scheme(), info.host()));
+        }
+        let scheme = match req.uri().scheme_str() {
+            Some(scheme) => scheme,
+            None if req.app_config().secure() => "https",
+            None => "http",
+        };
+        let host = req
+            .headers()
+            .get(header::HOST)
+            .and_then(|v| v.to_str().ok())
+            .or_else(|| req.uri().authority().map(|a| a.as_str()))
+            .unwrap_or_else(|| req.app_config().host());
+        Origin(format!("{scheme}://{host}"))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+#[derive(Serialize)]
+struct Href {
+    href: String,
+}
+
+#[derive(Serialize)]
+struct UserLinks {
+    #[serde(rename = "self", skip_serializing_if = "Option::is_none")]
+    this: Option<Href>,
+    collection: Href,
+}
+
+/// A user with its `_links`.
+pub struct Linked<'a> {
+    record: &'a Record,
+    origin: &'a Origin,
+}
+
+impl Serialize for Linked<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let origin = self.origin.as_str();
+        let mut map = serializer.serialize_map(None)?;
+        self.record.serialize_fields(&mut map, false)?;
+        let links = UserLinks {
+            this: self.record.id().map(|id| Href { href: format!("{origin}/users/{id}") }),
+            collection: Href { href: format!("{origin}/users") },
+        };
+        map.serialize_entry("_links", &links)?;
+        map.end()
+    }
+}
+
+pub fn user<'a>(record: &'a Record, origin: &'a Origin) -> Linked<'a> {
+    Linked { record, origin }
+}
+
+pub struct LinkedUsers<'a> {
+    records: &'a [Record],
+    origin: &'a Origin,
+}
+
+impl Serialize for LinkedUsers<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.records.iter().map(|record| user(record, self.origin)))
+    }
+}
+
+/// GET /users without pagination.
+pub fn users<'a>(records: &'a [Record], origin: &'a Origin) -> LinkedUsers<'a> {
+    LinkedUsers { records, origin }
+}
+
+#[derive(Serialize)]
+struct PageLinks {
+    #[serde(rename = "self")]
+    this: Href,
+    first: Href,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    prev: Option<Href>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next: Option<Href>,
+    last: Href,
+}
+
+/// `PaginatedUsers` with links.
+#[derive(Serialize)]
+pub struct Page<'a> {
+    data: LinkedUsers<'a>,
+    total: i64,
+    limit: i64,
+    offset: i64,
+    _links: PageLinks,
+}
+
+pub fn page<'a>(
+    records: &'a [Record],
+    total: i64,
+    limit: i64,
+    offset: i64,
+    fields: Fields,
+    origin: &'a Origin,
+) -> Page<'a> {
+    let links = jsonapi::page_links(origin.as_str(), total, limit, offset, fields);
+    let href = |href| Href { href };
+    Page {
+        data: users(records, origin),
+        total,
+        limit,
+        offset,
+        _links: PageLinks {
+            this: href(links.this),
+            first: href(links.first),
+            prev: links.prev.map(href),
+            next: links.next.map(href),
+            last: href(links.last),
+        },
+    }
+}
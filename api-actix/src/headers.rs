@@ -0,0 +1,85 @@
+//! GET /headers-echo?headers=N&header_bytes=M — header parsing and writing.
+//!
+//! Every request header is read back into the JSON body, grouped by name (a
+//! repeated header keeps all its values; bytes that are not UTF-8 are
+//! replaced), with the count and total size.  The response also
+//! carries `headers` synthetic headers, `x-synthetic-0` … `x-synthetic-{N-1}`,
+//! each with a value of `header_bytes` bytes (default 32).
+//!
+//! Driving it with many or large request headers (`k6` `headers`, `h2load
+//! -H`) and many or large response headers measures the header path that the
+//! body-centric endpoints barely touch.  Requests over the server's own limits
+//! (96 headers in Actix's HTTP/1 parser, and a cap on the size of the head)
+//! are refused before the handler, which is part of what differs across
+//! frameworks.
+
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Most synthetic response headers.
+const MAX_HEADERS: usize = 512;
+/// Longest synthetic header value.
+const MAX_HEADER_BYTES: usize = 8192;
+const DEFAULT_HEADER_BYTES: usize = 32;
+
+#[derive(Deserialize)]
+struct EchoParams {
+    headers: Option<usize>,
+    header_bytes: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct Echo {
+    /// Request header lines, repeats included.
+    count: usize,
+    /// Bytes of the request header names and values.
+    bytes: usize,
+    headers: BTreeMap<String, Vec<String>>,
+}
+
+/// `len` printable bytes, the same for every header and run.
+fn synthetic_value(len: usize) -> HeaderValue {
+    const ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
+    let value: Vec<u8> = (0..len).map(|i| ALPHABET[i % ALPHABET.len()]).collect();
+    HeaderValue::from_bytes(&value).expect("alphanumeric header value")
+}
+
+/// GET /headers-echo
+#[get("/headers-echo")]
+async fn headers_echo(req: HttpRequest, query: web::Query<EchoParams>) -> impl Responder {
+    let synthetic = query.headers.unwrap_or(0);
+    let header_bytes = query.header_bytes.unwrap_or(DEFAULT_HEADER_BYTES);
+    if synthetic > MAX_HEADERS || header_bytes > MAX_HEADER_BYTES {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("headers must be at most {MAX_HEADERS} and header_bytes at most {MAX_HEADER_BYTES}")
+        }));
+    }
+
+    let mut echo = Echo { count: 0, bytes: 0, headers: BTreeMap::new() };
+    for (name, value) in req.headers() {
+        echo.count += 1;
+        echo.bytes += name.as_str().len() + value.len();
+        echo.headers
+            .entry(name.as_str().to_owned())
+            .or_default()
+            .push(String::from_utf8_lossy(value.as_bytes()).into_owned());
+    }
+
+    let mut res = HttpResponse::Ok().json(echo);
+    if synthetic > 0 {
+        let value = synthetic_value(header_bytes);
+        let headers = res.headers_mut();
+        headers.reserve(synthetic);
+        for i in 0..synthetic {
+            let name = HeaderName::try_from(format!("x-synthetic-{i}")).expect("valid header name");
+            headers.insert(name, value.clone());
+        }
+    }
+    res
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(headers_echo);
+}
@@ -0,0 +1,171 @@
+//! Experimental HTTP/3 listener (`--features http3`, enabled with `HTTP3=on`).
+//!
+//! Actix-web has no QUIC transport, so requests arriving over `h3` + `quinn`
+//! are converted into Actix requests and dispatched to the same [`crate::app`]
+//! the TCP server uses: routes, middleware and metrics are shared.  The
+//! endpoint runs on the main system thread only, which is enough to compare
+//! protocol behaviour but not to measure throughput against the TCP workers.
+//!
+//! Request bodies are buffered before dispatch and responses are sent as a
+//! single DATA frame; the benchmark payloads are small.
+
+use std::net::SocketAddr;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use actix_web::body::{self, MessageBody};
+use actix_web::dev::{Service, ServiceResponse};
+use actix_web::http::Method;
+use actix_web::test::{self, TestRequest};
+use actix_web::web;
+use bytes::{Buf, Bytes, BytesMut};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
+
+use crate::config::Http3Settings;
+use crate::AppState;
+
+/// Binds the UDP endpoint and starts accepting connections in the background.
+pub fn spawn(state: web::Data<AppState>, settings: &Http3Settings) -> std::io::Result<()> {
+    let tls = tls_config(settings)?;
+    let crypto = quinn::crypto::rustls::QuicServerConfig::try_from(tls)
+        .map_err(std::io::Error::other)?;
+    let server_config = quinn::ServerConfig::with_crypto(Arc::new(crypto));
+    let addr = SocketAddr::from(([0, 0, 0, 0], settings.port));
+    let endpoint = quinn::Endpoint::server(server_config, addr)?;
+    println!("HTTP/3 (experimental) listening on udp/{addr}");
+
+    actix_web::rt::spawn(async move {
+        let service = Rc::new(test::init_service(crate::app(state)).await);
+        while let Some(incoming) = endpoint.accept().await {
+            let service = Rc::clone(&service);
+            actix_web::rt::spawn(async move {
+                match incoming.await {
+                    Ok(conn) => serve_connection(conn, service).await,
+                    Err(e) => eprintln!("HTTP/3 handshake failed: {e}"),
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+/// TLS 1.3 with ALPN `h3`, from TLS_CERT_FILE/TLS_KEY_FILE or a self-signed
+/// certificate for `localhost`.
+fn tls_config(settings: &Http3Settings) -> std::io::Result<rustls::ServerConfig> {
+    let (certs, key) = match (&settings.tls_cert_file, &settings.tls_key_file) {
+        (Some(cert), Some(key)) => {
+            let certs = CertificateDer::pem_file_iter(cert)
+                .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+                .map_err(|e| std::io::Error::other(format!("{cert}: {e}")))?;
+            let key = PrivateKeyDer::from_pem_file(key)
+                .map_err(|e| std::io::Error::other(format!("{key}: {e}")))?;
+            (certs, key)
+        }
+        _ => {
+            let generated = rcgen::generate_simple_self_signed(vec!["localhost".to_string()])
+                .map_err(std::io::Error::other)?;
+            println!("HTTP/3 using a self-signed certificate for localhost");
+            let key = PrivatePkcs8KeyDer::from(generated.key_pair.serialize_der());
+            (vec![generated.cert.der().clone()], key.into())
+        }
+    };
+
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let mut tls = rustls::ServerConfig::builder_with_provider(provider)
+        .with_protocol_versions(&[&rustls::version::TLS13])
+        .and_then(|b| b.with_no_client_auth().with_single_cert(certs, key))
+        .map_err(std::io::Error::other)?;
+    tls.alpn_protocols = vec![b"h3".to_vec()];
+    Ok(tls)
+}
+
+async fn serve_connection<S, B>(conn: quinn::Connection, service: Rc<S>)
+where
+    S: Service<actix_http::Request, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    B: MessageBody + 'static,
+{
+    let peer = conn.remote_address();
+    let mut h3 = match h3::server::Connection::<_, Bytes>::new(h3_quinn::Connection::new(conn)).await {
+        Ok(h3) => h3,
+        Err(e) => {
+            eprintln!("HTTP/3 connection setup failed: {e}");
+            return;
+        }
+    };
+
+    loop {
+        match h3.accept().await {
+            Ok(Some(resolver)) => {
+                let service = Rc::clone(&service);
+                actix_web::rt::spawn(async move {
+                    match resolver.resolve_request().await {
+                        Ok((req, stream)) => {
+                            if let Err(e) = serve_request(req, stream, peer, &*service).await {
+                                eprintln!("HTTP/3 request failed: {e}");
+                            }
+                        }
+                        Err(e) => eprintln!("HTTP/3 request failed: {e}"),
+                    }
+                });
+            }
+            Ok(None) => break,
+            Err(e) => {
+                if !e.is_h3_no_error() {
+                    eprintln!("HTTP/3 connection error: {e}");
+                }
+                break;
+            }
+        }
+    }
+}
+
+type RequestStream = h3::server::RequestStream<h3_quinn::BidiStream<Bytes>, Bytes>;
+
+async fn serve_request<S, B>(
+    req: http::Request<()>,
+    mut stream: RequestStream,
+    peer: SocketAddr,
+    service: &S,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    S: Service<actix_http::Request, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    B: MessageBody + 'static,
+{
+    let mut payload = BytesMut::new();
+    while let Some(mut chunk) = stream.recv_data().await? {
+        payload.extend_from_slice(&chunk.copy_to_bytes(chunk.remaining()));
+    }
+
+    let (parts, ()) = req.into_parts();
+    let mut actix_req = TestRequest::default()
+        .method(Method::from_bytes(parts.method.as_str().as_bytes())?)
+        .uri(parts.uri.path_and_query().map_or("/", |pq| pq.as_str()))
+        .peer_addr(peer)
+        .set_payload(payload.freeze());
+    if let Some(authority) = parts.uri.authority() {
+        actix_req = actix_req.insert_header(("host", authority.as_str()));
+    }
+    for (name, value) in &parts.headers {
+        actix_req = actix_req.append_header((name.as_str(), value.as_bytes()));
+    }
+
+    // Errors are rendered the same way the TCP dispatcher does (e.g. the 504
+    // from the timeout middleware).
+    let res = match service.call(actix_req.to_request()).await {
+        Ok(res) => res.into_parts().1.map_into_boxed_body(),
+        Err(e) => e.error_response(),
+    };
+
+    let mut head = http::Response::builder().status(res.status().as_u16());
+    for (name, value) in res.headers() {
+        head = head.header(name.as_str(), value.as_bytes());
+    }
+    stream.send_response(head.body(())?).await?;
+    let body = body::to_bytes(res.into_body()).await?;
+    if !body.is_empty() {
+        stream.send_data(body).await?;
+    }
+    stream.finish().await?;
+    Ok(())
+}
@@ -0,0 +1,252 @@
+//! Background jobs on a Postgres queue (`JOB_WORKERS` > 0 enables it).
+//!
+//! `POST /jobs` inserts a row into `jobs`; `JOB_WORKERS` tasks on the main
+//! system claim queued rows with `FOR UPDATE SKIP LOCKED`, run them and record
+//! the result, and `GET /jobs/{id}` reports the status.  The workers draw from
+//! the same connection pool as the request handlers, which is the contention
+//! this subsystem exists to measure.
+//!
+//! An idle worker polls every `JOB_POLL_MS`; a busy one claims the next job
+//! immediately.
+
+use actix_web::{get, post, web, HttpResponse, Responder};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::Duration;
+
+use crate::db::cancellable;
+use crate::metrics::Metrics;
+use crate::AppState;
+
+/// Longest `sleep` job accepted, in ms.
+const MAX_SLEEP_MS: u64 = 60_000;
+
+/// Work a job can do; the JSON shape of `POST /jobs` bodies,
+/// e.g. `{"kind": "sleep", "payload": {"ms": 100}}`.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "kind", content = "payload", rename_all = "snake_case")]
+pub enum Job {
+    /// Waits `ms` milliseconds, standing in for a call to a slow service.
+    Sleep { ms: u64 },
+    /// Aggregates the `users` table.
+    UserStats,
+}
+
+#[derive(Serialize)]
+struct JobStatus {
+    id: i64,
+    kind: String,
+    status: String,
+    attempts: i32,
+    result: Option<Value>,
+    error: Option<String>,
+    created_at: DateTime<Utc>,
+    started_at: Option<DateTime<Utc>>,
+    finished_at: Option<DateTime<Utc>>,
+}
+
+fn row_to_status(row: &tokio_postgres::Row) -> JobStatus {
+    JobStatus {
+        id: row.get("id"),
+        kind: row.get("kind"),
+        status: row.get("status"),
+        attempts: row.get("attempts"),
+        result: row.get("result"),
+        error: row.get("error"),
+        created_at: row.get("created_at"),
+        started_at: row.get("started_at"),
+        finished_at: row.get("finished_at"),
+    }
+}
+
+const STATUS_COLUMNS: &str =
+    "id, kind, status, attempts, result, error, created_at, started_at, finished_at";
+
+/// POST /jobs  — enqueues a job and returns 202 with its status.
+#[post("/jobs")]
+async fn enqueue_job(data: web::Data<AppState>, body: web::Json<Job>) -> impl Responder {
+    let job = body.into_inner();
+    if let Job::Sleep { ms } = job {
+        if ms > MAX_SLEEP_MS {
+            return HttpResponse::BadRequest()
+                .json(serde_json::json!({ "error": "sleep ms must be at most 60000" }));
+        }
+    }
+    // Stored in the request's own shape so the worker can deserialize it back.
+    let encoded = serde_json::to_value(&job).unwrap_or_default();
+    let kind = encoded["kind"].as_str().unwrap_or_default().to_string();
+    let payload = encoded.get("payload").cloned().unwrap_or(Value::Null);
+
+    let client = match data.pool.get().await {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Pool error: {e}");
+            return HttpResponse::InternalServerError()
+                .json(serde_json::json!({ "error": "Database connection error" }));
+        }
+    };
+
+    let sql = format!("INSERT INTO jobs (kind, payload) VALUES ($1, $2) RETURNING {STATUS_COLUMNS}");
+    match cancellable(&client, client.query_one(&sql, &[&kind, &payload])).await {
+        Ok(row) => {
+            let status = row_to_status(&row);
+            HttpResponse::Accepted()
+                .insert_header(("Location", format!("/jobs/{}", status.id)))
+                .json(status)
+        }
+        Err(e) => {
+            eprintln!("Insert error: {e}");
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({ "error": "Database insert error" }))
+        }
+    }
+}
+
+/// GET /jobs/:id  — returns the job's status, or 404.
+#[get("/jobs/{id}")]
+async fn get_job(data: web::Data<AppState>, path: web::Path<i64>) -> impl Responder {
+    let id = path.into_inner();
+    let client = match data.pool.get().await {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Pool error: {e}");
+            return HttpResponse::InternalServerError()
+                .json(serde_json::json!({ "error": "Database connection error" }));
+        }
+    };
+
+    let sql = format!("SELECT {STATUS_COLUMNS} FROM jobs WHERE id = $1");
+    match cancellable(&client, client.query_opt(&sql, &[&id])).await {
+        Ok(Some(row)) => HttpResponse::Ok().json(row_to_status(&row)),
+        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({ "error": "Job not found" })),
+        Err(e) => {
+            eprintln!("Query error: {e}");
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({ "error": "Database query error" }))
+        }
+    }
+}
+
+/// Registers `/jobs` and `/jobs/{id}`.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(enqueue_job).service(get_job);
+}
+
+// ---------------------------------------------------------------------------
+// Workers
+// ---------------------------------------------------------------------------
+
+/// Requeues jobs left `running` by a previous process, then starts `workers`
+/// worker tasks on the current system.
+pub async fn spawn_workers(state: web::Data<AppState>, workers: usize, poll: Duration) {
+    if let Ok(client) = state.pool.get().await {
+        match client
+            .execute("UPDATE jobs SET status = 'queued' WHERE status = 'running'", &[])
+            .await
+        {
+            Ok(0) => {}
+            Ok(n) => println!("Requeued {n} interrupted job(s)"),
+            Err(e) => eprintln!("Job requeue error: {e}"),
+        }
+    }
+    for _ in 0..workers {
+        actix_web::rt::spawn(worker(state.clone(), poll));
+    }
+}
+
+async fn worker(state: web::Data<AppState>, poll: Duration) {
+    loop {
+        match run_next(&state).await {
+            Ok(true) => {}
+            Ok(false) => tokio::time::sleep(poll).await,
+            Err(e) => {
+                eprintln!("Job worker error: {e}");
+                tokio::time::sleep(poll).await;
+            }
+        }
+    }
+}
+
+/// Claims and runs one job; `Ok(false)` when the queue is empty.
+async fn run_next(state: &AppState) -> Result<bool, Box<dyn std::error::Error>> {
+    let client = state.pool.get().await?;
+    // The claim commits on its own, so the row lock is held only for the
+    // UPDATE, not for the job; a concurrent worker skips the row instead of
+    // waiting for it.
+    let claimed = client
+        .query_opt(
+            "UPDATE jobs \
+             SET status = 'running', started_at = NOW(), attempts = attempts + 1 \
+             WHERE id = ( \
+                 SELECT id FROM jobs WHERE status = 'queued' \
+                 ORDER BY id LIMIT 1 FOR UPDATE SKIP LOCKED \
+             ) \
+             RETURNING id, kind, payload",
+            &[],
+        )
+        .await?;
+    let Some(row) = claimed else {
+        return Ok(false);
+    };
+    drop(client);
+
+    let id: i64 = row.get("id");
+    let kind: String = row.get("kind");
+    let payload: Option<Value> = row.get("payload");
+    let job = serde_json::from_value::<Job>(serde_json::json!({ "kind": kind, "payload": payload }));
+    let outcome = match job {
+        Ok(job) => execute(state, job).await,
+        Err(e) => Err(format!("invalid job: {e}")),
+    };
+
+    let client = state.pool.get().await?;
+    match outcome {
+        Ok(result) => {
+            client
+                .execute(
+                    "UPDATE jobs SET status = 'done', result = $2, finished_at = NOW() WHERE id = $1",
+                    &[&id, &result],
+                )
+                .await?;
+            Metrics::inc(&state.metrics.jobs_done_total);
+        }
+        Err(error) => {
+            client
+                .execute(
+                    "UPDATE jobs SET status = 'failed', error = $2, finished_at = NOW() WHERE id = $1",
+                    &[&id, &error],
+                )
+                .await?;
+            Metrics::inc(&state.metrics.jobs_failed_total);
+        }
+    }
+    Ok(true)
+}
+
+async fn execute(state: &AppState, job: Job) -> Result<Value, String> {
+    match job {
+        Job::Sleep { ms } => {
+            tokio::time::sleep(Duration::from_millis(ms)).await;
+            Ok(serde_json::json!({ "slept_ms": ms }))
+        }
+        Job::UserStats => {
+            let client = state.pool.get().await.map_err(|e| e.to_string())?;
+            let row = client
+                .query_one(
+                    "SELECT COUNT(*)::bigint AS count, AVG(age)::float8 AS avg_age, \
+                            MIN(created_at) AS first, MAX(created_at) AS last \
+                     FROM users",
+                    &[],
+                )
+                .await
+                .map_err(|e| e.to_string())?;
+            Ok(serde_json::json!({
+                "count": row.get::<_, i64>("count"),
+                "avg_age": row.get::<_, Option<f64>>("avg_age"),
+                "first_created_at": row.get::<_, Option<DateTime<Utc>>>("first"),
+                "last_created_at": row.get::<_, Option<DateTime<Utc>>>("last"),
+            }))
+        }
+    }
+}
@@ -0,0 +1,240 @@
+//! JSON:API output (<https://jsonapi.org/format/1.1/>) for the `/users` endpoints.
+//!
+//! Selected per request by `Accept: application/vnd.api+json` (see
+//! `negotiate`); every other request gets the plain payloads unchanged.  Users are written as
+//! resource objects (`type`, `id`, `attributes`, `links`) inside a top-level
+//! document with `links`, and collections add `meta.total` plus
+//! `first`/`prev`/`next`/`last` links when paginated.  Users have no
+//! relationships, so resource objects carry none.  Sparse fieldsets work as
+//! `fields[users]=name,email` or the plain `fields=`.
+//!
+//! The documents are serialized straight from [`Record`], without an
+//! intermediate `serde_json::Value`, so the measured difference is the
+//! envelope itself.  Error bodies (`{"error": msg}`) are rewritten into
+//! `{"errors": [...]}` by [`errors`], which wraps the whole app.
+//!
+//! Only the output is JSON:API: request bodies keep the plain shape, and none
+//! of this is part of the OpenAPI document.
+
+use actix_web::body::{self, BoxBody, EitherBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header;
+use actix_web::http::StatusCode;
+use actix_web::middleware::Next;
+use actix_web::{Error, HttpResponse, HttpResponseBuilder};
+use serde::ser::{Error as _, SerializeMap, Serializer};
+use serde::Serialize;
+
+use crate::fields::{Fields, Record};
+use crate::negotiate::{negotiate, Encoding};
+
+pub const MEDIA_TYPE: &str = "application/vnd.api+json";
+
+/// Serializes `doc` with the JSON:API content type.
+pub fn respond(mut builder: HttpResponseBuilder, doc: impl Serialize) -> HttpResponse {
+    builder.content_type(MEDIA_TYPE).json(doc)
+}
+
+#[derive(Serialize)]
+struct Version {
+    version: &'static str,
+}
+
+const VERSION: Version = Version { version: "1.1" };
+
+#[derive(Serialize)]
+struct Links {
+    #[serde(rename = "self")]
+    this: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    first: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    prev: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last: Option<String>,
+}
+
+impl Links {
+    fn this(this: String) -> Links {
+        Links { this, first: None, prev: None, next: None, last: None }
+    }
+}
+
+#[derive(Serialize)]
+struct Meta {
+    total: i64,
+}
+
+#[derive(Serialize)]
+pub struct Document<D> {
+    jsonapi: Version,
+    data: D,
+    links: Links,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    meta: Option<Meta>,
+}
+
+/// A user as a resource object.
+pub struct Resource<'a>(&'a Record);
+
+impl Serialize for Resource<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let id = self.0.id().ok_or_else(|| S::Error::custom("resource without id"))?;
+        let mut map = serializer.serialize_map(Some(4))?;
+        map.serialize_entry("type", "users")?;
+        map.serialize_entry("id", &id.to_string())?;
+        map.serialize_entry("attributes", &Attributes(self.0))?;
+        map.serialize_entry("links", &Links::this(format!("/users/{id}")))?;
+        map.end()
+    }
+}
+
+struct Attributes<'a>(&'a Record);
+
+impl Serialize for Attributes<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        self.0.serialize_fields(&mut map, true)?;
+        map.end()
+    }
+}
+
+struct Resources<'a>(&'a [Record]);
+
+impl Serialize for Resources<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.iter().map(Resource))
+    }
+}
+
+/// `/users?...` with the canonical spelling of the current query.
+pub fn users_link(fields: Fields, page: Option<(i64, i64)>) -> String {
+    let mut params = Vec::new();
+    if let Some((limit, offset)) = page {
+        params.push(format!("limit={limit}&offset={offset}"));
+    }
+    if let Some(list) = fields.query() {
+        params.push(format!("fields={list}"));
+    }
+    match params.is_empty() {
+        true => "/users".to_owned(),
+        false => format!("/users?{}", params.join("&")),
+    }
+}
+
+/// Document for a single user.
+pub fn resource(record: &Record) -> Document<Resource<'_>> {
+    let this = record.id().map_or_else(|| "/users".to_owned(), |id| format!("/users/{id}"));
+    Document { jsonapi: VERSION, data: Resource(record), links: Links::this(this), meta: None }
+}
+
+/// Document for GET /users without pagination.
+pub fn collection(records: &[Record], total: i64, fields: Fields) -> Document<impl Serialize + '_> {
+    Document {
+        jsonapi: VERSION,
+        data: Resources(records),
+        links: Links::this(users_link(fields, None)),
+        meta: Some(Meta { total }),
+    }
+}
+
+/// Navigation links of one page of GET /users, each prefixed by `origin`
+/// (empty for relative links).  Shared with `hal`.
+pub struct PageLinks {
+    pub this: String,
+    pub first: String,
+    pub prev: Option<String>,
+    pub next: Option<String>,
+    pub last: String,
+}
+
+pub fn page_links(origin: &str, total: i64, limit: i64, offset: i64, fields: Fields) -> PageLinks {
+    let link = |offset| format!("{origin}{}", users_link(fields, Some((limit, offset))));
+    let last = if total > 0 { (total - 1) / limit * limit } else { 0 };
+    PageLinks {
+        this: link(offset),
+        first: link(0),
+        prev: (offset > 0).then(|| link((offset - limit).max(0))),
+        next: (offset + limit < total).then(|| link(offset + limit)),
+        last: link(last),
+    }
+}
+
+/// Document for one page of GET /users?limit=N&offset=M.
+pub fn page(records: &[Record], total: i64, limit: i64, offset: i64, fields: Fields) -> Document<impl Serialize + '_> {
+    let links = page_links("", total, limit, offset, fields);
+    Document {
+        jsonapi: VERSION,
+        data: Resources(records),
+        links: Links {
+            this: links.this,
+            first: Some(links.first),
+            prev: links.prev,
+            next: links.next,
+            last: Some(links.last),
+        },
+        meta: Some(Meta { total }),
+    }
+}
+
+#[derive(Serialize)]
+struct ErrorObject<'a> {
+    status: String,
+    title: &'a str,
+}
+
+#[derive(Serialize)]
+struct ErrorDocument<'a> {
+    jsonapi: Version,
+    errors: [ErrorObject<'a>; 1],
+}
+
+impl<'a> ErrorDocument<'a> {
+    fn new(status: StatusCode, title: &'a str) -> ErrorDocument<'a> {
+        ErrorDocument { jsonapi: VERSION, errors: [ErrorObject { status: status.as_str().to_owned(), title }] }
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct PlainError {
+    error: String,
+}
+
+/// Rewrites `{"error": msg}` error bodies into JSON:API error documents for
+/// requests that negotiated JSON:API; everything else passes through.
+pub async fn errors(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody, BoxBody>>, Error> {
+    if negotiate(req.headers()) != Some(Encoding::JsonApi) {
+        return next.call(req).await.map(ServiceResponse::map_into_left_body);
+    }
+    let res = next.call(req).await?;
+    let plain_json = res
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .is_some_and(|v| v.as_bytes().starts_with(b"application/json"));
+    if !(res.status().is_client_error() || res.status().is_server_error()) || !plain_json {
+        return Ok(res.map_into_left_body());
+    }
+
+    let (http_req, res) = res.into_parts();
+    let status = res.status();
+    let headers = res.headers().clone();
+    let body = body::to_bytes(res.into_body())
+        .await
+        .map_err(|e| actix_web::error::ErrorInternalServerError(Into::<Box<dyn std::error::Error>>::into(e).to_string()))?;
+    let title = serde_json::from_slice::<PlainError>(&body).map_or_else(
+        |_| status.canonical_reason().unwrap_or("Error").to_owned(),
+        |plain| plain.error,
+    );
+    let mut res = respond(HttpResponse::build(status), ErrorDocument::new(status, &title));
+    for (name, value) in headers.iter() {
+        if name != header::CONTENT_TYPE && name != header::CONTENT_LENGTH {
+            res.headers_mut().append(name.clone(), value.clone());
+        }
+    }
+    Ok(ServiceResponse::new(http_req, res).map_into_right_body())
+}
@@ -0,0 +1,88 @@
+//! Primary key of `users`.
+//!
+//! By default the `SERIAL` integer of `scripts/init.sql`.  With the
+//! `bigint-keys` feature an `i64`, on a database converted by
+//! `scripts/migrations/003-bigint-keys.sql` (`BIGSERIAL`), for tables past
+//! the `INTEGER` range or to measure the wider key.  With the `uuid-keys`
+//! feature a UUIDv7, on a database converted by
+//! `scripts/migrations/002-uuid-keys.sql` (which makes `uuid_v7()` the
+//! column default, so the SQL of every write stays the same).  Path
+//! parameters, `?since_id=`, response bodies and the audit log follow
+//! [`UserId`]; the repositories are written against the [`Key`] bounds.
+//!
+//! UUIDv7 keys start with a millisecond timestamp, so they sort in insertion
+//! order like the integers and `ORDER BY id` (pagination, export, long
+//! polling) keeps its meaning, but they are four times wider in the index
+//! and 36 bytes of JSON instead of up to 10, and parsing one costs more.
+//! They cannot be drawn from `1..=MAX(id)`: a UUID build reads random rows
+//! with `ORDER BY RANDOM()` (see `repo::draws`).
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fmt::{Debug, Display};
+use std::hash::Hash;
+use tokio_postgres::types::ToSql;
+
+#[cfg(all(feature = "uuid-keys", feature = "bigint-keys"))]
+compile_error!("features `uuid-keys` and `bigint-keys` are mutually exclusive");
+
+#[cfg(not(any(feature = "uuid-keys", feature = "bigint-keys")))]
+pub type UserId = i32;
+#[cfg(feature = "bigint-keys")]
+pub type UserId = i64;
+#[cfg(feature = "uuid-keys")]
+pub type UserId = uuid::Uuid;
+
+/// What the repositories and handlers need of a key.
+pub trait Key:
+    Copy + Ord + Hash + Debug + Display + Serialize + DeserializeOwned + ToSql + Sync + Send + 'static
+{
+    /// Below every key: the default `since_id` of GET /users/poll.
+    const MIN: Self;
+
+    /// Key of the `seq`-th (1-based) row of the in-memory repository;
+    /// PostgreSQL assigns its own.  Increasing with `seq`.
+    fn generate(seq: i32) -> Self;
+
+    /// The key `n` of a `1..=MAX(id)` draw (see `repo::draws`); `None` when
+    /// keys are not numbered.
+    fn drawn(n: i64) -> Option<Self>;
+}
+
+impl Key for i32 {
+    const MIN: i32 = 0;
+
+    fn generate(seq: i32) -> i32 {
+        seq
+    }
+
+    fn drawn(n: i64) -> Option<i32> {
+        i32::try_from(n).ok()
+    }
+}
+
+impl Key for i64 {
+    const MIN: i64 = 0;
+
+    fn generate(seq: i32) -> i64 {
+        seq.into()
+    }
+
+    fn drawn(n: i64) -> Option<i64> {
+        Some(n)
+    }
+}
+
+#[cfg(feature = "uuid-keys")]
+impl Key for uuid::Uuid {
+    const MIN: uuid::Uuid = uuid::Uuid::nil();
+
+    /// `now_v7` is monotonic within the process, so keys still sort by `seq`.
+    fn generate(_seq: i32) -> uuid::Uuid {
+        uuid::Uuid::now_v7()
+    }
+
+    fn drawn(_n: i64) -> Option<uuid::Uuid> {
+        None
+    }
+}
@@ -0,0 +1,1163 @@
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceFactory, ServiceRequest, ServiceResponse};
+use actix_web::http::header;
+use actix_web::middleware::{Condition, DefaultHeaders};
+use actix_web::{delete, get, post, put, web, App, HttpRequest, HttpResponse, HttpServer, Responder};
+use chrono::{DateTime, Utc};
+use db::Pool;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use utoipa::{IntoParams, ToSchema};
+
+mod admin;
+mod auth;
+mod bulk;
+mod compress_bench;
+pub mod config;
+mod conn_stats;
+mod cookies;
+pub mod db;
+mod etag;
+pub mod fields;
+mod events;
+mod export;
+pub mod filter;
+mod form;
+#[cfg(feature = "postgis")]
+pub mod geo;
+mod hal;
+mod headers;
+#[cfg(feature = "http3")]
+mod http3;
+mod jsonapi;
+mod jobs;
+pub mod key;
+pub mod metadata;
+mod metrics;
+mod negotiate;
+pub mod middleware;
+mod openapi;
+mod payload;
+mod poll;
+mod query_echo;
+#[cfg(not(feature = "bb8"))]
+mod pool_autosize;
+mod pool_stats;
+mod profiling;
+pub mod repo;
+mod routing;
+#[cfg(feature = "row-encoding")]
+mod rowjson;
+mod scheduler;
+mod session;
+pub mod status;
+mod tags;
+mod tenant;
+pub mod text;
+pub mod timestamp;
+mod timing;
+pub mod transfer;
+mod webhooks;
+
+use config::{Config, RandomRows, TimestampFormat};
+use middleware::load_shed::LoadShedder;
+use fields::{Fields, FieldsParams, Record};
+use filter::Filter;
+use key::UserId;
+use negotiate::{Negotiated, Output};
+use middleware::real_ip::client_ip;
+use repo::{RepoError, Scope, UserRepository};
+use status::Status;
+use tenant::Tenant;
+use text::Text;
+
+// ---------------------------------------------------------------------------
+// Global allocator (selected at build time: --features mimalloc | jemalloc)
+// ---------------------------------------------------------------------------
+
+#[cfg(all(feature = "mimalloc", feature = "jemalloc"))]
+compile_error!("features `mimalloc` and `jemalloc` are mutually exclusive");
+
+#[cfg(feature = "jemalloc")]
+#[global_allocator]
+static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
+
+#[cfg(all(feature = "mimalloc", not(feature = "jemalloc")))]
+#[global_allocator]
+static GLOBAL: mimalloc::MiMalloc = mimalloc::MiMalloc;
+
+/// Allocator compiled into this binary, reported by GET / and GET /config.
+const ALLOCATOR: &str = if cfg!(feature = "jemalloc") {
+    "jemalloc"
+} else if cfg!(feature = "mimalloc") {
+    "mimalloc"
+} else {
+    "system"
+};
+
+/// Cargo features compiled into this binary, reported by GET /config.
+const FEATURES: &[(&str, bool)] = &[
+    ("mimalloc", cfg!(feature = "mimalloc")),
+    ("jemalloc", cfg!(feature = "jemalloc")),
+    ("heap-profiling", cfg!(feature = "heap-profiling")),
+    ("http3", cfg!(feature = "http3")),
+    ("bb8", cfg!(feature = "bb8")),
+    ("camel-case", cfg!(feature = "camel-case")),
+    ("interned-strings", cfg!(feature = "interned-strings")),
+    ("row-encoding", cfg!(feature = "row-encoding")),
+    ("uuid-keys", cfg!(feature = "uuid-keys")),
+    ("bigint-keys", cfg!(feature = "bigint-keys")),
+    ("postgis", cfg!(feature = "postgis")),
+];
+
+/// Build provenance embedded by build.rs.
+const GIT_SHA: &str = env!("BUILD_GIT_SHA");
+const RUSTC_VERSION: &str = env!("BUILD_RUSTC_VERSION");
+const BUILD_PROFILE: &str = if cfg!(debug_assertions) { "debug" } else { "release" };
+
+// ---------------------------------------------------------------------------
+// Domain types
+// ---------------------------------------------------------------------------
+
+/// Represents a row in the `users` table.  Field names follow the columns,
+/// or camelCase with the `camel-case` feature (see `fields::KEYS`); the
+/// strings are shared with the `interned-strings` feature (see `text`),
+/// `created_at` is written in the configured format (see `timestamp`), the
+/// id is a UUIDv7 or a BIGINT with the `uuid-keys` or `bigint-keys` feature
+/// (see `key`), and `tags` and `status` are optional in the schema, the other
+/// APIs having no such columns (see `tags` and `status`).
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct User {
+    #[cfg_attr(not(feature = "uuid-keys"), schema(inline))]
+    #[cfg_attr(feature = "uuid-keys", schema(value_type = String, format = Uuid))]
+    pub id: UserId,
+    #[schema(value_type = String)]
+    pub name: Text,
+    #[schema(value_type = String)]
+    pub email: Text,
+    pub age: Option<i32>,
+    #[serde(serialize_with = "timestamp::serialize", deserialize_with = "timestamp::deserialize")]
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub status: Status,
+}
+
+/// Request body for POST /users.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateUser {
+    pub name: String,
+    #[serde(deserialize_with = "email")]
+    pub email: String,
+    pub age: Option<i32>,
+    #[serde(default, deserialize_with = "tags::deserialize")]
+    pub tags: Vec<String>,
+}
+
+/// Request body for PUT /users/:id.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateUser {
+    pub name: Option<String>,
+    #[serde(default, deserialize_with = "optional_email")]
+    pub email: Option<String>,
+    pub age: Option<i32>,
+    #[serde(default, deserialize_with = "tags::deserialize_optional")]
+    pub tags: Option<Vec<String>>,
+    pub status: Option<Status>,
+}
+
+/// Emails are stored trimmed and lowercased, so ` Ana@Example.com` and
+/// `ana@example.com` are one address, as for the `lower(email)` unique index
+/// in `init.sql`, which also covers the other APIs.
+fn email<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    Ok(String::deserialize(deserializer)?.trim().to_lowercase())
+}
+
+fn optional_email<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    Ok(Option::<String>::deserialize(deserializer)?.map(|email| email.trim().to_lowercase()))
+}
+
+// ---------------------------------------------------------------------------
+// Application state
+// ---------------------------------------------------------------------------
+
+pub struct AppState {
+    pub pool: Pool,
+    /// Storage behind the `/db`, `/queries` and `/users` handlers.
+    pub users: Arc<dyn UserRepository>,
+    pub config: Config,
+    pub metrics: metrics::Metrics,
+    pub shedder: LoadShedder,
+    /// Per-route semaphores (`ROUTE_CONCURRENCY`).
+    pub route_limits: middleware::concurrency::RouteLimits,
+    /// Read/write queues (`PRIORITY_SLOTS`).
+    pub priority: middleware::priority::PriorityScheduler,
+    /// Session store and signing key when `SESSION_STORE` is not off.
+    pub sessions: Option<session::Sessions>,
+    pub cache: middleware::cache::ResponseCache,
+    /// Broadcast side of the LISTEN/NOTIFY change feed.
+    pub events: events::ChangeFeed,
+    /// User list kept by the `cached_users` scheduled task.
+    pub users_snapshot: scheduler::UsersSnapshot,
+    /// Bodies served by GET /payload-static, built at startup.
+    pub payloads: payload::StaticPayloads,
+    /// Idle compressors of GET /compress-bench.
+    pub compressors: compress_bench::Compressors,
+    /// Per-endpoint duration histograms (`DB_TIMING`).
+    pub timing: timing::Timing,
+    /// Per-route HDR histograms written on shutdown (`LATENCY_SUMMARY_PATH`).
+    pub latency: middleware::latency::LatencyLog,
+    /// Connection and protocol counters behind GET /conn-stats.
+    pub conn_stats: Arc<conn_stats::ConnStats>,
+}
+
+impl AppState {
+    /// State for `config` on top of an already built pool (see `db::build_pool`);
+    /// opens the session store when one is configured.
+    pub async fn new(pool: Pool, config: Config) -> std::io::Result<AppState> {
+        timestamp::set(config.timestamp_format);
+        let shedder = LoadShedder::new(
+            config.shed.max_in_flight,
+            config.shed.p99_budget_ms,
+            config.shed.window_ms,
+        );
+        let route_limits = middleware::concurrency::RouteLimits::new(
+            &config.route_concurrency.limits,
+            config.route_concurrency.queue_depth,
+        );
+        let priority = middleware::priority::PriorityScheduler::new(&config.priority);
+        let sessions = session::Sessions::build(&config.session).await?;
+        let payloads = payload::StaticPayloads::build(&config.static_payload_sizes, config.static_payload_zstd_level)?;
+        Ok(AppState {
+            users: Arc::new(repo::Postgres::new(pool.clone(), &config)),
+            pool,
+            config,
+            metrics: metrics::Metrics::default(),
+            shedder,
+            route_limits,
+            priority,
+            cache: Default::default(),
+            users_snapshot: Default::default(),
+            events: Default::default(),
+            sessions,
+            payloads,
+            compressors: Default::default(),
+            timing: Default::default(),
+            latency: Default::default(),
+            conn_stats: Default::default(),
+        })
+    }
+
+    /// Serves the user handlers from `users` instead of the pool.
+    pub fn with_users(mut self, users: Arc<dyn UserRepository>) -> Self {
+        self.users = users;
+        self
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Helper: map a postgres Row into a User
+// ---------------------------------------------------------------------------
+
+pub fn row_to_user(row: &tokio_postgres::Row) -> User {
+    User {
+        id: row.get("id"),
+        name: text::intern(row.get("name")),
+        email: text::intern(row.get("email")),
+        age: row.get("age"),
+        created_at: row.get("created_at"),
+        tags: row.get("tags"),
+        status: row.get("status"),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Handlers
+// ---------------------------------------------------------------------------
+
+/// The 500 for a failed repository call: `Database connection error` when no
+/// connection could be had, else `message`; `context` prefixes the log line.
+fn storage_error(context: &str, message: &str, e: RepoError) -> HttpResponse {
+    let message = match e {
+        RepoError::Pool(e) => {
+            eprintln!("Pool error: {e}");
+            "Database connection error"
+        }
+        e => {
+            eprintln!("{context} error: {e}");
+            message
+        }
+    };
+    HttpResponse::InternalServerError().json(serde_json::json!({ "error": message }))
+}
+
+/// GET /
+#[utoipa::path(tag = "benchmark", responses((status = 200, description = "Service banner", body = serde_json::Value)))]
+#[get("/")]
+async fn index(data: web::Data<AppState>) -> impl Responder {
+    HttpResponse::Ok().json(serde_json::json!({
+        "message": "Actix-web API",
+        "framework": "actix-web",
+        "runtime": "rust",
+        "allocator": ALLOCATOR,
+        "cpus": data.config.runtime.cpus,
+        "workers": data.config.runtime.workers,
+        "tokio_worker_threads": data.config.runtime.tokio_worker_threads,
+        "max_blocking_threads": data.config.runtime.max_blocking_threads,
+        "tokio_flavor": data.config.runtime.tokio_flavor
+    }))
+}
+
+/// GET /config  — effective runtime configuration and build provenance, stored
+/// by the harness next to every result so runs can be audited and reproduced.
+#[get("/config")]
+async fn config_endpoint(data: web::Data<AppState>) -> impl Responder {
+    let features: Vec<&str> = FEATURES
+        .iter()
+        .filter(|(_, enabled)| *enabled)
+        .map(|(name, _)| *name)
+        .collect();
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "framework": "actix-web",
+        "runtime": data.config.runtime,
+        "tcp": data.config.tcp,
+        "shed": data.config.shed,
+        "route_concurrency": data.config.route_concurrency,
+        "priority": data.config.priority,
+        "session": data.config.session,
+        "scheduler": {
+            "interval_secs": data.config.scheduler_interval_secs,
+            "jitter_ms": data.config.scheduler_jitter_ms,
+            "cached_users_encoding": data.config.cached_users_encoding
+        },
+        "jobs": {
+            "workers": data.config.job_workers,
+            "poll_ms": data.config.job_poll_ms
+        },
+        "trusted_proxies": data.config.trusted_proxies,
+        "request_timeout_ms": data.config.request_timeout_ms,
+        "response_cache": data.config.response_cache,
+        "events": data.config.events,
+        "long_poll_interval_ms": data.config.long_poll_interval_ms,
+        "tenancy": {
+            "enabled": data.config.multi_tenant,
+            "domain": data.config.tenant_domain,
+            "scoping": data.config.tenant_scoping
+        },
+        "audit": data.config.audit,
+        "transaction_per_request": data.config.transaction_per_request,
+        "webhooks": {
+            "enabled": data.config.webhooks,
+            "max_attempts": data.config.webhook_max_attempts
+        },
+        "hypermedia": data.config.hypermedia,
+        "json": {
+            "envelope": data.config.json_envelope,
+            "pretty": data.config.json_pretty
+        },
+        "users_streaming": data.config.users_streaming,
+        "timestamp_format": data.config.timestamp_format,
+        "random_rows": {
+            "selection": data.config.random_rows,
+            "seed": data.config.random_seed,
+            "max_id_refresh_secs": data.config.max_id_refresh_secs
+        },
+        "static_payload_sizes": data.config.static_payload_sizes,
+        "static_payload_zstd_level": data.config.static_payload_zstd_level,
+        "compress_bench": data.config.compress_bench,
+        "routing_bench": data.config.routing_bench,
+        "http3": data.config.http3,
+        "pool": {
+            "implementation": db::IMPLEMENTATION,
+            "max_size": data.pool.status().max_size,
+            "stats_interval_ms": data.config.pool_stats_interval_ms,
+            "autosize": data.config.pool_autosize
+        },
+        "db_timing": data.config.db_timing,
+        "latency_summary_path": data.config.latency_summary_path,
+        "admin": data.config.admin_token.is_some(),
+        "allocator": ALLOCATOR,
+        "build": {
+            "profile": BUILD_PROFILE,
+            "git_sha": GIT_SHA,
+            "rustc": RUSTC_VERSION,
+            "features": features
+        }
+    }))
+}
+
+/// GET /json
+#[utoipa::path(tag = "benchmark", responses((status = 200, description = "Static greeting", body = openapi::Message)))]
+#[get("/json")]
+async fn json_endpoint(output: Output) -> impl Responder {
+    Negotiated::new(output, serde_json::json!({
+        "message": "Hello, World!",
+        "framework": "actix-web"
+    }))
+}
+
+/// GET /db  — returns one random user from the database.
+#[utoipa::path(tag = "benchmark", responses(
+    (status = 200, description = "A random user", body = User),
+    (status = 404, description = "Table is empty", body = openapi::ErrorResponse),
+    (status = 500, description = "Database error", body = openapi::ErrorResponse),
+))]
+#[get("/db")]
+async fn db_endpoint(data: web::Data<AppState>, output: Output, tenant: Tenant) -> impl Responder {
+    let user = match data.users.random_one(tenant.scope()).await {
+        Ok(Some(u)) => u,
+        Ok(None) => {
+            return HttpResponse::NotFound()
+                .json(serde_json::json!({ "error": "No users found" }));
+        }
+        Err(e) => return storage_error("Query", "Database query error", e),
+    };
+
+    Negotiated::new(output, user).respond(HttpResponse::Ok())
+}
+
+/// Query parameters for GET /queries.
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct QueriesParams {
+    count: Option<i64>,
+}
+
+/// GET /queries?count=N  — returns N random users (1 ≤ N ≤ 500, default 1).
+#[utoipa::path(tag = "benchmark", params(QueriesParams), responses(
+    (status = 200, description = "N random users", body = Vec<User>),
+    (status = 400, description = "Invalid query string", body = openapi::ErrorResponse),
+    (status = 500, description = "Database error", body = openapi::ErrorResponse),
+))]
+#[get("/queries")]
+async fn queries_endpoint(
+    data: web::Data<AppState>,
+    query: web::Query<QueriesParams>,
+    output: Output,
+    tenant: Tenant,
+) -> impl Responder {
+    let count = query.count.unwrap_or(1).clamp(1, 500);
+
+    #[cfg(feature = "row-encoding")]
+    if output.is_plain_json() {
+        return match data.users.random_json(tenant.scope(), count).await {
+            Ok(body) => HttpResponse::Ok().content_type(output.encoding.content_type()).body(body),
+            Err(e) => storage_error("Query", "Database query error", e),
+        };
+    }
+
+    match data.users.random(tenant.scope(), count).await {
+        Ok(users) => Negotiated::new(output, users).respond(HttpResponse::Ok()),
+        Err(e) => storage_error("Query", "Database query error", e),
+    }
+}
+
+/// Query parameters for GET /users (paginação opcional).
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct UsersParams {
+    pub limit:  Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// Path of the /users/:id routes, for the OpenAPI document (the handlers
+/// take `web::Path<UserId>`).  A UUID key is a string in the schema.
+#[derive(IntoParams)]
+#[into_params(parameter_in = Path)]
+pub struct UserPath {
+    /// User id
+    #[cfg_attr(not(feature = "uuid-keys"), param(inline))]
+    #[cfg_attr(feature = "uuid-keys", param(value_type = String, format = Uuid))]
+    pub id: UserId,
+}
+
+/// Users per age bucket, for GET /users/age-histogram.  Bucket `i` holds
+/// the ages in `[min + i·w, min + (i+1)·w)`, with `w = (max + 1 − min) / N`.
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct AgeHistogram {
+    /// Youngest and oldest age on record; `None` when no user has an age.
+    pub min: Option<i32>,
+    pub max: Option<i32>,
+    /// Users per bucket, empty buckets included (all zero without ages).
+    pub counts: Vec<i64>,
+    /// Users without an age.
+    pub unknown: i64,
+}
+
+/// Resposta paginada para GET /users?limit=N.
+#[derive(Serialize, ToSchema)]
+pub struct PaginatedUsers {
+    #[schema(value_type = Vec<User>)]
+    pub data:   Vec<Record>,
+    pub total:  i64,
+    pub limit:  i64,
+    pub offset: i64,
+}
+
+/// GET /users  — retorna todos os usuários ou uma página quando ?limit=N é informado.
+/// `?fields=id,name` projeta só essas colunas (ver `fields`); `?metadata.plan=pro`
+/// e `?tag=beta` restringem aos usuários que atendem todos os filtros (ver `filter`).
+/// Responde 304 quando o `If-None-Match` do cliente ainda corresponde ao ETag da coleção.
+#[utoipa::path(tag = "users", params(UsersParams), responses(
+    (status = 200, description = "All users, or one page with ?limit", body = openapi::UsersResponse,
+        headers(("ETag" = String, description = "Collection version"))),
+    (status = 304, description = "If-None-Match matches the current ETag"),
+    (status = 400, description = "Invalid query string", body = openapi::ErrorResponse),
+    (status = 500, description = "Database error", body = openapi::ErrorResponse),
+))]
+#[get("/users")]
+async fn get_users(
+    req:   HttpRequest,
+    data:  web::Data<AppState>,
+    query: web::Query<UsersParams>,
+    projection: web::Query<FieldsParams>,
+    pairs: web::Query<Vec<(String, String)>>,
+    output: Output,
+    tenant: Tenant,
+) -> impl Responder {
+    let fields = match Fields::parse(projection.list()) {
+        Ok(f) if output.encoding.is_jsonapi() => f.with_id(),
+        Ok(f) => f,
+        Err(msg) => return HttpResponse::BadRequest().json(serde_json::json!({ "error": msg })),
+    };
+    let filter = match Filter::parse(&pairs) {
+        Ok(filter) => filter,
+        Err(msg) => return HttpResponse::BadRequest().json(serde_json::json!({ "error": msg })),
+    };
+
+    let scope = tenant.scope();
+
+    // Contagem total + última escrita: versão da coleção (ETag), que muda
+    // também com a escrita de uma linha fora do filtro
+    let (total, last_write) = match data.users.version(scope).await {
+        Ok(v)  => v,
+        Err(e) => return storage_error("Count query", "Database query error", e),
+    };
+    let mut etag = fields.etag(etag::collection(total, last_write));
+    if let Some(filter) = &filter {
+        etag = filter.etag(etag);
+    }
+    let etag = output.encoding.etag(etag);
+    let origin = data.config.hypermedia.then(|| hal::Origin::of(&req, &data.config.trusted_proxies));
+    if etag::matches(req.headers(), &etag) {
+        return HttpResponse::NotModified()
+            .insert_header((actix_web::http::header::ETAG, etag))
+            .finish();
+    }
+
+    if let Some(limit_raw) = query.limit {
+        // ── Paginação ──────────────────────────────────────────────────────
+        let limit:  i64 = limit_raw.clamp(1, 100);
+        let offset: i64 = query.offset.unwrap_or(0).max(0);
+
+        // Página de dados (e, com filtro, o total de usuários que o atendem)
+        let (total, page) = match &filter {
+            Some(filter) => match data.users.matching(scope, filter, Some(limit), offset, fields).await {
+                Ok(m)  => m,
+                Err(e) => return storage_error("Query", "Database query error", e),
+            },
+            None => match data.users.page(scope, limit, offset, fields).await {
+                Ok(p)  => (total, p),
+                Err(e) => return storage_error("Query", "Database query error", e),
+            },
+        };
+
+        let mut res = HttpResponse::Ok();
+        res.insert_header((actix_web::http::header::ETAG, etag));
+        if output.encoding.is_jsonapi() {
+            return Negotiated::new(output, jsonapi::page(&page, total, limit, offset, fields)).respond(res);
+        }
+        if let Some(origin) = &origin {
+            return Negotiated::new(output, hal::page(&page, total, limit, offset, fields, origin)).respond(res);
+        }
+        return Negotiated::new(output, PaginatedUsers { data: page, total, limit, offset }).respond(res);
+    }
+
+    // ── Sem paginação: retorna todos ───────────────────────────────────────
+    if data.config.users_streaming && output.is_plain_json() && fields.is_all() && origin.is_none() && filter.is_none() {
+        let users = match data.users.stream_all(scope).await {
+            Ok(users) => users,
+            Err(e) => return storage_error("Query", "Database query error", e),
+        };
+        return HttpResponse::Ok()
+            .insert_header((actix_web::http::header::ETAG, etag))
+            .content_type(output.encoding.content_type())
+            .streaming(export::json_array(users));
+    }
+
+    #[cfg(feature = "row-encoding")]
+    if output.is_plain_json() && fields.is_all() && origin.is_none() && filter.is_none() {
+        return match data.users.all_json(scope).await {
+            Ok(body) => HttpResponse::Ok()
+                .insert_header((actix_web::http::header::ETAG, etag))
+                .content_type(output.encoding.content_type())
+                .body(body),
+            Err(e) => storage_error("Query", "Database query error", e),
+        };
+    }
+
+    let (total, all) = match &filter {
+        Some(filter) => match data.users.matching(scope, filter, None, 0, fields).await {
+            Ok(m)  => m,
+            Err(e) => return storage_error("Query", "Database query error", e),
+        },
+        None => match data.users.all(scope, fields).await {
+            Ok(u)  => (total, u),
+            Err(e) => return storage_error("Query", "Database query error", e),
+        },
+    };
+
+    let mut res = HttpResponse::Ok();
+    res.insert_header((actix_web::http::header::ETAG, etag));
+    if output.encoding.is_jsonapi() {
+        return Negotiated::new(output, jsonapi::collection(&all, total, fields)).respond(res);
+    }
+    if let Some(origin) = &origin {
+        return Negotiated::new(output, hal::users(&all, origin)).respond(res);
+    }
+    Negotiated::new(output, all).respond(res)
+}
+
+/// Query parameters for GET /users/age-histogram.
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct HistogramParams {
+    /// Number of buckets, clamped to 1..=100 (default 10)
+    buckets: Option<i32>,
+}
+
+/// GET /users/age-histogram?buckets=N  — users per age bucket (1 ≤ N ≤ 100,
+/// default 10), aggregated by the database in one query over the whole table.
+#[utoipa::path(tag = "users", params(HistogramParams), responses(
+    (status = 200, description = "Users per age bucket", body = AgeHistogram),
+    (status = 400, description = "Invalid query string", body = openapi::ErrorResponse),
+    (status = 500, description = "Database error", body = openapi::ErrorResponse),
+))]
+#[get("/users/age-histogram")]
+async fn age_histogram(
+    data: web::Data<AppState>,
+    query: web::Query<HistogramParams>,
+    output: Output,
+    tenant: Tenant,
+) -> impl Responder {
+    let buckets = query.buckets.unwrap_or(10).clamp(1, 100);
+
+    match data.users.age_histogram(tenant.scope(), buckets).await {
+        Ok(histogram) => Negotiated::new(output, histogram).respond(HttpResponse::Ok()),
+        Err(e) => storage_error("Query", "Database query error", e),
+    }
+}
+
+/// GET /users/:id  — returns a single user by primary key.
+#[utoipa::path(tag = "users", params(UserPath), responses(
+    (status = 200, description = "The user", body = User),
+    (status = 404, description = "No such user", body = openapi::ErrorResponse),
+    (status = 500, description = "Database error", body = openapi::ErrorResponse),
+))]
+#[get("/users/{id}")]
+async fn get_user_by_id(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    path: web::Path<UserId>,
+    projection: web::Query<FieldsParams>,
+    output: Output,
+    tenant: Tenant,
+) -> impl Responder {
+    let id = path.into_inner();
+    let fields = match Fields::parse(projection.list()) {
+        Ok(f) if output.encoding.is_jsonapi() => f.with_id(),
+        Ok(f) => f,
+        Err(msg) => return HttpResponse::BadRequest().json(serde_json::json!({ "error": msg })),
+    };
+
+    match data.users.by_id(tenant.scope(), id, fields).await {
+        Ok(Some(user)) if output.encoding.is_jsonapi() => Negotiated::new(output, jsonapi::resource(&user)).respond(HttpResponse::Ok()),
+        Ok(Some(user)) if data.config.hypermedia => {
+            let origin = hal::Origin::of(&req, &data.config.trusted_proxies);
+            Negotiated::new(output, hal::user(&user, &origin)).respond(HttpResponse::Ok())
+        }
+        Ok(Some(user)) => Negotiated::new(output, user).respond(HttpResponse::Ok()),
+        Ok(None) => {
+            HttpResponse::NotFound().json(serde_json::json!({ "error": "User not found" }))
+        }
+        Err(e) => storage_error("Query", "Database query error", e),
+    }
+}
+
+/// POST /users  — creates a user and returns 201 with the new object.
+/// Takes the body as JSON or as a URL-encoded form (see `form`).
+#[utoipa::path(tag = "users", request_body = CreateUser, responses(
+    (status = 201, description = "Created user", body = User),
+    (status = 400, description = "Invalid body", body = openapi::ErrorResponse),
+    (status = 409, description = "Email already exists", body = openapi::ErrorResponse),
+    (status = 500, description = "Database error", body = openapi::ErrorResponse),
+))]
+#[post("/users")]
+async fn create_user(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    body: form::Body<CreateUser>,
+    output: Output,
+    tenant: Tenant,
+) -> impl Responder {
+    let scope = Scope { actor: client_ip(&req), ..tenant.scope() };
+    match data.users.insert(scope, &body).await {
+        Ok(user) => {
+            if output.encoding.is_jsonapi() {
+                let mut res = HttpResponse::Created();
+                res.insert_header((header::LOCATION, format!("/users/{}", user.id)));
+                return Negotiated::new(output, jsonapi::resource(&Record::Full(user))).respond(res);
+            }
+            if data.config.hypermedia {
+                let origin = hal::Origin::of(&req, &data.config.trusted_proxies);
+                return Negotiated::new(output, hal::user(&Record::Full(user), &origin)).respond(HttpResponse::Created());
+            }
+            Negotiated::new(output, user).respond(HttpResponse::Created())
+        }
+        Err(RepoError::Conflict(e)) => {
+            eprintln!("Insert error: {e}");
+            HttpResponse::Conflict()
+                .json(serde_json::json!({ "error": "Email already exists" }))
+        }
+        Err(e) => storage_error("Insert", "Database insert error", e),
+    }
+}
+
+/// PUT /users/:id  — updates a user and returns the updated object, or 404.
+#[utoipa::path(tag = "users", params(UserPath), request_body = UpdateUser, responses(
+    (status = 200, description = "Updated user", body = User),
+    (status = 400, description = "Invalid or empty body", body = openapi::ErrorResponse),
+    (status = 404, description = "No such user", body = openapi::ErrorResponse),
+    (status = 409, description = "Email already in use", body = openapi::ErrorResponse),
+    (status = 500, description = "Database error", body = openapi::ErrorResponse),
+))]
+#[put("/users/{id}")]
+async fn update_user(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    path: web::Path<UserId>,
+    body: web::Json<UpdateUser>,
+    output: Output,
+    tenant: Tenant,
+) -> impl Responder {
+    let id = path.into_inner();
+
+    if body.name.is_none()
+        && body.email.is_none()
+        && body.age.is_none()
+        && body.tags.is_none()
+        && body.status.is_none()
+    {
+        return HttpResponse::BadRequest()
+            .json(serde_json::json!({ "error": "At least one field (name, email, age, tags, status) is required" }));
+    }
+
+    let scope = Scope { actor: client_ip(&req), ..tenant.scope() };
+    match data.users.update(scope, id, &body).await {
+        Ok(Some(user)) => {
+            if output.encoding.is_jsonapi() {
+                return Negotiated::new(output, jsonapi::resource(&Record::Full(user))).respond(HttpResponse::Ok());
+            }
+            if data.config.hypermedia {
+                let origin = hal::Origin::of(&req, &data.config.trusted_proxies);
+                return Negotiated::new(output, hal::user(&Record::Full(user), &origin)).respond(HttpResponse::Ok());
+            }
+            Negotiated::new(output, user).respond(HttpResponse::Ok())
+        }
+        Ok(None) => {
+            HttpResponse::NotFound().json(serde_json::json!({ "error": "User not found" }))
+        }
+        Err(RepoError::Conflict(_)) => {
+            HttpResponse::Conflict()
+                .json(serde_json::json!({ "error": "Email already in use" }))
+        }
+        Err(e) => storage_error("Update", "Database update error", e),
+    }
+}
+
+/// DELETE /users/:id  — removes a user and returns 204, or 404.
+#[utoipa::path(tag = "users", params(UserPath), responses(
+    (status = 204, description = "Deleted"),
+    (status = 404, description = "No such user", body = openapi::ErrorResponse),
+    (status = 500, description = "Database error", body = openapi::ErrorResponse),
+))]
+#[delete("/users/{id}")]
+async fn delete_user(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    path: web::Path<UserId>,
+    tenant: Tenant,
+) -> impl Responder {
+    let id = path.into_inner();
+
+    let scope = Scope { actor: client_ip(&req), ..tenant.scope() };
+    match data.users.delete(scope, id).await {
+        Ok(0) => {
+            HttpResponse::NotFound().json(serde_json::json!({ "error": "User not found" }))
+        }
+        Ok(_) => HttpResponse::NoContent().finish(),
+        Err(e) => storage_error("Delete", "Database delete error", e),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Application
+// ---------------------------------------------------------------------------
+
+/// A JSON 400 carrying the error message, for extractors that cannot
+/// deserialize the request (body, query string).
+pub fn bad_request<E: std::fmt::Debug + std::fmt::Display + 'static>(err: E) -> actix_web::Error {
+    let response = HttpResponse::BadRequest().json(serde_json::json!({ "error": err.to_string() }));
+    actix_web::error::InternalError::from_response(err, response).into()
+}
+
+/// Builds the application: shared state, middleware, extractor error handlers
+/// and routes.  Used by the TCP server, the HTTP/3 listener and the tests.
+pub fn app(
+    state: web::Data<AppState>,
+) -> App<
+    impl ServiceFactory<
+        ServiceRequest,
+        Config = (),
+        Response = ServiceResponse<impl MessageBody>,
+        Error = actix_web::Error,
+        InitError = (),
+    >,
+> {
+    let profiling = state.config.profiling;
+    let sessions = state.sessions.clone();
+    let jobs = state.config.job_workers > 0;
+    let scheduled = state.config.scheduler_interval_secs > 0;
+    let change_feed = state.config.events;
+    let webhooks = state.config.webhooks;
+    let static_payloads = !state.config.static_payload_sizes.is_empty();
+    let compress_bench = state.config.compress_bench;
+    let routing_bench = state.config.routing_bench;
+    let admin = state.config.admin_token.is_some();
+    App::new()
+        .app_data(state)
+        // Registered last = outermost: count the request on its connection,
+        // start the clocks, catch panics, shed, then resolve the client
+        // address, then start the deadline, then serve from the cache, then
+        // wait for the class's turn, then take the route's permit, then load
+        // the session, and only then scope the request's transaction.
+        .wrap(actix_web::middleware::from_fn(middleware::transaction::transaction))
+        .wrap(session::middleware(sessions.as_ref()))
+        .wrap(actix_web::middleware::from_fn(middleware::concurrency::route_concurrency))
+        .wrap(actix_web::middleware::from_fn(middleware::priority::priority))
+        .wrap(actix_web::middleware::from_fn(middleware::cache::response_cache))
+        .wrap(actix_web::middleware::from_fn(middleware::timeout::timeout))
+        .wrap(actix_web::middleware::from_fn(middleware::real_ip::real_ip))
+        .wrap(actix_web::middleware::from_fn(middleware::load_shed::load_shed))
+        .wrap(actix_web::middleware::from_fn(jsonapi::errors))
+        .wrap(actix_web::middleware::from_fn(middleware::catch_panic::catch_panic))
+        .wrap(actix_web::middleware::from_fn(timing::record))
+        .wrap(actix_web::middleware::from_fn(middleware::latency::latency))
+        .wrap(actix_web::middleware::from_fn(conn_stats::count))
+        // Return a proper JSON 400 when the request body cannot be deserialized.
+        .app_data(
+            web::JsonConfig::default().error_handler(|err, _req| bad_request(err)),
+        )
+        // Return a proper JSON 400 when query params cannot be deserialized.
+        .app_data(
+            web::QueryConfig::default().error_handler(|err, _req| bad_request(err)),
+        )
+        // And when a form-encoded body cannot be.
+        .app_data(
+            web::FormConfig::default().error_handler(|err, _req| bad_request(err)),
+        )
+        .service(index)
+        .service(config_endpoint)
+        .service(metrics::metrics_endpoint)
+        .configure(pool_stats::configure)
+        .configure(conn_stats::configure)
+        .service(json_endpoint)
+        .configure(headers::configure)
+        .configure(cookies::configure)
+        .configure(query_echo::configure)
+        .configure(routing::configure)
+        .configure(form::configure)
+        .service(db_endpoint)
+        .service(queries_endpoint)
+        .service(get_users)
+        // Before /users/{id}, which would otherwise claim /users/age-histogram,
+        // /users/export, /users/poll, /users/stats and /users/near.
+        .service(age_histogram)
+        .configure(export::configure)
+        .configure(poll::configure)
+        .configure(|cfg| {
+            if scheduled {
+                scheduler::configure(cfg);
+            }
+        })
+        .configure(|#[allow(unused_variables)] cfg| {
+            #[cfg(feature = "postgis")]
+            geo::configure(cfg);
+        })
+        .service(get_user_by_id)
+        .configure(metadata::configure)
+        .configure(transfer::configure)
+        .service(create_user)
+        .configure(bulk::configure)
+        .service(update_user)
+        .service(delete_user)
+        .configure(auth::configure)
+        .configure(openapi::configure)
+        .configure(|cfg| {
+            if change_feed {
+                events::configure(cfg);
+            }
+            if jobs {
+                jobs::configure(cfg);
+            }
+            if webhooks {
+                webhooks::configure(cfg);
+            }
+            if static_payloads {
+                payload::configure(cfg);
+            }
+            if compress_bench {
+                compress_bench::configure(cfg);
+            }
+            if sessions.is_some() {
+                session::configure(cfg);
+            }
+            if profiling {
+                profiling::configure(cfg);
+            }
+            if admin {
+                admin::configure(cfg);
+            }
+        })
+        // After every other route, so that only their own lookups walk the
+        // generated table.
+        .configure(|cfg| {
+            if routing_bench {
+                routing::configure_generated(cfg);
+            }
+        })
+}
+
+// ---------------------------------------------------------------------------
+// Server
+// ---------------------------------------------------------------------------
+
+/// Serves until shutdown; `main` runs it on the configured runtime.
+pub async fn run(config: Config) -> std::io::Result<()> {
+    let pool = db::build_pool(&config).expect("Failed to build database connection pool");
+
+    // Eagerly verify the pool works before binding the HTTP server.  Under
+    // docker-compose Postgres may still be starting, so retry with backoff.
+    db::wait_for_database(
+        &pool,
+        config.db_startup_retries,
+        Duration::from_secs(config.db_startup_timeout),
+    )
+    .await
+    .map_err(std::io::Error::other)?;
+    println!("Database connection pool ready ({}).", db::IMPLEMENTATION);
+
+    let profiling = config.profiling;
+    let runtime = config.runtime.clone();
+    let tcp = config.tcp.clone();
+    let bind_addr = format!("0.0.0.0:{}", config.port);
+    let shed = config.shed.clone();
+    let request_timeout_ms = config.request_timeout_ms;
+    let response_cache = config.response_cache;
+    let http3 = config.http3.clone();
+    let trusted_proxies = config.trusted_proxies.clone();
+    let (job_workers, job_poll_ms) = (config.job_workers, config.job_poll_ms);
+    let (scheduler_interval, scheduler_jitter) = (
+        Duration::from_secs(config.scheduler_interval_secs),
+        Duration::from_millis(config.scheduler_jitter_ms),
+    );
+    let session_store = config.session.store;
+    let state = web::Data::new(AppState::new(pool, config).await?);
+
+    println!("Starting Actix-web server on {bind_addr} (allocator: {ALLOCATOR})");
+    println!(
+        "CPUs: {}, workers: {}, max blocking threads/worker: {}",
+        runtime.cpus, runtime.workers, runtime.max_blocking_threads
+    );
+    println!(
+        "TCP_NODELAY: {}, backlog: {}, keep-alive: {}s, client timeout: {}ms",
+        tcp.tcp_nodelay, tcp.listen_backlog, tcp.keepalive_secs, tcp.client_timeout_ms
+    );
+    println!("Request timeout: {request_timeout_ms}ms");
+    let pool_stats_interval = Duration::from_millis(state.config.pool_stats_interval_ms);
+    println!("Pool stats sampled every {}ms at /pool-stats", pool_stats_interval.as_millis());
+    pool_stats::spawn(state.clone(), pool_stats_interval);
+    // Validation rejects POOL_AUTOSIZE with bb8.
+    #[cfg(not(feature = "bb8"))]
+    if state.config.pool_autosize.enabled {
+        let autosize = &state.config.pool_autosize;
+        println!(
+            "Pool autosize: {}..={} connections, target wait {}ms, decided every {}ms, {} left free in Postgres",
+            autosize.min, autosize.max, autosize.target_wait_ms, autosize.interval_ms, autosize.reserve
+        );
+        pool_autosize::spawn(state.clone());
+    }
+    let latency_summary_path = state.config.latency_summary_path.clone();
+    if let Some(path) = &latency_summary_path {
+        println!("Latency summary will be written to {path} on shutdown");
+    }
+    if state.config.admin_token.is_some() {
+        println!("Admin routes enabled at /admin (bearer token)");
+    }
+    if state.config.db_timing {
+        println!("DB timing enabled: per-endpoint request, query and pool-wait histograms on /metrics");
+    }
+    if response_cache {
+        println!("Response cache enabled");
+    }
+    if shed.max_in_flight > 0 || shed.p99_budget_ms > 0 {
+        println!(
+            "Load shedding: max in-flight: {}, p99 budget: {}ms over {}ms windows",
+            shed.max_in_flight, shed.p99_budget_ms, shed.window_ms
+        );
+    }
+    let route_concurrency = &state.config.route_concurrency;
+    if !route_concurrency.limits.is_empty() {
+        let limits: Vec<String> =
+            route_concurrency.limits.iter().map(|(route, permits)| format!("{route}={permits}")).collect();
+        println!(
+            "Route concurrency limits: {} (queue depth {})",
+            limits.join(", "),
+            route_concurrency.queue_depth
+        );
+    }
+    let priority = &state.config.priority;
+    if priority.slots > 0 {
+        let classify = format!("{:?}", priority.classify).to_lowercase();
+        println!(
+            "Priority scheduling: {} slot(s), classified by {classify}, read/write weights {}/{}",
+            priority.slots, priority.read_weight, priority.write_weight
+        );
+    }
+    if session_store != config::SessionBackend::Off {
+        let store = format!("{session_store:?}").to_lowercase();
+        println!("Sessions enabled ({store} store) at /session/visit-count");
+    }
+    if job_workers > 0 {
+        println!("Background jobs: {job_workers} worker(s), polling every {job_poll_ms}ms");
+        jobs::spawn_workers(state.clone(), job_workers, Duration::from_millis(job_poll_ms)).await;
+    }
+    if state.config.events {
+        events::spawn(state.clone());
+    }
+    if state.config.multi_tenant {
+        let scoping = format!("{:?}", state.config.tenant_scoping).to_lowercase();
+        match &state.config.tenant_domain {
+            Some(domain) => println!("Multi-tenant mode ({scoping}): X-Tenant-Id or <tenant>.{domain}"),
+            None => println!("Multi-tenant mode ({scoping}): X-Tenant-Id"),
+        }
+    }
+    if state.config.audit {
+        println!("Audit log enabled: /users mutations recorded in audit_log");
+    }
+    if state.config.transaction_per_request {
+        println!("Transaction per request: BEGIN on the first query, COMMIT after the response");
+    }
+    if state.config.webhooks {
+        println!(
+            "Webhooks enabled: up to {} delivery attempt(s)",
+            state.config.webhook_max_attempts
+        );
+        webhooks::spawn_dispatcher(state.clone()).await;
+    }
+    if state.config.compress_bench {
+        println!("Compression benchmark enabled: GET /compress-bench");
+    }
+    if state.config.routing_bench {
+        println!(
+            "Routing benchmark: {} generated routes, {} .. {}",
+            routing::GENERATED_ROUTES,
+            routing::FIRST_ROUTE,
+            routing::LAST_ROUTE
+        );
+    }
+    if state.config.hypermedia {
+        println!("Hypermedia enabled: /users responses carry absolute HAL _links");
+    }
+    if state.config.json_envelope || state.config.json_pretty {
+        println!(
+            "JSON output: envelope {}, pretty-print {}",
+            if state.config.json_envelope { "on" } else { "off" },
+            if state.config.json_pretty { "on" } else { "off" }
+        );
+    }
+    if state.config.users_streaming {
+        println!("GET /users streamed as a JSON array from a cursor ({} rows per chunk)", repo::EXPORT_BATCH);
+    }
+    if state.config.timestamp_format != TimestampFormat::Rfc3339 {
+        println!("Timestamps serialized as {:?}", state.config.timestamp_format);
+    }
+    match (state.config.random_rows, state.config.random_seed) {
+        (RandomRows::OrderByRandom, _) => println!("Random rows: ORDER BY RANDOM()"),
+        (RandomRows::IdRange, Some(seed)) => println!("Random rows: drawn ids by primary key (seed {seed})"),
+        (RandomRows::IdRange, None) => println!("Random rows: drawn ids by primary key"),
+    }
+    if !scheduler_interval.is_zero() {
+        println!(
+            "Scheduled refreshes every {}s (jitter up to {}ms)",
+            scheduler_interval.as_secs(),
+            scheduler_jitter.as_millis()
+        );
+        scheduler::spawn(state.clone(), scheduler_interval, scheduler_jitter);
+    }
+    if !trusted_proxies.is_empty() {
+        let list: Vec<String> = trusted_proxies.iter().map(ToString::to_string).collect();
+        println!("Trusting X-Forwarded-For/Forwarded from: {}", list.join(", "));
+    }
+    if profiling {
+        println!("Profiling endpoints enabled under /debug/pprof");
+    }
+
+    // The QUIC endpoint runs on the main system next to the TCP server.
+    #[cfg(feature = "http3")]
+    if http3.enabled {
+        http3::spawn(state.clone(), &http3)?;
+    }
+    let alt_svc = http3
+        .enabled
+        .then(|| format!("h3=\":{}\"; ma=86400", http3.port));
+
+    let conn_stats = state.conn_stats.clone();
+    let server_state = state.clone();
+    HttpServer::new(move || {
+        app(server_state.clone())
+            // Advertise the QUIC listener to HTTP/1.1 clients.
+            .wrap(Condition::new(
+                alt_svc.is_some(),
+                DefaultHeaders::new().add((header::ALT_SVC, alt_svc.clone().unwrap_or_default())),
+            ))
+    })
+    // Defaults to the CPUs the container may use; override with WORKERS.
+    .workers(runtime.workers)
+    .on_connect(conn_stats::on_connect(conn_stats))
+    .worker_max_blocking_threads(runtime.max_blocking_threads)
+    .tcp_nodelay(tcp.tcp_nodelay)
+    .backlog(tcp.listen_backlog)
+    .keep_alive(tcp.keep_alive())
+    .client_request_timeout(Duration::from_millis(tcp.client_timeout_ms))
+    // Treat a client's FIN as a disconnect so the in-flight handler is dropped
+    // and its query cancelled (see db::cancellable) instead of running on.
+    .h1_allow_half_closed(false)
+    // Graceful shutdown: wait up to 30 s for in-flight requests.
+    .shutdown_timeout(30)
+    .bind(&bind_addr)?
+    .run()
+    .await?;
+
+    if let Some(path) = latency_summary_path {
+        match state.latency.write_summary(&path) {
+            Ok(()) => println!("Latency summary written to {path}"),
+            Err(e) => eprintln!("Could not write latency summary to {path}: {e}"),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,193 @@
+//! Free-form attributes of a user: the `metadata JSONB` column.
+//!
+//! * `GET /users/:id/metadata` — `{"id", "metadata"}`;
+//! * `PATCH /users/:id/metadata` — a JSON object of changes keyed by
+//!   dot-separated paths, `{"plan": "pro", "limits.seats": 10, "trial": null}`,
+//!   applied by the database in one `UPDATE` as nested `jsonb_set(…, true)`
+//!   calls (`null` removes the key with `#-`), in key order.  As with
+//!   `jsonb_set`, a nested key is only created under an object that exists;
+//!   a key sorts before the paths under it, so `{"limits": {}, "limits.seats":
+//!   10}` creates the object and then fills it.  Answers with the resulting
+//!   metadata;
+//! * `GET /users?metadata.plan=pro` — only the users whose metadata contains
+//!   every such parameter (`metadata @> '{"plan": "pro"}'`, served by the GIN
+//!   index of `init.sql`; see `filter`).  A value is read as JSON when it
+//!   parses (`10`, `true`, `"10"`, `["a"]`) and as a string otherwise.
+//!
+//! The other APIs never read the column.  Like bulk writes, metadata patches
+//! are neither audited nor delivered to webhooks; they do move `updated_at`,
+//! and so the collection ETag.
+
+use actix_web::{get, patch, web, HttpResponse, Responder};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::key::UserId;
+use crate::negotiate::{Negotiated, Output};
+use crate::tenant::Tenant;
+use crate::{storage_error, AppState};
+
+/// Most changes in one PATCH.
+pub const MAX_CHANGES: usize = 32;
+/// Most segments in one path.
+pub const MAX_DEPTH: usize = 8;
+/// Query parameters with this prefix filter GET /users.
+const FILTER_PREFIX: &str = "metadata.";
+
+/// One change of a PATCH: `value` at `path`, or the key removed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    pub path: Vec<String>,
+    pub value: Option<Value>,
+}
+
+fn path(key: &str) -> Result<Vec<String>, String> {
+    let path: Vec<String> = key.split('.').map(str::to_owned).collect();
+    if path.iter().any(String::is_empty) {
+        return Err(format!("Invalid metadata path '{key}'"));
+    }
+    if path.len() > MAX_DEPTH {
+        return Err(format!("Metadata path '{key}' is deeper than {MAX_DEPTH}"));
+    }
+    Ok(path)
+}
+
+/// The changes of a PATCH body, in key order.
+pub fn changes(body: Map<String, Value>) -> Result<Vec<Change>, String> {
+    if body.is_empty() {
+        return Err("At least one metadata key is required".into());
+    }
+    if body.len() > MAX_CHANGES {
+        return Err(format!("At most {MAX_CHANGES} metadata keys per request"));
+    }
+    body.into_iter()
+        .map(|(key, value)| Ok(Change { path: path(&key)?, value: (!value.is_null()).then_some(value) }))
+        .collect()
+}
+
+/// The containment document of the `metadata.*` parameters of `query`;
+/// `None` without any.
+pub fn filter(query: &[(String, String)]) -> Result<Option<Value>, String> {
+    let mut filter = Map::new();
+    for (key, raw) in query {
+        let Some(key) = key.strip_prefix(FILTER_PREFIX) else {
+            continue;
+        };
+        let path = path(key)?;
+        let value = serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.clone()));
+        let (last, parents) = path.split_last().expect("paths are not empty");
+        let mut object = &mut filter;
+        for segment in parents {
+            let child = object.entry(segment.clone()).or_insert_with(|| Value::Object(Map::new()));
+            object = child.as_object_mut().ok_or_else(|| format!("Conflicting metadata filters on '{key}'"))?;
+        }
+        if object.insert(last.clone(), value).is_some() {
+            return Err(format!("Conflicting metadata filters on '{key}'"));
+        }
+    }
+    Ok((!filter.is_empty()).then_some(Value::Object(filter)))
+}
+
+/// `changes` applied to `doc` as the database applies them, for the
+/// in-memory repository.
+pub fn apply(doc: &mut Value, changes: &[Change]) {
+    for change in changes {
+        let (last, parents) = change.path.split_last().expect("paths are not empty");
+        let Some(parent) = parents.iter().try_fold(&mut *doc, |node, segment| child(node, segment)) else {
+            continue;
+        };
+        match (parent, &change.value) {
+            (Value::Object(object), Some(value)) => {
+                object.insert(last.clone(), value.clone());
+            }
+            (Value::Object(object), None) => {
+                object.remove(last);
+            }
+            (Value::Array(array), value) => {
+                let Ok(index) = last.parse::<usize>() else { continue };
+                match value {
+                    Some(value) if index < array.len() => array[index] = value.clone(),
+                    Some(value) => array.push(value.clone()),
+                    None if index < array.len() => {
+                        array.remove(index);
+                    }
+                    None => {}
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn child<'a>(node: &'a mut Value, segment: &str) -> Option<&'a mut Value> {
+    match node {
+        Value::Object(object) => object.get_mut(segment),
+        Value::Array(array) => array.get_mut(segment.parse::<usize>().ok()?),
+        _ => None,
+    }
+}
+
+/// `doc @> filter`, for the in-memory repository.
+pub fn contains(doc: &Value, filter: &Value) -> bool {
+    match (doc, filter) {
+        (Value::Object(doc), Value::Object(filter)) => {
+            filter.iter().all(|(key, wanted)| doc.get(key).is_some_and(|value| contains(value, wanted)))
+        }
+        (Value::Array(doc), Value::Array(filter)) => {
+            filter.iter().all(|wanted| doc.iter().any(|value| contains(value, wanted)))
+        }
+        // jsonb compares numbers by value: 10 = 10.0.
+        (Value::Number(a), Value::Number(b)) => a.as_f64() == b.as_f64(),
+        (doc, filter) => doc == filter,
+    }
+}
+
+/// Body of GET and PATCH /users/:id/metadata.
+#[derive(Serialize)]
+pub struct Metadata {
+    pub id: UserId,
+    pub metadata: Value,
+}
+
+/// GET /users/:id/metadata  — the user's metadata, or 404.
+#[get("/users/{id}/metadata")]
+async fn get_metadata(
+    data: web::Data<AppState>,
+    path: web::Path<UserId>,
+    output: Output,
+    tenant: Tenant,
+) -> impl Responder {
+    let id = path.into_inner();
+    match data.users.metadata(tenant.scope(), id).await {
+        Ok(Some(metadata)) => Negotiated::new(output, Metadata { id, metadata }).respond(HttpResponse::Ok()),
+        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({ "error": "User not found" })),
+        Err(e) => storage_error("Query", "Database query error", e),
+    }
+}
+
+/// PATCH /users/:id/metadata  — applies the changes and returns the
+/// resulting metadata, or 404.
+#[patch("/users/{id}/metadata")]
+async fn patch_metadata(
+    data: web::Data<AppState>,
+    path: web::Path<UserId>,
+    body: web::Json<Map<String, Value>>,
+    output: Output,
+    tenant: Tenant,
+) -> impl Responder {
+    let id = path.into_inner();
+    let changes = match changes(body.into_inner()) {
+        Ok(changes) => changes,
+        Err(msg) => return HttpResponse::BadRequest().json(serde_json::json!({ "error": msg })),
+    };
+
+    match data.users.patch_metadata(tenant.scope(), id, &changes).await {
+        Ok(Some(metadata)) => Negotiated::new(output, Metadata { id, metadata }).respond(HttpResponse::Ok()),
+        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({ "error": "User not found" })),
+        Err(e) => storage_error("Update", "Database update error", e),
+    }
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_metadata).service(patch_metadata);
+}
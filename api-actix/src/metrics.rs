@@ -0,0 +1,170 @@
+//! Process-wide counters exposed in Prometheus text format at GET /metrics.
+//!
+//! Plain atomics rather than a metrics library: the set is small, updates sit
+//! on the hot path of every request, and the exposition format is trivial.
+
+use actix_web::{get, web, HttpResponse, Responder};
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::AppState;
+
+#[derive(Default)]
+pub struct Metrics {
+    /// Requests currently being handled (gauge).
+    pub in_flight: AtomicU64,
+    /// Requests rejected with 503 by the load shedder.
+    pub shed_inflight_total: AtomicU64,
+    pub shed_latency_total: AtomicU64,
+    /// Requests answered with 504 after exceeding `REQUEST_TIMEOUT_MS`.
+    pub timed_out_total: AtomicU64,
+    /// Handler panics answered with 500 (`middleware::catch_panic`).
+    pub panics_total: AtomicU64,
+    /// Response cache lookups by outcome, and purges triggered by writes.
+    pub cache_hits_total: AtomicU64,
+    pub cache_misses_total: AtomicU64,
+    pub cache_bypass_total: AtomicU64,
+    pub cache_purges_total: AtomicU64,
+    /// Background jobs finished by outcome.
+    pub jobs_done_total: AtomicU64,
+    pub jobs_failed_total: AtomicU64,
+    /// Scheduled refresh runs by outcome (`scheduler`).
+    pub refreshes_ok_total: AtomicU64,
+    pub refreshes_failed_total: AtomicU64,
+    pub refreshes_skipped_total: AtomicU64,
+    /// Open GET /events streams (gauge) and change notifications received.
+    pub events_subscribers: AtomicU64,
+    pub events_published_total: AtomicU64,
+    /// Webhook delivery attempts by outcome (`webhooks`).
+    pub webhooks_delivered_total: AtomicU64,
+    pub webhooks_retried_total: AtomicU64,
+    pub webhooks_failed_total: AtomicU64,
+}
+
+impl Metrics {
+    pub fn inc(counter: &AtomicU64) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Renders every metric in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        gauge(&mut out, "http_requests_in_flight", "Requests currently being handled.", &self.in_flight);
+        counter_family(
+            &mut out,
+            "http_requests_shed_total",
+            "Requests rejected with 503 by the load shedder.",
+            "reason",
+            &[("in_flight", &self.shed_inflight_total), ("latency", &self.shed_latency_total)],
+        );
+        counter(
+            &mut out,
+            "http_requests_timed_out_total",
+            "Requests answered with 504 after exceeding the request timeout.",
+            &self.timed_out_total,
+        );
+        counter(
+            &mut out,
+            "http_panics_total",
+            "Handler panics caught and answered with 500.",
+            &self.panics_total,
+        );
+        counter_family(
+            &mut out,
+            "http_cache_requests_total",
+            "Response cache lookups by result.",
+            "result",
+            &[
+                ("hit", &self.cache_hits_total),
+                ("miss", &self.cache_misses_total),
+                ("bypass", &self.cache_bypass_total),
+            ],
+        );
+        counter(
+            &mut out,
+            "http_cache_purges_total",
+            "Response cache purges triggered by successful writes.",
+            &self.cache_purges_total,
+        );
+        counter_family(
+            &mut out,
+            "background_jobs_total",
+            "Background jobs finished by status.",
+            "status",
+            &[("done", &self.jobs_done_total), ("failed", &self.jobs_failed_total)],
+        );
+        counter_family(
+            &mut out,
+            "scheduled_refreshes_total",
+            "Scheduled refresh runs by result; skipped = previous run still in progress.",
+            "result",
+            &[
+                ("ok", &self.refreshes_ok_total),
+                ("error", &self.refreshes_failed_total),
+                ("skipped", &self.refreshes_skipped_total),
+            ],
+        );
+        gauge(&mut out, "sse_subscribers", "Open GET /events streams.", &self.events_subscribers);
+        counter(
+            &mut out,
+            "change_events_total",
+            "User change notifications received from Postgres.",
+            &self.events_published_total,
+        );
+        counter_family(
+            &mut out,
+            "webhook_deliveries_total",
+            "Webhook delivery attempts by result; failed = attempts exhausted.",
+            "result",
+            &[
+                ("delivered", &self.webhooks_delivered_total),
+                ("retry", &self.webhooks_retried_total),
+                ("failed", &self.webhooks_failed_total),
+            ],
+        );
+        out
+    }
+}
+
+/// A metric family with one series per label value: `value` reads a series
+/// from the labelled item.
+pub struct Family<F> {
+    pub name: &'static str,
+    pub kind: &'static str,
+    pub help: &'static str,
+    pub value: F,
+}
+
+pub fn gauge(out: &mut String, name: &str, help: &str, value: &AtomicU64) {
+    let _ = writeln!(out, "# HELP {name} {help}\n# TYPE {name} gauge");
+    let _ = writeln!(out, "{name} {}", value.load(Ordering::Relaxed));
+}
+
+pub fn counter(out: &mut String, name: &str, help: &str, value: &AtomicU64) {
+    let _ = writeln!(out, "# HELP {name} {help}\n# TYPE {name} counter");
+    let _ = writeln!(out, "{name} {}", value.load(Ordering::Relaxed));
+}
+
+pub fn counter_family(out: &mut String, name: &str, help: &str, label: &str, series: &[(&str, &AtomicU64)]) {
+    let _ = writeln!(out, "# HELP {name} {help}\n# TYPE {name} counter");
+    for (value, counter) in series {
+        let _ = writeln!(out, "{name}{{{label}=\"{value}\"}} {}", counter.load(Ordering::Relaxed));
+    }
+}
+
+/// GET /metrics
+#[get("/metrics")]
+async fn metrics_endpoint(data: web::Data<AppState>) -> impl Responder {
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body({
+            let mut body = data.metrics.render();
+            data.pool.stats().render(&mut body);
+            data.timing.render(&mut body);
+            data.conn_stats.render(&mut body);
+            data.route_limits.render(&mut body);
+            data.priority.render(&mut body);
+            crate::text::render(&mut body);
+            body
+        })
+}
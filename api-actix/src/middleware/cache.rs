@@ -0,0 +1,231 @@
+//! In-process response cache for idempotent GETs (`RESPONSE_CACHE=on`).
+//!
+//! Off by default: the standard scenarios measure the framework, not a cache.
+//! When enabled it models the common "framework + micro-cache" deployment:
+//!
+//! * only routes listed in [`POLICIES`] are cached, keyed by path + query
+//!   (prefixed by the tenant in multi-tenant mode, suffixed by the negotiated
+//!   representation, e.g. `#msgpack`, and by the origin when `HYPERMEDIA=on`
+//!   makes links absolute), and only `200` responses are stored;
+//! * responses carry `X-Cache: HIT|MISS` and `Cache-Control: max-age=<ttl>`;
+//! * a request with `Cache-Control: no-cache` or `no-store` bypasses the cache,
+//!   and a hit whose stored `ETag` matches `If-None-Match` is answered with 304;
+//! * a successful write (POST/PUT/PATCH/DELETE) purges every entry under the
+//!   same top-level path, so `/users` readers never see data older than the
+//!   last write through this instance.
+
+use actix_web::body::{self, BoxBody, EitherBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::error::ErrorInternalServerError;
+use actix_web::http::header::{self, HeaderMap, HeaderValue};
+use actix_web::http::{Method, StatusCode};
+use actix_web::middleware::Next;
+use actix_web::web::{self, Bytes};
+use actix_web::{Error, HttpResponse};
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+use crate::etag;
+use crate::hal;
+use crate::negotiate;
+use crate::metrics::Metrics;
+use crate::tenant;
+use crate::AppState;
+
+/// Cacheable routes and their TTLs; `*` matches a single path segment.
+const POLICIES: &[(&str, Duration)] = &[
+    ("/json", Duration::from_secs(60)),
+    ("/users", Duration::from_secs(1)),
+    ("/users/*", Duration::from_secs(5)),
+];
+
+/// Paths a policy matches that must never be cached: an export is streamed
+/// rather than buffered, and a long poll waits for what is new since the
+/// request, so a stored answer would end the wait.
+const EXCLUDED: &[&str] = &["/users/export", "/users/poll"];
+
+/// Upper bound on stored responses (`/users?page=N` alone is unbounded).
+const MAX_ENTRIES: usize = 10_000;
+
+struct Entry {
+    headers: HeaderMap,
+    body: Bytes,
+    expires: Instant,
+}
+
+#[derive(Default)]
+pub struct ResponseCache {
+    entries: RwLock<HashMap<String, Entry>>,
+}
+
+impl ResponseCache {
+    fn get(&self, key: &str) -> Option<(HeaderMap, Bytes, Duration)> {
+        let entries = self.entries.read().unwrap();
+        let entry = entries.get(key)?;
+        let ttl = entry.expires.checked_duration_since(Instant::now())?;
+        Some((entry.headers.clone(), entry.body.clone(), ttl))
+    }
+
+    fn insert(&self, key: String, entry: Entry) {
+        let mut entries = self.entries.write().unwrap();
+        if entries.len() >= MAX_ENTRIES {
+            let now = Instant::now();
+            entries.retain(|_, e| e.expires > now);
+            if entries.len() >= MAX_ENTRIES {
+                return;
+            }
+        }
+        entries.insert(key, entry);
+    }
+
+    /// Drops every entry, returning how many there were.
+    pub fn clear(&self) -> usize {
+        let mut entries = self.entries.write().unwrap();
+        let count = entries.len();
+        entries.clear();
+        count
+    }
+
+    /// Drops every entry, of every tenant, whose path falls under the
+    /// top-level `prefix`.
+    fn purge(&self, prefix: &str) {
+        self.entries.write().unwrap().retain(|key, _| {
+            let key = &key[key.find('/').unwrap_or(0)..];
+            top_level(key.split(['?', '#']).next().unwrap_or(key)) != prefix
+        });
+    }
+}
+
+/// Looks up the TTL for a path in [`POLICIES`].
+fn ttl_for(path: &str) -> Option<Duration> {
+    if EXCLUDED.contains(&path) {
+        return None;
+    }
+    POLICIES.iter().find_map(|(pattern, ttl)| {
+        let mut want = pattern.split('/');
+        let mut got = path.split('/');
+        loop {
+            match (want.next(), got.next()) {
+                (None, None) => return Some(*ttl),
+                (Some("*"), Some(seg)) if !seg.is_empty() => {}
+                (Some(w), Some(g)) if w == g => {}
+                _ => return None,
+            }
+        }
+    })
+}
+
+/// First path segment, e.g. `/users` for `/users/42`.
+fn top_level(path: &str) -> &str {
+    match path[1..].find('/') {
+        Some(i) => &path[..i + 1],
+        None => path,
+    }
+}
+
+fn bypass(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CACHE_CONTROL)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains("no-cache") || v.contains("no-store"))
+}
+
+fn cached_response(status: &'static str, headers: HeaderMap, body: Bytes, ttl: Duration) -> HttpResponse {
+    let mut res = HttpResponse::with_body(StatusCode::OK, body);
+    for (name, value) in headers.iter() {
+        res.headers_mut().append(name.clone(), value.clone());
+    }
+    res.headers_mut().insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_str(&format!("max-age={}", ttl.as_secs())).unwrap(),
+    );
+    res.headers_mut().insert(header::HeaderName::from_static("x-cache"), HeaderValue::from_static(status));
+    res.map_into_boxed_body()
+}
+
+pub async fn response_cache(
+    data: web::Data<AppState>,
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody, BoxBody>>, Error> {
+    if !data.config.response_cache {
+        return next.call(req).await.map(ServiceResponse::map_into_left_body);
+    }
+    let cache = &data.cache;
+    let metrics = &data.metrics;
+
+    if matches!(*req.method(), Method::POST | Method::PUT | Method::PATCH | Method::DELETE) {
+        let prefix = top_level(req.path()).to_owned();
+        let res = next.call(req).await?;
+        if res.status().is_success() {
+            cache.purge(&prefix);
+            Metrics::inc(&metrics.cache_purges_total);
+        }
+        return Ok(res.map_into_left_body());
+    }
+
+    let ttl = match ttl_for(req.path()) {
+        Some(ttl) if req.method() == Method::GET => ttl,
+        _ => return next.call(req).await.map(ServiceResponse::map_into_left_body),
+    };
+    if bypass(req.headers()) {
+        Metrics::inc(&metrics.cache_bypass_total);
+        return next.call(req).await.map(ServiceResponse::map_into_left_body);
+    }
+
+    // Tenant ids contain no '/', so the path still starts at the first one.
+    let tenant = match tenant::resolve(req.request(), &data.config) {
+        Ok(tenant) => tenant.unwrap_or_default(),
+        Err(_) => return next.call(req).await.map(ServiceResponse::map_into_left_body),
+    };
+    let representation = match negotiate::negotiate(req.headers()) {
+        Some(encoding) => encoding.cache_suffix(),
+        None => return next.call(req).await.map(ServiceResponse::map_into_left_body),
+    };
+    let mut key = match req.query_string() {
+        "" => format!("{tenant}{}{representation}", req.path()),
+        query => format!("{tenant}{}?{query}{representation}", req.path()),
+    };
+    if data.config.hypermedia {
+        key.push('#');
+        key.push_str(hal::Origin::of(req.request(), &data.config.trusted_proxies).as_str());
+    }
+    if let Some((headers, body, remaining)) = cache.get(&key) {
+        Metrics::inc(&metrics.cache_hits_total);
+        let current = headers.get(header::ETAG).and_then(|v| v.to_str().ok());
+        if current.is_some_and(|tag| etag::matches(req.headers(), tag)) {
+            let res = HttpResponse::NotModified()
+                .insert_header((header::ETAG, current.unwrap_or_default().to_owned()))
+                .finish();
+            return Ok(req.into_response(res).map_into_right_body());
+        }
+        let res = cached_response("HIT", headers, body, remaining);
+        return Ok(req.into_response(res).map_into_right_body());
+    }
+
+    Metrics::inc(&metrics.cache_misses_total);
+    let res = next.call(req).await?;
+    if res.status() != StatusCode::OK {
+        return Ok(res.map_into_left_body());
+    }
+
+    // Buffer the body so it can be both stored and sent.
+    let (http_req, res) = res.into_parts();
+    let (head, body) = res.into_parts();
+    let body = body::to_bytes(body)
+        .await
+        .map_err(|e| ErrorInternalServerError(Into::<Box<dyn std::error::Error>>::into(e).to_string()))?;
+    let headers = head.headers().clone();
+    cache.insert(
+        key,
+        Entry {
+            headers: headers.clone(),
+            body: body.clone(),
+            expires: Instant::now() + ttl,
+        },
+    );
+
+    let res = cached_response("MISS", headers, body, ttl);
+    Ok(ServiceResponse::new(http_req, res).map_into_right_body())
+}
@@ -0,0 +1,66 @@
+//! Turns a panicking handler into a 500 JSON response.
+//!
+//! Without this a panic unwinds through the worker, which resets the
+//! connection: under load it shows up in the client as a connection error
+//! with nothing to tie it to a request.  Here the unwind stops at the
+//! middleware, the client gets `{"error": "Internal server error"}`, the
+//! panic is counted in `http_panics_total` on /metrics and logged with the
+//! request line.  The panic hook set by [`install_hook`] logs the message,
+//! location and a backtrace whatever `RUST_BACKTRACE` says.
+//!
+//! Only works with unwinding panics (the default; the release profile does
+//! not set `panic = "abort"`).
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::error::InternalError;
+use actix_web::middleware::Next;
+use actix_web::{web, Error, HttpResponse};
+use futures_util::FutureExt;
+use std::any::Any;
+use std::backtrace::Backtrace;
+use std::panic::AssertUnwindSafe;
+
+use crate::metrics::Metrics;
+use crate::AppState;
+
+/// Replaces the default hook: logs every panic with its thread, location and
+/// a backtrace.  Set once in `main`, before any worker starts.
+pub fn install_hook() {
+    std::panic::set_hook(Box::new(|info| {
+        let thread = std::thread::current();
+        eprintln!(
+            "Panic in thread '{}': {info}\n{}",
+            thread.name().unwrap_or("<unnamed>"),
+            Backtrace::force_capture()
+        );
+    }));
+}
+
+fn message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("<non-string panic payload>")
+}
+
+pub async fn catch_panic(
+    data: web::Data<AppState>,
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    // `req` moves into the handler chain, as in `timeout`.
+    let (method, path) = (req.method().clone(), req.path().to_owned());
+    match AssertUnwindSafe(next.call(req)).catch_unwind().await {
+        Ok(res) => res,
+        Err(payload) => {
+            Metrics::inc(&data.metrics.panics_total);
+            eprintln!("Handler panicked: {method} {path}: {}", message(payload.as_ref()));
+            let response = HttpResponse::InternalServerError()
+                .json(serde_json::json!({ "error": "Internal server error" }));
+            // Rendered by the dispatcher; there is no request left to attach it to.
+            Err(InternalError::from_response("handler panicked", response).into())
+        }
+    }
+}
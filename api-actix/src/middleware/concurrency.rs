@@ -0,0 +1,153 @@
+//! Per-route concurrency limits (`ROUTE_CONCURRENCY`): at most N requests of
+//! a route pattern run at once, so a run can be measured at a bounded
+//! operating point instead of only at unbounded saturation.
+//!
+//! Each limited route has a semaphore with N permits.  A request that finds
+//! them all taken waits for one while fewer than `ROUTE_QUEUE_DEPTH` others of
+//! the route are waiting, and is otherwise answered with 429 at once.  The
+//! wait counts against `REQUEST_TIMEOUT_MS`, which wraps this middleware.
+//!
+//! Routes are keyed by pattern as registered (`/users/{id}`), for every
+//! method; unlisted routes are not limited.  Cache hits are served before a
+//! permit is taken.  /metrics carries, per limited route, the permits in use,
+//! the waiting requests (queue depth) and the 429s.
+
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use actix_web::{web, Error, HttpResponse};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::sync::Semaphore;
+
+use crate::metrics::Family;
+use crate::AppState;
+
+struct RouteLimit {
+    limit: usize,
+    permits: Semaphore,
+    /// Requests waiting for a permit (gauge).
+    waiting: AtomicU64,
+    /// Highest `waiting` seen since startup.
+    waiting_max: AtomicU64,
+    rejected_total: AtomicU64,
+}
+
+/// The limited routes, built once from the configuration.
+#[derive(Default)]
+pub struct RouteLimits {
+    routes: HashMap<String, RouteLimit>,
+    queue_depth: u64,
+}
+
+impl RouteLimits {
+    pub fn new(limits: &BTreeMap<String, usize>, queue_depth: u64) -> Self {
+        let routes = limits
+            .iter()
+            .map(|(route, limit)| {
+                let limit = RouteLimit {
+                    limit: *limit,
+                    permits: Semaphore::new(*limit),
+                    waiting: AtomicU64::new(0),
+                    waiting_max: AtomicU64::new(0),
+                    rejected_total: AtomicU64::new(0),
+                };
+                (route.clone(), limit)
+            })
+            .collect();
+        RouteLimits { routes, queue_depth }
+    }
+
+    /// Appends the per-route series to a /metrics body; nothing when no
+    /// route is limited.
+    pub fn render(&self, out: &mut String) {
+        if self.routes.is_empty() {
+            return;
+        }
+        let mut routes: Vec<(&String, &RouteLimit)> = self.routes.iter().collect();
+        routes.sort_unstable_by_key(|(route, _)| *route);
+
+        let families: [RouteFamily; 5] = [
+            Family {
+                name: "route_concurrency_limit",
+                kind: "gauge",
+                help: "Permits per limited route.",
+                value: |l| l.limit as u64,
+            },
+            Family {
+                name: "route_concurrency_in_use",
+                kind: "gauge",
+                help: "Permits currently held per limited route.",
+                value: |l| (l.limit - l.permits.available_permits()) as u64,
+            },
+            Family {
+                name: "route_concurrency_queue_depth",
+                kind: "gauge",
+                help: "Requests waiting for a permit per limited route.",
+                value: |l| l.waiting.load(Ordering::Relaxed),
+            },
+            Family {
+                name: "route_concurrency_queue_depth_max",
+                kind: "gauge",
+                help: "Most requests seen waiting per limited route.",
+                value: |l| l.waiting_max.load(Ordering::Relaxed),
+            },
+            Family {
+                name: "route_concurrency_rejected_total",
+                kind: "counter",
+                help: "Requests answered with 429 per limited route.",
+                value: |l| l.rejected_total.load(Ordering::Relaxed),
+            },
+        ];
+        for Family { name, kind, help, value } in families {
+            let _ = writeln!(out, "# HELP {name} {help}\n# TYPE {name} {kind}");
+            for (route, limit) in &routes {
+                let _ = writeln!(out, "{name}{{route=\"{route}\"}} {}", value(limit));
+            }
+        }
+    }
+}
+
+/// A per-route series of `/metrics`, read from the route's limit.
+type RouteFamily = Family<fn(&RouteLimit) -> u64>;
+
+/// Decrements the queue gauge even when the waiting request is dropped.
+struct Waiting<'a>(&'a AtomicU64);
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+pub async fn route_concurrency(
+    data: web::Data<AppState>,
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    let limits = &data.route_limits;
+    let limit = match req.match_pattern().and_then(|route| limits.routes.get(&route)) {
+        Some(limit) => limit,
+        None => return next.call(req).await.map(ServiceResponse::map_into_left_body),
+    };
+
+    let _permit = match limit.permits.try_acquire() {
+        Ok(permit) => permit,
+        Err(_) => {
+            let waiting = limit.waiting.fetch_add(1, Ordering::Relaxed) + 1;
+            let _waiting = Waiting(&limit.waiting);
+            if waiting > limits.queue_depth {
+                limit.rejected_total.fetch_add(1, Ordering::Relaxed);
+                let response = HttpResponse::TooManyRequests()
+                    .insert_header(("Retry-After", "1"))
+                    .json(serde_json::json!({ "error": "Too many concurrent requests for this route" }));
+                return Ok(req.into_response(response).map_into_right_body());
+            }
+            limit.waiting_max.fetch_max(waiting, Ordering::Relaxed);
+            // Never closed, so acquiring can only succeed.
+            limit.permits.acquire().await.expect("route semaphore is never closed")
+        }
+    };
+    next.call(req).await.map(ServiceResponse::map_into_left_body)
+}
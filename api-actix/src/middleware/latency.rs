@@ -0,0 +1,167 @@
+//! Server-side latency record (`LATENCY_SUMMARY_PATH`): an HDR histogram per
+//! route, written as a JSON percentile summary when the server shuts down.
+//!
+//! The load generator's own percentiles suffer from coordinated omission: a
+//! stalled connection stops sending, so the slow period is under-sampled.
+//! This records every request the server answered, from the outermost
+//! middleware to the response, at 3 significant digits between 1 µs and 60 s.
+//!
+//! Each worker thread records into its own shard, so the hot path takes only
+//! an uncontended lock; the shards are merged when the summary is written,
+//! after graceful shutdown has drained the in-flight requests.  Requests are
+//! keyed by method and route pattern (`/users/{id}`); unrouted ones fall under
+//! `unmatched`, and errors rendered by the server (the 504 from
+//! `REQUEST_TIMEOUT_MS`) under `error`, since their route is no longer known.
+//! A histogram takes ~140 KB per route and worker, so this is off unless a
+//! path is set.
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::Method;
+use actix_web::middleware::Next;
+use actix_web::{web, Error};
+use chrono::{DateTime, Utc};
+use hdrhistogram::Histogram;
+use serde::Serialize;
+use std::cell::OnceCell;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use crate::AppState;
+
+type Shard = HashMap<(Method, String), Histogram<u64>>;
+
+thread_local! {
+    /// This thread's shard of the process-wide [`LatencyLog`].
+    static SHARD: OnceCell<Arc<Mutex<Shard>>> = const { OnceCell::new() };
+}
+
+fn histogram() -> Histogram<u64> {
+    Histogram::new_with_bounds(1, 60_000_000, 3).expect("valid histogram bounds")
+}
+
+/// Every shard, one per thread that recorded a request.
+pub struct LatencyLog {
+    started_at: DateTime<Utc>,
+    shards: Mutex<Vec<Arc<Mutex<Shard>>>>,
+}
+
+impl Default for LatencyLog {
+    fn default() -> Self {
+        LatencyLog { started_at: Utc::now(), shards: Mutex::default() }
+    }
+}
+
+impl LatencyLog {
+    fn record(&self, key: (Method, String), micros: u64) {
+        SHARD.with(|cell| {
+            let shard = cell.get_or_init(|| {
+                let shard = Arc::<Mutex<Shard>>::default();
+                self.shards.lock().unwrap_or_else(|e| e.into_inner()).push(shard.clone());
+                shard
+            });
+            let mut shard = shard.lock().unwrap_or_else(|e| e.into_inner());
+            shard.entry(key).or_insert_with(histogram).saturating_record(micros);
+        });
+    }
+
+    /// Merges the shards and writes the summary to `path`.
+    pub fn write_summary(&self, path: &str) -> std::io::Result<()> {
+        let mut routes: HashMap<(Method, String), Histogram<u64>> = HashMap::new();
+        for shard in self.shards.lock().unwrap_or_else(|e| e.into_inner()).iter() {
+            for (key, hist) in shard.lock().unwrap_or_else(|e| e.into_inner()).iter() {
+                // Same bounds everywhere, so adding cannot fail.
+                let _ = routes.entry(key.clone()).or_insert_with(histogram).add(hist);
+            }
+        }
+        let mut all = histogram();
+        let mut summaries: Vec<RouteSummary> = routes
+            .iter()
+            .map(|((method, route), hist)| {
+                let _ = all.add(hist);
+                RouteSummary { method: method.as_str(), route, stats: Percentiles::of(hist) }
+            })
+            .collect();
+        summaries.sort_unstable_by(|a, b| (a.route, a.method).cmp(&(b.route, b.method)));
+
+        let summary = Summary {
+            unit: "us",
+            started_at: self.started_at,
+            written_at: Utc::now(),
+            all: Percentiles::of(&all),
+            routes: summaries,
+        };
+        let json = serde_json::to_vec_pretty(&summary).map_err(std::io::Error::other)?;
+        std::fs::write(path, json)
+    }
+}
+
+#[derive(Serialize)]
+struct Percentiles {
+    count: u64,
+    min: u64,
+    mean: f64,
+    p50: u64,
+    p90: u64,
+    p99: u64,
+    #[serde(rename = "p99.9")]
+    p99_9: u64,
+    #[serde(rename = "p99.99")]
+    p99_99: u64,
+    max: u64,
+}
+
+impl Percentiles {
+    fn of(hist: &Histogram<u64>) -> Percentiles {
+        Percentiles {
+            count: hist.len(),
+            min: hist.min(),
+            mean: (hist.mean() * 10.0).round() / 10.0,
+            p50: hist.value_at_quantile(0.5),
+            p90: hist.value_at_quantile(0.9),
+            p99: hist.value_at_quantile(0.99),
+            p99_9: hist.value_at_quantile(0.999),
+            p99_99: hist.value_at_quantile(0.9999),
+            max: hist.max(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct RouteSummary<'a> {
+    method: &'a str,
+    route: &'a str,
+    #[serde(flatten)]
+    stats: Percentiles,
+}
+
+#[derive(Serialize)]
+struct Summary<'a> {
+    unit: &'static str,
+    started_at: DateTime<Utc>,
+    written_at: DateTime<Utc>,
+    all: Percentiles,
+    routes: Vec<RouteSummary<'a>>,
+}
+
+pub async fn latency(
+    data: web::Data<AppState>,
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    if data.config.latency_summary_path.is_none() {
+        return next.call(req).await;
+    }
+
+    let method = req.method().clone();
+    let started = Instant::now();
+    let res = next.call(req).await;
+    let micros = u64::try_from(started.elapsed().as_micros()).unwrap_or(u64::MAX);
+    let route = match &res {
+        Ok(res) => res.request().match_pattern().unwrap_or_else(|| "unmatched".to_owned()),
+        Err(_) => "error".to_owned(),
+    };
+    data.latency.record((method, route), micros);
+    res
+}
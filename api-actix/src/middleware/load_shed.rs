@@ -0,0 +1,171 @@
+//! Load shedding: reject with 503 instead of queueing once the service is
+//! saturated, so latency stays bounded for the requests that are admitted.
+//!
+//! Two independent triggers, each disabled when its setting is 0:
+//!
+//! * `SHED_MAX_INFLIGHT`  — more than N requests already in progress;
+//! * `SHED_P99_BUDGET_MS` — the p99 latency of the previous window
+//!   (`SHED_WINDOW_MS`, default 1 s) exceeded the budget.  The next window is
+//!   then shed entirely, after which traffic is admitted and measured again.
+//!
+//! `/metrics`, `/pool-stats`, `/conn-stats` and `/config` are never shed so
+//! overload stays observable.  A `/users/poll` counts as in flight while it
+//! waits, but its duration is not a latency sample: the wait is the point.
+
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use actix_web::{web, Error, HttpResponse};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::metrics::Metrics;
+use crate::AppState;
+
+/// Sub-buckets per power of two in the latency histogram (~12% resolution).
+const SUB_BUCKETS: usize = 8;
+const BUCKETS: usize = 64 * SUB_BUCKETS;
+
+pub struct LoadShedder {
+    max_in_flight: u64,
+    p99_budget_us: u64,
+    window: Duration,
+    started: Instant,
+    /// Start of the current window, in µs since `started`.
+    window_start_us: AtomicU64,
+    /// Latency histogram of the current window, in µs.
+    buckets: Box<[AtomicU64]>,
+    /// p99 of the last completed window, in µs.
+    last_p99_us: AtomicU64,
+}
+
+impl LoadShedder {
+    pub fn new(max_in_flight: u64, p99_budget_ms: u64, window_ms: u64) -> Self {
+        LoadShedder {
+            max_in_flight,
+            p99_budget_us: p99_budget_ms * 1000,
+            window: Duration::from_millis(window_ms.max(1)),
+            started: Instant::now(),
+            window_start_us: AtomicU64::new(0),
+            buckets: (0..BUCKETS).map(|_| AtomicU64::new(0)).collect(),
+            last_p99_us: AtomicU64::new(0),
+        }
+    }
+
+    fn record(&self, latency: Duration) {
+        let us = u64::try_from(latency.as_micros()).unwrap_or(u64::MAX);
+        self.buckets[bucket_index(us)].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Closes the current window once it is older than `window`, publishing its
+    /// p99.  A window spent shedding records nothing and therefore publishes 0,
+    /// which re-admits traffic for the next window.
+    fn rollover_if_due(&self) {
+        let now_us = u64::try_from(self.started.elapsed().as_micros()).unwrap_or(u64::MAX);
+        let start_us = self.window_start_us.load(Ordering::Relaxed);
+        if now_us.saturating_sub(start_us) < self.window.as_micros() as u64 {
+            return;
+        }
+        // One thread wins the rollover; concurrent records may land in either window.
+        if self
+            .window_start_us
+            .compare_exchange(start_us, now_us, Ordering::AcqRel, Ordering::Relaxed)
+            .is_ok()
+        {
+            let counts: Vec<u64> = self.buckets.iter().map(|b| b.swap(0, Ordering::Relaxed)).collect();
+            self.last_p99_us.store(percentile(&counts, 0.99), Ordering::Relaxed);
+        }
+    }
+
+    fn over_latency_budget(&self) -> bool {
+        self.p99_budget_us > 0 && self.last_p99_us.load(Ordering::Relaxed) > self.p99_budget_us
+    }
+}
+
+/// Maps a value to a log-linear bucket: 8 sub-buckets per power of two.
+fn bucket_index(us: u64) -> usize {
+    if us < SUB_BUCKETS as u64 {
+        return us as usize;
+    }
+    let octave = 63 - us.leading_zeros() as usize; // ≥ 3
+    let sub = ((us >> (octave - 3)) & (SUB_BUCKETS as u64 - 1)) as usize;
+    (octave - 2) * SUB_BUCKETS + sub
+}
+
+/// Upper bound of a bucket, the inverse of [`bucket_index`].
+fn bucket_upper(index: usize) -> u64 {
+    if index < SUB_BUCKETS {
+        return index as u64;
+    }
+    let octave = index / SUB_BUCKETS + 2;
+    let sub = (index % SUB_BUCKETS) as u64;
+    ((SUB_BUCKETS as u64 + sub + 1) << (octave - 3)) - 1
+}
+
+fn percentile(counts: &[u64], q: f64) -> u64 {
+    let total: u64 = counts.iter().sum();
+    if total == 0 {
+        return 0;
+    }
+    let rank = (total as f64 * q).ceil() as u64;
+    let mut seen = 0;
+    for (i, &c) in counts.iter().enumerate() {
+        seen += c;
+        if seen >= rank {
+            return bucket_upper(i);
+        }
+    }
+    bucket_upper(counts.len() - 1)
+}
+
+/// Decrements the in-flight gauge even when the handler future is dropped.
+struct InFlight<'a>(&'a AtomicU64);
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+pub async fn load_shed(
+    data: web::Data<AppState>,
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    if matches!(req.path(), "/metrics" | "/pool-stats" | "/conn-stats" | "/config") {
+        return next.call(req).await.map(ServiceResponse::map_into_left_body);
+    }
+
+    let shedder = &data.shedder;
+    let metrics = &data.metrics;
+
+    let in_flight = metrics.in_flight.fetch_add(1, Ordering::Relaxed) + 1;
+    let _guard = InFlight(&metrics.in_flight);
+
+    if shedder.p99_budget_us > 0 {
+        shedder.rollover_if_due();
+    }
+
+    let reason = if shedder.max_in_flight > 0 && in_flight > shedder.max_in_flight {
+        Some(&metrics.shed_inflight_total)
+    } else if shedder.over_latency_budget() {
+        Some(&metrics.shed_latency_total)
+    } else {
+        None
+    };
+    if let Some(counter) = reason {
+        Metrics::inc(counter);
+        let response = HttpResponse::ServiceUnavailable()
+            .insert_header(("Retry-After", "1"))
+            .json(serde_json::json!({ "error": "Server overloaded" }));
+        return Ok(req.into_response(response).map_into_right_body());
+    }
+
+    let sampled = req.path() != "/users/poll";
+    let started = Instant::now();
+    let res = next.call(req).await;
+    if shedder.p99_budget_us > 0 && sampled {
+        shedder.record(started.elapsed());
+    }
+    res.map(ServiceResponse::map_into_left_body)
+}
@@ -0,0 +1,11 @@
+//! Request middleware, registered in `main` with `middleware::from_fn`.
+
+pub mod cache;
+pub mod catch_panic;
+pub mod concurrency;
+pub mod latency;
+pub mod load_shed;
+pub mod priority;
+pub mod real_ip;
+pub mod timeout;
+pub mod transaction;
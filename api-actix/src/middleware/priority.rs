@@ -0,0 +1,223 @@
+//! Priority scheduling experiment (`PRIORITY_SLOTS`): requests are classified
+//! as reads or writes and run through a fixed number of slots, handed out from
+//! one queue per class by weighted round robin, so a write storm cannot starve
+//! the reads (or the other way round).
+//!
+//! * `PRIORITY_CLASSIFY=method` — GET, HEAD and OPTIONS are reads, the rest
+//!   writes; `header` takes the class from `X-Request-Class: read|write` and
+//!   falls back to the method when the header is missing or unknown;
+//! * `PRIORITY_READ_WEIGHT` / `PRIORITY_WRITE_WEIGHT` — slots handed to each
+//!   class per round while both have requests waiting (default 4 / 1).
+//!
+//! A request takes a free slot at once only while nobody is queued, so the
+//! weights decide every hand-over under contention.  The wait counts against
+//! `REQUEST_TIMEOUT_MS`; a request dropped while queued is skipped, or gives
+//! its slot back if it had just been handed one.  /metrics carries, per class,
+//! the queue depth, the time spent queued and the whole request duration.
+//! `/metrics`, `/pool-stats`, `/conn-stats` and `/config` are not scheduled.
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::Method;
+use actix_web::middleware::Next;
+use actix_web::{web, Error};
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Instant;
+use tokio::sync::oneshot;
+
+use crate::config::{PrioritySettings, RequestClass};
+use crate::metrics::Family;
+use crate::timing::{micros, Histogram};
+use crate::AppState;
+
+const CLASSES: [&str; 2] = ["read", "write"];
+const READ: usize = 0;
+const WRITE: usize = 1;
+
+fn classify(req: &ServiceRequest, by: RequestClass) -> usize {
+    if by == RequestClass::Header {
+        match req.headers().get("x-request-class").and_then(|v| v.to_str().ok()) {
+            Some(v) if v.eq_ignore_ascii_case("read") => return READ,
+            Some(v) if v.eq_ignore_ascii_case("write") => return WRITE,
+            _ => {}
+        }
+    }
+    match *req.method() {
+        Method::GET | Method::HEAD | Method::OPTIONS => READ,
+        _ => WRITE,
+    }
+}
+
+struct Queues {
+    in_use: u64,
+    waiting: [VecDeque<oneshot::Sender<()>>; 2],
+    /// Hand-overs left to each class in the current round.
+    credit: [u32; 2],
+}
+
+#[derive(Default)]
+struct ClassStats {
+    wait: Histogram,
+    total: Histogram,
+}
+
+pub struct PriorityScheduler {
+    slots: u64,
+    classify: RequestClass,
+    weights: [u32; 2],
+    queues: Mutex<Queues>,
+    stats: [ClassStats; 2],
+}
+
+impl PriorityScheduler {
+    pub fn new(settings: &PrioritySettings) -> Self {
+        let weights = [settings.read_weight, settings.write_weight];
+        PriorityScheduler {
+            slots: settings.slots,
+            classify: settings.classify,
+            weights,
+            queues: Mutex::new(Queues { in_use: 0, waiting: Default::default(), credit: weights }),
+            stats: Default::default(),
+        }
+    }
+
+    fn queues(&self) -> std::sync::MutexGuard<'_, Queues> {
+        self.queues.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Waits for a slot; the slot is released when the returned guard drops.
+    async fn acquire(&self, class: usize) -> Slot<'_> {
+        let rx = {
+            let mut queues = self.queues();
+            if queues.in_use < self.slots && queues.waiting.iter().all(VecDeque::is_empty) {
+                queues.in_use += 1;
+                return Slot(self);
+            }
+            let (tx, rx) = oneshot::channel();
+            queues.waiting[class].push_back(tx);
+            rx
+        };
+        let mut waiter = Waiter { scheduler: self, rx };
+        // Senders are only dropped by sending, so the wait cannot fail.
+        let _ = (&mut waiter.rx).await;
+        Slot(self)
+    }
+
+    /// Hands the slot to the next waiter by weighted round robin, or frees it.
+    fn release(&self) {
+        let mut queues = self.queues();
+        while let Some(class) = self.next_class(&mut queues) {
+            let tx = queues.waiting[class].pop_front().expect("next_class picks a non-empty queue");
+            if tx.send(()).is_ok() {
+                return;
+            }
+        }
+        queues.in_use -= 1;
+    }
+
+    fn next_class(&self, queues: &mut Queues) -> Option<usize> {
+        for _ in 0..2 {
+            for class in [READ, WRITE] {
+                if queues.credit[class] > 0 && !queues.waiting[class].is_empty() {
+                    queues.credit[class] -= 1;
+                    return Some(class);
+                }
+            }
+            queues.credit = self.weights;
+        }
+        None
+    }
+
+    /// Appends the per-class series to a /metrics body; nothing when
+    /// scheduling is off.
+    pub fn render(&self, out: &mut String) {
+        if self.slots == 0 {
+            return;
+        }
+        let (in_use, depths) = {
+            let queues = self.queues();
+            (queues.in_use, [queues.waiting[READ].len(), queues.waiting[WRITE].len()])
+        };
+        let _ = writeln!(
+            out,
+            "# HELP priority_slots_in_use Scheduler slots held by requests.\n\
+             # TYPE priority_slots_in_use gauge\npriority_slots_in_use {in_use}"
+        );
+        let _ = writeln!(
+            out,
+            "# HELP priority_queue_depth Requests queued per class.\n# TYPE priority_queue_depth gauge"
+        );
+        for (class, depth) in CLASSES.iter().zip(depths) {
+            let _ = writeln!(out, "priority_queue_depth{{class=\"{class}\"}} {depth}");
+        }
+        let families: [ClassFamily; 2] = [
+            Family {
+                name: "priority_wait_duration_seconds",
+                kind: "histogram",
+                help: "Time queued for a slot per class.",
+                value: |s| &s.wait,
+            },
+            Family {
+                name: "priority_request_duration_seconds",
+                kind: "histogram",
+                help: "Request duration, wait included, per class.",
+                value: |s| &s.total,
+            },
+        ];
+        for Family { name, kind, help, value: histogram } in families {
+            let _ = writeln!(out, "# HELP {name} {help}\n# TYPE {name} {kind}");
+            for (class, stats) in CLASSES.iter().zip(&self.stats) {
+                histogram(stats).render(out, name, &format!("class=\"{class}\""));
+            }
+        }
+    }
+}
+
+/// A per-class histogram of `/metrics`.
+type ClassFamily = Family<fn(&ClassStats) -> &Histogram>;
+
+/// A held slot, handed on or freed when dropped.
+struct Slot<'a>(&'a PriorityScheduler);
+
+impl Drop for Slot<'_> {
+    fn drop(&mut self) {
+        self.0.release();
+    }
+}
+
+/// A queued request; if it is dropped right after being handed a slot, the
+/// slot goes to the next waiter instead of leaking.
+struct Waiter<'a> {
+    scheduler: &'a PriorityScheduler,
+    rx: oneshot::Receiver<()>,
+}
+
+impl Drop for Waiter<'_> {
+    fn drop(&mut self) {
+        if self.rx.try_recv().is_ok() {
+            self.scheduler.release();
+        }
+    }
+}
+
+pub async fn priority(
+    data: web::Data<AppState>,
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let scheduler = &data.priority;
+    if scheduler.slots == 0 || matches!(req.path(), "/metrics" | "/pool-stats" | "/conn-stats" | "/config") {
+        return next.call(req).await;
+    }
+
+    let class = classify(&req, scheduler.classify);
+    let started = Instant::now();
+    let _slot = scheduler.acquire(class).await;
+    let stats = &scheduler.stats[class];
+    stats.wait.observe(micros(started.elapsed()));
+    let res = next.call(req).await;
+    stats.total.observe(micros(started.elapsed()));
+    res
+}
@@ -0,0 +1,159 @@
+//! Client address behind reverse proxies (`TRUSTED_PROXIES`).
+//!
+//! When the TCP peer is a trusted proxy the client is taken from `Forwarded`
+//! (RFC 7239) or, failing that, `X-Forwarded-For`: the chain is walked from the
+//! right, skipping trusted hops, and the first untrusted address wins.  Entries
+//! to its left were supplied by the client and prove nothing.  Otherwise the
+//! peer itself is the client, so with no trusted proxies (the default) the
+//! headers are ignored.
+//!
+//! The result is stored as [`ClientIp`] in the request extensions; logs and
+//! per-client policies such as rate limits should key on it, not on the peer.
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{self, HeaderMap};
+use actix_web::middleware::Next;
+use actix_web::{web, Error, HttpMessage};
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+
+use crate::AppState;
+
+/// Resolved client address of the current request.
+#[derive(Debug, Clone, Copy)]
+pub struct ClientIp(pub IpAddr);
+
+/// Client address stored by [`real_ip`], if the request went through it.
+pub fn client_ip(req: &impl HttpMessage) -> Option<IpAddr> {
+    req.extensions().get::<ClientIp>().map(|c| c.0)
+}
+
+pub async fn real_ip(
+    data: web::Data<AppState>,
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    if let Some(peer) = req.peer_addr() {
+        let ip = resolve(peer.ip().to_canonical(), req.headers(), &data.config.trusted_proxies);
+        req.extensions_mut().insert(ClientIp(ip));
+    }
+    next.call(req).await
+}
+
+fn resolve(peer: IpAddr, headers: &HeaderMap, trusted: &[Cidr]) -> IpAddr {
+    let is_trusted = |ip: IpAddr| trusted.iter().any(|net| net.contains(ip));
+    if !is_trusted(peer) {
+        return peer;
+    }
+
+    let mut chain = forwarded_for(headers);
+    if chain.is_empty() {
+        chain = x_forwarded_for(headers);
+    }
+    for hop in chain.iter().rev() {
+        match hop {
+            Some(ip) if is_trusted(*ip) => continue,
+            Some(ip) => return *ip,
+            // "unknown" or an obfuscated identifier: nothing further left can
+            // be attributed, so fall back to the proxy itself.
+            None => return peer,
+        }
+    }
+    // Every hop is a trusted proxy: the leftmost is as close as we get.
+    chain.first().copied().flatten().unwrap_or(peer)
+}
+
+/// `for=` parameters of every `Forwarded` element, in order.
+fn forwarded_for(headers: &HeaderMap) -> Vec<Option<IpAddr>> {
+    headers
+        .get_all(header::FORWARDED)
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .filter_map(|element| {
+            element.split(';').find_map(|pair| {
+                let (key, value) = pair.split_once('=')?;
+                key.trim().eq_ignore_ascii_case("for").then(|| parse_node(value))
+            })
+        })
+        .collect()
+}
+
+fn x_forwarded_for(headers: &HeaderMap) -> Vec<Option<IpAddr>> {
+    headers
+        .get_all("x-forwarded-for")
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(parse_node)
+        .collect()
+}
+
+/// Parses a node: `192.0.2.1`, `"192.0.2.1:4711"`, `"[2001:db8::1]:4711"`,
+/// or a bare IPv6 address.
+fn parse_node(node: &str) -> Option<IpAddr> {
+    let node = node.trim().trim_matches('"');
+    if let Ok(ip) = node.parse::<IpAddr>() {
+        return Some(ip.to_canonical());
+    }
+    if let Ok(addr) = node.parse::<SocketAddr>() {
+        return Some(addr.ip().to_canonical());
+    }
+    let inner = node.strip_prefix('[')?.split(']').next()?;
+    inner.parse::<IpAddr>().ok()
+}
+
+/// An address block such as `10.0.0.0/8` or `::1/128`; a bare address is a
+/// single host.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cidr {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl Cidr {
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        let (net, ip, bits) = match (self.addr, ip) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => (u32::from(net) as u128, u32::from(ip) as u128, 32),
+            (IpAddr::V6(net), IpAddr::V6(ip)) => (u128::from(net), u128::from(ip), 128),
+            _ => return false,
+        };
+        self.prefix == 0 || (net ^ ip) >> (bits - u32::from(self.prefix)) == 0
+    }
+}
+
+impl FromStr for Cidr {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Cidr, String> {
+        let (addr, prefix) = s.split_once('/').map_or((s, None), |(a, p)| (a, Some(p)));
+        let addr: IpAddr = addr
+            .trim()
+            .parse()
+            .map_err(|_| format!("`{s}` is not an IP address or CIDR block"))?;
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            None => max,
+            Some(p) => p
+                .trim()
+                .parse::<u8>()
+                .ok()
+                .filter(|p| *p <= max)
+                .ok_or_else(|| format!("`{s}` has an invalid prefix length"))?,
+        };
+        Ok(Cidr { addr, prefix })
+    }
+}
+
+impl fmt::Display for Cidr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix)
+    }
+}
+
+impl Serialize for Cidr {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        s.collect_str(self)
+    }
+}
@@ -0,0 +1,53 @@
+//! Per-request deadline (`REQUEST_TIMEOUT_MS`, default 5 s, 0 disables).
+//!
+//! A handler that overruns is dropped and answered with 504; dropping it also
+//! cancels its in-flight Postgres query (see `db::cancellable`), so an overload
+//! run drains on its own instead of piling up work until a restart.
+//!
+//! `/debug/pprof/*` is exempt: a CPU profile deliberately runs for N seconds.
+//! So is `/admin/*`: a reset re-seeds the table and its length depends on the
+//! row count asked for, and `/users/poll`, which waits for its own `timeout`.
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::error::InternalError;
+use actix_web::middleware::Next;
+use actix_web::{web, Error, HttpResponse};
+use std::time::Duration;
+
+use super::real_ip;
+use crate::metrics::Metrics;
+use crate::AppState;
+
+pub async fn timeout(
+    data: web::Data<AppState>,
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let timeout_ms = data.config.request_timeout_ms;
+    if timeout_ms == 0
+        || req.path().starts_with("/debug/pprof")
+        || req.path().starts_with("/admin/")
+        || req.path() == "/users/poll"
+    {
+        return next.call(req).await;
+    }
+
+    // `req` moves into the handler chain (and must stay the only handle to the
+    // request for routing to work), so keep just what the log line needs.
+    let (method, uri) = (req.method().clone(), req.uri().clone());
+    let client = real_ip::client_ip(&req).map_or_else(|| "-".to_string(), |ip| ip.to_string());
+    match tokio::time::timeout(Duration::from_millis(timeout_ms), next.call(req)).await {
+        Ok(res) => res,
+        Err(_) => {
+            Metrics::inc(&data.metrics.timed_out_total);
+            eprintln!("Request timed out after {timeout_ms}ms: {method} {} (client {client})", uri.path());
+            let response = HttpResponse::GatewayTimeout().json(serde_json::json!({
+                "error": "Request timed out",
+                "timeout_ms": timeout_ms
+            }));
+            // Rendered by the dispatcher; there is no request left to attach it to.
+            Err(InternalError::from_response("request timed out", response).into())
+        }
+    }
+}
@@ -0,0 +1,151 @@
+//! Transaction per request (`TRANSACTION_PER_REQUEST=on`).
+//!
+//! Several ORMs and frameworks run each request in a transaction by default;
+//! this mode does the same so the cost of that over autocommit can be
+//! measured.  The middleware sets up a task-local slot, and the repository's
+//! first call in the request checks out a connection, sends `BEGIN` and
+//! leaves the connection in the slot; later calls in the same request take it
+//! from there (see [`checkout`]).  Once the handler has answered, the
+//! middleware sends `COMMIT`, or `ROLLBACK` when the answer is a 5xx, and the
+//! connection goes back to the pool.  Requests that never reach the database
+//! open no transaction.
+//!
+//! A request dropped before that (client disconnect, `REQUEST_TIMEOUT_MS`)
+//! rolls back in a spawned task, so no connection returns to the pool inside
+//! an open transaction.  `/users/poll` is exempt: it would hold a connection
+//! idle in transaction for as long as it waits.
+
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use actix_web::{web, Error, HttpResponse};
+use std::cell::Cell;
+use std::ops::{Deref, DerefMut};
+use tokio_postgres::Client;
+
+use crate::db::{abandon, Connection, Pool};
+use crate::repo::RepoError;
+use crate::{timing, AppState};
+
+/// The current request's transaction.
+#[derive(Default)]
+struct Request {
+    /// The connection, between repository calls.
+    conn: Cell<Option<Connection>>,
+    /// `BEGIN` has been sent; the connection is out in a [`Checkout`] when
+    /// `conn` is empty.
+    begun: Cell<bool>,
+}
+
+tokio::task_local! {
+    static REQUEST: Request;
+}
+
+impl Drop for Request {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take() {
+            abandon(conn);
+        }
+    }
+}
+
+/// A connection for one repository call: the request's, inside its
+/// transaction, or a plain pool checkout.  Derefs to `tokio_postgres::Client`.
+pub struct Checkout {
+    conn: Option<Connection>,
+    request: bool,
+}
+
+impl Checkout {
+    /// Whether the call runs inside the request's transaction, which then
+    /// stands in for the per-call ones (RLS, audit).
+    pub fn in_transaction(&self) -> bool {
+        self.request
+    }
+}
+
+impl Deref for Checkout {
+    type Target = Client;
+
+    fn deref(&self) -> &Client {
+        self.conn.as_ref().expect("connection taken before drop")
+    }
+}
+
+impl DerefMut for Checkout {
+    fn deref_mut(&mut self) -> &mut Client {
+        self.conn.as_mut().expect("connection taken before drop")
+    }
+}
+
+impl Drop for Checkout {
+    fn drop(&mut self) {
+        // Tokio keeps the task-local set while dropping the scoped future, so
+        // a dropped request still gets its connection back here first.
+        if let Some(conn) = self.conn.take().filter(|_| self.request) {
+            let _ = REQUEST.try_with(|request| request.conn.set(Some(conn)));
+        }
+    }
+}
+
+/// Connection for a repository call.  Outside a transaction-per-request
+/// scope, or while the request's connection is in use by another call, it
+/// is a plain checkout.
+pub async fn checkout(pool: &Pool) -> Result<Checkout, RepoError> {
+    let held = REQUEST.try_with(|request| (request.conn.take(), request.begun.get()));
+    let conn = match held {
+        Ok((Some(conn), _)) => conn,
+        Ok((None, false)) => {
+            let conn = pool.get().await?;
+            timing::db(conn.batch_execute("BEGIN")).await?;
+            let _ = REQUEST.try_with(|request| request.begun.set(true));
+            conn
+        }
+        Ok((None, true)) | Err(_) => return Ok(Checkout { conn: Some(pool.get().await?), request: false }),
+    };
+    Ok(Checkout { conn: Some(conn), request: true })
+}
+
+pub async fn transaction<B: MessageBody>(
+    data: web::Data<AppState>,
+    req: ServiceRequest,
+    next: Next<B>,
+) -> Result<ServiceResponse<EitherBody<B>>, Error> {
+    if !data.config.transaction_per_request || req.path() == "/users/poll" {
+        return next.call(req).await.map(ServiceResponse::map_into_left_body);
+    }
+
+    REQUEST
+        .scope(Request::default(), async {
+            let res = next.call(req).await;
+            let Some(conn) = REQUEST.with(|request| request.conn.take()) else {
+                return res.map(ServiceResponse::map_into_left_body);
+            };
+            // Still the request's until the transaction ends: if this future is
+            // dropped meanwhile, the connection goes back to the slot and is
+            // rolled back with it.
+            let mut conn = Checkout { conn: Some(conn), request: true };
+            let status = match &res {
+                Ok(res) => res.status(),
+                Err(e) => e.as_response_error().status_code(),
+            };
+            let end = if status.is_server_error() { "ROLLBACK" } else { "COMMIT" };
+            let outcome = timing::db(conn.batch_execute(end)).await;
+            conn.request = false;
+            match (res, outcome) {
+                (Ok(res), Err(e)) if end == "COMMIT" => {
+                    eprintln!("Database commit error: {e}");
+                    let response =
+                        HttpResponse::InternalServerError().json(serde_json::json!({ "error": "Database commit error" }));
+                    Ok(res.into_response(response).map_into_right_body())
+                }
+                (res, outcome) => {
+                    if let Err(e) = outcome {
+                        eprintln!("Database {} error: {e}", end.to_lowercase());
+                    }
+                    res.map(ServiceResponse::map_into_left_body)
+                }
+            }
+        })
+        .await
+}
@@ -0,0 +1,371 @@
+//! Content negotiation for `/json`, `/db`, `/queries` and the `/users`
+//! endpoints (reads, and the user returned by POST and PUT).
+//!
+//! [`Encoding`] is picked from `Accept` with q-values (RFC 9110 §12.5.1): each
+//! supported type takes the weight of the most specific range that matches it,
+//! the highest non-zero weight wins, and ties go to the order of [`SUPPORTED`],
+//! so JSON stays the answer to a missing `Accept` or `*/*`.  When nothing
+//! matches the extractor answers 406.  [`Negotiated`] then encodes the value:
+//!
+//! * JSON        — `serde_json`, byte-for-byte what the handlers wrote before;
+//! * MessagePack — `rmp-serde`, maps with field names like the JSON objects;
+//! * CBOR        — `ciborium`;
+//! * CSV         — one row per user with a header line; a page keeps only its
+//!   `data` rows, and nested values (such as HAL `_links`) become JSON text.
+//!   Column order needs the field names before the first row, so CSV goes
+//!   through a JSON round trip instead of a streaming serializer;
+//! * XML         — `quick-xml`, also through a JSON value: objects become
+//!   elements named by their keys under a `<response>` root, and a list
+//!   becomes `<item>` elements, so every payload has one shape of document.
+//!
+//! `application/vnd.api+json` is negotiated here too, but only when named
+//! explicitly and without media type parameters (wildcards never select it);
+//! the `/users` handlers then build `jsonapi` documents, and everywhere else
+//! it is answered as plain JSON.  Error bodies stay JSON in every encoding.
+//!
+//! Handlers extract an [`Output`]: the encoding plus the [`Style`] of the
+//! configuration.  `JSON_ENVELOPE=on` wraps every payload in
+//! `{"data": …, "meta": {"status": N}}` (JSON, MessagePack and CBOR; CSV rows
+//! and JSON:API documents are left alone), and `JSON_PRETTY=on` indents JSON
+//! (and XML).
+//! Both happen here, in [`Negotiated`], so no handler knows about them.
+
+use actix_web::body::BoxBody;
+use actix_web::dev::Payload;
+use actix_web::http::header::{self, HeaderMap};
+use actix_web::{web, Error, FromRequest, HttpRequest, HttpResponse, HttpResponseBuilder, Responder};
+use serde::de::{Deserialize, Deserializer, MapAccess, Visitor};
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+use std::future::{ready, Ready};
+
+use crate::AppState;
+
+/// Representation of a response body.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Json,
+    MsgPack,
+    Cbor,
+    Csv,
+    Xml,
+    JsonApi,
+}
+
+/// Accepted media types in order of preference; aliases share an encoding.
+const SUPPORTED: &[(&str, Encoding)] = &[
+    ("application/json", Encoding::Json),
+    ("application/msgpack", Encoding::MsgPack),
+    ("application/x-msgpack", Encoding::MsgPack),
+    ("application/vnd.msgpack", Encoding::MsgPack),
+    ("application/cbor", Encoding::Cbor),
+    ("text/csv", Encoding::Csv),
+    ("application/xml", Encoding::Xml),
+    ("text/xml", Encoding::Xml),
+    (crate::jsonapi::MEDIA_TYPE, Encoding::JsonApi),
+];
+
+impl Encoding {
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Encoding::Json => "application/json",
+            Encoding::MsgPack => "application/msgpack",
+            Encoding::Cbor => "application/cbor",
+            Encoding::Csv => "text/csv; charset=utf-8",
+            Encoding::Xml => "application/xml; charset=utf-8",
+            Encoding::JsonApi => crate::jsonapi::MEDIA_TYPE,
+        }
+    }
+
+    pub fn is_jsonapi(self) -> bool {
+        self == Encoding::JsonApi
+    }
+
+    /// Response cache key suffix for this representation.
+    pub fn cache_suffix(self) -> &'static str {
+        match self {
+            Encoding::Json => "",
+            Encoding::MsgPack => "#msgpack",
+            Encoding::Cbor => "#cbor",
+            Encoding::Csv => "#csv",
+            Encoding::Xml => "#xml",
+            Encoding::JsonApi => "#jsonapi",
+        }
+    }
+
+    /// Keeps the ETag of each representation apart from the JSON one.
+    pub fn etag(self, etag: String) -> String {
+        match self {
+            Encoding::Json => etag,
+            other => format!("{}-{}\"", etag.trim_end_matches('"'), &other.cache_suffix()[1..]),
+        }
+    }
+
+    fn encode<T: Serialize>(self, value: &T, pretty: bool) -> Result<Vec<u8>, String> {
+        match self {
+            Encoding::Json | Encoding::JsonApi if pretty => serde_json::to_vec_pretty(value).map_err(|e| e.to_string()),
+            Encoding::Json | Encoding::JsonApi => serde_json::to_vec(value).map_err(|e| e.to_string()),
+            Encoding::MsgPack => rmp_serde::to_vec_named(value).map_err(|e| e.to_string()),
+            Encoding::Cbor => {
+                let mut out = Vec::new();
+                ciborium::into_writer(value, &mut out).map_err(|e| e.to_string())?;
+                Ok(out)
+            }
+            Encoding::Csv => csv(value),
+            Encoding::Xml => xml(value, pretty),
+        }
+    }
+}
+
+/// One `Accept` range: `type/subtype`, whether it carries parameters other
+/// than `q`, and its weight.
+struct Range<'a> {
+    media: &'a str,
+    params: bool,
+    q: f32,
+}
+
+fn ranges(headers: &HeaderMap) -> impl Iterator<Item = Range<'_>> {
+    headers
+        .get_all(header::ACCEPT)
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .filter_map(|range| {
+            let mut parts = range.split(';');
+            let media = parts.next()?.trim();
+            let (mut params, mut q) = (false, 1.0);
+            for param in parts.map(str::trim).filter(|p| !p.is_empty()) {
+                match param.split_once('=') {
+                    Some((key, value)) if key.trim().eq_ignore_ascii_case("q") => {
+                        q = value.trim().parse::<f32>().ok().filter(|q| (0.0..=1.0).contains(q))?;
+                    }
+                    _ => params = true,
+                }
+            }
+            (!media.is_empty()).then_some(Range { media, params, q })
+        })
+}
+
+/// Weight of `media` under the most specific matching range, if any.
+fn weight(headers: &HeaderMap, media: &str, encoding: Encoding) -> Option<f32> {
+    let (kind, _) = media.split_once('/')?;
+    let mut best: Option<(u8, f32)> = None;
+    for range in ranges(headers) {
+        let specificity = if range.media.eq_ignore_ascii_case(media) {
+            // JSON:API forbids serving a range that names extensions or profiles.
+            if encoding == Encoding::JsonApi && range.params {
+                continue;
+            }
+            3
+        } else if encoding == Encoding::JsonApi {
+            continue;
+        } else if range.media.strip_suffix("/*").is_some_and(|t| t.eq_ignore_ascii_case(kind)) {
+            2
+        } else if range.media == "*/*" {
+            1
+        } else {
+            continue;
+        };
+        if best.is_none_or(|(s, _)| specificity > s) {
+            best = Some((specificity, range.q));
+        }
+    }
+    best.map(|(_, q)| q)
+}
+
+/// The preferred encoding, `None` when `Accept` excludes every one (406).
+pub fn negotiate(headers: &HeaderMap) -> Option<Encoding> {
+    if ranges(headers).next().is_none() {
+        return Some(Encoding::Json);
+    }
+    let mut chosen: Option<(f32, Encoding)> = None;
+    for &(media, encoding) in SUPPORTED {
+        match weight(headers, media, encoding) {
+            Some(q) if q > 0.0 && chosen.is_none_or(|(best, _)| q > best) => chosen = Some((q, encoding)),
+            _ => {}
+        }
+    }
+    chosen.map(|(_, encoding)| encoding)
+}
+
+/// How payloads are written, from `JSON_ENVELOPE` and `JSON_PRETTY`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Style {
+    pub envelope: bool,
+    pub pretty: bool,
+}
+
+/// What a handler needs to encode its response: the negotiated encoding and
+/// the configured [`Style`].
+#[derive(Debug, Clone, Copy)]
+pub struct Output {
+    pub encoding: Encoding,
+    pub style: Style,
+}
+
+impl FromRequest for Output {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let style = req
+            .app_data::<web::Data<AppState>>()
+            .map(|data| Style { envelope: data.config.json_envelope, pretty: data.config.json_pretty })
+            .unwrap_or_default();
+        ready(negotiate(req.headers()).map(|encoding| Output { encoding, style }).ok_or_else(|| {
+            let msg = "Not acceptable: supported types are application/json, application/msgpack, \
+                       application/cbor, text/csv, application/xml and application/vnd.api+json";
+            let response = HttpResponse::NotAcceptable().json(serde_json::json!({ "error": msg }));
+            actix_web::error::InternalError::from_response(msg, response).into()
+        }))
+    }
+}
+
+#[derive(Serialize)]
+struct Envelope<'a, T> {
+    data: &'a T,
+    meta: Meta,
+}
+
+#[derive(Serialize)]
+struct Meta {
+    status: u16,
+}
+
+impl Output {
+    /// Plain JSON with neither envelope nor indentation: the body the
+    /// streaming and `row-encoding` paths write by themselves.
+    pub fn is_plain_json(self) -> bool {
+        self.encoding == Encoding::Json && !self.style.envelope && !self.style.pretty
+    }
+}
+
+/// A value encoded as the request negotiated.
+pub struct Negotiated<T> {
+    output: Output,
+    value: T,
+}
+
+impl<T: Serialize> Negotiated<T> {
+    pub fn new(output: Output, value: T) -> Negotiated<T> {
+        Negotiated { output, value }
+    }
+
+    /// Finishes `builder` (status and headers already set) with the body.
+    pub fn respond(self, mut builder: HttpResponseBuilder) -> HttpResponse {
+        let Output { encoding, style } = self.output;
+        // Finished first so the envelope can carry the status set by the handler.
+        let res = builder.content_type(encoding.content_type()).finish();
+        let encoded = match encoding {
+            Encoding::Json | Encoding::MsgPack | Encoding::Cbor if style.envelope => {
+                let meta = Meta { status: res.status().as_u16() };
+                encoding.encode(&Envelope { data: &self.value, meta }, style.pretty)
+            }
+            _ => encoding.encode(&self.value, style.pretty),
+        };
+        match encoded {
+            Ok(body) => res.set_body(BoxBody::new(body)),
+            Err(e) => {
+                eprintln!("Encode error: {e}");
+                HttpResponse::InternalServerError().json(serde_json::json!({ "error": "Encoding error" }))
+            }
+        }
+    }
+}
+
+impl<T: Serialize> Responder for Negotiated<T> {
+    type Body = BoxBody;
+
+    fn respond_to(self, _: &HttpRequest) -> HttpResponse {
+        self.respond(HttpResponse::Ok())
+    }
+}
+
+// ---------------------------------------------------------------------------
+// CSV
+// ---------------------------------------------------------------------------
+
+/// A JSON object with its keys in document order.
+struct Row(Vec<(String, Value)>);
+
+impl<'de> Deserialize<'de> for Row {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Row, D::Error> {
+        struct RowVisitor;
+
+        impl<'de> Visitor<'de> for RowVisitor {
+            type Value = Row;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an object")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Row, A::Error> {
+                let mut row = Vec::with_capacity(map.size_hint().unwrap_or(0));
+                while let Some(entry) = map.next_entry()? {
+                    row.push(entry);
+                }
+                Ok(Row(row))
+            }
+        }
+
+        deserializer.deserialize_map(RowVisitor)
+    }
+}
+
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum Table {
+    Rows(Vec<Row>),
+    Page { data: Vec<Row> },
+    Row(Row),
+}
+
+fn csv<T: Serialize>(value: &T) -> Result<Vec<u8>, String> {
+    let json = serde_json::to_vec(value).map_err(|e| e.to_string())?;
+    let rows = match serde_json::from_slice::<Table>(&json).map_err(|_| "not a table".to_owned())? {
+        Table::Rows(rows) | Table::Page { data: rows } => rows,
+        Table::Row(row) => vec![row],
+    };
+    let mut out = csv::Writer::from_writer(Vec::new());
+    if let Some(first) = rows.first() {
+        out.write_record(first.0.iter().map(|(key, _)| key.as_str())).map_err(|e| e.to_string())?;
+    }
+    for row in &rows {
+        let cells = row.0.iter().map(|(_, value)| match value {
+            Value::Null => String::new(),
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        });
+        out.write_record(cells).map_err(|e| e.to_string())?;
+    }
+    out.into_inner().map_err(|e| e.to_string())
+}
+
+// ---------------------------------------------------------------------------
+// XML
+// ---------------------------------------------------------------------------
+
+/// A top-level list, one `<item>` per element.
+#[derive(Serialize)]
+struct Items<'a> {
+    item: &'a [Value],
+}
+
+fn xml<T: Serialize>(value: &T, pretty: bool) -> Result<Vec<u8>, String> {
+    let value = serde_json::to_value(value).map_err(|e| e.to_string())?;
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>");
+    if pretty {
+        out.push('\n');
+    }
+    let mut serializer = quick_xml::se::Serializer::with_root(&mut out, Some("response")).map_err(|e| e.to_string())?;
+    if pretty {
+        serializer.indent(' ', 2);
+    }
+    match &value {
+        Value::Array(items) => Items { item: items }.serialize(serializer),
+        other => other.serialize(serializer),
+    }
+    .map_err(|e| e.to_string())?;
+    Ok(out.into_bytes())
+}
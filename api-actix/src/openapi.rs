@@ -0,0 +1,96 @@
+//! OpenAPI 3.1 description of the benchmark API, generated by `utoipa` from
+//! the handler annotations in `main.rs`.
+//!
+//! The document covers the routes every implementation serves (`/`,
+//! `/json`, `/db`, `/queries`, `/users…`), and with them the new `/users/*`
+//! endpoints the conformance contract expects of every implementation
+//! (`/users/age-histogram`).  Actix-only endpoints such as `/config`,
+//! `/metrics` and `/debug/pprof` are left out so that
+//! `scripts/check-openapi.py` can compare it against the other frameworks.
+
+use actix_web::{get, web, HttpResponse, Responder};
+use serde::Serialize;
+use utoipa::{OpenApi, ToSchema};
+
+use crate::status::Status;
+use crate::{AgeHistogram, CreateUser, PaginatedUsers, UpdateUser, User};
+
+/// Body of every 4xx/5xx response.
+#[derive(Serialize, ToSchema)]
+pub struct ErrorResponse {
+    pub error: String,
+}
+
+/// Body of GET /json.
+#[derive(Serialize, ToSchema)]
+pub struct Message {
+    pub message: String,
+    pub framework: String,
+}
+
+/// GET /users returns the full list, or a page when `?limit=N` is given.
+/// Documentation only: the handler builds the two shapes directly.
+#[allow(dead_code)]
+#[derive(Serialize, ToSchema)]
+#[serde(untagged)]
+pub enum UsersResponse {
+    All(Vec<User>),
+    Page(PaginatedUsers),
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Web Framework Benchmark API", version = "1.0.0"),
+    paths(
+        crate::index,
+        crate::json_endpoint,
+        crate::db_endpoint,
+        crate::queries_endpoint,
+        crate::get_users,
+        crate::age_histogram,
+        crate::get_user_by_id,
+        crate::create_user,
+        crate::update_user,
+        crate::delete_user,
+    ),
+    components(schemas(
+        User, Status, CreateUser, UpdateUser, PaginatedUsers, UsersResponse, AgeHistogram, Message, ErrorResponse
+    ))
+)]
+struct ApiDoc;
+
+/// GET /openapi.json
+#[get("/openapi.json")]
+async fn openapi_json() -> impl Responder {
+    HttpResponse::Ok().json(ApiDoc::openapi())
+}
+
+/// Swagger UI page; the assets come from a CDN so the binary (and the build
+/// and image-size measurements) stay unaffected.
+const SWAGGER_HTML: &str = r##"<!doctype html>
+<html lang="en">
+<head>
+  <meta charset="utf-8">
+  <title>Actix-web API — Swagger UI</title>
+  <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css">
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js"></script>
+  <script>SwaggerUIBundle({ url: "/openapi.json", dom_id: "#swagger-ui" });</script>
+</body>
+</html>
+"##;
+
+/// GET /docs
+#[get("/docs")]
+async fn swagger_ui() -> impl Responder {
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(SWAGGER_HTML)
+}
+
+/// Registers `/openapi.json` and `/docs`.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(openapi_json).service(swagger_ui);
+}
@@ -0,0 +1,205 @@
+//! GET /payload-static?bytes=N — precompressed static bodies
+//! (`STATIC_PAYLOAD_SIZES`, e.g. `1024,65536,1048576`; empty = endpoint off).
+//!
+//! At startup every configured size gets a deterministic text body of exactly
+//! N bytes (lines of user-like JSON, so it compresses like an API response),
+//! compressed once with Brotli (quality 11), zstd (`STATIC_PAYLOAD_ZSTD_LEVEL`,
+//! default 19) and gzip (level 9).  A request
+//! only picks a variant from `Accept-Encoding` and sends the stored bytes:
+//! no compression CPU on the request path, which isolates the send path from
+//! the cost of compressing on the fly.
+//!
+//! `Accept-Encoding` q-values are honoured; on equal weights Brotli wins over
+//! zstd, zstd over gzip and gzip over identity.  `identity` is acceptable unless excluded
+//! (`identity;q=0`, or `*;q=0` without naming it), and a request that leaves
+//! nothing acceptable gets 406.  Sizes that were not generated get 404 with
+//! the list of available ones.
+
+use actix_web::http::header::{self, HeaderMap, HeaderValue};
+use actix_web::web::{self, Bytes};
+use actix_web::{get, HttpRequest, HttpResponse, Responder};
+use serde::Deserialize;
+use std::io::Write;
+
+use crate::AppState;
+
+/// Largest size accepted in `STATIC_PAYLOAD_SIZES` (64 MiB).
+pub const MAX_SIZE: usize = 64 << 20;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Coding {
+    Br,
+    Zstd,
+    Gzip,
+    Identity,
+}
+
+impl Coding {
+    fn token(self) -> &'static str {
+        match self {
+            Coding::Br => "br",
+            Coding::Zstd => "zstd",
+            Coding::Gzip => "gzip",
+            Coding::Identity => "identity",
+        }
+    }
+}
+
+/// One size in its four encodings.
+struct Payload {
+    size: usize,
+    identity: Bytes,
+    gzip: Bytes,
+    br: Bytes,
+    zstd: Bytes,
+}
+
+impl Payload {
+    fn get(&self, coding: Coding) -> &Bytes {
+        match coding {
+            Coding::Br => &self.br,
+            Coding::Zstd => &self.zstd,
+            Coding::Gzip => &self.gzip,
+            Coding::Identity => &self.identity,
+        }
+    }
+}
+
+/// Every generated payload, ordered by size.
+#[derive(Default)]
+pub struct StaticPayloads(Vec<Payload>);
+
+impl StaticPayloads {
+    pub fn build(sizes: &[usize], zstd_level: i32) -> std::io::Result<StaticPayloads> {
+        let mut payloads = Vec::with_capacity(sizes.len());
+        for &size in sizes {
+            let identity = body(size);
+            let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+            gzip.write_all(&identity)?;
+            let gzip = gzip.finish()?;
+            let mut br = Vec::new();
+            let params = brotli::enc::BrotliEncoderParams { quality: 11, ..Default::default() };
+            brotli::BrotliCompress(&mut identity.as_slice(), &mut br, &params)?;
+            let zstd = zstd::bulk::compress(&identity, zstd_level)?;
+            println!(
+                "Static payload {size} B: gzip {} B, br {} B, zstd {} B",
+                gzip.len(),
+                br.len(),
+                zstd.len()
+            );
+            payloads.push(Payload {
+                size,
+                identity: identity.into(),
+                gzip: gzip.into(),
+                br: br.into(),
+                zstd: zstd.into(),
+            });
+        }
+        Ok(StaticPayloads(payloads))
+    }
+
+    fn find(&self, size: usize) -> Option<&Payload> {
+        self.0.iter().find(|p| p.size == size)
+    }
+
+    fn sizes(&self) -> Vec<usize> {
+        self.0.iter().map(|p| p.size).collect()
+    }
+}
+
+/// `size` bytes of newline-separated user objects; the last line is cut short.
+pub(crate) fn body(size: usize) -> Vec<u8> {
+    const NAMES: [&str; 8] = ["Ana", "Bob", "Carlos", "Diana", "Eduardo", "Fernanda", "Gabriel", "Helena"];
+    const DOMAINS: [&str; 4] = ["gmail.com", "outlook.com", "yahoo.com", "benchmark.dev"];
+    let mut out = Vec::with_capacity(size + 128);
+    let mut seed: u32 = 0x2545_f491;
+    let mut id = 1;
+    while out.len() < size {
+        // xorshift keeps the content varied but identical across runs.
+        seed ^= seed << 13;
+        seed ^= seed >> 17;
+        seed ^= seed << 5;
+        let name = NAMES[seed as usize % NAMES.len()];
+        let domain = DOMAINS[(seed >> 8) as usize % DOMAINS.len()];
+        let age = 18 + (seed >> 16) % 60;
+        let _ = writeln!(out, r#"{{"id":{id},"name":"{name}","email":"user{id}@{domain}","age":{age}}}"#);
+        id += 1;
+    }
+    out.truncate(size);
+    out
+}
+
+/// Codings listed in `Accept-Encoding` with their weights.
+fn weights(headers: &HeaderMap) -> impl Iterator<Item = (&str, f32)> {
+    headers
+        .get_all(header::ACCEPT_ENCODING)
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .filter_map(|item| {
+            let mut parts = item.split(';');
+            let coding = parts.next()?.trim();
+            let q = parts
+                .filter_map(|p| p.split_once('='))
+                .find(|(k, _)| k.trim().eq_ignore_ascii_case("q"))
+                .map_or(Some(1.0), |(_, v)| v.trim().parse::<f32>().ok())?;
+            (!coding.is_empty()).then_some((coding, q))
+        })
+}
+
+/// The encoding to send, `None` when every one is excluded.
+fn choose(headers: &HeaderMap) -> Option<Coding> {
+    let listed = |coding: &str| {
+        weights(headers)
+            .find(|(c, _)| c.eq_ignore_ascii_case(coding))
+            .map(|(_, q)| q)
+    };
+    let wildcard = listed("*");
+    let mut best: Option<(f32, Coding)> = None;
+    for coding in [Coding::Br, Coding::Zstd, Coding::Gzip, Coding::Identity] {
+        let q = match (listed(coding.token()), wildcard) {
+            (Some(q), _) | (None, Some(q)) => q,
+            (None, None) if coding == Coding::Identity => 1.0,
+            (None, None) => continue,
+        };
+        if q > 0.0 && best.is_none_or(|(b, _)| q > b) {
+            best = Some((q, coding));
+        }
+    }
+    best.map(|(_, coding)| coding)
+}
+
+#[derive(Deserialize)]
+struct PayloadParams {
+    bytes: usize,
+}
+
+/// GET /payload-static?bytes=N
+#[get("/payload-static")]
+async fn payload_static(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    query: web::Query<PayloadParams>,
+) -> impl Responder {
+    let Some(payload) = data.payloads.find(query.bytes) else {
+        return HttpResponse::NotFound().json(serde_json::json!({
+            "error": "No payload of that size",
+            "sizes": data.payloads.sizes()
+        }));
+    };
+    let Some(coding) = choose(req.headers()) else {
+        return HttpResponse::NotAcceptable()
+            .json(serde_json::json!({ "error": "Not acceptable: supported encodings are br, zstd, gzip and identity" }));
+    };
+
+    let mut res = HttpResponse::Ok();
+    res.content_type("text/plain; charset=utf-8")
+        .insert_header((header::VARY, HeaderValue::from_static("accept-encoding")));
+    if coding != Coding::Identity {
+        res.insert_header((header::CONTENT_ENCODING, HeaderValue::from_static(coding.token())));
+    }
+    res.body(payload.get(coding).clone())
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(payload_static);
+}
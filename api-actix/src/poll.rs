@@ -0,0 +1,93 @@
+//! Long polling for new users: GET /users/poll?since_id=N&timeout=S.
+//!
+//! Users with an id above `since_id` (up to [`BATCH`], in id order) are
+//! returned at once when there are any.  Otherwise the request is held until
+//! one is inserted or `timeout` seconds (default 30, at most 60) have passed,
+//! and then answered with what arrived, possibly `[]`.  The client sends the
+//! last id it got as the next `since_id`.
+//!
+//! With `EVENTS=on` a waiting poll is woken by the LISTEN/NOTIFY change feed
+//! (`events`): each insert it announces costs one more read.  Without it the
+//! table is re-read every `LONG_POLL_INTERVAL_MS`.  Either way the wait holds
+//! the HTTP connection and a timer, but no database connection, and one last
+//! read at the deadline catches an insert the feed missed while reconnecting.
+//!
+//! The wait is exempt from `REQUEST_TIMEOUT_MS` and is not a latency sample
+//! for load shedding (`middleware::timeout`, `middleware::load_shed`).
+
+use actix_web::web::Bytes;
+use actix_web::{get, web, HttpResponse, Responder};
+use serde::Deserialize;
+use std::time::Duration;
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+use tokio::time::{sleep_until, Instant};
+
+use crate::key::{Key, UserId};
+use crate::negotiate::{Negotiated, Output};
+use crate::tenant::Tenant;
+use crate::{events, storage_error, AppState};
+
+/// Most users returned by one poll.
+pub const BATCH: i64 = 100;
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+const MAX_TIMEOUT_SECS: u64 = 60;
+
+#[derive(Deserialize)]
+pub struct PollParams {
+    since_id: Option<UserId>,
+    timeout: Option<u64>,
+}
+
+/// GET /users/poll  — users created after `since_id`, waiting up to
+/// `timeout` seconds for the first one.
+#[get("/users/poll")]
+async fn poll(
+    data: web::Data<AppState>,
+    query: web::Query<PollParams>,
+    output: Output,
+    tenant: Tenant,
+) -> impl Responder {
+    let since_id = query.since_id.unwrap_or(<UserId as Key>::MIN);
+    let timeout = query.timeout.unwrap_or(DEFAULT_TIMEOUT_SECS).min(MAX_TIMEOUT_SECS);
+    let deadline = Instant::now() + Duration::from_secs(timeout);
+    let interval = Duration::from_millis(data.config.long_poll_interval_ms);
+    // Subscribed before the first read, so an insert committed between that
+    // read and the wait still wakes it.
+    let mut changes = data.config.events.then(|| data.events.subscribe());
+
+    loop {
+        let users = match data.users.after(tenant.scope(), since_id, BATCH).await {
+            Ok(users) => users,
+            Err(e) => return storage_error("Query", "Database query error", e),
+        };
+        if !users.is_empty() || Instant::now() >= deadline {
+            return Negotiated::new(output, users).respond(HttpResponse::Ok());
+        }
+        wait(changes.as_mut(), interval, deadline).await;
+    }
+}
+
+/// Until the feed announces an insert (or `interval` passes, without the
+/// feed), or `deadline`.
+async fn wait(changes: Option<&mut Receiver<Bytes>>, interval: Duration, deadline: Instant) {
+    let Some(changes) = changes else {
+        return sleep_until(deadline.min(Instant::now() + interval)).await;
+    };
+    loop {
+        tokio::select! {
+            received = changes.recv() => match received {
+                Ok(message) if !events::is_insert(&message) => continue,
+                // Missed messages may have been inserts: look again.
+                Ok(_) | Err(RecvError::Lagged(_)) => return,
+                // The sender lives in `AppState`; only reachable at shutdown.
+                Err(RecvError::Closed) => return sleep_until(deadline).await,
+            },
+            _ = sleep_until(deadline) => return,
+        }
+    }
+}
+
+/// Registers `/users/poll`; must come before `/users/{id}`.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(poll);
+}
@@ -0,0 +1,116 @@
+//! Adaptive pool sizing (`POOL_AUTOSIZE=on`, deadpool only): a controller that
+//! moves the pool's maximum size between `POOL_AUTOSIZE_MIN` and
+//! `POOL_AUTOSIZE_MAX`, starting from `POOL_SIZE`.
+//!
+//! Every `POOL_AUTOSIZE_INTERVAL_MS` it takes the average checkout wait of the
+//! interval from the pool counters (see `pool_stats`):
+//!
+//! * above `POOL_AUTOSIZE_TARGET_WAIT_MS`, the pool grows by a quarter (at
+//!   least one connection), but only into the connections Postgres still has
+//!   free (`max_connections` minus `pg_stat_activity`) beyond
+//!   `POOL_AUTOSIZE_RESERVE`, so other clients are never starved;
+//! * below a quarter of the target with idle connections for three intervals
+//!   in a row, it shrinks by half the idle ones.
+//!
+//! Shrinking closes connections as they come back to the pool.  Each change
+//! is printed and kept for GET /pool-stats (see `pool_stats::Decisions`),
+//! which the harness plots with `scripts/pool-autosize.py`; `db_pool_max_size`
+//! and `db_pool_resizes_total` follow on /metrics.
+
+use actix_web::web;
+use chrono::Utc;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use crate::db::cancellable;
+use crate::metrics::Metrics;
+use crate::pool_stats::Decision;
+use crate::AppState;
+
+/// Quiet intervals in a row before shrinking.
+const QUIET_INTERVALS: u32 = 3;
+
+/// Connections Postgres can still accept.
+async fn pg_headroom(state: &AppState) -> Result<i64, String> {
+    let client = state.pool.get().await.map_err(|e| e.to_string())?;
+    let row = cancellable(
+        &client,
+        client.query_one(
+            "SELECT current_setting('max_connections')::int8 - (SELECT count(*) FROM pg_stat_activity)",
+            &[],
+        ),
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(row.get(0))
+}
+
+/// Runs the controller until the server stops.
+pub fn spawn(state: web::Data<AppState>) {
+    let settings = state.config.pool_autosize.clone();
+    actix_web::rt::spawn(async move {
+        let stats = state.pool.stats();
+        let mut ticks = tokio::time::interval(Duration::from_millis(settings.interval_ms));
+        ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        let mut last = (0, 0);
+        let mut quiet = 0;
+        loop {
+            ticks.tick().await;
+            let counts = (
+                stats.checkouts_total.load(Ordering::Relaxed),
+                stats.wait_us_total.load(Ordering::Relaxed),
+            );
+            let (checkouts, wait_us) = (counts.0 - last.0, counts.1 - last.1);
+            last = counts;
+            let avg_wait_ms = if checkouts > 0 { wait_us as f64 / checkouts as f64 / 1000.0 } else { 0.0 };
+
+            let status = state.pool.status();
+            let size = status.max_size;
+            let target = settings.target_wait_ms as f64;
+            let (to, reason, headroom) = if avg_wait_ms > target && size < settings.max {
+                quiet = 0;
+                let free = match pg_headroom(&state).await {
+                    Ok(free) => free,
+                    Err(e) => {
+                        eprintln!("Pool autosize: could not read Postgres headroom: {e}");
+                        continue;
+                    }
+                };
+                let room = usize::try_from(free - settings.reserve as i64).unwrap_or(0);
+                let to = (size + (size / 4).max(1)).min(settings.max).min(size + room);
+                (to, "wait", Some(free))
+            } else if avg_wait_ms < target / 4.0 && status.available > 0 && size > settings.min {
+                quiet += 1;
+                if quiet < QUIET_INTERVALS {
+                    continue;
+                }
+                quiet = 0;
+                ((size - (status.available / 2).max(1)).max(settings.min), "idle", None)
+            } else {
+                quiet = 0;
+                continue;
+            };
+            if to == size {
+                continue;
+            }
+
+            state.pool.resize(to);
+            Metrics::inc(if to > size { &stats.grown_total } else { &stats.shrunk_total });
+            let headroom_note = headroom.map(|free| format!(", {free} free in Postgres")).unwrap_or_default();
+            println!(
+                "Pool autosize: {size} -> {to} ({reason}: avg wait {avg_wait_ms:.2}ms, {} waiting, {} idle{headroom_note})",
+                status.waiting, status.available
+            );
+            stats.decisions.push(Decision {
+                at: Utc::now(),
+                from: size,
+                to,
+                reason,
+                avg_wait_ms,
+                waiting: status.waiting,
+                available: status.available,
+                headroom,
+            });
+        }
+    });
+}
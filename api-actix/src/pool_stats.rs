@@ -0,0 +1,167 @@
+//! Connection pool instrumentation: GET /pool-stats and the `db_pool_*`
+//! series on /metrics.
+//!
+//! The pool only reports a point-in-time [`Status`] (size, available,
+//! waiting).  A background task samples it every `POOL_STATS_INTERVAL_MS`
+//! into gauges, so scraping never touches the pool's lock.  What the pool
+//! does not count is counted here: connections created and recycled (the
+//! deadpool hooks or the bb8 manager, see `db`), and `get()` calls that gave
+//! up waiting (`db::Pool::get`), plus the time every checkout waited.  A
+//! plateau with `waiting` > 0 and timeouts
+//! climbing is pool starvation; with idle connections available it is not.
+
+use actix_web::{get, web, HttpResponse, Responder};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::db::Status;
+use crate::metrics::{counter, counter_family, gauge};
+use crate::AppState;
+
+#[derive(Default)]
+pub struct PoolStats {
+    /// Last sample of [`Status`] (gauges).
+    pub max_size: AtomicU64,
+    pub size: AtomicU64,
+    pub available: AtomicU64,
+    pub waiting: AtomicU64,
+    /// Unix time of the last sample in ms, 0 before the first one.
+    pub sampled_at_ms: AtomicU64,
+    /// Connections opened and recycled by the pool, and timed-out checkouts.
+    pub created_total: AtomicU64,
+    pub recycled_total: AtomicU64,
+    pub timeouts_total: AtomicU64,
+    /// Checkouts and the time they spent waiting, timed out or not.
+    pub checkouts_total: AtomicU64,
+    pub wait_us_total: AtomicU64,
+    /// Maximum size changes made by `pool_autosize`.
+    pub grown_total: AtomicU64,
+    pub shrunk_total: AtomicU64,
+    pub decisions: Decisions,
+}
+
+impl PoolStats {
+    fn record(&self, status: Status) {
+        self.max_size.store(status.max_size as u64, Ordering::Relaxed);
+        self.size.store(status.size as u64, Ordering::Relaxed);
+        self.available.store(status.available as u64, Ordering::Relaxed);
+        self.waiting.store(status.waiting as u64, Ordering::Relaxed);
+        self.sampled_at_ms.store(Utc::now().timestamp_millis() as u64, Ordering::Relaxed);
+    }
+
+    pub fn record_wait(&self, waited: Duration) {
+        self.checkouts_total.fetch_add(1, Ordering::Relaxed);
+        self.wait_us_total
+            .fetch_add(u64::try_from(waited.as_micros()).unwrap_or(u64::MAX), Ordering::Relaxed);
+    }
+
+    /// Appends the `db_pool_*` series to a /metrics body.
+    pub fn render(&self, out: &mut String) {
+        gauge(out, "db_pool_max_size", "Configured maximum pool size.", &self.max_size);
+        gauge(out, "db_pool_size", "Open connections at the last sample.", &self.size);
+        gauge(out, "db_pool_available", "Idle connections at the last sample.", &self.available);
+        gauge(out, "db_pool_waiting", "Checkouts waiting for a connection at the last sample.", &self.waiting);
+        counter(out, "db_pool_connections_created_total", "Connections opened by the pool.", &self.created_total);
+        counter(out, "db_pool_connections_recycled_total", "Connections recycled on checkout.", &self.recycled_total);
+        counter(out, "db_pool_timeouts_total", "Checkouts that timed out.", &self.timeouts_total);
+        counter(out, "db_pool_checkouts_total", "Connection checkouts.", &self.checkouts_total);
+        let _ = writeln!(
+            out,
+            "# HELP db_pool_wait_seconds_total Time spent waiting for a connection.\n\
+             # TYPE db_pool_wait_seconds_total counter\ndb_pool_wait_seconds_total {}",
+            self.wait_us_total.load(Ordering::Relaxed) as f64 / 1e6
+        );
+        counter_family(
+            out,
+            "db_pool_resizes_total",
+            "Maximum size changes by POOL_AUTOSIZE.",
+            "direction",
+            &[("grow", &self.grown_total), ("shrink", &self.shrunk_total)],
+        );
+    }
+}
+
+/// Autosize decisions kept for /pool-stats.
+const KEPT: usize = 512;
+
+/// A maximum size change made by `pool_autosize`.
+#[derive(Clone, Serialize)]
+pub struct Decision {
+    pub at: DateTime<Utc>,
+    pub from: usize,
+    pub to: usize,
+    pub reason: &'static str,
+    pub avg_wait_ms: f64,
+    pub waiting: usize,
+    pub available: usize,
+    /// Free Postgres connections when growing; not read when shrinking.
+    pub headroom: Option<i64>,
+}
+
+/// The last decisions, oldest first.
+#[derive(Default)]
+pub struct Decisions(Mutex<VecDeque<Decision>>);
+
+impl Decisions {
+    pub fn push(&self, decision: Decision) {
+        let mut decisions = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        if decisions.len() == KEPT {
+            decisions.pop_front();
+        }
+        decisions.push_back(decision);
+    }
+
+    pub fn list(&self) -> Vec<Decision> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).iter().cloned().collect()
+    }
+}
+
+/// Samples the pool status every `interval`.
+pub fn spawn(state: web::Data<AppState>, interval: Duration) {
+    state.pool.stats().record(state.pool.status());
+    actix_web::rt::spawn(async move {
+        let mut ticks = tokio::time::interval(interval);
+        ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            ticks.tick().await;
+            state.pool.stats().record(state.pool.status());
+        }
+    });
+}
+
+/// GET /pool-stats  — the last sample plus the pool counters.
+#[get("/pool-stats")]
+async fn pool_stats(data: web::Data<AppState>) -> impl Responder {
+    let stats = data.pool.stats();
+    let load = |v: &AtomicU64| v.load(Ordering::Relaxed);
+    let sampled_at = match load(&stats.sampled_at_ms) {
+        0 => None,
+        ms => DateTime::<Utc>::from_timestamp_millis(ms as i64),
+    };
+    HttpResponse::Ok().json(serde_json::json!({
+        "max_size": load(&stats.max_size),
+        "size": load(&stats.size),
+        "available": load(&stats.available),
+        "waiting": load(&stats.waiting),
+        "created": load(&stats.created_total),
+        "recycled": load(&stats.recycled_total),
+        "timeouts": load(&stats.timeouts_total),
+        "sampled_at": sampled_at,
+        "interval_ms": data.config.pool_stats_interval_ms,
+        "autosize": data.config.pool_autosize.enabled.then(|| serde_json::json!({
+            "min": data.config.pool_autosize.min,
+            "max": data.config.pool_autosize.max,
+            "target_wait_ms": data.config.pool_autosize.target_wait_ms,
+            "decisions": stats.decisions.list()
+        }))
+    }))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(pool_stats);
+}
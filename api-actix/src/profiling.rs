@@ -0,0 +1,124 @@
+//! On-demand profiling endpoints compatible with `go tool pprof` / `pprof`.
+//!
+//! Registered under `/debug/pprof` only when `PROFILING=on`, so a live
+//! benchmark target can be profiled without restarting it under `perf`:
+//!
+//! ```text
+//! go tool pprof -http=: http://localhost:3004/debug/pprof/profile?seconds=30
+//! go tool pprof -http=: http://localhost:3004/debug/pprof/heap
+//! ```
+//!
+//! The heap endpoint exists only in builds with the `heap-profiling` feature,
+//! which swaps the global allocator for jemalloc with sampling enabled.
+
+use actix_web::{get, web, HttpResponse, Responder};
+use pprof::protos::Message;
+use serde::Deserialize;
+use std::time::Duration;
+
+/// Query parameters for GET /debug/pprof/profile.
+#[derive(Deserialize)]
+pub struct ProfileParams {
+    seconds: Option<u64>,
+}
+
+/// GET /debug/pprof/profile?seconds=N  — samples all threads for N seconds
+/// (1 ≤ N ≤ 300, default 30) and returns a pprof protobuf.
+#[get("/profile")]
+async fn cpu_profile(query: web::Query<ProfileParams>) -> impl Responder {
+    let seconds = query.seconds.unwrap_or(30).clamp(1, 300);
+
+    // Only one profiler can be active per process; a concurrent request fails here.
+    let guard = match pprof::ProfilerGuardBuilder::default()
+        .frequency(99)
+        .blocklist(&["libc", "libgcc", "pthread", "vdso"])
+        .build()
+    {
+        Ok(g) => g,
+        Err(e) => {
+            eprintln!("Profiler error: {e}");
+            return HttpResponse::Conflict()
+                .json(serde_json::json!({ "error": "Profiler already running" }));
+        }
+    };
+
+    tokio::time::sleep(Duration::from_secs(seconds)).await;
+
+    let profile = match guard.report().build().and_then(|r| r.pprof()) {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("Profile report error: {e}");
+            return HttpResponse::InternalServerError()
+                .json(serde_json::json!({ "error": "Failed to build profile" }));
+        }
+    };
+
+    match profile.write_to_bytes() {
+        Ok(body) => HttpResponse::Ok()
+            .content_type("application/octet-stream")
+            .body(body),
+        Err(e) => {
+            eprintln!("Profile encode error: {e}");
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({ "error": "Failed to encode profile" }))
+        }
+    }
+}
+
+/// jemalloc reads its options from this symbol at startup: enable profiling
+/// and sample roughly every 512 KiB allocated (2^19 bytes).
+#[cfg(feature = "heap-profiling")]
+#[export_name = "_rjem_malloc_conf"]
+pub static MALLOC_CONF: &[u8] = b"prof:true,prof_active:true,lg_prof_sample:19\0";
+
+/// GET /debug/pprof/heap  — dumps the sampled jemalloc heap profile
+/// (legacy `heap_v2` format, readable by `pprof` and `jeprof`).
+#[cfg(feature = "heap-profiling")]
+#[get("/heap")]
+async fn heap_profile() -> impl Responder {
+    let path = std::env::temp_dir().join(format!("api-actix-{}.heap", std::process::id()));
+    let c_path = match std::ffi::CString::new(path.to_string_lossy().into_owned()) {
+        Ok(p) => p,
+        Err(_) => {
+            return HttpResponse::InternalServerError()
+                .json(serde_json::json!({ "error": "Invalid dump path" }));
+        }
+    };
+
+    // SAFETY: `prof.dump` takes a NUL-terminated path that outlives the call.
+    let dumped = unsafe { tikv_jemalloc_ctl::raw::write(b"prof.dump\0", c_path.as_ptr()) };
+    if let Err(e) = dumped {
+        eprintln!("Heap dump error: {e}");
+        return HttpResponse::InternalServerError()
+            .json(serde_json::json!({ "error": "Failed to dump heap profile" }));
+    }
+
+    // The dump can be several MB: read it off the worker thread.
+    let body = web::block(move || {
+        let body = std::fs::read(&path);
+        let _ = std::fs::remove_file(&path);
+        body
+    })
+    .await;
+    match body {
+        Ok(Ok(b)) => HttpResponse::Ok().content_type("application/octet-stream").body(b),
+        Ok(Err(e)) => {
+            eprintln!("Heap dump read error: {e}");
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({ "error": "Failed to read heap profile" }))
+        }
+        Err(e) => {
+            eprintln!("Blocking pool error: {e}");
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({ "error": "Failed to read heap profile" }))
+        }
+    }
+}
+
+/// Registers the `/debug/pprof` route group.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    let scope = web::scope("/debug/pprof").service(cpu_profile);
+    #[cfg(feature = "heap-profiling")]
+    let scope = scope.service(heap_profile);
+    cfg.service(scope);
+}
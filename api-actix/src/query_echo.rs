@@ -0,0 +1,87 @@
+//! GET /query-echo — deep query string deserialization.
+//!
+//! The query string is parsed with `serde_qs` into a nested structure —
+//! arrays, nested objects, optional enums, dates and timestamps — and echoed
+//! back as JSON, so the cost measured is the extractor's.  Actix's own
+//! `web::Query` uses `serde_urlencoded`, which only knows flat `key=value`
+//! pairs; bracketed keys are what nested-query frameworks accept:
+//!
+//! ```text
+//! /query-echo?ids[]=1&ids[]=2&tags[0]=a&sort=desc&since=2024-01-31
+//!     &until=2024-12-31T23:59:59Z&page[limit]=20&page[offset]=40
+//!     &filter[age][min]=18&filter[age][max]=65&filter[status][]=active
+//!     &filter[name]=ana
+//! ```
+//!
+//! Every field is optional.  Nesting deeper than [`MAX_DEPTH`], a value of
+//! the wrong type or an unknown enum variant is answered with 400.
+
+use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Deepest bracket nesting accepted.
+const MAX_DEPTH: usize = 5;
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum SortOrder {
+    Asc,
+    Desc,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Status {
+    Active,
+    Inactive,
+    Pending,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+struct Range {
+    min: Option<i32>,
+    max: Option<i32>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+struct Filter {
+    name: Option<String>,
+    age: Option<Range>,
+    status: Vec<Status>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+struct Page {
+    limit: Option<u32>,
+    offset: Option<u32>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+struct Query {
+    ids: Vec<i32>,
+    tags: Vec<String>,
+    sort: Option<SortOrder>,
+    since: Option<NaiveDate>,
+    until: Option<DateTime<Utc>>,
+    page: Option<Page>,
+    filter: Option<Filter>,
+}
+
+/// GET /query-echo
+#[get("/query-echo")]
+async fn query_echo(req: HttpRequest) -> impl Responder {
+    // Non-strict: percent-encoded brackets (`ids%5B%5D=1`) count as brackets.
+    match serde_qs::Config::new(MAX_DEPTH, false).deserialize_str::<Query>(req.query_string()) {
+        Ok(query) => HttpResponse::Ok().json(query),
+        Err(e) => HttpResponse::BadRequest().json(serde_json::json!({ "error": format!("Invalid query string: {e}") })),
+    }
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(query_echo);
+}
@@ -0,0 +1,94 @@
+//! Random rows for `/db` and `/queries` by id (`RANDOM_ROW_SELECTION=id-range`).
+//!
+//! `ORDER BY RANDOM()` scans and sorts the whole table on every call, a cost
+//! that grows with the table and dominates the measurement.  Instead, ids are
+//! drawn uniformly from `1..=MAX(id)` by splitmix64 over a shared counter and
+//! read by primary key (`WHERE id = $1`, as TechEmpower does), so the
+//! database does one index lookup per row.
+//!
+//! The counter starts at `RANDOM_SEED`, or at a random value per process.
+//! With a seed every run draws the same sequence of ids.  Concurrent requests
+//! share the sequence, so which request gets which id still depends on
+//! scheduling; the ids served over a run do not.
+//!
+//! `MAX(id)` is cached and read again once older than
+//! `MAX_ID_REFRESH_SECS`, so new rows join the range.  An id with no row
+//! (deleted since) is drawn again, up to [`MAX_ROUNDS`] rounds; a table with
+//! more gaps than rows can come up short.  `/admin/reset` restarts the
+//! sequence and rereads the maximum.
+//!
+//! Ids are drawn as `i64` and converted to the build's key type (see `key`);
+//! a build with `uuid-keys` has no range to draw from, and the configuration
+//! requires `order-by-random` there.
+
+use std::hash::{BuildHasher, RandomState};
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Draw rounds before a call settles for the rows found so far.
+pub const MAX_ROUNDS: usize = 4;
+
+const GAMMA: u64 = 0x9E37_79B9_7F4A_7C15;
+
+pub struct IdDraws {
+    seed: u64,
+    state: AtomicU64,
+    /// `MAX(id)` when last read; 0 = not read yet.
+    max_id: AtomicI64,
+    /// When `max_id` was read, in ms since `epoch`.
+    read_at_ms: AtomicU64,
+    epoch: Instant,
+    /// Age at which `max_id` is read again; `None` = never.
+    refresh: Option<Duration>,
+}
+
+impl IdDraws {
+    /// Draws from `seed`, or from a per-process random seed.
+    pub fn new(seed: Option<u64>, refresh: Option<Duration>) -> Self {
+        let seed = seed.unwrap_or_else(|| RandomState::new().hash_one(0u8));
+        IdDraws {
+            seed,
+            state: AtomicU64::new(seed),
+            max_id: AtomicI64::new(0),
+            read_at_ms: AtomicU64::new(0),
+            epoch: Instant::now(),
+            refresh,
+        }
+    }
+
+    /// Next splitmix64 output.
+    fn next(&self) -> u64 {
+        let mut z = self.state.fetch_add(GAMMA, Ordering::Relaxed).wrapping_add(GAMMA);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn now_ms(&self) -> u64 {
+        self.epoch.elapsed().as_millis() as u64
+    }
+
+    /// The cached `MAX(id)`; `None` until [`IdDraws::set_max_id`] and once
+    /// it is due for a refresh.
+    pub fn max_id(&self) -> Option<i64> {
+        let max = self.max_id.load(Ordering::Relaxed);
+        let age = Duration::from_millis(self.now_ms().saturating_sub(self.read_at_ms.load(Ordering::Relaxed)));
+        Some(max).filter(|&max| max > 0 && self.refresh.is_none_or(|refresh| age < refresh))
+    }
+
+    pub fn set_max_id(&self, max: i64) {
+        self.max_id.store(max, Ordering::Relaxed);
+        self.read_at_ms.store(self.now_ms(), Ordering::Relaxed);
+    }
+
+    /// An id in `1..=max`.
+    pub fn draw(&self, max: i64) -> i64 {
+        1 + (self.next() % max as u64) as i64
+    }
+
+    /// Back to the first id of the sequence; the maximum is read again.
+    pub fn restart(&self) {
+        self.state.store(self.seed, Ordering::Relaxed);
+        self.max_id.store(0, Ordering::Relaxed);
+    }
+}
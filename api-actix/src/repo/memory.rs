@@ -0,0 +1,390 @@
+//! In-process [`UserRepository`]: the `users` table held in a vector, for
+//! driving the handlers without sockets or a database (`benches/handlers.rs`).
+//!
+//! [`MemoryUsers::seeded`] generates rows like `scripts/init.sql` (same
+//! names, emails, ages and tenants).  "Random" reads walk the table
+//! round-robin, so runs are repeatable; writes keep ids, the unique email and
+//! the collection version the way PostgreSQL does.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures_util::stream::{self, StreamExt};
+use rust_decimal::Decimal;
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use super::{BulkInsert, OnConflict, RepoError, Scope, UserRepository, UserStream};
+use crate::fields::{Fields, Record};
+use crate::filter::Filter;
+use crate::key::{Key, UserId};
+use crate::metadata::{self, Change};
+use crate::status::Status;
+use crate::text;
+use crate::transfer::{Balance, Outcome, Receipt, OPENING_BALANCE};
+use crate::{AgeHistogram, CreateUser, UpdateUser, User};
+
+const FIRST_NAMES: [&str; 25] = [
+    "Alice", "Bob", "Carlos", "Diana", "Eduardo", "Fernanda", "Gabriel", "Helena", "Igor", "Julia", "Kevin",
+    "Laura", "Marcos", "Natalia", "Otto", "Paula", "Rafael", "Sofia", "Thiago", "Ursula", "Victor", "Wendy",
+    "Xander", "Yasmin", "Zeca",
+];
+const LAST_NAMES: [&str; 15] = [
+    "Silva", "Santos", "Oliveira", "Souza", "Costa", "Ferreira", "Alves", "Pereira", "Lima", "Carvalho", "Melo",
+    "Ribeiro", "Almeida", "Nascimento", "Gomes",
+];
+const DOMAINS: [&str; 5] = ["gmail.com", "outlook.com", "yahoo.com", "hotmail.com", "benchmark.dev"];
+
+/// Tenant of rows inserted without one, as the column default in `init.sql`.
+const DEFAULT_TENANT: &str = "default";
+
+struct Entry {
+    tenant: String,
+    user: User,
+    metadata: Value,
+    balance: Decimal,
+    updated_at: DateTime<Utc>,
+}
+
+/// Rows ordered by id, like `ORDER BY id`.
+struct Table {
+    rows: Vec<Entry>,
+    emails: HashSet<String>,
+    /// Rows inserted so far, the `seq` of the next [`Key::generate`].
+    inserted: i32,
+    last_write: Option<DateTime<Utc>>,
+}
+
+impl Table {
+    fn visible<'a>(&'a self, tenant: Option<&'a str>) -> impl Iterator<Item = &'a Entry> + 'a {
+        self.rows.iter().filter(move |e| tenant.is_none_or(|t| e.tenant == t))
+    }
+
+    fn position(&self, id: UserId, tenant: Option<&str>) -> Option<usize> {
+        let index = self.rows.binary_search_by_key(&id, |e| e.user.id).ok()?;
+        tenant.is_none_or(|t| self.rows[index].tenant == t).then_some(index)
+    }
+
+    /// Appends a row whose email is already in `emails`.
+    fn push(&mut self, tenant: Option<&str>, user: &CreateUser, now: DateTime<Utc>) -> User {
+        self.inserted += 1;
+        let created = User {
+            id: UserId::generate(self.inserted),
+            name: text::intern(&user.name),
+            email: text::intern(&user.email),
+            age: user.age,
+            created_at: now,
+            tags: user.tags.clone(),
+            status: Status::default(),
+        };
+        self.last_write = Some(now);
+        self.rows.push(Entry {
+            tenant: tenant.unwrap_or(DEFAULT_TENANT).to_owned(),
+            user: created.clone(),
+            metadata: Value::Object(Map::new()),
+            balance: OPENING_BALANCE,
+            updated_at: now,
+        });
+        created
+    }
+}
+
+pub struct MemoryUsers {
+    table: Mutex<Table>,
+    cursor: AtomicUsize,
+}
+
+impl MemoryUsers {
+    /// A table with the first `n` rows of the `init.sql` seed.
+    pub fn seeded(n: usize) -> Self {
+        let now = Utc::now();
+        let rows: Vec<Entry> = (1..=n as i32)
+            .map(|i| {
+                let at = |len: usize| i as usize % len;
+                Entry {
+                    tenant: format!("tenant-{}", i % 10),
+                    user: User {
+                        id: UserId::generate(i),
+                        name: text::intern(&format!("{} {}", FIRST_NAMES[at(25)], LAST_NAMES[at(15)])),
+                        email: text::intern(&format!("user{i}@{}", DOMAINS[at(5)])),
+                        age: Some(18 + i % 62),
+                        created_at: now,
+                        tags: Vec::new(),
+                        status: Status::default(),
+                    },
+                    metadata: Value::Object(Map::new()),
+                    balance: OPENING_BALANCE,
+                    updated_at: now,
+                }
+            })
+            .collect();
+        let emails = rows.iter().map(|e| e.user.email.to_string()).collect();
+        MemoryUsers {
+            table: Mutex::new(Table {
+                rows,
+                emails,
+                inserted: n as i32,
+                last_write: (n > 0).then_some(now),
+            }),
+            cursor: AtomicUsize::new(0),
+        }
+    }
+
+    fn table(&self) -> std::sync::MutexGuard<'_, Table> {
+        self.table.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// The next `count` visible users after the shared cursor, wrapping.
+    fn next(&self, tenant: Option<&str>, count: usize) -> Vec<User> {
+        let table = self.table();
+        let start = self.cursor.fetch_add(count, Ordering::Relaxed);
+        // Unscoped reads index the table directly; a tenant's rows are
+        // gathered first, a scan like the `tenant_id` filter.
+        match tenant {
+            None => cycle(&table.rows, start, count),
+            Some(_) => cycle(&table.visible(tenant).collect::<Vec<_>>(), start, count),
+        }
+    }
+}
+
+/// `count` users of `entries` from `start`, wrapping, each at most once.
+fn cycle<E: std::borrow::Borrow<Entry>>(entries: &[E], start: usize, count: usize) -> Vec<User> {
+    if entries.is_empty() {
+        return Vec::new();
+    }
+    (start..start + count.min(entries.len()))
+        .map(|i| entries[i % entries.len()].borrow().user.clone())
+        .collect()
+}
+
+#[async_trait(?Send)]
+impl UserRepository for MemoryUsers {
+    async fn random_one(&self, scope: Scope<'_>) -> Result<Option<User>, RepoError> {
+        Ok(self.next(scope.tenant, 1).pop())
+    }
+
+    async fn random(&self, scope: Scope<'_>, count: i64) -> Result<Vec<User>, RepoError> {
+        Ok(self.next(scope.tenant, count.max(0) as usize))
+    }
+
+    async fn version(&self, scope: Scope<'_>) -> Result<(i64, Option<DateTime<Utc>>), RepoError> {
+        let table = self.table();
+        if scope.tenant.is_none() {
+            return Ok((table.rows.len() as i64, table.last_write));
+        }
+        let (count, last) = table
+            .visible(scope.tenant)
+            .fold((0, None), |(n, last), e| (n + 1, last.max(Some(e.updated_at))));
+        Ok((count, last))
+    }
+
+    async fn page(&self, scope: Scope<'_>, limit: i64, offset: i64, fields: Fields) -> Result<Vec<Record>, RepoError> {
+        let table = self.table();
+        Ok(table
+            .visible(scope.tenant)
+            .skip(offset.max(0) as usize)
+            .take(limit.max(0) as usize)
+            .map(|e| Record::from_user(e.user.clone(), fields))
+            .collect())
+    }
+
+    async fn all(&self, scope: Scope<'_>, fields: Fields) -> Result<Vec<Record>, RepoError> {
+        let table = self.table();
+        Ok(table.visible(scope.tenant).map(|e| Record::from_user(e.user.clone(), fields)).collect())
+    }
+
+    async fn by_id(&self, scope: Scope<'_>, id: UserId, fields: Fields) -> Result<Option<Record>, RepoError> {
+        let table = self.table();
+        let found = table.position(id, scope.tenant).map(|i| &table.rows[i].user);
+        Ok(found.map(|user| Record::from_user(user.clone(), fields)))
+    }
+
+    async fn matching(
+        &self,
+        scope: Scope<'_>,
+        filter: &Filter,
+        limit: Option<i64>,
+        offset: i64,
+        fields: Fields,
+    ) -> Result<(i64, Vec<Record>), RepoError> {
+        let table = self.table();
+        let matches = || table.visible(scope.tenant).filter(|e| filter.matches(&e.metadata, &e.user.tags, e.user.status));
+        let page = matches()
+            .skip(offset.max(0) as usize)
+            .take(limit.map_or(usize::MAX, |limit| limit.max(0) as usize))
+            .map(|e| Record::from_user(e.user.clone(), fields))
+            .collect();
+        Ok((matches().count() as i64, page))
+    }
+
+    async fn metadata(&self, scope: Scope<'_>, id: UserId) -> Result<Option<Value>, RepoError> {
+        let table = self.table();
+        Ok(table.position(id, scope.tenant).map(|i| table.rows[i].metadata.clone()))
+    }
+
+    async fn patch_metadata(&self, scope: Scope<'_>, id: UserId, changes: &[Change]) -> Result<Option<Value>, RepoError> {
+        let mut table = self.table();
+        let Some(index) = table.position(id, scope.tenant) else {
+            return Ok(None);
+        };
+        let now = Utc::now();
+        table.last_write = Some(now);
+        let entry = &mut table.rows[index];
+        metadata::apply(&mut entry.metadata, changes);
+        entry.updated_at = now;
+        Ok(Some(entry.metadata.clone()))
+    }
+
+    async fn transfer(&self, scope: Scope<'_>, from: UserId, to: UserId, amount: Decimal) -> Result<Outcome, RepoError> {
+        let mut table = self.table();
+        let (Some(from), Some(to)) = (table.position(from, scope.tenant), table.position(to, scope.tenant)) else {
+            return Ok(Outcome::NotFound);
+        };
+        if table.rows[from].balance < amount {
+            return Ok(Outcome::InsufficientFunds);
+        }
+        let now = Utc::now();
+        table.last_write = Some(now);
+        let mut moved = |index: usize, delta: Decimal| {
+            let entry = &mut table.rows[index];
+            entry.balance += delta;
+            entry.updated_at = now;
+            Balance { id: entry.user.id, balance: entry.balance }
+        };
+        let from = moved(from, -amount);
+        let to = moved(to, amount);
+        Ok(Outcome::Done(Receipt { amount, from, to }))
+    }
+
+    async fn after(&self, scope: Scope<'_>, since_id: UserId, limit: i64) -> Result<Vec<User>, RepoError> {
+        let table = self.table();
+        let start = table.rows.partition_point(|e| e.user.id <= since_id);
+        Ok(table.rows[start..]
+            .iter()
+            .filter(|e| scope.tenant.is_none_or(|t| e.tenant == t))
+            .take(limit as usize)
+            .map(|e| e.user.clone())
+            .collect())
+    }
+
+    async fn age_histogram(&self, scope: Scope<'_>, buckets: i32) -> Result<AgeHistogram, RepoError> {
+        let table = self.table();
+        let ages = || table.visible(scope.tenant).filter_map(|e| e.user.age);
+        let mut histogram = AgeHistogram {
+            min: ages().min(),
+            max: ages().max(),
+            counts: vec![0; buckets as usize],
+            unknown: table.visible(scope.tenant).filter(|e| e.user.age.is_none()).count() as i64,
+        };
+        if let (Some(min), Some(max)) = (histogram.min, histogram.max) {
+            // width_bucket over numeric: ⌊(age − min) · N / (max + 1 − min)⌋.
+            let width = i64::from(max + 1 - min);
+            for age in ages() {
+                histogram.counts[(i64::from(age - min) * i64::from(buckets) / width) as usize] += 1;
+            }
+        }
+        Ok(histogram)
+    }
+
+    /// A copy of the visible rows, taken now: the snapshot the PostgreSQL
+    /// export reads from.
+    async fn stream_all(&self, scope: Scope<'_>) -> Result<UserStream, RepoError> {
+        let users: Vec<User> = self.table().visible(scope.tenant).map(|e| e.user.clone()).collect();
+        Ok(stream::iter(users.into_iter().map(Ok)).boxed_local())
+    }
+
+    async fn insert(&self, scope: Scope<'_>, user: &CreateUser) -> Result<User, RepoError> {
+        let mut table = self.table();
+        if !table.emails.insert(user.email.clone()) {
+            return Err(RepoError::unique_violation());
+        }
+        Ok(table.push(scope.tenant, user, Utc::now()))
+    }
+
+    /// Row by row, with the outcome of the single PostgreSQL statement.
+    async fn insert_many(
+        &self,
+        scope: Scope<'_>,
+        users: &[CreateUser],
+        on_conflict: OnConflict,
+    ) -> Result<BulkInsert, RepoError> {
+        let mut table = self.table();
+        if on_conflict == OnConflict::Error {
+            let mut batch = HashSet::new();
+            if users.iter().any(|u| table.emails.contains(&u.email) || !batch.insert(&u.email)) {
+                return Err(RepoError::unique_violation());
+            }
+        }
+        let now = Utc::now();
+        let mut result = BulkInsert::default();
+        for (i, user) in users.iter().enumerate() {
+            // Only the last of repeated emails is written by DO UPDATE.
+            if on_conflict == OnConflict::Update && users[i + 1..].iter().any(|later| later.email == user.email) {
+                result.skipped += 1;
+                continue;
+            }
+            if table.emails.insert(user.email.clone()) {
+                table.push(scope.tenant, user, now);
+                result.inserted += 1;
+                continue;
+            }
+            let existing = table.rows.iter().position(|e| *e.user.email == *user.email);
+            let writable = |at: &usize| scope.tenant.is_none_or(|t| table.rows[*at].tenant == t);
+            match existing.filter(|at| on_conflict == OnConflict::Update && writable(at)) {
+                Some(at) => {
+                    let entry = &mut table.rows[at];
+                    entry.user.name = text::intern(&user.name);
+                    entry.user.age = user.age;
+                    entry.user.tags = user.tags.clone();
+                    entry.updated_at = now;
+                    table.last_write = Some(now);
+                    result.updated += 1;
+                }
+                None => result.skipped += 1,
+            }
+        }
+        Ok(result)
+    }
+
+    async fn update(&self, scope: Scope<'_>, id: UserId, user: &UpdateUser) -> Result<Option<User>, RepoError> {
+        let mut table = self.table();
+        let Some(index) = table.position(id, scope.tenant) else {
+            return Ok(None);
+        };
+        if let Some(email) = user.email.as_ref().filter(|&e| **e != *table.rows[index].user.email) {
+            if !table.emails.insert(email.clone()) {
+                return Err(RepoError::unique_violation());
+            }
+            let old = std::mem::replace(&mut table.rows[index].user.email, text::intern(email));
+            table.emails.remove(&*old);
+        }
+        let now = Utc::now();
+        table.last_write = Some(now);
+        let entry = &mut table.rows[index];
+        if let Some(name) = &user.name {
+            entry.user.name = text::intern(name);
+        }
+        if user.age.is_some() {
+            entry.user.age = user.age;
+        }
+        if let Some(tags) = &user.tags {
+            entry.user.tags = tags.clone();
+        }
+        if let Some(status) = user.status {
+            entry.user.status = status;
+        }
+        entry.updated_at = now;
+        Ok(Some(entry.user.clone()))
+    }
+
+    async fn delete(&self, scope: Scope<'_>, id: UserId) -> Result<u64, RepoError> {
+        let mut table = self.table();
+        let Some(index) = table.position(id, scope.tenant) else {
+            return Ok(0);
+        };
+        let entry = table.rows.remove(index);
+        table.emails.remove(&*entry.user.email);
+        Ok(1)
+    }
+}
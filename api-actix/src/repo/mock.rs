@@ -0,0 +1,215 @@
+//! [`UserRepository`] with scripted failures, for testing what the handlers
+//! answer when storage misbehaves (`tests/handlers.rs`).
+//!
+//! [`MockUsers`] serves calls from a [`MemoryUsers`] and records each one.
+//! [`MockUsers::fail`] queues an error for the next call of one operation;
+//! [`MockUsers::fail_all`] makes every call fail until
+//! [`MockUsers::recover`], like a database that went away.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde_json::Value;
+use std::sync::Mutex;
+
+use super::memory::MemoryUsers;
+use super::{BulkInsert, OnConflict, RepoError, Scope, UserRepository, UserStream};
+use crate::fields::{Fields, Record};
+use crate::filter::Filter;
+#[cfg(feature = "postgis")]
+use crate::geo::{Nearby, Point};
+use crate::key::UserId;
+use crate::metadata::Change;
+use crate::transfer::Outcome;
+use crate::{AgeHistogram, CreateUser, UpdateUser, User};
+
+/// A [`UserRepository`] method.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    RandomOne,
+    Random,
+    Version,
+    Page,
+    All,
+    ById,
+    Matching,
+    Metadata,
+    PatchMetadata,
+    Transfer,
+    #[cfg(feature = "postgis")]
+    Near,
+    After,
+    AgeHistogram,
+    StreamAll,
+    Insert,
+    InsertMany,
+    Update,
+    Delete,
+}
+
+#[derive(Default)]
+struct Script {
+    /// Failures still to happen, each consumed by the first call of its op.
+    queued: Vec<(Op, RepoError)>,
+    /// Failure of every call, while set.
+    outage: Option<RepoError>,
+    calls: Vec<Op>,
+}
+
+pub struct MockUsers {
+    inner: MemoryUsers,
+    script: Mutex<Script>,
+}
+
+impl MockUsers {
+    pub fn new(inner: MemoryUsers) -> Self {
+        MockUsers { inner, script: Mutex::default() }
+    }
+
+    fn script(&self) -> std::sync::MutexGuard<'_, Script> {
+        self.script.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// The next call of `op` fails with `error`; queued failures of the same
+    /// op happen in order.
+    pub fn fail(&self, op: Op, error: RepoError) -> &Self {
+        self.script().queued.push((op, error));
+        self
+    }
+
+    /// Every call fails with `error` until [`MockUsers::recover`].
+    pub fn fail_all(&self, error: RepoError) -> &Self {
+        self.script().outage = Some(error);
+        self
+    }
+
+    /// Ends a [`MockUsers::fail_all`] outage.
+    pub fn recover(&self) -> &Self {
+        self.script().outage = None;
+        self
+    }
+
+    /// Operations called so far, in order, failed ones included.
+    pub fn calls(&self) -> Vec<Op> {
+        self.script().calls.clone()
+    }
+
+    /// Records `op` and returns the failure scripted for it, if any.
+    fn check(&self, op: Op) -> Result<(), RepoError> {
+        let mut script = self.script();
+        script.calls.push(op);
+        if let Some(error) = &script.outage {
+            return Err(error.clone());
+        }
+        match script.queued.iter().position(|(queued, _)| *queued == op) {
+            Some(index) => Err(script.queued.remove(index).1),
+            None => Ok(()),
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl UserRepository for MockUsers {
+    async fn random_one(&self, scope: Scope<'_>) -> Result<Option<User>, RepoError> {
+        self.check(Op::RandomOne)?;
+        self.inner.random_one(scope).await
+    }
+
+    async fn random(&self, scope: Scope<'_>, count: i64) -> Result<Vec<User>, RepoError> {
+        self.check(Op::Random)?;
+        self.inner.random(scope, count).await
+    }
+
+    async fn version(&self, scope: Scope<'_>) -> Result<(i64, Option<DateTime<Utc>>), RepoError> {
+        self.check(Op::Version)?;
+        self.inner.version(scope).await
+    }
+
+    async fn page(&self, scope: Scope<'_>, limit: i64, offset: i64, fields: Fields) -> Result<Vec<Record>, RepoError> {
+        self.check(Op::Page)?;
+        self.inner.page(scope, limit, offset, fields).await
+    }
+
+    async fn all(&self, scope: Scope<'_>, fields: Fields) -> Result<Vec<Record>, RepoError> {
+        self.check(Op::All)?;
+        self.inner.all(scope, fields).await
+    }
+
+    async fn by_id(&self, scope: Scope<'_>, id: UserId, fields: Fields) -> Result<Option<Record>, RepoError> {
+        self.check(Op::ById)?;
+        self.inner.by_id(scope, id, fields).await
+    }
+
+    async fn matching(
+        &self,
+        scope: Scope<'_>,
+        filter: &Filter,
+        limit: Option<i64>,
+        offset: i64,
+        fields: Fields,
+    ) -> Result<(i64, Vec<Record>), RepoError> {
+        self.check(Op::Matching)?;
+        self.inner.matching(scope, filter, limit, offset, fields).await
+    }
+
+    async fn metadata(&self, scope: Scope<'_>, id: UserId) -> Result<Option<Value>, RepoError> {
+        self.check(Op::Metadata)?;
+        self.inner.metadata(scope, id).await
+    }
+
+    async fn patch_metadata(&self, scope: Scope<'_>, id: UserId, changes: &[Change]) -> Result<Option<Value>, RepoError> {
+        self.check(Op::PatchMetadata)?;
+        self.inner.patch_metadata(scope, id, changes).await
+    }
+
+    async fn transfer(&self, scope: Scope<'_>, from: UserId, to: UserId, amount: Decimal) -> Result<Outcome, RepoError> {
+        self.check(Op::Transfer)?;
+        self.inner.transfer(scope, from, to, amount).await
+    }
+
+    #[cfg(feature = "postgis")]
+    async fn near(&self, scope: Scope<'_>, origin: Point, radius: f64, limit: i64) -> Result<Vec<Nearby>, RepoError> {
+        self.check(Op::Near)?;
+        self.inner.near(scope, origin, radius, limit).await
+    }
+
+    async fn after(&self, scope: Scope<'_>, since_id: UserId, limit: i64) -> Result<Vec<User>, RepoError> {
+        self.check(Op::After)?;
+        self.inner.after(scope, since_id, limit).await
+    }
+
+    async fn age_histogram(&self, scope: Scope<'_>, buckets: i32) -> Result<AgeHistogram, RepoError> {
+        self.check(Op::AgeHistogram)?;
+        self.inner.age_histogram(scope, buckets).await
+    }
+
+    async fn stream_all(&self, scope: Scope<'_>) -> Result<UserStream, RepoError> {
+        self.check(Op::StreamAll)?;
+        self.inner.stream_all(scope).await
+    }
+
+    async fn insert(&self, scope: Scope<'_>, user: &CreateUser) -> Result<User, RepoError> {
+        self.check(Op::Insert)?;
+        self.inner.insert(scope, user).await
+    }
+
+    async fn insert_many(
+        &self,
+        scope: Scope<'_>,
+        users: &[CreateUser],
+        on_conflict: OnConflict,
+    ) -> Result<BulkInsert, RepoError> {
+        self.check(Op::InsertMany)?;
+        self.inner.insert_many(scope, users, on_conflict).await
+    }
+
+    async fn update(&self, scope: Scope<'_>, id: UserId, user: &UpdateUser) -> Result<Option<User>, RepoError> {
+        self.check(Op::Update)?;
+        self.inner.update(scope, id, user).await
+    }
+
+    async fn delete(&self, scope: Scope<'_>, id: UserId) -> Result<u64, RepoError> {
+        self.check(Op::Delete)?;
+        self.inner.delete(scope, id).await
+    }
+}
@@ -0,0 +1,1196 @@
+//! Tenant-aware access to the `users` table.
+//!
+//! Handlers see storage only as the [`UserRepository`] in `AppState`.  The
+//! server uses [`Postgres`], which checks out a connection per call and runs
+//! it through [`Users`]; [`memory::MemoryUsers`] keeps the table in process
+//! for benchmarks that measure the framework without a database, and
+//! [`mock::MockUsers`] adds scripted failures for testing the handlers.
+//!
+//! [`Users`] carries the request's tenant and applies it according to
+//! `TENANT_SCOPING`:
+//!
+//! * `where` — each statement gets a `tenant_id = $n` predicate (inserts set
+//!   the column), so a handler cannot reach another tenant's rows by
+//!   forgetting a WHERE clause;
+//! * `rls`   — the statements stay unscoped and each call runs in its own
+//!   transaction that first does `SET LOCAL ROLE benchmark_tenant` and
+//!   `SET LOCAL app.tenant_id`; the row-level security policy in `init.sql`
+//!   filters reads and checks writes, and the column default fills
+//!   `tenant_id` on insert.  That costs three extra round trips per call
+//!   (BEGIN, SET, COMMIT), which is part of what this mode measures.
+//!
+//! Without a tenant the statements are the single-tenant originals, byte for
+//! byte, so the default run keeps the query shapes the other four APIs use —
+//! except for `/db` and `/queries`, which read drawn ids unless
+//! `RANDOM_ROW_SELECTION=order-by-random` (see `draws`).
+//!
+//! With `AUDIT=on` every insert, update and delete runs in a transaction that
+//! locks the previous row (`SELECT … FOR UPDATE`), executes the same statement
+//! and records the changed fields in `audit_log`, so the change and its audit
+//! entry commit or roll back together.  With `WEBHOOKS=on` the write is
+//! followed, on the same connection, by its delivery rows (`webhooks`).
+//!
+//! [`UserRepository::stream_all`] (GET /users/export) reads on a connection
+//! of its own, in a `REPEATABLE READ READ ONLY` transaction through a
+//! server-side cursor fetched [`EXPORT_BATCH`] rows at a time: one snapshot
+//! for the whole export, a batch in memory at a time, and no row locks
+//! standing in the way of concurrent writes.
+//!
+//! With `TRANSACTION_PER_REQUEST=on` [`Postgres`] takes its connection from
+//! the request's transaction (`middleware::transaction`) instead, and the
+//! per-call transactions above are left out: their statements, `SET LOCAL`
+//! included, run directly in the request's one.
+
+pub mod memory;
+pub mod mock;
+mod draws;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use futures_util::stream::{self, LocalBoxStream, StreamExt, TryStreamExt};
+use rust_decimal::Decimal;
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::net::IpAddr;
+use std::time::Duration;
+use tokio_postgres::error::SqlState;
+use tokio_postgres::types::ToSql;
+use tokio_postgres::{CancelToken, Client, Error, GenericClient, Row, Transaction};
+
+use crate::config::{Config, RandomRows, TenantScoping};
+use crate::db::{abandon, cancel_on_drop, cancellable, Connection, Pool, PoolError};
+use crate::fields::{Fields, Record};
+use crate::filter::Filter;
+#[cfg(feature = "postgis")]
+use crate::geo::{Nearby, Point};
+use crate::key::{Key, UserId};
+use crate::metadata::Change;
+use crate::middleware::transaction::{self, Checkout};
+use crate::transfer::{Balance, Outcome, Receipt};
+use crate::{row_to_user, timing, webhooks, AgeHistogram, CreateUser, UpdateUser, User};
+#[cfg(feature = "row-encoding")]
+use crate::rowjson;
+#[cfg(feature = "row-encoding")]
+use actix_web::web::Bytes;
+use draws::{IdDraws, MAX_ROUNDS};
+
+/// Rows fetched from the export cursor at a time.
+pub const EXPORT_BATCH: usize = 1000;
+
+/// Users in id order, read as the stream is polled
+/// ([`UserRepository::stream_all`]).
+pub type UserStream = LocalBoxStream<'static, Result<User, RepoError>>;
+
+/// What a bulk insert does with a row whose email is taken
+/// (`?on_conflict=`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OnConflict {
+    /// The whole batch fails on the first taken email, like a single insert.
+    #[default]
+    Error,
+    /// `ON CONFLICT DO NOTHING`: the existing row stays as it is.
+    Skip,
+    /// `ON CONFLICT DO UPDATE`: the existing row takes the new name, age and
+    /// tags.
+    Update,
+}
+
+impl OnConflict {
+    /// The clause appended to the `INSERT`.  Unique emails are case
+    /// insensitive, so the arbiter is the `lower(email)` index.  A `scoped`
+    /// update only overwrites the tenant's own rows; a conflict with another
+    /// tenant's row counts as skipped.
+    pub fn clause(self, scoped: bool) -> &'static str {
+        match (self, scoped) {
+            (OnConflict::Error, _) => "",
+            (OnConflict::Skip, _) => " ON CONFLICT ((lower(email))) DO NOTHING",
+            (OnConflict::Update, false) => {
+                " ON CONFLICT ((lower(email))) DO UPDATE \
+                 SET name = EXCLUDED.name, age = EXCLUDED.age, tags = EXCLUDED.tags"
+            }
+            (OnConflict::Update, true) => {
+                " ON CONFLICT ((lower(email))) DO UPDATE \
+                 SET name = EXCLUDED.name, age = EXCLUDED.age, tags = EXCLUDED.tags \
+                 WHERE users.tenant_id = EXCLUDED.tenant_id"
+            }
+        }
+    }
+
+    /// `insert` (with its clause) wrapped so that it returns one row: the
+    /// rows inserted and the rows updated.  `xmax` is 0 on a freshly
+    /// inserted row only.
+    pub fn counting(self, insert: &str, scoped: bool) -> String {
+        format!(
+            "WITH written AS ({insert}{} RETURNING xmax = 0 AS inserted) \
+             SELECT COUNT(*) FILTER (WHERE inserted), COUNT(*) FILTER (WHERE NOT inserted) FROM written",
+            self.clause(scoped)
+        )
+    }
+}
+
+/// Outcome of a bulk insert: `skipped` rows were left out for a taken email.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct BulkInsert {
+    pub inserted: i64,
+    pub updated: i64,
+    pub skipped: i64,
+}
+
+/// Who a repository call is made for.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Scope<'a> {
+    /// Tenant the call is confined to (`MULTI_TENANT`).
+    pub tenant: Option<&'a str>,
+    /// Client address recorded as the actor of audited writes.
+    pub actor: Option<IpAddr>,
+}
+
+/// Why a repository call failed, as far as the handlers care.
+#[derive(Debug, Clone)]
+pub enum RepoError {
+    /// No connection could be checked out (pool timeout, database down).
+    Pool(String),
+    /// The write broke the unique email constraint.
+    Conflict(String),
+    /// Any other failure of the statement.
+    Query(String),
+}
+
+impl fmt::Display for RepoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RepoError::Pool(msg) | RepoError::Conflict(msg) | RepoError::Query(msg) => f.write_str(msg),
+        }
+    }
+}
+
+impl std::error::Error for RepoError {}
+
+impl RepoError {
+    /// What [`Postgres`] returns when the pool has no connection to give
+    /// within `POOL_TIMEOUT_MS`.
+    pub fn pool_timeout() -> Self {
+        RepoError::Pool("Timeout occurred while waiting for a slot to become available".into())
+    }
+
+    /// What [`Postgres`] returns for a duplicate email (SQLSTATE 23505).
+    pub fn unique_violation() -> Self {
+        RepoError::Conflict(r#"duplicate key value violates unique constraint "users_email_key""#.into())
+    }
+}
+
+impl From<PoolError> for RepoError {
+    fn from(e: PoolError) -> Self {
+        RepoError::Pool(e.to_string())
+    }
+}
+
+impl From<Error> for RepoError {
+    fn from(e: Error) -> Self {
+        // Duplicate email produces a unique-constraint violation (code 23505).
+        if e.code() == Some(&SqlState::UNIQUE_VIOLATION) {
+            RepoError::Conflict(e.to_string())
+        } else {
+            RepoError::Query(e.to_string())
+        }
+    }
+}
+
+/// The `users` operations behind `/db`, `/queries` and `/users`.
+///
+/// Futures are not `Send`: calls run on the Actix worker that received the
+/// request.
+#[async_trait(?Send)]
+pub trait UserRepository: Send + Sync {
+    /// One random user (GET /db).
+    async fn random_one(&self, scope: Scope<'_>) -> Result<Option<User>, RepoError>;
+
+    /// `count` random users (GET /queries).
+    async fn random(&self, scope: Scope<'_>, count: i64) -> Result<Vec<User>, RepoError>;
+
+    /// Row count and last write, the collection version behind the ETag.
+    async fn version(&self, scope: Scope<'_>) -> Result<(i64, Option<DateTime<Utc>>), RepoError>;
+
+    async fn page(&self, scope: Scope<'_>, limit: i64, offset: i64, fields: Fields) -> Result<Vec<Record>, RepoError>;
+
+    async fn all(&self, scope: Scope<'_>, fields: Fields) -> Result<Vec<Record>, RepoError>;
+
+    async fn by_id(&self, scope: Scope<'_>, id: UserId, fields: Fields) -> Result<Option<Record>, RepoError>;
+
+    /// Users that match `filter`, in id order, and how many there are:
+    /// `limit` of them from `offset`, or all without a limit
+    /// (GET /users?metadata.key=value&tag=x).
+    async fn matching(
+        &self,
+        scope: Scope<'_>,
+        filter: &Filter,
+        limit: Option<i64>,
+        offset: i64,
+        fields: Fields,
+    ) -> Result<(i64, Vec<Record>), RepoError>;
+
+    /// The user's metadata; `None` when no such user.
+    async fn metadata(&self, scope: Scope<'_>, id: UserId) -> Result<Option<Value>, RepoError>;
+
+    /// Applies `changes` to the user's metadata and returns the result;
+    /// `None` when no such user.
+    async fn patch_metadata(&self, scope: Scope<'_>, id: UserId, changes: &[Change]) -> Result<Option<Value>, RepoError>;
+
+    /// Moves `amount` from one user's balance to the other's, or neither
+    /// (POST /transfer).
+    async fn transfer(&self, scope: Scope<'_>, from: UserId, to: UserId, amount: Decimal) -> Result<Outcome, RepoError>;
+
+    /// Up to `limit` users within `radius` metres of `origin`, nearest first
+    /// (GET /users/near).  Users without a location are never near, so a
+    /// repository that keeps none finds nobody.
+    #[cfg(feature = "postgis")]
+    async fn near(&self, _scope: Scope<'_>, _origin: Point, _radius: f64, _limit: i64) -> Result<Vec<Nearby>, RepoError> {
+        Ok(Vec::new())
+    }
+
+    /// [`UserRepository::random`] as a JSON array (`row-encoding`).
+    #[cfg(feature = "row-encoding")]
+    async fn random_json(&self, scope: Scope<'_>, count: i64) -> Result<Bytes, RepoError> {
+        Ok(rowjson::value(&self.random(scope, count).await?))
+    }
+
+    /// Every field of [`UserRepository::all`] as a JSON array
+    /// (`row-encoding`).
+    #[cfg(feature = "row-encoding")]
+    async fn all_json(&self, scope: Scope<'_>) -> Result<Bytes, RepoError> {
+        Ok(rowjson::value(&self.all(scope, Fields::ALL).await?))
+    }
+
+    /// Up to `limit` users with an id above `since_id`, in id order
+    /// (GET /users/poll).
+    async fn after(&self, scope: Scope<'_>, since_id: UserId, limit: i64) -> Result<Vec<User>, RepoError>;
+
+    /// Users per age in `buckets` equal-width buckets between the youngest
+    /// and the oldest (GET /users/age-histogram).
+    async fn age_histogram(&self, scope: Scope<'_>, buckets: i32) -> Result<AgeHistogram, RepoError>;
+
+    /// Every user in id order, from one snapshot, read while the stream is
+    /// consumed (GET /users/export).
+    async fn stream_all(&self, scope: Scope<'_>) -> Result<UserStream, RepoError>;
+
+    async fn insert(&self, scope: Scope<'_>, user: &CreateUser) -> Result<User, RepoError>;
+
+    /// Inserts `users` in one statement (POST /users/bulk); a taken email is
+    /// handled as `on_conflict` says.
+    async fn insert_many(
+        &self,
+        scope: Scope<'_>,
+        users: &[CreateUser],
+        on_conflict: OnConflict,
+    ) -> Result<BulkInsert, RepoError>;
+
+    /// Updates only the provided fields; `None` when no such user.
+    async fn update(&self, scope: Scope<'_>, id: UserId, user: &UpdateUser) -> Result<Option<User>, RepoError>;
+
+    /// Number of rows deleted (0 or 1).
+    async fn delete(&self, scope: Scope<'_>, id: UserId) -> Result<u64, RepoError>;
+
+    /// Forgets what was derived from the table's contents; called by
+    /// `/admin/reset` after reseeding.
+    fn reset(&self) {}
+}
+
+/// [`UserRepository`] over the connection pool.
+pub struct Postgres {
+    pool: Pool,
+    scoping: TenantScoping,
+    audit: bool,
+    webhooks: bool,
+    /// Id draws replacing `ORDER BY RANDOM()` (`RANDOM_ROW_SELECTION`).
+    draws: Option<IdDraws>,
+}
+
+impl Postgres {
+    pub fn new(pool: Pool, config: &Config) -> Self {
+        Postgres {
+            pool,
+            scoping: config.tenant_scoping,
+            audit: config.audit,
+            webhooks: config.webhooks,
+            draws: (config.random_rows == RandomRows::IdRange).then(|| {
+                let refresh = config.max_id_refresh_secs;
+                IdDraws::new(config.random_seed, (refresh > 0).then(|| Duration::from_secs(refresh)))
+            }),
+        }
+    }
+
+    fn users<'a>(&'a self, client: &'a mut Checkout, scope: Scope<'a>) -> Users<'a> {
+        Users {
+            in_transaction: client.in_transaction(),
+            client,
+            tenant: scope.tenant,
+            scoping: self.scoping,
+            audit: self.audit,
+            actor: scope.actor,
+            // Draws span the whole table, so they only serve unscoped calls;
+            // a tenant's random rows keep ORDER BY RANDOM().
+            draws: self.draws.as_ref().filter(|_| scope.tenant.is_none()),
+        }
+    }
+
+    /// Enqueues webhook deliveries for a completed write on its connection.
+    async fn notify(&self, client: &Client, scope: Scope<'_>, event: &str, subject: Value) {
+        if self.webhooks {
+            webhooks::record(client, scope.tenant, event, subject).await;
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl UserRepository for Postgres {
+    async fn random_one(&self, scope: Scope<'_>) -> Result<Option<User>, RepoError> {
+        let mut client = transaction::checkout(&self.pool).await?;
+        Ok(self.users(&mut client, scope).random_one().await?)
+    }
+
+    async fn random(&self, scope: Scope<'_>, count: i64) -> Result<Vec<User>, RepoError> {
+        let mut client = transaction::checkout(&self.pool).await?;
+        Ok(self.users(&mut client, scope).random(count).await?)
+    }
+
+    async fn version(&self, scope: Scope<'_>) -> Result<(i64, Option<DateTime<Utc>>), RepoError> {
+        let mut client = transaction::checkout(&self.pool).await?;
+        Ok(self.users(&mut client, scope).version().await?)
+    }
+
+    async fn page(&self, scope: Scope<'_>, limit: i64, offset: i64, fields: Fields) -> Result<Vec<Record>, RepoError> {
+        let mut client = transaction::checkout(&self.pool).await?;
+        Ok(self.users(&mut client, scope).page(limit, offset, fields).await?)
+    }
+
+    async fn all(&self, scope: Scope<'_>, fields: Fields) -> Result<Vec<Record>, RepoError> {
+        let mut client = transaction::checkout(&self.pool).await?;
+        Ok(self.users(&mut client, scope).all(fields).await?)
+    }
+
+    async fn by_id(&self, scope: Scope<'_>, id: UserId, fields: Fields) -> Result<Option<Record>, RepoError> {
+        let mut client = transaction::checkout(&self.pool).await?;
+        Ok(self.users(&mut client, scope).by_id(id, fields).await?)
+    }
+
+    async fn matching(
+        &self,
+        scope: Scope<'_>,
+        filter: &Filter,
+        limit: Option<i64>,
+        offset: i64,
+        fields: Fields,
+    ) -> Result<(i64, Vec<Record>), RepoError> {
+        let mut client = transaction::checkout(&self.pool).await?;
+        Ok(self.users(&mut client, scope).matching(filter, limit, offset, fields).await?)
+    }
+
+    async fn metadata(&self, scope: Scope<'_>, id: UserId) -> Result<Option<Value>, RepoError> {
+        let mut client = transaction::checkout(&self.pool).await?;
+        Ok(self.users(&mut client, scope).metadata(id).await?)
+    }
+
+    async fn patch_metadata(&self, scope: Scope<'_>, id: UserId, changes: &[Change]) -> Result<Option<Value>, RepoError> {
+        let mut client = transaction::checkout(&self.pool).await?;
+        Ok(self.users(&mut client, scope).patch_metadata(id, changes).await?)
+    }
+
+    async fn transfer(&self, scope: Scope<'_>, from: UserId, to: UserId, amount: Decimal) -> Result<Outcome, RepoError> {
+        let mut client = transaction::checkout(&self.pool).await?;
+        Ok(self.users(&mut client, scope).transfer(from, to, amount).await?)
+    }
+
+    #[cfg(feature = "postgis")]
+    async fn near(&self, scope: Scope<'_>, origin: Point, radius: f64, limit: i64) -> Result<Vec<Nearby>, RepoError> {
+        let mut client = transaction::checkout(&self.pool).await?;
+        Ok(self.users(&mut client, scope).near(origin, radius, limit).await?)
+    }
+
+    #[cfg(feature = "row-encoding")]
+    async fn random_json(&self, scope: Scope<'_>, count: i64) -> Result<Bytes, RepoError> {
+        let mut client = transaction::checkout(&self.pool).await?;
+        Ok(rowjson::rows(&self.users(&mut client, scope).random_rows(count).await?))
+    }
+
+    #[cfg(feature = "row-encoding")]
+    async fn all_json(&self, scope: Scope<'_>) -> Result<Bytes, RepoError> {
+        let mut client = transaction::checkout(&self.pool).await?;
+        Ok(rowjson::rows(&self.users(&mut client, scope).all_rows(Fields::ALL).await?))
+    }
+
+    async fn after(&self, scope: Scope<'_>, since_id: UserId, limit: i64) -> Result<Vec<User>, RepoError> {
+        let mut client = transaction::checkout(&self.pool).await?;
+        Ok(self.users(&mut client, scope).after(since_id, limit).await?)
+    }
+
+    async fn age_histogram(&self, scope: Scope<'_>, buckets: i32) -> Result<AgeHistogram, RepoError> {
+        let mut client = transaction::checkout(&self.pool).await?;
+        Ok(self.users(&mut client, scope).age_histogram(buckets).await?)
+    }
+
+    async fn stream_all(&self, scope: Scope<'_>) -> Result<UserStream, RepoError> {
+        // A connection of its own, also with TRANSACTION_PER_REQUEST: the
+        // rows are read while the body is sent, after the handler returned.
+        let export = Export(Some(self.pool.get().await?));
+        let tenant = scope.tenant.map(str::to_owned);
+        timing::db(export.open(self.scoping, tenant.as_deref())).await?;
+        let batches = stream::try_unfold(Some(export), |export| async move {
+            match export {
+                Some(export) => export.next_batch().await,
+                None => Ok(None),
+            }
+        });
+        Ok(batches.map_ok(|users| stream::iter(users.into_iter().map(Ok))).try_flatten().boxed_local())
+    }
+
+    async fn insert(&self, scope: Scope<'_>, user: &CreateUser) -> Result<User, RepoError> {
+        let mut client = transaction::checkout(&self.pool).await?;
+        let user = self.users(&mut client, scope).insert(user).await?;
+        self.notify(&client, scope, "user.created", serde_json::json!(user)).await;
+        Ok(user)
+    }
+
+    async fn insert_many(
+        &self,
+        scope: Scope<'_>,
+        users: &[CreateUser],
+        on_conflict: OnConflict,
+    ) -> Result<BulkInsert, RepoError> {
+        let mut client = transaction::checkout(&self.pool).await?;
+        Ok(self.users(&mut client, scope).insert_many(users, on_conflict).await?)
+    }
+
+    async fn update(&self, scope: Scope<'_>, id: UserId, user: &UpdateUser) -> Result<Option<User>, RepoError> {
+        let mut client = transaction::checkout(&self.pool).await?;
+        let user = self.users(&mut client, scope).update(id, user).await?;
+        if let Some(user) = &user {
+            self.notify(&client, scope, "user.updated", serde_json::json!(user)).await;
+        }
+        Ok(user)
+    }
+
+    async fn delete(&self, scope: Scope<'_>, id: UserId) -> Result<u64, RepoError> {
+        let mut client = transaction::checkout(&self.pool).await?;
+        let deleted = self.users(&mut client, scope).delete(id).await?;
+        if deleted > 0 {
+            self.notify(&client, scope, "user.deleted", serde_json::json!({ "id": id })).await;
+        }
+        Ok(deleted)
+    }
+
+    fn reset(&self) {
+        if let Some(draws) = &self.draws {
+            draws.restart();
+        }
+    }
+}
+
+/// The export transaction's connection; rolled back if the stream is dropped
+/// before the last batch (client gone, or a failed fetch).
+struct Export(Option<Connection>);
+
+impl Export {
+    fn client(&self) -> &Client {
+        self.0.as_ref().expect("export connection taken before drop")
+    }
+
+    /// Opens the snapshot and declares the cursor over the tenant's rows.
+    async fn open(&self, scoping: TenantScoping, tenant: Option<&str>) -> Result<(), Error> {
+        let client = self.client();
+        client.batch_execute("BEGIN ISOLATION LEVEL REPEATABLE READ READ ONLY").await?;
+        match (tenant, scoping) {
+            (Some(tenant), TenantScoping::Where) => {
+                client
+                    .execute(
+                        "DECLARE export NO SCROLL CURSOR FOR \
+                         SELECT id, name, email, age, created_at, tags, status FROM users \
+                         WHERE tenant_id = $1 ORDER BY id",
+                        &[&tenant],
+                    )
+                    .await?;
+            }
+            (tenant, _) => {
+                if let Some(tenant) = tenant {
+                    set_tenant(client, tenant).await?;
+                }
+                client
+                    .batch_execute(
+                        "DECLARE export NO SCROLL CURSOR FOR \
+                         SELECT id, name, email, age, created_at, tags, status FROM users ORDER BY id",
+                    )
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The next batch, and the export itself unless that was the last one.
+    async fn next_batch(self) -> Result<Option<(Vec<User>, Option<Export>)>, RepoError> {
+        let client = self.client();
+        let rows = timing::db(cancellable(client, client.query(&format!("FETCH {EXPORT_BATCH} FROM export"), &[])))
+            .await?;
+        let users: Vec<User> = rows.iter().map(row_to_user).collect();
+        if users.len() < EXPORT_BATCH {
+            self.close().await?;
+            return Ok((!users.is_empty()).then_some((users, None)));
+        }
+        Ok(Some((users, Some(self))))
+    }
+
+    /// Ends the transaction and returns the connection to the pool.
+    async fn close(mut self) -> Result<(), Error> {
+        timing::db(self.client().batch_execute("COMMIT")).await?;
+        drop(self.0.take());
+        Ok(())
+    }
+}
+
+impl Drop for Export {
+    fn drop(&mut self) {
+        if let Some(conn) = self.0.take() {
+            abandon(conn);
+        }
+    }
+}
+
+/// Role the `tenant_isolation` policy applies to.  The connecting user owns
+/// `users` (or is a superuser) and so bypasses row-level security.
+const RLS_ROLE: &str = "benchmark_tenant";
+
+type Param<'p> = &'p (dyn ToSql + Sync);
+
+/// One call's statements on a checked-out connection.
+struct Users<'a> {
+    client: &'a mut Client,
+    /// The call runs inside the request's transaction
+    /// (`TRANSACTION_PER_REQUEST`) and opens none of its own.
+    in_transaction: bool,
+    tenant: Option<&'a str>,
+    scoping: TenantScoping,
+    audit: bool,
+    actor: Option<IpAddr>,
+    draws: Option<&'a IdDraws>,
+}
+
+/// One mutation as seen by [`Users::write`].
+struct Write<'s, 'p> {
+    action: &'static str,
+    /// Row to lock and snapshot before the change; `None` for inserts.
+    target: Option<UserId>,
+    sql: &'s str,
+    params: &'s [Param<'p>],
+    /// `RETURNING` statements yield the new row; others only a count.
+    returning: bool,
+}
+
+/// `params` plus the tenant id, when the statement is the scoped variant.
+fn with_tenant<'p>(params: &[Param<'p>], tenant: &'p Option<&str>) -> Vec<Param<'p>> {
+    let mut all = params.to_vec();
+    if let Some(tenant) = tenant {
+        all.push(tenant);
+    }
+    all
+}
+
+impl<'a> Users<'a> {
+    /// Tenant the SQL itself must filter on (`where` scoping).
+    fn where_tenant(&self) -> Option<&'a str> {
+        self.tenant.filter(|_| self.scoping == TenantScoping::Where)
+    }
+
+    /// Tenant to set on the transaction (`rls` scoping).
+    fn rls_tenant(&self) -> Option<&'a str> {
+        self.tenant.filter(|_| self.scoping == TenantScoping::Rls)
+    }
+
+    /// `global` unless the SQL must filter, else `scoped`, whose last
+    /// parameter is the tenant id appended by [`with_tenant`].
+    fn pick(&self, global: &'static str, scoped: &'static str) -> &'static str {
+        if self.where_tenant().is_some() { scoped } else { global }
+    }
+
+    /// Opens the per-call transaction under the policy's role and tenant.
+    async fn begin<'t>(client: &'t mut Client, tenant: &str) -> Result<Transaction<'t>, Error> {
+        let tx = client.transaction().await?;
+        set_tenant(&tx, tenant).await?;
+        Ok(tx)
+    }
+
+    /// Runs a read, timed for `DB_TIMING` like [`Users::write`].
+    async fn query(&mut self, sql: &str, params: &[Param<'_>]) -> Result<Vec<Row>, Error> {
+        timing::db(self.run_query(sql, params)).await
+    }
+
+    async fn run_query(&mut self, sql: &str, params: &[Param<'_>]) -> Result<Vec<Row>, Error> {
+        let Some(tenant) = self.rls_tenant() else {
+            return cancellable(self.client, self.client.query(sql, params)).await;
+        };
+        if self.in_transaction {
+            set_tenant(&*self.client, tenant).await?;
+            let rows = cancellable(self.client, self.client.query(sql, params)).await?;
+            self.client.batch_execute("RESET ROLE").await?;
+            return Ok(rows);
+        }
+        let token = self.client.cancel_token();
+        let tx = Self::begin(self.client, tenant).await?;
+        let rows = cancel_on_drop(token, tx.query(sql, params)).await?;
+        tx.commit().await?;
+        Ok(rows)
+    }
+
+    /// Runs a mutation, inside a transaction when RLS or auditing needs one.
+    /// Returns the affected row count and, for `RETURNING` statements, the
+    /// new row.
+    async fn write(&mut self, write: Write<'_, '_>) -> Result<(u64, Option<User>), Error> {
+        timing::db(self.run_write(write)).await
+    }
+
+    async fn run_write(&mut self, write: Write<'_, '_>) -> Result<(u64, Option<User>), Error> {
+        let token = self.client.cancel_token();
+        let audit = self.audit.then_some(Audit { actor: self.actor, tenant: self.tenant });
+        let where_tenant = self.where_tenant();
+        if self.in_transaction {
+            let Some(tenant) = self.rls_tenant() else {
+                return apply(&*self.client, token, &write, where_tenant, audit).await;
+            };
+            set_tenant(&*self.client, tenant).await?;
+            let result = apply(&*self.client, token, &write, where_tenant, audit).await?;
+            self.client.batch_execute("RESET ROLE").await?;
+            return Ok(result);
+        }
+        if audit.is_none() && self.rls_tenant().is_none() {
+            return apply(&*self.client, token, &write, where_tenant, audit).await;
+        }
+        let tx = match self.rls_tenant() {
+            Some(tenant) => Self::begin(self.client, tenant).await?,
+            None => self.client.transaction().await?,
+        };
+        let result = apply(&tx, token, &write, where_tenant, audit).await?;
+        tx.commit().await?;
+        Ok(result)
+    }
+
+    /// One random user (GET /db).
+    async fn random_one(&mut self) -> Result<Option<User>, Error> {
+        if let Some(draws) = self.draws {
+            return Ok(self.drawn(draws, 1).await?.first().map(row_to_user));
+        }
+        let sql = self.pick(
+            "SELECT id, name, email, age, created_at, tags, status \
+             FROM users ORDER BY RANDOM() LIMIT 1",
+            "SELECT id, name, email, age, created_at, tags, status \
+             FROM users WHERE tenant_id = $1 ORDER BY RANDOM() LIMIT 1",
+        );
+        let tenant = self.where_tenant();
+        let rows = self.query(sql, &with_tenant(&[], &tenant)).await?;
+        Ok(rows.first().map(row_to_user))
+    }
+
+    /// `count` random users (GET /queries).
+    async fn random(&mut self, count: i64) -> Result<Vec<User>, Error> {
+        Ok(self.random_rows(count).await?.iter().map(row_to_user).collect())
+    }
+
+    /// The rows behind [`Users::random`], for encoders that skip [`User`].
+    async fn random_rows(&mut self, count: i64) -> Result<Vec<Row>, Error> {
+        if let Some(draws) = self.draws {
+            return self.drawn(draws, count as usize).await;
+        }
+        let sql = self.pick(
+            "SELECT id, name, email, age, created_at, tags, status \
+             FROM users ORDER BY RANDOM() LIMIT $1",
+            "SELECT id, name, email, age, created_at, tags, status \
+             FROM users WHERE tenant_id = $2 ORDER BY RANDOM() LIMIT $1",
+        );
+        let tenant = self.where_tenant();
+        self.query(sql, &with_tenant(&[&count], &tenant)).await
+    }
+
+    /// Rows of up to `count` distinct users read by drawn ids, in draw order.
+    async fn drawn(&mut self, draws: &IdDraws, count: usize) -> Result<Vec<Row>, Error> {
+        let max = match draws.max_id() {
+            Some(max) => max,
+            None => {
+                let rows = self.query("SELECT COALESCE(MAX(id), 0)::bigint FROM users", &[]).await?;
+                let max: i64 = rows[0].get(0);
+                draws.set_max_id(max);
+                max
+            }
+        };
+        let count = count.min(max.max(0) as usize);
+        let mut users = Vec::with_capacity(count);
+        let mut seen = HashSet::new();
+        for _ in 0..MAX_ROUNDS {
+            let mut draw = Vec::with_capacity(count - users.len());
+            while draw.len() < count - users.len() && seen.len() < max as usize {
+                let Some(id) = UserId::drawn(draws.draw(max)) else { break };
+                if seen.insert(id) {
+                    draw.push(id);
+                }
+            }
+            if draw.is_empty() {
+                break;
+            }
+            let rows = match draw[..] {
+                [id] => {
+                    let sql = "SELECT id, name, email, age, created_at, tags, status FROM users WHERE id = $1";
+                    self.query(sql, &[&id]).await?
+                }
+                _ => {
+                    let sql = "SELECT id, name, email, age, created_at, tags, status FROM users WHERE id = ANY($1)";
+                    self.query(sql, &[&draw]).await?
+                }
+            };
+            let mut found: HashMap<UserId, Row> = rows.into_iter().map(|row| (row.get("id"), row)).collect();
+            users.extend(draw.iter().filter_map(|id| found.remove(id)));
+        }
+        Ok(users)
+    }
+
+    /// Row count and last write, the collection version behind the ETag.
+    async fn version(&mut self) -> Result<(i64, Option<DateTime<Utc>>), Error> {
+        let sql = self.pick(
+            "SELECT COUNT(*)::bigint, MAX(updated_at) FROM users",
+            "SELECT COUNT(*)::bigint, MAX(updated_at) FROM users WHERE tenant_id = $1",
+        );
+        let tenant = self.where_tenant();
+        let rows = self.query(sql, &with_tenant(&[], &tenant)).await?;
+        // An aggregate without GROUP BY always returns exactly one row.
+        Ok((rows[0].get(0), rows[0].get(1)))
+    }
+
+    async fn page(&mut self, limit: i64, offset: i64, fields: Fields) -> Result<Vec<Record>, Error> {
+        let sql = fields.project(self.pick(
+            "SELECT id, name, email, age, created_at, tags, status \
+             FROM users ORDER BY id LIMIT $1 OFFSET $2",
+            "SELECT id, name, email, age, created_at, tags, status \
+             FROM users WHERE tenant_id = $3 ORDER BY id LIMIT $1 OFFSET $2",
+        ));
+        let tenant = self.where_tenant();
+        let rows = self.query(&sql, &with_tenant(&[&limit, &offset], &tenant)).await?;
+        Ok(rows.into_iter().map(|row| Record::from_row(row, fields)).collect())
+    }
+
+    async fn all(&mut self, fields: Fields) -> Result<Vec<Record>, Error> {
+        Ok(self.all_rows(fields).await?.into_iter().map(|row| Record::from_row(row, fields)).collect())
+    }
+
+    /// The rows behind [`Users::all`], for encoders that skip [`Record`].
+    async fn all_rows(&mut self, fields: Fields) -> Result<Vec<Row>, Error> {
+        let sql = fields.project(self.pick(
+            "SELECT id, name, email, age, created_at, tags, status FROM users ORDER BY id",
+            "SELECT id, name, email, age, created_at, tags, status FROM users WHERE tenant_id = $1 ORDER BY id",
+        ));
+        let tenant = self.where_tenant();
+        self.query(&sql, &with_tenant(&[], &tenant)).await
+    }
+
+    async fn by_id(&mut self, id: UserId, fields: Fields) -> Result<Option<Record>, Error> {
+        let sql = fields.project(self.pick(
+            "SELECT id, name, email, age, created_at, tags, status FROM users WHERE id = $1",
+            "SELECT id, name, email, age, created_at, tags, status FROM users WHERE id = $1 AND tenant_id = $2",
+        ));
+        let tenant = self.where_tenant();
+        let rows = self.query(&sql, &with_tenant(&[&id], &tenant)).await?;
+        Ok(rows.into_iter().next().map(|row| Record::from_row(row, fields)))
+    }
+
+    /// Two statements: the count, then the rows.  Only the given filters
+    /// make it into the `WHERE` (an empty `@>` would still scan the GIN
+    /// index).  `LIMIT NULL` is no limit.
+    async fn matching(
+        &mut self,
+        filter: &Filter,
+        limit: Option<i64>,
+        offset: i64,
+        fields: Fields,
+    ) -> Result<(i64, Vec<Record>), Error> {
+        let tenant = self.where_tenant();
+        let mut params: Vec<Param<'_>> = Vec::new();
+        let mut conditions = Vec::new();
+        if let Some(metadata) = &filter.metadata {
+            params.push(metadata);
+            conditions.push(format!("metadata @> ${}", params.len()));
+        }
+        if !filter.tags.is_empty() {
+            params.push(&filter.tags);
+            conditions.push(format!("tags @> ${}::text[]", params.len()));
+        }
+        if let Some(status) = &filter.status {
+            params.push(status);
+            conditions.push(format!("status = ${}", params.len()));
+        }
+        if let Some(tenant) = &tenant {
+            params.push(tenant);
+            conditions.push(format!("tenant_id = ${}", params.len()));
+        }
+        let conditions = if conditions.is_empty() { "TRUE".to_owned() } else { conditions.join(" AND ") };
+        let count = format!("SELECT COUNT(*)::bigint FROM users WHERE {conditions}");
+        let total: i64 = self.query(&count, &params).await?[0].get(0);
+
+        let sql = format!(
+            "SELECT {} FROM users WHERE {conditions} ORDER BY id LIMIT ${} OFFSET ${}",
+            fields.columns(),
+            params.len() + 1,
+            params.len() + 2
+        );
+        params.extend([&limit as Param<'_>, &offset]);
+        let rows = self.query(&sql, &params).await?;
+        Ok((total, rows.into_iter().map(|row| Record::from_row(row, fields)).collect()))
+    }
+
+    async fn metadata(&mut self, id: UserId) -> Result<Option<Value>, Error> {
+        let sql = self.pick(
+            "SELECT metadata FROM users WHERE id = $1",
+            "SELECT metadata FROM users WHERE id = $1 AND tenant_id = $2",
+        );
+        let tenant = self.where_tenant();
+        let rows = self.query(sql, &with_tenant(&[&id], &tenant)).await?;
+        Ok(rows.first().map(|row| row.get(0)))
+    }
+
+    /// One `UPDATE`, the changes nested in key order:
+    /// `jsonb_set(jsonb_set(metadata, $2, $3, true) #- $4, …)`.  Not audited
+    /// and without webhook deliveries (see `metadata`).
+    async fn patch_metadata(&mut self, id: UserId, changes: &[Change]) -> Result<Option<Value>, Error> {
+        let mut params: Vec<Param<'_>> = vec![&id];
+        let mut expr = String::from("metadata");
+        for change in changes {
+            params.push(&change.path);
+            let path = params.len();
+            expr = match &change.value {
+                Some(value) => {
+                    params.push(value);
+                    format!("jsonb_set({expr}, ${path}::text[], ${}::jsonb, true)", path + 1)
+                }
+                None => format!("({expr} #- ${path}::text[])"),
+            };
+        }
+        let tenant = self.where_tenant();
+        let scoped = match tenant {
+            Some(_) => format!(" AND tenant_id = ${}", params.len() + 1),
+            None => String::new(),
+        };
+        let sql = format!("UPDATE users SET metadata = {expr} WHERE id = $1{scoped} RETURNING metadata");
+        let rows = self.query(&sql, &with_tenant(&params, &tenant)).await?;
+        Ok(rows.first().map(|row| row.get(0)))
+    }
+
+    /// Runs [`move_balance`] in a transaction of its own, or in the
+    /// request's, under the policy's role with RLS.  Not audited and without
+    /// webhook deliveries (see `transfer`).
+    async fn transfer(&mut self, from: UserId, to: UserId, amount: Decimal) -> Result<Outcome, Error> {
+        timing::db(self.run_transfer(from, to, amount)).await
+    }
+
+    async fn run_transfer(&mut self, from: UserId, to: UserId, amount: Decimal) -> Result<Outcome, Error> {
+        let where_tenant = self.where_tenant();
+        if self.in_transaction {
+            let Some(tenant) = self.rls_tenant() else {
+                return move_balance(&*self.client, from, to, amount, where_tenant).await;
+            };
+            set_tenant(&*self.client, tenant).await?;
+            let outcome = move_balance(&*self.client, from, to, amount, where_tenant).await?;
+            self.client.batch_execute("RESET ROLE").await?;
+            return Ok(outcome);
+        }
+        let token = self.client.cancel_token();
+        let tx = match self.rls_tenant() {
+            Some(tenant) => Self::begin(self.client, tenant).await?,
+            None => self.client.transaction().await?,
+        };
+        let outcome = cancel_on_drop(token, move_balance(&tx, from, to, amount, where_tenant)).await?;
+        tx.commit().await?;
+        Ok(outcome)
+    }
+
+    /// One statement: `ST_DWithin` picks the rows by the GiST index and
+    /// `ST_Distance` orders them, both on the spheroid.
+    #[cfg(feature = "postgis")]
+    async fn near(&mut self, origin: Point, radius: f64, limit: i64) -> Result<Vec<Nearby>, Error> {
+        let sql = self.pick(
+            "SELECT id, name, location, ST_Distance(location, origin) AS distance \
+             FROM users, (SELECT ST_SetSRID(ST_MakePoint($2, $1), 4326)::geography AS origin) o \
+             WHERE ST_DWithin(location, origin, $3) \
+             ORDER BY distance, id LIMIT $4",
+            "SELECT id, name, location, ST_Distance(location, origin) AS distance \
+             FROM users, (SELECT ST_SetSRID(ST_MakePoint($2, $1), 4326)::geography AS origin) o \
+             WHERE ST_DWithin(location, origin, $3) AND tenant_id = $5 \
+             ORDER BY distance, id LIMIT $4",
+        );
+        let tenant = self.where_tenant();
+        let rows = self.query(sql, &with_tenant(&[&origin.lat, &origin.lon, &radius, &limit], &tenant)).await?;
+        Ok(rows
+            .iter()
+            .map(|row| Nearby { id: row.get(0), name: row.get(1), location: row.get(2), distance: row.get(3) })
+            .collect())
+    }
+
+    async fn after(&mut self, since_id: UserId, limit: i64) -> Result<Vec<User>, Error> {
+        let sql = self.pick(
+            "SELECT id, name, email, age, created_at, tags, status \
+             FROM users WHERE id > $1 ORDER BY id LIMIT $2",
+            "SELECT id, name, email, age, created_at, tags, status \
+             FROM users WHERE id > $1 AND tenant_id = $3 ORDER BY id LIMIT $2",
+        );
+        let tenant = self.where_tenant();
+        let rows = self.query(sql, &with_tenant(&[&since_id, &limit], &tenant)).await?;
+        Ok(rows.iter().map(row_to_user).collect())
+    }
+
+    /// One scan: window aggregates put the table's age range on every row,
+    /// `width_bucket` places each age, and the groups come back one row per
+    /// non-empty bucket, plus a NULL bucket for users without an age.
+    /// `numeric` keeps the bucket edges exact.
+    async fn age_histogram(&mut self, buckets: i32) -> Result<AgeHistogram, Error> {
+        let sql = self.pick(
+            "SELECT width_bucket(age::numeric, lo, hi, $1) AS bucket, COUNT(*)::bigint, MIN(lo), MIN(hi) - 1 \
+             FROM (SELECT age, MIN(age) OVER () AS lo, MAX(age) OVER () + 1 AS hi FROM users) ages \
+             GROUP BY bucket ORDER BY bucket",
+            "SELECT width_bucket(age::numeric, lo, hi, $1) AS bucket, COUNT(*)::bigint, MIN(lo), MIN(hi) - 1 \
+             FROM (SELECT age, MIN(age) OVER () AS lo, MAX(age) OVER () + 1 AS hi \
+                   FROM users WHERE tenant_id = $2) ages \
+             GROUP BY bucket ORDER BY bucket",
+        );
+        let tenant = self.where_tenant();
+        let rows = self.query(sql, &with_tenant(&[&buckets], &tenant)).await?;
+        let mut histogram =
+            AgeHistogram { min: None, max: None, counts: vec![0; buckets as usize], unknown: 0 };
+        for row in rows {
+            match row.get::<_, Option<i32>>(0) {
+                Some(bucket) => {
+                    histogram.counts[bucket as usize - 1] = row.get(1);
+                    histogram.min = row.get(2);
+                    histogram.max = row.get(3);
+                }
+                None => histogram.unknown = row.get(1),
+            }
+        }
+        Ok(histogram)
+    }
+
+    async fn insert(&mut self, user: &CreateUser) -> Result<User, Error> {
+        let sql = self.pick(
+            "INSERT INTO users (name, email, age, tags) \
+             VALUES ($1, $2, $3, $4) \
+             RETURNING id, name, email, age, created_at, tags, status",
+            "INSERT INTO users (name, email, age, tags, tenant_id) \
+             VALUES ($1, $2, $3, $4, $5) \
+             RETURNING id, name, email, age, created_at, tags, status",
+        );
+        let tenant = self.where_tenant();
+        let params = with_tenant(&[&user.name, &user.email, &user.age, &user.tags], &tenant);
+        let write = Write { action: "create", target: None, sql, params: &params, returning: true };
+        let (_, row) = self.write(write).await?;
+        // INSERT … VALUES with RETURNING yields exactly the inserted row.
+        Ok(row.expect("INSERT … RETURNING returned no row"))
+    }
+
+    /// One `INSERT … SELECT FROM UNNEST` for the whole batch.  Not audited
+    /// and without webhook deliveries: it is the ingestion path.
+    async fn insert_many(&mut self, users: &[CreateUser], on_conflict: OnConflict) -> Result<BulkInsert, Error> {
+        // DO UPDATE cannot touch a row twice in one statement: of repeated
+        // emails only the last is written, and the others count as skipped.
+        let mut batch: Vec<&CreateUser> = users.iter().collect();
+        if on_conflict == OnConflict::Update {
+            let mut seen = HashSet::new();
+            batch.reverse();
+            batch.retain(|user| seen.insert(user.email.as_str()));
+            batch.reverse();
+        }
+        let names: Vec<&str> = batch.iter().map(|u| u.name.as_str()).collect();
+        let emails: Vec<&str> = batch.iter().map(|u| u.email.as_str()).collect();
+        let ages: Vec<Option<i32>> = batch.iter().map(|u| u.age).collect();
+        // UNNEST flattens a text[][] (and its rows would have to be of one
+        // length): each user's tags travel as one jsonb array instead.
+        let tags: Vec<Value> = batch.iter().map(|u| Value::from(u.tags.clone())).collect();
+
+        let insert = self.pick(
+            "INSERT INTO users (name, email, age, tags) \
+             SELECT name, email, age, ARRAY(SELECT jsonb_array_elements_text(tags)) \
+             FROM UNNEST($1::varchar[], $2::varchar[], $3::int[], $4::jsonb[]) AS u(name, email, age, tags)",
+            "INSERT INTO users (name, email, age, tags, tenant_id) \
+             SELECT name, email, age, ARRAY(SELECT jsonb_array_elements_text(tags)), $5 \
+             FROM UNNEST($1::varchar[], $2::varchar[], $3::int[], $4::jsonb[]) AS u(name, email, age, tags)",
+        );
+        let sql = on_conflict.counting(insert, self.tenant.is_some());
+        let tenant = self.where_tenant();
+        let rows = self.query(&sql, &with_tenant(&[&names, &emails, &ages, &tags], &tenant)).await?;
+        let (inserted, updated): (i64, i64) = (rows[0].get(0), rows[0].get(1));
+        Ok(BulkInsert { inserted, updated, skipped: users.len() as i64 - inserted - updated })
+    }
+
+    /// Updates only the provided fields; `None` when no such user.
+    async fn update(&mut self, id: UserId, user: &UpdateUser) -> Result<Option<User>, Error> {
+        // COALESCE updates only provided fields in a single query — the same
+        // SQL pattern used by all 5 frameworks for fair comparison.
+        let sql = self.pick(
+            "UPDATE users \
+             SET name  = COALESCE($1, name), \
+                 email = COALESCE($2, email), \
+                 age   = COALESCE($3, age), \
+                 tags  = COALESCE($5, tags), \
+                 status = COALESCE($6, status) \
+             WHERE id = $4 \
+             RETURNING id, name, email, age, created_at, tags, status",
+            "UPDATE users \
+             SET name  = COALESCE($1, name), \
+                 email = COALESCE($2, email), \
+                 age   = COALESCE($3, age), \
+                 tags  = COALESCE($5, tags), \
+                 status = COALESCE($6, status) \
+             WHERE id = $4 AND tenant_id = $7 \
+             RETURNING id, name, email, age, created_at, tags, status",
+        );
+        let tenant = self.where_tenant();
+        let params = with_tenant(&[&user.name, &user.email, &user.age, &id, &user.tags, &user.status], &tenant);
+        let write = Write { action: "update", target: Some(id), sql, params: &params, returning: true };
+        Ok(self.write(write).await?.1)
+    }
+
+    /// Number of rows deleted (0 or 1).
+    async fn delete(&mut self, id: UserId) -> Result<u64, Error> {
+        let sql = self.pick(
+            "DELETE FROM users WHERE id = $1",
+            "DELETE FROM users WHERE id = $1 AND tenant_id = $2",
+        );
+        let tenant = self.where_tenant();
+        let params = with_tenant(&[&id], &tenant);
+        let write = Write { action: "delete", target: Some(id), sql, params: &params, returning: false };
+        Ok(self.write(write).await?.0)
+    }
+}
+
+/// Puts the rest of the transaction under the policy's role and tenant.
+/// Inside the request's transaction the role is reset after the call, so
+/// webhook deliveries and later statements run as the owner again.
+async fn set_tenant<C: GenericClient>(client: &C, tenant: &str) -> Result<(), Error> {
+    // Tenant ids are validated to [A-Za-z0-9_-], so quoting is safe here;
+    // SET does not take bind parameters.
+    client.batch_execute(&format!("SET LOCAL ROLE {RLS_ROLE}; SET LOCAL app.tenant_id = '{tenant}'")).await
+}
+
+/// Audit details carried into [`apply`] while the transaction borrows the
+/// client.
+#[derive(Clone, Copy)]
+struct Audit<'a> {
+    actor: Option<IpAddr>,
+    tenant: Option<&'a str>,
+}
+
+/// Executes `write` on `client`, locking and snapshotting the row first and
+/// recording the change afterwards when audited.
+async fn apply<C: GenericClient>(
+    client: &C,
+    token: CancelToken,
+    write: &Write<'_, '_>,
+    where_tenant: Option<&str>,
+    audit: Option<Audit<'_>>,
+) -> Result<(u64, Option<User>), Error> {
+    let before = match write.target.filter(|_| audit.is_some()) {
+        Some(id) => {
+            let sql = if where_tenant.is_some() {
+                "SELECT id, name, email, age, created_at, tags, status FROM users \
+                 WHERE id = $1 AND tenant_id = $2 FOR UPDATE"
+            } else {
+                "SELECT id, name, email, age, created_at, tags, status FROM users WHERE id = $1 FOR UPDATE"
+            };
+            client.query_opt(sql, &with_tenant(&[&id], &where_tenant)).await?.as_ref().map(row_to_user)
+        }
+        None => None,
+    };
+
+    let (affected, after) = if write.returning {
+        let row = cancel_on_drop(token, client.query_opt(write.sql, write.params)).await?;
+        (u64::from(row.is_some()), row.as_ref().map(row_to_user))
+    } else {
+        (cancel_on_drop(token, client.execute(write.sql, write.params)).await?, None)
+    };
+
+    if let Some(audit) = audit.filter(|_| affected > 0) {
+        let entity_id = after.as_ref().or(before.as_ref()).map(|u| u.id);
+        let (before, after) = diff(before.as_ref(), after.as_ref());
+        client
+            .execute(
+                "INSERT INTO audit_log (actor, tenant_id, action, entity, entity_id, before, after) \
+                 VALUES ($1, $2, $3, 'user', $4, $5, $6)",
+                &[
+                    &audit.actor.map(|ip| ip.to_string()),
+                    &audit.tenant,
+                    &write.action,
+                    &entity_id,
+                    &before,
+                    &after,
+                ],
+            )
+            .await?;
+    }
+    Ok((affected, after))
+}
+
+/// Locks both users in id order, checks them, then moves `amount` with one
+/// UPDATE.  Nothing is written unless both exist and `from` can pay; the
+/// `balance >= 0` constraint of `init.sql` backs the check.
+async fn move_balance<C: GenericClient>(
+    client: &C,
+    from: UserId,
+    to: UserId,
+    amount: Decimal,
+    where_tenant: Option<&str>,
+) -> Result<Outcome, Error> {
+    let ids = vec![from, to];
+    let sql = if where_tenant.is_some() {
+        "SELECT id, balance FROM users WHERE id = ANY($1) AND tenant_id = $2 ORDER BY id FOR UPDATE"
+    } else {
+        "SELECT id, balance FROM users WHERE id = ANY($1) ORDER BY id FOR UPDATE"
+    };
+    let locked = client.query(sql, &with_tenant(&[&ids], &where_tenant)).await?;
+    let balance = |id: UserId| {
+        let row = locked.iter().find(|row| row.get::<_, UserId>(0) == id)?;
+        Some(row.get::<_, Decimal>(1))
+    };
+    let (Some(available), Some(credited)) = (balance(from), balance(to)) else {
+        return Ok(Outcome::NotFound);
+    };
+    if available < amount {
+        return Ok(Outcome::InsufficientFunds);
+    }
+
+    client
+        .execute(
+            "UPDATE users SET balance = balance + CASE WHEN id = $1 THEN -$3::numeric ELSE $3::numeric END \
+             WHERE id IN ($1, $2)",
+            &[&from, &to, &amount],
+        )
+        .await?;
+    Ok(Outcome::Done(Receipt {
+        amount,
+        from: Balance { id: from, balance: available - amount },
+        to: Balance { id: to, balance: credited + amount },
+    }))
+}
+
+/// Changed fields only: `before` holds their old values and `after` the new
+/// ones; a side without a row is NULL.
+fn diff(before: Option<&User>, after: Option<&User>) -> (Option<Value>, Option<Value>) {
+    let object = |user: Option<&User>| match user.map(serde_json::to_value) {
+        Some(Ok(Value::Object(fields))) => Some(fields),
+        _ => None,
+    };
+    match (object(before), object(after)) {
+        (Some(old), Some(new)) => {
+            let (mut removed, mut added) = (Map::new(), Map::new());
+            for (key, value) in new {
+                if old.get(&key) != Some(&value) {
+                    removed.insert(key.clone(), old.get(&key).cloned().unwrap_or(Value::Null));
+                    added.insert(key, value);
+                }
+            }
+            (Some(Value::Object(removed)), Some(Value::Object(added)))
+        }
+        (old, new) => (old.map(Value::Object), new.map(Value::Object)),
+    }
+}
@@ -0,0 +1,41 @@
+//! Routing benchmark: router lookup cost as the route table grows.
+//!
+//! `GET /deep/{a}/{b}/{c}/{d}/{e}` is always registered and echoes its five
+//! parameters, the cost of matching and extracting a long pattern.
+//!
+//! With `ROUTING_BENCH=on` the [`GENERATED_ROUTES`] parameterized routes
+//! written by `build.rs` are registered too, after every other route: four
+//! shapes under `/api/v1..v3/`, from [`FIRST_ROUTE`]
+//! (`/api/v1/users0/{id}`) to [`LAST_ROUTE`]
+//! (`/api/v2/orgs/{org}/comments499/{id}`).  Each answers with the pattern it
+//! matched and its parameters.  Actix tries resources in registration order,
+//! so a request for the last generated route pays a lookup over the whole
+//! table, while one for the first pays the existing routes only; the usual
+//! endpoints, registered earlier, are unaffected.
+
+use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
+use std::collections::BTreeMap;
+
+include!(concat!(env!("OUT_DIR"), "/routes.rs"));
+
+/// Handler of every generated route.
+async fn generated(req: HttpRequest) -> impl Responder {
+    let params: BTreeMap<&str, &str> = req.match_info().iter().collect();
+    HttpResponse::Ok().json(serde_json::json!({ "route": req.match_pattern(), "params": params }))
+}
+
+/// GET /deep/{a}/{b}/{c}/{d}/{e}
+#[get("/deep/{a}/{b}/{c}/{d}/{e}")]
+async fn deep(path: web::Path<(String, String, String, String, String)>) -> impl Responder {
+    let (a, b, c, d, e) = path.into_inner();
+    HttpResponse::Ok().json(serde_json::json!({ "a": a, "b": b, "c": c, "d": d, "e": e }))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(deep);
+}
+
+/// Registers the generated routes; called last in `app()`.
+pub fn configure_generated(cfg: &mut web::ServiceConfig) {
+    register_generated(cfg);
+}
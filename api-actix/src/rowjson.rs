@@ -0,0 +1,77 @@
+//! Direct row encoding (`row-encoding` feature): GET /queries and GET /users
+//! write their JSON array straight from the `tokio_postgres::Row`s into one
+//! `BytesMut` per request, without building a [`User`](crate::User) (or
+//! [`Record`](crate::fields::Record)) per row first.
+//!
+//! Only the plain JSON body takes this path: other encodings, the envelope,
+//! pretty printing, `?fields=`, pagination, HAL and JSON:API keep the
+//! `Negotiated` path.  The bytes are the same as serde's — keys from
+//! `fields::KEYS`, strings escaped and timestamps formatted by serde_json and
+//! chrono — so both paths answer with identical bodies and ETags.  The
+//! repositories that keep users in memory encode the `Vec<User>` they hold
+//! (see `UserRepository::random_json`).
+
+use bytes::{BufMut, Bytes, BytesMut};
+use serde::Serialize;
+use tokio_postgres::Row;
+
+use crate::fields::KEYS;
+use crate::key::UserId;
+use crate::status::Status;
+use crate::timestamp::Timestamp;
+
+/// Initial buffer space per row; a typical user object takes about 100 bytes.
+const ROW_CAPACITY: usize = 128;
+
+/// `rows` (every user column, in `SELECT id, name, email, age, created_at,
+/// tags, status` order) as a JSON array of user objects.
+pub fn rows(rows: &[Row]) -> Bytes {
+    let mut buf = BytesMut::with_capacity(2 + rows.len() * ROW_CAPACITY);
+    buf.put_u8(b'[');
+    for (i, row) in rows.iter().enumerate() {
+        if i > 0 {
+            buf.put_u8(b',');
+        }
+        write_row(&mut buf, row);
+    }
+    buf.put_u8(b']');
+    buf.freeze()
+}
+
+fn write_row(buf: &mut BytesMut, row: &Row) {
+    key(buf, 0, b'{');
+    write_json(buf, &row.get::<_, UserId>(0));
+    key(buf, 1, b',');
+    write_json(buf, row.get::<_, &str>(1));
+    key(buf, 2, b',');
+    write_json(buf, row.get::<_, &str>(2));
+    key(buf, 3, b',');
+    write_json(buf, &row.get::<_, Option<i32>>(3));
+    key(buf, 4, b',');
+    write_json(buf, &Timestamp(&row.get(4)));
+    key(buf, 5, b',');
+    write_json(buf, &row.get::<_, Vec<&str>>(5));
+    key(buf, 6, b',');
+    write_json(buf, &row.get::<_, Status>(6));
+    buf.put_u8(b'}');
+}
+
+/// `sep` followed by `"KEYS[i]":`; the keys need no escaping.
+fn key(buf: &mut BytesMut, i: usize, sep: u8) {
+    buf.put_u8(sep);
+    buf.put_u8(b'"');
+    buf.put_slice(KEYS[i].as_bytes());
+    buf.put_slice(b"\":");
+}
+
+fn write_json<T: Serialize + ?Sized>(buf: &mut BytesMut, value: &T) {
+    // Writing into memory cannot fail, and these values always serialize.
+    serde_json::to_writer(buf.writer(), value).expect("JSON into a buffer");
+}
+
+/// `value` encoded as JSON into a fresh buffer, for repositories without rows.
+pub fn value<T: Serialize>(value: &T) -> Bytes {
+    let mut buf = BytesMut::new();
+    write_json(&mut buf, value);
+    buf.freeze()
+}
@@ -0,0 +1,223 @@
+//! Periodic background refreshes (`SCHEDULER_INTERVAL_SECS` > 0 enables them).
+//!
+//! Two tasks run every interval, each delayed by a random jitter of up to
+//! `SCHEDULER_JITTER_MS` so they do not fire in lockstep:
+//!
+//! * `cached_users` — reloads the full user list into memory and GET
+//!   /cached-users serves that snapshot;
+//! * `user_stats`   — `REFRESH MATERIALIZED VIEW CONCURRENTLY user_stats`,
+//!   which GET /users/stats reads.
+//!
+//! A tick that arrives while the previous run of the same task is still in
+//! progress is skipped rather than queued, so a slow database cannot make runs
+//! pile up.  Outcomes are counted in `scheduled_refreshes_total`.
+//!
+//! The snapshot is double-buffered: a refresh builds the next one completely,
+//! serialized once into `Bytes` by default, and swaps it in under the write
+//! lock, so readers only ever clone an `Arc` and a reference-counted buffer.
+//! `CACHED_USERS_ENCODING=per-request` keeps the decoded list instead and
+//! serializes it on every request, the naive cache to measure against.
+
+use actix_web::web::Bytes;
+use actix_web::{get, web, HttpResponse, Responder};
+use chrono::{DateTime, Utc};
+use std::hash::{BuildHasher, RandomState};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::config::CachedEncoding;
+use crate::db::cancellable;
+use crate::metrics::Metrics;
+use crate::{AppState, User};
+
+/// Last snapshot of the user list, shared by every worker.
+#[derive(Default)]
+pub struct UsersSnapshot(RwLock<Option<Arc<Snapshot>>>);
+
+pub struct Snapshot {
+    body: Body,
+    refreshed_at: DateTime<Utc>,
+}
+
+enum Body {
+    Encoded(Bytes),
+    Users(Vec<User>),
+}
+
+impl UsersSnapshot {
+    fn get(&self) -> Option<Arc<Snapshot>> {
+        self.0.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    fn set(&self, snapshot: Snapshot) {
+        *self.0.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(snapshot));
+    }
+
+    /// Forgets the snapshot, if any; `/cached-users` answers 503 until the
+    /// next refresh.
+    pub fn clear(&self) -> bool {
+        self.0.write().unwrap_or_else(|e| e.into_inner()).take().is_some()
+    }
+}
+
+/// GET /cached-users  — the user list as of the last refresh, or 503 before
+/// the first one completes.
+#[get("/cached-users")]
+async fn cached_users(data: web::Data<AppState>) -> impl Responder {
+    let Some(snapshot) = data.users_snapshot.get() else {
+        return HttpResponse::ServiceUnavailable()
+            .json(serde_json::json!({ "error": "Snapshot not ready" }));
+    };
+    let body = match &snapshot.body {
+        Body::Encoded(bytes) => bytes.clone(),
+        Body::Users(users) => match serde_json::to_vec(users) {
+            Ok(json) => Bytes::from(json),
+            Err(e) => {
+                eprintln!("Serialization error: {e}");
+                return HttpResponse::InternalServerError()
+                    .json(serde_json::json!({ "error": "Serialization error" }));
+            }
+        },
+    };
+    HttpResponse::Ok()
+        .content_type("application/json")
+        .insert_header(("X-Refreshed-At", snapshot.refreshed_at.to_rfc3339()))
+        .body(body)
+}
+
+/// GET /users/stats  — per-domain aggregates from the `user_stats` view.
+#[get("/users/stats")]
+async fn user_stats(data: web::Data<AppState>) -> impl Responder {
+    let client = match data.pool.get().await {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Pool error: {e}");
+            return HttpResponse::InternalServerError()
+                .json(serde_json::json!({ "error": "Database connection error" }));
+        }
+    };
+
+    match cancellable(
+        &client,
+        client.query(
+            "SELECT domain, users, avg_age, min_age, max_age, computed_at \
+             FROM user_stats ORDER BY domain",
+            &[],
+        ),
+    )
+    .await
+    {
+        Ok(rows) => {
+            let total: i64 = rows.iter().map(|r| r.get::<_, i64>("users")).sum();
+            let computed_at: Option<DateTime<Utc>> = rows.first().map(|r| r.get("computed_at"));
+            let domains: Vec<_> = rows
+                .iter()
+                .map(|r| {
+                    serde_json::json!({
+                        "domain": r.get::<_, String>("domain"),
+                        "users": r.get::<_, i64>("users"),
+                        "avg_age": r.get::<_, Option<f64>>("avg_age"),
+                        "min_age": r.get::<_, Option<i32>>("min_age"),
+                        "max_age": r.get::<_, Option<i32>>("max_age"),
+                    })
+                })
+                .collect();
+            HttpResponse::Ok().json(serde_json::json!({
+                "total": total,
+                "domains": domains,
+                "computed_at": computed_at
+            }))
+        }
+        Err(e) => {
+            eprintln!("Query error: {e}");
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({ "error": "Database query error" }))
+        }
+    }
+}
+
+/// Registers `/cached-users` and `/users/stats`; must come before `/users/{id}`.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(cached_users).service(user_stats);
+}
+
+// ---------------------------------------------------------------------------
+// Tasks
+// ---------------------------------------------------------------------------
+
+#[derive(Clone, Copy)]
+enum Task {
+    CachedUsers,
+    UserStats,
+}
+
+/// Starts both refresh tasks on the current system.
+pub fn spawn(state: web::Data<AppState>, interval: Duration, jitter: Duration) {
+    for task in [Task::CachedUsers, Task::UserStats] {
+        actix_web::rt::spawn(schedule(state.clone(), task, interval, jitter));
+    }
+}
+
+async fn schedule(state: web::Data<AppState>, task: Task, interval: Duration, jitter: Duration) {
+    let running = Arc::new(AtomicBool::new(false));
+    let mut ticks = tokio::time::interval(interval);
+    ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    loop {
+        ticks.tick().await;
+        tokio::time::sleep(random_up_to(jitter)).await;
+
+        if running.swap(true, Ordering::AcqRel) {
+            Metrics::inc(&state.metrics.refreshes_skipped_total);
+            continue;
+        }
+        let (state, running) = (state.clone(), Arc::clone(&running));
+        actix_web::rt::spawn(async move {
+            let result = match task {
+                Task::CachedUsers => refresh_cached_users(&state).await,
+                Task::UserStats => refresh_user_stats(&state).await,
+            };
+            match result {
+                Ok(()) => Metrics::inc(&state.metrics.refreshes_ok_total),
+                Err(e) => {
+                    eprintln!("Scheduled refresh error: {e}");
+                    Metrics::inc(&state.metrics.refreshes_failed_total);
+                }
+            }
+            running.store(false, Ordering::Release);
+        });
+    }
+}
+
+/// Uniform in `0..=max`; the standard library's per-instance random hasher
+/// keys are enough for spreading timers.
+fn random_up_to(max: Duration) -> Duration {
+    let max_ms = max.as_millis() as u64;
+    if max_ms == 0 {
+        return Duration::ZERO;
+    }
+    Duration::from_millis(RandomState::new().hash_one(Instant::now()) % (max_ms + 1))
+}
+
+async fn refresh_cached_users(state: &AppState) -> Result<(), Box<dyn std::error::Error>> {
+    let client = state.pool.get().await?;
+    let rows = client
+        .query("SELECT id, name, email, age, created_at, tags, status FROM users ORDER BY id", &[])
+        .await?;
+    drop(client);
+    let users: Vec<_> = rows.iter().map(crate::row_to_user).collect();
+    let body = match state.config.cached_users_encoding {
+        CachedEncoding::PreEncoded => Body::Encoded(Bytes::from(serde_json::to_vec(&users)?)),
+        CachedEncoding::PerRequest => Body::Users(users),
+    };
+    state.users_snapshot.set(Snapshot { body, refreshed_at: Utc::now() });
+    Ok(())
+}
+
+async fn refresh_user_stats(state: &AppState) -> Result<(), Box<dyn std::error::Error>> {
+    let client = state.pool.get().await?;
+    client
+        .batch_execute("REFRESH MATERIALIZED VIEW CONCURRENTLY user_stats")
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,148 @@
+//! Optional server sessions (`SESSION_STORE=cookie|redis`, off by default), for
+//! measuring stateful-session overhead against the stateless endpoints.
+//!
+//! * `cookie` — the whole session lives in a signed cookie; no server state.
+//! * `redis`  — the cookie carries only a session id; state is kept in Redis
+//!   (`REDIS_URL`) and read and written on every request that touches it.
+//!
+//! Cookies are signed with `SESSION_KEY` (at least 64 bytes).  Without it a
+//! random key is generated at startup, so sessions do not survive a restart.
+//!
+//! GET /session/visit-count is registered only when sessions are enabled.
+
+use actix_session::storage::{
+    CookieSessionStore, LoadError, RedisSessionStore, SaveError, SessionKey, SessionStore,
+    UpdateError,
+};
+use actix_session::config::CookieContentSecurity;
+use actix_session::{Session, SessionMiddleware};
+use actix_web::cookie::time::Duration;
+use actix_web::cookie::Key;
+use actix_web::middleware::Condition;
+use actix_web::{get, web, HttpResponse, Responder};
+use std::collections::HashMap;
+
+use crate::config::{SessionBackend, SessionSettings};
+
+/// Session state as stored by `actix-session`.
+type State = HashMap<String, String>;
+
+/// Storage backend chosen at runtime; `SessionMiddleware` is generic over it.
+pub enum Store {
+    Cookie(CookieSessionStore),
+    Redis(RedisSessionStore),
+}
+
+impl SessionStore for Store {
+    async fn load(&self, key: &SessionKey) -> Result<Option<State>, LoadError> {
+        match self {
+            Store::Cookie(s) => s.load(key).await,
+            Store::Redis(s) => s.load(key).await,
+        }
+    }
+
+    async fn save(&self, state: State, ttl: &Duration) -> Result<SessionKey, SaveError> {
+        match self {
+            Store::Cookie(s) => s.save(state, ttl).await,
+            Store::Redis(s) => s.save(state, ttl).await,
+        }
+    }
+
+    async fn update(
+        &self,
+        key: SessionKey,
+        state: State,
+        ttl: &Duration,
+    ) -> Result<SessionKey, UpdateError> {
+        match self {
+            Store::Cookie(s) => s.update(key, state, ttl).await,
+            Store::Redis(s) => s.update(key, state, ttl).await,
+        }
+    }
+
+    async fn update_ttl(&self, key: &SessionKey, ttl: &Duration) -> Result<(), anyhow::Error> {
+        match self {
+            Store::Cookie(s) => s.update_ttl(key, ttl).await,
+            Store::Redis(s) => s.update_ttl(key, ttl).await,
+        }
+    }
+
+    async fn delete(&self, key: &SessionKey) -> Result<(), anyhow::Error> {
+        match self {
+            Store::Cookie(s) => s.delete(key).await,
+            Store::Redis(s) => s.delete(key).await,
+        }
+    }
+}
+
+/// Everything a worker needs to build its session middleware.
+#[derive(Clone)]
+pub struct Sessions {
+    key: Key,
+    redis: Option<RedisSessionStore>,
+}
+
+impl Sessions {
+    /// Connects to Redis when needed; `None` when sessions are off.
+    pub async fn build(settings: &SessionSettings) -> std::io::Result<Option<Sessions>> {
+        let key = match &settings.key {
+            Some(k) => Key::from(k.as_bytes()),
+            None => Key::generate(),
+        };
+        let redis = match settings.store {
+            SessionBackend::Off => return Ok(None),
+            SessionBackend::Cookie => None,
+            SessionBackend::Redis => Some(
+                RedisSessionStore::new(settings.redis_url.as_str())
+                    .await
+                    .map_err(|e| std::io::Error::other(format!("Redis session store: {e}")))?,
+            ),
+        };
+        Ok(Some(Sessions { key, redis }))
+    }
+
+    fn store(&self) -> Store {
+        match &self.redis {
+            Some(redis) => Store::Redis(redis.clone()),
+            None => Store::Cookie(CookieSessionStore::default()),
+        }
+    }
+}
+
+/// Session middleware for one worker; a no-op when sessions are off.
+pub fn middleware(sessions: Option<&Sessions>) -> Condition<SessionMiddleware<Store>> {
+    let (store, key) = match sessions {
+        Some(s) => (s.store(), s.key.clone()),
+        None => (Store::Cookie(CookieSessionStore::default()), Key::generate()),
+    };
+    let middleware = SessionMiddleware::builder(store, key)
+        .cookie_content_security(CookieContentSecurity::Signed)
+        // The benchmark talks plain HTTP; a Secure cookie would never come back.
+        .cookie_secure(false)
+        .build();
+    Condition::new(sessions.is_some(), middleware)
+}
+
+/// GET /session/visit-count  — increments and returns a per-session counter.
+#[get("/session/visit-count")]
+async fn visit_count(session: Session) -> impl Responder {
+    let visits = match session.get::<u64>("visits") {
+        Ok(v) => v.unwrap_or(0) + 1,
+        Err(e) => {
+            eprintln!("Session read error: {e}");
+            return HttpResponse::InternalServerError()
+                .json(serde_json::json!({ "error": "Failed to read session" }));
+        }
+    };
+    if let Err(e) = session.insert("visits", visits) {
+        eprintln!("Session write error: {e}");
+        return HttpResponse::InternalServerError()
+            .json(serde_json::json!({ "error": "Failed to write session" }));
+    }
+    HttpResponse::Ok().json(serde_json::json!({ "visits": visits }))
+}
+
+/// Registers the session endpoints.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(visit_count);
+}
@@ -0,0 +1,96 @@
+//! State of a user: the `status user_status` column, a Postgres ENUM
+//! (`active`, `inactive`, `suspended`), and the `status` field of [`User`].
+//!
+//! [`Status`] maps the type by hand: in binary mode an enum value travels as
+//! its label, so [`FromSql`] and [`ToSql`] read and write the label and
+//! accept only the `user_status` type.  A label the database knows and this
+//! enum does not is a decoding error, not a silent default.
+//!
+//! Every user starts `active` (the column default); PUT /users/:id sets the
+//! status, and `GET /users?status=suspended` lists only the users in it,
+//! alone or with the other filters (see `filter`).  Like `tags`, the field is
+//! optional in the OpenAPI document and the other APIs ignore the column.
+//!
+//! [`User`]: crate::User
+
+use actix_web::web::BytesMut;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+use tokio_postgres::types::{to_sql_checked, FromSql, IsNull, ToSql, Type};
+use utoipa::ToSchema;
+
+/// Name of the Postgres type.
+const TYPE_NAME: &str = "user_status";
+/// Query parameter of the filter.
+const FILTER_PARAM: &str = "status";
+
+/// A value of `user_status`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    #[default]
+    Active,
+    Inactive,
+    Suspended,
+}
+
+impl Status {
+    pub const ALL: [Status; 3] = [Status::Active, Status::Inactive, Status::Suspended];
+
+    /// The enum label, as in `init.sql` and in JSON.
+    pub fn label(self) -> &'static str {
+        match self {
+            Status::Active => "active",
+            Status::Inactive => "inactive",
+            Status::Suspended => "suspended",
+        }
+    }
+
+    pub fn parse(label: &str) -> Option<Status> {
+        Status::ALL.into_iter().find(|status| status.label() == label)
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+impl<'a> FromSql<'a> for Status {
+    fn from_sql(_: &Type, raw: &'a [u8]) -> Result<Status, Box<dyn Error + Sync + Send>> {
+        let label = std::str::from_utf8(raw)?;
+        Status::parse(label).ok_or_else(|| format!("unknown {TYPE_NAME} '{label}'").into())
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        ty.name() == TYPE_NAME
+    }
+}
+
+impl ToSql for Status {
+    fn to_sql(&self, _: &Type, out: &mut BytesMut) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+        out.extend_from_slice(self.label().as_bytes());
+        Ok(IsNull::No)
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        ty.name() == TYPE_NAME
+    }
+
+    to_sql_checked!();
+}
+
+/// The `status` parameter of `query`, if any; given once.
+pub fn filter(query: &[(String, String)]) -> Result<Option<Status>, String> {
+    let mut values = query.iter().filter(|(key, _)| key == FILTER_PARAM).map(|(_, value)| value);
+    let Some(label) = values.next() else {
+        return Ok(None);
+    };
+    if values.next().is_some() {
+        return Err(format!("{FILTER_PARAM} may be given once"));
+    }
+    let allowed = || Status::ALL.map(Status::label).join(", ");
+    Status::parse(label).map(Some).ok_or_else(|| format!("Unknown status '{label}' (allowed: {})", allowed()))
+}
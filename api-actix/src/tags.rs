@@ -0,0 +1,64 @@
+//! Labels of a user: the `tags TEXT[]` column, the `tags` field of [`User`].
+//!
+//! POST /users, PUT /users/:id and POST /users/bulk take an optional `tags`
+//! array (PUT replaces the whole list; form bodies carry none), stored as
+//! sent once each tag is trimmed.  `GET /users?tag=pro&tag=beta` returns only
+//! the users carrying every such tag (`tags @> '{pro,beta}'`, served by the
+//! GIN index of `init.sql`), alone or together with `?metadata.*` (see
+//! `filter`).
+//!
+//! The other APIs neither read nor write the column; the field is optional in
+//! the OpenAPI document, so their responses still match it.
+//!
+//! [`User`]: crate::User
+
+use serde::{Deserialize, Deserializer};
+
+/// Most tags of one user, and in one filter.
+pub const MAX_TAGS: usize = 16;
+/// Longest tag, in characters.
+pub const MAX_LEN: usize = 64;
+/// Query parameter of the filter, repeated for each tag.
+const FILTER_PARAM: &str = "tag";
+
+/// `tags` trimmed, or why they are refused; `per` names what holds them.
+fn validate(tags: Vec<String>, per: &str) -> Result<Vec<String>, String> {
+    if tags.len() > MAX_TAGS {
+        return Err(format!("At most {MAX_TAGS} tags per {per}"));
+    }
+    tags.into_iter()
+        .map(|tag| {
+            let tag = tag.trim();
+            match tag.chars().count() {
+                0 => Err("Tags must not be empty".to_owned()),
+                n if n > MAX_LEN => Err(format!("Tag '{tag}' is longer than {MAX_LEN} characters")),
+                _ => Ok(tag.to_owned()),
+            }
+        })
+        .collect()
+}
+
+/// `tags` of a create body; absent is no tags.
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    validate(Vec::deserialize(deserializer)?, "user").map_err(serde::de::Error::custom)
+}
+
+/// `tags` of an update body; absent keeps the stored list.
+pub fn deserialize_optional<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Vec<String>>, D::Error> {
+    Option::<Vec<String>>::deserialize(deserializer)?
+        .map(|tags| validate(tags, "user"))
+        .transpose()
+        .map_err(serde::de::Error::custom)
+}
+
+/// The `tag` parameters of `query`, each one required; empty without any.
+pub fn filter(query: &[(String, String)]) -> Result<Vec<String>, String> {
+    let tags: Vec<String> =
+        query.iter().filter(|(key, _)| key == FILTER_PARAM).map(|(_, tag)| tag.clone()).collect();
+    validate(tags, "filter")
+}
+
+/// `tags @> wanted`, for the in-memory repository.
+pub fn contains(tags: &[String], wanted: &[String]) -> bool {
+    wanted.iter().all(|tag| tags.contains(tag))
+}
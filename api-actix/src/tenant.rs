@@ -0,0 +1,83 @@
+//! Tenant resolution for multi-tenant mode (`MULTI_TENANT=on`).
+//!
+//! The tenant comes from the `X-Tenant-Id` header or, when `TENANT_DOMAIN` is
+//! set, from the subdomain: `acme.<TENANT_DOMAIN>` is tenant `acme`.  The
+//! header wins when both are present.  Ids are 1–63 ASCII letters, digits,
+//! `-` or `_`; subdomains are case-insensitive and lowercased.
+//!
+//! Handlers that touch `users` take a [`Tenant`] and pass its [`Scope`] to
+//! the repository, which scopes every statement.  With the mode on, a
+//! request that names no tenant is rejected with 400 before the handler runs.
+
+use actix_web::http::header::{self, HeaderName};
+use actix_web::{dev::Payload, web, FromRequest, HttpRequest, HttpResponse};
+use std::future::{ready, Ready};
+
+use crate::config::Config;
+use crate::repo::Scope;
+use crate::AppState;
+
+const TENANT_HEADER: HeaderName = HeaderName::from_static("x-tenant-id");
+const MAX_LEN: usize = 63;
+
+/// The request's tenant; `None` when multi-tenant mode is off.
+#[derive(Debug, Clone)]
+pub struct Tenant(pub Option<String>);
+
+impl Tenant {
+    pub fn id(&self) -> Option<&str> {
+        self.0.as_deref()
+    }
+
+    /// Repository scope for this tenant, without an actor.
+    pub fn scope(&self) -> Scope<'_> {
+        Scope { tenant: self.id(), actor: None }
+    }
+}
+
+fn valid(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_LEN
+        && id.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+}
+
+/// Tenant named by the request, `Ok(None)` when the mode is off.
+pub fn resolve(req: &HttpRequest, config: &Config) -> Result<Option<String>, &'static str> {
+    if !config.multi_tenant {
+        return Ok(None);
+    }
+    if let Some(value) = req.headers().get(TENANT_HEADER) {
+        return match value.to_str() {
+            Ok(id) if valid(id) => Ok(Some(id.to_owned())),
+            _ => Err("Invalid X-Tenant-Id"),
+        };
+    }
+    if let Some(domain) = &config.tenant_domain {
+        let host = req
+            .headers()
+            .get(header::HOST)
+            .and_then(|v| v.to_str().ok())
+            .or_else(|| req.uri().host())
+            .unwrap_or_default();
+        let host = host.rsplit_once(':').map_or(host, |(name, _port)| name).to_ascii_lowercase();
+        if let Some(sub) = host.strip_suffix(domain.as_str()).and_then(|s| s.strip_suffix('.')) {
+            return if valid(sub) { Ok(Some(sub.to_owned())) } else { Err("Invalid tenant subdomain") };
+        }
+    }
+    Err("Missing tenant: set X-Tenant-Id")
+}
+
+impl FromRequest for Tenant {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let Some(data) = req.app_data::<web::Data<AppState>>() else {
+            return ready(Ok(Tenant(None)));
+        };
+        ready(resolve(req, &data.config).map(Tenant).map_err(|msg| {
+            let response = HttpResponse::BadRequest().json(serde_json::json!({ "error": msg }));
+            actix_web::error::InternalError::from_response(msg, response).into()
+        }))
+    }
+}
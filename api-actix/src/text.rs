@@ -0,0 +1,67 @@
+//! String type of `User.name` and `User.email`.
+//!
+//! By default a plain `String`, so every row read allocates two strings.  With
+//! the `interned-strings` feature they are `Arc<str>` taken from a
+//! process-wide interner: the same name or email read again, by any request or
+//! by the /cached-users refresh, is a reference count increment instead of an
+//! allocation, and lists share their strings with the snapshot.  Building both
+//! ways and comparing heap profiles and the `hot_paths` benches quantifies the
+//! allocation churn of `Vec<User>` construction.
+//!
+//! The interner is never pruned; past [`CAPACITY`] strings new ones are
+//! allocated without being interned, so a write-heavy run cannot grow it
+//! without bound.
+
+#[cfg(feature = "interned-strings")]
+use std::collections::HashSet;
+#[cfg(feature = "interned-strings")]
+use std::sync::{Arc, LazyLock, RwLock};
+
+#[cfg(not(feature = "interned-strings"))]
+pub type Text = String;
+#[cfg(feature = "interned-strings")]
+pub type Text = Arc<str>;
+
+/// Most strings kept by the interner.
+#[cfg(feature = "interned-strings")]
+const CAPACITY: usize = 1 << 20;
+
+#[cfg(feature = "interned-strings")]
+static INTERNER: LazyLock<RwLock<HashSet<Arc<str>>>> = LazyLock::new(Default::default);
+
+#[cfg(not(feature = "interned-strings"))]
+pub fn intern(s: &str) -> Text {
+    s.to_owned()
+}
+
+/// The interned copy of `s`, added on first sight.
+#[cfg(feature = "interned-strings")]
+pub fn intern(s: &str) -> Text {
+    if let Some(text) = INTERNER.read().unwrap_or_else(|e| e.into_inner()).get(s) {
+        return text.clone();
+    }
+    let mut interner = INTERNER.write().unwrap_or_else(|e| e.into_inner());
+    if let Some(text) = interner.get(s) {
+        return text.clone();
+    }
+    let text: Arc<str> = Arc::from(s);
+    if interner.len() < CAPACITY {
+        interner.insert(text.clone());
+    }
+    text
+}
+
+/// Appends the interner size to a /metrics body; nothing without the feature.
+pub fn render(out: &mut String) {
+    #[cfg(feature = "interned-strings")]
+    {
+        use std::fmt::Write;
+        let len = INTERNER.read().unwrap_or_else(|e| e.into_inner()).len();
+        let _ = writeln!(
+            out,
+            "# HELP interned_strings Strings held by the interner.\n# TYPE interned_strings gauge\ninterned_strings {len}"
+        );
+    }
+    #[cfg(not(feature = "interned-strings"))]
+    let _ = out;
+}
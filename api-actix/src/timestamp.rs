@@ -0,0 +1,86 @@
+//! Serialized form of user timestamps (`TIMESTAMP_FORMAT`).
+//!
+//! The implementations write `created_at` differently: chrono prints as many
+//! fraction digits as the value needs (0, 3, 6 or 9), JavaScript's
+//! `toISOString` always three, Go's `RFC3339Nano` trims trailing zeros.  Byte
+//! level comparisons of the payloads trip on that, so the format is set here,
+//! for every representation of a user (`User`, `fields::Sparse`, `rowjson`):
+//!
+//! * `rfc3339` (default) — chrono's output, unchanged;
+//! * `rfc3339-secs`, `rfc3339-millis`, `rfc3339-micros`, `rfc3339-nanos` —
+//!   RFC 3339 in UTC (`Z`) with exactly 0, 3, 6 or 9 fraction digits,
+//!   truncated;
+//! * `epoch-millis` — milliseconds since the Unix epoch, as a JSON number.
+//!
+//! Timestamps are read as `TIMESTAMPTZ` into `DateTime<Utc>`, so the
+//! database session's `TimeZone` never reaches the output.  The format is
+//! process-wide, set when the application state is built; anything but the
+//! default leaves the OpenAPI contract (`format: date-time`), like the
+//! `camel-case` feature.  Deserializing accepts every format.
+
+use crate::config::TimestampFormat;
+use chrono::{DateTime, SecondsFormat, TimeZone, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::sync::atomic::{AtomicU8, Ordering};
+
+const FORMATS: [TimestampFormat; 6] = [
+    TimestampFormat::Rfc3339,
+    TimestampFormat::Rfc3339Secs,
+    TimestampFormat::Rfc3339Millis,
+    TimestampFormat::Rfc3339Micros,
+    TimestampFormat::Rfc3339Nanos,
+    TimestampFormat::EpochMillis,
+];
+
+/// Index into [`FORMATS`] of the format in use.
+static FORMAT: AtomicU8 = AtomicU8::new(0);
+
+/// Sets the format of every timestamp serialized from now on.
+pub fn set(format: TimestampFormat) {
+    let index = FORMATS.iter().position(|f| *f == format).unwrap_or(0);
+    FORMAT.store(index as u8, Ordering::Relaxed);
+}
+
+fn current() -> TimestampFormat {
+    FORMATS[FORMAT.load(Ordering::Relaxed) as usize]
+}
+
+/// A timestamp serialized in the configured format.
+pub struct Timestamp<'a>(pub &'a DateTime<Utc>);
+
+impl Serialize for Timestamp<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let digits = match current() {
+            TimestampFormat::Rfc3339 => return self.0.serialize(serializer),
+            TimestampFormat::EpochMillis => return serializer.serialize_i64(self.0.timestamp_millis()),
+            TimestampFormat::Rfc3339Secs => SecondsFormat::Secs,
+            TimestampFormat::Rfc3339Millis => SecondsFormat::Millis,
+            TimestampFormat::Rfc3339Micros => SecondsFormat::Micros,
+            TimestampFormat::Rfc3339Nanos => SecondsFormat::Nanos,
+        };
+        serializer.serialize_str(&self.0.to_rfc3339_opts(digits, true))
+    }
+}
+
+/// `serialize_with` for `DateTime<Utc>` fields.
+pub fn serialize<S: Serializer>(at: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error> {
+    Timestamp(at).serialize(serializer)
+}
+
+/// `deserialize_with` for `DateTime<Utc>` fields: RFC 3339 text or epoch
+/// milliseconds, whatever the configured format.
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DateTime<Utc>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Any {
+        Text(DateTime<Utc>),
+        Millis(i64),
+    }
+    match Any::deserialize(deserializer)? {
+        Any::Text(at) => Ok(at),
+        Any::Millis(ms) => Utc
+            .timestamp_millis_opt(ms)
+            .single()
+            .ok_or_else(|| serde::de::Error::custom(format!("timestamp {ms} ms is out of range"))),
+    }
+}
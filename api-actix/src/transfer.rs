@@ -0,0 +1,126 @@
+//! Money between users: the `balance NUMERIC(12,2)` column and
+//! `POST /transfer`.
+//!
+//! `{"from": 1, "to": 2, "amount": "10.50"}` moves the amount in one
+//! transaction that locks both rows in id order (so opposite transfers cannot
+//! deadlock), and answers with the amount and both resulting balances:
+//! `{"amount": "10.50", "from": {"id": 1, "balance": "989.50"}, "to": …}`.
+//! Unknown users are a 404 and a balance that would go negative a 409.
+//!
+//! Amounts are [`Decimal`]s end to end (`rust_decimal`, bound as NUMERIC),
+//! and always travel as JSON strings with two decimal places: a JSON number
+//! is refused rather than read through a float, so `"0.10"` three times is
+//! exactly `"0.30"`.  Every user starts with [`OPENING_BALANCE`], the column
+//! default of `init.sql`.
+//!
+//! Not part of the OpenAPI document, which only covers the routes every
+//! implementation serves; `contract-tests/tests/money.rs` checks the wire
+//! format against any API that does serve it.  Like metadata patches,
+//! transfers are neither audited nor delivered to webhooks.
+
+use actix_web::{post, web, HttpResponse, Responder};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::key::UserId;
+use crate::negotiate::{Negotiated, Output};
+use crate::tenant::Tenant;
+use crate::{storage_error, AppState};
+
+/// Decimal places of `balance` and of every amount.
+pub const SCALE: u32 = 2;
+/// Digits before the point that `NUMERIC(12,2)` holds.
+const MAX_DIGITS: usize = 10;
+/// Balance of a new user, as the column default.
+pub const OPENING_BALANCE: Decimal = Decimal::from_parts(100_000, 0, 0, false, SCALE);
+
+/// `text` as an amount: digits, optionally a point and one or two decimals,
+/// greater than zero.  Returned with exactly [`SCALE`] decimal places.
+pub fn parse(text: &str) -> Result<Decimal, String> {
+    let invalid = || format!("Invalid amount '{text}' (expected a string like \"10.50\")");
+    let (units, cents) = text.split_once('.').unwrap_or((text, ""));
+    let digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    if units.is_empty() || !digits(units) || !digits(cents) || text.ends_with('.') {
+        return Err(invalid());
+    }
+    if cents.len() > SCALE as usize {
+        return Err(format!("Amount '{text}' has more than {SCALE} decimal places"));
+    }
+    if units.trim_start_matches('0').len() > MAX_DIGITS {
+        return Err(format!("Amount '{text}' does not fit NUMERIC(12,2)"));
+    }
+    let mut amount: Decimal = text.parse().map_err(|_| invalid())?;
+    if amount.is_zero() {
+        return Err("Amount must be greater than zero".into());
+    }
+    amount.rescale(SCALE);
+    Ok(amount)
+}
+
+/// A JSON string read with [`parse`]; numbers are refused.
+fn deserialize_amount<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Decimal, D::Error> {
+    parse(&String::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+}
+
+/// Body of POST /transfer.
+#[derive(Debug, Deserialize)]
+pub struct TransferRequest {
+    pub from: UserId,
+    pub to: UserId,
+    #[serde(deserialize_with = "deserialize_amount")]
+    pub amount: Decimal,
+}
+
+/// A user's balance after a transfer.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Balance {
+    pub id: UserId,
+    #[serde(with = "rust_decimal::serde::str")]
+    pub balance: Decimal,
+}
+
+/// Body of a completed POST /transfer.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Receipt {
+    #[serde(with = "rust_decimal::serde::str")]
+    pub amount: Decimal,
+    pub from: Balance,
+    pub to: Balance,
+}
+
+/// What a transfer came to; only [`Outcome::Done`] changed anything.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    Done(Receipt),
+    /// One of the users does not exist (in the tenant).
+    NotFound,
+    /// `from` holds less than the amount.
+    InsufficientFunds,
+}
+
+/// POST /transfer  — moves `amount` from one user to another.
+#[post("/transfer")]
+async fn transfer(
+    data: web::Data<AppState>,
+    body: web::Json<TransferRequest>,
+    output: Output,
+    tenant: Tenant,
+) -> impl Responder {
+    let TransferRequest { from, to, amount } = body.into_inner();
+    if from == to {
+        return HttpResponse::BadRequest().json(serde_json::json!({ "error": "from and to must differ" }));
+    }
+
+    match data.users.transfer(tenant.scope(), from, to, amount).await {
+        Ok(Outcome::Done(receipt)) => Negotiated::new(output, receipt).respond(HttpResponse::Ok()),
+        Ok(Outcome::NotFound) => HttpResponse::NotFound().json(serde_json::json!({ "error": "User not found" })),
+        Ok(Outcome::InsufficientFunds) => {
+            HttpResponse::Conflict().json(serde_json::json!({ "error": "Insufficient funds" }))
+        }
+        Err(e) => storage_error("Transfer", "Database update error", e),
+    }
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(transfer);
+}
@@ -0,0 +1,373 @@
+//! Outbound webhooks for user mutations (`WEBHOOKS=on`).
+//!
+//! `POST /webhooks` registers a URL.  Every successful create, update or
+//! delete on `/users` then inserts one row per registered webhook into
+//! `webhook_deliveries`, and a dispatcher task on the main system POSTs the
+//! JSON payload:
+//!
+//! * signed with HMAC-SHA256 of `"<timestamp>.<body>"` using the webhook's
+//!   secret, sent as `X-Webhook-Signature: sha256=<hex>` next to
+//!   `X-Webhook-Timestamp` and `X-Webhook-Delivery`;
+//! * retried on network errors and non-2xx answers after 1 s, 2 s, 4 s, …
+//!   (capped at 5 min) until `WEBHOOK_MAX_ATTEMPTS`, then marked `failed`.
+//!
+//! Deliveries are claimed with `FOR UPDATE SKIP LOCKED` like `jobs`, and their
+//! status is readable at `GET /webhooks/{id}/deliveries`.  The client speaks
+//! plain HTTP only: receivers live on the benchmark network.
+
+use actix_web::{delete, get, post, web, HttpResponse, Responder};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::Sha256;
+use std::fmt::Write;
+use std::time::Duration;
+
+use crate::db::cancellable;
+use crate::metrics::Metrics;
+use crate::AppState;
+
+/// Idle dispatcher poll interval.
+const POLL: Duration = Duration::from_millis(500);
+/// Deliveries claimed and sent concurrently per round.
+const BATCH: i64 = 16;
+/// Per-attempt limit for connecting and reading the receiver's answer.
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_BACKOFF_SECS: i64 = 300;
+
+#[derive(Deserialize)]
+pub struct CreateWebhook {
+    pub url: String,
+    /// Generated when omitted; returned only in the 201 response.
+    pub secret: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Webhook {
+    id: i32,
+    url: String,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize)]
+struct Delivery {
+    id: i64,
+    event: String,
+    status: String,
+    attempts: i32,
+    last_status_code: Option<i32>,
+    last_error: Option<String>,
+    next_attempt_at: DateTime<Utc>,
+    created_at: DateTime<Utc>,
+    delivered_at: Option<DateTime<Utc>>,
+}
+
+/// Enqueues `event` for every registered webhook.  Called by the `/users`
+/// repository after the change succeeded, when `WEBHOOKS=on`; failures are
+/// logged, never returned to the client, whose write already happened.
+pub async fn record(client: &tokio_postgres::Client, tenant: Option<&str>, event: &str, subject: Value) {
+    let mut payload = serde_json::json!({
+        "event": event,
+        "data": subject,
+        "occurred_at": Utc::now(),
+    });
+    if let Some(id) = tenant {
+        payload["tenant"] = id.into();
+    }
+    if let Err(e) = client
+        .execute(
+            "INSERT INTO webhook_deliveries (webhook_id, event, payload) \
+             SELECT id, $1, $2 FROM webhooks",
+            &[&event, &payload],
+        )
+        .await
+    {
+        eprintln!("Webhook enqueue error: {e}");
+    }
+}
+
+fn random_secret() -> String {
+    use std::hash::{BuildHasher, RandomState};
+    (0..4).fold(String::new(), |mut out, i| {
+        let _ = write!(out, "{:016x}", RandomState::new().hash_one(i));
+        out
+    })
+}
+
+/// POST /webhooks  — registers a receiver; 201 with its id and secret.
+#[post("/webhooks")]
+async fn create_webhook(data: web::Data<AppState>, body: web::Json<CreateWebhook>) -> impl Responder {
+    let CreateWebhook { url, secret } = body.into_inner();
+    if !url.starts_with("http://") || url.parse::<actix_web::http::Uri>().is_err() {
+        return HttpResponse::BadRequest()
+            .json(serde_json::json!({ "error": "url must be an http:// URL" }));
+    }
+    let secret = secret.unwrap_or_else(random_secret);
+
+    let client = match data.pool.get().await {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Pool error: {e}");
+            return HttpResponse::InternalServerError()
+                .json(serde_json::json!({ "error": "Database connection error" }));
+        }
+    };
+
+    match cancellable(
+        &client,
+        client.query_one(
+            "INSERT INTO webhooks (url, secret) VALUES ($1, $2) RETURNING id, url, created_at",
+            &[&url, &secret],
+        ),
+    )
+    .await
+    {
+        Ok(row) => HttpResponse::Created().json(serde_json::json!({
+            "id": row.get::<_, i32>("id"),
+            "url": row.get::<_, String>("url"),
+            "secret": secret,
+            "created_at": row.get::<_, DateTime<Utc>>("created_at"),
+        })),
+        Err(e) => {
+            eprintln!("Insert error: {e}");
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({ "error": "Database insert error" }))
+        }
+    }
+}
+
+/// GET /webhooks  — registered receivers (secrets omitted).
+#[get("/webhooks")]
+async fn list_webhooks(data: web::Data<AppState>) -> impl Responder {
+    let client = match data.pool.get().await {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Pool error: {e}");
+            return HttpResponse::InternalServerError()
+                .json(serde_json::json!({ "error": "Database connection error" }));
+        }
+    };
+
+    match cancellable(&client, client.query("SELECT id, url, created_at FROM webhooks ORDER BY id", &[])).await {
+        Ok(rows) => HttpResponse::Ok().json(
+            rows.iter()
+                .map(|r| Webhook { id: r.get("id"), url: r.get("url"), created_at: r.get("created_at") })
+                .collect::<Vec<_>>(),
+        ),
+        Err(e) => {
+            eprintln!("Query error: {e}");
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({ "error": "Database query error" }))
+        }
+    }
+}
+
+/// DELETE /webhooks/:id  — unregisters a receiver and drops its deliveries.
+#[delete("/webhooks/{id}")]
+async fn delete_webhook(data: web::Data<AppState>, path: web::Path<i32>) -> impl Responder {
+    let id = path.into_inner();
+    let client = match data.pool.get().await {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Pool error: {e}");
+            return HttpResponse::InternalServerError()
+                .json(serde_json::json!({ "error": "Database connection error" }));
+        }
+    };
+
+    match cancellable(&client, client.execute("DELETE FROM webhooks WHERE id = $1", &[&id])).await {
+        Ok(0) => HttpResponse::NotFound().json(serde_json::json!({ "error": "Webhook not found" })),
+        Ok(_) => HttpResponse::NoContent().finish(),
+        Err(e) => {
+            eprintln!("Delete error: {e}");
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({ "error": "Database delete error" }))
+        }
+    }
+}
+
+/// GET /webhooks/:id/deliveries  — the 50 most recent deliveries, newest first.
+#[get("/webhooks/{id}/deliveries")]
+async fn list_deliveries(data: web::Data<AppState>, path: web::Path<i32>) -> impl Responder {
+    let id = path.into_inner();
+    let client = match data.pool.get().await {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Pool error: {e}");
+            return HttpResponse::InternalServerError()
+                .json(serde_json::json!({ "error": "Database connection error" }));
+        }
+    };
+
+    let sql = "SELECT d.id, d.event, d.status, d.attempts, d.last_status_code, d.last_error, \
+                      d.next_attempt_at, d.created_at, d.delivered_at \
+               FROM webhooks w LEFT JOIN webhook_deliveries d ON d.webhook_id = w.id \
+               WHERE w.id = $1 \
+               ORDER BY d.id DESC NULLS LAST LIMIT 50";
+    match cancellable(&client, client.query(sql, &[&id])).await {
+        // The LEFT JOIN yields one all-NULL row for a webhook without deliveries.
+        Ok(rows) if rows.is_empty() => {
+            HttpResponse::NotFound().json(serde_json::json!({ "error": "Webhook not found" }))
+        }
+        Ok(rows) => HttpResponse::Ok().json(
+            rows.iter()
+                .filter(|r| r.get::<_, Option<i64>>("id").is_some())
+                .map(|r| Delivery {
+                    id: r.get("id"),
+                    event: r.get("event"),
+                    status: r.get("status"),
+                    attempts: r.get("attempts"),
+                    last_status_code: r.get("last_status_code"),
+                    last_error: r.get("last_error"),
+                    next_attempt_at: r.get("next_attempt_at"),
+                    created_at: r.get("created_at"),
+                    delivered_at: r.get("delivered_at"),
+                })
+                .collect::<Vec<_>>(),
+        ),
+        Err(e) => {
+            eprintln!("Query error: {e}");
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({ "error": "Database query error" }))
+        }
+    }
+}
+
+/// Registers the `/webhooks` endpoints.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(create_webhook)
+        .service(list_webhooks)
+        .service(delete_webhook)
+        .service(list_deliveries);
+}
+
+// ---------------------------------------------------------------------------
+// Dispatcher
+// ---------------------------------------------------------------------------
+
+/// Requeues deliveries left `sending` by a previous process and starts the
+/// dispatcher on the current system.
+pub async fn spawn_dispatcher(state: web::Data<AppState>) {
+    if let Ok(client) = state.pool.get().await {
+        if let Err(e) = client
+            .execute("UPDATE webhook_deliveries SET status = 'pending' WHERE status = 'sending'", &[])
+            .await
+        {
+            eprintln!("Webhook requeue error: {e}");
+        }
+    }
+    actix_web::rt::spawn(async move {
+        let http = awc::Client::builder().timeout(DELIVERY_TIMEOUT).finish();
+        loop {
+            match dispatch_batch(&state, &http).await {
+                Ok(n) if n > 0 => {}
+                Ok(_) => tokio::time::sleep(POLL).await,
+                Err(e) => {
+                    eprintln!("Webhook dispatcher error: {e}");
+                    tokio::time::sleep(POLL).await;
+                }
+            }
+        }
+    });
+}
+
+/// Claims up to [`BATCH`] due deliveries, sends them concurrently and records
+/// each outcome.  Returns how many were claimed.
+async fn dispatch_batch(state: &AppState, http: &awc::Client) -> Result<usize, Box<dyn std::error::Error>> {
+    let client = state.pool.get().await?;
+    let claimed = client
+        .query(
+            "UPDATE webhook_deliveries d \
+             SET status = 'sending', attempts = d.attempts + 1 \
+             FROM webhooks w \
+             WHERE w.id = d.webhook_id AND d.id IN ( \
+                 SELECT id FROM webhook_deliveries \
+                 WHERE status = 'pending' AND next_attempt_at <= NOW() \
+                 ORDER BY next_attempt_at LIMIT $1 FOR UPDATE SKIP LOCKED \
+             ) \
+             RETURNING d.id, d.attempts, d.payload, w.url, w.secret",
+            &[&BATCH],
+        )
+        .await?;
+    drop(client);
+
+    let sends = claimed.iter().map(|row| async move {
+        let id: i64 = row.get("id");
+        let payload: Value = row.get("payload");
+        let url: String = row.get("url");
+        let secret: String = row.get("secret");
+        (id, row.get::<_, i32>("attempts"), send(http, id, &url, &secret, &payload).await)
+    });
+    let outcomes = futures_util::future::join_all(sends).await;
+
+    let client = state.pool.get().await?;
+    for (id, attempts, outcome) in outcomes {
+        let (code, error) = match outcome {
+            Ok(code) if (200..300).contains(&code) => {
+                client
+                    .execute(
+                        "UPDATE webhook_deliveries \
+                         SET status = 'delivered', last_status_code = $2, last_error = NULL, \
+                             delivered_at = NOW() \
+                         WHERE id = $1",
+                        &[&id, &code],
+                    )
+                    .await?;
+                Metrics::inc(&state.metrics.webhooks_delivered_total);
+                continue;
+            }
+            Ok(code) => (Some(code), format!("receiver answered {code}")),
+            Err(e) => (None, e),
+        };
+
+        let give_up = attempts >= state.config.webhook_max_attempts as i32;
+        let backoff_secs = 2_i64.saturating_pow(attempts.saturating_sub(1) as u32).min(MAX_BACKOFF_SECS);
+        client
+            .execute(
+                "UPDATE webhook_deliveries \
+                 SET status = CASE WHEN $4 THEN 'failed' ELSE 'pending' END, \
+                     last_status_code = $2, last_error = $3, \
+                     next_attempt_at = NOW() + make_interval(secs => $5) \
+                 WHERE id = $1",
+                &[&id, &code, &error, &give_up, &(backoff_secs as f64)],
+            )
+            .await?;
+        Metrics::inc(if give_up {
+            &state.metrics.webhooks_failed_total
+        } else {
+            &state.metrics.webhooks_retried_total
+        });
+    }
+    Ok(claimed.len())
+}
+
+/// POSTs one signed delivery; the receiver's status code, or the transport error.
+async fn send(http: &awc::Client, id: i64, url: &str, secret: &str, payload: &Value) -> Result<i32, String> {
+    let body = serde_json::to_vec(payload).map_err(|e| e.to_string())?;
+    let timestamp = Utc::now().timestamp().to_string();
+    let signature = sign(secret, &timestamp, &body);
+
+    let response = http
+        .post(url)
+        .insert_header(("Content-Type", "application/json"))
+        .insert_header(("X-Webhook-Delivery", id.to_string()))
+        .insert_header(("X-Webhook-Timestamp", timestamp))
+        .insert_header(("X-Webhook-Signature", format!("sha256={signature}")))
+        .send_body(body)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(i32::from(response.status().as_u16()))
+}
+
+/// Hex HMAC-SHA256 of `"<timestamp>.<body>"`.
+fn sign(secret: &str, timestamp: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(timestamp.as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    mac.finalize().into_bytes().iter().fold(String::new(), |mut out, b| {
+        let _ = write!(out, "{b:02x}");
+        out
+    })
+}
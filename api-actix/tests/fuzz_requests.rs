@@ -0,0 +1,219 @@
+//! Property tests for request deserialization: malformed JSON bodies,
+//! pathological query strings and overlong headers, sent through the whole
+//! application (middleware, extractors, error handlers) with `actix_web::test`.
+//!
+//! ```text
+//! cargo test --test fuzz_requests
+//! PROPTEST_CASES=5000 cargo test --release --test fuzz_requests
+//! ```
+//!
+//! Every generated input must be rejected with the documented 4xx and a JSON
+//! `{"error": ...}` body; a panic (a 500 from `catch_panic`) fails the case.
+//! No database is needed: inputs that would get past the extractors are
+//! discarded with `prop_assume!`, and the pool points at a closed port in case
+//! one slips through.  `fuzz/` has the coverage-guided counterpart.
+
+use actix_web::http::header::{HeaderName, HeaderValue, ACCEPT, AUTHORIZATION, CONTENT_TYPE};
+use actix_web::http::{StatusCode, Uri};
+use actix_web::test::{call_service, init_service, read_body, TestRequest};
+use actix_web::web;
+use api_actix::config::Config;
+use api_actix::key::{Key, UserId};
+use api_actix::{app, db, AppState, CreateUser, QueriesParams, UpdateUser, UsersParams};
+use figment::providers::Serialized;
+use figment::Figment;
+use proptest::prelude::*;
+use serde_json::{json, Value};
+
+const VALID_USER: &str = r#"{"name":"Ana Souza","email":"ana@example.com","age":30}"#;
+
+/// Defaults plus `overrides`, with an unreachable database.
+fn config(overrides: &[(&str, &str)]) -> Config {
+    let figment = overrides.iter().fold(
+        Figment::new().merge(Serialized::default("database_url", "postgresql://benchmark@127.0.0.1:1/benchmark")),
+        |figment, (key, value)| figment.merge(Serialized::default(key, value)),
+    );
+    Config::from_figment(figment).expect("valid test configuration")
+}
+
+/// Sends `req` through a fresh application; returns the status and the body
+/// parsed as JSON (`Null` when it is not JSON).
+fn send(config: Config, req: TestRequest) -> (StatusCode, Value) {
+    actix_web::rt::System::new().block_on(async move {
+        let pool = db::build_pool(&config).expect("pool");
+        let state = web::Data::new(AppState::new(pool, config).await.expect("state"));
+        let app = init_service(app(state)).await;
+        let res = call_service(&app, req.to_request()).await;
+        let status = res.status();
+        let body = read_body(res).await;
+        (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+    })
+}
+
+/// `/users/:id` for a well-formed id of the build's key type, so the request
+/// reaches the handler's extractors instead of a 404.
+fn user_path() -> String {
+    format!("/users/{}", UserId::generate(1))
+}
+
+fn post_json(path: &str, body: impl Into<Vec<u8>>) -> TestRequest {
+    TestRequest::post()
+        .uri(path)
+        .insert_header((CONTENT_TYPE, "application/json"))
+        .set_payload(body.into())
+}
+
+/// Asserts a rejection with `expected` and a JSON error message.
+fn rejected(status: StatusCode, body: &Value, expected: StatusCode) -> Result<(), TestCaseError> {
+    prop_assert_eq!(status, expected, "body: {}", body);
+    prop_assert!(
+        body["error"].as_str().is_some_and(|msg| !msg.is_empty()),
+        "no error message in {}",
+        body
+    );
+    Ok(())
+}
+
+/// Any JSON value, nested a few levels.
+fn json_value() -> impl Strategy<Value = Value> {
+    let leaf = prop_oneof![
+        Just(Value::Null),
+        any::<bool>().prop_map(Value::from),
+        any::<i64>().prop_map(Value::from),
+        any::<f64>().prop_map(Value::from),
+        ".{0,20}".prop_map(Value::from),
+    ];
+    leaf.prop_recursive(3, 16, 4, |inner| {
+        prop_oneof![
+            prop::collection::vec(inner.clone(), 0..4).prop_map(Value::from),
+            prop::collection::hash_map("[a-z]{1,6}", inner, 0..4).prop_map(|m| json!(m)),
+        ]
+    })
+}
+
+/// Bodies that are mostly not a valid user: random bytes, a valid body cut
+/// short, the right keys with values of any type, and deep nesting.
+fn malformed_body() -> impl Strategy<Value = Vec<u8>> {
+    prop_oneof![
+        prop::collection::vec(any::<u8>(), 0..512),
+        (0..VALID_USER.len()).prop_map(|n| VALID_USER.as_bytes()[..n].to_vec()),
+        (json_value(), json_value(), json_value())
+            .prop_map(|(name, email, age)| json!({ "name": name, "email": email, "age": age }).to_string().into_bytes()),
+        (json_value(), "[a-z]{1,8}")
+            .prop_map(|(value, key)| json!({ key: value }).to_string().into_bytes()),
+        (1usize..50_000).prop_map(|depth| "[".repeat(depth).into_bytes()),
+        (1usize..50_000).prop_map(|depth| r#"{"name":"#.repeat(depth).into_bytes()),
+    ]
+}
+
+/// Query strings over the parameters the handlers read: duplicated keys,
+/// overflowing numbers, stray `%`, signs and exponents.
+fn pathological_query() -> impl Strategy<Value = String> {
+    let key = prop_oneof![Just("count".to_owned()), Just("limit".to_owned()), Just("offset".to_owned()), "[a-z]{1,6}"];
+    let value = prop_oneof!["[-+0-9a-zA-Z%.eE]{0,24}", "9{19,400}", "-9{19,400}", "%[0-9A-Fa-f]?"];
+    prop::collection::vec((key, value), 1..6).prop_map(|pairs| {
+        pairs.iter().map(|(k, v)| format!("{k}={v}")).collect::<Vec<_>>().join("&")
+    })
+}
+
+proptest! {
+    #[test]
+    fn malformed_create_bodies_are_400(body in malformed_body()) {
+        prop_assume!(serde_json::from_slice::<CreateUser>(&body).is_err());
+        let (status, json) = send(config(&[]), post_json("/users", body));
+        rejected(status, &json, StatusCode::BAD_REQUEST)?;
+    }
+
+    #[test]
+    fn malformed_update_bodies_are_400(body in malformed_body()) {
+        prop_assume!(serde_json::from_slice::<UpdateUser>(&body).is_err());
+        let req = post_json(&user_path(), body).method(actix_web::http::Method::PUT);
+        let (status, json) = send(config(&[]), req);
+        rejected(status, &json, StatusCode::BAD_REQUEST)?;
+    }
+
+    #[test]
+    fn pathological_queries_are_400(query in pathological_query()) {
+        prop_assume!(web::Query::<QueriesParams>::from_query(&query).is_err());
+        let uri = format!("/queries?{query}");
+        prop_assume!(uri.parse::<Uri>().is_ok());
+        let (status, json) = send(config(&[]), TestRequest::get().uri(&uri));
+        rejected(status, &json, StatusCode::BAD_REQUEST)?;
+    }
+
+    #[test]
+    fn pathological_user_list_queries_are_400(query in pathological_query()) {
+        prop_assume!(web::Query::<UsersParams>::from_query(&query).is_err());
+        let uri = format!("/users?{query}");
+        prop_assume!(uri.parse::<Uri>().is_ok());
+        let (status, json) = send(config(&[]), TestRequest::get().uri(&uri));
+        rejected(status, &json, StatusCode::BAD_REQUEST)?;
+    }
+
+    #[test]
+    fn overlong_tenant_headers_are_400(value in "[ -~]{64,16384}") {
+        let value = HeaderValue::from_str(&value).expect("printable ASCII");
+        let req = TestRequest::get()
+            .uri(&user_path())
+            .insert_header((HeaderName::from_static("x-tenant-id"), value));
+        let (status, json) = send(config(&[("multi_tenant", "on")]), req);
+        rejected(status, &json, StatusCode::BAD_REQUEST)?;
+    }
+
+    #[test]
+    fn overlong_admin_tokens_are_401(token in "[ -~]{0,16384}") {
+        let value = HeaderValue::from_str(&format!("Bearer {token}")).expect("printable ASCII");
+        let req = TestRequest::post().uri("/admin/cache-clear").insert_header((AUTHORIZATION, value));
+        let (status, json) = send(config(&[("admin_token", "a-valid-admin-token")]), req);
+        rejected(status, &json, StatusCode::UNAUTHORIZED)?;
+    }
+
+    #[test]
+    fn arbitrary_accept_headers_negotiate_or_406(accept in "[ -~]{0,16384}") {
+        let value = HeaderValue::from_str(&accept).expect("printable ASCII");
+        let req = TestRequest::get().uri("/json").insert_header((ACCEPT, value));
+        let (status, json) = send(config(&[]), req);
+        if status != StatusCode::OK {
+            rejected(status, &json, StatusCode::NOT_ACCEPTABLE)?;
+        }
+    }
+}
+
+#[test]
+fn truncated_json_names_the_position() {
+    let (status, json) = send(config(&[]), post_json("/users", &VALID_USER.as_bytes()[..20]));
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(json["error"].as_str().unwrap().contains("EOF"), "{json}");
+}
+
+#[test]
+fn missing_field_is_named() {
+    let (status, json) = send(config(&[]), post_json("/users", r#"{"name":"Ana"}"#));
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(json["error"].as_str().unwrap().contains("email"), "{json}");
+}
+
+#[test]
+fn non_json_content_type_is_400() {
+    let req = TestRequest::post()
+        .uri("/users")
+        .insert_header((CONTENT_TYPE, "text/plain"))
+        .set_payload(VALID_USER);
+    let (status, json) = send(config(&[]), req);
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(json["error"].is_string(), "{json}");
+}
+
+#[test]
+fn oversized_body_is_400() {
+    let body = format!(r#"{{"name":"{}","email":"a@b.c"}}"#, "a".repeat(3 << 20));
+    let (status, json) = send(config(&[]), post_json("/users", body));
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(json["error"].is_string(), "{json}");
+}
+
+#[test]
+fn non_numeric_user_id_is_404() {
+    let (status, _) = send(config(&[]), TestRequest::get().uri("/users/abc"));
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,305 @@
+//! The user handlers against [`MockUsers`]: what each endpoint answers when
+//! the repository times out on the pool, hits the unique email constraint or
+//! fails a statement, and that a failure lasts only as long as it is
+//! scripted.
+//!
+//! ```text
+//! cargo test --test handlers
+//! ```
+//!
+//! No database: `AppState::with_users` swaps the PostgreSQL repository for
+//! the mock, and the pool points at a closed port.
+//!
+//! The requests address the seeded rows by their integer ids, so a build
+//! with `uuid-keys` leaves this file out (`endpoints.rs` covers that build
+//! with `uuid_keys`).
+#![cfg(not(feature = "uuid-keys"))]
+
+use actix_http::Request;
+use actix_web::body::MessageBody;
+use actix_web::dev::{Service, ServiceResponse};
+use actix_web::http::header::CONTENT_TYPE;
+use actix_web::http::StatusCode;
+use actix_web::test::{call_service, init_service, read_body, TestRequest};
+use actix_web::web;
+use api_actix::config::Config;
+use api_actix::repo::memory::MemoryUsers;
+use api_actix::repo::mock::{MockUsers, Op};
+use api_actix::repo::RepoError;
+use api_actix::{app, db, AppState};
+use figment::providers::Serialized;
+use figment::Figment;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+/// The application over `users`, with default settings.
+async fn app_with(
+    users: Arc<MockUsers>,
+) -> impl Service<Request, Response = ServiceResponse<impl MessageBody>, Error = actix_web::Error> {
+    let figment = Figment::new().merge(Serialized::default(
+        "database_url",
+        "postgresql://benchmark@127.0.0.1:1/benchmark",
+    ));
+    let config = Config::from_figment(figment).expect("valid test configuration");
+    let pool = db::build_pool(&config).expect("pool");
+    let state = AppState::new(pool, config).await.expect("state").with_users(users);
+    init_service(app(web::Data::new(state))).await
+}
+
+fn mock() -> Arc<MockUsers> {
+    Arc::new(MockUsers::new(MemoryUsers::seeded(100)))
+}
+
+/// Status and JSON body (`Null` when empty or not JSON).
+async fn send<S, B>(app: &S, req: TestRequest) -> (StatusCode, Value)
+where
+    S: Service<Request, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    B: MessageBody,
+{
+    let res = call_service(app, req.to_request()).await;
+    let status = res.status();
+    let body = read_body(res).await;
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+fn json_body(req: TestRequest, body: Value) -> TestRequest {
+    req.insert_header((CONTENT_TYPE, "application/json")).set_payload(body.to_string())
+}
+
+/// One request per user endpoint, with the repository operation it reaches
+/// first.
+fn every_endpoint() -> Vec<(Op, TestRequest)> {
+    #[allow(unused_mut)]
+    let mut endpoints = vec![
+        (Op::RandomOne, TestRequest::get().uri("/db")),
+        (Op::Random, TestRequest::get().uri("/queries?count=5")),
+        (Op::Version, TestRequest::get().uri("/users")),
+        (Op::Version, TestRequest::get().uri("/users?limit=10")),
+        (Op::ById, TestRequest::get().uri("/users/1")),
+        (Op::Metadata, TestRequest::get().uri("/users/1/metadata")),
+        (Op::AgeHistogram, TestRequest::get().uri("/users/age-histogram")),
+        (Op::After, TestRequest::get().uri("/users/poll?timeout=0")),
+        (Op::StreamAll, TestRequest::get().uri("/users/export")),
+        (Op::Insert, json_body(TestRequest::post().uri("/users"), json!({ "name": "Ana", "email": "ana@example.com" }))),
+        (Op::InsertMany, json_body(TestRequest::post().uri("/users/bulk"), json!([{ "name": "Ana", "email": "ana@example.com" }]))),
+        (Op::Update, json_body(TestRequest::put().uri("/users/1"), json!({ "age": 31 }))),
+        (Op::PatchMetadata, json_body(TestRequest::patch().uri("/users/1/metadata"), json!({ "plan": "pro" }))),
+        (Op::Transfer, json_body(TestRequest::post().uri("/transfer"), json!({ "from": 1, "to": 3, "amount": "10.00" }))),
+        (Op::Delete, TestRequest::delete().uri("/users/2")),
+    ];
+    #[cfg(feature = "postgis")]
+    endpoints.push((Op::Near, TestRequest::get().uri("/users/near?lat=-23.55&lon=-46.63&radius=5000")));
+    endpoints
+}
+
+#[actix_web::test]
+async fn pool_timeout_is_a_connection_error_everywhere() {
+    for (op, req) in every_endpoint() {
+        let users = mock();
+        users.fail(op, RepoError::pool_timeout());
+        let app = app_with(users.clone()).await;
+        let (status, body) = send(&app, req).await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR, "{op:?}: {body}");
+        assert_eq!(body, json!({ "error": "Database connection error" }), "{op:?}");
+        assert_eq!(users.calls(), [op]);
+    }
+}
+
+#[actix_web::test]
+async fn statement_failures_name_the_operation() {
+    let cases = [
+        (Op::RandomOne, TestRequest::get().uri("/db"), "Database query error"),
+        (Op::Random, TestRequest::get().uri("/queries"), "Database query error"),
+        (Op::Version, TestRequest::get().uri("/users"), "Database query error"),
+        (Op::All, TestRequest::get().uri("/users"), "Database query error"),
+        (Op::Page, TestRequest::get().uri("/users?limit=10"), "Database query error"),
+        (Op::Matching, TestRequest::get().uri("/users?metadata.plan=pro"), "Database query error"),
+        (Op::Matching, TestRequest::get().uri("/users?tag=beta&limit=10"), "Database query error"),
+        (Op::Matching, TestRequest::get().uri("/users?status=suspended"), "Database query error"),
+        (Op::ById, TestRequest::get().uri("/users/1"), "Database query error"),
+        (Op::Metadata, TestRequest::get().uri("/users/1/metadata"), "Database query error"),
+        (Op::AgeHistogram, TestRequest::get().uri("/users/age-histogram"), "Database query error"),
+        (Op::After, TestRequest::get().uri("/users/poll"), "Database query error"),
+        (Op::StreamAll, TestRequest::get().uri("/users/export"), "Database query error"),
+        (
+            Op::Insert,
+            json_body(TestRequest::post().uri("/users"), json!({ "name": "Ana", "email": "ana@example.com" })),
+            "Database insert error",
+        ),
+        (
+            Op::InsertMany,
+            json_body(TestRequest::post().uri("/users/bulk"), json!([{ "name": "Ana", "email": "ana@example.com" }])),
+            "Database insert error",
+        ),
+        (Op::Update, json_body(TestRequest::put().uri("/users/1"), json!({ "age": 31 })), "Database update error"),
+        (
+            Op::PatchMetadata,
+            json_body(TestRequest::patch().uri("/users/1/metadata"), json!({ "plan": "pro" })),
+            "Database update error",
+        ),
+        (
+            Op::Transfer,
+            json_body(TestRequest::post().uri("/transfer"), json!({ "from": 1, "to": 2, "amount": "10.00" })),
+            "Database update error",
+        ),
+        (Op::Delete, TestRequest::delete().uri("/users/1"), "Database delete error"),
+    ];
+    for (op, req, message) in cases {
+        let users = mock();
+        users.fail(op, RepoError::Query("canceling statement due to statement timeout".into()));
+        let app = app_with(users).await;
+        let (status, body) = send(&app, req).await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR, "{op:?}: {body}");
+        assert_eq!(body, json!({ "error": message }), "{op:?}");
+    }
+}
+
+#[actix_web::test]
+async fn unique_violation_is_409() {
+    let users = mock();
+    users.fail(Op::Insert, RepoError::unique_violation());
+    users.fail(Op::Update, RepoError::unique_violation());
+    let app = app_with(users).await;
+
+    let create = json_body(TestRequest::post().uri("/users"), json!({ "name": "Ana", "email": "ana@example.com" }));
+    let (status, body) = send(&app, create).await;
+    assert_eq!((status, body), (StatusCode::CONFLICT, json!({ "error": "Email already exists" })));
+
+    let update = json_body(TestRequest::put().uri("/users/1"), json!({ "email": "ana@example.com" }));
+    let (status, body) = send(&app, update).await;
+    assert_eq!((status, body), (StatusCode::CONFLICT, json!({ "error": "Email already in use" })));
+}
+
+#[actix_web::test]
+async fn duplicate_email_in_the_store_is_409() {
+    let app = app_with(mock()).await;
+    // user1@outlook.com is the first seeded row, as in init.sql.
+    let create = json_body(TestRequest::post().uri("/users"), json!({ "name": "Ana", "email": "user1@outlook.com" }));
+    assert_eq!(send(&app, create).await.0, StatusCode::CONFLICT);
+    let update = json_body(TestRequest::put().uri("/users/2"), json!({ "email": "user1@outlook.com" }));
+    assert_eq!(send(&app, update).await.0, StatusCode::CONFLICT);
+    let bulk = json_body(TestRequest::post().uri("/users/bulk"), json!([{ "name": "Ana", "email": "user1@outlook.com" }]));
+    assert_eq!(send(&app, bulk).await.0, StatusCode::CONFLICT);
+}
+
+#[actix_web::test]
+async fn overdraft_is_409_and_moves_nothing() {
+    let app = app_with(mock()).await;
+    let transfer = |from: i32, to: i32, amount: &str| {
+        json_body(TestRequest::post().uri("/transfer"), json!({ "from": from, "to": to, "amount": amount }))
+    };
+
+    let (status, body) = send(&app, transfer(1, 2, "1000")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["from"], json!({ "id": 1, "balance": "0.00" }));
+    let (status, body) = send(&app, transfer(1, 2, "0.01")).await;
+    assert_eq!((status, body), (StatusCode::CONFLICT, json!({ "error": "Insufficient funds" })));
+    let (_, body) = send(&app, transfer(2, 1, "0.01")).await;
+    assert_eq!(body, json!({ "amount": "0.01", "from": { "id": 2, "balance": "1999.99" }, "to": { "id": 1, "balance": "0.01" } }));
+}
+
+fn form_body(req: TestRequest, body: &str) -> TestRequest {
+    req.insert_header((CONTENT_TYPE, "application/x-www-form-urlencoded")).set_payload(body.to_owned())
+}
+
+#[actix_web::test]
+async fn users_can_be_created_from_a_form() {
+    let users = mock();
+    let app = app_with(users.clone()).await;
+
+    let create = form_body(TestRequest::post().uri("/users"), "name=Ana+Lima&email=+Ana%40Example.com&age=30");
+    let (status, body) = send(&app, create).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!((&body["name"], &body["email"], &body["age"]), (&json!("Ana Lima"), &json!("ana@example.com"), &json!(30)));
+
+    // Same validation as JSON, same JSON 400, and the store is not reached.
+    let (status, body) = send(&app, form_body(TestRequest::post().uri("/users"), "name=Ana")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["error"].is_string());
+    assert_eq!(users.calls(), [Op::Insert]);
+
+    let (status, body) = send(&app, form_body(TestRequest::post().uri("/form-echo"), "a=1&b=2&a=3")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, json!({ "count": 3, "fields": [["a", "1"], ["b", "2"], ["a", "3"]] }));
+}
+
+#[actix_web::test]
+async fn a_scripted_failure_happens_once() {
+    let users = mock();
+    users.fail(Op::ById, RepoError::pool_timeout());
+    let app = app_with(users.clone()).await;
+
+    assert_eq!(send(&app, TestRequest::get().uri("/users/1")).await.0, StatusCode::INTERNAL_SERVER_ERROR);
+    let (status, body) = send(&app, TestRequest::get().uri("/users/1")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["email"], "user1@outlook.com");
+    assert_eq!(users.calls(), [Op::ById, Op::ById]);
+}
+
+#[actix_web::test]
+async fn an_outage_lasts_until_recovery() {
+    let users = mock();
+    users.fail_all(RepoError::pool_timeout());
+    let app = app_with(users.clone()).await;
+    for _ in 0..3 {
+        assert_eq!(send(&app, TestRequest::get().uri("/db")).await.0, StatusCode::INTERNAL_SERVER_ERROR);
+    }
+    users.recover();
+    assert_eq!(send(&app, TestRequest::get().uri("/db")).await.0, StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn a_failed_page_is_not_half_served() {
+    let users = mock();
+    users.fail(Op::Page, RepoError::Query("connection closed".into()));
+    let app = app_with(users.clone()).await;
+    let (status, _) = send(&app, TestRequest::get().uri("/users?limit=10")).await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(users.calls(), [Op::Version, Op::Page]);
+}
+
+#[actix_web::test]
+async fn rejected_requests_never_reach_the_repository() {
+    let users = mock();
+    let app = app_with(users.clone()).await;
+    let requests = [
+        json_body(TestRequest::put().uri("/users/1"), json!({})),
+        json_body(TestRequest::post().uri("/users"), json!({ "name": "Ana" })),
+        TestRequest::get().uri("/queries?count=abc"),
+        TestRequest::get().uri("/users?fields=password"),
+        TestRequest::get().uri("/users/1?fields=password"),
+        json_body(TestRequest::patch().uri("/users/1/metadata"), json!({})),
+        json_body(TestRequest::patch().uri("/users/1/metadata"), json!({ "limits..seats": 3 })),
+        TestRequest::get().uri("/users?metadata.plan=pro&metadata.plan=free"),
+        TestRequest::get().uri("/users?tag=%20"),
+        json_body(TestRequest::post().uri("/users"), json!({ "name": "Ana", "email": "ana@example.com", "tags": [""] })),
+        json_body(TestRequest::put().uri("/users/1"), json!({ "tags": vec!["t"; 17] })),
+        TestRequest::get().uri("/users?status=deleted"),
+        TestRequest::get().uri("/users?status=active&status=inactive"),
+        json_body(TestRequest::put().uri("/users/1"), json!({ "status": "Active" })),
+        json_body(TestRequest::post().uri("/transfer"), json!({ "from": 1, "to": 1, "amount": "1.00" })),
+        json_body(TestRequest::post().uri("/transfer"), json!({ "from": 1, "to": 2, "amount": 0.1 })),
+        json_body(TestRequest::post().uri("/transfer"), json!({ "from": 1, "to": 2, "amount": "0.001" })),
+    ];
+    for req in requests {
+        assert_eq!(send(&app, req).await.0, StatusCode::BAD_REQUEST);
+    }
+    assert_eq!(users.calls(), []);
+}
+
+#[actix_web::test]
+async fn missing_rows_are_404() {
+    let app = app_with(mock()).await;
+    let requests = [
+        TestRequest::get().uri("/users/1000"),
+        json_body(TestRequest::put().uri("/users/1000"), json!({ "age": 31 })),
+        TestRequest::delete().uri("/users/1000"),
+        TestRequest::get().uri("/users/1000/metadata"),
+        json_body(TestRequest::patch().uri("/users/1000/metadata"), json!({ "plan": "pro" })),
+        json_body(TestRequest::post().uri("/transfer"), json!({ "from": 1, "to": 1000, "amount": "1.00" })),
+    ];
+    for req in requests {
+        let (status, body) = send(&app, req).await;
+        assert_eq!((status, body), (StatusCode::NOT_FOUND, json!({ "error": "User not found" })));
+    }
+}
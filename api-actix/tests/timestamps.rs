@@ -0,0 +1,87 @@
+//! `TIMESTAMP_FORMAT`: each format on every representation of a user, and
+//! that what is written deserializes back to the same instant.
+//!
+//! ```text
+//! cargo test --test timestamps
+//! ```
+//!
+//! A binary of its own because the format is process-wide: the formats are
+//! checked one after another, in one test, against [`MemoryUsers`] (no
+//! database).
+
+use actix_http::Request;
+use actix_web::body::MessageBody;
+use actix_web::dev::{Service, ServiceResponse};
+use actix_web::test::{call_service, init_service, read_body, TestRequest};
+use actix_web::web;
+use api_actix::config::Config;
+use api_actix::repo::memory::MemoryUsers;
+use api_actix::{app, db, AppState, User};
+use chrono::{DateTime, SecondsFormat, Utc};
+use figment::providers::Serialized;
+use figment::Figment;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+/// The application over `users` with `timestamp_format`.
+async fn app_with(
+    users: &Arc<MemoryUsers>,
+    format: &str,
+) -> impl Service<Request, Response = ServiceResponse<impl MessageBody>, Error = actix_web::Error> {
+    let figment = Figment::new()
+        .merge(Serialized::default("database_url", "postgresql://benchmark@127.0.0.1:1/benchmark"))
+        .merge(Serialized::default("timestamp_format", format));
+    let config = Config::from_figment(figment).expect("valid test configuration");
+    let pool = db::build_pool(&config).expect("pool");
+    let state = AppState::new(pool, config).await.expect("state").with_users(users.clone());
+    init_service(app(web::Data::new(state))).await
+}
+
+async fn get<S, B>(app: &S, uri: &str) -> Value
+where
+    S: Service<Request, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    B: MessageBody,
+{
+    let res = call_service(app, TestRequest::get().uri(uri).to_request()).await;
+    serde_json::from_slice(&read_body(res).await).expect("JSON body")
+}
+
+/// `at` truncated to `digits` fraction digits.
+fn truncated(at: DateTime<Utc>, digits: SecondsFormat) -> DateTime<Utc> {
+    at.to_rfc3339_opts(digits, true).parse().unwrap()
+}
+
+#[actix_web::test]
+async fn every_format_round_trips() {
+    let users = Arc::new(MemoryUsers::seeded(10));
+    let app = app_with(&users, "rfc3339-nanos").await;
+    // The first seeded user, whatever the key type of the build.
+    let first = get(&app, "/users").await[0].clone();
+    let path = match &first["id"] {
+        Value::String(id) => format!("/users/{id}"),
+        id => format!("/users/{id}"),
+    };
+    let exact: DateTime<Utc> = serde_json::from_value(first["created_at"].clone()).unwrap();
+
+    let text = |digits| json!(exact.to_rfc3339_opts(digits, true));
+    let at = |digits| truncated(exact, digits);
+    for (format, expected, instant) in [
+        ("rfc3339", json!(exact), exact),
+        ("rfc3339-secs", text(SecondsFormat::Secs), at(SecondsFormat::Secs)),
+        ("rfc3339-millis", text(SecondsFormat::Millis), at(SecondsFormat::Millis)),
+        ("rfc3339-micros", text(SecondsFormat::Micros), at(SecondsFormat::Micros)),
+        ("rfc3339-nanos", text(SecondsFormat::Nanos), exact),
+        ("epoch-millis", json!(exact.timestamp_millis()), at(SecondsFormat::Millis)),
+    ] {
+        let app = app_with(&users, format).await;
+        let one = get(&app, &path).await;
+        assert_eq!(one["created_at"], expected, "{format}");
+        assert_eq!(get(&app, "/users").await[0]["created_at"], expected, "{format} list");
+        assert_eq!(get(&app, &format!("{path}?fields=id,created_at")).await["created_at"], expected, "{format} fields");
+
+        let user: User = serde_json::from_value(one.clone()).unwrap();
+        assert_eq!(user.created_at, instant, "{format}");
+        // Written again, it is the same text.
+        assert_eq!(serde_json::to_value(&user).unwrap(), one, "{format}");
+    }
+}
@@ -0,0 +1,290 @@
+//! Typed async client for the benchmark API.
+//!
+//! One method per endpoint, taking and returning the types in [`types`], so
+//! tests and scripted scenarios drive the API without spelling out paths,
+//! bodies and status codes each time.  Every call checks the status the
+//! endpoint answers on success and turns anything else into
+//! [`Error::Status`], with the `error` field of the body.
+//!
+//! The calls follow the Actix API.  The endpoints of the shared contract (the
+//! OpenAPI document, see [`OPERATIONS`]) work against every implementation;
+//! the rest (`/users/bulk`, `/users/export`, `/auth/*`, `/admin/*`...) only
+//! against those that have them.  Diagnostics (`/metrics`, `/pool-stats`,
+//! profiling) are left out.
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), client::Error> {
+//! let api = client::Client::new("http://localhost:3004");
+//! let page = api.users_page(10, 0).await?;
+//! let user = api.user(page.data[0].id).await?;
+//! # Ok(()) }
+//! ```
+
+pub mod types;
+
+pub use types::*;
+
+use reqwest::{Method, RequestBuilder, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use std::fmt;
+use std::time::Duration;
+
+/// Covers the longest long poll (`timeout` is capped at 60 s by the API).
+const TIMEOUT: Duration = Duration::from_secs(90);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The documented operations (`METHOD /path`, as in the OpenAPI document)
+/// this client has a method for.
+pub const OPERATIONS: &[(&str, &str)] = &[
+    ("GET", "/"),
+    ("GET", "/json"),
+    ("GET", "/db"),
+    ("GET", "/queries"),
+    ("GET", "/users"),
+    ("POST", "/users"),
+    ("GET", "/users/{id}"),
+    ("PUT", "/users/{id}"),
+    ("DELETE", "/users/{id}"),
+];
+
+/// Why a call failed.
+#[derive(Debug)]
+pub enum Error {
+    /// Connection, timeout, or a response that could not be read.
+    Transport(reqwest::Error),
+    /// A body that does not match the expected type.
+    Decode(serde_json::Error),
+    /// A status other than the endpoint's success status.
+    Status { status: u16, error: String },
+}
+
+impl Error {
+    /// The status, when the API answered with an unexpected one.
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            Error::Status { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Transport(e) => write!(f, "request failed: {e}"),
+            Error::Decode(e) => write!(f, "unexpected body: {e}"),
+            Error::Status { status, error } => write!(f, "status {status}: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        Error::Transport(e)
+    }
+}
+
+/// Client for one API instance.  Cheap to clone: clones share the
+/// connection pool.
+#[derive(Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    base: String,
+    tenant: Option<String>,
+    admin_token: Option<String>,
+}
+
+impl Client {
+    /// Client for the API at `base` (`http://localhost:3004`).
+    pub fn new(base: &str) -> Client {
+        let http = reqwest::Client::builder()
+            .timeout(TIMEOUT)
+            .connect_timeout(CONNECT_TIMEOUT)
+            .build()
+            .expect("reqwest client without TLS cannot fail to build");
+        Client { http, base: base.trim_end_matches('/').to_owned(), tenant: None, admin_token: None }
+    }
+
+    /// Sends `X-Tenant-Id: <tenant>` with every call (`MULTI_TENANT=on`).
+    pub fn with_tenant(mut self, tenant: impl Into<String>) -> Client {
+        self.tenant = Some(tenant.into());
+        self
+    }
+
+    /// Token for the `/admin` calls (`ADMIN_TOKEN`).
+    pub fn with_admin_token(mut self, token: impl Into<String>) -> Client {
+        self.admin_token = Some(token.into());
+        self
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base
+    }
+
+    // ── Benchmark endpoints ─────────────────────────────────────────────────
+
+    /// GET /  — the service banner, whose shape varies by implementation.
+    pub async fn banner(&self) -> Result<serde_json::Value, Error> {
+        self.call(self.request(Method::GET, "/"), StatusCode::OK).await
+    }
+
+    /// GET /json
+    pub async fn json(&self) -> Result<Message, Error> {
+        self.call(self.request(Method::GET, "/json"), StatusCode::OK).await
+    }
+
+    /// GET /db  — one random user.
+    pub async fn db(&self) -> Result<User, Error> {
+        self.call(self.request(Method::GET, "/db"), StatusCode::OK).await
+    }
+
+    /// GET /queries?count=N  — `count` random users (clamped to 1–500 by the API).
+    pub async fn queries(&self, count: u32) -> Result<Vec<User>, Error> {
+        let req = self.request(Method::GET, "/queries").query(&[("count", count)]);
+        self.call(req, StatusCode::OK).await
+    }
+
+    // ── Users ───────────────────────────────────────────────────────────────
+
+    /// GET /users  — every user.
+    pub async fn users(&self) -> Result<Vec<User>, Error> {
+        self.call(self.request(Method::GET, "/users"), StatusCode::OK).await
+    }
+
+    /// GET /users?limit=N&offset=N
+    pub async fn users_page(&self, limit: i64, offset: i64) -> Result<Page, Error> {
+        let req = self.request(Method::GET, "/users").query(&[("limit", limit), ("offset", offset)]);
+        self.call(req, StatusCode::OK).await
+    }
+
+    /// GET /users/:id  — [`Error::Status`] with 404 when there is no such user.
+    pub async fn user(&self, id: i64) -> Result<User, Error> {
+        self.call(self.request(Method::GET, &format!("/users/{id}")), StatusCode::OK).await
+    }
+
+    /// POST /users  — 409 when the email is taken.
+    pub async fn create_user(&self, user: &CreateUser) -> Result<User, Error> {
+        self.call(self.request(Method::POST, "/users").json(user), StatusCode::CREATED).await
+    }
+
+    /// PUT /users/:id
+    pub async fn update_user(&self, id: i64, changes: &UpdateUser) -> Result<User, Error> {
+        let req = self.request(Method::PUT, &format!("/users/{id}")).json(changes);
+        self.call(req, StatusCode::OK).await
+    }
+
+    /// DELETE /users/:id
+    pub async fn delete_user(&self, id: i64) -> Result<(), Error> {
+        self.send(self.request(Method::DELETE, &format!("/users/{id}")), StatusCode::NO_CONTENT).await?;
+        Ok(())
+    }
+
+    /// GET /users/age-histogram?buckets=N
+    pub async fn age_histogram(&self, buckets: i32) -> Result<AgeHistogram, Error> {
+        let req = self.request(Method::GET, "/users/age-histogram").query(&[("buckets", buckets)]);
+        self.call(req, StatusCode::OK).await
+    }
+
+    /// GET /users/poll?since_id=N&timeout=S  — users created after `since_id`,
+    /// empty when none arrived within `timeout_secs`.
+    pub async fn poll(&self, since_id: i64, timeout_secs: u64) -> Result<Vec<User>, Error> {
+        let req = self
+            .request(Method::GET, "/users/poll")
+            .query(&[("since_id", since_id)])
+            .query(&[("timeout", timeout_secs)]);
+        self.call(req, StatusCode::OK).await
+    }
+
+    /// GET /users/export  — every user, read from the NDJSON stream.
+    pub async fn export(&self) -> Result<Vec<User>, Error> {
+        let body = self.send(self.request(Method::GET, "/users/export"), StatusCode::OK).await?.text().await?;
+        body.lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_str(line).map_err(Error::Decode))
+            .collect()
+    }
+
+    /// POST /users/bulk?on_conflict=
+    pub async fn bulk_insert(&self, users: &[CreateUser], on_conflict: OnConflict) -> Result<BulkInsert, Error> {
+        let req = self
+            .request(Method::POST, "/users/bulk")
+            .query(&[("on_conflict", on_conflict.as_str())])
+            .json(users);
+        self.call(req, StatusCode::OK).await
+    }
+
+    /// POST /transfer  — 404 for an unknown user, 409 when `from` cannot pay.
+    pub async fn transfer(&self, transfer: &Transfer) -> Result<Receipt, Error> {
+        self.call(self.request(Method::POST, "/transfer").json(transfer), StatusCode::OK).await
+    }
+
+    // ── Accounts ────────────────────────────────────────────────────────────
+
+    /// POST /auth/register  — 409 when the email is registered.
+    pub async fn register(&self, credentials: &Credentials) -> Result<Account, Error> {
+        self.call(self.request(Method::POST, "/auth/register").json(credentials), StatusCode::CREATED).await
+    }
+
+    /// POST /auth/login  — 401 for a wrong email or password.
+    pub async fn login(&self, credentials: &Credentials) -> Result<Account, Error> {
+        self.call(self.request(Method::POST, "/auth/login").json(credentials), StatusCode::OK).await
+    }
+
+    // ── Admin (needs `with_admin_token`) ────────────────────────────────────
+
+    /// POST /admin/reset?users=N  — `None` seeds the API's default count.
+    pub async fn admin_reset(&self, users: Option<i64>) -> Result<Reset, Error> {
+        let req = self.admin(Method::POST, "/admin/reset").query(&[("users", users)]);
+        self.call(req, StatusCode::OK).await
+    }
+
+    /// POST /admin/seed?users=N&on_conflict=
+    pub async fn admin_seed(&self, users: Option<i64>, on_conflict: OnConflict) -> Result<Seed, Error> {
+        let req = self
+            .admin(Method::POST, "/admin/seed")
+            .query(&[("users", users)])
+            .query(&[("on_conflict", on_conflict.as_str())]);
+        self.call(req, StatusCode::OK).await
+    }
+
+    // ── Plumbing ────────────────────────────────────────────────────────────
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let req = self.http.request(method, format!("{}{path}", self.base));
+        match &self.tenant {
+            Some(tenant) => req.header("X-Tenant-Id", tenant),
+            None => req,
+        }
+    }
+
+    fn admin(&self, method: Method, path: &str) -> RequestBuilder {
+        let req = self.request(method, path);
+        match &self.admin_token {
+            Some(token) => req.bearer_auth(token),
+            None => req,
+        }
+    }
+
+    /// Sends `req` and fails unless the answer is `expected`.
+    async fn send(&self, req: RequestBuilder, expected: StatusCode) -> Result<Response, Error> {
+        let res = req.send().await?;
+        if res.status() == expected {
+            return Ok(res);
+        }
+        let status = res.status().as_u16();
+        let body = res.text().await.unwrap_or_default();
+        // The documented error body; anything else is reported as is.
+        let error = serde_json::from_str::<serde_json::Value>(&body)
+            .ok()
+            .and_then(|v| v["error"].as_str().map(str::to_owned))
+            .unwrap_or(body);
+        Err(Error::Status { status, error })
+    }
+
+    async fn call<T: DeserializeOwned>(&self, req: RequestBuilder, expected: StatusCode) -> Result<T, Error> {
+        let body = self.send(req, expected).await?.bytes().await?;
+        serde_json::from_slice(&body).map_err(Error::Decode)
+    }
+}
@@ -0,0 +1,178 @@
+//! Request and response bodies, in the shapes the API documents.
+//!
+//! Responses are decoded leniently where the implementations differ: fields
+//! only some of them send are optional, and `created_at` is also accepted as
+//! `createdAt` (Actix built with `camel-case`), with any number of fraction
+//! digits or as epoch milliseconds (Actix's `TIMESTAMP_FORMAT`).
+
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// A row of `users`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct User {
+    /// `i64`, so that Actix built with `bigint-keys` fits too.
+    pub id: i64,
+    pub name: String,
+    pub email: String,
+    #[serde(default)]
+    pub age: Option<i32>,
+    #[serde(alias = "createdAt", deserialize_with = "timestamp")]
+    pub created_at: DateTime<Utc>,
+    /// Only Actix sends it.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// `active`, `inactive` or `suspended`; only Actix sends it.
+    #[serde(default)]
+    pub status: Option<String>,
+}
+
+/// RFC 3339 text or epoch milliseconds.
+fn timestamp<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DateTime<Utc>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Any {
+        Text(DateTime<Utc>),
+        Millis(i64),
+    }
+    match Any::deserialize(deserializer)? {
+        Any::Text(at) => Ok(at),
+        Any::Millis(ms) => Utc
+            .timestamp_millis_opt(ms)
+            .single()
+            .ok_or_else(|| serde::de::Error::custom(format!("timestamp {ms} ms is out of range"))),
+    }
+}
+
+/// Body of POST /users, and one element of POST /users/bulk.
+#[derive(Debug, Clone, Serialize)]
+pub struct CreateUser {
+    pub name: String,
+    pub email: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub age: Option<i32>,
+}
+
+/// Body of PUT /users/:id; fields left `None` are not sent and keep their
+/// value.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UpdateUser {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub age: Option<i32>,
+}
+
+/// GET /users?limit=N&offset=N.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Page {
+    pub data: Vec<User>,
+    pub total: i64,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+/// GET /json.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Message {
+    pub message: String,
+    pub framework: String,
+}
+
+/// GET /users/age-histogram.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AgeHistogram {
+    pub min: Option<i32>,
+    pub max: Option<i32>,
+    pub counts: Vec<i64>,
+    pub unknown: i64,
+}
+
+/// What a taken email does to a bulk insert or a seed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OnConflict {
+    /// Nothing is written; the call fails with 409.
+    #[default]
+    Error,
+    /// The existing row is kept.
+    Skip,
+    /// The existing row gets the new name and age.
+    Update,
+}
+
+impl OnConflict {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            OnConflict::Error => "error",
+            OnConflict::Skip => "skip",
+            OnConflict::Update => "update",
+        }
+    }
+}
+
+/// POST /users/bulk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct BulkInsert {
+    pub inserted: i64,
+    pub updated: i64,
+    pub skipped: i64,
+}
+
+/// Body of POST /transfer.  Amounts are decimal text (`"10.50"`), never
+/// floats, so they compare exactly.
+#[derive(Debug, Clone, Serialize)]
+pub struct Transfer {
+    pub from: i64,
+    pub to: i64,
+    pub amount: String,
+}
+
+/// A user's balance after a transfer, with two decimal places.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Balance {
+    pub id: i64,
+    pub balance: String,
+}
+
+/// POST /transfer.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Receipt {
+    pub amount: String,
+    pub from: Balance,
+    pub to: Balance,
+}
+
+/// Body of POST /auth/register and /auth/login.
+#[derive(Debug, Clone, Serialize)]
+pub struct Credentials {
+    pub email: String,
+    pub password: String,
+}
+
+/// An account, as returned by /auth/register and /auth/login.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Account {
+    pub id: i32,
+    pub email: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// POST /admin/reset.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Reset {
+    pub users: i64,
+    pub truncated: Vec<String>,
+    pub cached_responses_cleared: u64,
+    pub duration_ms: u64,
+}
+
+/// POST /admin/seed.
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct Seed {
+    pub inserted: i64,
+    pub updated: i64,
+    pub skipped: i64,
+    pub duration_ms: u64,
+}
@@ -0,0 +1,388 @@
+//! Conformance checks generated from an OpenAPI document and executed against
+//! a running API instance.
+//!
+//! For every operation in the spec the runner derives a handful of cases —
+//! required fields only, every field, an empty body, an unknown id — sends
+//! them, and checks that:
+//!
+//! * the status code is one the spec documents for that operation (a 2xx for
+//!   the valid cases, the documented 4xx for the invalid ones);
+//! * the body validates against the schema documented for that status,
+//!   including required fields, nullability and undeclared properties;
+//! * statuses documented without content (204, 304) come back empty.
+//!
+//! Item routes (`/users/{id}`) run against a fixture created through the
+//! collection's POST; everything created during the run is deleted afterwards.
+
+pub mod schema;
+
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Id used for the "unknown id" cases; no seeded or fixture row reaches it.
+const UNKNOWN_ID: &str = "2147483647";
+
+/// A parsed OpenAPI document.
+pub struct Spec {
+    doc: Value,
+}
+
+/// One `METHOD /path` entry of the spec.
+pub struct Operation {
+    pub method: String,
+    pub path: String,
+    /// `(name, schema, required)` of each query parameter.
+    pub query: Vec<(String, Value, bool)>,
+    pub request_body: Option<Value>,
+    /// Documented responses by status; `None` when the status has no content.
+    pub responses: BTreeMap<u16, Option<Value>>,
+}
+
+/// What a case expects back.
+enum Expect {
+    /// Any documented 2xx.
+    Success,
+    Status(u16),
+}
+
+struct Case {
+    name: &'static str,
+    path: String,
+    query: Vec<(String, String)>,
+    body: Option<Value>,
+    expect: Expect,
+}
+
+/// Outcome of a run: how many requests were checked and what failed.
+pub struct Report {
+    pub cases: usize,
+    pub failures: Vec<String>,
+}
+
+impl Spec {
+    pub fn fetch(url: &str) -> Result<Spec, String> {
+        let doc = ureq::get(url)
+            .timeout(Duration::from_secs(10))
+            .call()
+            .map_err(|e| format!("GET {url}: {e}"))?
+            .into_json()
+            .map_err(|e| format!("GET {url}: invalid JSON: {e}"))?;
+        Ok(Spec { doc })
+    }
+
+    pub fn from_value(doc: Value) -> Spec {
+        Spec { doc }
+    }
+
+    fn components(&self) -> &Value {
+        &self.doc["components"]
+    }
+
+    pub fn operations(&self) -> Vec<Operation> {
+        let mut ops = Vec::new();
+        for (path, item) in self.doc["paths"].as_object().into_iter().flatten() {
+            for (method, op) in item.as_object().into_iter().flatten() {
+                if !matches!(method.as_str(), "get" | "post" | "put" | "patch" | "delete") {
+                    continue;
+                }
+                let query = op["parameters"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter(|p| p["in"] == "query")
+                    .map(|p| {
+                        (
+                            p["name"].as_str().unwrap_or_default().to_owned(),
+                            p["schema"].clone(),
+                            p["required"] == Value::Bool(true),
+                        )
+                    })
+                    .collect();
+                let responses = op["responses"]
+                    .as_object()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|(status, resp)| {
+                        let schema = resp["content"]["application/json"].get("schema").cloned();
+                        Some((status.parse().ok()?, schema))
+                    })
+                    .collect();
+                ops.push(Operation {
+                    method: method.to_uppercase(),
+                    path: path.clone(),
+                    query,
+                    request_body: op["requestBody"]["content"]["application/json"].get("schema").cloned(),
+                    responses,
+                });
+            }
+        }
+        // Deletes last, so the fixtures survive until every other case ran.
+        ops.sort_by_key(|op| op.method == "DELETE");
+        ops
+    }
+}
+
+/// `/users/{id}` → `("/users", "id")`.
+fn item_route(path: &str) -> Option<(&str, &str)> {
+    let (parent, last) = path.rsplit_once('/')?;
+    let param = last.strip_prefix('{')?.strip_suffix('}')?;
+    Some((parent, param))
+}
+
+fn query_value(schema: &Value) -> String {
+    match schema["type"].as_str().or_else(|| schema["type"][0].as_str()) {
+        Some("integer") | Some("number") => "1".into(),
+        Some("boolean") => "true".into(),
+        _ => "contract".into(),
+    }
+}
+
+/// Runs every generated case for `spec` against the API at `base`.
+pub fn run(base: &str, spec: &Spec) -> Report {
+    let run_id = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or_default();
+    let mut runner = Runner {
+        base: base.trim_end_matches('/').to_owned(),
+        agent: ureq::AgentBuilder::new().timeout(Duration::from_secs(10)).build(),
+        spec,
+        run_id,
+        counter: 0,
+        created: Vec::new(),
+        report: Report { cases: 0, failures: Vec::new() },
+    };
+
+    let ops = spec.operations();
+    let mut fixtures = BTreeMap::new();
+    for op in &ops {
+        if let Some((parent, param)) = item_route(&op.path) {
+            if !fixtures.contains_key(&op.path) {
+                match runner.create_fixture(&ops, parent, param) {
+                    Ok(id) => {
+                        fixtures.insert(op.path.clone(), id);
+                    }
+                    Err(e) => runner.fail(op, "fixture", e),
+                }
+            }
+        }
+    }
+
+    for op in &ops {
+        for case in runner.cases(op, fixtures.get(&op.path)) {
+            runner.check(op, case);
+        }
+    }
+    runner.cleanup(&ops);
+    runner.report
+}
+
+struct Runner<'a> {
+    base: String,
+    agent: ureq::Agent,
+    spec: &'a Spec,
+    run_id: u128,
+    counter: u32,
+    /// `(item path, id)` of every row created, for cleanup.
+    created: Vec<(String, String)>,
+    report: Report,
+}
+
+impl Runner<'_> {
+    fn tag(&mut self) -> String {
+        self.counter += 1;
+        format!("contract-{}-{}", self.run_id, self.counter)
+    }
+
+    fn fail(&mut self, op: &Operation, case: &str, message: String) {
+        self.report.failures.push(format!("{} {} [{case}]: {message}", op.method, op.path));
+    }
+
+    /// Creates a row through `POST parent` and returns the value of `param`
+    /// (or `id`) in the response.
+    fn create_fixture(&mut self, ops: &[Operation], parent: &str, param: &str) -> Result<String, String> {
+        let post = ops
+            .iter()
+            .find(|op| op.method == "POST" && op.path == parent)
+            .ok_or_else(|| format!("no POST {parent} to create a fixture with"))?;
+        let schema = post.request_body.as_ref().ok_or("POST without a request body")?;
+        let tag = self.tag();
+        let body = schema::example(schema, self.spec.components(), true, &tag);
+        let (status, created) = self.send("POST", parent, &[], Some(&body))?;
+        if !(200..300).contains(&status) {
+            return Err(format!("POST {parent} returned {status}"));
+        }
+        let id = created.get(param).or_else(|| created.get("id")).ok_or("response has no id")?;
+        let id = match id {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        self.created.push((format!("{parent}/{{{param}}}"), id.clone()));
+        Ok(id)
+    }
+
+    fn cases(&mut self, op: &Operation, fixture: Option<&String>) -> Vec<Case> {
+        let components = self.spec.components();
+        let path_with = |id: &str| match item_route(&op.path) {
+            Some((parent, _)) => format!("{parent}/{id}"),
+            None => op.path.clone(),
+        };
+        let required_query: Vec<_> = op
+            .query
+            .iter()
+            .filter(|(_, _, required)| *required)
+            .map(|(name, schema, _)| (name.clone(), query_value(schema)))
+            .collect();
+        let all_query: Vec<_> = op.query.iter().map(|(name, schema, _)| (name.clone(), query_value(schema))).collect();
+        let rejects_bad_input = [400, 422].into_iter().find(|s| op.responses.contains_key(s));
+
+        let mut cases = Vec::new();
+        let valid_path = match (item_route(&op.path), fixture) {
+            (Some(_), Some(id)) => Some(path_with(id)),
+            (Some(_), None) => None,
+            (None, _) => Some(op.path.clone()),
+        };
+
+        if let Some(path) = valid_path.clone() {
+            let minimal = op.request_body.as_ref().map(|s| {
+                let tag = self.tag();
+                schema::example(s, components, false, &tag)
+            });
+            // A body with no required fields is the "empty body" case below.
+            if minimal.as_ref().is_none_or(|b| b.as_object().is_none_or(|o| !o.is_empty())) {
+                cases.push(Case {
+                    name: "required fields only",
+                    path: path.clone(),
+                    query: required_query.clone(),
+                    body: minimal,
+                    expect: Expect::Success,
+                });
+            }
+            if op.method != "DELETE" && (all_query.len() > required_query.len() || op.request_body.is_some()) {
+                let full = op.request_body.as_ref().map(|s| {
+                    let tag = self.tag();
+                    schema::example(s, components, true, &tag)
+                });
+                cases.push(Case {
+                    name: "all fields",
+                    path: path.clone(),
+                    query: all_query.clone(),
+                    body: full,
+                    expect: Expect::Success,
+                });
+            }
+            if let (Some(_), Some(status)) = (&op.request_body, rejects_bad_input) {
+                cases.push(Case {
+                    name: "empty body",
+                    path,
+                    query: required_query.clone(),
+                    body: Some(serde_json::json!({})),
+                    expect: Expect::Status(status),
+                });
+            }
+        }
+
+        if item_route(&op.path).is_some() && op.responses.contains_key(&404) {
+            let body = op.request_body.as_ref().map(|s| {
+                let tag = self.tag();
+                schema::example(s, components, true, &tag)
+            });
+            cases.push(Case {
+                name: "unknown id",
+                path: path_with(UNKNOWN_ID),
+                query: required_query,
+                body,
+                expect: Expect::Status(404),
+            });
+        }
+
+        // The valid DELETE removes the fixture; don't delete it twice in cleanup.
+        if op.method == "DELETE" {
+            if let Some(id) = fixture {
+                self.created.retain(|(path, created)| !(path == &op.path && created == id));
+            }
+        }
+        cases
+    }
+
+    fn check(&mut self, op: &Operation, case: Case) {
+        self.report.cases += 1;
+        let (status, body) = match self.send(&op.method, &case.path, &case.query, case.body.as_ref()) {
+            Ok(r) => r,
+            Err(e) => return self.fail(op, case.name, e),
+        };
+
+        if op.method == "POST" && (200..300).contains(&status) {
+            if let (Some(id), Some(item)) = (body.get("id"), self.item_path(&op.path)) {
+                self.created.push((item, id.to_string()));
+            }
+        }
+
+        let Some(documented) = op.responses.get(&status) else {
+            let listed: Vec<_> = op.responses.keys().map(u16::to_string).collect();
+            return self.fail(op, case.name, format!("status {status} is not documented (spec lists {})", listed.join(", ")));
+        };
+        match case.expect {
+            Expect::Success if !(200..300).contains(&status) => {
+                return self.fail(op, case.name, format!("expected a 2xx, got {status}: {body}"));
+            }
+            Expect::Status(want) if status != want => {
+                return self.fail(op, case.name, format!("expected {want}, got {status}: {body}"));
+            }
+            _ => {}
+        }
+
+        match documented {
+            Some(schema) => {
+                let mut errors = Vec::new();
+                schema::validate(schema, self.spec.components(), &body, "$", &mut errors);
+                for e in errors {
+                    self.fail(op, case.name, format!("{status} body: {e}"));
+                }
+            }
+            None if !body.is_null() => {
+                self.fail(op, case.name, format!("{status} is documented without content, got {body}"));
+            }
+            None => {}
+        }
+    }
+
+    /// `/users` → `/users/{id}` when the spec has such an item route.
+    fn item_path(&self, collection: &str) -> Option<String> {
+        self.spec
+            .operations()
+            .into_iter()
+            .map(|op| op.path)
+            .find(|path| item_route(path).is_some_and(|(parent, _)| parent == collection))
+    }
+
+    /// Sends a request; non-JSON or empty bodies come back as `Value::Null`.
+    fn send(&self, method: &str, path: &str, query: &[(String, String)], body: Option<&Value>) -> Result<(u16, Value), String> {
+        let mut req = self.agent.request(method, &format!("{}{path}", self.base));
+        for (k, v) in query {
+            req = req.query(k, v);
+        }
+        let result = match body {
+            Some(b) => req.send_json(b),
+            None => req.call(),
+        };
+        let resp = match result {
+            Ok(r) => r,
+            Err(ureq::Error::Status(_, r)) => r,
+            Err(e) => return Err(format!("{method} {path}: {e}")),
+        };
+        let status = resp.status();
+        let text = resp.into_string().map_err(|e| format!("{method} {path}: {e}"))?;
+        Ok((status, serde_json::from_str(&text).unwrap_or(Value::Null)))
+    }
+
+    fn cleanup(&mut self, ops: &[Operation]) {
+        for (item_path, id) in std::mem::take(&mut self.created) {
+            let deletable = ops.iter().any(|op| op.method == "DELETE" && op.path == item_path);
+            if let (true, Some((parent, _))) = (deletable, item_route(&item_path)) {
+                let _ = self.send("DELETE", &format!("{parent}/{id}"), &[], None);
+            }
+        }
+    }
+}
@@ -0,0 +1,230 @@
+//! The subset of JSON Schema (OpenAPI 3.1 dialect, plus 3.0 `nullable`) that
+//! the benchmark specs use: `$ref`, `type`, `properties`, `required`, `items`,
+//! `oneOf`/`anyOf`/`allOf` and `enum`.
+//!
+//! Validation is stricter than JSON Schema in one way: properties that the
+//! schema does not declare are reported, because an undocumented column in a
+//! response is exactly the drift these tests exist to catch.
+
+use serde_json::{json, Map, Value};
+
+/// Resolves `#/components/schemas/Name` references against `components`.
+fn deref<'a>(schema: &'a Value, components: &'a Value) -> &'a Value {
+    match schema.get("$ref").and_then(Value::as_str) {
+        Some(r) => {
+            let name = r.rsplit('/').next().unwrap_or(r);
+            let target = &components["schemas"][name];
+            if target.is_null() { schema } else { deref(target, components) }
+        }
+        None => schema,
+    }
+}
+
+/// Declared types, with `nullable: true` folded into `"null"`.
+fn types(schema: &Value) -> Vec<&str> {
+    let mut out: Vec<&str> = match &schema["type"] {
+        Value::String(t) => vec![t.as_str()],
+        Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    };
+    if schema["nullable"] == Value::Bool(true) {
+        out.push("null");
+    }
+    out
+}
+
+fn type_of(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Validates `value`, appending one message per violation to `errors`.
+/// `at` is the JSON path used in those messages.
+pub fn validate(schema: &Value, components: &Value, value: &Value, at: &str, errors: &mut Vec<String>) {
+    let schema = deref(schema, components);
+
+    for (keyword, need_one) in [("oneOf", true), ("anyOf", false)] {
+        if let Some(options) = schema[keyword].as_array() {
+            let matching = options
+                .iter()
+                .filter(|option| {
+                    let mut e = Vec::new();
+                    validate(option, components, value, at, &mut e);
+                    e.is_empty()
+                })
+                .count();
+            if matching == 0 || (need_one && matching > 1) {
+                errors.push(format!("{at}: matches {matching} of the {keyword} alternatives"));
+            }
+        }
+    }
+    if let Some(all) = schema["allOf"].as_array() {
+        for part in all {
+            validate(part, components, value, at, errors);
+        }
+    }
+
+    let declared = types(schema);
+    let actual = type_of(value);
+    let accepted = declared.is_empty()
+        || declared.contains(&actual)
+        || (actual == "integer" && declared.contains(&"number"));
+    if !accepted {
+        errors.push(format!("{at}: expected {}, got {actual}", declared.join(" | ")));
+        return;
+    }
+
+    if let Some(allowed) = schema["enum"].as_array() {
+        if !allowed.contains(value) {
+            errors.push(format!("{at}: {value} is not one of {}", Value::Array(allowed.clone())));
+        }
+    }
+
+    match value {
+        Value::Object(fields) => validate_object(schema, components, fields, at, errors),
+        Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    validate(item_schema, components, item, &format!("{at}[{i}]"), errors);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn validate_object(schema: &Value, components: &Value, fields: &Map<String, Value>, at: &str, errors: &mut Vec<String>) {
+    let Some(properties) = schema["properties"].as_object() else {
+        return;
+    };
+    for name in schema["required"].as_array().into_iter().flatten().filter_map(Value::as_str) {
+        if !fields.contains_key(name) {
+            errors.push(format!("{at}.{name}: required property is missing"));
+        }
+    }
+    for (name, value) in fields {
+        match properties.get(name) {
+            Some(property) => validate(property, components, value, &format!("{at}.{name}"), errors),
+            None => errors.push(format!("{at}.{name}: property is not declared in the spec")),
+        }
+    }
+}
+
+/// Builds a value that satisfies `schema`.  With `all_fields` optional object
+/// properties are filled too; otherwise only the required ones are, which
+/// exercises the defaults and the nullable columns.  `tag` makes strings
+/// unique per call so that unique constraints (e.g. `email`) are not hit.
+pub fn example(schema: &Value, components: &Value, all_fields: bool, tag: &str) -> Value {
+    example_named(schema, components, all_fields, tag, "")
+}
+
+fn example_named(schema: &Value, components: &Value, all_fields: bool, tag: &str, name: &str) -> Value {
+    let schema = deref(schema, components);
+    if let Some(first) = schema["enum"].as_array().and_then(|v| v.first()) {
+        return first.clone();
+    }
+    for keyword in ["oneOf", "anyOf"] {
+        if let Some(first) = schema[keyword].as_array().and_then(|v| v.first()) {
+            return example_named(first, components, all_fields, tag, name);
+        }
+    }
+
+    let kind = types(schema).into_iter().find(|t| *t != "null").unwrap_or("object");
+    match kind {
+        "string" if name.contains("email") => json!(format!("{tag}@contract.test")),
+        "string" if schema["format"] == "date-time" => json!("2024-01-01T00:00:00Z"),
+        "string" => json!(format!("Contract {tag}")),
+        "integer" => json!(30),
+        "number" => json!(1.5),
+        "boolean" => json!(true),
+        "array" => json!([]),
+        _ => {
+            let required: Vec<&str> = schema["required"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+                .collect();
+            let mut out = Map::new();
+            for (prop, prop_schema) in schema["properties"].as_object().into_iter().flatten() {
+                if all_fields || required.contains(&prop.as_str()) {
+                    out.insert(prop.clone(), example_named(prop_schema, components, all_fields, tag, prop));
+                }
+            }
+            Value::Object(out)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn components() -> Value {
+        json!({ "schemas": { "User": {
+            "type": "object",
+            "required": ["id", "email"],
+            "properties": {
+                "id": { "type": "integer" },
+                "email": { "type": "string" },
+                "age": { "type": ["integer", "null"] }
+            }
+        }}})
+    }
+
+    fn errors(schema: Value, value: Value) -> Vec<String> {
+        let mut e = Vec::new();
+        validate(&schema, &components(), &value, "$", &mut e);
+        e
+    }
+
+    #[test]
+    fn accepts_conforming_object_with_null() {
+        let user = json!({ "$ref": "#/components/schemas/User" });
+        assert!(errors(user, json!({ "id": 1, "email": "a@b", "age": null })).is_empty());
+    }
+
+    #[test]
+    fn reports_missing_undeclared_and_mistyped_fields() {
+        let user = json!({ "$ref": "#/components/schemas/User" });
+        let e = errors(user, json!({ "id": "1", "updated_at": "x" }));
+        assert_eq!(e.len(), 3, "{e:?}");
+        assert!(e.iter().any(|m| m.starts_with("$.email: required")));
+        assert!(e.iter().any(|m| m.starts_with("$.updated_at: property is not declared")));
+        assert!(e.iter().any(|m| m.starts_with("$.id: expected integer")));
+    }
+
+    #[test]
+    fn openapi_30_nullable_is_honoured() {
+        assert!(errors(json!({ "type": "integer", "nullable": true }), Value::Null).is_empty());
+        assert_eq!(errors(json!({ "type": "integer" }), Value::Null).len(), 1);
+    }
+
+    #[test]
+    fn one_of_requires_exactly_one_match() {
+        let schema = json!({ "oneOf": [
+            { "type": "array", "items": { "$ref": "#/components/schemas/User" } },
+            { "type": "object", "required": ["data"], "properties": { "data": { "type": "array" } } }
+        ]});
+        assert!(errors(schema.clone(), json!([{ "id": 1, "email": "x" }])).is_empty());
+        assert!(errors(schema.clone(), json!({ "data": [] })).is_empty());
+        assert_eq!(errors(schema, json!("nope")).len(), 1);
+    }
+
+    #[test]
+    fn examples_fill_required_or_all_fields() {
+        let user = json!({ "$ref": "#/components/schemas/User" });
+        let minimal = example(&user, &components(), false, "t1");
+        assert_eq!(minimal, json!({ "id": 30, "email": "t1@contract.test" }));
+        let full = example(&user, &components(), true, "t2");
+        assert_eq!(full["age"], json!(30));
+        assert!(errors(user, full).is_empty());
+    }
+}
@@ -0,0 +1,33 @@
+//! Runs the generated contract checks against a live API:
+//!
+//! ```text
+//! CONTRACT_BASE_URL=http://localhost:3004 cargo test --manifest-path contract-tests/Cargo.toml
+//! ```
+//!
+//! The spec is read from `<base>/openapi.json` unless `CONTRACT_SPEC_URL`
+//! points elsewhere, which lets an implementation that does not publish a spec
+//! be checked against the Actix one.  Without `CONTRACT_BASE_URL` the test is
+//! skipped, since there is nothing to run against.
+
+use contract_tests::{run, Spec};
+
+#[test]
+fn api_conforms_to_its_openapi_spec() {
+    let Ok(base) = std::env::var("CONTRACT_BASE_URL") else {
+        eprintln!("skipped: set CONTRACT_BASE_URL to run the contract tests");
+        return;
+    };
+    let spec_url = std::env::var("CONTRACT_SPEC_URL")
+        .unwrap_or_else(|_| format!("{}/openapi.json", base.trim_end_matches('/')));
+    let spec = Spec::fetch(&spec_url).unwrap_or_else(|e| panic!("cannot load the spec: {e}"));
+
+    let report = run(&base, &spec);
+    eprintln!("{} contract cases run against {base}", report.cases);
+    assert!(report.cases > 0, "the spec at {spec_url} has no operations");
+    assert!(
+        report.failures.is_empty(),
+        "{} contract violation(s):\n  {}",
+        report.failures.len(),
+        report.failures.join("\n  ")
+    );
+}
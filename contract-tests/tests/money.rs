@@ -0,0 +1,71 @@
+//! POST /transfer against a live API: balances are `NUMERIC(12,2)` and must
+//! cross the wire as exact decimal strings, which drivers and ORMs disagree
+//! on (floats, numbers with the scale dropped, strings without it).
+//!
+//! ```text
+//! CONTRACT_BASE_URL=http://localhost:3004 cargo test --manifest-path contract-tests/Cargo.toml --test money
+//! ```
+//!
+//! Skipped without `CONTRACT_BASE_URL`, and for an implementation that does
+//! not serve the route (it is not part of the OpenAPI document).
+
+use client::{Client, CreateUser, Transfer};
+use serde_json::{json, Value};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Status of a POST /transfer with `body` sent as is, bypassing the client's
+/// types.
+fn post_raw(base: &str, body: Value) -> u16 {
+    match ureq::post(&format!("{}/transfer", base.trim_end_matches('/'))).send_json(body) {
+        Ok(resp) => resp.status(),
+        Err(ureq::Error::Status(status, _)) => status,
+        Err(e) => panic!("POST /transfer: {e}"),
+    }
+}
+
+#[tokio::test]
+async fn transfers_keep_exact_decimal_strings() {
+    let Ok(base) = std::env::var("CONTRACT_BASE_URL") else {
+        eprintln!("skipped: set CONTRACT_BASE_URL to run the contract tests");
+        return;
+    };
+    let api = Client::new(&base);
+    let tag = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+    let mut ids = Vec::new();
+    for who in ["payer", "payee"] {
+        let new = CreateUser { name: "Money".into(), email: format!("money-{who}-{tag}@example.com"), age: None };
+        ids.push(api.create_user(&new).await.unwrap().id);
+    }
+    let (payer, payee) = (ids[0], ids[1]);
+    let transfer = |amount: &str| Transfer { from: payer, to: payee, amount: amount.into() };
+
+    let first = match api.transfer(&transfer("0.10")).await {
+        // Both users were just created: a 404 is the route, not them.
+        Err(e) if e.status() == Some(404) => {
+            eprintln!("skipped: {base} does not serve POST /transfer");
+            return cleanup(&api, &ids).await;
+        }
+        result => result.unwrap(),
+    };
+    assert_eq!(first.amount, "0.10");
+    // 0.1 + 0.1 + 0.1 is 0.30000000000000004 in binary floating point.
+    api.transfer(&transfer("0.1")).await.unwrap();
+    let last = api.transfer(&transfer("0.10")).await.unwrap();
+    assert_eq!((last.from.balance.as_str(), last.to.balance.as_str()), ("999.70", "1000.30"));
+    assert_eq!((last.from.id, last.to.id), (payer, payee));
+
+    for amount in [json!(0.1), json!("0.001"), json!("-1.00"), json!("1e2"), json!("")] {
+        let status = post_raw(&base, json!({ "from": payer, "to": payee, "amount": amount }));
+        assert_eq!(status, 400, "amount {amount}");
+    }
+    let overdraft = api.transfer(&transfer("1000.00")).await.unwrap_err();
+    assert_eq!(overdraft.status(), Some(409), "{overdraft}");
+
+    cleanup(&api, &ids).await;
+}
+
+async fn cleanup(api: &Client, ids: &[i64]) {
+    for &id in ids {
+        let _ = api.delete_user(id).await;
+    }
+}
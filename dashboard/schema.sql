@@ -0,0 +1,26 @@
+-- Historical results store: one row per run, one row per (run, target, metric).
+-- Written by scripts/store-results.py, read by the dashboard. Plain SQL that
+-- both SQLite and PostgreSQL accept.
+
+CREATE TABLE IF NOT EXISTS runs (
+    run_id     TEXT PRIMARY KEY,   -- results/<run_id>
+    suite      TEXT NOT NULL,      -- experiment, latency, k8s, allocators, chaos, soak
+    started_at TEXT NOT NULL,      -- ISO 8601, UTC ("2025-01-01T12:00:00Z")
+    git_sha    TEXT,
+    dirty      BOOLEAN,
+    hostname   TEXT,
+    cpu_model  TEXT,
+    kernel     TEXT,
+    command    TEXT,
+    replay_of  TEXT
+);
+
+CREATE TABLE IF NOT EXISTS metrics (
+    run_id TEXT NOT NULL REFERENCES runs (run_id) ON DELETE CASCADE,
+    target TEXT NOT NULL,              -- framework, allocator or framework/phase
+    metric TEXT NOT NULL,              -- summary CSV column: rps, p99_ms, error_rate...
+    value  DOUBLE PRECISION NOT NULL,  -- median over the run's rounds
+    PRIMARY KEY (run_id, target, metric)
+);
+
+CREATE INDEX IF NOT EXISTS metrics_metric ON metrics (metric, target);
@@ -0,0 +1,156 @@
+//! Server-rendered SVG line charts: runs on the x axis, in the order given,
+//! one line per target.
+
+use crate::store::Point;
+use std::fmt::Write;
+
+const WIDTH: f64 = 880.0;
+const HEIGHT: f64 = 320.0;
+const LEFT: f64 = 64.0;
+const RIGHT: f64 = 160.0;
+const TOP: f64 = 32.0;
+const BOTTOM: f64 = 48.0;
+/// At most this many x labels; the rest are skipped evenly.
+const MAX_LABELS: usize = 12;
+
+const COLORS: [&str; 8] = [
+    "#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd", "#8c564b", "#e377c2", "#7f7f7f",
+];
+
+/// Line chart of `points` (ordered by run) titled `title`.
+pub fn line_chart(title: &str, points: &[Point]) -> String {
+    // Runs in first-seen order, labelled by commit when known.
+    let mut runs: Vec<(&str, String)> = Vec::new();
+    for p in points {
+        if !runs.iter().any(|(id, _)| *id == p.run_id) {
+            let label = match &p.git_sha {
+                Some(sha) if !sha.is_empty() => sha.chars().take(7).collect(),
+                _ => p.started_at.chars().take(10).collect(),
+            };
+            runs.push((&p.run_id, label));
+        }
+    }
+    let mut targets: Vec<&str> = points.iter().map(|p| p.target.as_str()).collect();
+    targets.sort_unstable();
+    targets.dedup();
+
+    let max = points.iter().map(|p| p.value).fold(0.0, f64::max);
+    let y_max = if max > 0.0 { max * 1.1 } else { 1.0 };
+    let plot_w = WIDTH - LEFT - RIGHT;
+    let plot_h = HEIGHT - TOP - BOTTOM;
+    let x = |i: usize| match runs.len() {
+        0 | 1 => LEFT + plot_w / 2.0,
+        n => LEFT + plot_w * i as f64 / (n - 1) as f64,
+    };
+    let y = |v: f64| TOP + plot_h * (1.0 - v / y_max);
+
+    let mut svg = String::new();
+    let _ = write!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {WIDTH} {HEIGHT}" width="{WIDTH}" height="{HEIGHT}" font-family="sans-serif" font-size="11">"#
+    );
+    let _ = write!(
+        svg,
+        r#"<text x="{LEFT}" y="18" font-size="14" font-weight="bold">{}</text>"#,
+        escape(title)
+    );
+
+    // Horizontal grid with the y scale.
+    for i in 0..=4 {
+        let v = y_max * i as f64 / 4.0;
+        let _ = write!(
+            svg,
+            r##"<line x1="{LEFT}" x2="{:.1}" y1="{y:.1}" y2="{y:.1}" stroke="#ddd"/><text x="{:.1}" y="{:.1}" text-anchor="end">{}</text>"##,
+            LEFT + plot_w,
+            LEFT - 6.0,
+            y(v) + 4.0,
+            format_value(v),
+            y = y(v),
+        );
+    }
+
+    let step = runs.len().div_ceil(MAX_LABELS).max(1);
+    for (i, (_, label)) in runs.iter().enumerate().step_by(step) {
+        let _ = write!(
+            svg,
+            r#"<text x="{:.1}" y="{:.1}" text-anchor="middle">{}</text>"#,
+            x(i),
+            HEIGHT - BOTTOM + 16.0,
+            escape(label)
+        );
+    }
+
+    for (t, target) in targets.iter().enumerate() {
+        let color = COLORS[t % COLORS.len()];
+        let series: Vec<(usize, &Point)> = points
+            .iter()
+            .filter(|p| p.target == *target)
+            .map(|p| {
+                (
+                    runs.iter().position(|(id, _)| *id == p.run_id).unwrap_or(0),
+                    p,
+                )
+            })
+            .collect();
+        let coords: Vec<String> = series
+            .iter()
+            .map(|(i, p)| format!("{:.1},{:.1}", x(*i), y(p.value)))
+            .collect();
+        let _ = write!(
+            svg,
+            r#"<polyline fill="none" stroke="{color}" stroke-width="2" points="{}"/>"#,
+            coords.join(" ")
+        );
+        for (i, p) in &series {
+            let _ = write!(
+                svg,
+                r#"<circle cx="{:.1}" cy="{:.1}" r="3" fill="{color}"><title>{} · {} · {}: {}</title></circle>"#,
+                x(*i),
+                y(p.value),
+                escape(target),
+                escape(&p.run_id),
+                escape(&runs[*i].1),
+                format_value(p.value)
+            );
+        }
+        let ly = TOP + 16.0 * t as f64;
+        let lx = WIDTH - RIGHT + 16.0;
+        let _ = write!(
+            svg,
+            r#"<rect x="{lx}" y="{:.1}" width="10" height="10" fill="{color}"/><text x="{}" y="{:.1}">{}</text>"#,
+            ly,
+            lx + 16.0,
+            ly + 9.0,
+            escape(target)
+        );
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+/// Compact number for axes and tooltips: `1234`, `12.3`, `0.012`.
+pub fn format_value(v: f64) -> String {
+    if v.abs() >= 100.0 {
+        format!("{v:.0}")
+    } else if v.abs() >= 1.0 {
+        format!("{v:.1}")
+    } else {
+        format!("{v:.3}")
+    }
+}
+
+/// Escapes text for HTML and SVG content and attribute values.
+pub fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
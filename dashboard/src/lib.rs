@@ -0,0 +1,147 @@
+//! Trend dashboard over the historical results store.
+//!
+//! `scripts/store-results.py` records the summary of each run (one value per
+//! target and metric, see `schema.sql`) in a SQLite file or a PostgreSQL
+//! database.  This service charts those values across runs, oldest first and
+//! labelled by commit, so a regression in RPS or p99 shows up as a step in a
+//! line rather than as a difference between two JSON files.
+//!
+//! ```text
+//! GET /                            suites, and charts for the selected one
+//!                                  (?suite=latency&metric=p99_ms; by default
+//!                                  rps and p99_ms, when stored)
+//! GET /trends?suite=..&metric=..   the same points as JSON
+//! ```
+
+pub mod chart;
+pub mod store;
+
+use actix_web::{get, web, HttpResponse, Responder};
+use chart::escape;
+use serde::Deserialize;
+use store::{Store, Suite};
+
+/// Metrics charted when the page does not ask for one.
+const DEFAULT_METRICS: [&str; 2] = ["rps", "p99_ms"];
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(index).service(trends);
+}
+
+#[derive(Deserialize)]
+struct IndexQuery {
+    suite: Option<String>,
+    metric: Option<String>,
+}
+
+#[get("/")]
+async fn index(
+    store: web::Data<Store>,
+    query: web::Query<IndexQuery>,
+) -> actix_web::Result<impl Responder> {
+    let suites = store
+        .suites()
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    let selected = match &query.suite {
+        Some(name) => match suites.iter().find(|s| &s.name == name) {
+            Some(suite) => Some(suite),
+            None => return Ok(HttpResponse::NotFound().body(format!("unknown suite: {name}"))),
+        },
+        None => suites.first(),
+    };
+
+    let mut body = String::new();
+    body.push_str(concat!(
+        "<!doctype html><html><head><meta charset=\"utf-8\"><title>Benchmark trends</title>",
+        "<style>body{font-family:sans-serif;margin:24px;color:#222}nav a{margin-right:12px}",
+        "nav a.on{font-weight:bold}section{margin:16px 0}</style></head><body><h1>Benchmark trends</h1>"
+    ));
+    match selected {
+        None => body.push_str(
+            "<p>No runs stored yet. Record one with \
+             <code>python3 scripts/store-results.py results/&lt;dir&gt;</code>.</p>",
+        ),
+        Some(suite) => {
+            body.push_str(&nav(&suites, suite, query.metric.as_deref()));
+            let metrics: Vec<&str> = match &query.metric {
+                Some(metric) => vec![metric.as_str()],
+                None => {
+                    let defaults: Vec<&str> = DEFAULT_METRICS
+                        .into_iter()
+                        .filter(|m| suite.metrics.iter().any(|s| s == m))
+                        .collect();
+                    if defaults.is_empty() {
+                        suite.metrics.iter().take(1).map(String::as_str).collect()
+                    } else {
+                        defaults
+                    }
+                }
+            };
+            for metric in metrics {
+                let points = store
+                    .trend(&suite.name, metric)
+                    .await
+                    .map_err(actix_web::error::ErrorInternalServerError)?;
+                body.push_str("<section>");
+                body.push_str(&chart::line_chart(
+                    &format!("{} · {metric}", suite.name),
+                    &points,
+                ));
+                body.push_str("</section>");
+            }
+        }
+    }
+    body.push_str("</body></html>");
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(body))
+}
+
+/// Links to every suite and to each metric of the selected one.
+fn nav(suites: &[Suite], selected: &Suite, metric: Option<&str>) -> String {
+    let link = |href: String, text: &str, on: bool| {
+        format!(
+            r#"<a href="{}"{}>{}</a>"#,
+            escape(&href),
+            if on { r#" class="on""# } else { "" },
+            escape(text)
+        )
+    };
+    let mut out = String::from("<nav><p>");
+    for suite in suites {
+        out.push_str(&link(
+            format!("/?suite={}", suite.name),
+            &suite.name,
+            suite.name == selected.name,
+        ));
+    }
+    out.push_str("</p><p>");
+    for m in &selected.metrics {
+        out.push_str(&link(
+            format!("/?suite={}&metric={m}", selected.name),
+            m,
+            metric == Some(m.as_str()),
+        ));
+    }
+    out.push_str("</p></nav>");
+    out
+}
+
+#[derive(Deserialize)]
+struct TrendQuery {
+    suite: String,
+    metric: String,
+}
+
+#[get("/trends")]
+async fn trends(
+    store: web::Data<Store>,
+    query: web::Query<TrendQuery>,
+) -> actix_web::Result<impl Responder> {
+    let points = store
+        .trend(&query.suite, &query.metric)
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(points))
+}
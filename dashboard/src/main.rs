@@ -0,0 +1,30 @@
+use actix_web::{web, App, HttpServer};
+use dashboard::store::Store;
+
+#[actix_web::main]
+async fn main() -> std::io::Result<()> {
+    let url = std::env::var("RESULTS_DB").unwrap_or_else(|_| "results/history.sqlite".into());
+    let port: u16 = std::env::var("PORT")
+        .ok()
+        .and_then(|p| p.parse().ok())
+        .unwrap_or(3100);
+
+    let store = match Store::connect(&url).await {
+        Ok(store) => store,
+        Err(e) => {
+            eprintln!("Results store error: {e:#}");
+            std::process::exit(1);
+        }
+    };
+    println!("Dashboard on http://0.0.0.0:{port} (results store: {url})");
+
+    let store = web::Data::new(store);
+    HttpServer::new(move || {
+        App::new()
+            .app_data(store.clone())
+            .configure(dashboard::configure)
+    })
+    .bind(("0.0.0.0", port))?
+    .run()
+    .await
+}
@@ -0,0 +1,146 @@
+//! Reads from the results store: a SQLite file or a PostgreSQL database with
+//! the tables in `schema.sql`.
+
+use anyhow::Context;
+use rusqlite::{Connection, OpenFlags};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// One metric of one target in one run.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Point {
+    pub run_id: String,
+    pub started_at: String,
+    pub git_sha: Option<String>,
+    pub target: String,
+    pub value: f64,
+}
+
+/// A suite (the script that produced the runs) and the metrics stored for it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Suite {
+    pub name: String,
+    pub metrics: Vec<String>,
+}
+
+#[derive(Clone)]
+pub enum Store {
+    /// Opened read-only on every query; the file is small and rarely read.
+    Sqlite(PathBuf),
+    Postgres(Arc<tokio_postgres::Client>),
+}
+
+const SUITES_SQL: &str = "SELECT DISTINCT r.suite, m.metric \
+     FROM metrics m JOIN runs r ON r.run_id = m.run_id \
+     ORDER BY r.suite, m.metric";
+
+const TREND_SQLITE: &str = "SELECT r.run_id, r.started_at, r.git_sha, m.target, m.value \
+     FROM metrics m JOIN runs r ON r.run_id = m.run_id \
+     WHERE r.suite = ?1 AND m.metric = ?2 \
+     ORDER BY r.started_at, r.run_id, m.target";
+
+const TREND_POSTGRES: &str = "SELECT r.run_id, r.started_at, r.git_sha, m.target, m.value \
+     FROM metrics m JOIN runs r ON r.run_id = m.run_id \
+     WHERE r.suite = $1 AND m.metric = $2 \
+     ORDER BY r.started_at, r.run_id, m.target";
+
+impl Store {
+    /// `postgres://` and `postgresql://` URLs connect to PostgreSQL; anything
+    /// else is the path of a SQLite file, with an optional `sqlite:` prefix.
+    pub async fn connect(url: &str) -> anyhow::Result<Self> {
+        if url.starts_with("postgres://") || url.starts_with("postgresql://") {
+            let (client, connection) = tokio_postgres::connect(url, tokio_postgres::NoTls)
+                .await
+                .context("connecting to the results store")?;
+            tokio::spawn(async move {
+                if let Err(e) = connection.await {
+                    eprintln!("results store connection: {e}");
+                }
+            });
+            Ok(Store::Postgres(Arc::new(client)))
+        } else {
+            let path = PathBuf::from(url.strip_prefix("sqlite:").unwrap_or(url));
+            // Fail at startup rather than on the first request.
+            open_sqlite(&path)?;
+            Ok(Store::Sqlite(path))
+        }
+    }
+
+    /// Suites with at least one stored metric, each with its metric names.
+    pub async fn suites(&self) -> anyhow::Result<Vec<Suite>> {
+        let pairs: Vec<(String, String)> = match self {
+            Store::Sqlite(path) => {
+                let path = path.clone();
+                tokio::task::spawn_blocking(move || -> anyhow::Result<_> {
+                    let conn = open_sqlite(&path)?;
+                    let mut stmt = conn.prepare(SUITES_SQL)?;
+                    let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+                    Ok(rows.collect::<Result<Vec<_>, _>>()?)
+                })
+                .await??
+            }
+            Store::Postgres(client) => client
+                .query(SUITES_SQL, &[])
+                .await?
+                .iter()
+                .map(|row| (row.get(0), row.get(1)))
+                .collect(),
+        };
+        let mut suites: Vec<Suite> = Vec::new();
+        for (suite, metric) in pairs {
+            match suites.last_mut() {
+                Some(last) if last.name == suite => last.metrics.push(metric),
+                _ => suites.push(Suite {
+                    name: suite,
+                    metrics: vec![metric],
+                }),
+            }
+        }
+        Ok(suites)
+    }
+
+    /// Values of `metric` for every target of `suite`, oldest run first.
+    pub async fn trend(&self, suite: &str, metric: &str) -> anyhow::Result<Vec<Point>> {
+        match self {
+            Store::Sqlite(path) => {
+                let (path, suite, metric) = (path.clone(), suite.to_owned(), metric.to_owned());
+                tokio::task::spawn_blocking(move || -> anyhow::Result<_> {
+                    let conn = open_sqlite(&path)?;
+                    let mut stmt = conn.prepare(TREND_SQLITE)?;
+                    let rows = stmt.query_map([&suite, &metric], |row| {
+                        Ok(Point {
+                            run_id: row.get(0)?,
+                            started_at: row.get(1)?,
+                            git_sha: row.get(2)?,
+                            target: row.get(3)?,
+                            value: row.get(4)?,
+                        })
+                    })?;
+                    Ok(rows.collect::<Result<Vec<_>, _>>()?)
+                })
+                .await?
+            }
+            Store::Postgres(client) => Ok(client
+                .query(TREND_POSTGRES, &[&suite, &metric])
+                .await?
+                .iter()
+                .map(|row| Point {
+                    run_id: row.get(0),
+                    started_at: row.get(1),
+                    git_sha: row.get(2),
+                    target: row.get(3),
+                    value: row.get(4),
+                })
+                .collect()),
+        }
+    }
+}
+
+fn open_sqlite(path: &Path) -> anyhow::Result<Connection> {
+    Connection::open_with_flags(
+        path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )
+    .with_context(|| format!("opening {}", path.display()))
+}
@@ -0,0 +1,164 @@
+//! The dashboard against a SQLite store created from `schema.sql`.
+
+use actix_web::{test, web, App};
+use dashboard::store::{Point, Store, Suite};
+use std::path::PathBuf;
+
+/// Store with two latency runs (two commits, two frameworks) and one
+/// allocator run without a commit.
+fn sample_store(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("dashboard-{name}-{}.sqlite", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let conn = rusqlite::Connection::open(&path).unwrap();
+    conn.execute_batch(include_str!("../schema.sql")).unwrap();
+    conn.execute_batch(
+        "INSERT INTO runs (run_id, suite, started_at, git_sha) VALUES
+             ('latency_2', 'latency', '2025-01-02T10:00:00Z', 'bbbbbbbbbbbb'),
+             ('latency_1', 'latency', '2025-01-01T10:00:00Z', 'aaaaaaaaaaaa'),
+             ('allocators_1', 'allocators', '2025-01-03T10:00:00Z', NULL);
+         INSERT INTO metrics (run_id, target, metric, value) VALUES
+             ('latency_1', 'actix', 'rps', 3500), ('latency_1', 'gin', 'rps', 2800),
+             ('latency_2', 'actix', 'rps', 3400), ('latency_2', 'gin', 'rps', 2900),
+             ('latency_1', 'actix', 'p99_ms', 4.5), ('latency_2', 'actix', 'p99_ms', 3.9),
+             ('allocators_1', 'mimalloc', 'mem_peak_mb', 42);",
+    )
+    .unwrap();
+    path
+}
+
+#[actix_web::test]
+async fn suites_list_their_metrics() {
+    let path = sample_store("suites");
+    let store = Store::connect(path.to_str().unwrap()).await.unwrap();
+    assert_eq!(
+        store.suites().await.unwrap(),
+        vec![
+            Suite {
+                name: "allocators".into(),
+                metrics: vec!["mem_peak_mb".into()]
+            },
+            Suite {
+                name: "latency".into(),
+                metrics: vec!["p99_ms".into(), "rps".into()]
+            },
+        ]
+    );
+    std::fs::remove_file(path).unwrap();
+}
+
+#[actix_web::test]
+async fn trends_are_ordered_by_run_start() {
+    let path = sample_store("trends");
+    let store = Store::connect(&format!("sqlite:{}", path.display()))
+        .await
+        .unwrap();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(store))
+            .configure(dashboard::configure),
+    )
+    .await;
+
+    let req = test::TestRequest::get()
+        .uri("/trends?suite=latency&metric=rps")
+        .to_request();
+    let points: Vec<serde_json::Value> = test::call_and_read_body_json(&app, req).await;
+    let summary: Vec<(&str, &str, f64)> = points
+        .iter()
+        .map(|p| {
+            (
+                p["run_id"].as_str().unwrap(),
+                p["target"].as_str().unwrap(),
+                p["value"].as_f64().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("latency_1", "actix", 3500.0),
+            ("latency_1", "gin", 2800.0),
+            ("latency_2", "actix", 3400.0),
+            ("latency_2", "gin", 2900.0),
+        ]
+    );
+
+    let req = test::TestRequest::get()
+        .uri("/trends?suite=latency")
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 400);
+    std::fs::remove_file(path).unwrap();
+}
+
+#[actix_web::test]
+async fn index_charts_default_metrics_of_the_selected_suite() {
+    let path = sample_store("index");
+    let store = Store::connect(path.to_str().unwrap()).await.unwrap();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(store))
+            .configure(dashboard::configure),
+    )
+    .await;
+
+    let req = test::TestRequest::get().uri("/?suite=latency").to_request();
+    let body = String::from_utf8(test::call_and_read_body(&app, req).await.to_vec()).unwrap();
+    assert!(body.contains("latency · rps"), "{body}");
+    assert!(body.contains("latency · p99_ms"), "{body}");
+    assert_eq!(
+        body.matches("<polyline").count(),
+        3,
+        "two targets for rps, one for p99_ms"
+    );
+    assert!(
+        body.contains(">aaaaaaa<") && body.contains(">bbbbbbb<"),
+        "x axis labelled by commit"
+    );
+
+    // Without rps or p99_ms, the first metric of the suite; without a commit,
+    // the run date labels the x axis.
+    let req = test::TestRequest::get().uri("/").to_request();
+    let body = String::from_utf8(test::call_and_read_body(&app, req).await.to_vec()).unwrap();
+    assert!(body.contains("allocators · mem_peak_mb"), "{body}");
+    assert!(body.contains(">2025-01-03<"), "{body}");
+
+    let req = test::TestRequest::get().uri("/?suite=missing").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 404);
+    std::fs::remove_file(path).unwrap();
+}
+
+#[actix_web::test]
+async fn empty_store_explains_how_to_fill_it() {
+    let path = std::env::temp_dir().join(format!("dashboard-empty-{}.sqlite", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    rusqlite::Connection::open(&path)
+        .unwrap()
+        .execute_batch(include_str!("../schema.sql"))
+        .unwrap();
+    let store = Store::connect(path.to_str().unwrap()).await.unwrap();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(store))
+            .configure(dashboard::configure),
+    )
+    .await;
+
+    let req = test::TestRequest::get().uri("/").to_request();
+    let body = String::from_utf8(test::call_and_read_body(&app, req).await.to_vec()).unwrap();
+    assert!(body.contains("store-results.py"), "{body}");
+    std::fs::remove_file(path).unwrap();
+}
+
+#[actix_web::test]
+async fn chart_escapes_target_names() {
+    let points = vec![Point {
+        run_id: "r1".into(),
+        started_at: "2025-01-01T00:00:00Z".into(),
+        git_sha: None,
+        target: "<gin>".into(),
+        value: 1.0,
+    }];
+    let svg = dashboard::chart::line_chart("a & b", &points);
+    assert!(svg.contains("&lt;gin&gt;") && svg.contains("a &amp; b"));
+    assert!(!svg.contains("<gin>"));
+}
@@ -8,9 +8,22 @@ Lê o summary.csv gerado por run-experiment.sh e produz:
   - stats_tests.txt   : resultados dos testes Mann-Whitney U
   - charts/*.png      : gráficos (se matplotlib disponível)
 
+Se o diretório contiver build_metrics.csv (measure-build.sh), a tabela final
+inclui tempo de build, tamanho da imagem e do binário de cada framework.
+
+Se houver <framework>/saturation_<framework>_analysis.csv (fase de saturação,
+find-saturation.py), os degraus de todos os frameworks viram pontos (RPS real,
+p99) num mesmo gráfico e os não dominados — nenhum outro ponto tem mais RPS e
+menos p99 — formam a fronteira de Pareto:
+  - pareto_frontier.csv          : todos os degraus, com a coluna pareto
+  - charts/pareto_frontier.png   : RPS × p99 por framework, com a fronteira
+Degraus com erro acima de --pareto-max-err % ficam de fora da fronteira.
+
 Métricas calculadas:
   RPS/Watt    = RPS_mediana / (Power_API_W - Power_baseline_W)
                   onde Power_API_W = energia_uj / elapsed_ms / 1000
+  J/1k req    = 1000 × Power_API_W / RPS_mediana   (energia total do pacote,
+                  sem descontar o baseline; vazio sem RAPL)
   RPS/USD     = RPS_extrapolada / custo_horario_USD
                   onde RPS_extrapolada = RPS_mediana * (100 / CPU_pct_mediana)
                   e custo_horario_USD = AWS t3.medium on-demand = $0.0416/h
@@ -80,6 +93,8 @@ def parse_args():
     p.add_argument('--results-dir',    required=True,  help="Diretório com summary.csv e baseline.json")
     p.add_argument('--baseline-power', type=float, default=None, help="Potência baseline em Watts")
     p.add_argument('--output-dir',     required=True,  help="Diretório para salvar resultados")
+    p.add_argument('--pareto-max-err', type=float, default=1.0,
+                   help="Erro %% máximo de um degrau na fronteira de Pareto (default: 1.0)")
     return p.parse_args()
 
 # ---------------------------------------------------------------------------
@@ -124,6 +139,41 @@ def load_baseline(results_dir: Path, cli_baseline: Optional[float]) -> float:
         return float(b.get('power_watts', 0))
     return 0.0
 
+def load_build_metrics(results_dir: Path) -> dict:
+    """Lê build_metrics.csv (opcional) e retorna framework → métricas de build."""
+    csv_path = results_dir / 'build_metrics.csv'
+    if not csv_path.exists():
+        return {}
+
+    build = {}
+    with open(csv_path, newline='') as f:
+        for row in csv.DictReader(f):
+            build[row['framework']] = {
+                'build_s':        float(row['build_s']),
+                'image_mb':       float(row['image_mb']),
+                'artifact_bytes': int(row['artifact_bytes']),
+                'stripped_bytes': int(row['stripped_bytes']) if row['stripped_bytes'] else None,
+            }
+    return build
+
+def load_sweep(results_dir: Path) -> list:
+    """Degraus da fase de saturação de todos os frameworks (saturation_<fw>_analysis.csv)."""
+    points = []
+    for fw in FRAMEWORKS:
+        csv_path = results_dir / fw / f'saturation_{fw}_analysis.csv'
+        if not csv_path.exists():
+            continue
+        with open(csv_path, newline='') as f:
+            for row in csv.DictReader(f):
+                points.append({
+                    'framework':  fw,
+                    'target_rps': int(row['target_rps']),
+                    'rps_real':   float(row['rps_real']),
+                    'p99_ms':     float(row['p99_ms']),
+                    'err_pct':    float(row['err_pct']),
+                })
+    return points
+
 # ---------------------------------------------------------------------------
 # Cálculo de métricas
 # ---------------------------------------------------------------------------
@@ -164,6 +214,7 @@ def compute_metrics(data: dict, baseline_power_w: float) -> dict:
             rapl_available = True
 
         rps_per_watt = rps_med / net_power_w
+        joules_per_1k = 1000.0 * power_med / rps_med if rapl_available and rps_med > 0 else None
 
         # RPS/USD: extrapola throughput máximo via CPU%
         if cpu_med > 0:
@@ -186,6 +237,7 @@ def compute_metrics(data: dict, baseline_power_w: float) -> dict:
             'error_rate_pct': err_med,
             'rps_extrap':     rps_extrap,
             'rps_per_watt':   rps_per_watt,
+            'joules_per_1k':  joules_per_1k,
             'rps_per_usd':    rps_per_usd,
             'rapl_available': rapl_available,
             'n_runs':         len(runs),
@@ -204,6 +256,18 @@ def rank(metrics: dict, key: str, reverse: bool = True) -> list:
     """Retorna lista de frameworks ordenada pelo key."""
     return sorted(metrics.keys(), key=lambda fw: metrics[fw][key], reverse=reverse)
 
+def pareto_frontier(points: list, max_err_pct: float) -> list:
+    """Pontos não dominados em (RPS real ↑, p99 ↓), em ordem crescente de RPS."""
+    candidates = sorted((p for p in points if p['err_pct'] <= max_err_pct),
+                        key=lambda p: (-p['rps_real'], p['p99_ms']))
+    frontier = []
+    best_p99 = float('inf')
+    for p in candidates:
+        if p['p99_ms'] < best_p99:
+            frontier.append(p)
+            best_p99 = p['p99_ms']
+    return frontier[::-1]
+
 # ---------------------------------------------------------------------------
 # Tabela final
 # ---------------------------------------------------------------------------
@@ -212,9 +276,9 @@ def format_table(metrics: dict, baseline_power: float) -> str:
     fws = FRAMEWORKS
     lines = []
 
-    lines.append("=" * 120)
+    lines.append("=" * 130)
     lines.append("RESULTADOS DO EXPERIMENTO — Eficiência Energética e Financeira de Frameworks Web")
-    lines.append("=" * 120)
+    lines.append("=" * 130)
     lines.append(f"Baseline (idle+postgres): {baseline_power:.3f} W")
     lines.append(f"Custo AWS t3.medium: US$ {AWS_T3_MEDIUM_USD_PER_HOUR:.4f}/h")
     lines.append("")
@@ -232,17 +296,19 @@ def format_table(metrics: dict, baseline_power: float) -> str:
         f"{'CPU%':>6} "
         f"{'Mem(MB)':>8} "
         f"{'RPS/W':>10} "
+        f"{'J/1k req':>9} "
         f"{'RPS/USD':>12} "
         f"{'Err%':>6}"
     )
     lines.append(hdr)
-    lines.append("-" * 120)
+    lines.append("-" * 130)
 
     for fw in fws:
         if fw not in metrics:
             lines.append(f"{'  '+fw:<14}  (sem dados)")
             continue
         m = metrics[fw]
+        j_1k = f"{m['joules_per_1k']:>9.2f}" if m['joules_per_1k'] is not None else f"{'-':>9}"
         lines.append(
             f"{fw:<14} "
             f"{m['rps_median']:>8.1f} "
@@ -255,11 +321,12 @@ def format_table(metrics: dict, baseline_power: float) -> str:
             f"{m['cpu_pct']:>6.1f} "
             f"{m['mem_mb']:>8.1f} "
             f"{m['rps_per_watt']:>10.1f} "
+            f"{j_1k} "
             f"{m['rps_per_usd']:>12.0f} "
             f"{m['error_rate_pct']:>6.4f}"
         )
 
-    lines.append("=" * 120)
+    lines.append("=" * 130)
     lines.append("")
 
     # Rankings
@@ -291,19 +358,60 @@ def format_table(metrics: dict, baseline_power: float) -> str:
 
     return "\n".join(lines)
 
+def format_build_table(build: dict) -> str:
+    lines = []
+    lines.append("BUILD E TAMANHO DE ARTEFATO:")
+    lines.append(f"  {'Framework':<12} {'Build(s)':>9} {'Imagem(MB)':>11} {'Artefato(MB)':>13} {'Stripped(MB)':>13}")
+    for fw in FRAMEWORKS:
+        if fw not in build:
+            continue
+        b = build[fw]
+        stripped = f"{b['stripped_bytes'] / 1048576:>13.2f}" if b['stripped_bytes'] is not None else f"{'-':>13}"
+        lines.append(
+            f"  {fw:<12} "
+            f"{b['build_s']:>9.1f} "
+            f"{b['image_mb']:>11.1f} "
+            f"{b['artifact_bytes'] / 1048576:>13.2f} "
+            f"{stripped}"
+        )
+    lines.append("")
+    return "\n".join(lines)
+
+def format_pareto_table(frontier: list, max_err_pct: float) -> str:
+    lines = []
+    lines.append(f"FRONTEIRA DE PARETO (RPS real × P99, degraus com erro ≤ {max_err_pct:g}%):")
+    lines.append(f"  {'Framework':<12} {'Alvo(RPS)':>10} {'Real(RPS)':>10} {'P99(ms)':>9} {'Erro%':>7}")
+    for p in frontier:
+        lines.append(
+            f"  {p['framework']:<12} "
+            f"{p['target_rps']:>10} "
+            f"{p['rps_real']:>10.0f} "
+            f"{p['p99_ms']:>9.2f} "
+            f"{p['err_pct']:>7.3f}"
+        )
+    counts = defaultdict(int)
+    for p in frontier:
+        counts[p['framework']] += 1
+    lines.append(f"  Pontos na fronteira por framework: "
+                 f"{', '.join(f'{fw}={counts[fw]}' for fw in FRAMEWORKS if counts[fw]) or '-'}")
+    lines.append("")
+    return "\n".join(lines)
+
 # ---------------------------------------------------------------------------
 # CSV final
 # ---------------------------------------------------------------------------
 
-def write_final_csv(metrics: dict, output_dir: Path):
+def write_final_csv(metrics: dict, build: dict, output_dir: Path):
     out = output_dir / 'final_table.csv'
     fieldnames = [
         'framework', 'n_runs', 'rps_median', 'rps_std',
         'p50_ms', 'p95_ms', 'p99_ms',
         'power_watts', 'net_power_w', 'cpu_pct', 'mem_mb',
-        'rps_extrap', 'rps_per_watt', 'rps_per_usd',
+        'rps_extrap', 'rps_per_watt', 'joules_per_1k_req', 'rps_per_usd',
         'error_rate_pct', 'rank_rps', 'rank_rps_per_watt', 'rank_rps_per_usd',
     ]
+    if build:
+        fieldnames += ['build_s', 'image_mb', 'artifact_bytes', 'stripped_bytes']
 
     rank_rps    = rank(metrics, 'rps_median')
     rank_rpsw   = rank(metrics, 'rps_per_watt')
@@ -316,7 +424,7 @@ def write_final_csv(metrics: dict, output_dir: Path):
             if fw not in metrics:
                 continue
             m = metrics[fw]
-            writer.writerow({
+            row = {
                 'framework':        fw,
                 'n_runs':           m['n_runs'],
                 'rps_median':       round(m['rps_median'],    2),
@@ -330,15 +438,42 @@ def write_final_csv(metrics: dict, output_dir: Path):
                 'mem_mb':           round(m['mem_mb'],        1),
                 'rps_extrap':       round(m['rps_extrap'],    1),
                 'rps_per_watt':     round(m['rps_per_watt'],  2),
+                'joules_per_1k_req':round(m['joules_per_1k'], 3) if m['joules_per_1k'] is not None else '',
                 'rps_per_usd':      round(m['rps_per_usd'],   0),
                 'error_rate_pct':   round(m['error_rate_pct'],4),
                 'rank_rps':         rank_rps.index(fw)    + 1,
                 'rank_rps_per_watt':rank_rpsw.index(fw)  + 1,
                 'rank_rps_per_usd': rank_rpsusd.index(fw) + 1,
-            })
+            }
+            if build:
+                b = build.get(fw, {})
+                row.update({
+                    'build_s':        b.get('build_s', ''),
+                    'image_mb':       b.get('image_mb', ''),
+                    'artifact_bytes': b.get('artifact_bytes', ''),
+                    'stripped_bytes': b.get('stripped_bytes') or '',
+                })
+            writer.writerow(row)
 
     print(f"[ok] Tabela CSV salva em {out}")
 
+def write_pareto_csv(points: list, frontier: list, output_dir: Path):
+    out = output_dir / 'pareto_frontier.csv'
+    on_frontier = {(p['framework'], p['target_rps']) for p in frontier}
+    with open(out, 'w', newline='') as f:
+        writer = csv.DictWriter(f, fieldnames=['framework', 'target_rps', 'rps_real', 'p99_ms', 'err_pct', 'pareto'])
+        writer.writeheader()
+        for p in points:
+            writer.writerow({
+                'framework':  p['framework'],
+                'target_rps': p['target_rps'],
+                'rps_real':   round(p['rps_real'], 1),
+                'p99_ms':     round(p['p99_ms'],   2),
+                'err_pct':    round(p['err_pct'],  4),
+                'pareto':     int((p['framework'], p['target_rps']) in on_frontier),
+            })
+    print(f"[ok] Fronteira de Pareto salva em {out}")
+
 # ---------------------------------------------------------------------------
 # Testes estatísticos
 # ---------------------------------------------------------------------------
@@ -378,6 +513,14 @@ def write_stats_tests(metrics: dict, output_dir: Path):
 # Gráficos
 # ---------------------------------------------------------------------------
 
+FRAMEWORK_COLORS = {
+    'express': '#68A063',  # verde Node.js
+    'fastify': '#000000',  # preto Fastify
+    'elysia':  '#C490D1',  # lilás Bun
+    'actix':   '#CE422B',  # vermelho Rust
+    'gin':     '#00ACD7',  # azul Go
+}
+
 def generate_charts(metrics: dict, output_dir: Path):
     if not HAS_MATPLOTLIB:
         return
@@ -388,14 +531,7 @@ def generate_charts(metrics: dict, output_dir: Path):
     fws    = [fw for fw in FRAMEWORKS if fw in metrics]
     labels = [fw.capitalize() for fw in fws]
 
-    colors = {
-        'express': '#68A063',  # verde Node.js
-        'fastify': '#000000',  # preto Fastify
-        'elysia':  '#C490D1',  # lilás Bun
-        'actix':   '#CE422B',  # vermelho Rust
-        'gin':     '#00ACD7',  # azul Go
-    }
-    bar_colors = [colors.get(fw, '#888888') for fw in fws]
+    bar_colors = [FRAMEWORK_COLORS.get(fw, '#888888') for fw in fws]
 
     def make_bar(ax, values, title, ylabel, color_list=None):
         x = np.arange(len(fws))
@@ -473,6 +609,41 @@ def generate_charts(metrics: dict, output_dir: Path):
     plt.close(fig)
     print(f"[ok] Gráfico salvo: {charts_dir / 'ranking_comparison.png'}")
 
+def plot_pareto(points: list, frontier: list, output_dir: Path):
+    if not HAS_MATPLOTLIB:
+        return
+
+    charts_dir = output_dir / 'charts'
+    charts_dir.mkdir(exist_ok=True)
+
+    fig, ax = plt.subplots(figsize=(10, 6))
+    for fw in FRAMEWORKS:
+        fw_points = sorted((p for p in points if p['framework'] == fw), key=lambda p: p['target_rps'])
+        if not fw_points:
+            continue
+        ax.plot([p['rps_real'] for p in fw_points], [p['p99_ms'] for p in fw_points],
+                marker='o', markersize=4, linewidth=1, alpha=0.8,
+                color=FRAMEWORK_COLORS.get(fw, '#888888'), label=fw.capitalize())
+
+    if frontier:
+        ax.step([p['rps_real'] for p in frontier], [p['p99_ms'] for p in frontier],
+                where='pre', color='#FF9800', linewidth=2, linestyle='--', label='Fronteira de Pareto')
+        ax.scatter([p['rps_real'] for p in frontier], [p['p99_ms'] for p in frontier],
+                   s=70, facecolors='none', edgecolors='#FF9800', linewidths=1.5, zorder=3)
+
+    ax.set_title('Throughput × Latência (degraus da saturação)', fontsize=11, fontweight='bold')
+    ax.set_xlabel('RPS real', fontsize=9)
+    ax.set_ylabel('P99 (ms)', fontsize=9)
+    ax.set_yscale('log')
+    ax.legend(fontsize=9)
+    ax.spines['top'].set_visible(False)
+    ax.spines['right'].set_visible(False)
+    ax.grid(alpha=0.3)
+    plt.tight_layout()
+    fig.savefig(charts_dir / 'pareto_frontier.png', dpi=150, bbox_inches='tight')
+    plt.close(fig)
+    print(f"[ok] Gráfico salvo: {charts_dir / 'pareto_frontier.png'}")
+
 # ---------------------------------------------------------------------------
 # Ponto de entrada
 # ---------------------------------------------------------------------------
@@ -495,8 +666,16 @@ def main():
     print("[info] Calculando métricas...")
     metrics = compute_metrics(data, baseline_power)
 
+    build = load_build_metrics(results_dir)
+    sweep = load_sweep(results_dir)
+    frontier = pareto_frontier(sweep, args.pareto_max_err)
+
     # Tabela textual
     table = format_table(metrics, baseline_power)
+    if build:
+        table += "\n" + format_build_table(build)
+    if sweep:
+        table += "\n" + format_pareto_table(frontier, args.pareto_max_err)
     print("\n" + table)
 
     table_path = output_dir / 'final_table.txt'
@@ -504,9 +683,12 @@ def main():
         f.write(table)
     print(f"[ok] Tabela final salva em {table_path}")
 
-    write_final_csv(metrics, output_dir)
+    write_final_csv(metrics, build, output_dir)
     write_stats_tests(metrics, output_dir)
     generate_charts(metrics, output_dir)
+    if sweep:
+        write_pareto_csv(sweep, frontier, output_dir)
+        plot_pareto(sweep, frontier, output_dir)
 
 if __name__ == '__main__':
     main()
@@ -0,0 +1,267 @@
+#!/usr/bin/env python3
+"""
+analyze-soak.py — snapshots periódicos e detecção de tendências em testes de soak.
+
+Lê o CSV do k6 (--out csv) e as amostras de docker stats coletadas pelo
+run-soak.sh e, para cada janela de INTERVAL segundos, calcula:
+  - RPS real, p50/p99 e taxa de erro
+  - RSS médio (MB) e CPU% médio do container
+
+Em seguida ajusta uma reta (mínimos quadrados) sobre RSS e p99 ao longo do
+tempo, ignorando as janelas de aquecimento, e sinaliza:
+  - crescimento de RSS acima de --rss-threshold MB/h   (suspeita de vazamento)
+  - deriva de p99 acima de --p99-drift %/h              (degradação progressiva)
+
+Uso:
+  python3 scripts/analyze-soak.py \\
+    --k6-csv results/soak_<ts>/actix/k6.csv \\
+    --stats-csv results/soak_<ts>/actix/docker_stats.csv \\
+    --framework actix \\
+    --output-dir results/soak_<ts>/actix \\
+    [--interval 60] [--skip-windows 5] [--plot]
+
+Saída:
+  soak_<framework>_snapshots.csv  — uma linha por janela
+  soak_<framework>_report.json    — inclinações e flags
+  soak_<framework>_plot.png       — (se --plot)
+  Terminal: SOAK_FLAGS=<lista separada por vírgula ou vazio>
+"""
+
+import argparse
+import csv
+import json
+import sys
+from collections import defaultdict
+from pathlib import Path
+
+# ---------------------------------------------------------------------------
+# Argumentos
+# ---------------------------------------------------------------------------
+
+def parse_args():
+    p = argparse.ArgumentParser(description="Analisa um teste de soak")
+    p.add_argument('--k6-csv',        required=True,             help="CSV exportado pelo k6 (--out csv=...)")
+    p.add_argument('--stats-csv',     required=True,             help="CSV timestamp,cpu_pct,mem_mb do docker stats")
+    p.add_argument('--framework',     required=True,             help="Nome do framework")
+    p.add_argument('--output-dir',    default='.',               help="Diretório de saída (default: .)")
+    p.add_argument('--interval',      type=int,   default=60,    help="Tamanho da janela em segundos (default: 60)")
+    p.add_argument('--skip-windows',  type=int,   default=5,     help="Janelas iniciais ignoradas no ajuste (default: 5)")
+    p.add_argument('--rss-threshold', type=float, default=5.0,   help="Crescimento de RSS sinalizado, MB/h (default: 5)")
+    p.add_argument('--p99-drift',     type=float, default=20.0,  help="Deriva de p99 sinalizada, %%/h (default: 20)")
+    p.add_argument('--plot',          action='store_true',       help="Gera gráfico PNG")
+    return p.parse_args()
+
+# ---------------------------------------------------------------------------
+# Leitura
+# ---------------------------------------------------------------------------
+
+def load_k6_windows(path: str, interval: int) -> dict:
+    """Agrupa eventos do k6 em janelas de `interval` segundos (timestamp Unix)."""
+    windows = defaultdict(lambda: {'durations': [], 'failed': [], 'reqs': 0})
+
+    with open(path, newline='') as f:
+        for row in csv.DictReader(f):
+            metric = row.get('metric_name', '').strip()
+            if metric not in ('http_req_duration', 'http_req_failed', 'http_reqs'):
+                continue
+            try:
+                ts  = int(float(row['timestamp']))
+                val = float(row['metric_value'])
+            except (KeyError, ValueError, TypeError):
+                continue
+
+            w = windows[ts - ts % interval]
+            if metric == 'http_req_duration':
+                w['durations'].append(val)
+            elif metric == 'http_req_failed':
+                w['failed'].append(val)
+            else:
+                w['reqs'] += 1
+
+    return dict(windows)
+
+def load_stats_windows(path: str, interval: int) -> dict:
+    """Média de CPU% e memória (MB) por janela."""
+    windows = defaultdict(lambda: {'cpu': [], 'mem': []})
+
+    with open(path, newline='') as f:
+        for row in csv.DictReader(f):
+            try:
+                ts = int(row['timestamp'])
+                windows[ts - ts % interval]['cpu'].append(float(row['cpu_pct']))
+                windows[ts - ts % interval]['mem'].append(float(row['mem_mb']))
+            except (KeyError, ValueError, TypeError):
+                continue
+
+    return dict(windows)
+
+# ---------------------------------------------------------------------------
+# Snapshots e tendências
+# ---------------------------------------------------------------------------
+
+def percentile(sorted_vals: list, p: float) -> float:
+    if not sorted_vals:
+        return 0.0
+    idx = int(p / 100 * len(sorted_vals))
+    return sorted_vals[min(idx, len(sorted_vals) - 1)]
+
+def build_snapshots(k6: dict, stats: dict, interval: int) -> list:
+    snapshots = []
+    if not k6:
+        return snapshots
+    t0 = min(k6.keys())
+
+    for start in sorted(k6.keys()):
+        w = k6[start]
+        durations = sorted(w['durations'])
+        s = stats.get(start, {'cpu': [], 'mem': []})
+        snapshots.append({
+            'minute':   (start - t0) / 60.0,
+            'rps':      w['reqs'] / interval,
+            'p50_ms':   percentile(durations, 50),
+            'p99_ms':   percentile(durations, 99),
+            'err_pct':  (sum(w['failed']) / len(w['failed']) * 100) if w['failed'] else 0.0,
+            'cpu_pct':  (sum(s['cpu']) / len(s['cpu'])) if s['cpu'] else 0.0,
+            'mem_mb':   (sum(s['mem']) / len(s['mem'])) if s['mem'] else 0.0,
+        })
+
+    # A última janela costuma estar incompleta
+    return snapshots[:-1] if len(snapshots) > 1 else snapshots
+
+def linear_slope(xs: list, ys: list) -> float:
+    """Inclinação por mínimos quadrados (0 se não houver pontos suficientes)."""
+    n = len(xs)
+    if n < 2:
+        return 0.0
+    mx = sum(xs) / n
+    my = sum(ys) / n
+    den = sum((x - mx) ** 2 for x in xs)
+    if den == 0:
+        return 0.0
+    return sum((x - mx) * (y - my) for x, y in zip(xs, ys)) / den
+
+def detect_trends(snapshots: list, args) -> dict:
+    steady = snapshots[args.skip_windows:]
+    hours  = [s['minute'] / 60.0 for s in steady]
+
+    mem_pts = [(h, s['mem_mb']) for h, s in zip(hours, steady) if s['mem_mb'] > 0]
+    rss_slope = linear_slope([h for h, _ in mem_pts], [m for _, m in mem_pts])
+
+    p99_slope = linear_slope(hours, [s['p99_ms'] for s in steady])
+    p99_base  = steady[0]['p99_ms'] if steady else 0.0
+    p99_drift_pct = (p99_slope / p99_base * 100) if p99_base > 0 else 0.0
+
+    flags = []
+    if rss_slope > args.rss_threshold:
+        flags.append('rss_growth')
+    if p99_drift_pct > args.p99_drift:
+        flags.append('latency_drift')
+
+    return {
+        'framework':            args.framework,
+        'windows':              len(snapshots),
+        'windows_used':         len(steady),
+        'interval_s':           args.interval,
+        'rss_slope_mb_per_h':   round(rss_slope, 3),
+        'rss_start_mb':         round(steady[0]['mem_mb'], 1) if steady else 0.0,
+        'rss_end_mb':           round(steady[-1]['mem_mb'], 1) if steady else 0.0,
+        'p99_slope_ms_per_h':   round(p99_slope, 3),
+        'p99_drift_pct_per_h':  round(p99_drift_pct, 2),
+        'rss_threshold_mb_per_h': args.rss_threshold,
+        'p99_drift_threshold_pct_per_h': args.p99_drift,
+        'flags':                flags,
+    }
+
+# ---------------------------------------------------------------------------
+# Saída
+# ---------------------------------------------------------------------------
+
+def write_snapshots(snapshots: list, framework: str, output_dir: Path):
+    out = output_dir / f'soak_{framework}_snapshots.csv'
+    with open(out, 'w', newline='') as f:
+        writer = csv.DictWriter(f, fieldnames=['framework', 'minute', 'rps', 'p50_ms', 'p99_ms', 'err_pct', 'cpu_pct', 'mem_mb'])
+        writer.writeheader()
+        for s in snapshots:
+            writer.writerow({
+                'framework': framework,
+                'minute':    round(s['minute'], 1),
+                'rps':       round(s['rps'], 1),
+                'p50_ms':    round(s['p50_ms'], 2),
+                'p99_ms':    round(s['p99_ms'], 2),
+                'err_pct':   round(s['err_pct'], 4),
+                'cpu_pct':   round(s['cpu_pct'], 2),
+                'mem_mb':    round(s['mem_mb'], 1),
+            })
+    print(f"[ok] Snapshots salvos em {out}")
+
+def plot_soak(snapshots: list, report: dict, framework: str, output_dir: Path):
+    try:
+        import matplotlib
+        matplotlib.use('Agg')
+        import matplotlib.pyplot as plt
+    except ImportError:
+        print("[warn] matplotlib não disponível — gráfico não gerado")
+        return
+
+    x = [s['minute'] for s in snapshots]
+    fig, axes = plt.subplots(3, 1, figsize=(12, 9), sharex=True)
+    fig.suptitle(f'Teste de Soak — {framework}', fontsize=13, fontweight='bold')
+
+    axes[0].plot(x, [s['mem_mb'] for s in snapshots], color='#9C27B0', linewidth=2)
+    axes[0].set_ylabel('RSS (MB)')
+    axes[0].set_title(f"Memória — {report['rss_slope_mb_per_h']:+.2f} MB/h", fontsize=10)
+    axes[0].grid(alpha=0.3)
+
+    axes[1].plot(x, [s['p50_ms'] for s in snapshots], color='#4CAF50', label='P50', linewidth=2)
+    axes[1].plot(x, [s['p99_ms'] for s in snapshots], color='#F44336', label='P99', linewidth=2)
+    axes[1].set_ylabel('Latência (ms)')
+    axes[1].set_title(f"Latência — p99 {report['p99_drift_pct_per_h']:+.1f} %/h", fontsize=10)
+    axes[1].legend(fontsize=9)
+    axes[1].grid(alpha=0.3)
+
+    axes[2].plot(x, [s['rps'] for s in snapshots], color='#2196F3', linewidth=2)
+    axes[2].set_ylabel('req/s')
+    axes[2].set_xlabel('Minuto')
+    axes[2].set_title('Throughput', fontsize=10)
+    axes[2].grid(alpha=0.3)
+
+    plt.tight_layout()
+    out = output_dir / f'soak_{framework}_plot.png'
+    fig.savefig(out, dpi=150, bbox_inches='tight')
+    plt.close(fig)
+    print(f"[ok] Gráfico salvo em {out}")
+
+# ---------------------------------------------------------------------------
+# Ponto de entrada
+# ---------------------------------------------------------------------------
+
+def main():
+    args = parse_args()
+    output_dir = Path(args.output_dir)
+    output_dir.mkdir(parents=True, exist_ok=True)
+
+    k6    = load_k6_windows(args.k6_csv, args.interval)
+    stats = load_stats_windows(args.stats_csv, args.interval)
+
+    snapshots = build_snapshots(k6, stats, args.interval)
+    if len(snapshots) <= args.skip_windows:
+        print(f"[erro] Apenas {len(snapshots)} janelas — insuficiente para detectar tendências")
+        sys.exit(1)
+
+    report = detect_trends(snapshots, args)
+    write_snapshots(snapshots, args.framework, output_dir)
+
+    report_path = output_dir / f'soak_{args.framework}_report.json'
+    with open(report_path, 'w') as f:
+        json.dump(report, f, indent=2)
+    print(f"[ok] Relatório salvo em {report_path}")
+
+    if args.plot:
+        plot_soak(snapshots, report, args.framework, output_dir)
+
+    print(f"[info] RSS: {report['rss_start_mb']} → {report['rss_end_mb']} MB  ({report['rss_slope_mb_per_h']:+.2f} MB/h)")
+    print(f"[info] p99: {report['p99_slope_ms_per_h']:+.2f} ms/h  ({report['p99_drift_pct_per_h']:+.1f} %/h)")
+    print(f"SOAK_FLAGS={','.join(report['flags'])}")
+
+if __name__ == '__main__':
+    main()
@@ -0,0 +1,219 @@
+#!/usr/bin/env python3
+"""
+check-openapi.py — verifica se as APIs publicam especificações OpenAPI
+estruturalmente idênticas.
+
+A especificação é o contrato de conformidade entre as implementações: mesmas
+rotas, mesmos parâmetros, mesmos códigos de status e mesmos schemas. Para cada
+framework em execução o script baixa GET /openapi.json e compara com a
+referência (Actix por padrão) após normalizar o documento:
+
+  - `$ref` são expandidos a partir de components.schemas
+  - textos (summary, description, title, example, tags, operationId) e
+    `format` são ignorados — cada gerador preenche esses campos à sua maneira
+  - `nullable: true` (OpenAPI 3.0) e `type: [T, "null"]` (3.1) são equivalentes
+  - listas `required` e `enum` são comparadas sem considerar a ordem
+
+Frameworks que ainda não publicam /openapi.json são listados como "sem spec"
+e só reprovam a verificação com --strict.
+
+Uso:
+  python3 scripts/check-openapi.py [--frameworks "express actix gin"]
+                                   [--reference actix] [--host localhost]
+                                   [--save-dir results/openapi] [--strict]
+
+Saída:
+  Terminal: diferenças por framework (caminho no documento → referência × atual)
+  Código de saída 0 se todas as specs disponíveis forem idênticas, 1 caso contrário
+"""
+
+import argparse
+import json
+import sys
+import urllib.error
+import urllib.request
+from pathlib import Path
+
+FRAMEWORK_PORTS = {
+    'express': 3001,
+    'fastify': 3002,
+    'elysia':  3003,
+    'actix':   3004,
+    'gin':     3005,
+}
+
+IGNORED_KEYS = {'summary', 'description', 'title', 'example', 'examples',
+                'tags', 'operationId', 'format', 'externalDocs', 'deprecated'}
+
+# ---------------------------------------------------------------------------
+# Argumentos
+# ---------------------------------------------------------------------------
+
+def parse_args():
+    p = argparse.ArgumentParser(description="Compara as specs OpenAPI das APIs")
+    p.add_argument('--frameworks', default=' '.join(FRAMEWORK_PORTS), help="Frameworks a verificar (default: todos)")
+    p.add_argument('--reference',  default='actix',                   help="Framework de referência (default: actix)")
+    p.add_argument('--host',       default='localhost',               help="Host das APIs (default: localhost)")
+    p.add_argument('--save-dir',   default=None,                      help="Salva as specs baixadas neste diretório")
+    p.add_argument('--strict',     action='store_true',               help="Reprova frameworks sem /openapi.json")
+    return p.parse_args()
+
+# ---------------------------------------------------------------------------
+# Download e normalização
+# ---------------------------------------------------------------------------
+
+def fetch_spec(host, port):
+    try:
+        with urllib.request.urlopen(f"http://{host}:{port}/openapi.json", timeout=5) as r:
+            return json.load(r)
+    except (urllib.error.URLError, OSError, json.JSONDecodeError):
+        return None
+
+
+def resolve(node, schemas, seen=()):
+    """Expande $ref recursivamente (refs cíclicas ficam como {'$ref': nome})."""
+    if isinstance(node, list):
+        return [resolve(n, schemas, seen) for n in node]
+    if not isinstance(node, dict):
+        return node
+    if '$ref' in node:
+        name = node['$ref'].rsplit('/', 1)[-1]
+        if name in seen or name not in schemas:
+            return {'$ref': name}
+        return resolve(schemas[name], schemas, seen + (name,))
+    return {k: resolve(v, schemas, seen) for k, v in node.items()}
+
+
+def normalize_schema(node):
+    if isinstance(node, list):
+        return [normalize_schema(n) for n in node]
+    if not isinstance(node, dict):
+        return node
+
+    out = {k: normalize_schema(v) for k, v in node.items() if k not in IGNORED_KEYS}
+
+    types = out.pop('type', None)
+    types = set(types) if isinstance(types, list) else ({types} if types else set())
+    if out.pop('nullable', False):
+        types.add('null')
+    if types:
+        out['type'] = sorted(types)
+    for key in ('required', 'enum'):
+        if key in out:
+            out[key] = sorted(out[key], key=json.dumps)
+    for key in ('oneOf', 'anyOf'):
+        if key in out:
+            out[key] = sorted(out[key], key=lambda s: json.dumps(s, sort_keys=True))
+    return out
+
+
+def json_schema(content):
+    media = (content or {}).get('application/json')
+    return normalize_schema(media.get('schema')) if media else None
+
+
+def normalize(spec):
+    """Reduz o documento a {"<MÉTODO> <rota>": operação normalizada}."""
+    schemas = spec.get('components', {}).get('schemas', {})
+    ops = {}
+    for path, item in resolve(spec.get('paths', {}), schemas).items():
+        for method, op in item.items():
+            if method not in ('get', 'post', 'put', 'patch', 'delete'):
+                continue
+            ops[f"{method.upper()} {path}"] = {
+                'parameters': {
+                    f"{p['in']}:{p['name']}": {
+                        'required': p.get('required', False),
+                        'schema': normalize_schema(p.get('schema')),
+                    }
+                    for p in op.get('parameters', [])
+                },
+                'requestBody': {
+                    'required': op['requestBody'].get('required', False),
+                    'schema': json_schema(op['requestBody'].get('content')),
+                } if 'requestBody' in op else None,
+                'responses': {
+                    status: json_schema(resp.get('content'))
+                    for status, resp in op.get('responses', {}).items()
+                },
+            }
+    return ops
+
+# ---------------------------------------------------------------------------
+# Comparação
+# ---------------------------------------------------------------------------
+
+MISSING = object()
+
+
+def diff(ref, cur, path=''):
+    """Lista (caminho, referência, atual) para cada divergência."""
+    if isinstance(ref, dict) and isinstance(cur, dict):
+        out = []
+        for key in sorted(set(ref) | set(cur)):
+            out += diff(ref.get(key, MISSING), cur.get(key, MISSING), f"{path}.{key}" if path else key)
+        return out
+    if ref != cur:
+        return [(path, ref, cur)]
+    return []
+
+
+def show(value):
+    if value is MISSING:
+        return "(ausente)"
+    return json.dumps(value, ensure_ascii=False, sort_keys=True)
+
+# ---------------------------------------------------------------------------
+# Main
+# ---------------------------------------------------------------------------
+
+def main():
+    args = parse_args()
+    frameworks = args.frameworks.split()
+    if args.reference not in frameworks:
+        frameworks.insert(0, args.reference)
+
+    specs, missing = {}, []
+    for fw in frameworks:
+        if fw not in FRAMEWORK_PORTS:
+            print(f"Framework desconhecido: {fw}", file=sys.stderr)
+            sys.exit(2)
+        spec = fetch_spec(args.host, FRAMEWORK_PORTS[fw])
+        if spec is None:
+            missing.append(fw)
+            continue
+        specs[fw] = spec
+        if args.save_dir:
+            Path(args.save_dir).mkdir(parents=True, exist_ok=True)
+            with open(Path(args.save_dir) / f"openapi_{fw}.json", 'w') as f:
+                json.dump(spec, f, indent=2, sort_keys=True)
+
+    if args.reference not in specs:
+        print(f"✗ Referência '{args.reference}' sem /openapi.json (a API está rodando?)", file=sys.stderr)
+        sys.exit(1)
+
+    reference = normalize(specs[args.reference])
+    print(f"Referência: {args.reference} ({len(reference)} operações)\n")
+
+    failed = False
+    for fw in frameworks:
+        if fw == args.reference:
+            continue
+        if fw in missing:
+            print(f"  {fw:<8} sem spec (GET /openapi.json indisponível)")
+            failed |= args.strict
+            continue
+        differences = diff(reference, normalize(specs[fw]))
+        if not differences:
+            print(f"  {fw:<8} ✓ idêntica")
+            continue
+        failed = True
+        print(f"  {fw:<8} ✗ {len(differences)} diferença(s)")
+        for where, ref, cur in differences:
+            print(f"      {where}\n        {args.reference}: {show(ref)}\n        {fw}: {show(cur)}")
+
+    sys.exit(1 if failed else 0)
+
+
+if __name__ == '__main__':
+    main()
@@ -81,6 +81,20 @@ else
 fi
 echo ""
 
+# --- Profiling (opcional, run-experiment.sh --profile) ---
+echo "--- Profiling (opcional) ---"
+if command -v perf &>/dev/null; then
+  ok "perf encontrado"
+else
+  warn "perf não encontrado (necessário apenas para --profile) — sudo apt-get install linux-tools-\$(uname -r)"
+fi
+if command -v inferno-flamegraph &>/dev/null || command -v flamegraph.pl &>/dev/null; then
+  ok "gerador de flamegraph encontrado"
+else
+  warn "inferno/FlameGraph não encontrado (necessário apenas para --profile) — cargo install inferno"
+fi
+echo ""
+
 # --- Intel RAPL ---
 echo "--- Intel RAPL (medição de energia) ---"
 RAPL_PATH="/sys/class/powercap/intel-rapl/intel-rapl:0/energy_uj"
@@ -128,6 +142,25 @@ else
 fi
 echo ""
 
+# --- Pinning de CPU (opcional, --pin) ---
+echo "--- Pinning de CPU (opcional) ---"
+if command -v taskset &>/dev/null; then
+  ok "taskset encontrado"
+else
+  warn "taskset não encontrado (necessário apenas para --pin) — sudo apt-get install util-linux"
+fi
+NUMA_NODES=$(ls -d /sys/devices/system/node/node[0-9]* 2>/dev/null | wc -l)
+if [ "$NUMA_NODES" -gt 1 ]; then
+  if command -v numactl &>/dev/null; then
+    ok "$NUMA_NODES nós NUMA, numactl encontrado (--pin numa)"
+  else
+    warn "$NUMA_NODES nós NUMA, mas numactl não encontrado (--pin numa fixa só as CPUs) — sudo apt-get install numactl"
+  fi
+else
+  ok "Um nó NUMA (--pin numa indisponível; use --pin auto ou listas)"
+fi
+echo ""
+
 # --- Portas ---
 echo "--- Portas de rede (3001-3005, 5432) ---"
 PORTS=(3001 3002 3003 3004 3005 5432)
@@ -1,17 +1,164 @@
 -- Schema unificado para benchmark de frameworks web
 -- Usado por todas as 5 APIs: Express, Fastify, Elysia, Actix-web, Gin
 
+-- Estado do usuário (campo status e GET /users?status=x no Actix), um ENUM do
+-- Postgres mapeado para um enum Rust. Bancos criados antes dele:
+-- scripts/migrations/008-user-status.sql.
+DO $$
+BEGIN
+    CREATE TYPE user_status AS ENUM ('active', 'inactive', 'suspended');
+EXCEPTION WHEN duplicate_object THEN NULL;
+END
+$$;
+
 CREATE TABLE IF NOT EXISTS users (
     id         SERIAL PRIMARY KEY,
     name       VARCHAR(255) NOT NULL,
-    email      VARCHAR(255) NOT NULL UNIQUE,
+    email      VARCHAR(255) NOT NULL,
     age        INTEGER,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    -- Só o Actix com MULTI_TENANT=on filtra por ela; as demais APIs ignoram.
+    -- O default lê o tenant da transação (TENANT_SCOPING=rls).
+    tenant_id  VARCHAR(63) NOT NULL
+               DEFAULT COALESCE(NULLIF(current_setting('app.tenant_id', true), ''), 'default'),
+    -- Atributos livres (PATCH /users/:id/metadata e GET /users?metadata.x=y
+    -- no Actix); as demais APIs não leem a coluna. Bancos criados antes dela:
+    -- scripts/migrations/004-user-metadata.sql.
+    metadata   JSONB NOT NULL DEFAULT '{}',
+    -- Rótulos (campo tags do usuário e GET /users?tag=x no Actix); as demais
+    -- APIs não leem a coluna. Bancos criados antes dela:
+    -- scripts/migrations/005-user-tags.sql.
+    tags       TEXT[] NOT NULL DEFAULT '{}',
+    -- Saldo em reais (POST /transfer no Actix): NUMERIC, nunca float. Todo
+    -- usuário começa com 1000.00 e o CHECK impede saldo negativo; as demais
+    -- APIs não leem a coluna. Bancos criados antes dela:
+    -- scripts/migrations/007-user-balance.sql.
+    balance    NUMERIC(12,2) NOT NULL DEFAULT 1000.00
+               CONSTRAINT users_balance_non_negative CHECK (balance >= 0),
+    -- Todo usuário começa active; o PUT do Actix muda o estado. Sem índice:
+    -- com três valores, o filtro quase sempre lê boa parte da tabela.
+    status     user_status NOT NULL DEFAULT 'active'
+);
+
+-- E-mail único sem diferenciar maiúsculas: Ana@Example.com e ana@example.com
+-- são o mesmo endereço para todas as APIs. O nome é o da antiga constraint
+-- UNIQUE, então as mensagens de erro não mudam. Bancos criados antes disso:
+-- scripts/migrations/001-email-unique-lower.sql.
+CREATE UNIQUE INDEX IF NOT EXISTS users_email_key ON users (lower(email));
+
+-- Isolamento por RLS (MULTI_TENANT=on com TENANT_SCOPING=rls no Actix): cada
+-- chamada abre uma transação com SET LOCAL ROLE benchmark_tenant e
+-- SET LOCAL app.tenant_id, e a política filtra leituras e valida escritas. O
+-- dono da tabela (o usuário das APIs) ignora RLS, então as demais APIs e o
+-- modo padrão do Actix não pagam nada por ela.
+DO $$
+BEGIN
+    CREATE ROLE benchmark_tenant NOLOGIN;
+EXCEPTION WHEN duplicate_object THEN NULL;
+END
+$$;
+GRANT benchmark_tenant TO CURRENT_USER;
+GRANT SELECT, INSERT, UPDATE, DELETE ON users TO benchmark_tenant;
+GRANT USAGE ON SEQUENCE users_id_seq TO benchmark_tenant;
+
+ALTER TABLE users ENABLE ROW LEVEL SECURITY;
+CREATE POLICY tenant_isolation ON users TO benchmark_tenant
+    USING (tenant_id = current_setting('app.tenant_id', true))
+    WITH CHECK (tenant_id = current_setting('app.tenant_id', true));
+
+-- updated_at é mantido pelo banco, e não pelo SQL de cada API, para que todas
+-- continuem executando o mesmo UPDATE. Junto com COUNT(*), MAX(updated_at)
+-- versiona a coleção (ETag de GET /users no Actix).
+CREATE OR REPLACE FUNCTION touch_updated_at() RETURNS trigger AS $$
+BEGIN
+    NEW.updated_at := NOW();
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS users_touch_updated_at ON users;
+CREATE TRIGGER users_touch_updated_at
+    BEFORE UPDATE ON users
+    FOR EACH ROW EXECUTE FUNCTION touch_updated_at();
+
+-- Contas para POST /auth/register e /auth/login (Actix). O hash argon2id em
+-- formato PHC já inclui algoritmo, parâmetros e salt.
+CREATE TABLE IF NOT EXISTS accounts (
+    id            SERIAL PRIMARY KEY,
+    email         VARCHAR(255) NOT NULL UNIQUE,
+    password_hash TEXT NOT NULL,
+    created_at    TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Fila de jobs em segundo plano do Actix (POST /jobs, GET /jobs/:id). Os
+-- workers disputam as linhas com FOR UPDATE SKIP LOCKED.
+CREATE TABLE IF NOT EXISTS jobs (
+    id          BIGSERIAL PRIMARY KEY,
+    kind        TEXT NOT NULL,
+    payload     JSONB,
+    status      TEXT NOT NULL DEFAULT 'queued'
+                CHECK (status IN ('queued', 'running', 'done', 'failed')),
+    attempts    INTEGER NOT NULL DEFAULT 0,
+    result      JSONB,
+    error       TEXT,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    started_at  TIMESTAMPTZ,
+    finished_at TIMESTAMPTZ
+);
+
+-- Só as linhas pendentes entram no índice usado pelo claim
+CREATE INDEX IF NOT EXISTS idx_jobs_queued ON jobs(id) WHERE status = 'queued';
+
+-- Auditoria das escritas em /users do Actix (AUDIT=on): gravada na mesma
+-- transação da mudança, com só os campos alterados em before/after.
+CREATE TABLE IF NOT EXISTS audit_log (
+    id          BIGSERIAL PRIMARY KEY,
+    actor       TEXT,
+    tenant_id   VARCHAR(63),
+    action      TEXT NOT NULL CHECK (action IN ('create', 'update', 'delete')),
+    entity      TEXT NOT NULL,
+    entity_id   INTEGER,
+    before      JSONB,
+    after       JSONB,
+    occurred_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+GRANT INSERT ON audit_log TO benchmark_tenant;
+GRANT USAGE ON SEQUENCE audit_log_id_seq TO benchmark_tenant;
+
+-- Webhooks do Actix (POST /webhooks): cada escrita em /users gera uma entrega
+-- por webhook, enviada e reenviada com backoff pelo dispatcher.
+CREATE TABLE IF NOT EXISTS webhooks (
+    id         SERIAL PRIMARY KEY,
+    url        TEXT NOT NULL,
+    secret     TEXT NOT NULL,
     created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
 );
 
+CREATE TABLE IF NOT EXISTS webhook_deliveries (
+    id               BIGSERIAL PRIMARY KEY,
+    webhook_id       INTEGER NOT NULL REFERENCES webhooks(id) ON DELETE CASCADE,
+    event            TEXT NOT NULL,
+    payload          JSONB NOT NULL,
+    status           TEXT NOT NULL DEFAULT 'pending'
+                     CHECK (status IN ('pending', 'sending', 'delivered', 'failed')),
+    attempts         INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_status_code INTEGER,
+    last_error       TEXT,
+    created_at       TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    delivered_at     TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_pending
+    ON webhook_deliveries(next_attempt_at) WHERE status = 'pending';
+CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_webhook
+    ON webhook_deliveries(webhook_id, id);
+
 -- Seed: 10.000 registros (alinhado com TechEmpower Framework Benchmarks)
--- Nomes e domínios variados para simular dados reais
-INSERT INTO users (name, email, age)
+-- Nomes e domínios variados para simular dados reais, distribuídos entre 10
+-- tenants (tenant-0 … tenant-9) para o modo multi-tenant
+INSERT INTO users (name, email, age, tenant_id)
 SELECT
     (ARRAY[
         'Alice','Bob','Carlos','Diana','Eduardo','Fernanda','Gabriel','Helena',
@@ -27,13 +174,50 @@ SELECT
     (ARRAY['gmail.com','outlook.com','yahoo.com','hotmail.com','benchmark.dev'])
     [ 1 + (i % 5) ],
 
-    18 + (i % 62)   -- idades entre 18 e 79 anos
+    18 + (i % 62),  -- idades entre 18 e 79 anos
+
+    'tenant-' || (i % 10)
 
 FROM generate_series(1, 10000) AS s(i)
 ON CONFLICT DO NOTHING;
 
--- Índice para buscas por e-mail (POST /users, unicidade)
+-- O trigger do feed de mudanças (GET /events no Actix com EVENTS=on) não faz
+-- parte do schema padrão: o NOTIFY serializaria as escritas de todas as APIs.
+-- Rodadas com EVENTS=on aplicam scripts/migrations/009-change-feed.sql.
+
+-- Índice para buscas por e-mail exato (a unicidade é por lower(email))
 CREATE INDEX IF NOT EXISTS idx_users_email ON users(email);
 
+-- MAX(updated_at) por índice em vez de varredura
+CREATE INDEX IF NOT EXISTS idx_users_updated_at ON users(updated_at);
+
+-- Consultas com escopo de tenant (MULTI_TENANT=on no Actix): listagem e
+-- paginação por (tenant_id, id), versão da coleção por (tenant_id, updated_at)
+CREATE INDEX IF NOT EXISTS idx_users_tenant_id ON users(tenant_id, id);
+CREATE INDEX IF NOT EXISTS idx_users_tenant_updated_at ON users(tenant_id, updated_at);
+
+-- Filtros por metadata (metadata @> '{"plan": "pro"}' no Actix); jsonb_path_ops
+-- só atende @>, com um índice menor que o jsonb_ops padrão
+CREATE INDEX IF NOT EXISTS idx_users_metadata ON users USING GIN (metadata jsonb_path_ops);
+
+-- Filtros por rótulo (tags @> '{pro,beta}' no Actix)
+CREATE INDEX IF NOT EXISTS idx_users_tags ON users USING GIN (tags);
+
+-- Agregados por domínio de e-mail para GET /users/stats (Actix), recalculados
+-- periodicamente pelo agendador com REFRESH ... CONCURRENTLY, que exige o
+-- índice único.
+CREATE MATERIALIZED VIEW IF NOT EXISTS user_stats AS
+SELECT
+    split_part(email, '@', 2) AS domain,
+    COUNT(*)::bigint          AS users,
+    AVG(age)::float8          AS avg_age,
+    MIN(age)                  AS min_age,
+    MAX(age)                  AS max_age,
+    NOW()                     AS computed_at
+FROM users
+GROUP BY 1;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_user_stats_domain ON user_stats(domain);
+
 -- Atualiza estatísticas para o query planner usar planos ótimos desde o início
 ANALYZE users;
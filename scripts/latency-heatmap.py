@@ -0,0 +1,212 @@
+#!/usr/bin/env python3
+"""
+latency-heatmap.py — percentis de latência segundo a segundo e heatmap no tempo.
+
+Os percentis agregados da medição escondem o aquecimento, as pausas e os picos
+periódicos. Este script lê o CSV do k6 (--out csv=..., opcionalmente .gz) e,
+para cada segundo da execução, calcula requisições, p50/p90/p99/p99.9 e máximo.
+O heatmap tem o tempo no eixo x, faixas de latência em escala logarítmica no
+eixo y e a contagem de requisições de cada faixa na cor, com p50 e p99
+sobrepostos.
+
+Um segundo é marcado como pico quando seu p99 passa de --spike-factor vezes a
+mediana dos p99 por segundo.
+
+Uso:
+  python3 scripts/latency-heatmap.py \\
+    --k6-csv results/latency_<ts>/latency_actix_k6.csv.gz \\
+    --framework actix \\
+    --output-dir results/latency_<ts> \\
+    [--buckets 40] [--spike-factor 3]
+
+Saída:
+  latency_<framework>_by_second.csv  — uma linha por segundo
+  latency_<framework>_heatmap.svg    — heatmap (se matplotlib disponível)
+  Terminal: segundos com pico
+"""
+
+import argparse
+import csv
+import gzip
+import math
+import statistics
+import sys
+from collections import defaultdict
+from pathlib import Path
+
+# ---------------------------------------------------------------------------
+# Argumentos
+# ---------------------------------------------------------------------------
+
+def parse_args():
+    p = argparse.ArgumentParser(description="Percentis por segundo e heatmap de latência")
+    p.add_argument('--k6-csv',       required=True,             help="CSV exportado pelo k6 (--out csv=..., .gz aceito)")
+    p.add_argument('--framework',    required=True,             help="Nome do framework")
+    p.add_argument('--output-dir',   default='.',               help="Diretório de saída (default: .)")
+    p.add_argument('--buckets',      type=int,   default=40,    help="Faixas de latência no eixo y (default: 40)")
+    p.add_argument('--spike-factor', type=float, default=3.0,   help="p99 do segundo / mediana dos p99 que marca um pico (default: 3)")
+    return p.parse_args()
+
+# ---------------------------------------------------------------------------
+# Leitura
+# ---------------------------------------------------------------------------
+
+def load_durations(path: str) -> dict:
+    """Segundo (timestamp Unix) → durações de http_req_duration em ms."""
+    seconds = defaultdict(list)
+    opener = gzip.open if path.endswith('.gz') else open
+
+    with opener(path, 'rt', newline='') as f:
+        for row in csv.DictReader(f):
+            if row.get('metric_name', '').strip() != 'http_req_duration':
+                continue
+            try:
+                seconds[int(float(row['timestamp']))].append(float(row['metric_value']))
+            except (KeyError, ValueError, TypeError):
+                continue
+
+    return dict(seconds)
+
+# ---------------------------------------------------------------------------
+# Percentis por segundo
+# ---------------------------------------------------------------------------
+
+def percentile(sorted_vals: list, p: float) -> float:
+    if not sorted_vals:
+        return 0.0
+    idx = int(p / 100 * len(sorted_vals))
+    return sorted_vals[min(idx, len(sorted_vals) - 1)]
+
+def build_rows(seconds: dict) -> list:
+    if not seconds:
+        return []
+    t0 = min(seconds.keys())
+    rows = []
+    for ts in range(t0, max(seconds.keys()) + 1):
+        durations = sorted(seconds.get(ts, []))
+        rows.append({
+            'second':   ts - t0,
+            'requests': len(durations),
+            'p50_ms':   percentile(durations, 50),
+            'p90_ms':   percentile(durations, 90),
+            'p99_ms':   percentile(durations, 99),
+            'p999_ms':  percentile(durations, 99.9),
+            'max_ms':   durations[-1] if durations else 0.0,
+            'durations': durations,
+        })
+
+    # O último segundo costuma estar incompleto
+    return rows[:-1] if len(rows) > 1 else rows
+
+def find_spikes(rows: list, factor: float) -> list:
+    p99s = [r['p99_ms'] for r in rows if r['requests']]
+    if not p99s:
+        return []
+    base = statistics.median(p99s)
+    return [r for r in rows if r['requests'] and base > 0 and r['p99_ms'] > base * factor]
+
+# ---------------------------------------------------------------------------
+# Saída
+# ---------------------------------------------------------------------------
+
+def write_rows(rows: list, spikes: list, framework: str, output_dir: Path):
+    out = output_dir / f'latency_{framework}_by_second.csv'
+    spike_seconds = {r['second'] for r in spikes}
+    with open(out, 'w', newline='') as f:
+        writer = csv.DictWriter(f, fieldnames=['framework', 'second', 'requests', 'p50_ms', 'p90_ms', 'p99_ms', 'p999_ms', 'max_ms', 'spike'])
+        writer.writeheader()
+        for r in rows:
+            writer.writerow({
+                'framework': framework,
+                'second':    r['second'],
+                'requests':  r['requests'],
+                'p50_ms':    round(r['p50_ms'], 2),
+                'p90_ms':    round(r['p90_ms'], 2),
+                'p99_ms':    round(r['p99_ms'], 2),
+                'p999_ms':   round(r['p999_ms'], 2),
+                'max_ms':    round(r['max_ms'], 2),
+                'spike':     int(r['second'] in spike_seconds),
+            })
+    print(f"[ok] Percentis por segundo salvos em {out}")
+
+def plot_heatmap(rows: list, spikes: list, framework: str, buckets: int, output_dir: Path):
+    try:
+        import matplotlib
+        matplotlib.use('Agg')
+        import matplotlib.pyplot as plt
+        from matplotlib.colors import LogNorm
+        import numpy as np
+    except ImportError:
+        print("[warn] matplotlib/numpy não disponível — heatmap não gerado")
+        return
+
+    all_durations = [d for r in rows for d in r['durations'] if d > 0]
+    if not all_durations:
+        print("[warn] nenhuma duração positiva — heatmap não gerado")
+        return
+
+    # Faixas logarítmicas entre a menor e a maior latência observadas
+    lo = math.log10(min(all_durations))
+    hi = math.log10(max(all_durations))
+    if hi - lo < 1e-6:
+        hi = lo + 1
+    edges = np.logspace(lo, hi, buckets + 1)
+
+    counts = np.zeros((buckets, len(rows)))
+    for i, r in enumerate(rows):
+        if r['durations']:
+            counts[:, i], _ = np.histogram(r['durations'], bins=edges)
+    counts = np.ma.masked_equal(counts, 0)
+
+    x_edges = np.arange(len(rows) + 1)
+    x_mid = x_edges[:-1] + 0.5
+
+    fig, ax = plt.subplots(figsize=(14, 6))
+    mesh = ax.pcolormesh(x_edges, edges, counts, cmap='viridis', norm=LogNorm(), shading='flat')
+    fig.colorbar(mesh, ax=ax, label='requisições por faixa')
+
+    ax.plot(x_mid, [r['p50_ms'] or np.nan for r in rows], color='white', linewidth=1, label='P50')
+    ax.plot(x_mid, [r['p99_ms'] or np.nan for r in rows], color='#F44336', linewidth=1, label='P99')
+    if spikes:
+        ax.scatter([r['second'] + 0.5 for r in spikes], [r['p99_ms'] for r in spikes],
+                   marker='v', color='#FF9800', s=18, zorder=3, label='pico')
+
+    ax.set_yscale('log')
+    ax.set_xlabel('Segundo')
+    ax.set_ylabel('Latência (ms)')
+    ax.set_title(f'Latência por segundo — {framework}', fontsize=12, fontweight='bold')
+    ax.legend(fontsize=9, loc='upper right')
+
+    plt.tight_layout()
+    out = output_dir / f'latency_{framework}_heatmap.svg'
+    fig.savefig(out, format='svg', bbox_inches='tight')
+    plt.close(fig)
+    print(f"[ok] Heatmap salvo em {out}")
+
+# ---------------------------------------------------------------------------
+# Ponto de entrada
+# ---------------------------------------------------------------------------
+
+def main():
+    args = parse_args()
+    output_dir = Path(args.output_dir)
+    output_dir.mkdir(parents=True, exist_ok=True)
+
+    rows = build_rows(load_durations(args.k6_csv))
+    if not rows:
+        print(f"[erro] Nenhum http_req_duration em {args.k6_csv}")
+        sys.exit(1)
+
+    spikes = find_spikes(rows, args.spike_factor)
+    write_rows(rows, spikes, args.framework, output_dir)
+    plot_heatmap(rows, spikes, args.framework, args.buckets, output_dir)
+
+    if spikes:
+        listed = ', '.join(f"{r['second']}s ({r['p99_ms']:.1f}ms)" for r in spikes[:20])
+        more = f" e mais {len(spikes) - 20}" if len(spikes) > 20 else ''
+        print(f"[info] {len(spikes)} segundo(s) com pico de p99: {listed}{more}")
+    else:
+        print("[info] Nenhum pico de p99 por segundo")
+
+if __name__ == '__main__':
+    main()
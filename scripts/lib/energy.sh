@@ -0,0 +1,113 @@
+# energy.sh — energia do host das APIs pelos contadores RAPL (powercap)
+#
+# O kernel expõe os contadores RAPL em /sys/class/powercap/intel-rapl:N (um
+# por pacote; o driver intel_rapl também cobre AMD Zen). Com --energy, cada
+# segundo da medição lê o energy_uj de todos os pacotes, e a energia do
+# intervalo é a soma das diferenças, tratando a volta do contador pelo
+# max_energy_range_uj de cada zona. Sem os contadores, --energy falha no
+# início, não no fim da medição.
+#
+# Com --remote ssh:// (lib/remote.sh, carregado antes) os contadores lidos são
+# os do host remoto; com context:NOME não há como lê-los.
+#
+# Os contadores costumam ser legíveis só pelo root:
+#   sudo chmod a+r /sys/class/powercap/intel-rapl:*/energy_uj
+#
+# Uso (depois de definir log/warn/error):
+#   source "$SCRIPT_DIR/lib/energy.sh"
+#   energy_setup "$ENERGY" || exit 1
+#   energy_sample_start arquivo.csv ... energy_sample_stop
+#   energy_joules arquivo.csv           # J no intervalo amostrado
+
+ENERGY_ENABLED=false
+ENERGY_SAMPLER_PID=""
+
+# Lista as zonas de pacote: "nome max_energy_range_uj" por linha
+_energy_zones_sh() {
+  cat <<'SHEOF'
+for zone in /sys/class/powercap/intel-rapl:*; do
+  case "${zone##*/}" in *:*:*) continue ;; esac
+  [ -r "$zone/energy_uj" ] || continue
+  echo "${zone##*/} $(cat "$zone/max_energy_range_uj" 2>/dev/null || echo 4294967295)"
+done
+SHEOF
+}
+
+# Amostrador: timestamp_ms,zona,energy_uj,max_energy_range_uj a cada segundo
+_energy_sampler_sh() {
+  cat <<'SHEOF'
+while :; do
+  ts=$(date +%s%3N)
+  for zone in /sys/class/powercap/intel-rapl:*; do
+    case "${zone##*/}" in *:*:*) continue ;; esac
+    uj=$(cat "$zone/energy_uj" 2>/dev/null) || continue
+    echo "$ts,${zone##*/},$uj,$(cat "$zone/max_energy_range_uj" 2>/dev/null || echo 4294967295)"
+  done
+  sleep 1
+done
+SHEOF
+}
+
+_energy_target_sh() {
+  if [ "${REMOTE_MODE:-off}" = ssh ]; then
+    remote_sh sh -s
+  else
+    sh -s
+  fi
+}
+
+energy_setup() {
+  local enabled="${1:-false}" zones
+  ENERGY_ENABLED=false
+  [ "$enabled" = true ] || return 0
+
+  if [ "${REMOTE_MODE:-off}" = context ]; then
+    error "--energy: o RAPL do host remoto só é lido via ssh (use --remote ssh://...)"
+    return 1
+  fi
+  zones=$(_energy_zones_sh | _energy_target_sh 2>/dev/null)
+  if [ -z "$zones" ]; then
+    error "--energy: nenhum contador RAPL legível em /sys/class/powercap/intel-rapl:*"
+    error "Execute: sudo chmod a+r /sys/class/powercap/intel-rapl:*/energy_uj"
+    return 1
+  fi
+  ENERGY_ENABLED=true
+  log "RAPL: $(echo "$zones" | awk '{print $1}' | paste -sd' ')"
+}
+
+energy_sample_start() {
+  local file="$1"
+  ENERGY_SAMPLER_PID=""
+  $ENERGY_ENABLED || return 0
+  echo "timestamp_ms,zone,energy_uj,max_energy_range_uj" > "$file"
+  (
+    _energy_sampler_sh | _energy_target_sh | if [ "${REMOTE_MODE:-off}" = ssh ]; then
+      # Amostras no relógio local, como em remote_sample_start
+      while IFS=, read -r ts rest; do echo "$(( ts - CLOCK_OFFSET_MS )),$rest"; done
+    else
+      cat
+    fi >> "$file"
+  ) &
+  ENERGY_SAMPLER_PID=$!
+}
+
+energy_sample_stop() {
+  [ -n "$ENERGY_SAMPLER_PID" ] || return 0
+  pkill -P "$ENERGY_SAMPLER_PID" 2>/dev/null || true
+  kill "$ENERGY_SAMPLER_PID" 2>/dev/null || true
+  wait "$ENERGY_SAMPLER_PID" 2>/dev/null || true
+  ENERGY_SAMPLER_PID=""
+}
+
+# Joules entre a primeira e a última amostra, somando todas as zonas
+energy_joules() {
+  awk -F, 'NR > 1 && $3 != "" {
+      if ($2 in prev) {
+        d = $3 - prev[$2]
+        if (d < 0) d += $4
+        total += d
+      }
+      prev[$2] = $3
+    }
+    END { printf "%.3f\n", total / 1e6 }' "$1"
+}
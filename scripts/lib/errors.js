@@ -0,0 +1,145 @@
+/**
+ * errors.js — classificação de falhas e orçamento de erros dos scripts k6
+ *
+ * Uma resposta fora do esperado não pode só baixar o RPS em silêncio. Cada
+ * requisição passa por `recordOutcome`, que a classifica pelo código de erro
+ * do k6, pelo status e pelo corpo:
+ *
+ *   connect_refused  conexão recusada (API fora do ar, backlog cheio)
+ *   connect_timeout  a conexão não abriu a tempo
+ *   timeout          sem resposta dentro de REQ_TIMEOUT
+ *   reset            conexão derrubada pelo servidor (RST, broken pipe)
+ *   dns / tls        falha de resolução ou de handshake
+ *   network          outra falha de rede
+ *   truncated        status recebido, mas o corpo terminou antes do fim
+ *   status_5xx       erro do servidor
+ *   status_4xx       4xx que o script não aceita (409 costuma ser aceito)
+ *   body_mismatch    status de sucesso com um corpo que não confere (JSON
+ *                    inválido, divergência da spec — ver schema.js)
+ *
+ * Cada classe vira um contador `errors_<classe>`, e as primeiras
+ * ERROR_SAMPLES falhas de cada classe por VU são registradas no console como
+ * uma linha JSON `{"error_sample": ...}` com status, URL e o início do corpo
+ * (use `k6 run --console-output arquivo` para guardá-las). A taxa de falhas
+ * (métrica `errors`) tem o threshold `rate<=ERROR_BUDGET`: acima do orçamento
+ * o k6 termina com código 99.
+ *
+ * Variáveis de ambiente:
+ *   ERROR_BUDGET  — fração máxima de falhas (default: 0.01; 1 desliga)
+ *   ERROR_SAMPLES — amostras por classe e por VU (default: 3)
+ */
+
+import { Counter, Rate } from 'k6/metrics';
+
+export const ERROR_BUDGET = parseFloat(__ENV.ERROR_BUDGET || '0.01');
+const ERROR_SAMPLES = parseInt(__ENV.ERROR_SAMPLES || '3', 10);
+const SAMPLE_BYTES = 512;
+
+const CLASSES = [
+  'connect_refused', 'connect_timeout', 'timeout', 'reset', 'dns', 'tls', 'network',
+  'truncated', 'status_5xx', 'status_4xx', 'body_mismatch',
+];
+
+const errorRate = new Rate('errors');
+const counters = {};
+for (const cls of CLASSES) counters[cls] = new Counter(`errors_${cls}`);
+const sampled = {};
+
+/** Para `options.thresholds`. */
+export const errorThresholds = { errors: [`rate<=${ERROR_BUDGET}`] };
+
+// Códigos de erro do k6: https://grafana.com/docs/k6/latest/javascript-api/error-codes/
+function networkClass(code) {
+  if (code === 1050 || code === 1211) return 'timeout';
+  if (code === 1222) return 'connect_refused';
+  if (code === 1221) return 'connect_timeout';
+  if (code === 1201 || code === 1212) return 'reset';
+  if (code >= 1100 && code < 1200) return 'dns';
+  if (code >= 1300 && code < 1400) return 'tls';
+  return 'network';
+}
+
+/**
+ * `{ cls, detail }` da falha, ou `null` se a resposta é aceita.
+ * `accept(status)` decide os status aceitos (default: 2xx); `validate(res)`
+ * confere o corpo de uma resposta aceita e devolve `null` ou o problema.
+ */
+export function classify(res, accept, validate) {
+  const cls = statusClass(res, accept);
+  if (cls) return { cls };
+  const detail = validate ? validate(res) : null;
+  return detail ? { cls: 'body_mismatch', detail } : null;
+}
+
+function statusClass(res, accept) {
+  if (res.status === 0) return networkClass(res.error_code);
+  // O k6 entrega o status mesmo quando a leitura do corpo falha no meio
+  const length = parseInt(res.headers['Content-Length'] ?? '', 10);
+  // Um texto com menos caracteres que o Content-Length pode ainda ter os bytes todos
+  const short = typeof res.body === 'string' && res.body.length < length && byteLength(res.body) < length;
+  if (res.error || short) return 'truncated';
+  if (!accept(res.status)) return res.status >= 500 ? 'status_5xx' : 'status_4xx';
+  return null;
+}
+
+function byteLength(text) {
+  let bytes = 0;
+  for (let i = 0; i < text.length; i++) {
+    const c = text.charCodeAt(i);
+    if (c < 0x80) bytes += 1;
+    else if (c < 0x800) bytes += 2;
+    else if (c >= 0xd800 && c < 0xdc00) { bytes += 4; i++; }
+    else bytes += 3;
+  }
+  return bytes;
+}
+
+const is2xx = (status) => status >= 200 && status < 300;
+
+/** Classifica e contabiliza `res`; devolve `true` se foi aceita. */
+export function recordOutcome(res, { accept = is2xx, validate } = {}) {
+  const failure = classify(res, accept, validate);
+  errorRate.add(failure !== null);
+  if (failure === null) return true;
+  const { cls, detail } = failure;
+  counters[cls].add(1);
+  sampled[cls] = (sampled[cls] || 0) + 1;
+  if (sampled[cls] <= ERROR_SAMPLES) {
+    console.warn(JSON.stringify({
+      error_sample: cls,
+      vu:           __VU,
+      method:       res.request?.method,
+      url:          res.request?.url,
+      status:       res.status,
+      error:        res.error || undefined,
+      detail,
+      body:         typeof res.body === 'string' ? res.body.slice(0, SAMPLE_BYTES) : undefined,
+    }));
+  }
+  return false;
+}
+
+/** `{ errors: { classe: n }, classified_error_rate, error_budget, error_budget_exceeded }` */
+export function errorSummary(data) {
+  const m = data.metrics;
+  const errors = {};
+  for (const cls of CLASSES) {
+    const n = m[`errors_${cls}`]?.values?.count ?? 0;
+    if (n > 0) errors[cls] = n;
+  }
+  const rate = m.errors?.values?.rate ?? 0;
+  return {
+    errors,
+    classified_error_rate: rate,
+    error_budget:          ERROR_BUDGET,
+    error_budget_exceeded: rate > ERROR_BUDGET,
+  };
+}
+
+/** Linha de stdout com as falhas por classe e o orçamento. */
+export function errorLine(summary) {
+  const classes = Object.entries(summary.errors).map(([cls, n]) => `${cls}=${n}`).join('  ') || 'nenhuma';
+  const verdict = summary.error_budget_exceeded ? 'ESTOURADO' : 'ok';
+  return `falhas: ${classes}  (${(summary.classified_error_rate * 100).toFixed(4)}% ` +
+         `de ${(summary.error_budget * 100).toFixed(2)}% permitidos: ${verdict})`;
+}
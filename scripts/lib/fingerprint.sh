@@ -0,0 +1,166 @@
+# fingerprint.sh — ambiente de execução anexado a cada resultado
+#
+# Resultados sem o ambiente viram comparação de maçã com laranja: um p99 de
+# outro kernel, com turbo ligado ou SMT desligado não é comparável. Cada
+# orquestrador grava environment.json no diretório de resultados:
+#
+#   loadgen   máquina que roda o script e o k6: kernel, SO, modelo e flags da
+#             CPU, núcleos físicos/lógicos, sockets, nós NUMA, governor, turbo,
+#             SMT, memória, swap, transparent hugepages, virtualização, rustc
+#   target    o mesmo para o host das APIs com --remote ssh:// (lib/remote.sh);
+#             null quando é a mesma máquina (ou sem ssh para lê-lo)
+#   docker    versões do cliente, do daemon (o do alvo, com --remote) e do compose
+#   k6, repo  versão do k6; commit do repositório e se há mudanças locais
+#   builds    build de cada API que expõe GET /config (o Actix: rustc, features
+#             do cargo, perfil, commit e alocador), juntado no fim da execução
+#
+# Uso:
+#   source "$SCRIPT_DIR/lib/fingerprint.sh"
+#   fingerprint_write "$RESULTS_DIR/environment.json"    # no início
+#   fingerprint_builds "$RESULTS_DIR/environment.json"   # depois das medições
+#   fingerprint_nodes "$RESULTS_DIR/environment.json" kubectl   # run-k8s.sh
+
+# Fatos do host em linhas chave=valor; POSIX sh, para rodar também via ssh
+_fingerprint_host_sh() {
+  cat <<'SHEOF'
+cpu=/sys/devices/system/cpu
+echo "hostname=$(hostname)"
+echo "kernel=$(uname -r)"
+echo "arch=$(uname -m)"
+echo "os=$(. /etc/os-release 2>/dev/null && echo "$PRETTY_NAME")"
+echo "cpu_model=$(grep -m1 'model name' /proc/cpuinfo | cut -d: -f2 | sed 's/^ *//')"
+echo "cpu_flags=$(grep -m1 -E '^(flags|Features)' /proc/cpuinfo | cut -d: -f2 | sed 's/^ *//')"
+echo "cpu_logical=$(nproc --all 2>/dev/null || grep -c ^processor /proc/cpuinfo)"
+echo "cpu_physical=$(cat $cpu/cpu[0-9]*/topology/core_cpus_list $cpu/cpu[0-9]*/topology/thread_siblings_list 2>/dev/null | sort -u | wc -l)"
+echo "sockets=$(cat $cpu/cpu[0-9]*/topology/physical_package_id 2>/dev/null | sort -u | wc -l)"
+echo "numa_nodes=$(ls -d /sys/devices/system/node/node[0-9]* 2>/dev/null | wc -l)"
+echo "governor=$(cat $cpu/cpu[0-9]*/cpufreq/scaling_governor 2>/dev/null | sort -u | paste -sd, -)"
+if [ -r $cpu/intel_pstate/no_turbo ]; then
+  echo "turbo=$([ "$(cat $cpu/intel_pstate/no_turbo)" = 0 ] && echo on || echo off)"
+elif [ -r $cpu/cpufreq/boost ]; then
+  echo "turbo=$([ "$(cat $cpu/cpufreq/boost)" = 1 ] && echo on || echo off)"
+fi
+echo "smt_control=$(cat $cpu/smt/control 2>/dev/null)"
+echo "smt_active=$(cat $cpu/smt/active 2>/dev/null)"
+echo "mem_total_mb=$(awk '/^MemTotal/{printf "%d", $2/1024}' /proc/meminfo)"
+echo "swap_total_mb=$(awk '/^SwapTotal/{printf "%d", $2/1024}' /proc/meminfo)"
+echo "thp=$(sed 's/.*\[\(.*\)\].*/\1/' /sys/kernel/mm/transparent_hugepage/enabled 2>/dev/null)"
+echo "virtualization=$(systemd-detect-virt 2>/dev/null || true)"
+echo "rustc=$(rustc --version 2>/dev/null)"
+SHEOF
+}
+
+# chave=valor → objeto JSON (flags viram lista, contagens viram números)
+_fingerprint_to_json() {
+  python3 -c '
+import json, sys
+facts = {}
+for line in sys.stdin:
+    key, sep, value = line.rstrip("\n").partition("=")
+    if not sep:
+        continue
+    if key == "cpu_flags":
+        facts[key] = value.split()
+    elif value.isdigit():
+        facts[key] = int(value)
+    else:
+        facts[key] = value or None
+print(json.dumps(facts))'
+}
+
+fingerprint_host() {
+  _fingerprint_host_sh | sh -s 2>/dev/null | _fingerprint_to_json
+}
+
+fingerprint_write() {
+  local file="$1" target=null
+  if [ "${REMOTE_MODE:-off}" = ssh ]; then
+    target=$(_fingerprint_host_sh | remote_sh sh -s 2>/dev/null | _fingerprint_to_json) || target=null
+  fi
+  local project_dir
+  project_dir="$(cd "$(dirname "${BASH_SOURCE[0]}")/../.." && pwd)"
+  python3 - "$file" "$(fingerprint_host)" "$target" <<PYEOF
+import json, sys
+file, loadgen, target = sys.argv[1], json.loads(sys.argv[2]), json.loads(sys.argv[3])
+env = {
+    "collected_at": "$(date -Iseconds)",
+    "loadgen": loadgen,
+    "target": target,
+    "docker": {
+        "client":  "$(docker version --format '{{.Client.Version}}' 2>/dev/null || true)" or None,
+        "server":  "$(docker version --format '{{.Server.Version}}' 2>/dev/null || true)" or None,
+        "compose": "$(docker compose version --short 2>/dev/null || true)" or None,
+    },
+    "k6": "$(k6 version 2>/dev/null | head -1 || true)" or None,
+    "repo": {
+        "git_sha": "$(git -C "$project_dir" rev-parse HEAD 2>/dev/null || true)" or None,
+        "dirty":   $([ -n "$(git -C "$project_dir" status --porcelain 2>/dev/null)" ] && echo True || echo False),
+    },
+    "actix_features": "${ACTIX_FEATURES:-}",
+    "builds": {},
+}
+with open(file, "w") as f:
+    json.dump(env, f, indent=2, ensure_ascii=False)
+PYEOF
+}
+
+# Junta ao environment.json o bloco "build" (e o alocador) de cada config*.json
+# salvo pelo orquestrador: <dir>/<nome>/config.json ou <dir>/config_<nome>.json
+fingerprint_builds() {
+  local file="$1"
+  [ -f "$file" ] || return 0
+  python3 - "$file" <<'PYEOF'
+import glob, json, os, sys
+file = sys.argv[1]
+root = os.path.dirname(file)
+with open(file) as f:
+    env = json.load(f)
+paths = glob.glob(os.path.join(root, '*', 'config.json')) + glob.glob(os.path.join(root, 'config_*.json'))
+for path in sorted(paths):
+    name = os.path.basename(os.path.dirname(path)) if path.endswith('/config.json') \
+        else os.path.basename(path)[len('config_'):-len('.json')]
+    try:
+        with open(path) as f:
+            config = json.load(f)
+    except (OSError, ValueError):
+        continue
+    if 'build' in config:
+        env['builds'][name] = {**config['build'], 'allocator': config.get('allocator')}
+with open(file, 'w') as f:
+    json.dump(env, f, indent=2, ensure_ascii=False)
+PYEOF
+}
+
+# No Kubernetes o alvo são os nós do cluster: "target" vira a lista deles
+# (nodeInfo e capacidade), lida pelo kubectl passado como argumento
+fingerprint_nodes() {
+  local file="$1"; shift
+  [ -f "$file" ] || return 0
+  { "$@" get nodes -o json 2>/dev/null || true; } | python3 -c '
+import json, sys
+file = sys.argv[1]
+try:
+    items = json.load(sys.stdin)["items"]
+except ValueError:
+    sys.exit(0)
+nodes = []
+for node in items:
+    info, capacity = node["status"]["nodeInfo"], node["status"]["capacity"]
+    nodes.append({
+        "name": node["metadata"]["name"],
+        "kernel": info["kernelVersion"],
+        "os": info["osImage"],
+        "arch": info["architecture"],
+        "container_runtime": info["containerRuntimeVersion"],
+        "kubelet": info["kubeletVersion"],
+        "cpu": capacity.get("cpu"),
+        "memory": capacity.get("memory"),
+        "instance_type": node["metadata"].get("labels", {}).get("node.kubernetes.io/instance-type"),
+    })
+with open(file) as f:
+    env = json.load(f)
+env["target"] = nodes
+with open(file, "w") as f:
+    json.dump(env, f, indent=2, ensure_ascii=False)
+' "$file"
+}
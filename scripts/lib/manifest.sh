@@ -0,0 +1,102 @@
+# manifest.sh — manifesto de reprodutibilidade de cada execução
+#
+# Número publicado precisa poder ser refeito. Cada orquestrador grava
+# run_manifest.json no diretório de resultados com o necessário para repetir a
+# execução com scripts/replay.sh:
+#
+#   script, args, cwd   o comando exato (argumentos originais, diretório)
+#   seeds               SEED dos sorteios do k6 (lib/seed.js) e RANDOM_SEED
+#                       da API; sorteados quando não informados
+#   repo                commit e, se a árvore tinha mudanças, repo.patch com o
+#                       `git diff` delas ao lado do manifesto
+#   env                 variáveis do docker-compose.yml definidas no ambiente
+#                       (ACTIX_FEATURES, WORKERS, HTTP3...), ou seja, a
+#                       configuração das APIs e do banco
+#   images              imagem de cada serviço medido: referência e id
+#                       (sha256 local) ou digest do registry
+#   replay_of           execução de origem, quando esta é um replay
+#
+# Uso:
+#   source "$SCRIPT_DIR/lib/manifest.sh"
+#   ARGS=("$@")                                   # antes de ler as opções
+#   manifest_write "$RESULTS_DIR/run_manifest.json" "${ARGS[@]}"
+#   manifest_image "$RESULTS_DIR/run_manifest.json" api-actix      # com o container criado
+
+# Semente do k6 e, quando a API aceita, do sorteio de ids do Actix
+# (RANDOM_SEED não combina com MULTI_TENANT=on nem com order-by-random)
+SEED="${SEED:-$(( (RANDOM << 15 | RANDOM) + 1 ))}"
+export SEED
+if [ -z "${RANDOM_SEED:-}" ] && [ "${MULTI_TENANT:-off}" != on ] \
+   && [ "${RANDOM_ROW_SELECTION:-id-range}" = id-range ]; then
+  export RANDOM_SEED="$SEED"
+fi
+
+manifest_write() {
+  local file="$1"; shift
+  local project_dir script patch="" index
+  project_dir="$(cd "$(dirname "${BASH_SOURCE[0]}")/../.." && pwd)"
+  script="$(realpath --relative-to="$project_dir" "$0")"
+  if [ -n "$(git -C "$project_dir" status --porcelain -- . ':!results' 2>/dev/null)" ]; then
+    # Índice temporário, para o patch levar também os arquivos novos
+    patch="$(dirname "$file")/repo.patch"
+    index=$(mktemp)
+    GIT_INDEX_FILE="$index" git -C "$project_dir" read-tree HEAD
+    GIT_INDEX_FILE="$index" git -C "$project_dir" add -A -- . ':!results'
+    GIT_INDEX_FILE="$index" git -C "$project_dir" diff --cached --binary HEAD > "$patch"
+    rm -f "$index"
+  fi
+  python3 - "$file" "$project_dir" "$script" "$patch" "$@" <<PYEOF
+import json, os, re, sys
+file, project_dir, script, patch, args = sys.argv[1], sys.argv[2], sys.argv[3], sys.argv[4], sys.argv[5:]
+with open(os.path.join(project_dir, 'docker-compose.yml')) as f:
+    names = sorted(set(re.findall(r'\\$\\{([A-Z0-9_]+)', f.read())))
+manifest = {
+    "run_id": os.path.basename(os.path.dirname(os.path.abspath(file))),
+    "created_at": "$(date -Iseconds)",
+    "script": script,
+    "args": args,
+    "cwd": os.getcwd(),
+    "seeds": {
+        "k6": int(os.environ["SEED"]),
+        "api": int(os.environ["RANDOM_SEED"]) if os.environ.get("RANDOM_SEED", "").isdigit() else None,
+    },
+    "repo": {
+        "git_sha": "$(git -C "$project_dir" rev-parse HEAD 2>/dev/null || true)" or None,
+        "patch": os.path.basename(patch) if patch else None,
+    },
+    "env": {name: os.environ[name] for name in names if name in os.environ},
+    "images": {},
+    "replay_of": os.environ.get("REPLAY_OF") or None,
+}
+with open(file, "w") as f:
+    json.dump(manifest, f, indent=2, ensure_ascii=False)
+PYEOF
+}
+
+# Registra a imagem do container de um serviço do compose, sob NOME (default:
+# o serviço; p.ex. api-actix@mimalloc quando o mesmo serviço é reconstruído)
+manifest_image() {
+  local file="$1" service="$2" name="${3:-$2}" cid ref id digest
+  [ -f "$file" ] || return 0
+  cid=$(docker compose ps -aq "$service" 2>/dev/null | head -1 || true)
+  [ -n "$cid" ] || return 0
+  ref=$(docker inspect -f '{{.Config.Image}}' "$cid" 2>/dev/null || true)
+  id=$(docker inspect -f '{{.Image}}' "$cid" 2>/dev/null || true)
+  digest=$(docker image inspect -f '{{join .RepoDigests " "}}' "$id" 2>/dev/null | cut -d' ' -f1 || true)
+  manifest_image_ref "$file" "$name" "$ref" "$id" "$digest"
+}
+
+# Registra uma imagem informada: NOME REF ID [DIGEST] (p.ex. a de um pod no Kubernetes)
+manifest_image_ref() {
+  [ -f "$1" ] || return 0
+  python3 - "$@" <<'PYEOF'
+import json, sys
+file, name, ref, image_id = sys.argv[1:5]
+digest = sys.argv[5] if len(sys.argv) > 5 else ""
+with open(file) as f:
+    manifest = json.load(f)
+manifest["images"][name] = {"image": ref, "id": image_id, "digest": digest or None}
+with open(file, "w") as f:
+    json.dump(manifest, f, indent=2, ensure_ascii=False)
+PYEOF
+}
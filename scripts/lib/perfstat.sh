@@ -0,0 +1,80 @@
+# perfstat.sh — contadores do perf stat para os processos de uma API
+#
+# Throughput diferente entre variantes work-stealing e thread-per-core se
+# explica por trocas de contexto, syscalls e falhas de cache, que o RPS não
+# mostra. Com --perf-stat, `perf stat` conta durante a medição, em todos os
+# processos do container (vistos pelo host):
+#
+#   context-switches         trocas de contexto
+#   raw_syscalls:sys_enter   syscalls (tracepoint; exige perf_event_paranoid
+#                            <= -1 ou root, e tracefs montado)
+#   cache-misses             falhas de cache (contador de hardware)
+#   branch-misses            desvios mal previstos (contador de hardware)
+#
+# Diferente do perf record do --profile, contar não amostra pilhas e o
+# overhead é desprezível, então a contagem roda na própria medição. As threads
+# são as existentes ao anexar: depois do warm-up os pools já estão no ar.
+# Contadores indisponíveis (VMs sem PMU) ficam vazios no resumo.
+#
+# Só vale com as APIs nesta máquina (sem --remote).
+#
+# Uso (depois de definir log/warn/error):
+#   source "$SCRIPT_DIR/lib/perfstat.sh"
+#   perfstat_setup "$PERF_STAT" || exit 1
+#   perfstat_start benchmark_actix arquivo.txt ... perfstat_stop
+#   perfstat_values arquivo.txt    # context_switches,syscalls,cache_misses,branch_misses
+#
+# PERF — comando do perf (default: perf; use "sudo perf" se necessário)
+
+PERF="${PERF:-perf}"
+PERFSTAT_ENABLED=false
+PERFSTAT_PID=""
+PERFSTAT_EVENTS=(context-switches raw_syscalls:sys_enter cache-misses branch-misses)
+
+perfstat_setup() {
+  local enabled="${1:-false}"
+  PERFSTAT_ENABLED=false
+  [ "$enabled" = true ] || return 0
+
+  if [ "${REMOTE_MODE:-off}" != off ]; then
+    error "--perf-stat anexa o perf a processos locais; não vale com --remote"
+    return 1
+  fi
+  if ! $PERF stat -e context-switches -- true &>/dev/null; then
+    error "--perf-stat: '$PERF stat' indisponível (instale o perf ou use PERF=\"sudo perf\")"
+    return 1
+  fi
+  PERFSTAT_ENABLED=true
+}
+
+perfstat_start() {
+  local container="$1" file="$2" pids
+  PERFSTAT_PID=""
+  $PERFSTAT_ENABLED || return 0
+  pids=$(docker top "$container" -eo pid 2>/dev/null | tail -n +2 | paste -sd,)
+  if [ -z "$pids" ]; then
+    warn "--perf-stat: nenhum processo em $container"
+    return 0
+  fi
+  # -x, : CSV (valor,unidade,evento,...); eventos não suportados saem como <not supported>
+  $PERF stat -x, -o "$file" -p "$pids" \
+    $(printf -- '-e %s ' "${PERFSTAT_EVENTS[@]}") &>/dev/null &
+  PERFSTAT_PID=$!
+}
+
+perfstat_stop() {
+  [ -n "$PERFSTAT_PID" ] || return 0
+  # SIGINT faz o perf gravar as contagens antes de sair
+  kill -INT "$PERFSTAT_PID" 2>/dev/null || true
+  wait "$PERFSTAT_PID" 2>/dev/null || true
+  PERFSTAT_PID=""
+}
+
+# Contagens na ordem de PERFSTAT_EVENTS, separadas por vírgula (vazio se ausente)
+perfstat_values() {
+  local file="$1" event out=()
+  for event in "${PERFSTAT_EVENTS[@]}"; do
+    out+=("$(awk -F, -v ev="$event" '$3 == ev && $1 ~ /^[0-9.]+$/ { printf "%.0f", $1 }' "$file" 2>/dev/null)")
+  done
+  (IFS=,; echo "${out[*]}")
+}
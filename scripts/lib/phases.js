@@ -0,0 +1,66 @@
+/**
+ * phases.js — latência por fase da requisição, para os resumos dos scripts k6
+ *
+ * O k6 mede cada requisição em fases (`res.timings`) e as agrega em métricas
+ * próprias; o p99 total sozinho não diz se o tempo foi gasto abrindo conexões
+ * (churn de HTTP/1.1, handshakes TLS) ou no servidor. Este módulo resume as
+ * fases e conta as conexões novas:
+ *
+ *   blocked          espera por um slot de conexão livre, incluindo a
+ *                    resolução de DNS (o k6 não a mede à parte)
+ *   connecting       abertura da conexão TCP
+ *   tls_handshaking  handshake TLS (zero em http://)
+ *   sending          envio da requisição
+ *   waiting          TTFB: do fim do envio ao primeiro byte da resposta
+ *   receiving        leitura do corpo
+ *   duration         sending + waiting + receiving (o http_req_duration)
+ *
+ * Uso: `countConnection(res)` após cada requisição, `phaseSummary(data)` e
+ * `phaseLine(summary)` no handleSummary.
+ */
+
+import { Counter } from 'k6/metrics';
+
+const PHASES = [
+  ['blocked',         'http_req_blocked'],
+  ['connecting',      'http_req_connecting'],
+  ['tls_handshaking', 'http_req_tls_handshaking'],
+  ['sending',         'http_req_sending'],
+  ['waiting',         'http_req_waiting'],
+  ['receiving',       'http_req_receiving'],
+  ['duration',        'http_req_duration'],
+];
+
+const newConnections = new Counter('new_connections');
+
+/** Conta a requisição se ela abriu uma conexão em vez de reusar uma do pool. */
+export function countConnection(res) {
+  if (res.timings.connecting > 0) newConnections.add(1);
+}
+
+/** `{ phases: { fase: { p50_ms, p99_ms, max_ms } }, new_connections, connection_reuse_rate }` */
+export function phaseSummary(data) {
+  const m = data.metrics;
+  const phases = {};
+  for (const [name, metric] of PHASES) {
+    const v = m[metric]?.values ?? {};
+    phases[name] = { p50_ms: v['med'] ?? 0, p99_ms: v['p(99)'] ?? 0, max_ms: v['max'] ?? 0 };
+  }
+  const requests = m.http_reqs?.values?.count ?? 0;
+  const opened = m.new_connections?.values?.count ?? 0;
+  return {
+    phases,
+    new_connections:       opened,
+    connection_reuse_rate: requests > 0 ? 1 - opened / requests : 0,
+  };
+}
+
+/** Linha de stdout com o p99 de cada fase. */
+export function phaseLine(summary) {
+  const p = summary.phases;
+  const ms = (phase) => `${p[phase].p99_ms.toFixed(2)}ms`;
+  return `p99 por fase: bloqueio/dns=${ms('blocked')}  conexão=${ms('connecting')}  ` +
+         `tls=${ms('tls_handshaking')}  envio=${ms('sending')}  ttfb=${ms('waiting')}  ` +
+         `leitura=${ms('receiving')}  conexões novas=${summary.new_connections} ` +
+         `(reuso ${(summary.connection_reuse_rate * 100).toFixed(2)}%)`;
+}
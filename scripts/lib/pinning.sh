@@ -0,0 +1,211 @@
+# pinning.sh — CPUs disjuntas para a API, o banco e o gerador de carga
+#
+# Com tudo na mesma máquina, o k6 e o PostgreSQL disputam núcleos com a API e
+# o resultado varia conforme o escalonador os distribui. Com --pin, cada papel
+# roda num conjunto próprio de CPUs:
+#
+#   api      containers api-* (cpuset via API_CPUSET no docker-compose.yml)
+#   db       container postgres (cpuset via DB_CPUSET)
+#   loadgen  processo do k6 (taskset, ou numactl quando há nó NUMA dedicado)
+#
+# Formatos de --pin:
+#   api=2-5,db=6-7,loadgen=8-11   listas explícitas (papel omitido = sem pinning)
+#   auto                          divide as CPUs online: 1/3 para o k6, do
+#                                 resto 2/3 para a API e 1/3 para o banco
+#   numa                          k6 no último nó NUMA (CPUs e memória), API e
+#                                 banco no nó 0; exige 2+ nós
+#
+# O limite `cpus:` de cada serviço continua valendo: o cpuset diz onde o
+# container roda, a cota diz quanto.
+#
+# Com --remote (lib/remote.sh, carregado antes) API e banco ficam em outro
+# host: só as listas explícitas valem, api/db são conferidas contra as CPUs do
+# host remoto (quando há ssh) e o k6 contra as locais.
+#
+# Uso (depois de definir log/warn/error):
+#   source "$SCRIPT_DIR/lib/pinning.sh"
+#   pin_setup "$PIN" || exit 1
+#   pin_loadgen k6 run ...
+#   pin_json postgres api-actix > cpu_pinning.json
+
+API_CPUSET=""
+DB_CPUSET=""
+LOADGEN_CPUSET=""
+LOADGEN_NODE=""
+PIN_MODE="off"
+
+# "0-3,6" → "0 1 2 3 6"
+pin_expand() {
+  local part out=()
+  IFS=',' read -r -a parts <<< "$1"
+  for part in "${parts[@]}"; do
+    if [[ $part =~ ^([0-9]+)-([0-9]+)$ ]]; then
+      out+=($(seq "${BASH_REMATCH[1]}" "${BASH_REMATCH[2]}"))
+    elif [[ $part =~ ^[0-9]+$ ]]; then
+      out+=("$part")
+    else
+      return 1
+    fi
+  done
+  echo "${out[@]}"
+}
+
+# "0 1 2 3 6" → "0-3,6"
+pin_compact() {
+  local cpu start="" prev="" out=""
+  for cpu in $*; do
+    if [ -n "$prev" ] && [ "$cpu" -eq $((prev + 1)) ]; then
+      prev=$cpu
+      continue
+    fi
+    [ -n "$start" ] && out+="${out:+,}$start$([ "$prev" != "$start" ] && echo "-$prev")"
+    start=$cpu; prev=$cpu
+  done
+  [ -n "$start" ] && out+="${out:+,}$start$([ "$prev" != "$start" ] && echo "-$prev")"
+  echo "$out"
+}
+
+# Nós NUMA (separados por vírgula) das CPUs de uma lista
+pin_nodes() {
+  local cpu node nodes=()
+  for cpu in $(pin_expand "$1"); do
+    for node in /sys/devices/system/cpu/cpu"$cpu"/node*; do
+      [ -e "$node" ] && nodes+=("${node##*node}")
+    done
+  done
+  [ ${#nodes[@]} -gt 0 ] && printf '%s\n' "${nodes[@]}" | sort -nu | paste -sd,
+  return 0
+}
+
+pin_auto() {
+  local cpus=($(pin_expand "$(cat /sys/devices/system/cpu/online)"))
+  local n=${#cpus[@]}
+  if [ "$n" -lt 3 ]; then
+    error "--pin auto precisa de 3+ CPUs (há $n)"
+    return 1
+  fi
+  local loadgen=$(( n / 3 ))
+  local db=$(( (n - loadgen) / 3 )); [ "$db" -lt 1 ] && db=1
+  local api=$(( n - loadgen - db ))
+  API_CPUSET=$(pin_compact "${cpus[@]:0:api}")
+  DB_CPUSET=$(pin_compact "${cpus[@]:api:db}")
+  LOADGEN_CPUSET=$(pin_compact "${cpus[@]:api+db}")
+}
+
+pin_numa() {
+  local nodes=($(ls -d /sys/devices/system/node/node[0-9]* 2>/dev/null | sed 's#.*/node##' | sort -n))
+  if [ ${#nodes[@]} -lt 2 ]; then
+    error "--pin numa precisa de 2+ nós NUMA (há ${#nodes[@]})"
+    return 1
+  fi
+  local first=($(pin_expand "$(cat /sys/devices/system/node/node"${nodes[0]}"/cpulist)"))
+  local n=${#first[@]}
+  if [ "$n" -lt 2 ]; then
+    error "--pin numa precisa de 2+ CPUs no nó ${nodes[0]}"
+    return 1
+  fi
+  local db=$(( n / 3 )); [ "$db" -lt 1 ] && db=1
+  local api=$(( n - db ))
+  API_CPUSET=$(pin_compact "${first[@]:0:api}")
+  DB_CPUSET=$(pin_compact "${first[@]:api}")
+  LOADGEN_NODE="${nodes[-1]}"
+  LOADGEN_CPUSET=$(cat /sys/devices/system/node/node"$LOADGEN_NODE"/cpulist)
+}
+
+# Valida --pin e exporta API_CPUSET / DB_CPUSET para o docker compose
+pin_setup() {
+  local spec="${1:-}" entry role list remote="${REMOTE_MODE:-off}"
+  if [ "$remote" != off ] && { [ "$spec" = auto ] || [ "$spec" = numa ]; }; then
+    error "--pin $spec não vale com --remote; use listas (api=...,db=...,loadgen=...)"
+    return 1
+  fi
+  case "$spec" in
+    ""|off) return 0 ;;
+    auto)   PIN_MODE=auto; pin_auto || return 1 ;;
+    numa)   PIN_MODE=numa; pin_numa || return 1 ;;
+    *)
+      PIN_MODE=manual
+      # A vírgula separa papéis e também itens da lista ("api=0-1,4,db=5")
+      for entry in $(echo "$spec" | sed 's/,\([a-z]\)/ \1/g'); do
+        role="${entry%%=*}"; list="${entry#*=}"
+        if ! pin_expand "$list" >/dev/null; then
+          error "--pin: lista de CPUs inválida para $role: '$list'"
+          return 1
+        fi
+        case "$role" in
+          api)     API_CPUSET="$list" ;;
+          db)      DB_CPUSET="$list" ;;
+          loadgen) LOADGEN_CPUSET="$list" ;;
+          *) error "--pin: papel desconhecido '$role' (use api, db, loadgen, auto ou numa)"; return 1 ;;
+        esac
+      done
+      ;;
+  esac
+
+  local local_online target_online seen=" " cpu online
+  local_online=" $(pin_expand "$(cat /sys/devices/system/cpu/online)") "
+  case "$remote" in
+    off) target_online="$local_online" ;;
+    ssh) target_online=" $(pin_expand "$(remote_sh cat /sys/devices/system/cpu/online)") " ;;
+    *)   target_online="" ;;   # sem ssh, o docker recusa um cpuset inválido ao subir
+  esac
+  for role in API DB LOADGEN; do
+    list="${role}_CPUSET"
+    online=$([ "$role" = LOADGEN ] && echo "$local_online" || echo "$target_online")
+    # Em hosts diferentes, o k6 não disputa CPUs com API e banco
+    [ "$role" = LOADGEN ] && [ "$remote" != off ] && seen=" "
+    for cpu in $(pin_expand "${!list}"); do
+      if [ -n "$online" ] && [[ $online != *" $cpu "* ]]; then
+        error "--pin: CPU $cpu (${role,,}) não está online"
+        return 1
+      fi
+      if [[ $seen == *" $cpu "* ]]; then
+        error "--pin: CPU $cpu aparece em mais de um papel — os conjuntos precisam ser disjuntos"
+        return 1
+      fi
+      seen+="$cpu "
+    done
+  done
+  if [ -n "$LOADGEN_CPUSET" ] && ! command -v taskset &>/dev/null; then
+    error "--pin: taskset não encontrado (pacote util-linux)"
+    return 1
+  fi
+
+  export API_CPUSET DB_CPUSET
+  log "CPUs: api=${API_CPUSET:-livre} db=${DB_CPUSET:-livre} loadgen=${LOADGEN_CPUSET:-livre}${LOADGEN_NODE:+ (nó NUMA $LOADGEN_NODE)}"
+}
+
+# Roda o gerador de carga nas CPUs reservadas para ele
+pin_loadgen() {
+  if [ -n "$LOADGEN_NODE" ] && command -v numactl &>/dev/null; then
+    numactl --cpunodebind="$LOADGEN_NODE" --membind="$LOADGEN_NODE" "$@"
+  elif [ -n "$LOADGEN_CPUSET" ]; then
+    taskset -c "$LOADGEN_CPUSET" "$@"
+  else
+    "$@"
+  fi
+}
+
+# Objeto JSON com o pinning pedido e, para cada serviço do compose passado,
+# o cpuset efetivo do container (HostConfig.CpusetCpus; "" = sem pinning)
+pin_json() {
+  local service id effective sep="" api_nodes="" db_nodes=""
+  # Os nós NUMA vêm do sysfs local; com --remote, só os do k6 fazem sentido
+  if [ "${REMOTE_MODE:-off}" = off ]; then
+    api_nodes=$(pin_nodes "$API_CPUSET"); db_nodes=$(pin_nodes "$DB_CPUSET")
+  fi
+  echo "{"
+  echo "  \"mode\": \"$PIN_MODE\","
+  echo "  \"api\":     {\"cpus\": \"$API_CPUSET\", \"numa_nodes\": \"$api_nodes\"},"
+  echo "  \"db\":      {\"cpus\": \"$DB_CPUSET\", \"numa_nodes\": \"$db_nodes\"},"
+  echo "  \"loadgen\": {\"cpus\": \"$LOADGEN_CPUSET\", \"numa_nodes\": \"$(pin_nodes "$LOADGEN_CPUSET")\", \"membind\": \"$LOADGEN_NODE\"},"
+  echo -n "  \"containers\": {"
+  for service in "$@"; do
+    id=$(docker compose ps -q "$service" 2>/dev/null || true)
+    effective=$([ -n "$id" ] && docker inspect -f '{{.HostConfig.CpusetCpus}}' "$id" 2>/dev/null || true)
+    echo -n "$sep\"$service\": \"$effective\""
+    sep=", "
+  done
+  echo "}"
+  echo "}"
+}
@@ -0,0 +1,181 @@
+# remote.sh — API e banco num host remoto, carga gerada localmente
+#
+# Com o k6 na mesma máquina, a rede é o loopback e o gerador de carga disputa o
+# host com o servidor. Com --remote, o docker compose sobe os containers em
+# outro host e o k6 continua local, atravessando a rede de verdade:
+#
+#   ssh://[user@]host[:porta]   docker via SSH (DOCKER_HOST); o ssh também lê o
+#                               relógio, o RAPL e a CPU/memória do host remoto
+#   context:NOME                contexto do docker (DOCKER_CONTEXT, p.ex. tcp://
+#                               com TLS); sem ssh não há RAPL nem amostragem do
+#                               host, só o `docker stats` dos containers
+#
+# As APIs são acessadas em http://<TARGET_HOST>:<porta>; TARGET_HOST vem do
+# endereço do docker e pode ser trocado com --target-host (p.ex. o IP da rede
+# de benchmark, diferente do de gerência).
+#
+# Relógio: os dois hosts não têm o mesmo relógio, então `remote_clock_offset`
+# estima a diferença (remoto − local) pelo ponto médio da ida e volta, ficando
+# com a amostra de menor RTT; o erro é no máximo RTT/2. As amostras do host
+# remoto são convertidas para o relógio local antes de gravadas.
+#
+# Uso (depois de definir log/warn/error):
+#   source "$SCRIPT_DIR/lib/remote.sh"
+#   remote_setup "$REMOTE" "$TARGET_HOST" || exit 1
+#   remote_clock_offset > "$RESULTS_DIR/clock_offset.json"   # com o postgres no ar
+#   remote_sample_start arquivo.csv ... remote_sample_stop
+
+REMOTE_MODE="off"     # off | ssh | context
+REMOTE_SSH=()         # comando ssh para o host remoto (modo ssh)
+CLOCK_OFFSET_MS=0
+REMOTE_SAMPLER_PID=""
+
+SSH_OPTS=(-o BatchMode=yes -o ControlMaster=auto -o "ControlPath=/tmp/bench-ssh-%C" -o ControlPersist=10m)
+
+remote_setup() {
+  local spec="${1:-}" override="${2:-}" host endpoint
+  case "$spec" in
+    "")
+      TARGET_HOST="${override:-localhost}"
+      return 0
+      ;;
+    ssh://*)
+      REMOTE_MODE=ssh
+      export DOCKER_HOST="$spec"
+      # ssh://user@host:2222 → ssh -p 2222 user@host
+      host="${spec#ssh://}"
+      if [[ $host =~ ^(.*):([0-9]+)$ ]]; then
+        REMOTE_SSH=(ssh "${SSH_OPTS[@]}" -p "${BASH_REMATCH[2]}" "${BASH_REMATCH[1]}")
+        host="${BASH_REMATCH[1]}"
+      else
+        REMOTE_SSH=(ssh "${SSH_OPTS[@]}" "$host")
+      fi
+      TARGET_HOST="${override:-${host#*@}}"
+      if ! "${REMOTE_SSH[@]}" true; then
+        error "--remote: ssh para $spec falhou (precisa de acesso por chave, sem senha)"
+        return 1
+      fi
+      ;;
+    context:*)
+      REMOTE_MODE=context
+      export DOCKER_CONTEXT="${spec#context:}"
+      if ! endpoint=$(docker context inspect "$DOCKER_CONTEXT" -f '{{.Endpoints.docker.Host}}' 2>/dev/null); then
+        error "--remote: contexto do docker '$DOCKER_CONTEXT' não existe (docker context ls)"
+        return 1
+      fi
+      # tcp://host:2376, ssh://user@host → host
+      host="${endpoint#*://}"; host="${host#*@}"; host="${host%%[:/]*}"
+      TARGET_HOST="${override:-$host}"
+      if [ -z "$TARGET_HOST" ]; then
+        error "--remote: não deu para tirar o host de '$endpoint'; informe --target-host"
+        return 1
+      fi
+      ;;
+    *)
+      error "--remote: use ssh://[user@]host[:porta] ou context:NOME (recebido '$spec')"
+      return 1
+      ;;
+  esac
+  if ! docker info &>/dev/null; then
+    error "--remote: docker remoto inacessível em $spec"
+    return 1
+  fi
+  log "Alvo remoto: docker em $spec, APIs em $TARGET_HOST"
+}
+
+# Executa um comando no host remoto (só no modo ssh)
+remote_sh() {
+  "${REMOTE_SSH[@]}" "$@"
+}
+
+# Relógio do alvo em ms: pelo ssh ou, sem ele, pelo postgres do compose
+remote_now_ms() {
+  if [ "$REMOTE_MODE" = ssh ]; then
+    remote_sh date +%s%3N
+  else
+    docker compose exec -T postgres psql -U benchmark -d benchmark -Atc \
+      "SELECT (extract(epoch FROM clock_timestamp()) * 1000)::bigint"
+  fi
+}
+
+# Estima CLOCK_OFFSET_MS (remoto − local) e imprime o JSON da estimativa
+remote_clock_offset() {
+  local method=none best_rtt="" t0 t1 r rtt i
+  if [ "$REMOTE_MODE" != off ]; then
+    method=$([ "$REMOTE_MODE" = ssh ] && echo ssh || echo postgres)
+    for i in 1 2 3 4 5 6 7; do
+      t0=$(date +%s%3N)
+      r=$(remote_now_ms 2>/dev/null | tr -d '[:space:]') || continue
+      t1=$(date +%s%3N)
+      [[ $r =~ ^[0-9]+$ ]] || continue
+      rtt=$(( t1 - t0 ))
+      if [ -z "$best_rtt" ] || [ "$rtt" -lt "$best_rtt" ]; then
+        best_rtt=$rtt
+        CLOCK_OFFSET_MS=$(( r - (t0 + t1) / 2 ))
+      fi
+    done
+    [ -z "$best_rtt" ] && warn "Não foi possível ler o relógio do alvo — offset assumido 0"
+  fi
+  cat <<EOF
+{
+  "remote":     "${DOCKER_HOST:-${DOCKER_CONTEXT:-}}",
+  "method":     "$method",
+  "offset_ms":  $CLOCK_OFFSET_MS,
+  "rtt_ms":     ${best_rtt:-null},
+  "error_ms":   $([ -n "$best_rtt" ] && echo $(( (best_rtt + 1) / 2 )) || echo null)
+}
+EOF
+}
+
+# Amostra CPU, memória e RAPL do host remoto a cada segundo, em background:
+# timestamp (ms, relógio local),cpu_pct,mem_used_mb,rapl_uj
+remote_sample_start() {
+  local file="$1" rapl="${2:-/sys/class/powercap/intel-rapl/intel-rapl:0/energy_uj}"
+  REMOTE_SAMPLER_PID=""
+  [ "$REMOTE_MODE" = ssh ] || return 0
+  echo "timestamp_ms,cpu_pct,mem_used_mb,rapl_uj" > "$file"
+  (
+  remote_sh sh -s -- "$rapl" <<'SHEOF' \
+    | while IFS=, read -r ts rest; do echo "$(( ts - CLOCK_OFFSET_MS )),$rest"; done >> "$file"
+rapl_path=$1
+prev_total=0; prev_idle=0
+while :; do
+  set -- $(head -1 /proc/stat); shift
+  idle=$(( $4 + $5 )); total=0
+  for v in "$@"; do total=$(( total + v )); done
+  mem=$(awk '/^MemTotal/{t=$2} /^MemAvailable/{a=$2} END{printf "%d", (t-a)/1024}' /proc/meminfo)
+  rapl=$(cat "$rapl_path" 2>/dev/null || echo 0)
+  if [ "$prev_total" -gt 0 ] && [ "$total" -gt "$prev_total" ]; then
+    busy=$(( 100 * ((total - prev_total) - (idle - prev_idle)) / (total - prev_total) ))
+    echo "$(date +%s%3N),$busy,$mem,$rapl"
+  fi
+  prev_total=$total; prev_idle=$idle
+  sleep 1
+done
+SHEOF
+  ) &
+  REMOTE_SAMPLER_PID=$!
+}
+
+remote_sample_stop() {
+  [ -n "$REMOTE_SAMPLER_PID" ] || return 0
+  # Derruba o subshell e o pipeline (ssh + leitura); o loop remoto morre com o SIGPIPE
+  pkill -P "$REMOTE_SAMPLER_PID" 2>/dev/null || true
+  kill "$REMOTE_SAMPLER_PID" 2>/dev/null || true
+  wait "$REMOTE_SAMPLER_PID" 2>/dev/null || true
+  REMOTE_SAMPLER_PID=""
+}
+
+# Hardware do host remoto para a configuração do experimento; `null` sem ssh
+remote_system_json() {
+  if [ "$REMOTE_MODE" != ssh ]; then
+    echo null
+    return 0
+  fi
+  remote_sh sh -s <<'SHEOF'
+printf '{"hostname": "%s", "kernel": "%s", "cpu_model": "%s", "cpu_cores": %s, "ram_gb": %s}\n' \
+  "$(hostname)" "$(uname -r)" \
+  "$(grep 'model name' /proc/cpuinfo | head -1 | cut -d: -f2 | sed 's/^ *//')" \
+  "$(nproc)" "$(awk '/MemTotal/{printf "%.1f", $2/1024/1024}' /proc/meminfo)"
+SHEOF
+}
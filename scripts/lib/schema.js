@@ -0,0 +1,158 @@
+/**
+ * schema.js — validação amostrada das respostas contra a spec OpenAPI
+ *
+ * Um servidor rápido que devolve o corpo errado não pode vencer o benchmark.
+ * Com a spec carregada no setup (`loadSpec`), `schemaValidator` confere 1 em
+ * cada SCHEMA_SAMPLE respostas JSON contra o schema documentado para o método,
+ * a rota e o status, com as mesmas regras do crate contract-tests
+ * (src/schema.rs): `$ref`, `type` (com `nullable` da 3.0), `properties`,
+ * `required`, `items`, `oneOf`/`anyOf`/`allOf`, `enum`, e propriedades não
+ * declaradas contam como divergência. Rotas e status fora da spec não são
+ * conferidos.
+ *
+ * Variáveis de ambiente:
+ *   SPEC_URL      — spec usada (default: <API_URL>/openapi.json)
+ *   SPEC_FILE     — spec salva em arquivo (caminho absoluto), no lugar de
+ *                   SPEC_URL; com a do Actix (`curl localhost:3004/openapi.json`)
+ *                   valida uma API que não publica a sua
+ *   SCHEMA_SAMPLE — confere 1 a cada N respostas (default: 100; 0 desliga)
+ */
+
+import http from 'k6/http';
+import { Counter } from 'k6/metrics';
+
+const SCHEMA_SAMPLE = parseInt(__ENV.SCHEMA_SAMPLE || '100', 10);
+// open() só existe no contexto de inicialização
+const SPEC_FILE = __ENV.SPEC_FILE ? open(__ENV.SPEC_FILE) : null;
+
+const checked = new Counter('schema_checked');
+const mismatched = new Counter('schema_mismatches');
+
+/** Spec para o `setup()` devolver aos VUs; `null` se indisponível ou desligada. */
+export function loadSpec(apiUrl) {
+  if (SCHEMA_SAMPLE <= 0) return null;
+  if (SPEC_FILE) return JSON.parse(SPEC_FILE);
+  const url = __ENV.SPEC_URL || `${apiUrl}/openapi.json`;
+  const res = http.get(url, { tags: { name: 'spec' } });
+  try {
+    if (res.status === 200 && res.json().paths) return res.json();
+  } catch (_) { /* cai no aviso abaixo */ }
+  console.warn(`[schema] spec indisponível em ${url} (status ${res.status}) — validação desligada`);
+  return null;
+}
+
+function deref(schema, components) {
+  const ref = schema && schema.$ref;
+  if (typeof ref !== 'string') return schema;
+  const target = components?.schemas?.[ref.split('/').pop()];
+  return target ? deref(target, components) : schema;
+}
+
+function types(schema) {
+  const t = schema.type;
+  const out = typeof t === 'string' ? [t] : Array.isArray(t) ? t.slice() : [];
+  if (schema.nullable === true) out.push('null');
+  return out;
+}
+
+function typeOf(value) {
+  if (value === null) return 'null';
+  if (Array.isArray(value)) return 'array';
+  if (typeof value === 'number') return Number.isInteger(value) ? 'integer' : 'number';
+  return typeof value; // boolean, string, object
+}
+
+/** Acrescenta a `errors` uma mensagem por violação; `at` é o caminho JSON. */
+export function validate(schema, components, value, at, errors) {
+  schema = deref(schema, components) || {};
+
+  for (const [keyword, needOne] of [['oneOf', true], ['anyOf', false]]) {
+    if (!Array.isArray(schema[keyword])) continue;
+    const matching = schema[keyword].filter((option) => {
+      const e = [];
+      validate(option, components, value, at, e);
+      return e.length === 0;
+    }).length;
+    if (matching === 0 || (needOne && matching > 1)) {
+      errors.push(`${at}: casa com ${matching} das alternativas de ${keyword}`);
+    }
+  }
+  for (const part of schema.allOf || []) validate(part, components, value, at, errors);
+
+  const declared = types(schema);
+  const actual = typeOf(value);
+  if (declared.length > 0 && !declared.includes(actual) && !(actual === 'integer' && declared.includes('number'))) {
+    errors.push(`${at}: esperado ${declared.join(' | ')}, veio ${actual}`);
+    return;
+  }
+  if (Array.isArray(schema.enum) && !schema.enum.some((v) => JSON.stringify(v) === JSON.stringify(value))) {
+    errors.push(`${at}: ${JSON.stringify(value)} fora de ${JSON.stringify(schema.enum)}`);
+  }
+
+  if (actual === 'object' && schema.properties) {
+    for (const name of schema.required || []) {
+      if (!(name in value)) errors.push(`${at}.${name}: propriedade obrigatória ausente`);
+    }
+    for (const [name, v] of Object.entries(value)) {
+      if (schema.properties[name]) validate(schema.properties[name], components, v, `${at}.${name}`, errors);
+      else errors.push(`${at}.${name}: propriedade não declarada na spec`);
+    }
+  } else if (actual === 'array' && schema.items) {
+    value.forEach((item, i) => validate(schema.items, components, item, `${at}[${i}]`, errors));
+  }
+}
+
+/** `[{ method, regex, literal, responses: { status: schema } }]`, rotas literais primeiro. */
+function compileRoutes(spec) {
+  const routes = [];
+  for (const [path, item] of Object.entries(spec.paths)) {
+    for (const [method, op] of Object.entries(item)) {
+      const responses = {};
+      for (const [status, resp] of Object.entries(op.responses || {})) {
+        const schema = resp?.content?.['application/json']?.schema;
+        if (schema) responses[status] = schema;
+      }
+      const pattern = path.replace(/[.*+?^$()|[\]\\]/g, '\\$&').replace(/\{[^}]+\}/g, '[^/]+');
+      routes.push({ method: method.toUpperCase(), regex: new RegExp(`^${pattern}$`), literal: !path.includes('{'), responses });
+    }
+  }
+  return routes.sort((a, b) => Number(b.literal) - Number(a.literal));
+}
+
+/**
+ * `(res) => null | mensagem` para o `validate` de `recordOutcome`: confere
+ * uma amostra das respostas contra a spec. Sem spec, sempre `null`.
+ */
+export function schemaValidator(spec) {
+  if (!spec) return () => null;
+  const routes = compileRoutes(spec);
+  let seen = 0;
+  return (res) => {
+    if (++seen % SCHEMA_SAMPLE !== 0) return null;
+    const url = res.request?.url || res.url;
+    const path = url.replace(/^[a-z]+:\/\/[^/]+/i, '').split('?')[0];
+    const route = routes.find((r) => r.method === res.request?.method && r.regex.test(path));
+    const schema = route?.responses[String(res.status)];
+    if (!schema) return null;
+
+    checked.add(1);
+    let body;
+    try { body = res.json(); } catch (_) {
+      mismatched.add(1);
+      return 'corpo não é JSON';
+    }
+    const errors = [];
+    validate(schema, spec.components, body, '$', errors);
+    if (errors.length === 0) return null;
+    mismatched.add(1);
+    return errors.slice(0, 5).join('; ');
+  };
+}
+
+/** `{ schema_checked, schema_mismatches }` para o resumo. */
+export function schemaSummary(data) {
+  return {
+    schema_checked:    data.metrics.schema_checked?.values?.count ?? 0,
+    schema_mismatches: data.metrics.schema_mismatches?.values?.count ?? 0,
+  };
+}
@@ -0,0 +1,21 @@
+/**
+ * seed.js — sequência pseudoaleatória reproduzível nos scripts k6
+ *
+ * Os scripts sorteiam endpoint, ids e transições de sessão com Math.random.
+ * Com SEED definido, cada VU recebe a semente SEED + número do VU, e a
+ * sequência de sorteios de cada VU se repete entre execuções (a alocação de
+ * iterações aos VUs nos executores de taxa fixa depende do tempo, então o
+ * conjunto total de requisições é o mesmo em distribuição, não em ordem).
+ * Os orquestradores sorteiam SEED quando não informado e o gravam em
+ * run_manifest.json (ver scripts/lib/manifest.sh).
+ *
+ * Uso: `import './lib/seed.js';` antes de qualquer sorteio.
+ */
+
+import { randomSeed } from 'k6';
+
+export const SEED = parseInt(__ENV.SEED || '0', 10);
+
+if (SEED) {
+  randomSeed(SEED + __VU);
+}
@@ -0,0 +1,114 @@
+# table.sh — users com N linhas, para medir a latência em função do tamanho
+#
+# Com 10.000 linhas a tabela e os índices cabem no shared_buffers e o
+# benchmark mede o framework; com 1M e 100M o B-tree ganha níveis, o cache
+# deixa de conter tudo e a mesma consulta passa a ir ao disco. Com
+# --table-rows N, a tabela é recriada com N linhas antes das medições:
+#
+#   1. TRUNCATE ... RESTART IDENTITY (ids de 1 a N, como os sorteios do k6);
+#   2. os índices secundários e o trigger do feed de mudanças (migração 009,
+#      se aplicada) saem durante o INSERT (o seed de 100M leva minutos assim,
+#      e horas sem isso);
+#   3. o INSERT repete o seed do init.sql com generate_series(1, N);
+#   4. os índices voltam com maintenance_work_mem maior (com a migração 006,
+#      depois dos pontos do seed em location), o user_stats é recalculado e
+#      ANALYZE atualiza as estatísticas do planejador.
+#
+# A chave primária fica (os INSERTs a mantêm em ordem, sem page splits). O
+# tamanho em disco (tabela + índices + TOAST) vai para o resumo junto com N.
+# Acima de ~2 bilhões de ids use o Actix com bigint-keys e
+# scripts/migrations/003-bigint-keys.sql.
+#
+# Uso (depois de definir log/warn/error, com o postgres no ar):
+#   source "$SCRIPT_DIR/lib/table.sh"
+#   table_seed 1000000 || exit 1
+#   table_bytes             # tabela + índices + TOAST, em bytes
+#
+# SEED_WORK_MEM — maintenance_work_mem ao recriar os índices (default: 1GB)
+
+SEED_WORK_MEM="${SEED_WORK_MEM:-1GB}"
+
+table_psql() {
+  docker compose exec -T postgres psql -U benchmark -d benchmark -v ON_ERROR_STOP=1 -qAt "$@"
+}
+
+table_seed() {
+  local rows="$1"
+  log "Recriando users com $rows linhas..."
+  if ! table_psql -v rows="$rows" -v work_mem="$SEED_WORK_MEM" <<'SQL'
+BEGIN;
+TRUNCATE users RESTART IDENTITY;
+-- Com a migração 009: o trigger do feed de mudanças
+DO $$
+BEGIN
+    IF EXISTS (SELECT 1 FROM pg_trigger
+                WHERE tgrelid = 'users'::regclass AND tgname = 'users_notify_change') THEN
+        ALTER TABLE users DISABLE TRIGGER users_notify_change;
+    END IF;
+END
+$$;
+DROP INDEX IF EXISTS users_email_key, idx_users_email, idx_users_updated_at,
+                     idx_users_tenant_id, idx_users_tenant_updated_at, idx_users_metadata,
+                     idx_users_tags, idx_users_location;
+
+INSERT INTO users (name, email, age, tenant_id)
+SELECT
+    (ARRAY[
+        'Alice','Bob','Carlos','Diana','Eduardo','Fernanda','Gabriel','Helena',
+        'Igor','Julia','Kevin','Laura','Marcos','Natalia','Otto','Paula',
+        'Rafael','Sofia','Thiago','Ursula','Victor','Wendy','Xander','Yasmin','Zeca'
+    ])[ 1 + (i % 25) ] || ' ' ||
+    (ARRAY[
+        'Silva','Santos','Oliveira','Souza','Costa','Ferreira','Alves','Pereira',
+        'Lima','Carvalho','Melo','Ribeiro','Almeida','Nascimento','Gomes'
+    ])[ 1 + (i % 15) ],
+    'user' || i || '@' ||
+    (ARRAY['gmail.com','outlook.com','yahoo.com','hotmail.com','benchmark.dev'])
+    [ 1 + (i % 5) ],
+    18 + (i % 62),
+    'tenant-' || (i % 10)
+FROM generate_series(1, :rows) AS s(i);
+
+SET LOCAL maintenance_work_mem = :'work_mem';
+-- Com a migração 006 (PostGIS): os pontos do seed, antes dos demais índices
+-- (o UPDATE de todas as linhas não os mantém), e o índice GiST
+DO $$
+BEGIN
+    IF to_regprocedure('users_seed_location(bigint)') IS NOT NULL THEN
+        UPDATE users u SET location = users_seed_location(s.n)
+        FROM (SELECT id, row_number() OVER (ORDER BY id) AS n FROM users) s
+        WHERE u.id = s.id;
+        CREATE INDEX idx_users_location ON users USING GIST (location);
+    END IF;
+END
+$$;
+CREATE UNIQUE INDEX users_email_key ON users (lower(email));
+CREATE INDEX idx_users_email ON users(email);
+CREATE INDEX idx_users_updated_at ON users(updated_at);
+CREATE INDEX idx_users_tenant_id ON users(tenant_id, id);
+CREATE INDEX idx_users_tenant_updated_at ON users(tenant_id, updated_at);
+CREATE INDEX idx_users_metadata ON users USING GIN (metadata jsonb_path_ops);
+CREATE INDEX idx_users_tags ON users USING GIN (tags);
+DO $$
+BEGIN
+    IF EXISTS (SELECT 1 FROM pg_trigger
+                WHERE tgrelid = 'users'::regclass AND tgname = 'users_notify_change') THEN
+        ALTER TABLE users ENABLE TRIGGER users_notify_change;
+    END IF;
+END
+$$;
+COMMIT;
+
+REFRESH MATERIALIZED VIEW user_stats;
+VACUUM ANALYZE users;
+SQL
+  then
+    error "Falha ao recriar users com $rows linhas"
+    return 1
+  fi
+  success "users com $rows linhas ($(table_bytes | numfmt --to=iec) em disco)"
+}
+
+table_bytes() {
+  table_psql -c "SELECT pg_total_relation_size('users')"
+}
@@ -11,6 +11,8 @@
  *   API_URL    — URL base da API (default: http://localhost:3001)
  *   TARGET_RPS — requisições/segundo desejadas (default: 200)
  *   DURATION   — duração da fase de carga (default: 120s)
+ *   SEED       — semente dos sorteios, para repetir a execução (lib/seed.js)
+ *   USER_IDS   — PUT /users/:id sorteia ids de 1 a USER_IDS (default: 10000)
  *
  * Distribuição de endpoints (~70% leitura, ~30% escrita):
  *   35% GET  /db              — single random user (DB read)
@@ -24,6 +26,7 @@
 import http from 'k6/http';
 import { check } from 'k6';
 import { Counter, Rate, Trend } from 'k6/metrics';
+import './lib/seed.js';
 
 // ---------------------------------------------------------------------------
 // Configuração
@@ -32,6 +35,7 @@ import { Counter, Rate, Trend } from 'k6/metrics';
 const API_URL    = __ENV.API_URL    || 'http://localhost:3001';
 const TARGET_RPS = parseInt(__ENV.TARGET_RPS || '200', 10);
 const DURATION   = __ENV.DURATION   || '120s';
+const USER_IDS   = parseInt(__ENV.USER_IDS || '10000', 10);
 
 export const options = {
   scenarios: {
@@ -184,7 +188,7 @@ export default function () {
       break;
     }
     case 'update': {
-      const id = randomInt(1, 10000);
+      const id = randomInt(1, USER_IDS);
       const payload = JSON.stringify({
         name: randomName(),
         age:  randomInt(18, 65),
@@ -0,0 +1,275 @@
+/**
+ * load-test-latency.js — latência sob carga com taxa de chegada fixa (open-loop)
+ *
+ * Ao contrário do load-test.js (closed-loop: cada VU só dispara a próxima
+ * requisição quando a anterior termina), aqui as requisições chegam a uma taxa
+ * fixa, independentemente de quanto o servidor demora para responder — no
+ * estilo do wrk2. Assim o tempo em fila entra na latência medida e os
+ * percentis altos (p99.9) deixam de ser mascarados pela omissão coordenada.
+ *
+ * Uso:
+ *   k6 run -e API_URL=http://localhost:3004 \
+ *          -e TARGET_RPS=1400 \
+ *          -e DURATION=120s \
+ *          -e SUMMARY_FILE=results/latency_actix.json \
+ *          scripts/load-test-latency.js
+ *
+ * Variáveis de ambiente:
+ *   API_URL      — URL base da API (default: http://localhost:3001)
+ *   TARGET_RPS   — taxa de chegada fixa em req/s (default: 200)
+ *   DURATION     — duração da medição (default: 120s)
+ *   MAX_VUS      — teto de VUs para sustentar a taxa (default: TARGET_RPS × 5)
+ *   REQ_TIMEOUT  — timeout por requisição (default: 60s, o mesmo do k6)
+ *   SUMMARY_FILE — caminho do JSON compacto de saída (opcional)
+ *   ERROR_BUDGET — fração de falhas acima da qual o teste falha (default: 0.01)
+ *   SPEC_URL, SPEC_FILE, SCHEMA_SAMPLE — validação amostrada contra a spec
+ *                  OpenAPI (default: 1 em 100 contra <API_URL>/openapi.json)
+ *   SEED         — semente dos sorteios, para repetir a execução (lib/seed.js)
+ *   USER_IDS     — PUT /users/:id sorteia ids de 1 a USER_IDS (default: 10000, o seed
+ *                  do init.sql; o run-latency.sh passa o --table-rows)
+ *
+ * Se o k6 não tiver VUs livres para disparar uma requisição no instante
+ * programado, ela é contada em `dropped_iterations`. Um valor > 0 indica que
+ * o alvo não sustentou a taxa e os percentis devem ser descartados.
+ *
+ * Distribuição de endpoints: a mesma do load-test-energy.js.
+ *
+ * O resumo traz também o p50/p99 de cada fase da requisição (espera por
+ * conexão, conexão, TLS, TTFB...) e quantas conexões foram abertas; ver
+ * lib/phases.js. As falhas são classificadas (conexão recusada, timeout, 5xx,
+ * corpo que não é JSON...) e contadas por classe, e o teste falha acima de
+ * ERROR_BUDGET (default 1%); ver lib/errors.js. Uma amostra das respostas é
+ * conferida contra a spec OpenAPI (SPEC_URL, SCHEMA_SAMPLE; ver lib/schema.js),
+ * e um corpo divergente conta como falha.
+ */
+
+import http from 'k6/http';
+import { check } from 'k6';
+import { Counter, Rate } from 'k6/metrics';
+import { countConnection, phaseSummary, phaseLine } from './lib/phases.js';
+import { recordOutcome, errorThresholds, errorSummary, errorLine } from './lib/errors.js';
+import { loadSpec, schemaValidator, schemaSummary } from './lib/schema.js';
+import './lib/seed.js';
+
+// ---------------------------------------------------------------------------
+// Configuração
+// ---------------------------------------------------------------------------
+
+const API_URL      = __ENV.API_URL      || 'http://localhost:3001';
+const TARGET_RPS   = parseInt(__ENV.TARGET_RPS || '200', 10);
+const DURATION     = __ENV.DURATION     || '120s';
+const MAX_VUS      = parseInt(__ENV.MAX_VUS || String(TARGET_RPS * 5), 10);
+const SUMMARY_FILE = __ENV.SUMMARY_FILE || '';
+const REQ_TIMEOUT  = __ENV.REQ_TIMEOUT  || '60s';
+const USER_IDS     = parseInt(__ENV.USER_IDS || '10000', 10);
+
+export const options = {
+  scenarios: {
+    fixed_rate: {
+      executor:        'constant-arrival-rate',
+      rate:            TARGET_RPS,
+      timeUnit:        '1s',
+      duration:        DURATION,
+      preAllocatedVUs: Math.ceil(TARGET_RPS * 0.5),
+      maxVUs:          MAX_VUS,
+    },
+  },
+  // Acima de ERROR_BUDGET (lib/errors.js) o k6 termina com código 99
+  thresholds: errorThresholds,
+  // p(99.9) não faz parte do resumo padrão do k6
+  summaryTrendStats: ['avg', 'min', 'med', 'max', 'p(90)', 'p(99)', 'p(99.9)'],
+};
+
+const successRate = new Rate('success_rate');
+
+// Distribuição de respostas por classe; status 0 = timeout ou falha de conexão
+const status2xx = new Counter('status_2xx');
+const status4xx = new Counter('status_4xx');
+const status5xx = new Counter('status_5xx');
+const status0   = new Counter('status_0');
+
+function countStatus(status) {
+  if (status === 0)       status0.add(1);
+  else if (status >= 500) status5xx.add(1);
+  else if (status >= 400) status4xx.add(1);
+  else                    status2xx.add(1);
+}
+
+// ---------------------------------------------------------------------------
+// Helpers para gerar dados de escrita
+// ---------------------------------------------------------------------------
+
+let writeCounter = 0;
+function uniqueEmail() {
+  writeCounter++;
+  return `lat_${__VU}_${writeCounter}_${Date.now()}@test.dev`;
+}
+
+function randomInt(min, max) {
+  return Math.floor(Math.random() * (max - min + 1)) + min;
+}
+
+const FIRST_NAMES = ['Ana','Carlos','Maria','Pedro','Julia','Lucas','Fernanda','Rafael','Camila','Diego'];
+const LAST_NAMES  = ['Silva','Santos','Oliveira','Costa','Lima','Pereira','Souza','Alves','Rocha','Ferreira'];
+
+function randomName() {
+  return FIRST_NAMES[randomInt(0, FIRST_NAMES.length - 1)] + ' ' +
+         LAST_NAMES[randomInt(0, LAST_NAMES.length - 1)];
+}
+
+// ---------------------------------------------------------------------------
+// Seleção de endpoint por peso (mesma distribuição do load-test-energy.js)
+// 35% /db · 20% /queries · 15% /json · 15% /users · 10% POST · 5% PUT
+// ---------------------------------------------------------------------------
+
+const ENDPOINT_WEIGHTS = [
+  { weight: 35, name: 'db'     },
+  { weight: 20, name: 'queries'},
+  { weight: 15, name: 'json'   },
+  { weight: 15, name: 'users'  },
+  { weight: 10, name: 'create' },
+  { weight:  5, name: 'update' },
+];
+
+const cumulative = [];
+let acc = 0;
+for (const ep of ENDPOINT_WEIGHTS) {
+  acc += ep.weight;
+  cumulative.push({ threshold: acc, ...ep });
+}
+
+function pickEndpoint() {
+  const r = Math.random() * 100;
+  for (const ep of cumulative) {
+    if (r < ep.threshold) return ep.name;
+  }
+  return cumulative[cumulative.length - 1].name;
+}
+
+// ---------------------------------------------------------------------------
+// Função principal
+// ---------------------------------------------------------------------------
+
+const HEADERS = { 'Content-Type': 'application/json' };
+
+// 409: e-mail já existente num POST, esperado sob carga
+const acceptStatus = (status) => (status >= 200 && status < 300) || status === 409;
+
+// Corpo de cada resposta aceita: JSON, e 1 em SCHEMA_SAMPLE contra a spec
+let checkBody = null;
+function bodyChecker(spec) {
+  const checkSchema = schemaValidator(spec);
+  return (res) => {
+    if (res.status === 204) return null;
+    try { res.json(); } catch (_) { return 'corpo não é JSON'; }
+    return checkSchema(res);
+  };
+}
+
+export default function (data) {
+  checkBody = checkBody || bodyChecker(data.spec);
+  const epName = pickEndpoint();
+  const params = { headers: HEADERS, tags: { endpoint: epName }, timeout: REQ_TIMEOUT };
+  let res;
+
+  switch (epName) {
+    case 'db':
+      res = http.get(`${API_URL}/db`, params);
+      break;
+    case 'queries':
+      res = http.get(`${API_URL}/queries?count=5`, params);
+      break;
+    case 'json':
+      res = http.get(`${API_URL}/json`, params);
+      break;
+    case 'users':
+      res = http.get(`${API_URL}/users?limit=20&offset=0`, params);
+      break;
+    case 'create': {
+      const payload = JSON.stringify({
+        name:  randomName(),
+        email: uniqueEmail(),
+        age:   randomInt(18, 65),
+      });
+      res = http.post(`${API_URL}/users`, payload, params);
+      break;
+    }
+    case 'update': {
+      const id = randomInt(1, USER_IDS);
+      const payload = JSON.stringify({
+        name: randomName(),
+        age:  randomInt(18, 65),
+      });
+      res = http.put(`${API_URL}/users/${id}`, payload, params);
+      break;
+    }
+  }
+
+  const ok = recordOutcome(res, { accept: acceptStatus, validate: checkBody });
+  successRate.add(ok);
+  countStatus(res.status);
+  countConnection(res);
+  check(res, { 'status ok': () => ok });
+}
+
+// ---------------------------------------------------------------------------
+// Setup
+// ---------------------------------------------------------------------------
+
+export function setup() {
+  const res = http.get(`${API_URL}/`);
+  if (res.status !== 200) {
+    console.error(`[setup] ERRO: API não respondeu em ${API_URL}/ — status ${res.status}`);
+  } else {
+    console.log(`[setup] Taxa fixa: ${TARGET_RPS} req/s por ${DURATION} (maxVUs=${MAX_VUS})`);
+  }
+  return { apiUrl: API_URL, spec: loadSpec(API_URL) };
+}
+
+// ---------------------------------------------------------------------------
+// handleSummary — resumo compacto com p99.9 e iterações descartadas
+// ---------------------------------------------------------------------------
+
+export function handleSummary(data) {
+  const m   = data.metrics;
+  const dur = m.http_req_duration?.values ?? {};
+
+  const summary = {
+    target_rps:         TARGET_RPS,
+    achieved_rps:       m.http_reqs?.values?.rate ?? 0,
+    requests:           m.http_reqs?.values?.count ?? 0,
+    p50_ms:             dur['med']     ?? 0,
+    p90_ms:             dur['p(90)']   ?? 0,
+    p99_ms:             dur['p(99)']   ?? 0,
+    p999_ms:            dur['p(99.9)'] ?? 0,
+    max_ms:             dur['max']     ?? 0,
+    error_rate:         m.http_req_failed?.values?.rate ?? 0,
+    dropped_iterations: m.dropped_iterations?.values?.count ?? 0,
+    status_2xx:         m.status_2xx?.values?.count ?? 0,
+    status_4xx:         m.status_4xx?.values?.count ?? 0,
+    status_5xx:         m.status_5xx?.values?.count ?? 0,
+    status_0:           m.status_0?.values?.count ?? 0,
+    ...phaseSummary(data),
+    ...errorSummary(data),
+    ...schemaSummary(data),
+  };
+
+  const lines = [
+    '',
+    `[latência] ${API_URL} @ ${TARGET_RPS} req/s (alcançado: ${summary.achieved_rps.toFixed(1)})`,
+    `[latência] p50=${summary.p50_ms.toFixed(2)}ms  p90=${summary.p90_ms.toFixed(2)}ms  ` +
+      `p99=${summary.p99_ms.toFixed(2)}ms  p99.9=${summary.p999_ms.toFixed(2)}ms  max=${summary.max_ms.toFixed(2)}ms`,
+    `[latência] erro=${(summary.error_rate * 100).toFixed(4)}%  descartadas=${summary.dropped_iterations}`,
+    `[latência] 2xx=${summary.status_2xx}  4xx=${summary.status_4xx}  5xx=${summary.status_5xx}  timeout/conexão=${summary.status_0}`,
+    `[latência] ${phaseLine(summary)}`,
+    `[latência] ${errorLine(summary)}`,
+    `[latência] spec: ${summary.schema_checked} respostas conferidas, ${summary.schema_mismatches} divergentes`,
+    '',
+  ];
+
+  const out = { stdout: lines.join('\n') };
+  if (SUMMARY_FILE) {
+    out[SUMMARY_FILE] = JSON.stringify(summary, null, 2);
+  }
+  return out;
+}
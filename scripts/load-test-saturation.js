@@ -14,6 +14,10 @@
  *     --out csv=results/saturation_express.csv \
  *     scripts/load-test-saturation.js
  *
+ * Com SEED definido os sorteios se repetem entre execuções (lib/seed.js).
+ * PUT /users/:id sorteia ids de 1 a USER_IDS (default: 10000, o seed do
+ * init.sql).
+ *
  * Distribuição de endpoints (~70% leitura, ~30% escrita):
  *   35% GET  /db              — single random user (DB read)
  *   20% GET  /queries?count=5 — multiple random users (DB reads)
@@ -26,6 +30,7 @@
 import http from 'k6/http';
 import { check } from 'k6';
 import { Counter, Rate, Trend } from 'k6/metrics';
+import './lib/seed.js';
 
 // ---------------------------------------------------------------------------
 // Parâmetros
@@ -38,6 +43,7 @@ const MAX_RPS       = parseInt(__ENV.MAX_RPS       || '5000', 10);
 const STEP_DURATION = __ENV.STEP_DURATION || '30s';
 const ERR_THRESHOLD = parseFloat(__ENV.ERR_THRESHOLD || '1');   // %
 const P99_THRESHOLD = parseFloat(__ENV.P99_THRESHOLD || '1000'); // ms
+const USER_IDS      = parseInt(__ENV.USER_IDS      || '10000', 10);
 
 // ---------------------------------------------------------------------------
 // Geração dos degraus (escada)
@@ -199,7 +205,7 @@ export default function () {
       break;
     }
     case 'update': {
-      const id = randomInt(1, 10000);
+      const id = randomInt(1, USER_IDS);
       const payload = JSON.stringify({
         name: randomName(),
         age:  randomInt(18, 65),
@@ -0,0 +1,496 @@
+/**
+ * load-test-sessions.js — sessões de usuário como máquinas de estado
+ *
+ * Os outros scripts sorteiam cada requisição de forma independente. Aqui cada
+ * iteração é uma sessão: um tipo de sessão é sorteado por peso e percorre uma
+ * máquina de estados definida num arquivo JSON (por exemplo login → listagem
+ * → leitura → atualização), com transições por peso e tempo de espera
+ * ("think time") entre os passos. Sessões mistas e realistas mudam o uso do
+ * pool e do cache — releituras do que acabou de ser listado, escrita logo
+ * após leitura — de um jeito que requisições uniformes não reproduzem.
+ *
+ * Uso:
+ *   k6 run -e API_URL=http://localhost:3004 \
+ *          -e SCENARIO_FILE=scenarios/sessions.json \
+ *          -e VUS=100 -e DURATION=120s \
+ *          -e SUMMARY_FILE=results/sessions_actix.json \
+ *          scripts/load-test-sessions.js
+ *
+ * Variáveis de ambiente:
+ *   API_URL       — URL base da API (default: http://localhost:3001)
+ *   SCENARIO_FILE — arquivo de cenário, relativo a este script
+ *                   (default: scenarios/sessions.json)
+ *   VUS           — usuários simultâneos, cada um numa sessão por vez (default: 50)
+ *   DURATION      — duração da medição (default: 120s)
+ *   MAX_STEPS     — passos por sessão antes de encerrá-la à força (default: 50)
+ *   REQ_TIMEOUT   — timeout por requisição (default: 60s)
+ *   SUMMARY_FILE  — caminho do JSON compacto de saída (opcional)
+ *   ERROR_BUDGET  — fração de falhas acima da qual o teste falha (default: 0.01;
+ *                   ver lib/errors.js)
+ *   SPEC_URL, SPEC_FILE, SCHEMA_SAMPLE — validação amostrada contra a spec
+ *                   (lib/schema.js)
+ *   SEED          — semente dos sorteios, para repetir a execução (lib/seed.js)
+ *
+ * Formato do cenário (ver scripts/scenarios/):
+ *
+ *   {
+ *     "think_ms": [200, 1000],          // espera entre passos, sorteada no intervalo
+ *     "accounts": 20,                   // contas criadas no setup para /auth/login
+ *     "feeds": { "clientes": "scenarios/feeds/clientes.csv" },  // CSV com cabeçalho, relativo ao script
+ *     "sessions": {
+ *       "leitor": {
+ *         "weight": 70,                 // peso no sorteio do tipo de sessão
+ *         "start": "lista",
+ *         "states": {
+ *           "lista": {
+ *             "method": "GET",
+ *             "path": "/users?limit=20&offset={{ rand_int(0, 9980) }}",
+ *             "capture": "user_id",     // guarda o id de um usuário da resposta
+ *             "next": { "le": 80, "lista": 15, "end": 5 }
+ *           },
+ *           "le": { "method": "GET", "path": "/users/{{ user_id }}", "next": { "end": 1 } }
+ *         }
+ *       }
+ *     }
+ *   }
+ *
+ * Cada estado faz uma requisição e sorteia o próximo entre `next`, por peso;
+ * `end` encerra a sessão. `think_ms` vale para o cenário, a sessão ou o
+ * estado (o mais específico vence). `capture` guarda numa variável da sessão
+ * o `id` do objeto da resposta, ou de um elemento sorteado de um array (ou do
+ * `data` de uma página). Um 409 conta como sucesso, como nos outros scripts.
+ *
+ * Templates: em `path` e nos textos de `body`, `{{ expr }}` é avaliado a cada
+ * requisição, para que cada POST crie um usuário diferente e cada GET caia
+ * numa linha diferente. `expr` é:
+ *   user_id                  variável da sessão (capturada, ou `account_email`
+ *                            e `account_password`, uma conta do setup)
+ *   clientes.email           coluna do feed `clientes`: uma linha por sessão,
+ *                            em sequência, recomeçando ao fim do arquivo
+ *   rand_int(1, 100000)      inteiro uniforme no intervalo fechado
+ *   rand_choice('a', 'b')    um dos argumentos
+ *   name() first_name() last_name() email() age() uuid() now()
+ *                            dados falsos; `email()` nunca se repete
+ *   a | b                    `a`, ou `b` se a variável `a` ainda não existe
+ * Um texto que é só `{{ expr }}` mantém o tipo (número continua número).
+ * Funções, variáveis e feeds desconhecidos reprovam o cenário antes do teste.
+ */
+
+import http from 'k6/http';
+import { check, sleep } from 'k6';
+import { SharedArray } from 'k6/data';
+import exec from 'k6/execution';
+import { Counter, Rate, Trend } from 'k6/metrics';
+import { countConnection, phaseSummary, phaseLine } from './lib/phases.js';
+import { recordOutcome, errorThresholds, errorSummary, errorLine } from './lib/errors.js';
+import { loadSpec, schemaValidator, schemaSummary } from './lib/schema.js';
+import './lib/seed.js';
+
+// ---------------------------------------------------------------------------
+// Configuração
+// ---------------------------------------------------------------------------
+
+const API_URL       = __ENV.API_URL       || 'http://localhost:3001';
+const SCENARIO_FILE = __ENV.SCENARIO_FILE || 'scenarios/sessions.json';
+const VUS           = parseInt(__ENV.VUS || '50', 10);
+const DURATION      = __ENV.DURATION      || '120s';
+const MAX_STEPS     = parseInt(__ENV.MAX_STEPS || '50', 10);
+const REQ_TIMEOUT   = __ENV.REQ_TIMEOUT   || '60s';
+const SUMMARY_FILE  = __ENV.SUMMARY_FILE  || '';
+
+const ACCOUNT_PASSWORD = 'sessao-de-carga-123';
+
+export const options = {
+  scenarios: {
+    sessions: {
+      executor: 'constant-vus',
+      vus:      VUS,
+      duration: DURATION,
+    },
+  },
+  thresholds: errorThresholds,
+  summaryTrendStats: ['avg', 'min', 'med', 'max', 'p(90)', 'p(99)', 'p(99.9)'],
+};
+
+// ---------------------------------------------------------------------------
+// Cenário: lido e validado no contexto de inicialização
+// ---------------------------------------------------------------------------
+
+const scenario = validate(JSON.parse(open(SCENARIO_FILE)));
+
+function validate(s) {
+  const fail = (msg) => { throw new Error(`[cenário] ${SCENARIO_FILE}: ${msg}`); };
+  if (!s.sessions || Object.keys(s.sessions).length === 0) fail('nenhuma sessão em "sessions"');
+  for (const [name, session] of Object.entries(s.sessions)) {
+    if (!(session.weight > 0)) fail(`sessão ${name}: "weight" deve ser positivo`);
+    if (!session.states || !session.states[session.start]) {
+      fail(`sessão ${name}: "start" deve nomear um estado`);
+    }
+    for (const [stateName, state] of Object.entries(session.states)) {
+      if (stateName === 'end') fail(`sessão ${name}: "end" é reservado`);
+      if (!state.method || !state.path) fail(`sessão ${name}, estado ${stateName}: faltam "method" e "path"`);
+      const next = Object.entries(state.next || {});
+      if (next.length === 0) fail(`sessão ${name}, estado ${stateName}: "next" vazio (use {"end": 1})`);
+      for (const [target, weight] of next) {
+        if (target !== 'end' && !session.states[target]) {
+          fail(`sessão ${name}, estado ${stateName}: transição para estado inexistente "${target}"`);
+        }
+        if (!(weight > 0)) fail(`sessão ${name}, estado ${stateName}: peso de "${target}" deve ser positivo`);
+      }
+    }
+  }
+  return s;
+}
+
+/** [{ threshold, value }] acumulado, para sortear por peso. */
+function cumulative(weights) {
+  let acc = 0;
+  return weights.map(([value, weight]) => ({ threshold: (acc += weight), value }));
+}
+
+function pick(table) {
+  const r = Math.random() * table[table.length - 1].threshold;
+  for (const entry of table) {
+    if (r < entry.threshold) return entry.value;
+  }
+  return table[table.length - 1].value;
+}
+
+const SESSION_TABLE = cumulative(Object.entries(scenario.sessions).map(([name, s]) => [name, s.weight]));
+const NEXT_TABLES = {};
+for (const [name, session] of Object.entries(scenario.sessions)) {
+  NEXT_TABLES[name] = {};
+  for (const [stateName, state] of Object.entries(session.states)) {
+    NEXT_TABLES[name][stateName] = cumulative(Object.entries(state.next));
+  }
+}
+
+// ---------------------------------------------------------------------------
+// Métricas: latência por estado, sessões concluídas e duração da sessão
+// ---------------------------------------------------------------------------
+
+const successRate     = new Rate('success_rate');
+const sessionsDone    = new Counter('sessions_completed');
+const sessionsCut     = new Counter('sessions_truncated');
+const sessionDuration = new Trend('session_duration', true);
+
+// Nomes de métrica do k6 aceitam só [A-Za-z0-9_]
+const metricName = (session, state) => `state_${session}_${state}`.replace(/[^A-Za-z0-9_]/g, '_');
+const STATE_TRENDS = {};
+for (const [name, session] of Object.entries(scenario.sessions)) {
+  for (const stateName of Object.keys(session.states)) {
+    STATE_TRENDS[`${name}/${stateName}`] = new Trend(metricName(name, stateName), true);
+  }
+}
+
+// ---------------------------------------------------------------------------
+// Templates {{ expr }}: compilados no init, avaliados a cada requisição
+// ---------------------------------------------------------------------------
+
+const FIRST_NAMES = ['Ana','Carlos','Maria','Pedro','Julia','Lucas','Fernanda','Rafael','Camila','Diego'];
+const LAST_NAMES  = ['Silva','Santos','Oliveira','Costa','Lima','Pereira','Souza','Alves','Rocha','Ferreira'];
+
+function randomInt(min, max) {
+  return Math.floor(Math.random() * (max - min + 1)) + min;
+}
+
+let writeCounter = 0;
+const FUNCTIONS = {
+  rand_int:    (min, max) => randomInt(min, max),
+  rand_choice: (...options) => options[randomInt(0, options.length - 1)],
+  first_name:  () => FIRST_NAMES[randomInt(0, FIRST_NAMES.length - 1)],
+  last_name:   () => LAST_NAMES[randomInt(0, LAST_NAMES.length - 1)],
+  name:        () => FUNCTIONS.first_name() + ' ' + FUNCTIONS.last_name(),
+  email:       () => `sess_${__VU}_${++writeCounter}_${Date.now()}@test.dev`,
+  age:         () => randomInt(18, 65),
+  uuid:        () => 'xxxxxxxx-xxxx-4xxx-yxxx-xxxxxxxxxxxx'.replace(/[xy]/g, (c) =>
+                 (c === 'x' ? randomInt(0, 15) : randomInt(8, 11)).toString(16)),
+  now:         () => new Date().toISOString(),
+};
+
+/** CSV com cabeçalho → objetos; campos entre aspas podem ter vírgulas e "". */
+function parseCsv(text) {
+  const rows = [];
+  let row = [], field = '', quoted = false;
+  for (let i = 0; i < text.length; i++) {
+    const c = text[i];
+    if (quoted) {
+      if (c === '"' && text[i + 1] === '"') { field += '"'; i++; }
+      else if (c === '"') quoted = false;
+      else field += c;
+    } else if (c === '"') quoted = true;
+    else if (c === ',') { row.push(field); field = ''; }
+    else if (c === '\n' || c === '\r') {
+      if (c === '\r' && text[i + 1] === '\n') i++;
+      row.push(field); field = '';
+      if (row.length > 1 || row[0] !== '') rows.push(row);
+      row = [];
+    } else field += c;
+  }
+  row.push(field);
+  if (row.length > 1 || row[0] !== '') rows.push(row);
+
+  const [header, ...data] = rows;
+  // Números do CSV chegam ao corpo como números
+  const value = (v) => (/^-?\d+(\.\d+)?$/.test(v) ? Number(v) : v);
+  return data.map((r) => Object.fromEntries(header.map((h, i) => [h.trim(), value(r[i] ?? '')])));
+}
+
+const FEEDS = {};
+for (const [name, file] of Object.entries(scenario.feeds || {})) {
+  FEEDS[name] = new SharedArray(`feed_${name}`, () => parseCsv(open(file)));
+  if (FEEDS[name].length === 0) throw new Error(`[cenário] feed ${name}: ${file} não tem linhas`);
+}
+
+/** Divide `text` em `sep` fora de aspas e parênteses. */
+function splitTop(text, sep) {
+  const parts = [];
+  let depth = 0, quote = null, current = '';
+  for (const c of text) {
+    if (quote) { if (c === quote) quote = null; }
+    else if (c === "'" || c === '"') quote = c;
+    else if (c === '(') depth++;
+    else if (c === ')') depth--;
+    else if (c === sep && depth === 0) { parts.push(current); current = ''; continue; }
+    current += c;
+  }
+  parts.push(current);
+  return parts.map((p) => p.trim());
+}
+
+function literal(arg, fail) {
+  if (/^-?\d+(\.\d+)?$/.test(arg)) return Number(arg);
+  const quoted = arg.match(/^'(.*)'$|^"(.*)"$/);
+  if (quoted) return quoted[1] ?? quoted[2];
+  return fail(`argumento inválido "${arg}" (use número ou texto entre aspas)`);
+}
+
+/**
+ * `expr` → função (vars) => valor; `undefined` quando é uma variável ainda
+ * não definida. `known` são as variáveis que a sessão pode ter.
+ */
+function compileTerm(expr, known, fail) {
+  let m;
+  if ((m = expr.match(/^(\w+)\((.*)\)$/))) {
+    const fn = FUNCTIONS[m[1]];
+    if (!fn) fail(`função desconhecida ${m[1]}()`);
+    const args = m[2].trim() === '' ? [] : splitTop(m[2], ',').map((a) => literal(a, fail));
+    return () => fn(...args);
+  }
+  if ((m = expr.match(/^(\w+)\.(\w+)$/))) {
+    const [, feed, column] = m;
+    if (!FEEDS[feed]) fail(`feed desconhecido "${feed}"`);
+    if (!(column in FEEDS[feed][0])) fail(`feed ${feed} não tem a coluna "${column}"`);
+    return (vars) => vars.feeds[feed][column];
+  }
+  if (/^\w+$/.test(expr)) {
+    if (!known.has(expr)) fail(`variável desconhecida "${expr}"`);
+    return (vars) => vars[expr];
+  }
+  return fail(`expressão inválida "{{ ${expr} }}"`);
+}
+
+function compileExpr(expr, known, fail) {
+  const terms = splitTop(expr, '|').map((t) => compileTerm(t, known, fail));
+  return (vars) => {
+    for (const term of terms) {
+      const v = term(vars);
+      if (v !== undefined) return v;
+    }
+    throw new Error(`[cenário] {{ ${expr} }}: variável ainda não definida nesta sessão`);
+  };
+}
+
+/** Compila strings com `{{ }}` dentro de `value` (recursivo em objetos e arrays). */
+function compileTemplate(value, known, fail) {
+  if (typeof value === 'string') {
+    const parts = value.split(/\{\{(.*?)\}\}/);
+    if (parts.length === 1) return () => value;
+    // Só a expressão: o valor mantém o tipo
+    if (parts.length === 3 && parts[0] === '' && parts[2] === '') return compileExpr(parts[1].trim(), known, fail);
+    // Posições ímpares são expressões
+    const compiled = parts.map((p, i) => (i % 2 ? compileExpr(p.trim(), known, fail) : () => p));
+    return (vars) => compiled.map((part) => String(part(vars))).join('');
+  }
+  if (Array.isArray(value)) {
+    const items = value.map((v) => compileTemplate(v, known, fail));
+    return (vars) => items.map((item) => item(vars));
+  }
+  if (value && typeof value === 'object') {
+    const fields = Object.entries(value).map(([k, v]) => [k, compileTemplate(v, known, fail)]);
+    return (vars) => Object.fromEntries(fields.map(([k, field]) => [k, field(vars)]));
+  }
+  return () => value;
+}
+
+const TEMPLATES = {};
+for (const [name, session] of Object.entries(scenario.sessions)) {
+  const known = new Set(Object.values(session.states).map((st) => st.capture).filter(Boolean));
+  if (scenario.accounts > 0) ['account_email', 'account_password'].forEach((v) => known.add(v));
+  TEMPLATES[name] = {};
+  for (const [stateName, state] of Object.entries(session.states)) {
+    const fail = (msg) => { throw new Error(`[cenário] ${SCENARIO_FILE}: sessão ${name}, estado ${stateName}: ${msg}`); };
+    TEMPLATES[name][stateName] = {
+      path: compileTemplate(state.path, known, fail),
+      body: state.body === undefined ? null : compileTemplate(state.body, known, fail),
+    };
+  }
+}
+
+/** Linha de cada feed para a sessão: a iteração do teste, contada entre todos os VUs. */
+function feedRows() {
+  const n = exec.scenario.iterationInTest;
+  const rows = {};
+  for (const [name, feed] of Object.entries(FEEDS)) {
+    rows[name] = feed[n % feed.length];
+  }
+  return rows;
+}
+
+/** `id` do objeto, ou de um elemento sorteado de um array / `data`. */
+function capturedId(res) {
+  let body;
+  try { body = res.json(); } catch (_) { return undefined; }
+  const list = Array.isArray(body) ? body : (body && Array.isArray(body.data) ? body.data : null);
+  if (list) return list.length > 0 ? list[randomInt(0, list.length - 1)].id : undefined;
+  return body ? body.id : undefined;
+}
+
+function thinkTime(session, state) {
+  const range = state.think_ms || session.think_ms || scenario.think_ms;
+  if (!range) return;
+  sleep(randomInt(range[0], range[1]) / 1000);
+}
+
+// ---------------------------------------------------------------------------
+// Função principal: uma sessão por iteração
+// ---------------------------------------------------------------------------
+
+const HEADERS = { 'Content-Type': 'application/json' };
+
+let checkSchema = null;
+
+export default function (data) {
+  checkSchema = checkSchema || schemaValidator(data.spec);
+  const sessionName = pick(SESSION_TABLE);
+  const session = scenario.sessions[sessionName];
+  const vars = { feeds: feedRows() };
+  if (data.accounts.length > 0) {
+    vars.account_email = data.accounts[randomInt(0, data.accounts.length - 1)];
+    vars.account_password = ACCOUNT_PASSWORD;
+  }
+
+  const started = Date.now();
+  let stateName = session.start;
+  let steps = 0;
+  while (stateName !== 'end') {
+    if (steps++ >= MAX_STEPS) {
+      sessionsCut.add(1);
+      return;
+    }
+    const state = session.states[stateName];
+    const params = {
+      headers: HEADERS,
+      tags:    { session: sessionName, state: stateName },
+      timeout: REQ_TIMEOUT,
+    };
+    const template = TEMPLATES[sessionName][stateName];
+    const body = template.body ? JSON.stringify(template.body(vars)) : null;
+    const res = http.request(state.method, API_URL + template.path(vars), body, params);
+
+    const ok = recordOutcome(res, { accept: (s) => (s >= 200 && s < 300) || s === 409, validate: checkSchema });
+    successRate.add(ok);
+    check(res, { 'status ok': () => ok });
+    countConnection(res);
+    STATE_TRENDS[`${sessionName}/${stateName}`].add(res.timings.duration);
+    if (ok && state.capture) {
+      const id = capturedId(res);
+      if (id !== undefined) vars[state.capture] = id;
+    }
+
+    stateName = pick(NEXT_TABLES[sessionName][stateName]);
+    if (stateName !== 'end') thinkTime(session, state);
+  }
+  sessionsDone.add(1);
+  sessionDuration.add(Date.now() - started);
+}
+
+// ---------------------------------------------------------------------------
+// Setup: contas para os estados de login
+// ---------------------------------------------------------------------------
+
+export function setup() {
+  const res = http.get(`${API_URL}/`);
+  if (res.status !== 200) {
+    console.error(`[setup] ERRO: API não respondeu em ${API_URL}/ — status ${res.status}`);
+  }
+  const accounts = [];
+  for (let i = 0; i < (scenario.accounts || 0); i++) {
+    const email = `sessao_${i}_${Date.now()}@test.dev`;
+    const reg = http.post(`${API_URL}/auth/register`,
+      JSON.stringify({ email, password: ACCOUNT_PASSWORD }), { headers: HEADERS });
+    if (reg.status !== 201) {
+      console.error(`[setup] ERRO: POST /auth/register respondeu ${reg.status}`);
+      break;
+    }
+    accounts.push(email);
+  }
+  console.log(`[setup] ${Object.keys(scenario.sessions).length} tipos de sessão, ` +
+              `${VUS} VUs por ${DURATION}, ${accounts.length} contas`);
+  return { accounts, spec: loadSpec(API_URL) };
+}
+
+// ---------------------------------------------------------------------------
+// handleSummary — latência por estado e ritmo das sessões
+// ---------------------------------------------------------------------------
+
+export function handleSummary(data) {
+  const m = data.metrics;
+  const dur = m.http_req_duration?.values ?? {};
+  const states = {};
+  for (const key of Object.keys(STATE_TRENDS)) {
+    const [session, state] = key.split('/');
+    const v = m[metricName(session, state)]?.values;
+    if (!v) continue;
+    states[key] = { p50_ms: v['med'], p99_ms: v['p(99)'], max_ms: v['max'] };
+  }
+
+  const summary = {
+    scenario_file:      SCENARIO_FILE,
+    vus:                VUS,
+    requests:           m.http_reqs?.values?.count ?? 0,
+    rps:                m.http_reqs?.values?.rate ?? 0,
+    sessions_completed: m.sessions_completed?.values?.count ?? 0,
+    sessions_truncated: m.sessions_truncated?.values?.count ?? 0,
+    session_p50_ms:     m.session_duration?.values?.['med'] ?? 0,
+    p50_ms:             dur['med']   ?? 0,
+    p99_ms:             dur['p(99)'] ?? 0,
+    success_rate:       m.success_rate?.values?.rate ?? 0,
+    ...phaseSummary(data),
+    ...errorSummary(data),
+    ...schemaSummary(data),
+    states,
+  };
+
+  const lines = [
+    '',
+    `[sessões] ${API_URL} · ${SCENARIO_FILE} · ${VUS} VUs`,
+    `[sessões] ${summary.sessions_completed} concluídas, ${summary.sessions_truncated} cortadas em ${MAX_STEPS} passos, ` +
+      `duração p50=${summary.session_p50_ms.toFixed(0)}ms`,
+    `[sessões] ${summary.requests} requisições (${summary.rps.toFixed(1)} req/s), ` +
+      `p50=${summary.p50_ms.toFixed(2)}ms  p99=${summary.p99_ms.toFixed(2)}ms  ` +
+      `sucesso=${(summary.success_rate * 100).toFixed(2)}%`,
+    `[sessões] ${phaseLine(summary)}`,
+    `[sessões] ${errorLine(summary)}`,
+    `[sessões] spec: ${summary.schema_checked} respostas conferidas, ${summary.schema_mismatches} divergentes`,
+    ...Object.entries(states).map(([key, s]) =>
+      `[sessões]   ${key.padEnd(28)} p50=${s.p50_ms.toFixed(2)}ms  p99=${s.p99_ms.toFixed(2)}ms`),
+    '',
+  ];
+
+  const out = { stdout: lines.join('\n') };
+  if (SUMMARY_FILE) {
+    out[SUMMARY_FILE] = JSON.stringify(summary, null, 2);
+  }
+  return out;
+}
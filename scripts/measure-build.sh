@@ -0,0 +1,113 @@
+#!/usr/bin/env bash
+# measure-build.sh — tempo de build, tamanho de imagem e de binário por framework
+#
+# Constrói cada imagem em sequência e sem cache (builds paralelos disputam CPU
+# e distorcem o tempo) e registra:
+#
+#   build_s         — tempo de parede do `docker compose build --no-cache`
+#   image_mb        — tamanho da imagem final
+#   artifact_bytes  — binário de release (actix, gin) ou diretório /app (node/bun)
+#   stripped_bytes  — binário após `strip` (vazio para runtimes interpretados)
+#
+# Uso:
+#   ./scripts/measure-build.sh [--output-dir results/<timestamp>] [--frameworks "actix gin"]
+#
+# Saída: <output-dir>/build_metrics.csv — lido pelo analyze-results.py
+#
+set -euo pipefail
+
+OUTPUT_DIR=""
+FRAMEWORKS=""
+
+while [[ $# -gt 0 ]]; do
+  case $1 in
+    --output-dir) OUTPUT_DIR="$2"; shift 2 ;;
+    --frameworks) FRAMEWORKS="$2"; shift 2 ;;
+    *) echo "Argumento desconhecido: $1"; exit 1 ;;
+  esac
+done
+
+SCRIPT_DIR="$(cd "$(dirname "${BASH_SOURCE[0]}")" && pwd)"
+PROJECT_DIR="$(dirname "$SCRIPT_DIR")"
+
+# Commit embutido na imagem do Actix e reportado por GET /config
+export GIT_SHA="${GIT_SHA:-$(git -C "$PROJECT_DIR" rev-parse --short=12 HEAD 2>/dev/null || true)}"
+
+OUTPUT_DIR="${OUTPUT_DIR:-$PROJECT_DIR/results/build_$(date +%Y%m%d_%H%M%S)}"
+mkdir -p "$OUTPUT_DIR"
+
+CYAN='\033[0;36m'; YELLOW='\033[1;33m'; GREEN='\033[0;32m'; NC='\033[0m'
+log()     { echo -e "${CYAN}[$(date +%H:%M:%S)]${NC} $*"; }
+success() { echo -e "${GREEN}[$(date +%H:%M:%S)] ✓${NC} $*"; }
+warn()    { echo -e "${YELLOW}[$(date +%H:%M:%S)] !${NC} $*"; }
+
+now_ms() { date +%s%3N; }
+
+declare -A FRAMEWORK_SERVICES=(
+  [express]=api-express
+  [fastify]=api-fastify
+  [elysia]=api-elysia
+  [actix]=api-actix
+  [gin]=api-gin
+)
+
+# Artefato de deploy dentro da imagem
+declare -A FRAMEWORK_ARTIFACTS=(
+  [express]=/app
+  [fastify]=/app
+  [elysia]=/app
+  [actix]=/app/api-actix
+  [gin]=/app/api-gin
+)
+
+if [ -n "$FRAMEWORKS" ]; then
+  read -r -a FRAMEWORK_ORDER <<< "$FRAMEWORKS"
+else
+  FRAMEWORK_ORDER=(express fastify elysia actix gin)
+fi
+
+cd "$PROJECT_DIR"
+
+OUT_CSV="$OUTPUT_DIR/build_metrics.csv"
+echo "framework,build_s,image_mb,artifact_bytes,stripped_bytes" > "$OUT_CSV"
+
+TMP_DIR=$(mktemp -d)
+trap 'rm -rf "$TMP_DIR"' EXIT
+
+for FRAMEWORK in "${FRAMEWORK_ORDER[@]}"; do
+  SERVICE="${FRAMEWORK_SERVICES[$FRAMEWORK]}"
+  ARTIFACT="${FRAMEWORK_ARTIFACTS[$FRAMEWORK]}"
+
+  log "[$FRAMEWORK] build sem cache..."
+  T0=$(now_ms)
+  docker compose build --no-cache "$SERVICE" >/dev/null
+  T1=$(now_ms)
+  BUILD_S=$(echo "scale=2; ($T1 - $T0) / 1000" | bc)
+
+  IMAGE=$(docker compose config --images "$SERVICE")
+  IMAGE_BYTES=$(docker image inspect -f '{{.Size}}' "$IMAGE")
+  IMAGE_MB=$(echo "scale=1; $IMAGE_BYTES / 1048576" | bc)
+
+  # Extrai o artefato de um container criado (não iniciado)
+  CID=$(docker create "$IMAGE")
+  rm -rf "${TMP_DIR:?}/artifact"
+  docker cp "$CID:$ARTIFACT" "$TMP_DIR/artifact" >/dev/null
+  docker rm "$CID" >/dev/null
+
+  ARTIFACT_BYTES=$(du -sb "$TMP_DIR/artifact" | cut -f1)
+  STRIPPED_BYTES=""
+  if [ -f "$TMP_DIR/artifact" ]; then
+    if command -v strip &>/dev/null && strip -o "$TMP_DIR/stripped" "$TMP_DIR/artifact" 2>/dev/null; then
+      STRIPPED_BYTES=$(stat -c %s "$TMP_DIR/stripped")
+    else
+      warn "[$FRAMEWORK] strip indisponível ou falhou — stripped_bytes em branco"
+    fi
+  fi
+
+  echo "$FRAMEWORK,$BUILD_S,$IMAGE_MB,$ARTIFACT_BYTES,$STRIPPED_BYTES" >> "$OUT_CSV"
+  success "[$FRAMEWORK] build=${BUILD_S}s  imagem=${IMAGE_MB}MB  artefato=${ARTIFACT_BYTES}B  stripped=${STRIPPED_BYTES:-n/a}B"
+done
+
+echo ""
+column -t -s, "$OUT_CSV"
+success "Métricas de build salvas em $OUT_CSV"
@@ -0,0 +1,202 @@
+#!/usr/bin/env python3
+"""
+measure-startup.py — cold start e tempo até a primeira requisição.
+
+Para cada framework e cada repetição, recria o container da API e mede, a
+partir do instante em que o processo foi iniciado (`State.StartedAt` do
+Docker):
+
+  bind_ms       — porta HTTP aceitando conexões TCP
+  first_ok_ms   — primeira resposta 200 em GET /      (sem banco)
+  first_db_ms   — primeira resposta 200 em GET /db    (com ida ao banco)
+
+A conexão é feita direto no IP do container na rede do compose: pela porta
+publicada no host o docker-proxy aceita conexões antes de a aplicação ter
+feito bind, o que mascararia o tempo de bind. Em ambientes onde o IP do
+container não é roteável (Docker Desktop), use --via-host.
+
+Uso:
+  python3 scripts/measure-startup.py [--runs 10] [--frameworks actix gin]
+                                     [--output-dir results/startup]
+                                     [--via-host]
+
+Saída:
+  startup.csv          — uma linha por repetição
+  startup_summary.csv  — mediana, mínimo e máximo por framework
+"""
+
+import argparse
+import csv
+import http.client
+import json
+import socket
+import statistics
+import subprocess
+import sys
+import time
+from datetime import datetime, timezone
+from pathlib import Path
+
+# ---------------------------------------------------------------------------
+# Constantes
+# ---------------------------------------------------------------------------
+
+FRAMEWORKS = ['express', 'fastify', 'elysia', 'actix', 'gin']
+
+FRAMEWORK_PORTS = {
+    'express': 3001,
+    'fastify': 3002,
+    'elysia':  3003,
+    'actix':   3004,
+    'gin':     3005,
+}
+
+PROJECT_DIR = Path(__file__).resolve().parent.parent
+
+POLL_INTERVAL_S = 0.005
+TIMEOUT_S       = 60.0
+
+# ---------------------------------------------------------------------------
+# Argumentos
+# ---------------------------------------------------------------------------
+
+def parse_args():
+    p = argparse.ArgumentParser(description="Mede cold start das APIs")
+    p.add_argument('--runs',       type=int, default=10,      help="Repetições por framework (default: 10)")
+    p.add_argument('--frameworks', nargs='+', default=FRAMEWORKS, help="Subconjunto de frameworks")
+    p.add_argument('--output-dir', default=None,              help="Diretório de saída (default: results/startup_<ts>)")
+    p.add_argument('--via-host',   action='store_true',       help="Usa localhost:<porta publicada> em vez do IP do container")
+    return p.parse_args()
+
+# ---------------------------------------------------------------------------
+# Docker
+# ---------------------------------------------------------------------------
+
+def compose(*args):
+    subprocess.run(['docker', 'compose', *args], cwd=PROJECT_DIR, check=True,
+                   stdout=subprocess.DEVNULL, stderr=subprocess.DEVNULL)
+
+def container_id(service: str) -> str:
+    out = subprocess.run(['docker', 'compose', 'ps', '-aq', service],
+                         cwd=PROJECT_DIR, check=True, capture_output=True, text=True).stdout.strip()
+    return out.splitlines()[0]
+
+def inspect(cid: str) -> dict:
+    out = subprocess.run(['docker', 'inspect', cid], check=True,
+                         capture_output=True, text=True).stdout
+    return json.loads(out)[0]
+
+def parse_docker_time(s: str) -> float:
+    """'2025-01-01T12:00:00.123456789Z' → epoch em segundos (precisão de µs)."""
+    head, _, frac = s.rstrip('Z').partition('.')
+    dt = datetime.strptime(head, '%Y-%m-%dT%H:%M:%S').replace(tzinfo=timezone.utc)
+    return dt.timestamp() + float('0.' + (frac or '0')[:6])
+
+# ---------------------------------------------------------------------------
+# Sondagem
+# ---------------------------------------------------------------------------
+
+def wait_tcp(host: str, port: int, deadline: float) -> float:
+    while time.time() < deadline:
+        try:
+            with socket.create_connection((host, port), timeout=0.05):
+                return time.time()
+        except OSError:
+            time.sleep(POLL_INTERVAL_S)
+    raise TimeoutError(f"{host}:{port} não aceitou conexões")
+
+def wait_http_ok(host: str, port: int, path: str, deadline: float) -> float:
+    while time.time() < deadline:
+        try:
+            conn = http.client.HTTPConnection(host, port, timeout=1)
+            conn.request('GET', path)
+            status = conn.getresponse().status
+            conn.close()
+            if status == 200:
+                return time.time()
+        except (OSError, http.client.HTTPException):
+            pass
+        time.sleep(POLL_INTERVAL_S)
+    raise TimeoutError(f"GET {path} não retornou 200")
+
+def measure_once(framework: str, via_host: bool) -> dict:
+    service = f'api-{framework}'
+
+    # Container novo a cada repetição: nada de cache de JIT, pool ou page cache do processo
+    compose('rm', '-sf', service)
+    compose('create', service)
+    compose('start', service)
+
+    info = inspect(container_id(service))
+    started_at = parse_docker_time(info['State']['StartedAt'])
+
+    if via_host:
+        host, port = 'localhost', FRAMEWORK_PORTS[framework]
+    else:
+        networks = info['NetworkSettings']['Networks']
+        host = next(iter(networks.values()))['IPAddress']
+        port = FRAMEWORK_PORTS[framework]
+
+    deadline = started_at + TIMEOUT_S
+    t_bind = wait_tcp(host, port, deadline)
+    t_ok   = wait_http_ok(host, port, '/', deadline)
+    t_db   = wait_http_ok(host, port, '/db', deadline)
+
+    return {
+        'bind_ms':     (t_bind - started_at) * 1000,
+        'first_ok_ms': (t_ok   - started_at) * 1000,
+        'first_db_ms': (t_db   - started_at) * 1000,
+    }
+
+# ---------------------------------------------------------------------------
+# Ponto de entrada
+# ---------------------------------------------------------------------------
+
+def main():
+    args = parse_args()
+    output_dir = Path(args.output_dir) if args.output_dir else \
+        PROJECT_DIR / 'results' / f"startup_{time.strftime('%Y%m%d_%H%M%S')}"
+    output_dir.mkdir(parents=True, exist_ok=True)
+
+    subprocess.run(['docker', 'compose', 'up', '-d', '--wait', 'postgres'],
+                   cwd=PROJECT_DIR, check=True)
+
+    rows = []
+    for fw in args.frameworks:
+        if fw not in FRAMEWORK_PORTS:
+            print(f"[erro] framework desconhecido: {fw}")
+            sys.exit(1)
+        for run in range(1, args.runs + 1):
+            try:
+                m = measure_once(fw, args.via_host)
+            except TimeoutError as e:
+                print(f"[warn] {fw} rodada {run}: {e}")
+                continue
+            rows.append({'framework': fw, 'run': run, **{k: round(v, 2) for k, v in m.items()}})
+            print(f"[info] {fw:8s} #{run:<3d} bind={m['bind_ms']:8.1f}ms  "
+                  f"/={m['first_ok_ms']:8.1f}ms  /db={m['first_db_ms']:8.1f}ms")
+        compose('stop', f'api-{fw}')
+
+    out = output_dir / 'startup.csv'
+    with open(out, 'w', newline='') as f:
+        writer = csv.DictWriter(f, fieldnames=['framework', 'run', 'bind_ms', 'first_ok_ms', 'first_db_ms'])
+        writer.writeheader()
+        writer.writerows(rows)
+    print(f"[ok] Medições salvas em {out}")
+
+    out = output_dir / 'startup_summary.csv'
+    with open(out, 'w', newline='') as f:
+        writer = csv.writer(f)
+        writer.writerow(['framework', 'n_runs', 'metric', 'median_ms', 'min_ms', 'max_ms'])
+        for fw in args.frameworks:
+            fw_rows = [r for r in rows if r['framework'] == fw]
+            if not fw_rows:
+                continue
+            for metric in ('bind_ms', 'first_ok_ms', 'first_db_ms'):
+                vals = [r[metric] for r in fw_rows]
+                writer.writerow([fw, len(vals), metric,
+                                 round(statistics.median(vals), 2), min(vals), max(vals)])
+    print(f"[ok] Resumo salvo em {out}")
+
+if __name__ == '__main__':
+    main()
@@ -0,0 +1,36 @@
+-- E-mail único sem diferenciar maiúsculas, para bancos criados com o
+-- init.sql anterior (email VARCHAR(255) NOT NULL UNIQUE).
+--
+--   docker compose exec -T postgres psql -U benchmark -v ON_ERROR_STOP=1 \
+--     < scripts/migrations/001-email-unique-lower.sql
+--
+-- Normaliza os e-mails existentes (trim + minúsculas) e troca a constraint
+-- UNIQUE(email) por um índice único em lower(email) com o mesmo nome. Se dois
+-- usuários só diferem na caixa do e-mail, a migração falha listando-os e nada
+-- é alterado; resolva-os antes de rodar de novo. Rodar duas vezes não muda
+-- nada.
+
+BEGIN;
+
+DO $$
+DECLARE
+    duplicates TEXT;
+BEGIN
+    SELECT string_agg(format('%s (ids %s)', normalized, ids), ', ')
+      INTO duplicates
+      FROM (SELECT lower(btrim(email)) AS normalized, string_agg(id::text, ',' ORDER BY id) AS ids
+              FROM users
+             GROUP BY 1
+            HAVING COUNT(*) > 1) d;
+    IF duplicates IS NOT NULL THEN
+        RAISE EXCEPTION 'e-mails duplicados sem diferenciar maiúsculas: %', duplicates;
+    END IF;
+END
+$$;
+
+UPDATE users SET email = lower(btrim(email)) WHERE email <> lower(btrim(email));
+
+ALTER TABLE users DROP CONSTRAINT IF EXISTS users_email_key;
+CREATE UNIQUE INDEX IF NOT EXISTS users_email_key ON users (lower(email));
+
+COMMIT;
@@ -0,0 +1,63 @@
+-- Chave primária UUIDv7 em users, para o Actix compilado com a feature
+-- uuid-keys (ACTIX_FEATURES=uuid-keys). As demais APIs esperam ids inteiros:
+-- use um volume separado para essa variante.
+--
+--   docker compose exec -T postgres psql -U benchmark -v ON_ERROR_STOP=1 \
+--     < scripts/migrations/002-uuid-keys.sql
+--
+-- uuid_v7() segue a RFC 9562: 48 bits de milissegundos Unix, 12 bits da
+-- fração do milissegundo (resolução de microssegundos, então chaves geradas
+-- em sequência continuam em ordem) e o resto aleatório. Ela vira o default da
+-- coluna, e os INSERTs das APIs não mudam. As linhas existentes recebem chaves
+-- de created_at + id microssegundos, na mesma ordem dos ids antigos.
+-- audit_log.entity_id passa a uuid (as entradas antigas ficam sem id) e o
+-- feed de mudanças publica o id como texto. Rodar duas vezes não muda nada.
+
+BEGIN;
+
+CREATE OR REPLACE FUNCTION uuid_v7(at TIMESTAMPTZ DEFAULT clock_timestamp()) RETURNS uuid AS $$
+DECLARE
+    micros BIGINT := floor(extract(epoch FROM at) * 1000000);
+    fraction INTEGER := ((micros % 1000) * 4096 / 1000)::int;
+    bytes BYTEA := uuid_send(gen_random_uuid());
+BEGIN
+    bytes := overlay(bytes PLACING substring(int8send(micros / 1000) FROM 3) FROM 1 FOR 6);
+    -- Versão 7 e os 12 bits da fração; a variante é a do UUID aleatório.
+    bytes := set_byte(bytes, 6, x'70'::int | (fraction >> 8));
+    bytes := set_byte(bytes, 7, fraction & 255);
+    RETURN encode(bytes, 'hex')::uuid;
+END;
+$$ LANGUAGE plpgsql VOLATILE;
+
+DO $$
+BEGIN
+    IF (SELECT data_type FROM information_schema.columns
+         WHERE table_name = 'users' AND column_name = 'id') = 'uuid' THEN
+        RETURN;
+    END IF;
+
+    ALTER TABLE users ADD COLUMN new_id uuid;
+    UPDATE users SET new_id = uuid_v7(created_at + id * interval '1 microsecond');
+    -- Leva junto a chave primária, users_id_seq e idx_users_tenant_id.
+    ALTER TABLE users DROP COLUMN id;
+    ALTER TABLE users RENAME COLUMN new_id TO id;
+    ALTER TABLE users ALTER COLUMN id SET DEFAULT uuid_v7(), ALTER COLUMN id SET NOT NULL;
+    ALTER TABLE users ADD PRIMARY KEY (id);
+    CREATE INDEX idx_users_tenant_id ON users(tenant_id, id);
+
+    ALTER TABLE audit_log ALTER COLUMN entity_id TYPE uuid USING NULL;
+END
+$$;
+
+CREATE OR REPLACE FUNCTION notify_user_change() RETURNS trigger AS $$
+DECLARE
+    changed_id TEXT := CASE WHEN TG_OP = 'DELETE' THEN OLD.id ELSE NEW.id END;
+BEGIN
+    PERFORM pg_notify('user_changes', json_build_object('op', TG_OP, 'id', changed_id)::text);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+ANALYZE users;
+
+COMMIT;
@@ -0,0 +1,32 @@
+-- Chave primária BIGINT (BIGSERIAL) em users, para o Actix compilado com a
+-- feature bigint-keys (ACTIX_FEATURES=bigint-keys) e tabelas que passam do
+-- limite do INTEGER (2.147.483.647 ids, contando os já consumidos pela
+-- sequência). As demais APIs continuam lendo os ids como números, mas o
+-- driver do Node devolve BIGINT como string: use um volume separado para
+-- essa variante.
+--
+--   docker compose exec -T postgres psql -U benchmark -v ON_ERROR_STOP=1 \
+--     < scripts/migrations/003-bigint-keys.sql
+--
+-- Reescreve a tabela e os índices (em tabelas grandes, leva o tempo de um
+-- seed), alarga a sequência e audit_log.entity_id, e o feed de mudanças passa
+-- a declarar o id como BIGINT. Rodar duas vezes não muda nada.
+
+BEGIN;
+
+ALTER TABLE users ALTER COLUMN id TYPE bigint;
+ALTER SEQUENCE users_id_seq AS bigint;
+ALTER TABLE audit_log ALTER COLUMN entity_id TYPE bigint;
+
+CREATE OR REPLACE FUNCTION notify_user_change() RETURNS trigger AS $$
+DECLARE
+    changed_id BIGINT := CASE WHEN TG_OP = 'DELETE' THEN OLD.id ELSE NEW.id END;
+BEGIN
+    PERFORM pg_notify('user_changes', json_build_object('op', TG_OP, 'id', changed_id)::text);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+ANALYZE users;
+
+COMMIT;
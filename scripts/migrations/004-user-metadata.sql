@@ -0,0 +1,16 @@
+-- Coluna metadata (JSONB) em users, para bancos criados antes dela no
+-- init.sql: PATCH /users/:id/metadata e GET /users?metadata.x=y do Actix.
+--
+--   docker compose exec -T postgres psql -U benchmark -v ON_ERROR_STOP=1 \
+--     < scripts/migrations/004-user-metadata.sql
+--
+-- Com um default constante, ADD COLUMN não reescreve a tabela; o índice GIN
+-- percorre todas as linhas (em tabelas grandes, leva o tempo de um índice
+-- secundário do seed). Rodar duas vezes não muda nada.
+
+BEGIN;
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS metadata JSONB NOT NULL DEFAULT '{}';
+CREATE INDEX IF NOT EXISTS idx_users_metadata ON users USING GIN (metadata jsonb_path_ops);
+
+COMMIT;
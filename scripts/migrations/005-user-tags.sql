@@ -0,0 +1,16 @@
+-- Coluna tags (TEXT[]) em users, para bancos criados antes dela no init.sql.
+-- O Actix lê a coluna em toda consulta de usuários (campo tags e
+-- GET /users?tag=x): aplique antes de subir a versão nova.
+--
+--   docker compose exec -T postgres psql -U benchmark -v ON_ERROR_STOP=1 \
+--     < scripts/migrations/005-user-tags.sql
+--
+-- Com um default constante, ADD COLUMN não reescreve a tabela; o índice GIN
+-- percorre todas as linhas. Rodar duas vezes não muda nada.
+
+BEGIN;
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS tags TEXT[] NOT NULL DEFAULT '{}';
+CREATE INDEX IF NOT EXISTS idx_users_tags ON users USING GIN (tags);
+
+COMMIT;
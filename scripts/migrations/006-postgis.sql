@@ -0,0 +1,34 @@
+-- Coluna location (geography(Point)) em users, para GET /users/near do Actix
+-- compilado com a feature postgis. Precisa de uma imagem com PostGIS:
+--
+--   POSTGRES_IMAGE=postgis/postgis:16-3.4-alpine docker compose up -d postgres
+--   docker compose exec -T postgres psql -U benchmark -v ON_ERROR_STOP=1 \
+--     < scripts/migrations/006-postgis.sql
+--
+-- As demais APIs não leem a coluna. Cada usuário sem localização recebe um
+-- ponto determinístico dentro do retângulo do Brasil (users_seed_location,
+-- pela ordem dos ids), e o índice GiST atende o ST_DWithin. Rodar duas vezes
+-- não muda nada; usuários criados depois pela API ficam sem localização.
+
+BEGIN;
+
+CREATE EXTENSION IF NOT EXISTS postgis;
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS location geography(Point, 4326);
+
+-- n-ésimo ponto do seed: longitude de -73.9 a -34.9, latitude de -33.7 a 5.2
+CREATE OR REPLACE FUNCTION users_seed_location(n BIGINT) RETURNS geography
+LANGUAGE sql IMMUTABLE AS $$
+    SELECT ST_SetSRID(ST_MakePoint(-73.9 + (n * 7919 % 39000) / 1000.0,
+                                   -33.7 + (n * 104729 % 38900) / 1000.0), 4326)::geography
+$$;
+
+UPDATE users u SET location = users_seed_location(s.n)
+FROM (SELECT id, row_number() OVER (ORDER BY id) AS n FROM users WHERE location IS NULL) s
+WHERE u.id = s.id;
+
+CREATE INDEX IF NOT EXISTS idx_users_location ON users USING GIST (location);
+
+COMMIT;
+
+ANALYZE users;
@@ -0,0 +1,23 @@
+-- Coluna balance (NUMERIC(12,2)) em users, para bancos criados antes dela no
+-- init.sql. Só POST /transfer do Actix lê e escreve a coluna; aplique antes de
+-- subir a versão nova.
+--
+--   docker compose exec -T postgres psql -U benchmark -v ON_ERROR_STOP=1 \
+--     < scripts/migrations/007-user-balance.sql
+--
+-- Com um default constante, ADD COLUMN não reescreve a tabela: as linhas
+-- existentes passam a ter o saldo inicial de 1000.00. O CHECK valida todas as
+-- linhas uma vez. Rodar duas vezes não muda nada.
+
+BEGIN;
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS balance NUMERIC(12,2) NOT NULL DEFAULT 1000.00;
+
+DO $$
+BEGIN
+    ALTER TABLE users ADD CONSTRAINT users_balance_non_negative CHECK (balance >= 0);
+EXCEPTION WHEN duplicate_object THEN NULL;
+END
+$$;
+
+COMMIT;
@@ -0,0 +1,22 @@
+-- Tipo user_status (ENUM) e coluna status em users, para bancos criados antes
+-- deles no init.sql. O Actix lê a coluna em toda consulta de usuários (campo
+-- status e GET /users?status=x): aplique antes de subir a versão nova.
+--
+--   docker compose exec -T postgres psql -U benchmark -v ON_ERROR_STOP=1 \
+--     < scripts/migrations/008-user-status.sql
+--
+-- Com um default constante, ADD COLUMN não reescreve a tabela: as linhas
+-- existentes ficam active. Rodar duas vezes não muda nada.
+
+BEGIN;
+
+DO $$
+BEGIN
+    CREATE TYPE user_status AS ENUM ('active', 'inactive', 'suspended');
+EXCEPTION WHEN duplicate_object THEN NULL;
+END
+$$;
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS status user_status NOT NULL DEFAULT 'active';
+
+COMMIT;
@@ -0,0 +1,38 @@
+-- Trigger do feed de mudanças (GET /events e o long polling do Actix com
+-- EVENTS=on): cada escrita em users publica {"op", "id"} no canal
+-- user_changes, qualquer que seja a API que escreveu. Aplique só nas rodadas
+-- com EVENTS=on:
+--
+--   docker compose exec -T postgres psql -U benchmark -v ON_ERROR_STOP=1 \
+--     < scripts/migrations/009-change-feed.sql
+--
+-- Fica fora do init.sql porque o NOTIFY toma o lock global da fila de
+-- notificações no commit: com o trigger, toda escrita das cinco APIs (e cada
+-- linha de /users/bulk) passa por ele, mesmo sem ninguém escutando. Para
+-- voltar ao schema padrão (inclusive em volumes criados quando o init.sql
+-- ainda instalava o trigger):
+--
+--   DROP TRIGGER IF EXISTS users_notify_change ON users;
+--
+-- O id vai com o tipo da coluna (número, ou texto com a migração 002), então
+-- a ordem em relação às migrações 002 e 003 não importa. Rodar duas vezes não
+-- muda nada.
+
+BEGIN;
+
+CREATE OR REPLACE FUNCTION notify_user_change() RETURNS trigger AS $$
+BEGIN
+    PERFORM pg_notify('user_changes', json_build_object(
+        'op', TG_OP,
+        'id', CASE WHEN TG_OP = 'DELETE' THEN OLD.id ELSE NEW.id END
+    )::text);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS users_notify_change ON users;
+CREATE TRIGGER users_notify_change
+    AFTER INSERT OR UPDATE OR DELETE ON users
+    FOR EACH ROW EXECUTE FUNCTION notify_user_change();
+
+COMMIT;
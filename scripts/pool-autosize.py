@@ -0,0 +1,127 @@
+#!/usr/bin/env python3
+"""
+pool-autosize.py — decisões do POOL_AUTOSIZE do Actix ao longo da medição.
+
+Com POOL_AUTOSIZE=on o Actix muda o tamanho máximo do pool de conexões a partir
+da espera média por conexão e da folga de conexões do Postgres, e guarda as
+últimas decisões em GET /pool-stats (campo "autosize"). Este script lê esse
+JSON e grava as decisões em CSV e num gráfico do tamanho do pool no tempo, com
+a espera média que motivou cada mudança.
+
+Uso:
+  python3 scripts/pool-autosize.py \\
+    --pool-stats results/latency_<ts>/pool_stats_actix.json \\
+    --framework actix \\
+    --output-dir results/latency_<ts>
+
+Saída:
+  pool_autosize_<framework>.csv  — uma linha por decisão
+  pool_autosize_<framework>.png  — tamanho do pool e espera média (se matplotlib disponível)
+"""
+
+import argparse
+import csv
+import json
+import re
+import sys
+from datetime import datetime
+from pathlib import Path
+
+FIELDS = ['at', 'from', 'to', 'reason', 'avg_wait_ms', 'waiting', 'available', 'headroom']
+
+# ---------------------------------------------------------------------------
+# Argumentos
+# ---------------------------------------------------------------------------
+
+def parse_args():
+    p = argparse.ArgumentParser(description="Decisões do POOL_AUTOSIZE no tempo")
+    p.add_argument('--pool-stats', required=True, help="JSON de GET /pool-stats ao fim da medição")
+    p.add_argument('--framework',  required=True, help="Nome do framework")
+    p.add_argument('--output-dir', default='.',   help="Diretório de saída (default: .)")
+    return p.parse_args()
+
+def parse_time(value: str) -> datetime:
+    """RFC 3339 do chrono; frações além de µs não são aceitas antes do Python 3.11."""
+    value = re.sub(r'(\.\d{6})\d+', r'\1', value.replace('Z', '+00:00'))
+    return datetime.fromisoformat(value)
+
+# ---------------------------------------------------------------------------
+# Saída
+# ---------------------------------------------------------------------------
+
+def write_csv(decisions: list, framework: str, output_dir: Path):
+    out = output_dir / f'pool_autosize_{framework}.csv'
+    with open(out, 'w', newline='') as f:
+        writer = csv.DictWriter(f, fieldnames=FIELDS, extrasaction='ignore')
+        writer.writeheader()
+        writer.writerows(decisions)
+    print(f"[ok] Decisões salvas em {out}")
+
+def plot(decisions: list, autosize: dict, framework: str, output_dir: Path):
+    try:
+        import matplotlib
+        matplotlib.use('Agg')
+        import matplotlib.pyplot as plt
+    except ImportError:
+        print("[warn] matplotlib não disponível — gráfico não gerado")
+        return
+
+    times = [parse_time(d['at']) for d in decisions]
+    t0 = times[0]
+    secs = [(t - t0).total_seconds() for t in times]
+
+    # Degraus: o tamanho antes da primeira decisão, depois o de cada uma
+    xs = [secs[0]] + secs
+    ys = [decisions[0]['from']] + [d['to'] for d in decisions]
+
+    fig, ax = plt.subplots(figsize=(12, 5))
+    ax.step(xs, ys, where='post', color='#1976D2', linewidth=2, label='tamanho máximo do pool')
+    ax.axhline(autosize['min'], color='#9E9E9E', linestyle=':', linewidth=1)
+    ax.axhline(autosize['max'], color='#9E9E9E', linestyle=':', linewidth=1, label='limites')
+    ax.set_xlabel('Segundos desde a primeira decisão')
+    ax.set_ylabel('Conexões')
+
+    ax2 = ax.twinx()
+    ax2.scatter(secs, [d['avg_wait_ms'] for d in decisions], color='#F44336', s=16, label='espera média (ms)')
+    ax2.axhline(autosize['target_wait_ms'], color='#F44336', linestyle='--', linewidth=1, label='espera alvo')
+    ax2.set_ylabel('Espera média por conexão (ms)')
+
+    lines = ax.get_legend_handles_labels()
+    lines2 = ax2.get_legend_handles_labels()
+    ax.legend(lines[0] + lines2[0], lines[1] + lines2[1], fontsize=9, loc='upper left')
+    ax.set_title(f'POOL_AUTOSIZE — {framework}', fontsize=12, fontweight='bold')
+
+    plt.tight_layout()
+    out = output_dir / f'pool_autosize_{framework}.png'
+    fig.savefig(out, dpi=150, bbox_inches='tight')
+    plt.close(fig)
+    print(f"[ok] Gráfico salvo em {out}")
+
+# ---------------------------------------------------------------------------
+# Ponto de entrada
+# ---------------------------------------------------------------------------
+
+def main():
+    args = parse_args()
+    output_dir = Path(args.output_dir)
+    output_dir.mkdir(parents=True, exist_ok=True)
+
+    with open(args.pool_stats) as f:
+        autosize = json.load(f).get('autosize')
+    if not autosize:
+        print(f"[erro] {args.pool_stats} sem o campo autosize (POOL_AUTOSIZE desligado?)")
+        sys.exit(1)
+
+    decisions = autosize['decisions']
+    if not decisions:
+        print("[info] Nenhuma mudança de tamanho do pool")
+        return
+
+    write_csv(decisions, args.framework, output_dir)
+    plot(decisions, autosize, args.framework, output_dir)
+    grown = sum(1 for d in decisions if d['to'] > d['from'])
+    print(f"[info] {grown} aumento(s), {len(decisions) - grown} redução(ões); "
+          f"tamanho final {decisions[-1]['to']}")
+
+if __name__ == '__main__':
+    main()
@@ -0,0 +1,71 @@
+#!/usr/bin/env bash
+# profile-target.sh — perf + flamegraph do processo de uma API em execução
+#
+# Anexa `perf record` ao processo principal do container (PID visto pelo host)
+# durante N segundos e converte as amostras num flamegraph SVG. Usa o inferno
+# (cargo install inferno) se disponível; senão, os scripts do FlameGraph
+# (stackcollapse-perf.pl / flamegraph.pl) no PATH.
+#
+# O desenrolamento de pilha usa DWARF (.eh_frame), que os binários de release
+# do Rust já incluem. Para Node/Bun os frames JIT aparecem sem nome a menos que
+# o runtime seja iniciado com --perf-basic-prof.
+#
+# Uso:
+#   ./scripts/profile-target.sh --container benchmark_actix --duration 60 \
+#                               --output results/<ts>/actix/flamegraph_energy.svg
+#
+# Variáveis de ambiente:
+#   PERF — comando do perf (default: perf; use "sudo perf" se necessário)
+#
+set -euo pipefail
+
+CONTAINER=""
+DURATION=60
+OUTPUT=""
+FREQ=99
+PERF="${PERF:-perf}"
+
+while [[ $# -gt 0 ]]; do
+  case $1 in
+    --container) CONTAINER="$2"; shift 2 ;;
+    --duration)  DURATION="$2";  shift 2 ;;
+    --output)    OUTPUT="$2";    shift 2 ;;
+    --freq)      FREQ="$2";      shift 2 ;;
+    *) echo "Argumento desconhecido: $1"; exit 1 ;;
+  esac
+done
+
+if [ -z "$CONTAINER" ] || [ -z "$OUTPUT" ]; then
+  echo "Uso: $0 --container NOME --duration N --output arquivo.svg [--freq 99]"
+  exit 1
+fi
+
+PID=$(docker inspect -f '{{.State.Pid}}' "$CONTAINER")
+if [ -z "$PID" ] || [ "$PID" -eq 0 ]; then
+  echo "Container $CONTAINER não está em execução" >&2
+  exit 1
+fi
+
+TMP_DIR=$(mktemp -d)
+trap 'rm -rf "$TMP_DIR"' EXIT
+
+$PERF record -F "$FREQ" --call-graph dwarf -p "$PID" -o "$TMP_DIR/perf.data" \
+  -- sleep "$DURATION" >/dev/null 2>&1
+
+$PERF script -i "$TMP_DIR/perf.data" 2>/dev/null > "$TMP_DIR/perf.script"
+
+mkdir -p "$(dirname "$OUTPUT")"
+TITLE="$CONTAINER — $(basename "$OUTPUT" .svg)"
+
+if command -v inferno-collapse-perf &>/dev/null; then
+  inferno-collapse-perf < "$TMP_DIR/perf.script" \
+    | inferno-flamegraph --title "$TITLE" > "$OUTPUT"
+elif command -v stackcollapse-perf.pl &>/dev/null; then
+  stackcollapse-perf.pl < "$TMP_DIR/perf.script" \
+    | flamegraph.pl --title "$TITLE" > "$OUTPUT"
+else
+  echo "inferno ou FlameGraph não encontrados — instale: cargo install inferno" >&2
+  exit 1
+fi
+
+echo "Flamegraph salvo em $OUTPUT"
@@ -0,0 +1,199 @@
+#!/usr/bin/env bash
+# replay.sh — repete uma execução a partir do seu run_manifest.json
+#
+# Todo orquestrador grava run_manifest.json (ver scripts/lib/manifest.sh) com
+# o comando, as sementes, o commit, as variáveis do compose e as imagens
+# medidas. Este script refaz a execução com tudo isso fixado:
+#
+#   1. confere que a árvore está no commit da execução (ou, com --checkout,
+#      cria um git worktree nele e aplica o repo.patch, se houver)
+#   2. reaponta as tags do compose para as imagens exatas da execução (pelo id
+#      local ou, se a imagem sumiu, pelo digest do registry)
+#   3. exporta as variáveis do compose, SEED e RANDOM_SEED
+#   4. roda o mesmo script com os mesmos argumentos; o novo resultado recebe um
+#      manifesto com replay_of apontando para a execução de origem
+#
+# Uso:
+#   ./scripts/replay.sh <run-id | results/<dir>> [--checkout] [--rebuild] [--dry-run]
+#
+# Opções:
+#   --checkout   roda num worktree no commit da execução quando a árvore atual
+#                difere dele; o resultado é movido para results/ ao final
+#   --rebuild    não reaproveita as imagens da execução (constrói de novo)
+#   --dry-run    mostra o que seria feito, sem rodar
+#
+# Limites: o sorteio é o mesmo por VU, mas o escalonamento das iterações nos
+# executores de taxa fixa depende do tempo; o hardware e o kernel não são
+# reproduzidos — compare o environment.json das duas execuções. O
+# run-allocator-sweep.sh sempre reconstrói a imagem do Actix, e no Kubernetes
+# o cluster baixa as imagens pela tag: confira os ids no manifesto novo.
+#
+set -euo pipefail
+
+RUN=""
+CHECKOUT=false
+REBUILD=false
+DRY_RUN=false
+
+while [[ $# -gt 0 ]]; do
+  case $1 in
+    --checkout) CHECKOUT=true; shift ;;
+    --rebuild)  REBUILD=true;  shift ;;
+    --dry-run)  DRY_RUN=true;  shift ;;
+    -*) echo "Opção desconhecida: $1"; exit 1 ;;
+    *)  RUN="$1"; shift ;;
+  esac
+done
+
+SCRIPT_DIR="$(cd "$(dirname "${BASH_SOURCE[0]}")" && pwd)"
+PROJECT_DIR="$(dirname "$SCRIPT_DIR")"
+
+CYAN='\033[0;36m'; YELLOW='\033[1;33m'; GREEN='\033[0;32m'
+RED='\033[0;31m';  BOLD='\033[1m';      NC='\033[0m'
+
+log()     { echo -e "${CYAN}[$(date +%H:%M:%S)]${NC} $*"; }
+success() { echo -e "${GREEN}[$(date +%H:%M:%S)] ✓${NC} $*"; }
+error()   { echo -e "${RED}[$(date +%H:%M:%S)] ✗${NC} $*" >&2; }
+warn()    { echo -e "${YELLOW}[$(date +%H:%M:%S)] !${NC} $*"; }
+header()  { echo -e "\n${BOLD}${YELLOW}=== $* ===${NC}\n"; }
+
+if [ -z "$RUN" ]; then
+  error "Informe a execução: ./scripts/replay.sh <run-id | results/<dir>>"
+  exit 1
+fi
+if [ -d "$RUN" ]; then
+  RUN_DIR="$(cd "$RUN" && pwd)"
+else
+  RUN_DIR="$PROJECT_DIR/results/$RUN"
+fi
+MANIFEST="$RUN_DIR/run_manifest.json"
+if [ ! -f "$MANIFEST" ]; then
+  error "Sem run_manifest.json em $RUN_DIR (execução anterior ao manifesto?)"
+  exit 1
+fi
+
+# Manifesto → variáveis do shell
+eval "$(python3 - "$MANIFEST" <<'PYEOF'
+import json, shlex, sys
+m = json.load(open(sys.argv[1]))
+q = shlex.quote
+print(f"RUN_ID={q(m['run_id'])}")
+print(f"SCRIPT={q(m['script'])}")
+print(f"RUN_CWD={q(m.get('cwd') or '')}")
+print(f"RUN_SHA={q(m['repo'].get('git_sha') or '')}")
+print(f"RUN_PATCH={q(m['repo'].get('patch') or '')}")
+print("RUN_ARGS=(" + " ".join(q(a) for a in m['args']) + ")")
+print("RUN_ENV=(" + " ".join(q(f'{k}={v}') for k, v in m['env'].items()) + ")")
+print(f"RUN_SEED={q(str(m['seeds']['k6']))}")
+print("RUN_IMAGES=(" + " ".join(q(f"{name}|{i['image']}|{i['id']}|{i.get('digest') or ''}")
+                               for name, i in m['images'].items()) + ")")
+PYEOF
+)"
+
+header "Replay de $RUN_ID"
+log "Comando: $SCRIPT ${RUN_ARGS[*]}"
+log "Commit: ${RUN_SHA:-?}${RUN_PATCH:+ + $RUN_PATCH}  SEED=$RUN_SEED"
+
+# ---------------------------------------------------------------------------
+# Árvore: a atual, se estiver no commit da execução, ou um worktree
+# ---------------------------------------------------------------------------
+
+TREE="$PROJECT_DIR"
+HEAD_SHA=$(git -C "$PROJECT_DIR" rev-parse HEAD)
+DIRTY=$(git -C "$PROJECT_DIR" status --porcelain --untracked-files=no)
+if [ "$HEAD_SHA" != "$RUN_SHA" ] || [ -n "$DIRTY" ] || [ -n "$RUN_PATCH" ]; then
+  if ! $CHECKOUT; then
+    error "A árvore atual (${HEAD_SHA:0:12}${DIRTY:+ com mudanças}) não é a da execução (${RUN_SHA:0:12}${RUN_PATCH:+ + $RUN_PATCH})"
+    error "Use --checkout para rodar num worktree no commit da execução"
+    exit 1
+  fi
+  # Mesmo nome de diretório: o compose deriva dele o nome do projeto e das imagens
+  TREE="${TMPDIR:-/tmp}/replay_$RUN_ID/$(basename "$PROJECT_DIR")"
+  log "Worktree em $TREE"
+  if ! $DRY_RUN; then
+    git -C "$PROJECT_DIR" worktree add --detach "$TREE" "$RUN_SHA" >/dev/null
+    trap 'git -C "$PROJECT_DIR" worktree remove --force "$TREE"; rmdir "$(dirname "$TREE")"' EXIT
+    if [ -n "$RUN_PATCH" ]; then
+      git -C "$TREE" apply "$RUN_DIR/$RUN_PATCH"
+      log "Aplicado $RUN_PATCH"
+    fi
+  fi
+fi
+
+# ---------------------------------------------------------------------------
+# Imagens
+# ---------------------------------------------------------------------------
+
+# O docker das imagens é o do alvo da execução (--remote)
+for ((i = 0; i < ${#RUN_ARGS[@]}; i++)); do
+  if [ "${RUN_ARGS[$i]}" = --remote ]; then
+    case "${RUN_ARGS[$((i + 1))]:-}" in
+      ssh://*)   export DOCKER_HOST="${RUN_ARGS[$((i + 1))]}" ;;
+      context:*) export DOCKER_CONTEXT="${RUN_ARGS[$((i + 1))]#context:}" ;;
+    esac
+  fi
+done
+
+RESTORED=true
+if $REBUILD; then
+  RESTORED=false
+elif [ "$SCRIPT" = scripts/run-k8s.sh ]; then
+  RESTORED=false
+  warn "No Kubernetes as imagens vêm do registry pela tag; confira os ids no manifesto novo"
+else
+  for entry in "${RUN_IMAGES[@]+"${RUN_IMAGES[@]}"}"; do
+    IFS='|' read -r name ref id digest <<< "$entry"
+    if [[ $name == *@* ]]; then
+      warn "$name: reconstruída pelo próprio script"
+      continue
+    fi
+    if ! docker image inspect "$id" &>/dev/null && [ -n "$digest" ]; then
+      log "$name: baixando $digest"
+      $DRY_RUN || docker pull -q "$digest" >/dev/null || true
+      id="$digest"
+    fi
+    if docker image inspect "$id" &>/dev/null; then
+      log "$name: $ref → ${id:0:19}"
+      $DRY_RUN || docker tag "$id" "$ref"
+    else
+      warn "$name: imagem ${id:0:19} não existe mais; o compose vai construí-la (ou baixá-la) de novo"
+      RESTORED=false
+    fi
+  done
+fi
+
+ARGS=("${RUN_ARGS[@]+"${RUN_ARGS[@]}"}")
+if [ "$SCRIPT" = scripts/run-experiment.sh ] && $RESTORED && [[ " ${ARGS[*]-} " != *" --skip-build "* ]]; then
+  ARGS+=(--skip-build)
+fi
+
+# ---------------------------------------------------------------------------
+# Execução
+# ---------------------------------------------------------------------------
+
+for kv in "${RUN_ENV[@]+"${RUN_ENV[@]}"}"; do
+  export "${kv?}"
+done
+export SEED="$RUN_SEED" REPLAY_OF="$RUN_ID"
+
+if $DRY_RUN; then
+  log "Ambiente: ${RUN_ENV[*]-} SEED=$SEED REPLAY_OF=$REPLAY_OF"
+  log "Rodaria: (cd ${RUN_CWD:-$PROJECT_DIR} && bash $TREE/$SCRIPT ${ARGS[*]-})"
+  exit 0
+fi
+
+[ -d "$RUN_CWD" ] || RUN_CWD="$PROJECT_DIR"
+BEFORE=$(ls "$TREE/results" 2>/dev/null || true)
+STATUS=0
+(cd "$RUN_CWD" && bash "$TREE/$SCRIPT" "${ARGS[@]+"${ARGS[@]}"}") || STATUS=$?
+
+if [ "$TREE" != "$PROJECT_DIR" ]; then
+  mkdir -p "$PROJECT_DIR/results"
+  for dir in $(ls "$TREE/results" 2>/dev/null); do
+    if ! grep -qx "$dir" <<< "$BEFORE"; then
+      mv "$TREE/results/$dir" "$PROJECT_DIR/results/"
+      success "Resultado do replay: results/$dir"
+    fi
+  done
+fi
+exit "$STATUS"
@@ -0,0 +1,198 @@
+#!/usr/bin/env python3
+"""
+report-results.py — compara dois conjuntos de resultados (base × head).
+
+Cada lado é um ou mais diretórios de resultados; o CSV de resumo de cada um
+é lido como em store-results.py (mediana das rodadas por alvo e métrica) e as
+suítes presentes nos dois lados são comparadas alvo a alvo.
+
+Formatos:
+  gh-comment   Markdown para colar num PR ou issue do GitHub: uma tabela
+               compacta com vazão e p99 por suíte/alvo e, por suíte, um bloco
+               <details> recolhível com todas as métricas comparáveis.
+
+Cada delta recebe um indicador de acordo com o sentido da métrica (vazão:
+maior é melhor; latência, erros, CPU, memória, energia: menor é melhor):
+  🟢 melhora além do limiar   🔴 piora além do limiar   ⚪ dentro do limiar
+Colunas de configuração (taxa alvo, contadores do RAPL, duração) não entram.
+
+Uso:
+  python3 scripts/report-results.py --format gh-comment \\
+    --base results/latency_20250101_120000 \\
+    --head results/latency_20250102_090000 results/20250102_100000 \\
+    --output comment.md
+
+  --threshold   variação em % abaixo da qual o delta é ruído (default: 2)
+"""
+
+import argparse
+import importlib.util
+import sys
+from pathlib import Path
+
+SCRIPTS_DIR = Path(__file__).resolve().parent
+
+# read_run() de store-results.py: mesma leitura de resumo e metadados
+_spec = importlib.util.spec_from_file_location('store_results', SCRIPTS_DIR / 'store-results.py')
+store_results = importlib.util.module_from_spec(_spec)
+_spec.loader.exec_module(store_results)
+
+HIGHER_IS_BETTER = {'rps', 'achieved_rps', 'rps_per_watt', 'rps_per_usd'}
+
+LOWER_IS_BETTER = {
+    'error_rate', 'errors', 'dropped_iterations', 'status_5xx', 'status_0', 'schema_mismatches',
+    'power_watts', 'energy_uj', 'energy_j', 'joules_per_1k_req', 'cpu_pct', 'mem_mb',
+    'api_cpu_avg_m', 'api_mem_max_mi', 'db_cpu_avg_m',
+    'context_switches', 'syscalls', 'cache_misses', 'branch_misses',
+    'rss_end_mb', 'rss_slope_mb_per_h', 'p99_slope_ms_per_h', 'p99_drift_pct_per_h',
+}
+
+# Não são medidas de desempenho, mesmo terminando em _ms
+CONFIGURATION = {'elapsed_ms'}
+
+HEADLINE = [('rps', 'RPS'), ('p99_ms', 'P99 (ms)')]
+
+
+def parse_args():
+    p = argparse.ArgumentParser(description="Compara dois conjuntos de resultados (base × head)")
+    p.add_argument('--base',      nargs='+', required=True, help="Diretórios de resultados da base")
+    p.add_argument('--head',      nargs='+', required=True, help="Diretórios de resultados do head")
+    p.add_argument('--format',    choices=['gh-comment'], default='gh-comment', help="Formato do relatório")
+    p.add_argument('--output',    required=True, help="Arquivo de saída")
+    p.add_argument('--threshold', type=float, default=2.0,
+                   help="Variação em %% considerada ruído (default: 2)")
+    return p.parse_args()
+
+
+def direction(metric: str) -> int:
+    """+1 se maior é melhor, -1 se menor é melhor, 0 se não é comparável."""
+    if metric in HIGHER_IS_BETTER:
+        return 1
+    if metric in LOWER_IS_BETTER or (metric.endswith('_ms') and metric not in CONFIGURATION):
+        return -1
+    return 0
+
+
+def load_side(dirs: list, label: str) -> dict:
+    """suíte → (metadados da execução, métricas); a última execução de cada suíte vence."""
+    side = {}
+    for d in dirs:
+        results_dir = Path(d).resolve()
+        run = store_results.read_run(results_dir) if results_dir.is_dir() else None
+        if run is None:
+            print(f"[aviso] {label}: {d}: nenhum CSV de resumo conhecido — ignorado", file=sys.stderr)
+            continue
+        suite = run[0]['suite']
+        if suite in side:
+            print(f"[aviso] {label}: mais de uma execução de {suite} — usando {results_dir.name}", file=sys.stderr)
+        side[suite] = run
+    return side
+
+
+def delta(metric: str, base: float, head: float, threshold: float):
+    """(variação em %, indicador); variação None quando a base é zero."""
+    if base == 0:
+        return None, '⚪' if head == 0 else ('🔴' if direction(metric) < 0 else '🟢')
+    pct = (head - base) / abs(base) * 100
+    if abs(pct) < threshold:
+        return pct, '⚪'
+    return pct, '🟢' if pct * direction(metric) > 0 else '🔴'
+
+
+def format_value(value: float) -> str:
+    if value == int(value) and abs(value) >= 1:
+        return f"{value:.0f}"
+    if abs(value) >= 100:
+        return f"{value:.1f}"
+    return f"{value:.3g}" if abs(value) < 1 else f"{value:.2f}"
+
+
+def format_delta(pct, emoji: str) -> str:
+    return f"{emoji} n/a" if pct is None else f"{emoji} {pct:+.1f}%"
+
+
+def describe(run: dict) -> str:
+    sha = (run['git_sha'] or '')[:8] or '?'
+    dirty = ' (sujo)' if run['dirty'] else ''
+    return f"`{sha}`{dirty} {run['run_id']}"
+
+
+def gh_comment(base: dict, head: dict, threshold: float) -> str:
+    suites = [s for s in base if s in head]
+    lines = ["## Benchmark: base × head", ""]
+
+    counts = {'🟢': 0, '🔴': 0}
+    headline_rows = []
+    details = []
+    for suite in suites:
+        (base_run, base_metrics), (head_run, head_metrics) = base[suite], head[suite]
+        keys = sorted(k for k in base_metrics if k in head_metrics and direction(k[1]) != 0)
+        # `rps` é cópia de `achieved_rps` nas suítes de taxa fixa; basta uma no detalhe
+        detail_keys = [(t, m) for t, m in keys if not (m == 'rps' and (t, 'achieved_rps') in base_metrics)]
+        targets = sorted({target for target, _ in keys})
+
+        for target in targets:
+            cells = []
+            for metric, _ in HEADLINE:
+                key = (target, metric)
+                if key in base_metrics and key in head_metrics:
+                    pct, emoji = delta(metric, base_metrics[key], head_metrics[key], threshold)
+                    cells.append(f"{format_value(head_metrics[key])} | {format_delta(pct, emoji)}")
+                else:
+                    cells.append("– | –")
+            headline_rows.append(f"| {suite} | {target} | {' | '.join(cells)} |")
+
+        rows = []
+        for target, metric in detail_keys:
+            b, h = base_metrics[(target, metric)], head_metrics[(target, metric)]
+            pct, emoji = delta(metric, b, h, threshold)
+            if emoji in counts:
+                counts[emoji] += 1
+            rows.append(f"| {target} | `{metric}` | {format_value(b)} | {format_value(h)} | {format_delta(pct, emoji)} |")
+
+        details += [
+            "<details>",
+            f"<summary><b>{suite}</b> — base {describe(base_run)} → head {describe(head_run)}</summary>",
+            "",
+            "| Alvo | Métrica | Base | Head | Δ |",
+            "|---|---|---:|---:|---:|",
+            *rows,
+            "",
+            "</details>",
+            "",
+        ]
+
+    lines.append(f"{counts['🟢']} melhora(s) 🟢, {counts['🔴']} piora(s) 🔴 "
+                 f"(limiar ±{threshold:g}%; ⚪ dentro do limiar)")
+    lines.append("")
+    header = ' | '.join(f"{label} | Δ" for _, label in HEADLINE)
+    lines.append(f"| Suíte | Alvo | {header} |")
+    lines.append("|---|---|" + "---:|---:|" * len(HEADLINE))
+    lines += headline_rows
+    lines.append("")
+    lines += details
+
+    only = sorted(set(base) ^ set(head))
+    if only:
+        lines.append(f"_Sem par para comparar: {', '.join(only)}._")
+        lines.append("")
+    return "\n".join(lines)
+
+
+def main():
+    args = parse_args()
+    base = load_side(args.base, 'base')
+    head = load_side(args.head, 'head')
+    if not any(s in head for s in base):
+        print("[erro] nenhuma suíte presente na base e no head", file=sys.stderr)
+        sys.exit(1)
+
+    report = gh_comment(base, head, args.threshold)
+    output = Path(args.output)
+    output.parent.mkdir(parents=True, exist_ok=True)
+    output.write_text(report)
+    print(f"[ok] Comparação salva em {output}")
+
+
+if __name__ == '__main__':
+    main()
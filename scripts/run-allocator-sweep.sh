@@ -0,0 +1,279 @@
+#!/usr/bin/env bash
+# run-allocator-sweep.sh — varredura do alocador global da API Actix
+#
+# O alocador escolhido em tempo de build (features `mimalloc` / `jemalloc` do
+# api-actix; nenhuma = malloc do sistema) muda o throughput em dezenas de
+# por cento. Para cada alocador o script reconstrói a imagem com
+# ACTIX_FEATURES=<feature> e mede:
+#
+#   1. RPS máximo sustentável (rampa load-test-saturation.js + find-saturation.py)
+#   2. latência a taxa fixa (load-test-latency.js) — --rps, ou --load-pct do
+#      RPS máximo daquele alocador
+#   3. memória média e de pico (docker stats) durante a taxa fixa
+#
+# Os demais frameworks não têm alocador selecionável (Node/Bun/Go usam o do
+# próprio runtime) e ficam fora da varredura.
+#
+# Uso:
+#   ./scripts/run-allocator-sweep.sh [--allocators "system mimalloc jemalloc"]
+#                                    [--rps N | --load-pct 70] [--duration 120s]
+#
+# Opções:
+#   --allocators "a b"  alocadores a comparar (default: system mimalloc jemalloc)
+#   --rps N             taxa fixa da fase de latência (ignora --load-pct)
+#   --load-pct N        % do RPS máximo de cada alocador (default: 70)
+#   --duration Xs       duração da fase de latência (default: 120s)
+#   --warmup Xs         warm-up antes da fase de latência (default: 30s)
+#   --max-rps N         teto da rampa de saturação (default: 5000)
+#   --step-rps N        incremento por degrau (default: 200)
+#   --step-duration Xs  duração de cada degrau (default: 30s)
+#
+set -euo pipefail
+
+# ---------------------------------------------------------------------------
+# Parâmetros (com defaults)
+# ---------------------------------------------------------------------------
+
+ALLOCATORS="system mimalloc jemalloc"
+FIXED_RPS=0
+LOAD_PCT=70
+DURATION=120s
+WARMUP_DURATION=30s
+
+SAT_START_RPS=200
+SAT_STEP_RPS=200
+SAT_MAX_RPS=5000
+SAT_STEP_DURATION=30s
+
+ARGS=("$@")
+while [[ $# -gt 0 ]]; do
+  case $1 in
+    --allocators)    ALLOCATORS="$2";        shift 2 ;;
+    --rps)           FIXED_RPS="$2";         shift 2 ;;
+    --load-pct)      LOAD_PCT="$2";          shift 2 ;;
+    --duration)      DURATION="$2";          shift 2 ;;
+    --warmup)        WARMUP_DURATION="$2";   shift 2 ;;
+    --max-rps)       SAT_MAX_RPS="$2";       shift 2 ;;
+    --step-rps)      SAT_STEP_RPS="$2";      shift 2 ;;
+    --step-duration) SAT_STEP_DURATION="$2"; shift 2 ;;
+    *) echo "Argumento desconhecido: $1"; exit 1 ;;
+  esac
+done
+
+# ---------------------------------------------------------------------------
+# Caminhos
+# ---------------------------------------------------------------------------
+
+SCRIPT_DIR="$(cd "$(dirname "${BASH_SOURCE[0]}")" && pwd)"
+PROJECT_DIR="$(dirname "$SCRIPT_DIR")"
+RESULTS_DIR="$PROJECT_DIR/results/allocators_$(date +%Y%m%d_%H%M%S)"
+
+SERVICE=api-actix
+API_URL="http://localhost:3004"
+
+# Commit embutido na imagem do Actix e reportado por GET /config
+export GIT_SHA="${GIT_SHA:-$(git -C "$PROJECT_DIR" rev-parse --short=12 HEAD 2>/dev/null || true)}"
+
+mkdir -p "$RESULTS_DIR"
+
+# ---------------------------------------------------------------------------
+# Cores e helpers de log
+# ---------------------------------------------------------------------------
+
+CYAN='\033[0;36m'; YELLOW='\033[1;33m'; GREEN='\033[0;32m'
+RED='\033[0;31m';  BOLD='\033[1m';      NC='\033[0m'
+
+log()     { echo -e "${CYAN}[$(date +%H:%M:%S)]${NC} $*"; }
+success() { echo -e "${GREEN}[$(date +%H:%M:%S)] ✓${NC} $*"; }
+error()   { echo -e "${RED}[$(date +%H:%M:%S)] ✗${NC} $*" >&2; }
+warn()    { echo -e "${YELLOW}[$(date +%H:%M:%S)] !${NC} $*"; }
+header()  { echo -e "\n${BOLD}${YELLOW}=== $* ===${NC}\n"; }
+
+source "$SCRIPT_DIR/lib/fingerprint.sh"
+source "$SCRIPT_DIR/lib/manifest.sh"
+fingerprint_write "$RESULTS_DIR/environment.json"
+manifest_write "$RESULTS_DIR/run_manifest.json" "${ARGS[@]}"
+
+# Feature do cargo correspondente a cada alocador
+declare -A ALLOCATOR_FEATURES=(
+  [system]=""
+  [mimalloc]=mimalloc
+  [jemalloc]=jemalloc
+)
+
+wait_api() {
+  for i in $(seq 1 30); do
+    if curl -sf "$API_URL/" &>/dev/null 2>&1; then
+      return 0
+    fi
+    sleep 1
+  done
+  return 1
+}
+
+# ---------------------------------------------------------------------------
+# Inicia PostgreSQL
+# ---------------------------------------------------------------------------
+
+header "Iniciando PostgreSQL"
+cd "$PROJECT_DIR"
+docker compose up -d postgres
+
+for i in $(seq 1 30); do
+  if docker compose exec -T postgres pg_isready -U benchmark -d benchmark &>/dev/null 2>&1; then
+    success "PostgreSQL pronto (tentativa $i)"
+    break
+  fi
+  if [ "$i" -eq 30 ]; then
+    error "PostgreSQL não ficou pronto em 30s"
+    exit 1
+  fi
+  sleep 1
+done
+
+# ---------------------------------------------------------------------------
+# Varredura
+# ---------------------------------------------------------------------------
+
+SUMMARY_CSV="$RESULTS_DIR/allocator_summary.csv"
+echo "allocator,max_rps,target_rps,achieved_rps,p50_ms,p99_ms,p999_ms,error_rate,mem_avg_mb,mem_peak_mb" > "$SUMMARY_CSV"
+
+for ALLOCATOR in $ALLOCATORS; do
+  if [ -z "${ALLOCATOR_FEATURES[$ALLOCATOR]+x}" ]; then
+    error "Alocador desconhecido: $ALLOCATOR (use system, mimalloc ou jemalloc)"
+    exit 1
+  fi
+  ALLOC_DIR="$RESULTS_DIR/$ALLOCATOR"
+  mkdir -p "$ALLOC_DIR"
+
+  header "Alocador: $ALLOCATOR"
+
+  log "[$ALLOCATOR] build com ACTIX_FEATURES=\"${ALLOCATOR_FEATURES[$ALLOCATOR]}\"..."
+  ACTIX_FEATURES="${ALLOCATOR_FEATURES[$ALLOCATOR]}" docker compose build "$SERVICE" >/dev/null
+  ACTIX_FEATURES="${ALLOCATOR_FEATURES[$ALLOCATOR]}" docker compose up -d --force-recreate "$SERVICE"
+
+  if ! wait_api; then
+    error "[$ALLOCATOR] API não respondeu em 30s — pulando"
+    docker compose stop "$SERVICE"
+    continue
+  fi
+  curl -sf "$API_URL/config" > "$ALLOC_DIR/config.json" 2>/dev/null || rm -f "$ALLOC_DIR/config.json"
+  manifest_image "$RESULTS_DIR/run_manifest.json" "$SERVICE" "$SERVICE@$ALLOCATOR"
+  REPORTED=$(curl -sf "$API_URL/" | python3 -c "import json,sys; print(json.load(sys.stdin).get('allocator','?'))")
+  if [ "$REPORTED" != "$ALLOCATOR" ]; then
+    warn "[$ALLOCATOR] a API reporta allocator=$REPORTED"
+  fi
+
+  # --- 1. Saturação ---
+  log "[$ALLOCATOR] saturação: ${SAT_START_RPS}→${SAT_MAX_RPS} req/s..."
+  SAT_CSV="$ALLOC_DIR/saturation_actix.csv"
+  k6 run \
+    -e API_URL="$API_URL" \
+    -e START_RPS="$SAT_START_RPS" \
+    -e STEP_RPS="$SAT_STEP_RPS" \
+    -e MAX_RPS="$SAT_MAX_RPS" \
+    -e STEP_DURATION="$SAT_STEP_DURATION" \
+    --out "csv=$SAT_CSV" \
+    --quiet \
+    "$SCRIPT_DIR/load-test-saturation.js" >/dev/null || true
+
+  MAX_RPS=$(python3 "$SCRIPT_DIR/find-saturation.py" \
+    --csv "$SAT_CSV" \
+    --start-rps "$SAT_START_RPS" \
+    --step-rps  "$SAT_STEP_RPS" \
+    --step-duration "$(echo "$SAT_STEP_DURATION" | tr -d 's')" \
+    --framework "actix-$ALLOCATOR" \
+    --output-dir "$ALLOC_DIR" \
+    2>/dev/null | grep '^RPS_MAX_SUSTAINABLE=' | cut -d= -f2 || echo "0")
+  MAX_RPS=${MAX_RPS:-0}
+  echo "$MAX_RPS" > "$ALLOC_DIR/max_sustainable_rps.txt"
+  success "[$ALLOCATOR] RPS máximo sustentável: ${MAX_RPS} req/s"
+
+  # --- 2. Latência a taxa fixa ---
+  if [ "$FIXED_RPS" -gt 0 ]; then
+    TARGET_RPS=$FIXED_RPS
+  else
+    TARGET_RPS=$(( MAX_RPS * LOAD_PCT / 100 ))
+  fi
+  if [ "$TARGET_RPS" -lt 1 ]; then
+    warn "[$ALLOCATOR] RPS alvo inválido — pulando fase de latência"
+    docker compose stop "$SERVICE"
+    continue
+  fi
+
+  # Recria o container: a rampa deixou o heap no pico e isso distorceria a memória
+  docker compose up -d --force-recreate "$SERVICE" >/dev/null
+  wait_api || true
+
+  log "[$ALLOCATOR] warm-up de $WARMUP_DURATION a ${TARGET_RPS} req/s..."
+  k6 run \
+    -e API_URL="$API_URL" \
+    -e TARGET_RPS="$TARGET_RPS" \
+    -e DURATION="$WARMUP_DURATION" \
+    --quiet \
+    "$SCRIPT_DIR/load-test-latency.js" >/dev/null || true
+
+  STATS_FILE="$ALLOC_DIR/docker_stats.csv"
+  echo "timestamp,mem_mb" > "$STATS_FILE"
+  docker stats --no-trunc --format "{{.MemUsage}}" "$SERVICE" 2>/dev/null \
+    | awk '{
+        t=systime();
+        mem=$0; sub(/ \/ .*/,"",mem);
+        val=mem; gsub(/[^0-9.]/,"",val); val=val+0;
+        if (mem ~ /GiB/) val=val*1024;
+        else if (mem ~ /KiB/) val=val/1024;
+        printf "%d,%.1f\n", t, val
+      }' >> "$STATS_FILE" &
+  STATS_PID=$!
+
+  LAT_JSON="$ALLOC_DIR/latency.json"
+  log "[$ALLOCATOR] latência a ${TARGET_RPS} req/s por $DURATION..."
+  k6 run \
+    -e API_URL="$API_URL" \
+    -e TARGET_RPS="$TARGET_RPS" \
+    -e DURATION="$DURATION" \
+    -e SUMMARY_FILE="$LAT_JSON" \
+    --quiet \
+    "$SCRIPT_DIR/load-test-latency.js" || true
+
+  kill "$STATS_PID" 2>/dev/null || true
+  wait "$STATS_PID" 2>/dev/null || true
+
+  if [ -f "$LAT_JSON" ]; then
+    python3 - "$LAT_JSON" "$STATS_FILE" "$ALLOCATOR" "$MAX_RPS" >> "$SUMMARY_CSV" <<'PYEOF'
+import csv, json, sys
+path, stats, allocator, max_rps = sys.argv[1:5]
+with open(path) as f: s = json.load(f)
+with open(stats) as f: mem = [float(r['mem_mb']) for r in csv.DictReader(f)]
+print(",".join([
+    allocator, max_rps, str(s['target_rps']),
+    f"{s['achieved_rps']:.2f}", f"{s['p50_ms']:.2f}", f"{s['p99_ms']:.2f}", f"{s['p999_ms']:.2f}",
+    f"{s['error_rate']*100:.4f}",
+    f"{sum(mem)/len(mem):.1f}" if mem else "", f"{max(mem):.1f}" if mem else "",
+]))
+PYEOF
+  else
+    warn "[$ALLOCATOR] k6 não gerou $LAT_JSON"
+  fi
+
+  docker compose stop "$SERVICE"
+  sleep 3
+done
+
+# Deixa a imagem padrão (malloc do sistema) para as execuções seguintes
+log "Reconstruindo $SERVICE com o alocador padrão..."
+docker compose build "$SERVICE" >/dev/null
+
+fingerprint_builds "$RESULTS_DIR/environment.json"
+manifest_image "$RESULTS_DIR/run_manifest.json" postgres
+
+# Histórico opcional (RESULTS_DB=results/history.sqlite ou postgresql://...)
+if [ -n "${RESULTS_DB:-}" ]; then
+  python3 "$SCRIPT_DIR/store-results.py" --db "$RESULTS_DB" "$RESULTS_DIR" \
+    || warn "Falha ao gravar no histórico ($RESULTS_DB)"
+fi
+
+header "Resultado"
+column -t -s, "$SUMMARY_CSV"
+echo ""
+success "Resultados em: $RESULTS_DIR"
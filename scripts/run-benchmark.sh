@@ -7,6 +7,9 @@ set -e
 RESULTS_DIR="./results/$(date +%Y%m%d_%H%M%S)"
 mkdir -p "$RESULTS_DIR"
 
+source "$(dirname "$0")/lib/fingerprint.sh"
+fingerprint_write "$RESULTS_DIR/environment.json"
+
 APIS=(
   "express:3001"
   "fastify:3002"
@@ -38,6 +41,9 @@ for api_port in "${APIS[@]}"; do
     sleep 2
   done
 
+  # Build da API (só o Actix expõe /config)
+  curl -sf "${BASE_URL}/config" > "${RESULTS_DIR}/config_${API_NAME}.json" 2>/dev/null || rm -f "${RESULTS_DIR}/config_${API_NAME}.json"
+
   # Executa k6
   k6 run \
     --env BASE_URL="${BASE_URL}" \
@@ -49,6 +55,8 @@ for api_port in "${APIS[@]}"; do
   echo "    Resultado salvo em: ${RESULTS_DIR}/${API_NAME}_summary.json"
 done
 
+fingerprint_builds "$RESULTS_DIR/environment.json"
+
 echo ""
 echo "======================================"
 echo " Benchmark concluído!"
@@ -0,0 +1,290 @@
+#!/usr/bin/env bash
+# run-chaos.sh — injeção de falhas no PostgreSQL e degradação de cada framework
+#
+# Mede como cada API se comporta quando o banco fica lento ou instável:
+# timeouts, propagação de erros (4xx/5xx) e esgotamento do pool. Para cada
+# framework roda duas fases com a mesma taxa fixa (load-test-latency.js):
+#
+#   baseline — banco saudável
+#   fault    — banco com a falha injetada
+#
+# Cenários:
+#   latency  — atraso (+ jitter) em todo pacote que sai do container postgres
+#   loss     — perda de pacotes na interface do postgres
+#   outage   — `docker pause` no postgres por --outage-secs no meio da fase
+#
+# Os cenários de rede usam `tc netem` num container auxiliar que compartilha o
+# namespace de rede do postgres (--net container:...), sem alterar a imagem
+# do banco. É necessário permissão para --cap-add NET_ADMIN.
+#
+# Uso:
+#   ./scripts/run-chaos.sh --scenario latency --delay 50ms --jitter 10ms --rps 500
+#   ./scripts/run-chaos.sh --scenario loss --loss 5% --rps 500 --frameworks "actix"
+#   ./scripts/run-chaos.sh --scenario outage --outage-secs 15 --rps 500
+#
+# Opções:
+#   --scenario S       latency | loss | outage (default: latency)
+#   --delay D          atraso do netem (default: 50ms)
+#   --jitter D         variação do atraso (default: 10ms)
+#   --loss P           perda de pacotes (default: 5%)
+#   --outage-secs N    duração da pausa do postgres (default: 15)
+#   --rps N            taxa fixa (default: 500)
+#   --duration Xs      duração de cada fase (default: 60s)
+#   --req-timeout Xs   timeout por requisição no k6 (default: 10s)
+#   --frameworks "a b" subconjunto de frameworks (default: todos)
+#
+set -euo pipefail
+
+# ---------------------------------------------------------------------------
+# Parâmetros (com defaults)
+# ---------------------------------------------------------------------------
+
+SCENARIO=latency
+DELAY=50ms
+JITTER=10ms
+LOSS=5%
+OUTAGE_SECS=15
+TARGET_RPS=500
+DURATION=60s
+REQ_TIMEOUT=10s
+FRAMEWORKS=""
+NETEM_IMAGE="${NETEM_IMAGE:-nicolaka/netshoot}"
+PG_CONTAINER=benchmark_postgres
+
+ARGS=("$@")
+while [[ $# -gt 0 ]]; do
+  case $1 in
+    --scenario)    SCENARIO="$2";    shift 2 ;;
+    --delay)       DELAY="$2";       shift 2 ;;
+    --jitter)      JITTER="$2";      shift 2 ;;
+    --loss)        LOSS="$2";        shift 2 ;;
+    --outage-secs) OUTAGE_SECS="$2"; shift 2 ;;
+    --rps)         TARGET_RPS="$2";  shift 2 ;;
+    --duration)    DURATION="$2";    shift 2 ;;
+    --req-timeout) REQ_TIMEOUT="$2"; shift 2 ;;
+    --frameworks)  FRAMEWORKS="$2";  shift 2 ;;
+    *) echo "Argumento desconhecido: $1"; exit 1 ;;
+  esac
+done
+
+case $SCENARIO in
+  latency|loss|outage) ;;
+  *) echo "Cenário inválido: $SCENARIO (use latency, loss ou outage)"; exit 1 ;;
+esac
+
+# ---------------------------------------------------------------------------
+# Caminhos
+# ---------------------------------------------------------------------------
+
+SCRIPT_DIR="$(cd "$(dirname "${BASH_SOURCE[0]}")" && pwd)"
+PROJECT_DIR="$(dirname "$SCRIPT_DIR")"
+RESULTS_DIR="$PROJECT_DIR/results/chaos_${SCENARIO}_$(date +%Y%m%d_%H%M%S)"
+LOAD_TEST_SCRIPT="$SCRIPT_DIR/load-test-latency.js"
+
+mkdir -p "$RESULTS_DIR"
+
+# ---------------------------------------------------------------------------
+# Cores e helpers de log
+# ---------------------------------------------------------------------------
+
+CYAN='\033[0;36m'; YELLOW='\033[1;33m'; GREEN='\033[0;32m'
+RED='\033[0;31m';  BOLD='\033[1m';      NC='\033[0m'
+
+log()     { echo -e "${CYAN}[$(date +%H:%M:%S)]${NC} $*"; }
+success() { echo -e "${GREEN}[$(date +%H:%M:%S)] ✓${NC} $*"; }
+error()   { echo -e "${RED}[$(date +%H:%M:%S)] ✗${NC} $*" >&2; }
+warn()    { echo -e "${YELLOW}[$(date +%H:%M:%S)] !${NC} $*"; }
+header()  { echo -e "\n${BOLD}${YELLOW}=== $* ===${NC}\n"; }
+
+source "$SCRIPT_DIR/lib/fingerprint.sh"
+source "$SCRIPT_DIR/lib/manifest.sh"
+fingerprint_write "$RESULTS_DIR/environment.json"
+manifest_write "$RESULTS_DIR/run_manifest.json" "${ARGS[@]}"
+
+# Converte "90s" / "2m" em segundos
+to_secs() {
+  case $1 in
+    *m) echo $(( ${1%m} * 60 )) ;;
+    *s) echo "${1%s}" ;;
+    *)  echo "$1" ;;
+  esac
+}
+
+# Executa `tc` dentro do namespace de rede do postgres
+netem() {
+  docker run --rm --net "container:$PG_CONTAINER" --cap-add NET_ADMIN \
+    "$NETEM_IMAGE" tc "$@"
+}
+
+fault_on() {
+  case $SCENARIO in
+    latency) netem qdisc replace dev eth0 root netem delay "$DELAY" "$JITTER" ;;
+    loss)    netem qdisc replace dev eth0 root netem loss "$LOSS" ;;
+    outage)  ;;  # a pausa é agendada durante a própria fase
+  esac
+}
+
+fault_off() {
+  case $SCENARIO in
+    latency|loss) netem qdisc del dev eth0 root 2>/dev/null || true ;;
+    outage)       docker unpause "$PG_CONTAINER" 2>/dev/null || true ;;
+  esac
+}
+
+# Garante que a falha não sobreviva a um Ctrl+C
+trap fault_off EXIT
+
+# run_phase <framework> <fase> <url>
+# As falhas são o objeto do teste: o orçamento de erros (lib/errors.js) fica desligado.
+run_phase() {
+  local fw="$1" phase="$2" url="$3"
+  k6 run \
+    -e API_URL="$url" \
+    -e TARGET_RPS="$TARGET_RPS" \
+    -e DURATION="$DURATION" \
+    -e REQ_TIMEOUT="$REQ_TIMEOUT" \
+    -e SUMMARY_FILE="$RESULTS_DIR/${fw}_${phase}.json" \
+    -e ERROR_BUDGET=1 \
+    --quiet \
+    "$LOAD_TEST_SCRIPT" || true
+}
+
+# ---------------------------------------------------------------------------
+# Frameworks definidos
+# ---------------------------------------------------------------------------
+
+declare -A FRAMEWORK_PORTS=(
+  [express]=3001
+  [fastify]=3002
+  [elysia]=3003
+  [actix]=3004
+  [gin]=3005
+)
+
+declare -A FRAMEWORK_SERVICES=(
+  [express]=api-express
+  [fastify]=api-fastify
+  [elysia]=api-elysia
+  [actix]=api-actix
+  [gin]=api-gin
+)
+
+if [ -n "$FRAMEWORKS" ]; then
+  read -r -a FRAMEWORK_ORDER <<< "$FRAMEWORKS"
+else
+  FRAMEWORK_ORDER=(express fastify elysia actix gin)
+fi
+
+# ---------------------------------------------------------------------------
+# Inicia PostgreSQL
+# ---------------------------------------------------------------------------
+
+header "Iniciando PostgreSQL"
+cd "$PROJECT_DIR"
+docker compose up -d postgres
+
+for i in $(seq 1 30); do
+  if docker compose exec -T postgres pg_isready -U benchmark -d benchmark &>/dev/null 2>&1; then
+    success "PostgreSQL pronto (tentativa $i)"
+    break
+  fi
+  if [ "$i" -eq 30 ]; then
+    error "PostgreSQL não ficou pronto em 30s"
+    exit 1
+  fi
+  sleep 1
+done
+
+case $SCENARIO in
+  latency) log "Falha: atraso de $DELAY ± $JITTER no postgres" ;;
+  loss)    log "Falha: perda de $LOSS dos pacotes do postgres" ;;
+  outage)  log "Falha: postgres pausado por ${OUTAGE_SECS}s no meio da fase" ;;
+esac
+
+# ---------------------------------------------------------------------------
+# Fases por framework
+# ---------------------------------------------------------------------------
+
+SUMMARY_CSV="$RESULTS_DIR/chaos_summary.csv"
+echo "framework,scenario,phase,target_rps,achieved_rps,p50_ms,p99_ms,p999_ms,error_rate,status_2xx,status_4xx,status_5xx,status_0,dropped_iterations" > "$SUMMARY_CSV"
+
+for FRAMEWORK in "${FRAMEWORK_ORDER[@]}"; do
+  SERVICE="${FRAMEWORK_SERVICES[$FRAMEWORK]}"
+  PORT="${FRAMEWORK_PORTS[$FRAMEWORK]}"
+  API_URL="http://localhost:$PORT"
+
+  header "Chaos ($SCENARIO): $FRAMEWORK @ ${TARGET_RPS} req/s"
+
+  docker compose up -d "$SERVICE"
+  for i in $(seq 1 30); do
+    if curl -sf "$API_URL/" &>/dev/null 2>&1; then
+      success "API $FRAMEWORK respondendo (tentativa $i)"
+      break
+    fi
+    if [ "$i" -eq 30 ]; then
+      error "API $FRAMEWORK não respondeu em 30s"
+      docker compose stop "$SERVICE"
+      continue 2
+    fi
+    sleep 1
+  done
+
+  # Configuração efetiva da API (só o Actix expõe /config)
+  curl -sf "$API_URL/config" > "$RESULTS_DIR/config_${FRAMEWORK}.json" 2>/dev/null || rm -f "$RESULTS_DIR/config_${FRAMEWORK}.json"
+  manifest_image "$RESULTS_DIR/run_manifest.json" "$SERVICE"
+
+  log "[$FRAMEWORK] fase baseline ($DURATION)..."
+  run_phase "$FRAMEWORK" baseline "$API_URL"
+
+  log "[$FRAMEWORK] fase com falha ($DURATION)..."
+  if [ "$SCENARIO" = "outage" ]; then
+    PHASE_SECS=$(to_secs "$DURATION")
+    (
+      sleep $(( PHASE_SECS / 3 ))
+      docker pause "$PG_CONTAINER" >/dev/null
+      sleep "$OUTAGE_SECS"
+      docker unpause "$PG_CONTAINER" >/dev/null
+    ) &
+    OUTAGE_PID=$!
+    run_phase "$FRAMEWORK" fault "$API_URL"
+    wait "$OUTAGE_PID" 2>/dev/null || true
+  else
+    fault_on
+    run_phase "$FRAMEWORK" fault "$API_URL"
+    fault_off
+  fi
+
+  for PHASE in baseline fault; do
+    PHASE_JSON="$RESULTS_DIR/${FRAMEWORK}_${PHASE}.json"
+    [ -f "$PHASE_JSON" ] || { warn "[$FRAMEWORK] $PHASE_JSON ausente"; continue; }
+    python3 - "$PHASE_JSON" "$FRAMEWORK" "$SCENARIO" "$PHASE" >> "$SUMMARY_CSV" <<'PYEOF'
+import json, sys
+path, fw, scenario, phase = sys.argv[1:5]
+with open(path) as f: s = json.load(f)
+print(",".join([
+    fw, scenario, phase, str(s['target_rps']),
+    f"{s['achieved_rps']:.2f}", f"{s['p50_ms']:.2f}", f"{s['p99_ms']:.2f}", f"{s['p999_ms']:.2f}",
+    f"{s['error_rate']*100:.4f}",
+    str(int(s['status_2xx'])), str(int(s['status_4xx'])), str(int(s['status_5xx'])), str(int(s['status_0'])),
+    str(int(s['dropped_iterations'])),
+]))
+PYEOF
+  done
+
+  docker compose stop "$SERVICE"
+  sleep 3
+done
+
+fingerprint_builds "$RESULTS_DIR/environment.json"
+manifest_image "$RESULTS_DIR/run_manifest.json" postgres
+
+# Histórico opcional (RESULTS_DB=results/history.sqlite ou postgresql://...)
+if [ -n "${RESULTS_DB:-}" ]; then
+  python3 "$SCRIPT_DIR/store-results.py" --db "$RESULTS_DB" "$RESULTS_DIR" \
+    || warn "Falha ao gravar no histórico ($RESULTS_DB)"
+fi
+
+header "Resultado"
+column -t -s, "$SUMMARY_CSV"
+echo ""
+success "Resultados em: $RESULTS_DIR"
@@ -21,7 +21,16 @@
 #   ./scripts/run-experiment.sh [--runs N] [--rps N] [--duration Xs]
 #                               [--max-rps N] [--step-rps N] [--step-duration Xs]
 #                               [--load-pct N] [--no-rapl] [--skip-build]
-#                               [--skip-saturation]
+#                               [--skip-saturation] [--measure-build] [--profile]
+#                               [--pin api=LISTA,db=LISTA,loadgen=LISTA | auto | numa]
+#                               [--remote ssh://user@host | context:NOME] [--target-host HOST]
+#
+# --pin separa as CPUs da API, do banco e do k6 (ver scripts/lib/pinning.sh);
+# o pinning pedido e o efetivo de cada container vão para os resultados.
+#
+# --remote sobe banco e APIs em outro host e gera a carga daqui (ver
+# scripts/lib/remote.sh); RAPL, CPU e memória passam a ser lidos do host
+# remoto pelo ssh, com os timestamps corrigidos pela diferença de relógio.
 #
 set -euo pipefail
 
@@ -36,7 +45,12 @@ WARMUP_DURATION=30s
 USE_RAPL=true       # desabilitar com --no-rapl
 SKIP_BUILD=false    # pular docker build com --skip-build
 SKIP_SATURATION=false # pular fase de saturação com --skip-saturation
+MEASURE_BUILD=false # build sequencial sem cache com métricas (--measure-build)
+PROFILE=false       # rodada extra com perf + flamegraph (--profile)
 LOAD_PCT=70         # % do menor max sustentável a usar como RPS comum
+PIN=""              # CPUs de API/banco/k6 (--pin); vazio = sem pinning
+REMOTE=""           # host das APIs (--remote); vazio = esta máquina
+TARGET_HOST=""      # host/IP das APIs para o k6 (default: o de --remote)
 
 # Fase de saturação
 SAT_START_RPS=200
@@ -44,6 +58,7 @@ SAT_STEP_RPS=200
 SAT_MAX_RPS=5000
 SAT_STEP_DURATION=30s
 
+ARGS=("$@")
 while [[ $# -gt 0 ]]; do
   case $1 in
     --runs)             RUNS="$2";              shift 2 ;;
@@ -56,6 +71,11 @@ while [[ $# -gt 0 ]]; do
     --no-rapl)          USE_RAPL=false;         shift   ;;
     --skip-build)       SKIP_BUILD=true;        shift   ;;
     --skip-saturation)  SKIP_SATURATION=true;   shift   ;;
+    --measure-build)    MEASURE_BUILD=true;     shift   ;;
+    --profile)          PROFILE=true;           shift   ;;
+    --pin)              PIN="$2";               shift 2 ;;
+    --remote)           REMOTE="$2";            shift 2 ;;
+    --target-host)      TARGET_HOST="$2";       shift 2 ;;
     *) echo "Argumento desconhecido: $1"; exit 1 ;;
   esac
 done
@@ -71,6 +91,9 @@ RAPL_PATH="/sys/class/powercap/intel-rapl/intel-rapl:0/energy_uj"
 LOAD_TEST_SCRIPT="$SCRIPT_DIR/load-test-energy.js"
 ANALYZE_SCRIPT="$SCRIPT_DIR/analyze-results.py"
 
+# Commit embutido na imagem do Actix e reportado por GET /config
+export GIT_SHA="${GIT_SHA:-$(git -C "$PROJECT_DIR" rev-parse --short=12 HEAD 2>/dev/null || true)}"
+
 mkdir -p "$RESULTS_DIR"
 
 # ---------------------------------------------------------------------------
@@ -86,10 +109,24 @@ error()   { echo -e "${RED}[$(date +%H:%M:%S)] ✗${NC} $*" >&2; }
 warn()    { echo -e "${YELLOW}[$(date +%H:%M:%S)] !${NC} $*"; }
 header()  { echo -e "\n${BOLD}${YELLOW}=== $* ===${NC}\n"; }
 
+source "$SCRIPT_DIR/lib/remote.sh"
+source "$SCRIPT_DIR/lib/pinning.sh"
+source "$SCRIPT_DIR/lib/fingerprint.sh"
+source "$SCRIPT_DIR/lib/manifest.sh"
+
+# Lê um arquivo do host das APIs (esta máquina ou, com --remote ssh://, a remota)
+target_cat() {
+  if [ "$REMOTE_MODE" = ssh ]; then
+    remote_sh cat "$1"
+  else
+    cat "$1"
+  fi
+}
+
 # Lê contador RAPL em µJ (retorna 0 se indisponível)
 read_rapl() {
-  if $USE_RAPL && [ -r "$RAPL_PATH" ]; then
-    cat "$RAPL_PATH"
+  if $USE_RAPL; then
+    target_cat "$RAPL_PATH" 2>/dev/null || echo "0"
   else
     echo "0"
   fi
@@ -98,6 +135,15 @@ read_rapl() {
 # Retorna timestamp Unix em milissegundos
 now_ms() { date +%s%3N; }
 
+# Converte "90s" / "2m" em segundos
+to_secs() {
+  case $1 in
+    *m) echo $(( ${1%m} * 60 )) ;;
+    *s) echo "${1%s}" ;;
+    *)  echo "$1" ;;
+  esac
+}
+
 # ---------------------------------------------------------------------------
 # Frameworks definidos
 # ---------------------------------------------------------------------------
@@ -146,25 +192,43 @@ if ! command -v python3 &>/dev/null; then
   exit 1
 fi
 
+remote_setup "$REMOTE" "$TARGET_HOST" || exit 1
+
+if $PROFILE && [ "$REMOTE_MODE" != off ]; then
+  error "--profile anexa o perf a um processo local; não vale com --remote"
+  exit 1
+fi
+
+if $USE_RAPL && [ "$REMOTE_MODE" = context ]; then
+  error "RAPL do host remoto só é lido via ssh: use --remote ssh://... ou --no-rapl"
+  exit 1
+fi
+
 if $USE_RAPL; then
-  if [ ! -r "$RAPL_PATH" ]; then
+  if ! target_cat "$RAPL_PATH" &>/dev/null; then
     error "RAPL não acessível em $RAPL_PATH"
     error "Execute: sudo chmod a+r $RAPL_PATH"
     error "RAPL é essencial para medição de energia. Abortando."
     exit 1
   else
-    RAPL_TEST=$(cat "$RAPL_PATH")
+    RAPL_TEST=$(target_cat "$RAPL_PATH")
     success "RAPL disponível (leitura atual: ${RAPL_TEST} µJ)"
   fi
 fi
 
+pin_setup "$PIN" || exit 1
+
 success "Todos os pré-requisitos OK"
 
 # ---------------------------------------------------------------------------
 # Build das imagens
 # ---------------------------------------------------------------------------
 
-if ! $SKIP_BUILD; then
+if $MEASURE_BUILD; then
+  header "Build das imagens Docker (com métricas de build)"
+  "$SCRIPT_DIR/measure-build.sh" --output-dir "$RESULTS_DIR"
+  success "Imagens construídas"
+elif ! $SKIP_BUILD; then
   header "Build das imagens Docker"
   cd "$PROJECT_DIR"
   docker compose build --parallel
@@ -192,6 +256,13 @@ for i in $(seq 1 30); do
   sleep 1
 done
 
+if [ "$REMOTE_MODE" != off ]; then
+  remote_clock_offset > "$RESULTS_DIR/clock_offset.json"
+  log "Relógio do alvo: ${CLOCK_OFFSET_MS}ms em relação ao local"
+fi
+fingerprint_write "$RESULTS_DIR/environment.json"
+manifest_write "$RESULTS_DIR/run_manifest.json" "${ARGS[@]}"
+
 # ---------------------------------------------------------------------------
 # Medição de baseline (sistema idle + só postgres)
 # ---------------------------------------------------------------------------
@@ -215,7 +286,7 @@ if $USE_RAPL && [ "$BASELINE_RAPL_START" -ne 0 ]; then
   if [ "$BASELINE_RAPL_END" -ge "$BASELINE_RAPL_START" ]; then
     BASELINE_ENERGY_UJ=$(( BASELINE_RAPL_END - BASELINE_RAPL_START ))
   else
-    MAX_RANGE=$(cat /sys/class/powercap/intel-rapl/intel-rapl:0/max_energy_range_uj 2>/dev/null || echo "4294967296")
+    MAX_RANGE=$(target_cat /sys/class/powercap/intel-rapl/intel-rapl:0/max_energy_range_uj 2>/dev/null || echo "4294967296")
     BASELINE_ENERGY_UJ=$(( MAX_RANGE - BASELINE_RAPL_START + BASELINE_RAPL_END ))
   fi
   BASELINE_POWER_W=$(echo "scale=4; $BASELINE_ENERGY_UJ / $BASELINE_ELAPSED_MS / 1000" | bc)
@@ -246,7 +317,7 @@ if ! $SKIP_SATURATION; then
   for FRAMEWORK in "${FRAMEWORK_ORDER[@]}"; do
     SERVICE="${FRAMEWORK_SERVICES[$FRAMEWORK]}"
     PORT="${FRAMEWORK_PORTS[$FRAMEWORK]}"
-    API_URL="http://localhost:$PORT"
+    API_URL="http://$TARGET_HOST:$PORT"
     FW_DIR="$RESULTS_DIR/$FRAMEWORK"
     mkdir -p "$FW_DIR"
 
@@ -270,7 +341,7 @@ if ! $SKIP_SATURATION; then
     log "[$FRAMEWORK] Saturação: ${SAT_START_RPS}→${SAT_MAX_RPS} req/s (+${SAT_STEP_RPS}/degrau, ${SAT_STEP_DURATION}/degrau)..."
     SAT_CSV="$FW_DIR/saturation_${FRAMEWORK}.csv"
 
-    k6 run \
+    pin_loadgen k6 run \
       -e API_URL="$API_URL" \
       -e START_RPS="$SAT_START_RPS" \
       -e STEP_RPS="$SAT_STEP_RPS" \
@@ -371,7 +442,11 @@ cat > "$RESULTS_DIR/experiment_config.json" <<EOF
   "kernel":      "$(uname -r)",
   "cpu_model":   "$(grep 'model name' /proc/cpuinfo 2>/dev/null | head -1 | cut -d: -f2 | xargs || echo unknown)",
   "cpu_cores":   $(nproc),
-  "ram_gb":      $(awk '/MemTotal/{printf "%.1f", $2/1024/1024}' /proc/meminfo 2>/dev/null || echo 0)
+  "ram_gb":      $(awk '/MemTotal/{printf "%.1f", $2/1024/1024}' /proc/meminfo 2>/dev/null || echo 0),
+  "remote":      "$REMOTE",
+  "target_host": "$TARGET_HOST",
+  "target":      $(remote_system_json),
+  "cpu_pinning": $(pin_json)
 }
 EOF
 
@@ -387,7 +462,7 @@ echo "framework,run,rps,p50_ms,p95_ms,p99_ms,error_rate,rapl_start_uj,rapl_end_u
 for FRAMEWORK in "${FRAMEWORK_ORDER[@]}"; do
   SERVICE="${FRAMEWORK_SERVICES[$FRAMEWORK]}"
   PORT="${FRAMEWORK_PORTS[$FRAMEWORK]}"
-  API_URL="http://localhost:$PORT"
+  API_URL="http://$TARGET_HOST:$PORT"
   FW_DIR="$RESULTS_DIR/$FRAMEWORK"
   mkdir -p "$FW_DIR"
 
@@ -412,9 +487,15 @@ for FRAMEWORK in "${FRAMEWORK_ORDER[@]}"; do
     sleep 1
   done
 
+  # Configuração efetiva da API (só o Actix expõe /config)
+  curl -sf "$API_URL/config" > "$FW_DIR/config.json" 2>/dev/null || rm -f "$FW_DIR/config.json"
+  manifest_image "$RESULTS_DIR/run_manifest.json" "$SERVICE"
+  # CPUs pedidas e as efetivas dos containers desta medição
+  pin_json postgres "$SERVICE" > "$FW_DIR/cpu_pinning.json"
+
   # --- Warm-up ---
   log "Warm-up de $WARMUP_DURATION ($FRAMEWORK)..."
-  k6 run \
+  pin_loadgen k6 run \
     -e API_URL="$API_URL" \
     -e TARGET_RPS="$TARGET_RPS" \
     -e DURATION="$WARMUP_DURATION" \
@@ -449,13 +530,15 @@ for FRAMEWORK in "${FRAMEWORK_ORDER[@]}"; do
           printf "%d,%.2f,%.1f\n", t, cpu, val
         }' >> "$STATS_FILE" &
     STATS_PID=$!
+    # CPU, memória e RAPL do host remoto (só com --remote ssh://)
+    remote_sample_start "$RUN_DIR/host_stats.csv" "$RAPL_PATH"
 
     # Lê RAPL antes
     RAPL_START=$(read_rapl)
     TS_START=$(now_ms)
 
     # Executa k6
-    k6 run \
+    pin_loadgen k6 run \
       -e API_URL="$API_URL" \
       -e TARGET_RPS="$TARGET_RPS" \
       -e DURATION="$DURATION" \
@@ -470,6 +553,7 @@ for FRAMEWORK in "${FRAMEWORK_ORDER[@]}"; do
     # Para coleta de stats
     kill "$STATS_PID" 2>/dev/null || true
     wait "$STATS_PID" 2>/dev/null || true
+    remote_sample_stop
 
     # Calcula energia
     ELAPSED_MS=$(( TS_END - TS_START ))
@@ -478,7 +562,7 @@ for FRAMEWORK in "${FRAMEWORK_ORDER[@]}"; do
       if [ "$RAPL_END" -ge "$RAPL_START" ]; then
         ENERGY_UJ=$(( RAPL_END - RAPL_START ))
       else
-        MAX_RANGE=$(cat /sys/class/powercap/intel-rapl/intel-rapl:0/max_energy_range_uj 2>/dev/null || echo "4294967296")
+        MAX_RANGE=$(target_cat /sys/class/powercap/intel-rapl/intel-rapl:0/max_energy_range_uj 2>/dev/null || echo "4294967296")
         ENERGY_UJ=$(( MAX_RANGE - RAPL_START + RAPL_END ))
       fi
       POWER_W=$(echo "scale=4; $ENERGY_UJ / $ELAPSED_MS / 1000" | bc)
@@ -533,6 +617,27 @@ print(f\"{v.get('rate',0)*100:.4f}\")
     success "Rodada $RUN: RPS=$RPS, P99=${P99}ms, Power=${POWER_W}W, CPU=${CPU_PCT}%"
   done
 
+  # --- Rodada de profiling (fora das rodadas medidas: o perf tem overhead) ---
+  if $PROFILE; then
+    log "Rodada de profiling ($FRAMEWORK)..."
+    "$SCRIPT_DIR/profile-target.sh" \
+      --container "$(docker compose ps -q "$SERVICE")" \
+      --duration "$(to_secs "$DURATION")" \
+      --output "$FW_DIR/flamegraph_energy.svg" &
+    PROFILE_PID=$!
+    pin_loadgen k6 run \
+      -e API_URL="$API_URL" \
+      -e TARGET_RPS="$TARGET_RPS" \
+      -e DURATION="$DURATION" \
+      --quiet \
+      "$LOAD_TEST_SCRIPT" || true
+    if wait "$PROFILE_PID"; then
+      success "Flamegraph: $FW_DIR/flamegraph_energy.svg"
+    else
+      warn "Profiling de $FRAMEWORK falhou (perf/inferno disponíveis?)"
+    fi
+  fi
+
   # --- Derruba a API ---
   docker compose stop "$SERVICE"
   log "Container $SERVICE parado"
@@ -553,11 +658,20 @@ success "PostgreSQL parado"
 
 header "Analisando resultados"
 
+fingerprint_builds "$RESULTS_DIR/environment.json"
+manifest_image "$RESULTS_DIR/run_manifest.json" postgres
+
 python3 "$ANALYZE_SCRIPT" \
   --results-dir "$RESULTS_DIR" \
   --baseline-power "$BASELINE_POWER_W" \
   --output-dir "$RESULTS_DIR"
 
+# Histórico opcional (RESULTS_DB=results/history.sqlite ou postgresql://...)
+if [ -n "${RESULTS_DB:-}" ]; then
+  python3 "$SCRIPT_DIR/store-results.py" --db "$RESULTS_DB" "$RESULTS_DIR" \
+    || warn "Falha ao gravar no histórico ($RESULTS_DB)"
+fi
+
 success "Análise concluída. Resultados em: $RESULTS_DIR"
 echo ""
 echo "Arquivos gerados:"
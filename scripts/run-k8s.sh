@@ -0,0 +1,440 @@
+#!/usr/bin/env bash
+# run-k8s.sh — benchmark das APIs num cluster Kubernetes
+#
+# O docker-compose mede as APIs atrás da rede do Docker; em produção elas
+# ficam atrás de Services (kube-proxy, Cilium...). Este script roda a mesma
+# medição dentro de um cluster:
+#
+#   1. Renderiza os manifests de k8s/ (${NOME} → parâmetros abaixo) em
+#      <resultados>/manifests/ e aplica no namespace
+#   2. Sobe o PostgreSQL (schema e seed de scripts/init.sql) e espera o readiness
+#   3. Para cada framework: sobe a API com limites de CPU/memória, espera o
+#      readiness, roda o script k6 num Job dentro do cluster contra o Service
+#      e recolhe o resumo do k6, o log, o posicionamento dos pods e o consumo
+#      de CPU/memória (kubectl top, requer metrics-server) a cada 5s
+#   4. Remove a API e o Job antes do próximo framework; o namespace é apagado
+#      no fim (mantenha com --keep)
+#
+# As imagens precisam estar acessíveis ao cluster: --build gera
+# <prefixo>api-<framework>:<tag> com o docker, --push as envia ao registry do
+# prefixo e --kind NOME as carrega num cluster kind.
+#
+# Uso:
+#   ./scripts/run-k8s.sh --rps 1000 [--frameworks "actix gin"] [--build --kind bench]
+#
+# Opções:
+#   --rps N               taxa fixa (default: 1000)
+#   --duration Xs         duração da medição (default: 120s)
+#   --frameworks "a b"    subconjunto de frameworks (default: todos)
+#   --script ARQ          script k6 de scripts/ (default: load-test-latency.js)
+#   --error-budget F      fração de falhas tolerada (default: 0.01)
+#   --namespace NS        namespace usado (default: benchmark)
+#   --kube-context CTX    contexto do kubectl (default: o atual)
+#   --image-prefix P      prefixo das imagens (default: benchmark/)
+#   --tag T               tag das imagens (default: latest)
+#   --pull-policy P       imagePullPolicy das APIs (default: IfNotPresent)
+#   --build / --push      docker build (e push) das imagens antes de rodar
+#   --kind NOME           carrega as imagens no cluster kind NOME
+#   --api-cpu / --api-memory          limites da API (default: 2 / 512Mi)
+#   --db-cpu / --db-memory            limites do PostgreSQL (default: 4 / 1Gi)
+#   --loadgen-cpu / --loadgen-memory  limites do k6 (default: 2 / 1Gi)
+#   --env CHAVE=VALOR     variável extra para as APIs (repetível)
+#   --k6-image IMG        imagem do k6 (default: grafana/k6:latest)
+#   --keep                não apaga o namespace no fim
+#
+set -euo pipefail
+
+# ---------------------------------------------------------------------------
+# Parâmetros (com defaults)
+# ---------------------------------------------------------------------------
+
+TARGET_RPS=1000
+DURATION=120s
+FRAMEWORKS=""
+SCRIPT=load-test-latency.js
+ERROR_BUDGET=0.01
+NAMESPACE=benchmark
+KUBE_CONTEXT=""
+IMAGE_PREFIX=benchmark/
+TAG=latest
+PULL_POLICY=IfNotPresent
+BUILD=false
+PUSH=false
+KIND_CLUSTER=""
+API_CPU=2;     API_MEMORY=512Mi
+DB_CPU=4;      DB_MEMORY=1Gi
+LOADGEN_CPU=2; LOADGEN_MEMORY=1Gi
+API_ENV=()
+K6_IMAGE=grafana/k6:latest
+KEEP=false
+
+ARGS=("$@")
+while [[ $# -gt 0 ]]; do
+  case $1 in
+    --rps)            TARGET_RPS="$2";     shift 2 ;;
+    --duration)       DURATION="$2";       shift 2 ;;
+    --frameworks)     FRAMEWORKS="$2";     shift 2 ;;
+    --script)         SCRIPT="$2";         shift 2 ;;
+    --error-budget)   ERROR_BUDGET="$2";   shift 2 ;;
+    --namespace)      NAMESPACE="$2";      shift 2 ;;
+    --kube-context)   KUBE_CONTEXT="$2";   shift 2 ;;
+    --image-prefix)   IMAGE_PREFIX="$2";   shift 2 ;;
+    --tag)            TAG="$2";            shift 2 ;;
+    --pull-policy)    PULL_POLICY="$2";    shift 2 ;;
+    --build)          BUILD=true;          shift   ;;
+    --push)           PUSH=true;           shift   ;;
+    --kind)           KIND_CLUSTER="$2";   shift 2 ;;
+    --api-cpu)        API_CPU="$2";        shift 2 ;;
+    --api-memory)     API_MEMORY="$2";     shift 2 ;;
+    --db-cpu)         DB_CPU="$2";         shift 2 ;;
+    --db-memory)      DB_MEMORY="$2";      shift 2 ;;
+    --loadgen-cpu)    LOADGEN_CPU="$2";    shift 2 ;;
+    --loadgen-memory) LOADGEN_MEMORY="$2"; shift 2 ;;
+    --env)            API_ENV+=("$2");     shift 2 ;;
+    --k6-image)       K6_IMAGE="$2";       shift 2 ;;
+    --keep)           KEEP=true;           shift   ;;
+    *) echo "Argumento desconhecido: $1"; exit 1 ;;
+  esac
+done
+
+# ---------------------------------------------------------------------------
+# Caminhos
+# ---------------------------------------------------------------------------
+
+SCRIPT_DIR="$(cd "$(dirname "${BASH_SOURCE[0]}")" && pwd)"
+PROJECT_DIR="$(dirname "$SCRIPT_DIR")"
+MANIFEST_DIR="$PROJECT_DIR/k8s"
+RESULTS_DIR="$PROJECT_DIR/results/k8s_$(date +%Y%m%d_%H%M%S)"
+
+if [ ! -f "$SCRIPT_DIR/$SCRIPT" ]; then
+  echo "Script k6 não encontrado: scripts/$SCRIPT"
+  exit 1
+fi
+
+mkdir -p "$RESULTS_DIR/manifests"
+
+# ---------------------------------------------------------------------------
+# Cores e helpers de log
+# ---------------------------------------------------------------------------
+
+CYAN='\033[0;36m'; YELLOW='\033[1;33m'; GREEN='\033[0;32m'
+RED='\033[0;31m';  BOLD='\033[1m';      NC='\033[0m'
+
+log()     { echo -e "${CYAN}[$(date +%H:%M:%S)]${NC} $*"; }
+success() { echo -e "${GREEN}[$(date +%H:%M:%S)] ✓${NC} $*"; }
+error()   { echo -e "${RED}[$(date +%H:%M:%S)] ✗${NC} $*" >&2; }
+warn()    { echo -e "${YELLOW}[$(date +%H:%M:%S)] !${NC} $*"; }
+header()  { echo -e "\n${BOLD}${YELLOW}=== $* ===${NC}\n"; }
+
+source "$SCRIPT_DIR/lib/fingerprint.sh"
+source "$SCRIPT_DIR/lib/manifest.sh"
+
+# Sem o compose, a semente da API vai como --env (se ela a aceita, ver lib/manifest.sh)
+if [ -n "${RANDOM_SEED:-}" ] && [[ " ${API_ENV[*]-} " != *" MULTI_TENANT=on "* && " ${API_ENV[*]-} " != *" RANDOM_"* ]]; then
+  API_ENV+=("RANDOM_SEED=$RANDOM_SEED")
+fi
+
+KUBECTL=(kubectl)
+[ -n "$KUBE_CONTEXT" ] && KUBECTL+=(--context "$KUBE_CONTEXT")
+K=("${KUBECTL[@]}" -n "$NAMESPACE")
+
+# Converte "90s" / "2m" em segundos
+to_secs() {
+  case $1 in
+    *m) echo $(( ${1%m} * 60 )) ;;
+    *s) echo "${1%s}" ;;
+    *)  echo "$1" ;;
+  esac
+}
+
+# Renderiza k8s/<template>.yaml com as variáveis de ambiente e aplica; uma
+# variável faltando é erro. Linhas de comentário ficam como estão. O manifest
+# renderizado fica em <resultados>/manifests/<nome>.yaml.
+apply_template() {
+  local template="$1" out="$RESULTS_DIR/manifests/$2.yaml"
+  python3 - "$MANIFEST_DIR/$template.yaml" "$out" <<'PYEOF'
+import os, string, sys
+src, dst = sys.argv[1], sys.argv[2]
+with open(src) as f:
+    lines = [line if line.lstrip().startswith('#') else string.Template(line).substitute(os.environ)
+             for line in f]
+with open(dst, 'w') as f:
+    f.writelines(lines)
+PYEOF
+  "${K[@]}" apply -f "$out" >/dev/null
+}
+
+# ConfigMap a partir de arquivos, recriado a cada execução
+apply_configmap() {
+  local name="$1"; shift
+  local args=()
+  for f in "$@"; do args+=(--from-file="$f"); done
+  "${K[@]}" create configmap "$name" "${args[@]}" --dry-run=client -o yaml | "${K[@]}" apply -f - >/dev/null
+}
+
+# ---------------------------------------------------------------------------
+# Frameworks definidos
+# ---------------------------------------------------------------------------
+
+declare -A FRAMEWORK_PORTS=(
+  [express]=3001
+  [fastify]=3002
+  [elysia]=3003
+  [actix]=3004
+  [gin]=3005
+)
+
+if [ -n "$FRAMEWORKS" ]; then
+  read -r -a FRAMEWORK_ORDER <<< "$FRAMEWORKS"
+else
+  FRAMEWORK_ORDER=(express fastify elysia actix gin)
+fi
+
+# ---------------------------------------------------------------------------
+# Verifica pré-requisitos
+# ---------------------------------------------------------------------------
+
+header "Verificando pré-requisitos"
+
+if ! command -v kubectl &>/dev/null; then
+  error "kubectl não encontrado"
+  exit 1
+fi
+if ! command -v python3 &>/dev/null; then
+  error "python3 não encontrado"
+  exit 1
+fi
+if ! "${KUBECTL[@]}" cluster-info --request-timeout=10s &>/dev/null; then
+  error "cluster inacessível (kubectl cluster-info falhou${KUBE_CONTEXT:+ no contexto $KUBE_CONTEXT})"
+  exit 1
+fi
+if ($BUILD || $PUSH || [ -n "$KIND_CLUSTER" ]) && ! command -v docker &>/dev/null; then
+  error "docker não encontrado (necessário para --build, --push e --kind)"
+  exit 1
+fi
+if [ -n "$KIND_CLUSTER" ] && ! command -v kind &>/dev/null; then
+  error "kind não encontrado (necessário para --kind)"
+  exit 1
+fi
+METRICS=true
+if ! "${KUBECTL[@]}" top node &>/dev/null; then
+  warn "kubectl top indisponível (metrics-server?) — sem CPU/memória dos pods"
+  METRICS=false
+fi
+success "Cluster: $("${KUBECTL[@]}" config current-context 2>/dev/null || echo '?'), namespace $NAMESPACE"
+
+# ---------------------------------------------------------------------------
+# Imagens
+# ---------------------------------------------------------------------------
+
+for FRAMEWORK in "${FRAMEWORK_ORDER[@]}"; do
+  IMAGE="${IMAGE_PREFIX}api-${FRAMEWORK}:${TAG}"
+  if $BUILD; then
+    log "[$FRAMEWORK] docker build → $IMAGE"
+    docker build -q -t "$IMAGE" "$PROJECT_DIR/api-$FRAMEWORK" >/dev/null
+  fi
+  if $PUSH; then
+    docker push -q "$IMAGE" >/dev/null
+  fi
+  if [ -n "$KIND_CLUSTER" ]; then
+    kind load docker-image "$IMAGE" --name "$KIND_CLUSTER" >/dev/null
+  fi
+done
+
+# ---------------------------------------------------------------------------
+# Namespace, ConfigMaps e PostgreSQL
+# ---------------------------------------------------------------------------
+
+header "Preparando namespace $NAMESPACE"
+
+"${KUBECTL[@]}" create namespace "$NAMESPACE" --dry-run=client -o yaml | "${KUBECTL[@]}" apply -f - >/dev/null
+apply_configmap postgres-init "$SCRIPT_DIR/init.sql"
+apply_configmap k6-scripts "$SCRIPT_DIR"/load-test*.js
+apply_configmap k6-lib "$SCRIPT_DIR"/lib/*.js
+
+# Itens do k6-lib montados em lib/ (o volume projetado não aceita subdiretório por ConfigMap)
+LIB_ITEMS=$(for f in "$SCRIPT_DIR"/lib/*.js; do
+  echo "                    - { key: $(basename "$f"), path: lib/$(basename "$f") }"
+done)
+
+export DB_CPU DB_MEMORY API_CPU API_MEMORY LOADGEN_CPU LOADGEN_MEMORY PULL_POLICY \
+       K6_IMAGE SCRIPT TARGET_RPS DURATION ERROR_BUDGET SEED LIB_ITEMS
+
+cleanup() {
+  if ! $KEEP; then
+    log "Apagando namespace $NAMESPACE..."
+    "${KUBECTL[@]}" delete namespace "$NAMESPACE" --wait=false >/dev/null 2>&1 || true
+  fi
+}
+trap cleanup EXIT
+
+apply_template postgres postgres
+log "Aguardando PostgreSQL..."
+if ! "${K[@]}" rollout status deployment/postgres --timeout=180s >/dev/null; then
+  error "PostgreSQL não ficou pronto em 180s"
+  "${K[@]}" describe pod -l app=postgres | tail -20 >&2
+  exit 1
+fi
+success "PostgreSQL pronto"
+
+# ---------------------------------------------------------------------------
+# Medição por framework
+# ---------------------------------------------------------------------------
+
+cat > "$RESULTS_DIR/k8s_config.json" <<EOF
+{
+  "timestamp":      "$(date -Iseconds)",
+  "context":        "$("${KUBECTL[@]}" config current-context 2>/dev/null || true)",
+  "server_version": "$("${KUBECTL[@]}" version -o json 2>/dev/null | python3 -c 'import json,sys; print(json.load(sys.stdin).get("serverVersion",{}).get("gitVersion",""))' 2>/dev/null || true)",
+  "nodes":          $("${KUBECTL[@]}" get nodes --no-headers 2>/dev/null | wc -l),
+  "namespace":      "$NAMESPACE",
+  "script":         "$SCRIPT",
+  "target_rps":     $TARGET_RPS,
+  "duration":       "$DURATION",
+  "limits": {
+    "api":     {"cpu": "$API_CPU", "memory": "$API_MEMORY"},
+    "db":      {"cpu": "$DB_CPU", "memory": "$DB_MEMORY"},
+    "loadgen": {"cpu": "$LOADGEN_CPU", "memory": "$LOADGEN_MEMORY"}
+  }
+}
+EOF
+fingerprint_write "$RESULTS_DIR/environment.json"
+manifest_write "$RESULTS_DIR/run_manifest.json" "${ARGS[@]}"
+fingerprint_nodes "$RESULTS_DIR/environment.json" "${KUBECTL[@]}"
+
+SUMMARY_CSV="$RESULTS_DIR/k8s_summary.csv"
+echo "framework,target_rps,achieved_rps,p50_ms,p90_ms,p99_ms,max_ms,error_rate,api_node,loadgen_node,api_cpu_avg_m,api_mem_max_mi,db_cpu_avg_m" > "$SUMMARY_CSV"
+JOB_TIMEOUT=$(( $(to_secs "$DURATION") + 300 ))
+
+for FRAMEWORK in "${FRAMEWORK_ORDER[@]}"; do
+  export SERVICE="api-$FRAMEWORK"
+  export PORT="${FRAMEWORK_PORTS[$FRAMEWORK]}"
+  export IMAGE="${IMAGE_PREFIX}api-${FRAMEWORK}:${TAG}"
+  export JOB="k6-$FRAMEWORK"
+  FW_DIR="$RESULTS_DIR/$FRAMEWORK"
+  mkdir -p "$FW_DIR"
+
+  EXTRA_ENV=""
+  for kv in "${API_ENV[@]+"${API_ENV[@]}"}"; do
+    EXTRA_ENV+="            - { name: ${kv%%=*}, value: \"${kv#*=}\" }"$'\n'
+  done
+  export EXTRA_ENV="${EXTRA_ENV%$'\n'}"
+
+  header "K8s: $FRAMEWORK ($IMAGE, ${TARGET_RPS} req/s)"
+
+  apply_template api "$SERVICE"
+  if ! "${K[@]}" rollout status "deployment/$SERVICE" --timeout=180s >/dev/null; then
+    error "[$FRAMEWORK] API não ficou pronta em 180s — pulando"
+    "${K[@]}" describe pod -l "app=$SERVICE" | tail -20 >&2
+    "${K[@]}" delete deployment,service "$SERVICE" --ignore-not-found >/dev/null
+    continue
+  fi
+  success "[$FRAMEWORK] API pronta"
+  manifest_image_ref "$RESULTS_DIR/run_manifest.json" "$SERVICE" "$IMAGE" \
+    "$("${K[@]}" get pods -l "app=$SERVICE" -o jsonpath='{.items[0].status.containerStatuses[0].imageID}' 2>/dev/null || true)"
+
+  "${K[@]}" delete job "$JOB" --ignore-not-found >/dev/null
+  apply_template loadgen-job "$JOB"
+  log "[$FRAMEWORK] k6 rodando no cluster (Job $JOB)..."
+
+  # CPU (milicores) e memória (MiB) dos pods a cada 5s
+  METRICS_CSV="$FW_DIR/pod_metrics.csv"
+  echo "timestamp,pod,role,cpu_m,mem_mi" > "$METRICS_CSV"
+  METRICS_PID=""
+  if $METRICS; then
+    while :; do
+      "${K[@]}" top pod -l 'role in (api,db,loadgen)' --no-headers 2>/dev/null \
+        | awk -v t="$(date +%s)" '{
+            role = ($1 ~ /^postgres/) ? "db" : ($1 ~ /^k6-/) ? "loadgen" : "api";
+            cpu = $2; sub(/m$/, "", cpu); mem = $3; sub(/Mi$/, "", mem);
+            printf "%d,%s,%s,%s,%s\n", t, $1, role, cpu, mem
+          }' >> "$METRICS_CSV" || true
+      sleep 5
+    done &
+    METRICS_PID=$!
+  fi
+
+  # Espera o Job terminar (sucesso ou falha)
+  STATUS=timeout
+  for _ in $(seq 1 "$JOB_TIMEOUT"); do
+    if [ "$("${K[@]}" get job "$JOB" -o jsonpath='{.status.succeeded}' 2>/dev/null)" = "1" ]; then
+      STATUS=ok; break
+    fi
+    if [ "$("${K[@]}" get job "$JOB" -o jsonpath='{.status.failed}' 2>/dev/null)" = "1" ]; then
+      STATUS=failed; break
+    fi
+    sleep 1
+  done
+  if [ -n "$METRICS_PID" ]; then
+    kill "$METRICS_PID" 2>/dev/null || true
+    wait "$METRICS_PID" 2>/dev/null || true
+  fi
+
+  # Log do k6 e os resumos que o Job imprime depois dos marcadores
+  "${K[@]}" logs "job/$JOB" > "$FW_DIR/k6.log" 2>&1 || true
+  python3 - "$FW_DIR/k6.log" "$FW_DIR" <<'PYEOF'
+import os, re, sys
+log, out = sys.argv[1], sys.argv[2]
+parts = re.split(r'^=== (\S+\.json) ===$', open(log, errors='replace').read(), flags=re.M)
+for name, body in zip(parts[1::2], parts[2::2]):
+    with open(os.path.join(out, name), 'w') as f:
+        f.write(body.strip() + '\n')
+PYEOF
+  "${K[@]}" get pods -o wide > "$FW_DIR/pods.txt" 2>/dev/null || true
+  "${K[@]}" get events --sort-by=.lastTimestamp > "$FW_DIR/events.txt" 2>/dev/null || true
+
+  case $STATUS in
+    ok)      success "[$FRAMEWORK] k6 concluído" ;;
+    failed)  warn "[$FRAMEWORK] Job do k6 falhou (orçamento de erros estourado ou erro do script) — ver $FW_DIR/k6.log" ;;
+    timeout) warn "[$FRAMEWORK] Job do k6 não terminou em ${JOB_TIMEOUT}s" ;;
+  esac
+
+  if [ -f "$FW_DIR/k6_summary.json" ]; then
+    API_NODE=$("${K[@]}" get pod -l "app=$SERVICE" -o jsonpath='{.items[0].spec.nodeName}' 2>/dev/null || true)
+    LOADGEN_NODE=$("${K[@]}" get pod -l "job-name=$JOB" -o jsonpath='{.items[0].spec.nodeName}' 2>/dev/null || true)
+    if [ -n "$API_NODE" ] && [ "$API_NODE" = "$LOADGEN_NODE" ]; then
+      warn "[$FRAMEWORK] k6 e API no mesmo nó ($API_NODE) — a carga disputa CPU com a API"
+    fi
+    python3 - "$FW_DIR" "$FRAMEWORK" "$TARGET_RPS" "$API_NODE" "$LOADGEN_NODE" >> "$SUMMARY_CSV" <<'PYEOF'
+import csv, json, os, sys
+fw_dir, fw, rps, api_node, loadgen_node = sys.argv[1:6]
+m = json.load(open(os.path.join(fw_dir, 'k6_summary.json'))).get('metrics', {})
+dur = m.get('http_req_duration', {})
+usage = {'api': [], 'db': []}
+mem_api = []
+with open(os.path.join(fw_dir, 'pod_metrics.csv')) as f:
+    for row in csv.DictReader(f):
+        if row['role'] in usage:
+            usage[row['role']].append(float(row['cpu_m']))
+        if row['role'] == 'api':
+            mem_api.append(float(row['mem_mi']))
+avg = lambda xs: f"{sum(xs) / len(xs):.0f}" if xs else ''
+print(",".join([
+    fw, rps,
+    f"{m.get('http_reqs', {}).get('rate', 0):.2f}",
+    f"{dur.get('med', 0):.2f}", f"{dur.get('p(90)', 0):.2f}",
+    f"{dur.get('p(99)', 0):.2f}", f"{dur.get('max', 0):.2f}",
+    f"{m.get('http_req_failed', {}).get('value', 0) * 100:.4f}",
+    api_node, loadgen_node,
+    avg(usage['api']), f"{max(mem_api):.0f}" if mem_api else '', avg(usage['db']),
+]))
+PYEOF
+  else
+    warn "[$FRAMEWORK] k6 não gerou k6_summary.json"
+  fi
+
+  "${K[@]}" delete job "$JOB" --ignore-not-found >/dev/null
+  "${K[@]}" delete deployment,service "$SERVICE" --ignore-not-found >/dev/null
+  sleep 3
+done
+
+# Histórico opcional (RESULTS_DB=results/history.sqlite ou postgresql://...)
+if [ -n "${RESULTS_DB:-}" ]; then
+  python3 "$SCRIPT_DIR/store-results.py" --db "$RESULTS_DB" "$RESULTS_DIR" \
+    || warn "Falha ao gravar no histórico ($RESULTS_DB)"
+fi
+
+header "Resultado"
+column -t -s, "$SUMMARY_CSV"
+echo ""
+success "Resultados em: $RESULTS_DIR"
@@ -0,0 +1,384 @@
+#!/usr/bin/env bash
+# run-latency.sh — latência sob carga com taxa de chegada fixa (open-loop)
+#
+# O RPS máximo medido em closed-loop esconde o tempo em fila: quando o servidor
+# desacelera, o gerador de carga também desacelera. Este script mede a latência
+# de cada framework a uma taxa fixa — por padrão 70% do seu RPS máximo
+# sustentável — e registra p50/p90/p99/p99.9.
+#
+# O RPS máximo de cada framework vem de uma execução anterior do
+# run-experiment.sh (arquivo <dir>/<framework>/max_sustainable_rps.txt) ou de
+# uma taxa única passada com --rps.
+#
+# Uso:
+#   ./scripts/run-latency.sh --saturation-dir results/<timestamp> [--load-pct 70]
+#   ./scripts/run-latency.sh --rps 1000 [--frameworks "actix gin"]
+#   ./scripts/run-latency.sh --rps 1000 --table-rows 1000000
+#
+# Opções:
+#   --saturation-dir DIR  diretório de resultados com max_sustainable_rps.txt
+#   --rps N               taxa fixa para todos os frameworks (ignora --load-pct)
+#   --load-pct N          % do RPS máximo sustentável (default: 70)
+#   --duration Xs         duração da medição (default: 120s)
+#   --warmup Xs           duração do warm-up (default: 30s)
+#   --frameworks "a b"    subconjunto de frameworks (default: todos)
+#   --error-budget F      fração de falhas tolerada por framework (default: 0.01);
+#                         acima dela o script termina com erro ao final
+#   --spec-file PATH      spec OpenAPI usada na validação amostrada dos corpos
+#                         (default: a que cada API publica em /openapi.json)
+#   --schema-sample N     confere 1 a cada N respostas contra a spec (default: 100; 0 desliga)
+#   --pin SPEC            CPUs disjuntas para API, banco e k6: api=LISTA,db=LISTA,loadgen=LISTA,
+#                         auto ou numa (ver scripts/lib/pinning.sh)
+#   --remote SPEC         banco e APIs em outro host (ssh://user@host ou context:NOME),
+#                         k6 local (ver scripts/lib/remote.sh)
+#   --target-host HOST    host/IP em que o k6 acessa as APIs (default: o de --remote)
+#   --energy              amostra os contadores RAPL do host das APIs durante a medição e
+#                         registra joules e joules por 1000 requisições (ver scripts/lib/energy.sh)
+#   --perf-stat           conta trocas de contexto, syscalls, cache e branch misses dos
+#                         processos da API durante a medição (ver scripts/lib/perfstat.sh)
+#   --heatmap             grava os eventos do k6 e gera percentis por segundo e o heatmap
+#                         de latência no tempo (scripts/latency-heatmap.py)
+#   --table-rows N        recria users com N linhas antes das medições (uma vez, para
+#                         todos os frameworks) e o k6 sorteia ids de 1 a N; N e o tamanho
+#                         da tabela com índices entram no resumo (ver scripts/lib/table.sh)
+#
+set -euo pipefail
+
+# ---------------------------------------------------------------------------
+# Parâmetros (com defaults)
+# ---------------------------------------------------------------------------
+
+SATURATION_DIR=""
+FIXED_RPS=0
+LOAD_PCT=70
+DURATION=120s
+WARMUP_DURATION=30s
+FRAMEWORKS=""
+ERROR_BUDGET=0.01
+SPEC_FILE=""
+SCHEMA_SAMPLE=100
+PIN=""
+REMOTE=""
+TARGET_HOST=""
+HEATMAP=false
+ENERGY=false
+PERF_STAT=false
+TABLE_ROWS=0
+
+ARGS=("$@")
+while [[ $# -gt 0 ]]; do
+  case $1 in
+    --saturation-dir) SATURATION_DIR="$2";  shift 2 ;;
+    --rps)            FIXED_RPS="$2";       shift 2 ;;
+    --load-pct)       LOAD_PCT="$2";        shift 2 ;;
+    --duration)       DURATION="$2";        shift 2 ;;
+    --warmup)         WARMUP_DURATION="$2"; shift 2 ;;
+    --frameworks)     FRAMEWORKS="$2";      shift 2 ;;
+    --error-budget)   ERROR_BUDGET="$2";    shift 2 ;;
+    --spec-file)      SPEC_FILE="$(realpath "$2")"; shift 2 ;;
+    --schema-sample)  SCHEMA_SAMPLE="$2";   shift 2 ;;
+    --pin)            PIN="$2";             shift 2 ;;
+    --remote)         REMOTE="$2";          shift 2 ;;
+    --target-host)    TARGET_HOST="$2";     shift 2 ;;
+    --heatmap)        HEATMAP=true;         shift ;;
+    --energy)         ENERGY=true;          shift ;;
+    --perf-stat)      PERF_STAT=true;       shift ;;
+    --table-rows)     TABLE_ROWS="$2";      shift 2 ;;
+    *) echo "Argumento desconhecido: $1"; exit 1 ;;
+  esac
+done
+
+if [ -z "$SATURATION_DIR" ] && [ "$FIXED_RPS" -eq 0 ]; then
+  echo "Informe --saturation-dir DIR ou --rps N"
+  exit 1
+fi
+
+# ---------------------------------------------------------------------------
+# Caminhos
+# ---------------------------------------------------------------------------
+
+SCRIPT_DIR="$(cd "$(dirname "${BASH_SOURCE[0]}")" && pwd)"
+PROJECT_DIR="$(dirname "$SCRIPT_DIR")"
+RESULTS_DIR="$PROJECT_DIR/results/latency_$(date +%Y%m%d_%H%M%S)"
+LOAD_TEST_SCRIPT="$SCRIPT_DIR/load-test-latency.js"
+
+mkdir -p "$RESULTS_DIR"
+
+# ---------------------------------------------------------------------------
+# Cores e helpers de log
+# ---------------------------------------------------------------------------
+
+CYAN='\033[0;36m'; YELLOW='\033[1;33m'; GREEN='\033[0;32m'
+RED='\033[0;31m';  BOLD='\033[1m';      NC='\033[0m'
+
+log()     { echo -e "${CYAN}[$(date +%H:%M:%S)]${NC} $*"; }
+success() { echo -e "${GREEN}[$(date +%H:%M:%S)] ✓${NC} $*"; }
+error()   { echo -e "${RED}[$(date +%H:%M:%S)] ✗${NC} $*" >&2; }
+warn()    { echo -e "${YELLOW}[$(date +%H:%M:%S)] !${NC} $*"; }
+header()  { echo -e "\n${BOLD}${YELLOW}=== $* ===${NC}\n"; }
+
+source "$SCRIPT_DIR/lib/remote.sh"
+source "$SCRIPT_DIR/lib/pinning.sh"
+source "$SCRIPT_DIR/lib/fingerprint.sh"
+source "$SCRIPT_DIR/lib/manifest.sh"
+source "$SCRIPT_DIR/lib/energy.sh"
+source "$SCRIPT_DIR/lib/perfstat.sh"
+source "$SCRIPT_DIR/lib/table.sh"
+remote_setup "$REMOTE" "$TARGET_HOST" || exit 1
+pin_setup "$PIN" || exit 1
+energy_setup "$ENERGY" || exit 1
+perfstat_setup "$PERF_STAT" || exit 1
+
+# ---------------------------------------------------------------------------
+# Frameworks definidos
+# ---------------------------------------------------------------------------
+
+declare -A FRAMEWORK_PORTS=(
+  [express]=3001
+  [fastify]=3002
+  [elysia]=3003
+  [actix]=3004
+  [gin]=3005
+)
+
+declare -A FRAMEWORK_SERVICES=(
+  [express]=api-express
+  [fastify]=api-fastify
+  [elysia]=api-elysia
+  [actix]=api-actix
+  [gin]=api-gin
+)
+
+if [ -n "$FRAMEWORKS" ]; then
+  read -r -a FRAMEWORK_ORDER <<< "$FRAMEWORKS"
+else
+  FRAMEWORK_ORDER=(express fastify elysia actix gin)
+fi
+
+# ---------------------------------------------------------------------------
+# Inicia PostgreSQL
+# ---------------------------------------------------------------------------
+
+header "Iniciando PostgreSQL"
+cd "$PROJECT_DIR"
+docker compose up -d postgres
+
+for i in $(seq 1 30); do
+  if docker compose exec -T postgres pg_isready -U benchmark -d benchmark &>/dev/null 2>&1; then
+    success "PostgreSQL pronto (tentativa $i)"
+    break
+  fi
+  if [ "$i" -eq 30 ]; then
+    error "PostgreSQL não ficou pronto em 30s"
+    exit 1
+  fi
+  sleep 1
+done
+
+if [ "$REMOTE_MODE" != off ]; then
+  remote_clock_offset > "$RESULTS_DIR/clock_offset.json"
+  log "Relógio do alvo: ${CLOCK_OFFSET_MS}ms em relação ao local"
+fi
+USER_IDS=10000
+if [ "$TABLE_ROWS" -gt 0 ]; then
+  table_seed "$TABLE_ROWS" || exit 1
+  USER_IDS=$TABLE_ROWS
+fi
+fingerprint_write "$RESULTS_DIR/environment.json"
+manifest_write "$RESULTS_DIR/run_manifest.json" "${ARGS[@]}"
+
+# ---------------------------------------------------------------------------
+# Medição por framework
+# ---------------------------------------------------------------------------
+
+SUMMARY_CSV="$RESULTS_DIR/latency_summary.csv"
+echo "framework,max_rps,target_rps,achieved_rps,p50_ms,p90_ms,p99_ms,p999_ms,max_ms,error_rate,dropped_iterations,blocked_p99_ms,connect_p99_ms,tls_p99_ms,ttfb_p99_ms,new_connections,errors,schema_checked,schema_mismatches,energy_j,joules_per_1k_req,context_switches,syscalls,cache_misses,branch_misses,table_rows,table_bytes" > "$SUMMARY_CSV"
+OVER_BUDGET=()
+
+for FRAMEWORK in "${FRAMEWORK_ORDER[@]}"; do
+  SERVICE="${FRAMEWORK_SERVICES[$FRAMEWORK]}"
+  PORT="${FRAMEWORK_PORTS[$FRAMEWORK]}"
+  API_URL="http://$TARGET_HOST:$PORT"
+
+  # --- Taxa alvo ---
+  MAX_RPS=0
+  if [ "$FIXED_RPS" -gt 0 ]; then
+    TARGET_RPS=$FIXED_RPS
+  else
+    MAX_FILE="$SATURATION_DIR/$FRAMEWORK/max_sustainable_rps.txt"
+    if [ ! -f "$MAX_FILE" ]; then
+      warn "[$FRAMEWORK] $MAX_FILE não encontrado — pulando"
+      continue
+    fi
+    MAX_RPS=$(cat "$MAX_FILE")
+    TARGET_RPS=$(( MAX_RPS * LOAD_PCT / 100 ))
+    if [ "$TARGET_RPS" -lt 1 ]; then
+      warn "[$FRAMEWORK] RPS máximo inválido ($MAX_RPS) — pulando"
+      continue
+    fi
+  fi
+
+  header "Latência: $FRAMEWORK (${TARGET_RPS} req/s fixos)"
+
+  docker compose up -d "$SERVICE"
+  for i in $(seq 1 30); do
+    if curl -sf "$API_URL/" &>/dev/null 2>&1; then
+      success "API $FRAMEWORK respondendo (tentativa $i)"
+      break
+    fi
+    if [ "$i" -eq 30 ]; then
+      error "API $FRAMEWORK não respondeu em 30s"
+      docker compose stop "$SERVICE"
+      continue 2
+    fi
+    sleep 1
+  done
+
+  # Configuração efetiva da API (só o Actix expõe /config)
+  curl -sf "$API_URL/config" > "$RESULTS_DIR/config_${FRAMEWORK}.json" 2>/dev/null || rm -f "$RESULTS_DIR/config_${FRAMEWORK}.json"
+  manifest_image "$RESULTS_DIR/run_manifest.json" "$SERVICE"
+  pin_json postgres "$SERVICE" > "$RESULTS_DIR/cpu_pinning_${FRAMEWORK}.json"
+
+  log "Warm-up de $WARMUP_DURATION ($FRAMEWORK)..."
+  pin_loadgen k6 run \
+    -e API_URL="$API_URL" \
+    -e TARGET_RPS="$TARGET_RPS" \
+    -e DURATION="$WARMUP_DURATION" \
+    -e USER_IDS="$USER_IDS" \
+    -e ERROR_BUDGET=1 \
+    --quiet \
+    "$LOAD_TEST_SCRIPT" >/dev/null || true
+
+  FW_SUMMARY="$RESULTS_DIR/latency_${FRAMEWORK}.json"
+  FW_CONSOLE="$RESULTS_DIR/latency_${FRAMEWORK}_console.log"
+  FW_K6_CSV="$RESULTS_DIR/latency_${FRAMEWORK}_k6.csv.gz"
+  K6_OUT=()
+  if [ "$HEATMAP" = true ]; then
+    K6_OUT=(--out "csv=$FW_K6_CSV")
+  fi
+  K6_EXIT=0
+  # CPU e memória do host remoto durante a medição (só com --remote ssh://)
+  remote_sample_start "$RESULTS_DIR/host_stats_${FRAMEWORK}.csv"
+  energy_sample_start "$RESULTS_DIR/energy_${FRAMEWORK}.csv"
+  perfstat_start "benchmark_${FRAMEWORK}" "$RESULTS_DIR/perf_stat_${FRAMEWORK}.txt"
+  pin_loadgen k6 run \
+    -e API_URL="$API_URL" \
+    -e TARGET_RPS="$TARGET_RPS" \
+    -e DURATION="$DURATION" \
+    -e USER_IDS="$USER_IDS" \
+    -e SUMMARY_FILE="$FW_SUMMARY" \
+    -e ERROR_BUDGET="$ERROR_BUDGET" \
+    -e SPEC_FILE="$SPEC_FILE" \
+    -e SCHEMA_SAMPLE="$SCHEMA_SAMPLE" \
+    --console-output "$FW_CONSOLE" \
+    ${K6_OUT[@]+"${K6_OUT[@]}"} \
+    --quiet \
+    "$LOAD_TEST_SCRIPT" || K6_EXIT=$?
+  remote_sample_stop
+  energy_sample_stop
+  perfstat_stop
+  # Decisões do POOL_AUTOSIZE (só o Actix, com POOL_AUTOSIZE=on)
+  if curl -sf "$API_URL/pool-stats" > "$RESULTS_DIR/pool_stats_${FRAMEWORK}.json" 2>/dev/null \
+    && python3 -c "import json, sys; sys.exit(0 if json.load(open(sys.argv[1])).get('autosize') else 1)" \
+         "$RESULTS_DIR/pool_stats_${FRAMEWORK}.json" 2>/dev/null; then
+    python3 "$SCRIPT_DIR/pool-autosize.py" \
+      --pool-stats "$RESULTS_DIR/pool_stats_${FRAMEWORK}.json" \
+      --framework "$FRAMEWORK" \
+      --output-dir "$RESULTS_DIR" || warn "[$FRAMEWORK] falha ao gerar o gráfico do POOL_AUTOSIZE"
+  else
+    rm -f "$RESULTS_DIR/pool_stats_${FRAMEWORK}.json"
+  fi
+  ENERGY_J=""
+  if $ENERGY_ENABLED; then
+    ENERGY_J=$(energy_joules "$RESULTS_DIR/energy_${FRAMEWORK}.csv")
+  fi
+  # Tamanho depois da medição: os POST /users de cada framework somam linhas
+  TABLE_BYTES=$(table_bytes 2>/dev/null || true)
+  PERF_COUNTS=",,,"
+  if $PERFSTAT_ENABLED; then
+    PERF_COUNTS=$(perfstat_values "$RESULTS_DIR/perf_stat_${FRAMEWORK}.txt")
+  fi
+
+  if [ "$HEATMAP" = true ] && [ -f "$FW_K6_CSV" ]; then
+    python3 "$SCRIPT_DIR/latency-heatmap.py" \
+      --k6-csv "$FW_K6_CSV" \
+      --framework "$FRAMEWORK" \
+      --output-dir "$RESULTS_DIR" || warn "[$FRAMEWORK] falha ao gerar o heatmap de latência"
+  fi
+
+  # Amostras de falha por classe (lib/errors.js) → latency_<fw>_errors.json
+  python3 - "$FW_CONSOLE" "$RESULTS_DIR/latency_${FRAMEWORK}_errors.json" <<'PYEOF'
+import json, re, sys
+src, dst = sys.argv[1], sys.argv[2]
+samples = {}
+try:
+    lines = open(src, encoding='utf-8', errors='replace').read().splitlines()
+except FileNotFoundError:
+    lines = []
+for line in lines:
+    if 'error_sample' not in line:
+        continue
+    # Linha crua ou no formato de log do k6 (msg="...")
+    m = re.search(r'msg="((?:[^"\\]|\\.)*)"', line)
+    try:
+        sample = json.loads(json.loads(f'"{m.group(1)}"') if m else line)
+    except (ValueError, TypeError):
+        continue
+    samples.setdefault(sample.pop('error_sample'), []).append(sample)
+with open(dst, 'w') as f:
+    json.dump(samples, f, indent=2, ensure_ascii=False)
+PYEOF
+  if [ "$K6_EXIT" -eq 99 ]; then
+    warn "[$FRAMEWORK] taxa de falhas acima do orçamento ($ERROR_BUDGET) — ver latency_${FRAMEWORK}_errors.json"
+    OVER_BUDGET+=("$FRAMEWORK")
+  fi
+
+  if [ -f "$FW_SUMMARY" ]; then
+    python3 - "$FW_SUMMARY" "$FRAMEWORK" "$MAX_RPS" "$ENERGY_J" "$PERF_COUNTS" \
+      "${TABLE_ROWS#0}" "$TABLE_BYTES" >> "$SUMMARY_CSV" <<'PYEOF'
+import json, sys
+path, fw, max_rps, energy_j, perf_counts, table_rows, table_bytes = sys.argv[1:8]
+with open(path) as f: s = json.load(f)
+per_1k = f"{float(energy_j) / s['requests'] * 1000:.3f}" if energy_j and s['requests'] else ""
+print(",".join([
+    fw, max_rps, str(s['target_rps']),
+    f"{s['achieved_rps']:.2f}", f"{s['p50_ms']:.2f}", f"{s['p90_ms']:.2f}",
+    f"{s['p99_ms']:.2f}", f"{s['p999_ms']:.2f}", f"{s['max_ms']:.2f}",
+    f"{s['error_rate']*100:.4f}", str(int(s['dropped_iterations'])),
+    *(f"{s['phases'][p]['p99_ms']:.2f}" for p in ('blocked', 'connecting', 'tls_handshaking', 'waiting')),
+    str(int(s['new_connections'])),
+    ";".join(f"{cls}={n}" for cls, n in s['errors'].items()),
+    str(int(s['schema_checked'])), str(int(s['schema_mismatches'])),
+    energy_j, per_1k, perf_counts, table_rows, table_bytes,
+]))
+PYEOF
+    DROPPED=$(python3 -c "import json; print(int(json.load(open('$FW_SUMMARY'))['dropped_iterations']))")
+    if [ "$DROPPED" -gt 0 ]; then
+      warn "[$FRAMEWORK] $DROPPED iterações descartadas — taxa não sustentada, percentis não confiáveis"
+    fi
+  else
+    warn "[$FRAMEWORK] k6 não gerou $FW_SUMMARY"
+  fi
+
+  docker compose stop "$SERVICE"
+  sleep 3
+done
+
+fingerprint_builds "$RESULTS_DIR/environment.json"
+manifest_image "$RESULTS_DIR/run_manifest.json" postgres
+
+# Histórico opcional (RESULTS_DB=results/history.sqlite ou postgresql://...)
+if [ -n "${RESULTS_DB:-}" ]; then
+  python3 "$SCRIPT_DIR/store-results.py" --db "$RESULTS_DB" "$RESULTS_DIR" \
+    || warn "Falha ao gravar no histórico ($RESULTS_DB)"
+fi
+
+header "Resultado"
+column -t -s, "$SUMMARY_CSV"
+echo ""
+success "Resultados em: $RESULTS_DIR"
+
+if [ ${#OVER_BUDGET[@]} -gt 0 ]; then
+  error "Orçamento de falhas ($ERROR_BUDGET) estourado: ${OVER_BUDGET[*]}"
+  exit 1
+fi
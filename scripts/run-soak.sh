@@ -0,0 +1,263 @@
+#!/usr/bin/env bash
+# run-soak.sh — teste de soak: carga mista por horas com snapshots periódicos
+#
+# Rodadas de 120s não revelam crescimento lento de memória nem degradação
+# progressiva de latência. Este script mantém o cenário misto do
+# load-test-latency.js a uma taxa fixa por um período longo, amostra CPU e RSS
+# do container a cada 10s e, ao final, o analyze-soak.py consolida snapshots
+# por janela (default: 1 min) e sinaliza tendências de RSS e de p99.
+#
+# Uso:
+#   ./scripts/run-soak.sh --duration 2h --rps 1000 [--frameworks "actix gin"]
+#   ./scripts/run-soak.sh --duration 2h --saturation-dir results/<timestamp> [--load-pct 50]
+#
+# Opções:
+#   --duration Xh         duração do soak por framework (default: 2h)
+#   --rps N               taxa fixa para todos os frameworks
+#   --saturation-dir DIR  usa <DIR>/<framework>/max_sustainable_rps.txt
+#   --load-pct N          % do RPS máximo sustentável (default: 50)
+#   --interval N          janela dos snapshots em segundos (default: 60)
+#   --rss-threshold N     crescimento de RSS sinalizado em MB/h (default: 5)
+#   --p99-drift N         deriva de p99 sinalizada em %/h (default: 20)
+#   --frameworks "a b"    subconjunto de frameworks (default: todos)
+#
+set -euo pipefail
+
+# ---------------------------------------------------------------------------
+# Parâmetros (com defaults)
+# ---------------------------------------------------------------------------
+
+DURATION=2h
+FIXED_RPS=0
+SATURATION_DIR=""
+LOAD_PCT=50
+INTERVAL=60
+RSS_THRESHOLD=5
+P99_DRIFT=20
+FRAMEWORKS=""
+STATS_EVERY=10
+
+ARGS=("$@")
+while [[ $# -gt 0 ]]; do
+  case $1 in
+    --duration)       DURATION="$2";       shift 2 ;;
+    --rps)            FIXED_RPS="$2";      shift 2 ;;
+    --saturation-dir) SATURATION_DIR="$2"; shift 2 ;;
+    --load-pct)       LOAD_PCT="$2";       shift 2 ;;
+    --interval)       INTERVAL="$2";       shift 2 ;;
+    --rss-threshold)  RSS_THRESHOLD="$2";  shift 2 ;;
+    --p99-drift)      P99_DRIFT="$2";      shift 2 ;;
+    --frameworks)     FRAMEWORKS="$2";     shift 2 ;;
+    *) echo "Argumento desconhecido: $1"; exit 1 ;;
+  esac
+done
+
+if [ -z "$SATURATION_DIR" ] && [ "$FIXED_RPS" -eq 0 ]; then
+  echo "Informe --rps N ou --saturation-dir DIR"
+  exit 1
+fi
+
+# ---------------------------------------------------------------------------
+# Caminhos
+# ---------------------------------------------------------------------------
+
+SCRIPT_DIR="$(cd "$(dirname "${BASH_SOURCE[0]}")" && pwd)"
+PROJECT_DIR="$(dirname "$SCRIPT_DIR")"
+RESULTS_DIR="$PROJECT_DIR/results/soak_$(date +%Y%m%d_%H%M%S)"
+LOAD_TEST_SCRIPT="$SCRIPT_DIR/load-test-latency.js"
+ANALYZE_SCRIPT="$SCRIPT_DIR/analyze-soak.py"
+
+mkdir -p "$RESULTS_DIR"
+
+# ---------------------------------------------------------------------------
+# Cores e helpers de log
+# ---------------------------------------------------------------------------
+
+CYAN='\033[0;36m'; YELLOW='\033[1;33m'; GREEN='\033[0;32m'
+RED='\033[0;31m';  BOLD='\033[1m';      NC='\033[0m'
+
+log()     { echo -e "${CYAN}[$(date +%H:%M:%S)]${NC} $*"; }
+success() { echo -e "${GREEN}[$(date +%H:%M:%S)] ✓${NC} $*"; }
+error()   { echo -e "${RED}[$(date +%H:%M:%S)] ✗${NC} $*" >&2; }
+warn()    { echo -e "${YELLOW}[$(date +%H:%M:%S)] !${NC} $*"; }
+header()  { echo -e "\n${BOLD}${YELLOW}=== $* ===${NC}\n"; }
+
+source "$SCRIPT_DIR/lib/fingerprint.sh"
+source "$SCRIPT_DIR/lib/manifest.sh"
+fingerprint_write "$RESULTS_DIR/environment.json"
+manifest_write "$RESULTS_DIR/run_manifest.json" "${ARGS[@]}"
+
+# Amostra CPU% e memória (MB) do container a cada STATS_EVERY segundos
+sample_stats() {
+  local service="$1" out="$2"
+  echo "timestamp,cpu_pct,mem_mb" > "$out"
+  while true; do
+    docker stats --no-stream --format "{{.CPUPerc}},{{.MemUsage}}" "$service" 2>/dev/null \
+      | awk -F',' -v t="$(date +%s)" '{
+          cpu=$1; gsub(/%/,"",cpu); cpu=cpu+0;
+          mem=$2; sub(/ \/ .*/,"",mem);
+          val=mem; gsub(/[^0-9.]/,"",val); val=val+0;
+          if (mem ~ /GiB/) val=val*1024;
+          else if (mem ~ /KiB/) val=val/1024;
+          printf "%d,%.2f,%.1f\n", t, cpu, val
+        }' >> "$out"
+    sleep "$STATS_EVERY"
+  done
+}
+
+# ---------------------------------------------------------------------------
+# Frameworks definidos
+# ---------------------------------------------------------------------------
+
+declare -A FRAMEWORK_PORTS=(
+  [express]=3001
+  [fastify]=3002
+  [elysia]=3003
+  [actix]=3004
+  [gin]=3005
+)
+
+declare -A FRAMEWORK_SERVICES=(
+  [express]=api-express
+  [fastify]=api-fastify
+  [elysia]=api-elysia
+  [actix]=api-actix
+  [gin]=api-gin
+)
+
+if [ -n "$FRAMEWORKS" ]; then
+  read -r -a FRAMEWORK_ORDER <<< "$FRAMEWORKS"
+else
+  FRAMEWORK_ORDER=(express fastify elysia actix gin)
+fi
+
+# ---------------------------------------------------------------------------
+# Inicia PostgreSQL
+# ---------------------------------------------------------------------------
+
+header "Iniciando PostgreSQL"
+cd "$PROJECT_DIR"
+docker compose up -d postgres
+
+for i in $(seq 1 30); do
+  if docker compose exec -T postgres pg_isready -U benchmark -d benchmark &>/dev/null 2>&1; then
+    success "PostgreSQL pronto (tentativa $i)"
+    break
+  fi
+  if [ "$i" -eq 30 ]; then
+    error "PostgreSQL não ficou pronto em 30s"
+    exit 1
+  fi
+  sleep 1
+done
+
+# ---------------------------------------------------------------------------
+# Soak por framework
+# ---------------------------------------------------------------------------
+
+SUMMARY_CSV="$RESULTS_DIR/soak_summary.csv"
+echo "framework,target_rps,duration,rss_start_mb,rss_end_mb,rss_slope_mb_per_h,p99_slope_ms_per_h,p99_drift_pct_per_h,flags" > "$SUMMARY_CSV"
+
+for FRAMEWORK in "${FRAMEWORK_ORDER[@]}"; do
+  SERVICE="${FRAMEWORK_SERVICES[$FRAMEWORK]}"
+  PORT="${FRAMEWORK_PORTS[$FRAMEWORK]}"
+  API_URL="http://localhost:$PORT"
+  FW_DIR="$RESULTS_DIR/$FRAMEWORK"
+  mkdir -p "$FW_DIR"
+
+  if [ "$FIXED_RPS" -gt 0 ]; then
+    TARGET_RPS=$FIXED_RPS
+  else
+    MAX_FILE="$SATURATION_DIR/$FRAMEWORK/max_sustainable_rps.txt"
+    if [ ! -f "$MAX_FILE" ]; then
+      warn "[$FRAMEWORK] $MAX_FILE não encontrado — pulando"
+      continue
+    fi
+    TARGET_RPS=$(( $(cat "$MAX_FILE") * LOAD_PCT / 100 ))
+  fi
+
+  header "Soak: $FRAMEWORK (${TARGET_RPS} req/s × ${DURATION})"
+
+  # Container recriado para que o RSS parta do zero
+  docker compose up -d --force-recreate "$SERVICE"
+  for i in $(seq 1 30); do
+    if curl -sf "$API_URL/" &>/dev/null 2>&1; then
+      success "API $FRAMEWORK respondendo (tentativa $i)"
+      break
+    fi
+    if [ "$i" -eq 30 ]; then
+      error "API $FRAMEWORK não respondeu em 30s"
+      docker compose stop "$SERVICE"
+      continue 2
+    fi
+    sleep 1
+  done
+
+  # Configuração efetiva da API (só o Actix expõe /config)
+  curl -sf "$API_URL/config" > "$RESULTS_DIR/config_${FRAMEWORK}.json" 2>/dev/null || rm -f "$RESULTS_DIR/config_${FRAMEWORK}.json"
+  manifest_image "$RESULTS_DIR/run_manifest.json" "$SERVICE"
+
+  STATS_FILE="$FW_DIR/docker_stats.csv"
+  sample_stats "$SERVICE" "$STATS_FILE" &
+  STATS_PID=$!
+
+  k6 run \
+    -e API_URL="$API_URL" \
+    -e TARGET_RPS="$TARGET_RPS" \
+    -e DURATION="$DURATION" \
+    -e SUMMARY_FILE="$FW_DIR/k6_summary.json" \
+    --out "csv=$FW_DIR/k6.csv" \
+    --quiet \
+    "$LOAD_TEST_SCRIPT" || true
+
+  kill "$STATS_PID" 2>/dev/null || true
+  wait "$STATS_PID" 2>/dev/null || true
+
+  python3 "$ANALYZE_SCRIPT" \
+    --k6-csv "$FW_DIR/k6.csv" \
+    --stats-csv "$STATS_FILE" \
+    --framework "$FRAMEWORK" \
+    --output-dir "$FW_DIR" \
+    --interval "$INTERVAL" \
+    --rss-threshold "$RSS_THRESHOLD" \
+    --p99-drift "$P99_DRIFT" \
+    --plot || true
+
+  REPORT="$FW_DIR/soak_${FRAMEWORK}_report.json"
+  if [ -f "$REPORT" ]; then
+    python3 - "$REPORT" "$TARGET_RPS" "$DURATION" >> "$SUMMARY_CSV" <<'PYEOF'
+import json, sys
+path, rps, duration = sys.argv[1], sys.argv[2], sys.argv[3]
+with open(path) as f: r = json.load(f)
+print(",".join([
+    r['framework'], rps, duration,
+    str(r['rss_start_mb']), str(r['rss_end_mb']), str(r['rss_slope_mb_per_h']),
+    str(r['p99_slope_ms_per_h']), str(r['p99_drift_pct_per_h']),
+    "|".join(r['flags']),
+]))
+PYEOF
+    FLAGS=$(python3 -c "import json; print(' '.join(json.load(open('$REPORT'))['flags']))")
+    if [ -n "$FLAGS" ]; then
+      warn "[$FRAMEWORK] tendências detectadas: $FLAGS"
+    else
+      success "[$FRAMEWORK] sem tendências de RSS ou latência"
+    fi
+  fi
+
+  docker compose stop "$SERVICE"
+  sleep 3
+done
+
+fingerprint_builds "$RESULTS_DIR/environment.json"
+manifest_image "$RESULTS_DIR/run_manifest.json" postgres
+
+# Histórico opcional (RESULTS_DB=results/history.sqlite ou postgresql://...)
+if [ -n "${RESULTS_DB:-}" ]; then
+  python3 "$SCRIPT_DIR/store-results.py" --db "$RESULTS_DB" "$RESULTS_DIR" \
+    || warn "Falha ao gravar no histórico ($RESULTS_DB)"
+fi
+
+header "Resultado"
+column -t -s, "$SUMMARY_CSV"
+echo ""
+success "Resultados em: $RESULTS_DIR"
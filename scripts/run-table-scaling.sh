@@ -0,0 +1,113 @@
+#!/usr/bin/env bash
+# run-table-scaling.sh — latência em função do tamanho da tabela users
+#
+# O seed padrão (10.000 linhas) cabe inteiro na memória e o benchmark mede só
+# o framework. Para cada tamanho o script recria users com N linhas
+# (scripts/lib/table.sh) e roda o run-latency.sh com --table-rows N: o k6
+# sorteia ids de 1 a N e o resumo registra N e o tamanho em disco. No fim,
+# table_scaling_summary.csv junta os latency_summary.csv de todos os
+# tamanhos, e a tabela volta ao seed do init.sql.
+#
+# O seed de 100M linhas ocupa ~15GB (tabela + índices) e leva minutos; o
+# volume do postgres precisa de espaço para isso. Os ids continuam cabendo
+# em INTEGER; acima de ~2 bilhões use o Actix com bigint-keys.
+#
+# Uso:
+#   ./scripts/run-table-scaling.sh --rps 1000 [--sizes 10000,1000000,100000000]
+#   ./scripts/run-table-scaling.sh --rps 500 --sizes 10000,1000000 --frameworks "actix gin"
+#
+# Opções:
+#   --sizes A,B,...  tamanhos da tabela, em linhas (default: 10000,1000000,100000000)
+#   --keep           não volta ao seed de 10.000 linhas no fim
+#   demais opções    repassadas ao run-latency.sh (--rps ou --saturation-dir são
+#                    obrigatórias; a taxa é a mesma em todos os tamanhos)
+#
+set -euo pipefail
+
+# ---------------------------------------------------------------------------
+# Parâmetros (com defaults)
+# ---------------------------------------------------------------------------
+
+SIZES="10000,1000000,100000000"
+KEEP=false
+LATENCY_ARGS=()
+
+while [[ $# -gt 0 ]]; do
+  case $1 in
+    --sizes)      SIZES="$2"; shift 2 ;;
+    --keep)       KEEP=true;  shift ;;
+    --table-rows) echo "Use --sizes em vez de --table-rows"; exit 1 ;;
+    *)            LATENCY_ARGS+=("$1"); shift ;;
+  esac
+done
+
+# ---------------------------------------------------------------------------
+# Caminhos
+# ---------------------------------------------------------------------------
+
+SCRIPT_DIR="$(cd "$(dirname "${BASH_SOURCE[0]}")" && pwd)"
+PROJECT_DIR="$(dirname "$SCRIPT_DIR")"
+RESULTS_DIR="$PROJECT_DIR/results/table_scaling_$(date +%Y%m%d_%H%M%S)"
+
+mkdir -p "$RESULTS_DIR"
+
+# ---------------------------------------------------------------------------
+# Cores e helpers de log
+# ---------------------------------------------------------------------------
+
+CYAN='\033[0;36m'; YELLOW='\033[1;33m'; GREEN='\033[0;32m'
+RED='\033[0;31m';  BOLD='\033[1m';      NC='\033[0m'
+
+log()     { echo -e "${CYAN}[$(date +%H:%M:%S)]${NC} $*"; }
+success() { echo -e "${GREEN}[$(date +%H:%M:%S)] ✓${NC} $*"; }
+error()   { echo -e "${RED}[$(date +%H:%M:%S)] ✗${NC} $*" >&2; }
+warn()    { echo -e "${YELLOW}[$(date +%H:%M:%S)] !${NC} $*"; }
+header()  { echo -e "\n${BOLD}${YELLOW}=== $* ===${NC}\n"; }
+
+source "$SCRIPT_DIR/lib/table.sh"
+
+# ---------------------------------------------------------------------------
+# Varredura
+# ---------------------------------------------------------------------------
+
+cd "$PROJECT_DIR"
+SUMMARY_CSV="$RESULTS_DIR/table_scaling_summary.csv"
+FAILED=()
+
+IFS=, read -r -a SIZE_LIST <<< "$SIZES"
+for ROWS in "${SIZE_LIST[@]}"; do
+  header "Tabela com $ROWS linhas"
+  if ! "$SCRIPT_DIR/run-latency.sh" --table-rows "$ROWS" ${LATENCY_ARGS[@]+"${LATENCY_ARGS[@]}"}; then
+    warn "run-latency.sh terminou com erro em $ROWS linhas"
+    FAILED+=("$ROWS")
+  fi
+
+  # O diretório que o run-latency.sh acabou de criar
+  RUN_DIR=$(ls -td "$PROJECT_DIR"/results/latency_* 2>/dev/null | head -1)
+  if [ -z "$RUN_DIR" ] || [ ! -f "$RUN_DIR/latency_summary.csv" ]; then
+    warn "Sem latency_summary.csv para $ROWS linhas"
+    continue
+  fi
+  ln -sfn "$RUN_DIR" "$RESULTS_DIR/rows_$ROWS"
+  if [ ! -f "$SUMMARY_CSV" ]; then
+    head -1 "$RUN_DIR/latency_summary.csv" > "$SUMMARY_CSV"
+  fi
+  tail -n +2 "$RUN_DIR/latency_summary.csv" >> "$SUMMARY_CSV"
+done
+
+if [ "$KEEP" = false ]; then
+  header "Restaurando o seed"
+  table_seed 10000 || warn "Falha ao restaurar users com 10.000 linhas"
+fi
+
+header "Resultado"
+if [ -f "$SUMMARY_CSV" ]; then
+  column -t -s, "$SUMMARY_CSV"
+fi
+echo ""
+success "Resultados em: $RESULTS_DIR"
+
+if [ ${#FAILED[@]} -gt 0 ]; then
+  error "Tamanhos com erro: ${FAILED[*]}"
+  exit 1
+fi
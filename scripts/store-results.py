@@ -0,0 +1,210 @@
+#!/usr/bin/env python3
+"""
+store-results.py — grava execuções no histórico de resultados (SQLite ou PostgreSQL).
+
+Cada diretório de resultados vira uma linha em `runs` (suíte, início, commit,
+host, comando) e, por alvo e métrica, uma linha em `metrics`, com a mediana
+das rodadas. O schema está em dashboard/schema.sql; o dashboard/ desenha as
+tendências por commit a partir dele.
+
+A suíte vem do CSV de resumo presente no diretório:
+  summary.csv            experiment  (alvo: framework; mediana das rodadas)
+  latency_summary.csv    latency     (framework, ou framework/linhas com --table-rows)
+  k8s_summary.csv        k8s         (framework)
+  allocator_summary.csv  allocators  (alocador)
+  chaos_summary.csv      chaos       (framework/fase)
+  soak_summary.csv       soak        (framework)
+Colunas do alvo vazias ou ausentes ficam fora dele, então resumos antigos
+mantêm os alvos de sempre. Toda coluna numérica vira uma métrica; `achieved_rps` é gravada também como
+`rps`, para que todas as suítes tenham a mesma métrica de vazão.
+
+Commit, início e comando vêm do run_manifest.json, e o host do
+environment.json; sem eles, do nome e da data do diretório. Gravar de novo a
+mesma execução substitui a anterior.
+
+Uso:
+  python3 scripts/store-results.py results/20250101_120000 [results/latency_* ...]
+  python3 scripts/store-results.py --db postgresql://bench@db/history results/*
+
+  --db   arquivo SQLite ou URL postgresql:// (default: $RESULTS_DB ou
+         results/history.sqlite); o PostgreSQL é gravado pelo psql
+"""
+
+import argparse
+import csv
+import json
+import math
+import os
+import shlex
+import sqlite3
+import statistics
+import subprocess
+import sys
+from collections import defaultdict
+from datetime import datetime, timezone
+from pathlib import Path
+
+PROJECT_DIR = Path(__file__).resolve().parent.parent
+SCHEMA = PROJECT_DIR / 'dashboard' / 'schema.sql'
+
+# arquivo de resumo → (suíte, colunas que formam o alvo)
+SUITES = [
+    ('summary.csv',           'experiment', ['framework']),
+    ('latency_summary.csv',   'latency',    ['framework', 'table_rows']),
+    ('k8s_summary.csv',       'k8s',        ['framework']),
+    ('allocator_summary.csv', 'allocators', ['allocator']),
+    ('chaos_summary.csv',     'chaos',      ['framework', 'phase']),
+    ('soak_summary.csv',      'soak',       ['framework']),
+]
+
+# Colunas numéricas que não são medidas
+IGNORED = {'run'}
+
+RUN_COLUMNS = ['run_id', 'suite', 'started_at', 'git_sha', 'dirty',
+               'hostname', 'cpu_model', 'kernel', 'command', 'replay_of']
+
+
+def parse_args():
+    p = argparse.ArgumentParser(description="Grava execuções no histórico de resultados")
+    p.add_argument('dirs', nargs='+', help="Diretórios de resultados")
+    p.add_argument('--db', default=os.environ.get('RESULTS_DB') or str(PROJECT_DIR / 'results' / 'history.sqlite'),
+                   help="Arquivo SQLite ou URL postgresql://")
+    return p.parse_args()
+
+
+def load_json(path: Path):
+    try:
+        with open(path) as f:
+            return json.load(f)
+    except (OSError, ValueError):
+        return None
+
+
+def to_utc(timestamp: str) -> str:
+    dt = datetime.fromisoformat(timestamp)
+    if dt.tzinfo is None:
+        dt = dt.astimezone()
+    return dt.astimezone(timezone.utc).strftime('%Y-%m-%dT%H:%M:%SZ')
+
+
+def read_metrics(csv_path: Path, key_columns: list) -> dict:
+    """(alvo, métrica) → mediana dos valores numéricos das linhas do alvo."""
+    values = defaultdict(list)
+    with open(csv_path, newline='') as f:
+        for row in csv.DictReader(f):
+            target = '/'.join(row[c] for c in key_columns if row.get(c))
+            for column, raw in row.items():
+                if column in key_columns or column in IGNORED or raw in (None, ''):
+                    continue
+                try:
+                    value = float(raw)
+                except ValueError:
+                    continue
+                if math.isfinite(value):
+                    values[(target, column)].append(value)
+                    if column == 'achieved_rps' and 'rps' not in row:
+                        values[(target, 'rps')].append(value)
+    return {key: statistics.median(v) for key, v in values.items()}
+
+
+def read_run(results_dir: Path):
+    """Metadados e métricas de um diretório; None se não há resumo conhecido."""
+    for filename, suite, key_columns in SUITES:
+        if (results_dir / filename).exists():
+            break
+    else:
+        return None
+
+    manifest = load_json(results_dir / 'run_manifest.json') or {}
+    environment = load_json(results_dir / 'environment.json') or {}
+    host = environment.get('target') if isinstance(environment.get('target'), dict) else environment.get('loadgen') or {}
+    repo = manifest.get('repo') or environment.get('repo') or {}
+
+    started = manifest.get('created_at') or environment.get('collected_at')
+    started_at = to_utc(started) if started else \
+        datetime.fromtimestamp((results_dir / filename).stat().st_mtime, timezone.utc).strftime('%Y-%m-%dT%H:%M:%SZ')
+    if 'patch' in repo:
+        dirty = repo['patch'] is not None
+    else:
+        dirty = repo.get('dirty')
+    command = shlex.join([manifest['script'], *manifest.get('args', [])]) if manifest.get('script') else None
+
+    run = {
+        'run_id':     results_dir.name,
+        'suite':      suite,
+        'started_at': started_at,
+        'git_sha':    repo.get('git_sha'),
+        'dirty':      dirty,
+        'hostname':   host.get('hostname'),
+        'cpu_model':  host.get('cpu_model'),
+        'kernel':     host.get('kernel'),
+        'command':    command,
+        'replay_of':  manifest.get('replay_of'),
+    }
+    return run, read_metrics(results_dir / filename, key_columns)
+
+
+def store_sqlite(db: str, runs: list):
+    Path(db).parent.mkdir(parents=True, exist_ok=True)
+    conn = sqlite3.connect(db)
+    with conn:
+        conn.executescript(SCHEMA.read_text())
+        for run, metrics in runs:
+            conn.execute("DELETE FROM metrics WHERE run_id = ?", (run['run_id'],))
+            conn.execute("DELETE FROM runs WHERE run_id = ?", (run['run_id'],))
+            conn.execute(f"INSERT INTO runs ({', '.join(RUN_COLUMNS)}) VALUES ({', '.join('?' * len(RUN_COLUMNS))})",
+                         [run[c] for c in RUN_COLUMNS])
+            conn.executemany("INSERT INTO metrics (run_id, target, metric, value) VALUES (?, ?, ?, ?)",
+                             [(run['run_id'], target, metric, value) for (target, metric), value in metrics.items()])
+    conn.close()
+
+
+def sql_literal(value) -> str:
+    if value is None:
+        return 'NULL'
+    if isinstance(value, bool):
+        return 'TRUE' if value else 'FALSE'
+    if isinstance(value, (int, float)):
+        return repr(value)
+    return "'" + str(value).replace("'", "''") + "'"
+
+
+def store_postgres(url: str, runs: list):
+    sql = ['SET client_min_messages = warning;', SCHEMA.read_text(), 'BEGIN;']
+    for run, metrics in runs:
+        run_id = sql_literal(run['run_id'])
+        sql.append(f"DELETE FROM metrics WHERE run_id = {run_id};")
+        sql.append(f"DELETE FROM runs WHERE run_id = {run_id};")
+        sql.append(f"INSERT INTO runs ({', '.join(RUN_COLUMNS)}) VALUES "
+                   f"({', '.join(sql_literal(run[c]) for c in RUN_COLUMNS)});")
+        for (target, metric), value in metrics.items():
+            sql.append(f"INSERT INTO metrics (run_id, target, metric, value) VALUES "
+                       f"({run_id}, {sql_literal(target)}, {sql_literal(metric)}, {value!r});")
+    sql.append('COMMIT;')
+    subprocess.run(['psql', url, '-q', '-v', 'ON_ERROR_STOP=1'], input='\n'.join(sql), text=True,
+                   check=True, stdout=subprocess.DEVNULL)
+
+
+def main():
+    args = parse_args()
+    runs = []
+    for d in args.dirs:
+        results_dir = Path(d).resolve()
+        run = read_run(results_dir) if results_dir.is_dir() else None
+        if run is None:
+            print(f"[aviso] {d}: nenhum CSV de resumo conhecido — ignorado", file=sys.stderr)
+            continue
+        runs.append(run)
+        print(f"{run[0]['run_id']}: {run[0]['suite']}, {len(run[1])} métricas, commit {(run[0]['git_sha'] or '?')[:12]}")
+    if not runs:
+        sys.exit(1)
+
+    if args.db.startswith(('postgres://', 'postgresql://')):
+        store_postgres(args.db, runs)
+    else:
+        store_sqlite(args.db, runs)
+    print(f"[ok] {len(runs)} execução(ões) gravada(s) em {args.db}")
+
+
+if __name__ == '__main__':
+    main()